@@ -0,0 +1,72 @@
+//! ANN Recall Benchmark: HNSW vs exact search over synthetic embeddings
+//!
+//! Run with: cargo run --release --example ann_recall -- [vectors] [dim] [queries]
+//!
+//! Vectors are drawn around a few hundred random centroids, which is closer to
+//! real sentence embeddings than uniform noise. Reports recall@10 and mean
+//! query latency for a range of beam widths, unfiltered and with a category
+//! filter that matches a quarter of the vectors.
+
+use karana_core::oracle::knowledge_manager::PrivacyLevel;
+use karana_core::oracle::vector_index::{measure_recall, HnswConfig, HnswIndex, IndexMetadata, SearchFilter};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::time::Instant;
+
+const K: usize = 10;
+const CATEGORIES: [&str; 4] = ["notes", "recipes", "work", "travel"];
+
+fn clustered(rng: &mut StdRng, centroids: &[Vec<f32>], spread: f32) -> Vec<f32> {
+    let centroid = &centroids[rng.gen_range(0..centroids.len())];
+    centroid.iter().map(|c| c + rng.gen_range(-spread..spread)).collect()
+}
+
+fn main() -> anyhow::Result<()> {
+    let args: Vec<usize> = std::env::args().skip(1).filter_map(|a| a.parse().ok()).collect();
+    let n = args.first().copied().unwrap_or(20_000);
+    let dim = args.get(1).copied().unwrap_or(384);
+    let num_queries = args.get(2).copied().unwrap_or(200);
+
+    let mut rng = StdRng::seed_from_u64(42);
+    let centroids: Vec<Vec<f32>> = (0..(n / 50).max(1))
+        .map(|_| (0..dim).map(|_| rng.gen_range(-1.0..1.0)).collect())
+        .collect();
+
+    println!("Building HNSW index: {} vectors x {} dims", n, dim);
+    let mut index = HnswIndex::new(dim, HnswConfig::default());
+    let start = Instant::now();
+    for id in 0..n {
+        let meta = IndexMetadata {
+            category: CATEGORIES[id % CATEGORIES.len()].to_string(),
+            tags: Vec::new(),
+            privacy: PrivacyLevel::Private,
+        };
+        index.insert(id as u64, &clustered(&mut rng, &centroids, 0.5), meta)?;
+    }
+    let build = start.elapsed();
+    println!("  built in {:.2?} ({:.0} inserts/s)\n", build, n as f64 / build.as_secs_f64());
+
+    let queries: Vec<Vec<f32>> = (0..num_queries).map(|_| clustered(&mut rng, &centroids, 0.5)).collect();
+    let filter = SearchFilter::default().category("work");
+
+    for (label, filter) in [("unfiltered", None), ("category=work", Some(&filter))] {
+        println!("{} ({} queries, recall@{})", label, num_queries, K);
+        println!("  {:>5}  {:>7}  {:>10}  {:>10}  {:>7}", "ef", "recall", "ann", "exact", "speedup");
+        for ef in [16, 32, 64, 128, 256] {
+            let report = measure_recall(&index, &queries, K, ef, filter);
+            let ann = report.ann_time / report.queries as u32;
+            let exact = report.exact_time / report.queries as u32;
+            println!(
+                "  {:>5}  {:>7.3}  {:>10.2?}  {:>10.2?}  {:>6.1}x",
+                ef,
+                report.recall,
+                ann,
+                exact,
+                exact.as_secs_f64() / ann.as_secs_f64().max(1e-9)
+            );
+        }
+        println!();
+    }
+
+    Ok(())
+}
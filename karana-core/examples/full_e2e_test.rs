@@ -138,6 +138,7 @@ fn main() -> anyhow::Result<()> {
             to: "did:karana:recipient".to_string(),
             amount: 42,
         },
+        1,
     );
     
     println!("│ Created signed transaction:");
@@ -7,13 +7,19 @@ use std::sync::{Arc, Mutex};
 use std::time::Instant;
 
 /// Model size variants available for different resource constraints
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub enum ModelSize {
     Tiny,    // ~100MB, fastest inference, lowest quality
     Small,   // ~500MB, balanced performance
     Medium,  // ~2GB, best quality, slower
 }
 
+/// Share of available memory the generative engine may keep as cached KV states
+const KV_CACHE_MEMORY_FRACTION: f64 = 0.10;
+
+/// Hard cap on cached KV states, whatever the free memory
+const KV_CACHE_MAX_BYTES: usize = 512 * 1024 * 1024;
+
 /// System resource status
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ResourceStatus {
@@ -55,6 +61,24 @@ impl ResourceStatus {
         })
     }
 
+    /// Same resources, `margin` worse on every axis (battery %, °C, CPU %, memory %).
+    /// A larger model is only chosen if it would still be chosen under the margin.
+    pub fn with_margin(&self, margin: f32) -> Self {
+        Self {
+            battery_percent: self.battery_percent - margin,
+            is_charging: self.is_charging,
+            available_memory_mb: (self.available_memory_mb as f32 * (1.0 - margin / 100.0)).max(0.0) as u64,
+            cpu_usage_percent: self.cpu_usage_percent + margin,
+            temperature_celsius: self.temperature_celsius + margin,
+        }
+    }
+
+    /// Memory budget for prompt-prefix KV caches (see `prefix_cache`)
+    pub fn kv_cache_budget_bytes(&self) -> usize {
+        let available = self.available_memory_mb as f64 * 1024.0 * 1024.0;
+        ((available * KV_CACHE_MEMORY_FRACTION) as usize).min(KV_CACHE_MAX_BYTES)
+    }
+
     fn get_battery_level() -> Result<f32> {
         // Platform-specific battery reading
         #[cfg(target_os = "linux")]
@@ -100,6 +124,18 @@ impl ResourceStatus {
     }
 }
 
+impl From<&crate::resource::ResourceSnapshot> for ResourceStatus {
+    fn from(snapshot: &crate::resource::ResourceSnapshot) -> Self {
+        Self {
+            battery_percent: snapshot.battery_level,
+            is_charging: snapshot.is_charging,
+            available_memory_mb: snapshot.memory_total.saturating_sub(snapshot.memory_used) / 1024 / 1024,
+            cpu_usage_percent: snapshot.cpu_usage,
+            temperature_celsius: snapshot.temperature,
+        }
+    }
+}
+
 /// Adaptive model selection policy
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AdaptivePolicy {
@@ -120,6 +156,23 @@ pub struct AdaptivePolicy {
     
     /// Force specific model (overrides adaptive selection)
     pub force_model: Option<ModelSize>,
+
+    /// Phase 7.6: How much better resources must be (battery %, °C, CPU %, memory %)
+    /// before swapping up to a larger model. Swapping down is immediate.
+    #[serde(default = "default_upgrade_margin")]
+    pub upgrade_margin: f32,
+
+    /// Phase 7.6: How long a larger model must stay selected before swapping up (s)
+    #[serde(default = "default_upgrade_dwell_secs")]
+    pub upgrade_dwell_secs: u64,
+}
+
+fn default_upgrade_margin() -> f32 {
+    5.0
+}
+
+fn default_upgrade_dwell_secs() -> u64 {
+    60
 }
 
 impl Default for AdaptivePolicy {
@@ -131,6 +184,8 @@ impl Default for AdaptivePolicy {
             cpu_threshold: 80.0,       // Throttle if CPU >80%
             temp_threshold: 70.0,      // Throttle if temp >70°C
             force_model: None,
+            upgrade_margin: default_upgrade_margin(),
+            upgrade_dwell_secs: default_upgrade_dwell_secs(),
         }
     }
 }
@@ -142,6 +197,9 @@ pub struct AdaptiveModelLoader {
     inference_times: Vec<f32>,  // Rolling window of inference times
     last_resource_check: Option<Instant>,
     check_interval_secs: u64,
+    // Phase 7.6: Size the generative engine actually runs, and a pending upgrade
+    loaded_model: Option<ModelSize>,
+    upgrade_candidate: Option<(ModelSize, Instant)>,
 }
 
 impl AdaptiveModelLoader {
@@ -152,6 +210,8 @@ impl AdaptiveModelLoader {
             inference_times: Vec::with_capacity(100),
             last_resource_check: None,
             check_interval_secs: 30,  // Check resources every 30s
+            loaded_model: None,
+            upgrade_candidate: None,
         }
     }
 
@@ -235,6 +295,53 @@ impl AdaptiveModelLoader {
         }
     }
 
+    /// Phase 7.6: Model size to swap to after observing `resources`, if any.
+    /// Downgrades are returned at once; upgrades only once the larger model has been
+    /// selected with `upgrade_margin` to spare for `upgrade_dwell_secs`, so readings
+    /// hovering around a threshold do not make the engine flap between sizes.
+    pub fn evaluate_swap(&mut self, resources: &ResourceStatus, now: Instant) -> Option<ModelSize> {
+        let target = match self.policy.force_model {
+            Some(forced) => forced,
+            None => self.select_based_on_resources(resources),
+        };
+        self.current_model = Some(target);
+
+        let Some(loaded) = self.loaded_model else {
+            return Some(target);
+        };
+        if target <= loaded || self.policy.force_model.is_some() {
+            self.upgrade_candidate = None;
+            return (target != loaded).then_some(target);
+        }
+
+        let target = self.select_based_on_resources(&resources.with_margin(self.policy.upgrade_margin));
+        if target <= loaded {
+            self.upgrade_candidate = None;
+            return None;
+        }
+        match self.upgrade_candidate {
+            Some((candidate, since)) if candidate == target => {
+                (now.duration_since(since).as_secs() >= self.policy.upgrade_dwell_secs).then_some(target)
+            }
+            _ => {
+                self.upgrade_candidate = Some((target, now));
+                (self.policy.upgrade_dwell_secs == 0).then_some(target)
+            }
+        }
+    }
+
+    /// Phase 7.6: Record that the generative engine now runs `size`
+    pub fn confirm_swap(&mut self, size: ModelSize) {
+        self.loaded_model = Some(size);
+        self.current_model = Some(size);
+        self.upgrade_candidate = None;
+    }
+
+    /// Size the generative engine runs (None before the first model is loaded)
+    pub fn loaded_model(&self) -> Option<ModelSize> {
+        self.loaded_model
+    }
+
     /// Record inference time for performance tracking
     pub fn record_inference_time(&mut self, duration_ms: f32) {
         self.inference_times.push(duration_ms);
@@ -277,6 +384,11 @@ impl AdaptiveModelLoader {
         }
     }
 
+    /// Active selection policy
+    pub fn policy(&self) -> &AdaptivePolicy {
+        &self.policy
+    }
+
     /// Force a specific model size
     pub fn force_model(&mut self, size: Option<ModelSize>) {
         self.policy.force_model = size;
@@ -299,36 +411,51 @@ pub struct ModelInfo {
     pub size: ModelSize,
     pub model_path: String,
     pub tokenizer_path: String,
+    /// Special tokens the model adds to the tokenizer file's vocabulary
+    pub added_tokens: &'static [&'static str],
     pub estimated_memory_mb: u64,
     pub avg_inference_ms: f32,
 }
 
+/// Phi-3 Mini uses the Llama vocabulary plus its chat tokens at ids 32000..=32010
+const PHI3_ADDED_TOKENS: &[&str] = &[
+    "<|endoftext|>", "<|assistant|>", "<|placeholder1|>", "<|placeholder2|>",
+    "<|placeholder3|>", "<|placeholder4|>", "<|system|>", "<|end|>",
+    "<|placeholder5|>", "<|placeholder6|>", "<|user|>",
+];
+
 impl ModelInfo {
-    pub fn for_size(size: ModelSize) -> Self {
+    /// Files for a model size, or None if no model of that size ships
+    pub fn for_size(size: ModelSize) -> Option<Self> {
         match size {
-            ModelSize::Tiny => Self {
+            // The model `KaranaAI::download_model` installs
+            ModelSize::Tiny => Some(Self {
                 size,
-                model_path: "models/tinyllama-1.1b-q4_0.gguf".to_string(),
-                tokenizer_path: "models/tokenizer.json".to_string(),
+                model_path: format!("karana-cache/models/{}", super::MODEL_FILE),
+                tokenizer_path: "models/tinyllama/tokenizer.json".to_string(),
+                added_tokens: &[],
                 estimated_memory_mb: 100,
                 avg_inference_ms: 50.0,
-            },
-            ModelSize::Small => Self {
+            }),
+            ModelSize::Small => Some(Self {
                 size,
-                model_path: "models/phi-3-mini-4k-q4_0.gguf".to_string(),
-                tokenizer_path: "models/tokenizer.json".to_string(),
+                model_path: "models/phi3-mini.q4.gguf".to_string(),
+                tokenizer_path: "models/tinyllama/tokenizer.json".to_string(),
+                added_tokens: PHI3_ADDED_TOKENS,
                 estimated_memory_mb: 500,
                 avg_inference_ms: 200.0,
-            },
-            ModelSize::Medium => Self {
-                size,
-                model_path: "models/llama-3-8b-q4_0.gguf".to_string(),
-                tokenizer_path: "models/tokenizer.json".to_string(),
-                estimated_memory_mb: 2048,
-                avg_inference_ms: 800.0,
-            },
+            }),
+            ModelSize::Medium => None,
         }
     }
+
+    /// Largest shipped model no bigger than `size`
+    pub fn shipped_up_to(size: ModelSize) -> Option<Self> {
+        [ModelSize::Medium, ModelSize::Small, ModelSize::Tiny]
+            .into_iter()
+            .filter(|candidate| *candidate <= size)
+            .find_map(Self::for_size)
+    }
 }
 
 #[cfg(test)]
@@ -344,6 +471,21 @@ mod tests {
         assert!(status.battery_percent >= 0.0 && status.battery_percent <= 100.0);
     }
 
+    #[test]
+    fn test_kv_cache_budget() {
+        let status = ResourceStatus {
+            battery_percent: 80.0,
+            is_charging: false,
+            available_memory_mb: 1024,
+            cpu_usage_percent: 10.0,
+            temperature_celsius: 40.0,
+        };
+        assert_eq!(status.kv_cache_budget_bytes(), 1024 * 1024 * 1024 / 10);
+
+        let plenty = ResourceStatus { available_memory_mb: 64 * 1024, ..status };
+        assert_eq!(plenty.kv_cache_budget_bytes(), KV_CACHE_MAX_BYTES);
+    }
+
     #[test]
     fn test_model_selection() {
         let mut loader = AdaptiveModelLoader::new(AdaptivePolicy::default());
@@ -362,6 +504,74 @@ mod tests {
         assert_eq!(model, ModelSize::Tiny);
     }
 
+    fn status(battery_percent: f32, temperature_celsius: f32) -> ResourceStatus {
+        ResourceStatus {
+            battery_percent,
+            is_charging: false,
+            available_memory_mb: 4096,
+            cpu_usage_percent: 10.0,
+            temperature_celsius,
+        }
+    }
+
+    #[test]
+    fn test_swap_hysteresis() {
+        let mut loader = AdaptiveModelLoader::new(AdaptivePolicy::default());
+        let start = Instant::now();
+        let at = |secs| start + std::time::Duration::from_secs(secs);
+
+        assert_eq!(loader.evaluate_swap(&status(90.0, 40.0), at(0)), Some(ModelSize::Medium));
+        loader.confirm_swap(ModelSize::Medium);
+
+        // Overheating: swap down immediately
+        assert_eq!(loader.evaluate_swap(&status(90.0, 72.0), at(1)), Some(ModelSize::Small));
+        loader.confirm_swap(ModelSize::Small);
+
+        // Just below the threshold is not enough to swap back up
+        assert_eq!(loader.evaluate_swap(&status(90.0, 68.0), at(2)), None);
+        assert_eq!(loader.evaluate_swap(&status(90.0, 68.0), at(200)), None);
+
+        // Cooled down with margin, but the upgrade waits out the dwell time
+        assert_eq!(loader.evaluate_swap(&status(90.0, 60.0), at(300)), None);
+        assert_eq!(loader.evaluate_swap(&status(90.0, 60.0), at(330)), None);
+        assert_eq!(loader.evaluate_swap(&status(90.0, 60.0), at(360)), Some(ModelSize::Medium));
+
+        // A spike in between restarts the dwell time
+        loader.confirm_swap(ModelSize::Small);
+        assert_eq!(loader.evaluate_swap(&status(90.0, 60.0), at(400)), None);
+        assert_eq!(loader.evaluate_swap(&status(90.0, 68.0), at(430)), None);
+        assert_eq!(loader.evaluate_swap(&status(90.0, 60.0), at(461)), None);
+        assert_eq!(loader.loaded_model(), Some(ModelSize::Small));
+    }
+
+    #[test]
+    fn test_forced_swap_skips_hysteresis() {
+        let mut loader = AdaptiveModelLoader::new(AdaptivePolicy::default());
+        loader.confirm_swap(ModelSize::Tiny);
+
+        loader.force_model(Some(ModelSize::Medium));
+        assert_eq!(loader.evaluate_swap(&status(10.0, 40.0), Instant::now()), Some(ModelSize::Medium));
+        loader.confirm_swap(ModelSize::Medium);
+        assert_eq!(loader.evaluate_swap(&status(10.0, 40.0), Instant::now()), None);
+    }
+
+    #[test]
+    fn test_each_size_has_its_own_tokenizer() {
+        let tokenizers: std::collections::HashSet<(String, &[&str])> = [ModelSize::Tiny, ModelSize::Small, ModelSize::Medium]
+            .into_iter()
+            .filter_map(ModelInfo::for_size)
+            .map(|info| (info.tokenizer_path, info.added_tokens))
+            .collect();
+        assert_eq!(tokenizers.len(), 2);
+    }
+
+    #[test]
+    fn test_unshipped_sizes_fall_back_to_smaller_models() {
+        assert!(ModelInfo::for_size(ModelSize::Medium).is_none());
+        assert_eq!(ModelInfo::shipped_up_to(ModelSize::Medium).map(|info| info.size), Some(ModelSize::Small));
+        assert_eq!(ModelInfo::shipped_up_to(ModelSize::Tiny).map(|info| info.size), Some(ModelSize::Tiny));
+    }
+
     #[test]
     fn test_inference_tracking() {
         let mut loader = AdaptiveModelLoader::new(AdaptivePolicy::default());
@@ -6,118 +6,17 @@ use anyhow::{Result, Context, anyhow};
 use async_trait::async_trait;
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
 
-// Tool trait for extensible tool system
-#[async_trait]
-pub trait Tool: Send + Sync {
-    fn name(&self) -> &str;
-    fn description(&self) -> &str;
-    fn parameters(&self) -> Vec<ToolParameter>;
-    async fn execute(&self, args: &ToolArgs) -> Result<ToolOutput>;
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ToolParameter {
-    pub name: String,
-    pub param_type: String,
-    pub description: String,
-    pub required: bool,
-}
-
-#[derive(Debug, Clone)]
-pub struct ToolArgs {
-    args: HashMap<String, Value>,
-}
-
-impl ToolArgs {
-    pub fn new() -> Self {
-        Self {
-            args: HashMap::new(),
-        }
-    }
-    
-    pub fn insert(&mut self, key: String, value: Value) {
-        self.args.insert(key, value);
-    }
-    
-    pub fn get_string(&self, key: &str) -> Result<String> {
-        self.args.get(key)
-            .and_then(|v| v.as_str())
-            .map(|s| s.to_string())
-            .ok_or_else(|| anyhow!("Missing or invalid argument: {}", key))
-    }
-    
-    pub fn get_f64(&self, key: &str) -> Result<f64> {
-        self.args.get(key)
-            .and_then(|v| v.as_f64())
-            .ok_or_else(|| anyhow!("Missing or invalid number argument: {}", key))
-    }
-    
-    pub fn get_optional_string(&self, key: &str) -> Option<String> {
-        self.args.get(key)
-            .and_then(|v| v.as_str())
-            .map(|s| s.to_string())
-    }
-}
-
-/// Production tool registry with real implementations
-pub struct ToolRegistry {
-    tools: HashMap<String, Arc<dyn Tool>>,
-}
-
-impl ToolRegistry {
-    pub fn new() -> Self {
-        let mut registry = Self {
-            tools: HashMap::new(),
-        };
-        
-        // Register core tools
-        registry.register_tool(Arc::new(CalculatorTool));
-        registry.register_tool(Arc::new(WeatherTool::new()));
-        registry.register_tool(Arc::new(WikipediaTool::new()));
-        registry.register_tool(Arc::new(WebSearchTool::new()));
-        
-        registry
-    }
-    
-    pub fn register_tool(&mut self, tool: Arc<dyn Tool>) {
-        self.tools.insert(tool.name().to_string(), tool);
-    }
-    
-    pub fn get_tool(&self, name: &str) -> Option<Arc<dyn Tool>> {
-        self.tools.get(name).cloned()
-    }
-    
-    pub fn list_tools(&self) -> Vec<String> {
-        self.tools.keys().cloned().collect()
-    }
-    
-    pub async fn call_tool(&self, tool_name: &str, input: &Value) -> Result<String> {
-        let tool = self.get_tool(tool_name)
-            .ok_or_else(|| anyhow!("Tool not found: {}", tool_name))?;
-        
-        // Parse input into ToolArgs
-        let mut args = ToolArgs::new();
-        if let Some(obj) = input.as_object() {
-            for (key, value) in obj {
-                args.insert(key.clone(), value.clone());
-            }
-        }
-        
-        let output = tool.execute(&args).await?;
-        Ok(output.output)
-    }
-}
-
-// ============================================================================
-// TOOL OUTPUT STRUCTURES
-// ============================================================================
+use crate::privacy::Permission;
+pub use crate::tools::{ParamType, Tool, ToolArgs, ToolOutput, ToolParameter, ToolRegistry, ToolResult};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ToolOutput {
-    pub tool_name: String,
-    pub output: String,
-    pub confidence: f32,
+/// Register the core information tools (calculator, weather, Wikipedia, web search)
+pub fn register_core_tools(registry: &mut ToolRegistry) {
+    registry.register(CalculatorTool);
+    registry.register(WeatherTool::new());
+    registry.register(WikipediaTool::new());
+    registry.register(WebSearchTool::new());
 }
 
 // ============================================================================
@@ -139,17 +38,12 @@ impl Tool for CalculatorTool {
     
     fn parameters(&self) -> Vec<ToolParameter> {
         vec![
-            ToolParameter {
-                name: "expression".to_string(),
-                param_type: "string".to_string(),
-                description: "Mathematical expression to evaluate".to_string(),
-                required: true,
-            }
+            ToolParameter::required("expression", ParamType::String, "Mathematical expression to evaluate"),
         ]
     }
     
     async fn execute(&self, args: &ToolArgs) -> Result<ToolOutput> {
-        let mut expr = args.get_string("expression")?;
+        let mut expr = args.require_string("expression")?;
         
         // Handle percentage calculations
         if expr.contains('%') {
@@ -165,11 +59,7 @@ impl Tool for CalculatorTool {
         let result = meval::eval_str(&expr)
             .map_err(|e| anyhow!("Math evaluation error: {}", e))?;
         
-        Ok(ToolOutput {
-            tool_name: "calculator".to_string(),
-            output: result.to_string(),
-            confidence: 1.0,  // Deterministic
-        })
+        Ok(ToolOutput::new(result.to_string(), 1.0))  // Deterministic
     }
 }
 
@@ -230,17 +120,21 @@ impl Tool for WeatherTool {
     
     fn parameters(&self) -> Vec<ToolParameter> {
         vec![
-            ToolParameter {
-                name: "location".to_string(),
-                param_type: "string".to_string(),
-                description: "City name or 'current' for current location".to_string(),
-                required: true,
-            }
+            ToolParameter::optional("location", ParamType::String, "City name or 'current' for current location")
+                .with_default("current"),
         ]
     }
     
+    fn permissions(&self) -> Vec<Permission> {
+        vec![Permission::Internet, Permission::LocationCoarse]
+    }
+    
+    fn timeout(&self) -> Option<Duration> {
+        Some(Duration::from_secs(10))
+    }
+    
     async fn execute(&self, args: &ToolArgs) -> Result<ToolOutput> {
-        let location = args.get_string("location")?;
+        let location = args.get_string("location").unwrap_or_else(|| "current".to_string());
         
         // Check cache (5 minute TTL)
         {
@@ -248,11 +142,7 @@ impl Tool for WeatherTool {
             if let Some((cached, timestamp)) = cache.get(&location) {
                 if timestamp.elapsed().as_secs() < 300 {
                     log::debug!("[WeatherTool] Cache hit for: {}", location);
-                    return Ok(ToolOutput {
-                        tool_name: "weather".to_string(),
-                        output: cached.clone(),
-                        confidence: 0.9,
-                    });
+                    return Ok(ToolOutput::new(cached.clone(), 0.9));
                 }
             }
         }
@@ -300,11 +190,7 @@ impl Tool for WeatherTool {
             cache.insert(location.clone(), (summary.clone(), std::time::Instant::now()));
         }
         
-        Ok(ToolOutput {
-            tool_name: "weather".to_string(),
-            output: summary,
-            confidence: 0.9,
-        })
+        Ok(ToolOutput::new(summary, 0.9))
     }
 }
 
@@ -333,17 +219,20 @@ impl Tool for WikipediaTool {
     
     fn parameters(&self) -> Vec<ToolParameter> {
         vec![
-            ToolParameter {
-                name: "topic".to_string(),
-                param_type: "string".to_string(),
-                description: "Topic or article name to search for".to_string(),
-                required: true,
-            }
+            ToolParameter::required("topic", ParamType::String, "Topic or article name to search for"),
         ]
     }
     
+    fn permissions(&self) -> Vec<Permission> {
+        vec![Permission::Internet]
+    }
+    
+    fn timeout(&self) -> Option<Duration> {
+        Some(Duration::from_secs(10))
+    }
+    
     async fn execute(&self, args: &ToolArgs) -> Result<ToolOutput> {
-        let topic = args.get_string("topic")?;
+        let topic = args.require_string("topic")?;
         
         // Check cache (1 hour TTL - Wikipedia is fairly static)
         {
@@ -351,11 +240,7 @@ impl Tool for WikipediaTool {
             if let Some((cached, timestamp)) = cache.get(&topic) {
                 if timestamp.elapsed().as_secs() < 3600 {
                     log::debug!("[WikipediaTool] Cache hit for: {}", topic);
-                    return Ok(ToolOutput {
-                        tool_name: "wikipedia".to_string(),
-                        output: cached.clone(),
-                        confidence: 0.95,
-                    });
+                    return Ok(ToolOutput::new(cached.clone(), 0.95));
                 }
             }
         }
@@ -371,11 +256,7 @@ impl Tool for WikipediaTool {
             .context("Failed to fetch Wikipedia data")?;
         
         if !response.status().is_success() {
-            return Ok(ToolOutput {
-                tool_name: "wikipedia".to_string(),
-                output: format!("No Wikipedia article found for '{}'", topic),
-                confidence: 0.3,
-            });
+            return Ok(ToolOutput::new(format!("No Wikipedia article found for '{}'", topic), 0.3));
         }
         
         let data: serde_json::Value = response.json().await?;
@@ -399,11 +280,7 @@ impl Tool for WikipediaTool {
             cache.insert(topic.clone(), (result.clone(), std::time::Instant::now()));
         }
         
-        Ok(ToolOutput {
-            tool_name: "wikipedia".to_string(),
-            output: result,
-            confidence: 0.95,
-        })
+        Ok(ToolOutput::new(result, 0.95))
     }
 }
 
@@ -432,17 +309,20 @@ impl Tool for WebSearchTool {
     
     fn parameters(&self) -> Vec<ToolParameter> {
         vec![
-            ToolParameter {
-                name: "query".to_string(),
-                param_type: "string".to_string(),
-                description: "Search query".to_string(),
-                required: true,
-            }
+            ToolParameter::required("query", ParamType::String, "Search query"),
         ]
     }
     
+    fn permissions(&self) -> Vec<Permission> {
+        vec![Permission::Internet]
+    }
+    
+    fn timeout(&self) -> Option<Duration> {
+        Some(Duration::from_secs(15))
+    }
+    
     async fn execute(&self, args: &ToolArgs) -> Result<ToolOutput> {
-        let query = args.get_string("query")?;
+        let query = args.require_string("query")?;
         
         // Check cache (15 minute TTL - web content changes frequently)
         {
@@ -450,11 +330,7 @@ impl Tool for WebSearchTool {
             if let Some((cached, timestamp)) = cache.get(&query) {
                 if timestamp.elapsed().as_secs() < 900 {
                     log::debug!("[WebSearchTool] Cache hit for: {}", query);
-                    return Ok(ToolOutput {
-                        tool_name: "web_search".to_string(),
-                        output: cached.clone(),
-                        confidence: 0.85,
-                    });
+                    return Ok(ToolOutput::new(cached.clone(), 0.85));
                 }
             }
         }
@@ -531,18 +407,14 @@ impl Tool for WebSearchTool {
             cache.insert(query.clone(), (summary.clone(), std::time::Instant::now()));
         }
         
-        Ok(ToolOutput {
-            tool_name: "web_search".to_string(),
-            output: summary,
-            confidence: 0.80,
-        })
+        Ok(ToolOutput::new(summary, 0.80))
     }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AgenticResponse {
     pub suggestion: String,
-    pub chain: Vec<ToolOutput>,
+    pub chain: Vec<ToolResult>,
     pub confidence: f32,
     pub reasoning_steps: Vec<String>,
 }
@@ -570,13 +442,23 @@ pub enum InputSource {
     PreviousOutput(usize),  // Reference step_id
 }
 
+/// Render request context as tool input (tools take context as text)
+fn context_text(context: &Value) -> Option<String> {
+    match context {
+        Value::Null => None,
+        Value::String(text) => Some(text.clone()),
+        Value::Object(map) if map.is_empty() => None,
+        other => Some(other.to_string()),
+    }
+}
+
 pub struct AgenticReasoner {
-    tool_registry: ToolRegistry,
+    tool_registry: Arc<ToolRegistry>,
     max_chain_depth: usize,
 }
 
 impl AgenticReasoner {
-    pub fn new(tool_registry: ToolRegistry) -> Self {
+    pub fn new(tool_registry: Arc<ToolRegistry>) -> Self {
         Self {
             tool_registry,
             max_chain_depth: 3,
@@ -636,10 +518,9 @@ impl AgenticReasoner {
                 } else {
                     InputSource::PreviousOutput(idx - 1)
                 },
-                input_data: if idx == 0 {
-                    json!({ "query": request, "context": context })
-                } else {
-                    json!({ "previous_step": idx - 1 })
+                input_data: match (idx, context_text(context)) {
+                    (0, Some(context)) => json!({ "query": request, "context": context }),
+                    _ => json!({ "query": request }),
                 },
             });
         }
@@ -653,7 +534,7 @@ impl AgenticReasoner {
     }
 
     /// Step 2: Execute tool chain with dependency resolution
-    async fn execute_chain(&self, plan: &ReasoningPlan, request: &str) -> Result<Vec<ToolOutput>> {
+    async fn execute_chain(&self, plan: &ReasoningPlan, request: &str) -> Result<Vec<ToolResult>> {
         let mut outputs = Vec::new();
         let mut previous_output: Option<String> = None;
 
@@ -664,12 +545,11 @@ impl AgenticReasoner {
 
             let input_data = match &step.input_source {
                 InputSource::Direct => step.input_data.clone(),
-                InputSource::PreviousOutput(step_id) => {
+                InputSource::PreviousOutput(_) => {
                     if let Some(prev) = &previous_output {
                         json!({
                             "query": request,
                             "context": prev,
-                            "previous_step": step_id
                         })
                     } else {
                         step.input_data.clone()
@@ -677,22 +557,18 @@ impl AgenticReasoner {
                 }
             };
 
-            let output = self.tool_registry.call_tool(&step.tool, &input_data).await?;
-            
-            outputs.push(ToolOutput {
-                tool_name: step.tool.clone(),
-                output: output.clone(),
-                confidence: plan.confidence * 0.95_f32.powi(step.step_id as i32), // Decay with depth
-            });
+            let mut result = self.tool_registry.execute(&step.tool, ToolArgs::from_value(input_data)?).await?;
+            result.confidence = plan.confidence * 0.95_f32.powi(step.step_id as i32); // Decay with depth
 
-            previous_output = Some(output);
+            previous_output = Some(result.output.clone());
+            outputs.push(result);
         }
 
         Ok(outputs)
     }
 
     /// Step 3: Synthesize final suggestion from chain outputs
-    fn synthesize_suggestion(&self, request: &str, chain: &[ToolOutput], plan: &ReasoningPlan) -> Result<String> {
+    fn synthesize_suggestion(&self, request: &str, chain: &[ToolResult], plan: &ReasoningPlan) -> Result<String> {
         if chain.is_empty() {
             return Ok("Unable to process request - please clarify intent.".to_string());
         }
@@ -741,10 +617,15 @@ impl AgenticReasoner {
 mod tests {
     use super::*;
 
+    fn builtin_registry() -> ToolRegistry {
+        let mut registry = ToolRegistry::new();
+        crate::tools::register_builtin_tools(&mut registry);
+        registry
+    }
+
     #[tokio::test]
     async fn test_simple_classification() {
-        let registry = ToolRegistry::new_mock();
-        let reasoner = AgenticReasoner::new(registry);
+        let reasoner = AgenticReasoner::new(Arc::new(builtin_registry()));
         
         let response = reasoner.reason_universal(
             "What's the weather like?",
@@ -757,8 +638,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_confidence_gate() {
-        let registry = ToolRegistry::new_mock();
-        let reasoner = AgenticReasoner::new(registry);
+        let reasoner = AgenticReasoner::new(Arc::new(builtin_registry()));
         
         let response = reasoner.execute_with_confidence_gate(
             "asdfghjkl qwerty",  // Nonsense
@@ -0,0 +1,945 @@
+// Phase 7.3: Grammar-Constrained Decoding
+// Restricts sampled tokens so generated text always matches a JSON schema or tool-call grammar.
+//
+// A `JsonGrammar` is compiled from a JSON Schema (or from tool declarations). During
+// generation a `ConstrainedDecoder` walks a character trie of the vocabulary alongside a
+// pushdown matcher and masks every token that would make the output invalid.
+
+use anyhow::{anyhow, Result};
+use candle_core::{DType, Tensor};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock};
+use tokenizers::Tokenizer;
+
+use crate::tools::{ParamType, ToolParameter, ToolSpec};
+
+/// Longest number literal the grammar accepts
+const MAX_NUMBER_LEN: usize = 24;
+
+/// Upper bound on parallel parses kept by the matcher
+const MAX_PARSES: usize = 64;
+
+/// Alternatives a value of unknown type may take
+static ANY_VALUE: LazyLock<Vec<Arc<JsonGrammar>>> = LazyLock::new(|| {
+    let any = Arc::new(JsonGrammar::Any);
+    vec![
+        Arc::new(JsonGrammar::Object { fields: Vec::new(), additional: Some(any.clone()) }),
+        Arc::new(JsonGrammar::Array { items: any }),
+        Arc::new(JsonGrammar::string()),
+        Arc::new(JsonGrammar::Number { integer: false }),
+        Arc::new(JsonGrammar::Boolean),
+        Arc::new(JsonGrammar::Null),
+    ]
+});
+
+/// Free-form object key
+static KEY: LazyLock<Arc<JsonGrammar>> = LazyLock::new(|| Arc::new(JsonGrammar::string()));
+
+/// Grammar for a JSON value
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonGrammar {
+    /// Object with declared fields, emitted in declaration order.
+    /// Without fields, `additional` allows arbitrary keys with that value grammar.
+    Object {
+        fields: Vec<GrammarField>,
+        additional: Option<Arc<JsonGrammar>>,
+    },
+    String {
+        allowed: Option<Vec<String>>,
+        max_length: Option<usize>,
+    },
+    Number {
+        integer: bool,
+    },
+    Boolean,
+    Null,
+    Array {
+        items: Arc<JsonGrammar>,
+    },
+    OneOf(Vec<Arc<JsonGrammar>>),
+    Any,
+}
+
+/// Object field in a `JsonGrammar::Object`
+#[derive(Debug, Clone, PartialEq)]
+pub struct GrammarField {
+    pub name: String,
+    pub grammar: Arc<JsonGrammar>,
+    pub required: bool,
+}
+
+impl GrammarField {
+    pub fn required(name: &str, grammar: JsonGrammar) -> Self {
+        Self { name: name.to_string(), grammar: Arc::new(grammar), required: true }
+    }
+
+    pub fn optional(name: &str, grammar: JsonGrammar) -> Self {
+        Self { required: false, ..Self::required(name, grammar) }
+    }
+}
+
+impl JsonGrammar {
+    /// Unconstrained string
+    pub fn string() -> Self {
+        JsonGrammar::String { allowed: None, max_length: None }
+    }
+
+    /// String of at most `max_length` characters
+    pub fn bounded_string(max_length: usize) -> Self {
+        JsonGrammar::String { allowed: None, max_length: Some(max_length) }
+    }
+
+    /// One of a fixed set of strings
+    pub fn string_enum<S: Into<String>>(values: impl IntoIterator<Item = S>) -> Self {
+        JsonGrammar::String {
+            allowed: Some(values.into_iter().map(Into::into).collect()),
+            max_length: None,
+        }
+    }
+
+    /// Object with the given fields in order
+    pub fn object(fields: Vec<GrammarField>) -> Self {
+        JsonGrammar::Object { fields, additional: None }
+    }
+
+    /// This grammar or `null`
+    pub fn nullable(self) -> Self {
+        JsonGrammar::OneOf(vec![Arc::new(JsonGrammar::Null), Arc::new(self)])
+    }
+
+    /// Compile a JSON Schema (type, properties, required, items, enum, anyOf/oneOf, maxLength).
+    /// Properties are emitted in map order, which is alphabetical for `serde_json::Map`.
+    pub fn from_json_schema(schema: &Value) -> Result<Self> {
+        let schema = schema.as_object()
+            .ok_or_else(|| anyhow!("JSON Schema must be an object, got {}", schema))?;
+
+        if let Some(alternatives) = schema.get("anyOf").or_else(|| schema.get("oneOf")) {
+            let alternatives = alternatives.as_array()
+                .ok_or_else(|| anyhow!("anyOf/oneOf must be an array"))?;
+            return Ok(JsonGrammar::OneOf(
+                alternatives.iter()
+                    .map(|s| Self::from_json_schema(s).map(Arc::new))
+                    .collect::<Result<_>>()?,
+            ));
+        }
+
+        if let Some(values) = schema.get("enum").and_then(Value::as_array)
+            && values.iter().all(Value::is_string)
+        {
+            return Ok(Self::string_enum(values.iter().filter_map(Value::as_str)));
+        }
+
+        let type_of = |name: &str| -> Result<Self> {
+            Ok(match name {
+                "object" => {
+                    let properties = schema.get("properties").and_then(Value::as_object);
+                    let required: Vec<&str> = schema.get("required")
+                        .and_then(Value::as_array)
+                        .map(|r| r.iter().filter_map(Value::as_str).collect())
+                        .unwrap_or_default();
+                    match properties {
+                        Some(properties) if !properties.is_empty() => JsonGrammar::object(
+                            properties.iter()
+                                .map(|(name, s)| Ok(GrammarField {
+                                    name: name.clone(),
+                                    grammar: Arc::new(Self::from_json_schema(s)?),
+                                    required: required.contains(&name.as_str()),
+                                }))
+                                .collect::<Result<_>>()?,
+                        ),
+                        _ if schema.get("additionalProperties") == Some(&Value::Bool(false)) => {
+                            JsonGrammar::object(Vec::new())
+                        }
+                        _ => JsonGrammar::Object {
+                            fields: Vec::new(),
+                            additional: Some(Arc::new(JsonGrammar::Any)),
+                        },
+                    }
+                }
+                "string" => JsonGrammar::String {
+                    allowed: None,
+                    max_length: schema.get("maxLength").and_then(Value::as_u64).map(|n| n as usize),
+                },
+                "number" => JsonGrammar::Number { integer: false },
+                "integer" => JsonGrammar::Number { integer: true },
+                "boolean" => JsonGrammar::Boolean,
+                "null" => JsonGrammar::Null,
+                "array" => JsonGrammar::Array {
+                    items: Arc::new(match schema.get("items") {
+                        Some(items) => Self::from_json_schema(items)?,
+                        None => JsonGrammar::Any,
+                    }),
+                },
+                other => return Err(anyhow!("Unsupported JSON Schema type '{}'", other)),
+            })
+        };
+
+        match schema.get("type") {
+            Some(Value::String(name)) => type_of(name),
+            Some(Value::Array(names)) => Ok(JsonGrammar::OneOf(
+                names.iter()
+                    .map(|n| n.as_str().ok_or_else(|| anyhow!("Invalid type {}", n)).and_then(type_of).map(Arc::new))
+                    .collect::<Result<_>>()?,
+            )),
+            Some(other) => Err(anyhow!("Invalid JSON Schema type {}", other)),
+            None => Ok(JsonGrammar::Any),
+        }
+    }
+
+    /// Argument object for a tool's declared parameters
+    pub fn from_parameters(parameters: &[ToolParameter]) -> Self {
+        JsonGrammar::object(
+            parameters.iter()
+                .map(|p| {
+                    let string_values: Option<Vec<String>> = p.allowed.as_ref().and_then(|values| {
+                        values.iter().map(|v| v.as_str().map(str::to_string)).collect()
+                    });
+                    let grammar = match (p.param_type, string_values) {
+                        (ParamType::String, Some(values)) => JsonGrammar::string_enum(values),
+                        (ParamType::String, None) => JsonGrammar::string(),
+                        (ParamType::Number, _) => JsonGrammar::Number { integer: false },
+                        (ParamType::Integer, _) => JsonGrammar::Number { integer: true },
+                        (ParamType::Boolean, _) => JsonGrammar::Boolean,
+                        (ParamType::Array, _) => JsonGrammar::Array { items: Arc::new(JsonGrammar::Any) },
+                        (ParamType::Object, _) => JsonGrammar::Object {
+                            fields: Vec::new(),
+                            additional: Some(Arc::new(JsonGrammar::Any)),
+                        },
+                    };
+                    GrammarField {
+                        name: p.name.clone(),
+                        grammar: Arc::new(grammar),
+                        required: p.required,
+                    }
+                })
+                .collect(),
+        )
+    }
+
+    /// `{"tool": <name>, "arguments": {...}}` for any of the given tools
+    pub fn tool_call(specs: &[ToolSpec]) -> Self {
+        JsonGrammar::OneOf(
+            specs.iter()
+                .map(|spec| Arc::new(JsonGrammar::object(vec![
+                    GrammarField::required("tool", JsonGrammar::string_enum([spec.name.as_str()])),
+                    GrammarField::required("arguments", JsonGrammar::from_parameters(&spec.parameters)),
+                ])))
+                .collect(),
+        )
+    }
+
+    /// Check a complete text against the grammar
+    pub fn matches(self: &Arc<Self>, text: &str) -> bool {
+        GrammarState::new(self.clone())
+            .advance_str(text)
+            .is_some_and(|state| state.is_complete())
+    }
+}
+
+// ============================================================================
+// PUSHDOWN MATCHER
+// ============================================================================
+
+#[derive(Debug, Clone)]
+enum ObjectState {
+    Open,
+    Key { field: usize, pos: usize },
+    FreeKey,
+    Colon(Arc<JsonGrammar>),
+    ValueStart(Arc<JsonGrammar>),
+    InValue,
+    AfterValue,
+    Comma,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum ArrayState {
+    Open,
+    InValue,
+    AfterValue,
+    Comma,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum NumberState {
+    Start,
+    Minus,
+    Zero,
+    Int,
+    Dot,
+    Frac,
+    Exp,
+    ExpSign,
+    ExpDigits,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Escape {
+    None,
+    Backslash,
+    Unicode(u8),
+}
+
+#[derive(Debug, Clone)]
+enum Frame {
+    /// A value that has not started yet
+    Value(Arc<JsonGrammar>),
+    Object { grammar: Arc<JsonGrammar>, next: usize, state: ObjectState, space: bool },
+    Array { items: Arc<JsonGrammar>, state: ArrayState, space: bool },
+    /// Inside a string; `text` is only tracked for enums
+    String { grammar: Arc<JsonGrammar>, text: String, len: usize, escape: Escape },
+    Number { integer: bool, state: NumberState, len: usize },
+    Literal { text: &'static str, pos: usize },
+}
+
+/// Outcome of feeding one character to the top frame
+enum Step {
+    Reject,
+    /// Consumed; the frame becomes one of these
+    Next(Vec<Frame>),
+    /// Consumed; the frame is complete
+    Done,
+    /// Replace the frame, then start a child value with the same character
+    Delegate(Frame, Arc<JsonGrammar>),
+    /// Try each alternative with the same character
+    Expand(Vec<Frame>),
+}
+
+impl Frame {
+    fn step(&self, c: char) -> Step {
+        match self {
+            Frame::Value(grammar) => Self::start_value(grammar, c),
+            Frame::Object { grammar, next, state, space } => {
+                let JsonGrammar::Object { fields, additional } = grammar.as_ref() else {
+                    return Step::Reject;
+                };
+                let with = |state: ObjectState, next: usize, space: bool| Frame::Object {
+                    grammar: grammar.clone(),
+                    next,
+                    state,
+                    space,
+                };
+                let rest_optional = fields[*next..].iter().all(|f| !f.required);
+                let free = fields.is_empty() && additional.is_some();
+
+                match state {
+                    ObjectState::Open | ObjectState::Comma | ObjectState::AfterValue | ObjectState::ValueStart(_)
+                        if c == ' ' && !space =>
+                    {
+                        Step::Next(vec![with(state.clone(), *next, true)])
+                    }
+                    ObjectState::Open | ObjectState::Comma if c == '"' => {
+                        if free {
+                            return Step::Delegate(with(ObjectState::FreeKey, *next, false), KEY.clone());
+                        }
+                        let mut keys = Vec::new();
+                        for (i, field) in fields.iter().enumerate().skip(*next) {
+                            keys.push(with(ObjectState::Key { field: i, pos: 0 }, *next, false));
+                            if field.required {
+                                break;
+                            }
+                        }
+                        Step::Next(keys)
+                    }
+                    ObjectState::Open if c == '}' && rest_optional => Step::Done,
+                    ObjectState::Key { field, pos } => {
+                        let name = &fields[*field].name;
+                        match name[*pos..].chars().next() {
+                            Some(expected) if expected == c => Step::Next(vec![
+                                with(ObjectState::Key { field: *field, pos: pos + c.len_utf8() }, *next, false),
+                            ]),
+                            None if c == '"' => Step::Next(vec![
+                                with(ObjectState::Colon(fields[*field].grammar.clone()), field + 1, false),
+                            ]),
+                            _ => Step::Reject,
+                        }
+                    }
+                    ObjectState::Colon(value) if c == ':' => {
+                        Step::Next(vec![with(ObjectState::ValueStart(value.clone()), *next, false)])
+                    }
+                    ObjectState::ValueStart(value) => {
+                        Step::Delegate(with(ObjectState::InValue, *next, false), value.clone())
+                    }
+                    ObjectState::AfterValue if c == ',' && (free || *next < fields.len()) => {
+                        Step::Next(vec![with(ObjectState::Comma, *next, false)])
+                    }
+                    ObjectState::AfterValue if c == '}' && rest_optional => Step::Done,
+                    _ => Step::Reject,
+                }
+            }
+            Frame::Array { items, state, space } => {
+                let with = |state: ArrayState, space: bool| Frame::Array { items: items.clone(), state, space };
+                match (state, c) {
+                    (ArrayState::InValue, _) => Step::Reject,
+                    (_, ' ') if !space => Step::Next(vec![with(*state, true)]),
+                    (ArrayState::Open | ArrayState::AfterValue, ']') => Step::Done,
+                    (ArrayState::AfterValue, ',') => Step::Next(vec![with(ArrayState::Comma, false)]),
+                    (ArrayState::Open | ArrayState::Comma, _) => {
+                        Step::Delegate(with(ArrayState::InValue, false), items.clone())
+                    }
+                    _ => Step::Reject,
+                }
+            }
+            Frame::String { grammar, text, len, escape } => {
+                let JsonGrammar::String { allowed, max_length } = grammar.as_ref() else {
+                    return Step::Reject;
+                };
+                let full = max_length.is_some_and(|max| *len >= max);
+                let with = |text: String, len: usize, escape: Escape| Frame::String {
+                    grammar: grammar.clone(),
+                    text,
+                    len,
+                    escape,
+                };
+
+                match escape {
+                    Escape::Unicode(remaining) if c.is_ascii_hexdigit() => {
+                        let escape = if *remaining > 1 { Escape::Unicode(remaining - 1) } else { Escape::None };
+                        Step::Next(vec![with(String::new(), *len, escape)])
+                    }
+                    Escape::Unicode(_) => Step::Reject,
+                    Escape::Backslash => match c {
+                        'u' => Step::Next(vec![with(String::new(), *len, Escape::Unicode(4))]),
+                        '"' | '\\' | '/' | 'b' | 'f' | 'n' | 'r' | 't' => {
+                            Step::Next(vec![with(String::new(), *len, Escape::None)])
+                        }
+                        _ => Step::Reject,
+                    },
+                    Escape::None => match (c, allowed) {
+                        ('"', Some(values)) if values.iter().any(|v| v == text) => Step::Done,
+                        ('"', Some(_)) => Step::Reject,
+                        ('"', None) => Step::Done,
+                        ('\\', None) if !full => Step::Next(vec![with(String::new(), len + 1, Escape::Backslash)]),
+                        (c, _) if (c as u32) < 0x20 || c == '\\' || full => Step::Reject,
+                        (c, Some(values)) => {
+                            let mut text = text.clone();
+                            text.push(c);
+                            if values.iter().any(|v| v.starts_with(&text)) {
+                                Step::Next(vec![with(text, len + 1, Escape::None)])
+                            } else {
+                                Step::Reject
+                            }
+                        }
+                        (_, None) => Step::Next(vec![with(String::new(), len + 1, Escape::None)]),
+                    },
+                }
+            }
+            Frame::Number { integer, state, len } => {
+                if *len >= MAX_NUMBER_LEN {
+                    return Step::Reject;
+                }
+                use NumberState::*;
+                let next = match (state, c) {
+                    (Start, '-') => Minus,
+                    (Start | Minus, '0') => Zero,
+                    (Start | Minus, '1'..='9') => Int,
+                    (Int, '0'..='9') => Int,
+                    (Zero | Int, '.') if !integer => Dot,
+                    (Dot | Frac, '0'..='9') => Frac,
+                    (Zero | Int | Frac, 'e' | 'E') if !integer => Exp,
+                    (Exp, '+' | '-') => ExpSign,
+                    (Exp | ExpSign | ExpDigits, '0'..='9') => ExpDigits,
+                    _ => return Step::Reject,
+                };
+                Step::Next(vec![Frame::Number { integer: *integer, state: next, len: len + 1 }])
+            }
+            Frame::Literal { text, pos } => {
+                if text[*pos..].starts_with(c) {
+                    if pos + 1 == text.len() {
+                        Step::Done
+                    } else {
+                        Step::Next(vec![Frame::Literal { text, pos: pos + 1 }])
+                    }
+                } else {
+                    Step::Reject
+                }
+            }
+        }
+    }
+
+    fn start_value(grammar: &Arc<JsonGrammar>, c: char) -> Step {
+        match grammar.as_ref() {
+            JsonGrammar::OneOf(alternatives) => {
+                Step::Expand(alternatives.iter().cloned().map(Frame::Value).collect())
+            }
+            JsonGrammar::Any => Step::Expand(ANY_VALUE.iter().cloned().map(Frame::Value).collect()),
+            JsonGrammar::Object { .. } if c == '{' => Step::Next(vec![Frame::Object {
+                grammar: grammar.clone(),
+                next: 0,
+                state: ObjectState::Open,
+                space: false,
+            }]),
+            JsonGrammar::Array { items } if c == '[' => Step::Next(vec![Frame::Array {
+                items: items.clone(),
+                state: ArrayState::Open,
+                space: false,
+            }]),
+            JsonGrammar::String { .. } if c == '"' => Step::Next(vec![Frame::String {
+                grammar: grammar.clone(),
+                text: String::new(),
+                len: 0,
+                escape: Escape::None,
+            }]),
+            JsonGrammar::Number { integer } => {
+                Frame::Number { integer: *integer, state: NumberState::Start, len: 0 }.step(c)
+            }
+            JsonGrammar::Boolean if c == 't' => Step::Next(vec![Frame::Literal { text: "true", pos: 1 }]),
+            JsonGrammar::Boolean if c == 'f' => Step::Next(vec![Frame::Literal { text: "false", pos: 1 }]),
+            JsonGrammar::Null if c == 'n' => Step::Next(vec![Frame::Literal { text: "null", pos: 1 }]),
+            _ => Step::Reject,
+        }
+    }
+
+    /// Whether the frame may end here without consuming anything (numbers)
+    fn can_finish(&self) -> bool {
+        matches!(
+            self,
+            Frame::Number { state: NumberState::Zero | NumberState::Int | NumberState::Frac | NumberState::ExpDigits, .. }
+        )
+    }
+
+    /// A child value on top of this frame completed
+    fn child_finished(&mut self) {
+        match self {
+            Frame::Object { grammar, state, space, .. } => {
+                *state = match (&*state, grammar.as_ref()) {
+                    (ObjectState::FreeKey, JsonGrammar::Object { additional: Some(value), .. }) => {
+                        ObjectState::Colon(value.clone())
+                    }
+                    _ => ObjectState::AfterValue,
+                };
+                *space = false;
+            }
+            Frame::Array { state, space, .. } => {
+                *state = ArrayState::AfterValue;
+                *space = false;
+            }
+            _ => {}
+        }
+    }
+}
+
+fn finish_child(stack: &mut [Frame]) {
+    if let Some(parent) = stack.last_mut() {
+        parent.child_finished();
+    }
+}
+
+fn advance_stack(mut stack: Vec<Frame>, c: char, out: &mut Vec<Vec<Frame>>) {
+    let Some(top) = stack.pop() else {
+        return; // Root value already complete
+    };
+
+    if top.can_finish() {
+        let mut finished = stack.clone();
+        finish_child(&mut finished);
+        advance_stack(finished, c, out);
+    }
+
+    match top.step(c) {
+        Step::Reject => {}
+        Step::Next(frames) => {
+            for frame in frames {
+                let mut next = stack.clone();
+                next.push(frame);
+                out.push(next);
+            }
+        }
+        Step::Done => {
+            finish_child(&mut stack);
+            out.push(stack);
+        }
+        Step::Delegate(frame, child) => {
+            stack.push(frame);
+            stack.push(Frame::Value(child));
+            advance_stack(stack, c, out);
+        }
+        Step::Expand(frames) => {
+            for frame in frames {
+                let mut next = stack.clone();
+                next.push(frame);
+                advance_stack(next, c, out);
+            }
+        }
+    }
+}
+
+/// Set of live parses of a partially generated value
+#[derive(Debug, Clone)]
+pub struct GrammarState {
+    stacks: Vec<Vec<Frame>>,
+}
+
+impl GrammarState {
+    pub fn new(grammar: Arc<JsonGrammar>) -> Self {
+        Self { stacks: vec![vec![Frame::Value(grammar)]] }
+    }
+
+    /// State after one more character, or `None` if it is not allowed
+    pub fn advance(&self, c: char) -> Option<Self> {
+        let mut stacks = Vec::new();
+        for stack in &self.stacks {
+            advance_stack(stack.clone(), c, &mut stacks);
+            if stacks.len() > MAX_PARSES {
+                stacks.truncate(MAX_PARSES);
+                break;
+            }
+        }
+        (!stacks.is_empty()).then_some(Self { stacks })
+    }
+
+    /// State after a string, or `None` if any character is not allowed
+    pub fn advance_str(&self, text: &str) -> Option<Self> {
+        text.chars().try_fold(self.clone(), |state, c| state.advance(c))
+    }
+
+    /// The text so far is a complete value
+    pub fn is_complete(&self) -> bool {
+        self.stacks.iter().any(|stack| match stack.as_slice() {
+            [] => true,
+            [only] => only.can_finish(),
+            _ => false,
+        })
+    }
+
+    /// Complete and nothing more can follow
+    pub fn is_finished(&self) -> bool {
+        self.stacks.iter().all(|stack| stack.is_empty())
+    }
+}
+
+// ============================================================================
+// TOKEN VOCABULARY
+// ============================================================================
+
+#[derive(Debug, Default)]
+struct TrieNode {
+    children: Vec<(char, usize)>,
+    tokens: Vec<u32>,
+}
+
+/// Decoded text of every token, arranged in a character trie
+#[derive(Debug)]
+pub struct TokenVocabulary {
+    texts: Vec<Option<String>>,
+    trie: Vec<TrieNode>,
+    eos_token: Option<u32>,
+}
+
+impl TokenVocabulary {
+    /// Build from token texts indexed by id (`None` for tokens that never appear in JSON)
+    pub fn new(texts: Vec<Option<String>>, eos_token: Option<u32>) -> Self {
+        let mut trie = vec![TrieNode::default()];
+        let mut edges: Vec<HashMap<char, usize>> = vec![HashMap::new()];
+
+        for (id, text) in texts.iter().enumerate() {
+            let Some(text) = text.as_deref().filter(|t| !t.is_empty()) else {
+                continue;
+            };
+            let mut node = 0;
+            for c in text.chars() {
+                node = match edges[node].get(&c) {
+                    Some(&child) => child,
+                    None => {
+                        let child = trie.len();
+                        trie.push(TrieNode::default());
+                        edges.push(HashMap::new());
+                        edges[node].insert(c, child);
+                        trie[node].children.push((c, child));
+                        child
+                    }
+                };
+            }
+            trie[node].tokens.push(id as u32);
+        }
+
+        Self { texts, trie, eos_token }
+    }
+
+    /// Build from a SentencePiece-style tokenizer (`▁` for spaces, `<0xNN>` byte fallback)
+    pub fn from_tokenizer(tokenizer: &Tokenizer) -> Self {
+        let special: Vec<u32> = tokenizer.get_added_tokens_decoder()
+            .iter()
+            .filter(|(_, token)| token.special)
+            .map(|(id, _)| *id)
+            .collect();
+
+        let texts = (0..tokenizer.get_vocab_size(true) as u32)
+            .map(|id| {
+                if special.contains(&id) {
+                    return None;
+                }
+                let piece = tokenizer.id_to_token(id)?;
+                if let Some(hex) = piece.strip_prefix("<0x").and_then(|p| p.strip_suffix('>')) {
+                    // Byte fallback: only ASCII bytes form whole characters
+                    let byte = u8::from_str_radix(hex, 16).ok()?;
+                    return byte.is_ascii().then(|| (byte as char).to_string());
+                }
+                Some(piece.replace('▁', " "))
+            })
+            .collect();
+
+        Self::new(texts, tokenizer.token_to_id("</s>"))
+    }
+
+    pub fn len(&self) -> usize {
+        self.texts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.texts.is_empty()
+    }
+
+    pub fn token_text(&self, token: u32) -> Option<&str> {
+        self.texts.get(token as usize)?.as_deref()
+    }
+
+    pub fn eos_token(&self) -> Option<u32> {
+        self.eos_token
+    }
+
+    fn collect_allowed(&self, node: usize, state: &GrammarState, allowed: &mut Vec<u32>) {
+        let node = &self.trie[node];
+        allowed.extend_from_slice(&node.tokens);
+        for &(c, child) in &node.children {
+            if let Some(next) = state.advance(c) {
+                self.collect_allowed(child, &next, allowed);
+            }
+        }
+    }
+}
+
+// ============================================================================
+// CONSTRAINED DECODER
+// ============================================================================
+
+/// Tracks generated text against a grammar and masks invalid next tokens
+pub struct ConstrainedDecoder {
+    vocabulary: Arc<TokenVocabulary>,
+    state: GrammarState,
+    text: String,
+}
+
+impl ConstrainedDecoder {
+    pub fn new(vocabulary: Arc<TokenVocabulary>, grammar: Arc<JsonGrammar>) -> Self {
+        Self {
+            vocabulary,
+            state: GrammarState::new(grammar),
+            text: String::new(),
+        }
+    }
+
+    /// Tokens that keep the output valid (EOS once the value is complete)
+    pub fn allowed_tokens(&self) -> Vec<u32> {
+        let mut allowed = Vec::new();
+        if !self.state.is_finished() {
+            self.vocabulary.collect_allowed(0, &self.state, &mut allowed);
+        }
+        if self.state.is_complete()
+            && let Some(eos) = self.vocabulary.eos_token()
+        {
+            allowed.push(eos);
+        }
+        allowed.sort_unstable();
+        allowed
+    }
+
+    /// Set the logits of disallowed tokens to -inf
+    pub fn mask_logits(&self, logits: &Tensor) -> Result<Tensor> {
+        let allowed = self.allowed_tokens();
+        if allowed.is_empty() {
+            return Err(anyhow!("Grammar allows no further tokens after {:?}", self.text));
+        }
+        let size = logits.dim(0)?;
+        let mut mask = vec![f32::NEG_INFINITY; size];
+        for token in allowed {
+            if let Some(slot) = mask.get_mut(token as usize) {
+                *slot = 0.0;
+            }
+        }
+        let mask = Tensor::from_vec(mask, size, logits.device())?;
+        Ok(logits.to_dtype(DType::F32)?.add(&mask)?)
+    }
+
+    /// Append a sampled token
+    pub fn accept(&mut self, token: u32) -> Result<()> {
+        if Some(token) == self.vocabulary.eos_token() && self.state.is_complete() {
+            self.state = GrammarState { stacks: vec![Vec::new()] };
+            return Ok(());
+        }
+        let text = self.vocabulary.token_text(token)
+            .ok_or_else(|| anyhow!("Token {} cannot appear in constrained output", token))?;
+        self.state = self.state.advance_str(text)
+            .ok_or_else(|| anyhow!("Token {:?} violates the grammar after {:?}", text, self.text))?;
+        self.text.push_str(text);
+        Ok(())
+    }
+
+    /// The output so far is a complete value
+    pub fn is_complete(&self) -> bool {
+        self.state.is_complete()
+    }
+
+    /// Complete and nothing more can follow
+    pub fn is_finished(&self) -> bool {
+        self.state.is_finished()
+    }
+
+    /// Text generated so far
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::ToolArgs;
+    use candle_core::Device;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    use serde_json::json;
+
+    fn weather_specs() -> Vec<ToolSpec> {
+        vec![
+            ToolSpec {
+                name: "weather".to_string(),
+                description: "Weather".to_string(),
+                parameters: vec![
+                    ToolParameter::optional("location", ParamType::String, "City").with_default("current"),
+                    ToolParameter::optional("units", ParamType::String, "Units").one_of(["metric", "imperial"]),
+                ],
+                permissions: Vec::new(),
+                supports_undo: false,
+                timeout: None,
+            },
+            ToolSpec {
+                name: "calculator".to_string(),
+                description: "Math".to_string(),
+                parameters: vec![
+                    ToolParameter::required("expression", ParamType::String, "Expression"),
+                    ToolParameter::optional("precision", ParamType::Integer, "Digits"),
+                ],
+                permissions: Vec::new(),
+                supports_undo: false,
+                timeout: None,
+            },
+        ]
+    }
+
+    #[test]
+    fn test_matches_json_schema() {
+        let grammar = Arc::new(JsonGrammar::from_json_schema(&json!({
+            "type": "object",
+            "properties": {
+                "action": {"enum": ["set_config", "tune_storage"]},
+                "confidence": {"type": "number"},
+                "tags": {"type": "array", "items": {"type": "string", "maxLength": 3}},
+                "value": {"type": ["string", "null"]},
+            },
+            "required": ["action", "confidence"],
+        })).unwrap());
+
+        assert!(grammar.matches(r#"{"action": "set_config", "confidence": 0.85}"#));
+        assert!(grammar.matches(r#"{"action":"tune_storage","confidence":-1.5e3,"tags":["a", "b\n"],"value":null}"#));
+        assert!(grammar.matches(r#"{"action": "set_config", "confidence": 1, "value": "x \"y\" é"}"#));
+
+        assert!(!grammar.matches(r#"{"action": "reboot", "confidence": 1}"#)); // not in enum
+        assert!(!grammar.matches(r#"{"confidence": 1, "action": "set_config"}"#)); // out of order
+        assert!(!grammar.matches(r#"{"action": "set_config"}"#)); // missing required
+        assert!(!grammar.matches(r#"{"action": "set_config", "confidence": 01}"#));
+        assert!(!grammar.matches(r#"{"action": "set_config", "confidence": 1, "tags": ["long"]}"#));
+        assert!(!grammar.matches(r#"{"action": "set_config", "confidence": 1, "extra": 2}"#));
+        assert!(!grammar.matches(r#"{"action": "set_config",  "confidence": 1}"#)); // one space at most
+        assert!(!grammar.matches(r#"{"action": "set_config", "confidence": 1"#)); // incomplete
+    }
+
+    #[test]
+    fn test_tool_call_grammar() {
+        let grammar = Arc::new(JsonGrammar::tool_call(&weather_specs()));
+
+        assert!(grammar.matches(r#"{"tool": "weather", "arguments": {}}"#));
+        assert!(grammar.matches(r#"{"tool": "weather", "arguments": {"units": "metric"}}"#));
+        assert!(grammar.matches(r#"{"tool": "calculator", "arguments": {"expression": "2+2", "precision": 3}}"#));
+
+        assert!(!grammar.matches(r#"{"tool": "wallet", "arguments": {}}"#));
+        assert!(!grammar.matches(r#"{"tool": "calculator", "arguments": {}}"#));
+        assert!(!grammar.matches(r#"{"tool": "weather", "arguments": {"expression": "2+2"}}"#));
+        assert!(!grammar.matches(r#"{"tool": "calculator", "arguments": {"expression": "1", "precision": 1.5}}"#));
+
+        let any = Arc::new(JsonGrammar::Any);
+        assert!(any.matches(r#"{"nested": [1, {"deep": true}], "empty": {}}"#));
+        assert!(any.matches("42"));
+        assert!(!any.matches(r#"{"a": 1,}"#));
+    }
+
+    /// Toy vocabulary: single characters plus a few multi-character pieces
+    fn vocabulary() -> Arc<TokenVocabulary> {
+        let mut texts: Vec<Option<String>> = vec![None]; // 0 = EOS
+        for c in " {}[]\":,.-0123456789abcdefghijklmnopqrstuvwxyz".chars() {
+            texts.push(Some(c.to_string()));
+        }
+        for piece in ["{\"", "\":", "\": \"", "\", \"", " \"", "weather", "calculator", "tool", "arguments", "expression", "}}", "\n", "é"] {
+            texts.push(Some(piece.to_string()));
+        }
+        Arc::new(TokenVocabulary::new(texts, Some(0)))
+    }
+
+    #[test]
+    fn test_allowed_tokens_and_masking() {
+        let vocabulary = vocabulary();
+        let mut decoder = ConstrainedDecoder::new(vocabulary.clone(), Arc::new(JsonGrammar::tool_call(&weather_specs())));
+        let texts = |tokens: Vec<u32>| -> Vec<String> {
+            tokens.iter().map(|t| vocabulary.token_text(*t).unwrap_or("<eos>").to_string()).collect()
+        };
+
+        assert_eq!(texts(decoder.allowed_tokens()), ["{", "{\""]);
+
+        for piece in ["{\"", "tool", "\": \""] {
+            let id = (0..vocabulary.len() as u32).find(|t| vocabulary.token_text(*t) == Some(piece)).unwrap();
+            decoder.accept(id).unwrap();
+        }
+        let mut allowed = texts(decoder.allowed_tokens());
+        allowed.sort();
+        assert_eq!(allowed, ["c", "calculator", "w", "weather"]);
+        assert!(decoder.accept(1).is_err()); // " " is not allowed here
+
+        let logits = Tensor::zeros(vocabulary.len() + 3, DType::F32, &Device::Cpu).unwrap();
+        let masked: Vec<f32> = decoder.mask_logits(&logits).unwrap().to_vec1().unwrap();
+        assert_eq!(masked.iter().filter(|v| v.is_finite()).count(), 4);
+    }
+
+    #[test]
+    fn test_random_sampling_always_produces_valid_calls() {
+        let vocabulary = vocabulary();
+        let specs = weather_specs();
+        let grammar = Arc::new(JsonGrammar::tool_call(&specs));
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut completed = 0;
+
+        for _ in 0..20 {
+            let mut decoder = ConstrainedDecoder::new(vocabulary.clone(), grammar.clone());
+            for _ in 0..400 {
+                if decoder.is_finished() {
+                    break;
+                }
+                let allowed = decoder.allowed_tokens();
+                // Bias towards closing so outputs stay short
+                let token = allowed.iter().copied()
+                    .find(|t| rng.gen_bool(0.3) && matches!(vocabulary.token_text(*t), Some("\"" | "}" | "}}")))
+                    .unwrap_or_else(|| allowed[rng.gen_range(0..allowed.len())]);
+                decoder.accept(token).unwrap();
+            }
+            if !decoder.is_complete() {
+                continue; // Ran out of budget inside a string
+            }
+
+            let call: crate::tools::ToolCall = serde_json::from_str(decoder.text()).unwrap();
+            let spec = specs.iter().find(|s| s.name == call.tool_name).unwrap();
+            spec.validate(ToolArgs::from_value(call.args).unwrap()).unwrap();
+            completed += 1;
+        }
+        assert!(completed >= 10, "only {} of 20 samples completed", completed);
+    }
+}
@@ -0,0 +1,271 @@
+// Kāraṇa OS - Phase 55: Calibration & Accuracy Measurement
+// Full-precision reference forward pass over Hugging Face Llama weights. It records
+// per-channel activation statistics for the quantizer and provides the teacher
+// predictions that quantized (or distilled) models are measured against.
+
+use anyhow::{Result, anyhow};
+use candle_core::{D, Device, Tensor};
+use candle_transformers::models::quantized_llama::ModelWeights as QLlama;
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Instant;
+use tokenizers::Tokenizer;
+
+use super::quantize::LlamaConfig;
+
+/// Longest calibration / evaluation sequence, in tokens
+pub const MAX_CALIBRATION_TOKENS: usize = 128;
+
+/// Prompts used when the caller supplies none (typical assistant traffic)
+pub const DEFAULT_CALIBRATION_PROMPTS: &[&str] = &[
+    "What is the weather like today and should I bring an umbrella?",
+    "Set a timer for ten minutes and remind me to check the oven.",
+    "Navigate to the nearest coffee shop and tell me how long it takes to walk there.",
+    "Translate the sign in front of me into English.",
+    "Summarize my unread messages from this morning.",
+    "How much battery is left on my glasses?",
+    "Send 25 tokens to Alice for lunch.",
+    "What am I looking at right now? Describe the scene briefly.",
+];
+
+/// Mean squared activation per input channel of each matrix multiply
+#[derive(Debug, Default)]
+pub struct ActivationStats {
+    sums: HashMap<String, (Vec<f64>, usize)>,
+}
+
+impl ActivationStats {
+    /// Accumulate `x` (tokens × channels)
+    fn record(&mut self, key: String, x: &Tensor) -> Result<()> {
+        let tokens = x.dim(0)?;
+        let squares = x.sqr()?.sum(0)?.to_vec1::<f32>()?;
+        let (sums, count) = self.sums.entry(key)
+            .or_insert_with(|| (vec![0.0; squares.len()], 0));
+        for (sum, square) in sums.iter_mut().zip(squares) {
+            *sum += square as f64;
+        }
+        *count += tokens;
+        Ok(())
+    }
+
+    /// Per-channel importance (mean squared activation) for a statistic key
+    pub fn importance(&self, key: &str) -> Option<Vec<f32>> {
+        let (sums, count) = self.sums.get(key)?;
+        let count = (*count).max(1) as f64;
+        Some(sums.iter().map(|sum| (sum / count) as f32).collect())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sums.is_empty()
+    }
+}
+
+/// Unquantized Llama evaluated straight from Hugging Face tensors
+pub struct ReferenceLlama<'a> {
+    config: &'a LlamaConfig,
+    weights: &'a HashMap<String, Tensor>,
+    device: Device,
+    /// Use the slower ops that support backpropagation (see `distill`)
+    trainable: bool,
+}
+
+impl<'a> ReferenceLlama<'a> {
+    pub fn new(config: &'a LlamaConfig, weights: &'a HashMap<String, Tensor>, device: &Device) -> Self {
+        Self { config, weights, device: device.clone(), trainable: false }
+    }
+
+    /// Same model with gradients flowing back to `weights` (e.g. `Var` tensors)
+    pub fn trainable(config: &'a LlamaConfig, weights: &'a HashMap<String, Tensor>, device: &Device) -> Self {
+        Self { trainable: true, ..Self::new(config, weights, device) }
+    }
+
+    fn weight(&self, name: &str) -> Result<&Tensor> {
+        self.weights.get(name).ok_or_else(|| anyhow!("Missing tensor {}", name))
+    }
+
+    fn linear(&self, x: &Tensor, name: &str) -> Result<Tensor> {
+        Ok(x.matmul(&self.weight(name)?.t()?)?)
+    }
+
+    /// Logits for every position (tokens × vocab), recording activation statistics if asked
+    pub fn forward(&self, tokens: &[u32], mut stats: Option<&mut ActivationStats>) -> Result<Tensor> {
+        let cfg = self.config;
+        let (t, n_head, n_kv, head_dim) = (tokens.len(), cfg.num_attention_heads, cfg.kv_heads(), cfg.head_dim());
+        let eps = cfg.rms_norm_eps as f32;
+        let mut record = |key: String, x: &Tensor| match stats.as_deref_mut() {
+            Some(stats) => stats.record(key, x),
+            None => Ok(()),
+        };
+        // The fused kernels have no backward pass
+        let norm = |x: &Tensor, weight: &Tensor| if self.trainable {
+            candle_nn::ops::rms_norm_slow(x, weight, eps)
+        } else {
+            candle_nn::ops::rms_norm(x, weight, eps)
+        };
+        let rope = |x: &Tensor, cos: &Tensor, sin: &Tensor| if self.trainable {
+            candle_nn::rotary_emb::rope_slow(x, cos, sin)
+        } else {
+            candle_nn::rotary_emb::rope(x, cos, sin)
+        };
+        let softmax = |x: &Tensor| if self.trainable {
+            candle_nn::ops::softmax(x, D::Minus1)
+        } else {
+            candle_nn::ops::softmax_last_dim(x)
+        };
+
+        let ids = Tensor::new(tokens, &self.device)?;
+        let mut x = self.weight("model.embed_tokens.weight")?.index_select(&ids, 0)?;
+        let (cos, sin) = self.rotary_tables(t)?;
+        let mask: Vec<f32> = (0..t * t)
+            .map(|i| if i % t > i / t { f32::NEG_INFINITY } else { 0.0 })
+            .collect();
+        let mask = Tensor::from_vec(mask, (t, t), &self.device)?;
+
+        for layer in 0..cfg.num_hidden_layers {
+            let prefix = format!("model.layers.{}", layer);
+            let w = |name: &str| format!("{}.{}.weight", prefix, name);
+
+            let h = norm(&x, self.weight(&w("input_layernorm"))?)?;
+            record(format!("blk.{}.attn_in", layer), &h)?;
+            let heads = |x: Tensor, n: usize| -> Result<Tensor> {
+                Ok(x.reshape((1, t, n, head_dim))?.transpose(1, 2)?.contiguous()?)
+            };
+            let q = heads(self.linear(&h, &w("self_attn.q_proj"))?, n_head)?;
+            let k = heads(self.linear(&h, &w("self_attn.k_proj"))?, n_kv)?;
+            let v = heads(self.linear(&h, &w("self_attn.v_proj"))?, n_kv)?;
+            let q = rope(&q, &cos, &sin)?;
+            let k = rope(&k, &cos, &sin)?;
+            let k = candle_transformers::utils::repeat_kv(k, n_head / n_kv)?;
+            let v = candle_transformers::utils::repeat_kv(v, n_head / n_kv)?;
+
+            let att = (q.matmul(&k.t()?)? / (head_dim as f64).sqrt())?.broadcast_add(&mask)?;
+            let att = softmax(&att)?;
+            let y = att.matmul(&v)?.transpose(1, 2)?.reshape((t, n_head * head_dim))?;
+            record(format!("blk.{}.attn_out_in", layer), &y)?;
+            x = (x + self.linear(&y, &w("self_attn.o_proj"))?)?;
+
+            let h = norm(&x, self.weight(&w("post_attention_layernorm"))?)?;
+            record(format!("blk.{}.ffn_in", layer), &h)?;
+            let gate = candle_nn::ops::silu(&self.linear(&h, &w("mlp.gate_proj"))?)?;
+            let y = (gate * self.linear(&h, &w("mlp.up_proj"))?)?;
+            record(format!("blk.{}.ffn_down_in", layer), &y)?;
+            x = (x + self.linear(&y, &w("mlp.down_proj"))?)?;
+        }
+
+        let x = norm(&x, self.weight("model.norm.weight")?)?;
+        record("output_in".into(), &x)?;
+        let head = if self.weights.contains_key("lm_head.weight") {
+            "lm_head.weight"
+        } else {
+            "model.embed_tokens.weight"
+        };
+        Ok(x.matmul(&self.weight(head)?.t()?)?)
+    }
+
+    fn rotary_tables(&self, t: usize) -> Result<(Tensor, Tensor)> {
+        let head_dim = self.config.head_dim();
+        let theta: Vec<f32> = (0..head_dim)
+            .step_by(2)
+            .map(|i| 1.0 / self.config.rope_theta.powf(i as f32 / head_dim as f32))
+            .collect();
+        let angles: Vec<f32> = (0..t)
+            .flat_map(|pos| theta.iter().map(move |freq| pos as f32 * freq))
+            .collect();
+        let angles = Tensor::from_vec(angles, (t, head_dim / 2), &self.device)?;
+        Ok((angles.cos()?, angles.sin()?))
+    }
+}
+
+/// Calibration prompts tokenized with the model's `tokenizer.json`
+pub fn tokenize_prompts(model_dir: &Path, prompts: &[String]) -> Result<Vec<Vec<u32>>> {
+    let tokenizer = Tokenizer::from_file(model_dir.join("tokenizer.json"))
+        .map_err(|e| anyhow!("Cannot load tokenizer: {}", e))?;
+    let mut sequences = Vec::new();
+    for prompt in prompts {
+        let encoding = tokenizer.encode(prompt.as_str(), true)
+            .map_err(|e| anyhow!("Tokenization error: {}", e))?;
+        let ids: Vec<u32> = encoding.get_ids().iter().copied().take(MAX_CALIBRATION_TOKENS).collect();
+        if ids.len() >= 2 {
+            sequences.push(ids);
+        }
+    }
+    if sequences.is_empty() {
+        return Err(anyhow!("Calibration prompts produced no usable token sequences"));
+    }
+    Ok(sequences)
+}
+
+/// Teacher predictions for a set of sequences
+pub struct ReferenceRun {
+    /// Argmax token at every position of every sequence
+    pub predictions: Vec<Vec<u32>>,
+    /// Prefill time per token
+    pub latency_ms: f32,
+}
+
+/// Run the reference over `sequences`, optionally collecting activation statistics
+pub fn reference_run(
+    model: &ReferenceLlama,
+    sequences: &[Vec<u32>],
+    mut stats: Option<&mut ActivationStats>,
+) -> Result<ReferenceRun> {
+    let start = Instant::now();
+    let mut predictions = Vec::new();
+    for tokens in sequences {
+        let logits = model.forward(tokens, stats.as_deref_mut())?;
+        predictions.push(logits.argmax(D::Minus1)?.to_vec1::<u32>()?);
+    }
+    Ok(ReferenceRun {
+        predictions,
+        latency_ms: per_token_ms(start, sequences),
+    })
+}
+
+/// Share of positions where two runs over the same sequences predict the same token, in percent
+pub fn prediction_agreement(a: &ReferenceRun, b: &ReferenceRun) -> f32 {
+    let (mut matches, mut total) = (0usize, 0usize);
+    for (x, y) in a.predictions.iter().zip(&b.predictions) {
+        matches += x.iter().zip(y).filter(|(x, y)| x == y).count();
+        total += x.len().min(y.len());
+    }
+    matches as f32 / total.max(1) as f32 * 100.0
+}
+
+/// Measured quality of a converted model against the reference
+#[derive(Debug, Clone, Copy)]
+pub struct Agreement {
+    /// Positions whose top-1 token matches the reference, in percent
+    pub top1_percent: f32,
+    /// Prefill time per token
+    pub latency_ms: f32,
+}
+
+/// Compare a GGUF model's next-token predictions with the reference's, position by position
+pub fn measure_agreement(model: &mut QLlama, sequences: &[Vec<u32>], reference: &ReferenceRun, device: &Device) -> Result<Agreement> {
+    let (mut matches, mut total) = (0usize, 0usize);
+    for (tokens, expected) in sequences.iter().zip(&reference.predictions) {
+        // Token by token: the quantized model only returns logits for its last input
+        for (pos, token) in tokens.iter().enumerate() {
+            let x = Tensor::new(&[*token], device)?.unsqueeze(0)?;
+            let predicted = model.forward(&x, pos)?.squeeze(0)?.argmax(D::Minus1)?.to_scalar::<u32>()?;
+            matches += (predicted == expected[pos]) as usize;
+            total += 1;
+        }
+    }
+
+    let start = Instant::now();
+    for tokens in sequences {
+        let x = Tensor::new(tokens.as_slice(), device)?.unsqueeze(0)?;
+        model.forward(&x, 0)?;
+    }
+
+    Ok(Agreement {
+        top1_percent: matches as f32 / total.max(1) as f32 * 100.0,
+        latency_ms: per_token_ms(start, sequences),
+    })
+}
+
+fn per_token_ms(start: Instant, sequences: &[Vec<u32>]) -> f32 {
+    let tokens: usize = sequences.iter().map(Vec::len).sum();
+    start.elapsed().as_secs_f32() * 1000.0 / tokens.max(1) as f32
+}
@@ -0,0 +1,177 @@
+// Kāraṇa OS - Phase 55: Knowledge Distillation
+// Trains a smaller Llama student on its teacher's output distribution over the
+// calibration prompts: cross-entropy to the teacher's temperature-softened
+// probabilities (KL divergence up to a constant), mixed by `alpha` with
+// cross-entropy on the prompts' actual next tokens.
+//
+// A student without a checkpoint starts from every other teacher layer, which keeps
+// the embeddings and output head and halves the depth.
+
+use anyhow::{Context, Result, anyhow};
+use candle_core::{D, Device, Tensor, Var};
+use candle_nn::{AdamW, Optimizer, ParamsAdamW};
+use std::collections::HashMap;
+use std::path::Path;
+
+use super::DistillationConfig;
+use super::calibration::{self, ReferenceLlama};
+use super::quantize::{self, LlamaConfig};
+
+/// Outcome of a training run
+#[derive(Debug, Clone, Copy)]
+pub struct TrainingRun {
+    /// Optimizer steps taken
+    pub steps: usize,
+    /// Mean loss of the last epoch
+    pub final_loss: f32,
+}
+
+/// Create `student_dir` from every other layer of the teacher
+pub fn init_student(teacher_dir: &Path, student_dir: &Path) -> Result<()> {
+    let device = Device::Cpu;
+    let config_path = teacher_dir.join("config.json");
+    let mut config: serde_json::Value = serde_json::from_str(
+        &std::fs::read_to_string(&config_path).with_context(|| format!("Cannot read {:?}", config_path))?,
+    )?;
+    let teacher_layers = LlamaConfig::load(teacher_dir)?.num_hidden_layers;
+    if teacher_layers < 2 {
+        return Err(anyhow!("Teacher has {} layer(s); provide a student checkpoint", teacher_layers));
+    }
+    let kept: Vec<usize> = (0..teacher_layers).step_by(2).collect();
+    config["num_hidden_layers"] = kept.len().into();
+
+    let (weights, _) = quantize::load_safetensors(teacher_dir, &device)?;
+    let mut student = HashMap::new();
+    for (name, tensor) in weights {
+        match layer_index(&name) {
+            None => {
+                student.insert(name, tensor);
+            }
+            Some((layer, rest)) => {
+                if let Some(new_layer) = kept.iter().position(|&kept| kept == layer) {
+                    student.insert(format!("model.layers.{}.{}", new_layer, rest), tensor);
+                }
+            }
+        }
+    }
+
+    std::fs::create_dir_all(student_dir)?;
+    std::fs::copy(teacher_dir.join("tokenizer.json"), student_dir.join("tokenizer.json"))?;
+    std::fs::write(student_dir.join("config.json"), serde_json::to_string_pretty(&config)?)?;
+    candle_core::safetensors::save(&student, student_dir.join("model.safetensors"))?;
+    log::info!("[Distill] Initialized {:?} from {} of {} teacher layers", student_dir, kept.len(), teacher_layers);
+    Ok(())
+}
+
+/// Train the student in `student_dir` against the teacher and save its weights back
+pub fn train_student(
+    teacher_dir: &Path,
+    student_dir: &Path,
+    config: &DistillationConfig,
+    prompts: &[String],
+) -> Result<TrainingRun> {
+    let device = Device::Cpu;
+    let sequences = calibration::tokenize_prompts(teacher_dir, prompts)?;
+    let temperature = config.temperature.max(1e-3) as f64;
+
+    let teacher_config = LlamaConfig::load(teacher_dir)?;
+    let soft_targets = {
+        let (weights, _) = quantize::load_safetensors(teacher_dir, &device)?;
+        let teacher = ReferenceLlama::new(&teacher_config, &weights, &device);
+        sequences.iter()
+            .map(|tokens| Ok(candle_nn::ops::softmax_last_dim(&(teacher.forward(tokens, None)? / temperature)?)?))
+            .collect::<Result<Vec<Tensor>>>()?
+    };
+
+    let student_config = LlamaConfig::load(student_dir)?;
+    if student_config.vocab_size != teacher_config.vocab_size {
+        return Err(anyhow!(
+            "Student vocabulary ({}) differs from the teacher's ({})",
+            student_config.vocab_size,
+            teacher_config.vocab_size
+        ));
+    }
+    let (weights, _) = quantize::load_safetensors(student_dir, &device)?;
+    let vars = weights.iter()
+        .map(|(name, tensor)| Ok((name.clone(), Var::from_tensor(tensor)?)))
+        .collect::<Result<HashMap<String, Var>>>()?;
+    drop(weights);
+    // Var tensors share storage with their Var, so optimizer steps show up here
+    let tensors: HashMap<String, Tensor> = vars.iter()
+        .map(|(name, var)| (name.clone(), var.as_tensor().clone()))
+        .collect();
+    let student = ReferenceLlama::trainable(&student_config, &tensors, &device);
+
+    let mut optimizer = AdamW::new(vars.into_values().collect(), ParamsAdamW {
+        lr: config.learning_rate as f64,
+        weight_decay: 0.0,
+        ..Default::default()
+    })?;
+    let order: Vec<usize> = (0..sequences.len()).collect();
+    let mut run = TrainingRun { steps: 0, final_loss: 0.0 };
+    for epoch in 0..config.epochs {
+        let mut epoch_loss = 0.0;
+        for batch in order.chunks(config.batch_size.max(1)) {
+            let losses = batch.iter()
+                .map(|&i| distillation_loss(&student.forward(&sequences[i], None)?, &soft_targets[i], &sequences[i], config))
+                .collect::<Result<Vec<Tensor>>>()?;
+            let loss = Tensor::stack(&losses, 0)?.mean_all()?;
+            optimizer.backward_step(&loss)?;
+            epoch_loss += loss.to_scalar::<f32>()? * batch.len() as f32;
+            run.steps += 1;
+        }
+        run.final_loss = epoch_loss / sequences.len() as f32;
+        log::debug!("[Distill] Epoch {}/{}: loss {:.4}", epoch + 1, config.epochs, run.final_loss);
+    }
+
+    save_weights(student_dir, &tensors)?;
+    log::info!("[Distill] Trained {:?} for {} steps (loss {:.4})", student_dir, run.steps, run.final_loss);
+    Ok(run)
+}
+
+/// alpha x T² x soft-target cross-entropy + (1 - alpha) x next-token cross-entropy
+fn distillation_loss(logits: &Tensor, soft_targets: &Tensor, tokens: &[u32], config: &DistillationConfig) -> Result<Tensor> {
+    let temperature = config.temperature.max(1e-3) as f64;
+    let alpha = config.alpha.clamp(0.0, 1.0) as f64;
+
+    let log_probs = candle_nn::ops::log_softmax(&(logits / temperature)?, D::Minus1)?;
+    // T² keeps the soft gradients on the same scale as the hard ones
+    let soft = ((soft_targets * log_probs)?.sum(D::Minus1)?.mean_all()? * (-temperature * temperature))?;
+
+    let positions = tokens.len() - 1;
+    let next = Tensor::new(&tokens[1..], logits.device())?;
+    let hard = candle_nn::loss::cross_entropy(&logits.narrow(0, 0, positions)?, &next)?;
+
+    Ok(((soft * alpha)? + (hard * (1.0 - alpha))?)?)
+}
+
+/// Replace the checkpoint's shards with one `model.safetensors`
+fn save_weights(dir: &Path, tensors: &HashMap<String, Tensor>) -> Result<()> {
+    let staged = dir.join("model.safetensors.tmp");
+    candle_core::safetensors::save(tensors, &staged)?;
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "safetensors") {
+            std::fs::remove_file(&path)?;
+        }
+    }
+    std::fs::rename(&staged, dir.join("model.safetensors"))?;
+    Ok(())
+}
+
+/// Split `model.layers.<n>.<rest>` into the layer index and the rest
+fn layer_index(name: &str) -> Option<(usize, &str)> {
+    let (layer, rest) = name.strip_prefix("model.layers.")?.split_once('.')?;
+    Some((layer.parse().ok()?, rest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layer_index() {
+        assert_eq!(layer_index("model.layers.12.mlp.up_proj.weight"), Some((12, "mlp.up_proj.weight")));
+        assert_eq!(layer_index("model.embed_tokens.weight"), None);
+    }
+}
@@ -0,0 +1,323 @@
+// Kāraṇa OS - Phase 55: Post-Training Quantization
+// Converts Hugging Face Llama safetensors into GGUF files readable by the quantized
+// Llama loader, quantizing matrices to Q8_0 / Q4_0 blocks.
+//
+// Block scales are chosen with calibration statistics: each 32-weight block tries a few
+// clipping ratios and keeps the one with the lowest error weighted by how strongly the
+// calibration prompts drive the matching input channels.
+
+use anyhow::{Context, Result, anyhow};
+use candle_core::quantized::gguf_file::{self, Value};
+use candle_core::quantized::{GgmlDType, QTensor};
+use candle_core::{DType, Device, Tensor};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::Path;
+
+use super::QuantizationLevel;
+use super::calibration::ActivationStats;
+
+/// Weights per Q8_0 / Q4_0 block
+pub const BLOCK_SIZE: usize = 32;
+
+/// Clipping ratios tried per block (1.0 = plain absmax scaling)
+const CLIP_RATIOS: [f32; 7] = [1.0, 0.95, 0.9, 0.85, 0.8, 0.75, 0.7];
+
+/// Hugging Face Llama `config.json` (the fields the conversion needs)
+#[derive(Debug, Clone, Deserialize)]
+pub struct LlamaConfig {
+    pub hidden_size: usize,
+    pub intermediate_size: usize,
+    pub num_hidden_layers: usize,
+    pub num_attention_heads: usize,
+    #[serde(default)]
+    pub num_key_value_heads: Option<usize>,
+    pub vocab_size: usize,
+    #[serde(default = "default_rms_norm_eps")]
+    pub rms_norm_eps: f64,
+    #[serde(default = "default_rope_theta")]
+    pub rope_theta: f32,
+    #[serde(default = "default_max_position_embeddings")]
+    pub max_position_embeddings: usize,
+    #[serde(default)]
+    pub tie_word_embeddings: bool,
+}
+
+fn default_rms_norm_eps() -> f64 {
+    1e-5
+}
+
+fn default_rope_theta() -> f32 {
+    10000.0
+}
+
+fn default_max_position_embeddings() -> usize {
+    2048
+}
+
+impl LlamaConfig {
+    pub fn load(model_dir: &Path) -> Result<Self> {
+        let path = model_dir.join("config.json");
+        let json = std::fs::read_to_string(&path)
+            .with_context(|| format!("Cannot read {:?}", path))?;
+        Ok(serde_json::from_str(&json)?)
+    }
+
+    pub fn kv_heads(&self) -> usize {
+        self.num_key_value_heads.unwrap_or(self.num_attention_heads)
+    }
+
+    pub fn head_dim(&self) -> usize {
+        self.hidden_size / self.num_attention_heads
+    }
+}
+
+/// Load every `*.safetensors` shard in `model_dir` as F32 tensors.
+/// Returns the tensors and the total size of the shards on disk.
+pub fn load_safetensors(model_dir: &Path, device: &Device) -> Result<(HashMap<String, Tensor>, u64)> {
+    let mut shards: Vec<_> = std::fs::read_dir(model_dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "safetensors"))
+        .collect();
+    shards.sort();
+    if shards.is_empty() {
+        return Err(anyhow!("No safetensors weights in {:?}", model_dir));
+    }
+
+    let mut weights = HashMap::new();
+    let mut bytes = 0;
+    for shard in shards {
+        bytes += std::fs::metadata(&shard)?.len();
+        for (name, tensor) in candle_core::safetensors::load(&shard, device)? {
+            weights.insert(name, tensor.to_dtype(DType::F32)?);
+        }
+    }
+    Ok((weights, bytes))
+}
+
+/// GGUF tensor name for a Hugging Face Llama tensor (None for tensors GGUF does not store)
+pub fn gguf_name(hf_name: &str) -> Option<String> {
+    match hf_name {
+        "model.embed_tokens.weight" => return Some("token_embd.weight".into()),
+        "model.norm.weight" => return Some("output_norm.weight".into()),
+        "lm_head.weight" => return Some("output.weight".into()),
+        _ => {}
+    }
+
+    let rest = hf_name.strip_prefix("model.layers.")?;
+    let (layer, suffix) = rest.split_once('.')?;
+    let layer: usize = layer.parse().ok()?;
+    let name = match suffix {
+        "self_attn.q_proj.weight" => "attn_q",
+        "self_attn.k_proj.weight" => "attn_k",
+        "self_attn.v_proj.weight" => "attn_v",
+        "self_attn.o_proj.weight" => "attn_output",
+        "mlp.gate_proj.weight" => "ffn_gate",
+        "mlp.up_proj.weight" => "ffn_up",
+        "mlp.down_proj.weight" => "ffn_down",
+        "input_layernorm.weight" => "attn_norm",
+        "post_attention_layernorm.weight" => "ffn_norm",
+        _ => return None,
+    };
+    Some(format!("blk.{}.{}.weight", layer, name))
+}
+
+/// Calibration statistic describing the inputs of a GGUF matrix
+fn activation_key(gguf_name: &str, tied_embeddings: bool) -> Option<String> {
+    if gguf_name == "output.weight" || (tied_embeddings && gguf_name == "token_embd.weight") {
+        return Some("output_in".into());
+    }
+    let (block, matrix) = gguf_name.strip_suffix(".weight")?.rsplit_once('.')?;
+    let input = match matrix {
+        "attn_q" | "attn_k" | "attn_v" => "attn_in",
+        "attn_output" => "attn_out_in",
+        "ffn_gate" | "ffn_up" => "ffn_in",
+        "ffn_down" => "ffn_down_in",
+        _ => return None,
+    };
+    Some(format!("{}.{}", block, input))
+}
+
+/// Reorder Q/K rows from the Hugging Face rotary layout (halves) to the GGUF one
+/// (interleaved pairs), as llama.cpp's converter does
+fn permute_rotary(weight: &Tensor, heads: usize) -> Result<Tensor> {
+    let (rows, cols) = weight.dims2()?;
+    Ok(weight
+        .reshape((heads, 2, rows / heads / 2, cols))?
+        .transpose(1, 2)?
+        .contiguous()?
+        .reshape((rows, cols))?)
+}
+
+/// Clip each block of `weights` (row-major, `cols` per row) to the range whose
+/// quantization error, weighted by `importance[col]`, is lowest
+pub fn calibrate_clipping(weights: &mut [f32], cols: usize, importance: &[f32], dtype: GgmlDType) {
+    for (i, block) in weights.chunks_mut(BLOCK_SIZE).enumerate() {
+        let start = (i * BLOCK_SIZE) % cols;
+        let importance = &importance[start..start + block.len()];
+        let extreme = signed_extreme(block);
+        if extreme == 0.0 {
+            continue;
+        }
+
+        let mut best = (f32::INFINITY, 1.0);
+        for clip in CLIP_RATIOS {
+            let error: f32 = block.iter().zip(importance)
+                .map(|(w, imp)| imp * (w - fake_quantize(*w, extreme * clip, dtype)).powi(2))
+                .sum();
+            if error < best.0 {
+                best = (error, clip);
+            }
+        }
+        let limit = extreme.abs() * best.1;
+        for w in block.iter_mut() {
+            *w = w.clamp(-limit, limit);
+        }
+    }
+}
+
+/// Block value with the largest magnitude, sign included
+fn signed_extreme(block: &[f32]) -> f32 {
+    block.iter().fold(0f32, |m, w| if w.abs() > m.abs() { *w } else { m })
+}
+
+/// Value `w` decodes to when its block's signed extreme is `extreme`
+/// (same rounding as ggml's reference quantizers)
+fn fake_quantize(w: f32, extreme: f32, dtype: GgmlDType) -> f32 {
+    match dtype {
+        GgmlDType::Q8_0 => {
+            let d = extreme.abs() / 127.0;
+            (w / d).round().clamp(-127.0, 127.0) * d
+        }
+        GgmlDType::Q4_0 => {
+            // 16 levels (-8..=7) × d, the extreme landing on -8
+            let d = extreme / -8.0;
+            ((w / d + 8.5).floor().clamp(0.0, 15.0) - 8.0) * d
+        }
+        _ => w,
+    }
+}
+
+/// Write the converted model to `path`; returns the GGUF file size in bytes
+pub fn write_gguf(
+    config: &LlamaConfig,
+    weights: &HashMap<String, Tensor>,
+    level: QuantizationLevel,
+    stats: &ActivationStats,
+    name: &str,
+    path: &Path,
+) -> Result<u64> {
+    let matrix_dtype = level.ggml_dtype()
+        .ok_or_else(|| anyhow!("{:?} has no GGUF encoding", level))?;
+
+    let mut tensors = Vec::new();
+    for (hf_name, weight) in weights {
+        let Some(gguf_name) = gguf_name(hf_name) else {
+            continue;
+        };
+        let weight = match gguf_name.rsplit('.').nth(1) {
+            Some("attn_q") => permute_rotary(weight, config.num_attention_heads)?,
+            Some("attn_k") => permute_rotary(weight, config.kv_heads())?,
+            _ => weight.clone(),
+        };
+        let qtensor = quantize_tensor(&gguf_name, &weight, matrix_dtype, stats, config.tie_word_embeddings)?;
+        tensors.push((gguf_name, qtensor));
+    }
+    tensors.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let metadata = [
+        ("general.architecture", Value::String("llama".into())),
+        ("general.name", Value::String(name.into())),
+        ("general.file_type", Value::U32(level.gguf_file_type())),
+        ("llama.context_length", Value::U32(config.max_position_embeddings as u32)),
+        ("llama.embedding_length", Value::U32(config.hidden_size as u32)),
+        ("llama.feed_forward_length", Value::U32(config.intermediate_size as u32)),
+        ("llama.block_count", Value::U32(config.num_hidden_layers as u32)),
+        ("llama.attention.head_count", Value::U32(config.num_attention_heads as u32)),
+        ("llama.attention.head_count_kv", Value::U32(config.kv_heads() as u32)),
+        ("llama.attention.layer_norm_rms_epsilon", Value::F32(config.rms_norm_eps as f32)),
+        ("llama.rope.dimension_count", Value::U32(config.head_dim() as u32)),
+        ("llama.rope.freq_base", Value::F32(config.rope_theta)),
+    ];
+    let metadata: Vec<(&str, &Value)> = metadata.iter().map(|(k, v)| (*k, v)).collect();
+    let tensors: Vec<(&str, &QTensor)> = tensors.iter().map(|(k, v)| (k.as_str(), v)).collect();
+
+    let mut file = std::fs::File::create(path)?;
+    gguf_file::write(&mut file, &metadata, &tensors)?;
+    Ok(std::fs::metadata(path)?.len())
+}
+
+/// Norms stay F32; matrices use `dtype` when their rows split into whole blocks
+fn quantize_tensor(
+    gguf_name: &str,
+    weight: &Tensor,
+    dtype: GgmlDType,
+    stats: &ActivationStats,
+    tied_embeddings: bool,
+) -> Result<QTensor> {
+    let Ok((rows, cols)) = weight.dims2() else {
+        return Ok(QTensor::quantize(weight, GgmlDType::F32)?);
+    };
+    let block_quantized = matches!(dtype, GgmlDType::Q8_0 | GgmlDType::Q4_0);
+    if block_quantized && cols % BLOCK_SIZE != 0 {
+        log::warn!("[Quantize] {} has {} columns, keeping F32", gguf_name, cols);
+        return Ok(QTensor::quantize(weight, GgmlDType::F32)?);
+    }
+    if !block_quantized {
+        return Ok(QTensor::quantize(weight, dtype)?);
+    }
+
+    let importance = activation_key(gguf_name, tied_embeddings)
+        .and_then(|key| stats.importance(&key))
+        .filter(|importance| importance.len() == cols)
+        .unwrap_or_else(|| vec![1.0; cols]);
+    let mut values = weight.flatten_all()?.to_vec1::<f32>()?;
+    calibrate_clipping(&mut values, cols, &importance, dtype);
+
+    let clipped = Tensor::from_vec(values, (rows, cols), weight.device())?;
+    Ok(QTensor::quantize(&clipped, dtype)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gguf_names() {
+        assert_eq!(gguf_name("model.layers.3.self_attn.o_proj.weight").as_deref(), Some("blk.3.attn_output.weight"));
+        assert_eq!(gguf_name("model.layers.0.post_attention_layernorm.weight").as_deref(), Some("blk.0.ffn_norm.weight"));
+        assert_eq!(gguf_name("model.layers.0.self_attn.rotary_emb.inv_freq"), None);
+        assert_eq!(activation_key("blk.3.attn_k.weight", false).as_deref(), Some("blk.3.attn_in"));
+        assert_eq!(activation_key("token_embd.weight", true).as_deref(), Some("output_in"));
+        assert_eq!(activation_key("token_embd.weight", false), None);
+    }
+
+    #[test]
+    fn test_clipping_lowers_weighted_error() {
+        // One outlier per block; channels 16.. matter most to the calibration inputs
+        let weights: Vec<f32> = (0..64)
+            .map(|i| if i % 32 == 0 { 1.0 } else { ((i * 37 % 17) as f32 - 8.0) / 80.0 })
+            .collect();
+        let importance: Vec<f32> = (0..32).map(|i| if i < 16 { 0.01 } else { 1.0 }).collect();
+
+        for dtype in [GgmlDType::Q8_0, GgmlDType::Q4_0] {
+            let weighted_error = |clipped: &[f32]| -> f32 {
+                clipped.chunks(32).zip(weights.chunks(32)).map(|(clipped, original)| {
+                    let extreme = signed_extreme(clipped);
+                    clipped.iter().zip(original).zip(&importance)
+                        .map(|((c, w), imp)| imp * (w - fake_quantize(*c, extreme, dtype)).powi(2))
+                        .sum::<f32>()
+                }).sum()
+            };
+
+            let mut calibrated = weights.clone();
+            calibrate_clipping(&mut calibrated, 32, &importance, dtype);
+            let (after, before) = (weighted_error(&calibrated), weighted_error(&weights));
+            assert!(after <= before, "{:?}", dtype);
+            if dtype == GgmlDType::Q4_0 {
+                // 16 levels cannot afford to spend range on an unimportant outlier
+                assert!(after < before * 0.8);
+            }
+        }
+    }
+}
@@ -0,0 +1,105 @@
+// Phase 7.6: Generative model weights by architecture
+// Each model size ships a GGUF of a different family (TinyLlama, Phi-3), so the
+// generative engine loads whichever quantized implementation the file declares in
+// `general.architecture` instead of assuming Llama.
+
+use anyhow::{Context, Result, anyhow};
+use candle_core::quantized::gguf_file;
+use candle_core::{Device, Tensor};
+use candle_transformers::models::quantized_llama::ModelWeights as QLlama;
+use candle_transformers::models::quantized_phi3::ModelWeights as QPhi3;
+use std::path::Path;
+use tokenizers::Tokenizer;
+
+/// Quantized generative model of any supported architecture
+#[derive(Debug, Clone)]
+pub enum GenModel {
+    Llama(Box<QLlama>),
+    /// Phi-3 writes its KV cache in place and never resets it, so every prompt
+    /// starts again from a copy of the freshly loaded model (`pristine`)
+    Phi3 { model: Box<QPhi3>, pristine: Box<QPhi3> },
+}
+
+impl GenModel {
+    /// Load a GGUF file, picking the implementation from its architecture
+    pub fn load(path: &Path, device: &Device) -> Result<Self> {
+        let mut file = std::fs::File::open(path)
+            .with_context(|| format!("Cannot open model {:?}", path))?;
+        let content = gguf_file::Content::read(&mut file)?;
+        Self::from_gguf(content, &mut file, device)
+    }
+
+    pub fn from_gguf<R: std::io::Seek + std::io::Read>(
+        content: gguf_file::Content,
+        reader: &mut R,
+        device: &Device,
+    ) -> Result<Self> {
+        match architecture(&content)?.as_str() {
+            "llama" => Ok(Self::Llama(Box::new(QLlama::from_gguf(content, reader, device)?))),
+            "phi3" => {
+                let model = Box::new(QPhi3::from_gguf(false, content, reader, device)?);
+                Ok(Self::Phi3 { pristine: model.clone(), model })
+            }
+            other => Err(anyhow!("Unsupported model architecture '{}'", other)),
+        }
+    }
+
+    /// GGUF architecture name ("llama", "phi3")
+    pub fn architecture(&self) -> &'static str {
+        match self {
+            Self::Llama(_) => "llama",
+            Self::Phi3 { .. } => "phi3",
+        }
+    }
+
+    /// Logits of the last position of `x`, which starts at `index_pos`
+    pub fn forward(&mut self, x: &Tensor, index_pos: usize) -> Result<Tensor> {
+        match self {
+            Self::Llama(model) => Ok(model.forward(x, index_pos)?),
+            Self::Phi3 { model, pristine } => {
+                if index_pos == 0 {
+                    *model = pristine.clone();
+                }
+                Ok(model.forward(x, index_pos)?)
+            }
+        }
+    }
+
+    /// Whether a clone can later resume from this model's KV cache. Llama's cache is
+    /// rebuilt on every append so clones are independent; Phi-3 clones share one
+    /// buffer that later appends overwrite.
+    pub fn resumable(&self) -> bool {
+        matches!(self, Self::Llama(_))
+    }
+
+    /// Tokens that end a generation
+    pub fn stop_tokens(&self, tokenizer: &Tokenizer) -> Vec<u32> {
+        let names: &[&str] = match self {
+            Self::Llama(_) => &["</s>"],
+            Self::Phi3 { .. } => &["<|end|>", "<|endoftext|>"],
+        };
+        let tokens: Vec<u32> = names.iter().filter_map(|name| tokenizer.token_to_id(name)).collect();
+        if tokens.is_empty() { vec![2] } else { tokens }
+    }
+}
+
+/// `general.architecture` of a GGUF file
+pub fn architecture(content: &gguf_file::Content) -> Result<String> {
+    let value = content.metadata.get("general.architecture")
+        .ok_or_else(|| anyhow!("GGUF is missing general.architecture"))?;
+    Ok(value.to_string()?.clone())
+}
+
+/// KV memory one encoded token occupies: K+V x layers x KV heads x head dim x f32
+pub fn kv_bytes_per_token(content: &gguf_file::Content) -> Result<usize> {
+    let arch = architecture(content)?;
+    let get = |key: &str| -> Result<usize> {
+        let key = format!("{}.{}", arch, key);
+        let value = content.metadata.get(&key).ok_or_else(|| anyhow!("GGUF is missing {}", key))?;
+        Ok(value.to_u32()? as usize)
+    };
+    let heads = get("attention.head_count")?;
+    let kv_heads = get("attention.head_count_kv").unwrap_or(heads);
+    let head_dim = get("embedding_length")? / heads.max(1);
+    Ok(2 * get("block_count")? * kv_heads * head_dim * 4)
+}
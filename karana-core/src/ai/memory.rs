@@ -1,9 +1,37 @@
 // Phase 54.3: Long-Term Memory & Feedback System
+//
+// Phase 7.7: Tiered episodic memory. Recent turns are kept verbatim; older ones are
+// periodically consolidated into `Episode`s (a summary from the local LLM, its
+// embedding, time span and extracted entities). Recall ranks episodes by recency,
+// importance and semantic similarity, and `forget` removes a topic from both tiers.
+// Both tiers are saved to one JSON file after every change, so episodes and forgotten
+// topics survive a restart.
 use anyhow::{Result, Context};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
-use std::collections::HashMap;
-use std::sync::{Arc, RwLock};
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex as StdMutex, RwLock};
+
+use crate::ai_layer::entities::{EntityExtractor, EntityType};
+use crate::ai_layer::AiContext;
+use super::KaranaAI;
+
+/// Turns always kept verbatim, however old
+const RECENT_TURNS: usize = 20;
+/// Turns younger than this are never consolidated (s)
+const RECENT_WINDOW_SECS: i64 = 60 * 60;
+/// A pause this long starts a new episode (s)
+const EPISODE_GAP_SECS: i64 = 30 * 60;
+/// Recency score halves every week
+const EPISODE_HALF_LIFE_SECS: f32 = 7.0 * 24.0 * 3600.0;
+/// Episodes less similar than this are never recalled
+const MIN_RECALL_SIMILARITY: f32 = 0.3;
+/// Recall score weights: recency, importance, similarity
+const RECALL_WEIGHTS: (f32, f32, f32) = (0.2, 0.3, 0.5);
+/// Episodes included in `retrieve_context`
+const CONTEXT_EPISODES: usize = 3;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SessionRecord {
@@ -23,10 +51,107 @@ pub struct ContextWindow {
     pub common_patterns: HashMap<String, f32>,  // intent -> frequency
 }
 
+/// Entity mentioned in an episode (from `ai_layer::entities`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EpisodeEntity {
+    pub kind: String,
+    pub value: String,
+}
+
+/// Consolidated summary of an older stretch of conversation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Episode {
+    pub id: u64,
+    pub summary: String,
+    /// Embedding of `summary` (empty if no embedding model was available)
+    pub embedding: Vec<f32>,
+    pub start: i64,
+    pub end: i64,
+    pub entities: Vec<EpisodeEntity>,
+    /// 0-1, from feedback, confidence and length of the conversation
+    pub importance: f32,
+    pub turn_count: usize,
+}
+
+/// Episode returned by `recall`, with its ranking
+#[derive(Debug, Clone)]
+pub struct RecalledEpisode {
+    pub episode: Episode,
+    pub score: f32,
+    pub similarity: f32,
+}
+
+/// What `forget` removed
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ForgetReport {
+    pub turns_removed: usize,
+    pub episodes_rewritten: usize,
+    pub episodes_removed: usize,
+}
+
+/// Summarizes and embeds memories; `KaranaAI` provides the on-device implementation
+pub trait MemorySummarizer: Send + Sync {
+    /// Short description of a stretch of conversation
+    fn summarize(&self, turns: &[SessionRecord]) -> Result<String>;
+
+    /// `summary` rewritten without anything about `topic` (empty if nothing is left)
+    fn redact(&self, summary: &str, topic: &str) -> Result<String>;
+
+    fn embed(&self, text: &str) -> Result<Vec<f32>>;
+}
+
+impl MemorySummarizer for StdMutex<KaranaAI> {
+    fn summarize(&self, turns: &[SessionRecord]) -> Result<String> {
+        let transcript: String = turns.iter()
+            .map(|turn| format!("User: {}\nAssistant: {}\n", turn.intent, turn.response))
+            .collect();
+        let prompt = format!(
+            "Summarize this conversation between a user and their smart glasses in one to three \
+             sentences. Keep names, places, times and decisions.\n\n{}\nSummary:",
+            transcript
+        );
+        self.lock().map_err(|e| anyhow::anyhow!("Lock poisoned: {}", e))?.complete(&prompt, 120)
+    }
+
+    fn redact(&self, summary: &str, topic: &str) -> Result<String> {
+        let prompt = format!(
+            "Rewrite this summary without any mention of \"{}\". If nothing is left, answer NONE.\n\n\
+             Summary: {}\nRewritten:",
+            topic, summary
+        );
+        let rewritten = self.lock().map_err(|e| anyhow::anyhow!("Lock poisoned: {}", e))?.complete(&prompt, 120)?;
+        Ok(if rewritten.trim().eq_ignore_ascii_case("none") { String::new() } else { rewritten })
+    }
+
+    fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        self.lock().map_err(|e| anyhow::anyhow!("Lock poisoned: {}", e))?.embed(text)
+    }
+}
+
 pub struct OracleMemory {
     sessions: Arc<RwLock<HashMap<String, SessionRecord>>>,
     context: Arc<RwLock<ContextWindow>>,
     feedback_threshold: f32,
+    // Phase 7.7: Tiered memory
+    turns: Arc<RwLock<VecDeque<SessionRecord>>>,
+    episodes: Arc<RwLock<Vec<Episode>>>,
+    next_episode_id: Arc<RwLock<u64>>,
+    summarizer: Option<Arc<dyn MemorySummarizer>>,
+    // Held while consolidating so `forget` cannot miss turns being summarized
+    consolidation: StdMutex<()>,
+    storage_path: Option<PathBuf>,
+    // Held while saving so concurrent saves cannot interleave
+    saving: StdMutex<()>,
+}
+
+/// What `OracleMemory` keeps on disk
+#[derive(Serialize, Deserialize)]
+struct MemorySnapshot {
+    sessions: HashMap<String, SessionRecord>,
+    context: ContextWindow,
+    turns: VecDeque<SessionRecord>,
+    episodes: Vec<Episode>,
+    next_episode_id: u64,
 }
 
 impl OracleMemory {
@@ -40,7 +165,39 @@ impl OracleMemory {
                 common_patterns: HashMap::new(),
             })),
             feedback_threshold: 0.7,
+            turns: Arc::new(RwLock::new(VecDeque::new())),
+            episodes: Arc::new(RwLock::new(Vec::new())),
+            next_episode_id: Arc::new(RwLock::new(1)),
+            summarizer: None,
+            consolidation: StdMutex::new(()),
+            storage_path: None,
+            saving: StdMutex::new(()),
+        }
+    }
+
+    /// Phase 7.7: Memory saved to `storage_path`, starting from what is already there
+    pub fn open(storage_path: PathBuf) -> Result<Self> {
+        if let Some(parent) = storage_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let memory = Self { storage_path: Some(storage_path), ..Self::new() };
+        if let Some(path) = memory.storage_path.as_deref().filter(|path| path.exists()) {
+            memory.load_from_disk(path)
+                .with_context(|| format!("Cannot load memory from {:?}", path))?;
         }
+        Ok(memory)
+    }
+
+    /// Path of the JSON memory file (`None` if memory is not persisted)
+    pub fn storage_path(&self) -> Option<&Path> {
+        self.storage_path.as_deref()
+    }
+
+    /// Summarize and embed episodes with `summarizer` (e.g. `Arc<Mutex<KaranaAI>>`).
+    /// Without one, summaries are extractive and recall falls back to keywords.
+    pub fn with_summarizer(mut self, summarizer: Arc<dyn MemorySummarizer>) -> Self {
+        self.summarizer = Some(summarizer);
+        self
     }
 
     /// Store session with initial confidence
@@ -57,7 +214,10 @@ impl OracleMemory {
             tools_used: tools,
         };
 
-        sessions.insert(intent.to_string(), record);
+        sessions.insert(intent.to_string(), record.clone());
+        self.turns.write()
+            .map_err(|e| anyhow::anyhow!("Lock poisoned: {}", e))?
+            .push_back(record);
 
         // Update context window
         let mut context = self.context.write()
@@ -71,7 +231,8 @@ impl OracleMemory {
         // Update pattern frequency
         *context.common_patterns.entry(intent.to_string()).or_insert(0.0) += 1.0;
 
-        Ok(())
+        drop((sessions, context));
+        self.save()
     }
 
     /// Retrieve relevant context for current request
@@ -113,6 +274,15 @@ impl OracleMemory {
             }
         }
 
+        // Older conversations, consolidated into episodes
+        drop(sessions);
+        for recalled in self.recall(intent, CONTEXT_EPISODES)? {
+            let date = chrono::DateTime::from_timestamp(recalled.episode.start, 0)
+                .map(|t| t.format("%Y-%m-%d").to_string())
+                .unwrap_or_default();
+            relevant.push(format!("Earlier ({}): {}", date, recalled.episode.summary));
+        }
+
         Ok(relevant)
     }
 
@@ -125,11 +295,342 @@ impl OracleMemory {
             // Update score with exponential moving average
             let delta = if helpful { 0.1 } else { -0.1 };
             record.feedback_score = (record.feedback_score + delta).clamp(0.0, 1.0);
+
+            let mut turns = self.turns.write()
+                .map_err(|e| anyhow::anyhow!("Lock poisoned: {}", e))?;
+            if let Some(turn) = turns.iter_mut().rev().find(|turn| turn.intent == intent) {
+                turn.feedback_score = record.feedback_score;
+            }
+            drop((turns, sessions));
+            return self.save();
         }
 
         Ok(())
     }
 
+    /// Phase 7.7: Consolidate turns that left the recent window into episodes.
+    /// Returns the number of episodes created. Call periodically (see `spawn_consolidation`).
+    pub fn consolidate(&self) -> Result<usize> {
+        self.consolidate_at(chrono::Utc::now().timestamp())
+    }
+
+    fn consolidate_at(&self, now: i64) -> Result<usize> {
+        let _guard = self.consolidation.lock()
+            .map_err(|e| anyhow::anyhow!("Lock poisoned: {}", e))?;
+
+        // Take the old turns out so the LLM runs without holding the memory locks
+        let old: Vec<SessionRecord> = {
+            let mut turns = self.turns.write()
+                .map_err(|e| anyhow::anyhow!("Lock poisoned: {}", e))?;
+            let eligible = turns.iter()
+                .take(turns.len().saturating_sub(RECENT_TURNS))
+                .take_while(|turn| now - turn.timestamp >= RECENT_WINDOW_SECS)
+                .count();
+            turns.drain(..eligible).collect()
+        };
+        if old.is_empty() {
+            return Ok(0);
+        }
+
+        let mut groups: Vec<&[SessionRecord]> = Vec::new();
+        let mut start = 0;
+        for i in 1..=old.len() {
+            if i == old.len() || old[i].timestamp - old[i - 1].timestamp > EPISODE_GAP_SECS {
+                groups.push(&old[start..i]);
+                start = i;
+            }
+        }
+
+        let mut episodes = Vec::new();
+        for group in groups {
+            match self.build_episode(group) {
+                Ok(episode) => episodes.push(episode),
+                Err(e) => {
+                    // Keep the turns verbatim and retry on the next run
+                    let mut turns = self.turns.write()
+                        .map_err(|e| anyhow::anyhow!("Lock poisoned: {}", e))?;
+                    let consolidated: usize = episodes.iter().map(|e: &Episode| e.turn_count).sum();
+                    for turn in old[consolidated..].iter().rev() {
+                        turns.push_front(turn.clone());
+                    }
+                    drop(turns);
+                    self.store_episodes(episodes, &old[..consolidated])?;
+                    return Err(e.context("Episode summarization failed"));
+                }
+            }
+        }
+
+        let created = episodes.len();
+        self.store_episodes(episodes, &old)?;
+        log::info!("[Memory] Consolidated {} turns into {} episodes", old.len(), created);
+        Ok(created)
+    }
+
+    fn build_episode(&self, turns: &[SessionRecord]) -> Result<Episode> {
+        let summary = match &self.summarizer {
+            Some(summarizer) => summarizer.summarize(turns)?.trim().to_string(),
+            None => extractive_summary(turns),
+        };
+        let embedding = self.embed(&summary);
+
+        // Not kept in `self`: custom extractors are not `Send`
+        let extractor = EntityExtractor::new();
+        let context = AiContext::default();
+        let mut entities: Vec<EpisodeEntity> = Vec::new();
+        for turn in turns {
+            for text in [&turn.intent, &turn.response] {
+                for entity in extractor.extract(text, &context) {
+                    if matches!(entity.entity_type, EntityType::Number | EntityType::Other) {
+                        continue;
+                    }
+                    let entity = EpisodeEntity {
+                        kind: entity.entity_type.as_str().to_string(),
+                        value: entity.normalized_value,
+                    };
+                    if !entities.contains(&entity) {
+                        entities.push(entity);
+                    }
+                }
+            }
+        }
+
+        let n = turns.len() as f32;
+        let feedback = turns.iter().map(|t| t.feedback_score).sum::<f32>() / n;
+        let confidence = turns.iter().map(|t| t.confidence).sum::<f32>() / n;
+        let importance = 0.6 * feedback + 0.2 * confidence + 0.2 * (n / 10.0).min(1.0);
+
+        let mut next_id = self.next_episode_id.write()
+            .map_err(|e| anyhow::anyhow!("Lock poisoned: {}", e))?;
+        let id = *next_id;
+        *next_id += 1;
+
+        Ok(Episode {
+            id,
+            summary,
+            embedding,
+            start: turns[0].timestamp,
+            end: turns[turns.len() - 1].timestamp,
+            entities,
+            importance: importance.clamp(0.0, 1.0),
+            turn_count: turns.len(),
+        })
+    }
+
+    /// Add episodes and drop the verbatim records they replace
+    fn store_episodes(&self, new: Vec<Episode>, consolidated: &[SessionRecord]) -> Result<()> {
+        let mut sessions = self.sessions.write()
+            .map_err(|e| anyhow::anyhow!("Lock poisoned: {}", e))?;
+        for turn in consolidated {
+            // Only if the intent has not come up again since
+            if sessions.get(&turn.intent).is_some_and(|r| r.timestamp <= turn.timestamp) {
+                sessions.remove(&turn.intent);
+            }
+        }
+        drop(sessions);
+        self.episodes.write()
+            .map_err(|e| anyhow::anyhow!("Lock poisoned: {}", e))?
+            .extend(new);
+        self.save()
+    }
+
+    /// Phase 7.7: Episodes relevant to `query`, best first
+    pub fn recall(&self, query: &str, limit: usize) -> Result<Vec<RecalledEpisode>> {
+        self.recall_at(query, limit, chrono::Utc::now().timestamp())
+    }
+
+    fn recall_at(&self, query: &str, limit: usize, now: i64) -> Result<Vec<RecalledEpisode>> {
+        let episodes = self.episodes.read()
+            .map_err(|e| anyhow::anyhow!("Lock poisoned: {}", e))?;
+        if episodes.is_empty() {
+            return Ok(Vec::new());
+        }
+        let query_embedding = self.embed(query);
+        let (w_recency, w_importance, w_similarity) = RECALL_WEIGHTS;
+
+        let mut recalled: Vec<RecalledEpisode> = episodes.iter()
+            .filter_map(|episode| {
+                let similarity = episode_similarity(query, &query_embedding, episode);
+                if similarity < MIN_RECALL_SIMILARITY {
+                    return None;
+                }
+                let age = (now - episode.end).max(0) as f32;
+                let recency = 0.5f32.powf(age / EPISODE_HALF_LIFE_SECS);
+                Some(RecalledEpisode {
+                    score: w_recency * recency + w_importance * episode.importance + w_similarity * similarity,
+                    similarity,
+                    episode: episode.clone(),
+                })
+            })
+            .collect();
+        recalled.sort_by(|a, b| b.score.total_cmp(&a.score));
+        recalled.truncate(limit);
+        Ok(recalled)
+    }
+
+    /// Phase 7.7: Forget everything about `topic`: matching recent turns are deleted,
+    /// and episode summaries and entities are rewritten without it (or deleted if
+    /// nothing else is left). The topic itself is not kept anywhere.
+    pub fn forget(&self, topic: &str) -> Result<ForgetReport> {
+        let topic = topic.trim();
+        if topic.is_empty() {
+            return Ok(ForgetReport::default());
+        }
+        let _guard = self.consolidation.lock()
+            .map_err(|e| anyhow::anyhow!("Lock poisoned: {}", e))?;
+        let mut report = ForgetReport::default();
+
+        {
+            let mut turns = self.turns.write()
+                .map_err(|e| anyhow::anyhow!("Lock poisoned: {}", e))?;
+            let before = turns.len();
+            turns.retain(|turn| !mentions(&turn.intent, topic) && !mentions(&turn.response, topic));
+            report.turns_removed = before - turns.len();
+        }
+        self.sessions.write()
+            .map_err(|e| anyhow::anyhow!("Lock poisoned: {}", e))?
+            .retain(|_, record| !mentions(&record.intent, topic) && !mentions(&record.response, topic));
+        {
+            let mut context = self.context.write()
+                .map_err(|e| anyhow::anyhow!("Lock poisoned: {}", e))?;
+            context.recent_intents.retain(|intent| !mentions(intent, topic));
+            context.common_patterns.retain(|intent, _| !mentions(intent, topic));
+        }
+
+        // Rewrite affected episodes outside the lock (the LLM is slow)
+        let affected: Vec<Episode> = self.episodes.read()
+            .map_err(|e| anyhow::anyhow!("Lock poisoned: {}", e))?
+            .iter()
+            .filter(|episode| {
+                mentions(&episode.summary, topic)
+                    || episode.entities.iter().any(|entity| mentions(&entity.value, topic))
+            })
+            .cloned()
+            .collect();
+
+        let mut rewritten = HashMap::new();
+        for mut episode in affected {
+            episode.entities.retain(|entity| !mentions(&entity.value, topic));
+            if mentions(&episode.summary, topic) {
+                episode.summary = self.redact(&episode.summary, topic);
+                episode.embedding = self.embed(&episode.summary);
+            }
+            rewritten.insert(episode.id, episode);
+        }
+
+        let mut episodes = self.episodes.write()
+            .map_err(|e| anyhow::anyhow!("Lock poisoned: {}", e))?;
+        episodes.retain_mut(|episode| match rewritten.remove(&episode.id) {
+            Some(new) if new.summary.is_empty() => {
+                report.episodes_removed += 1;
+                false
+            }
+            Some(new) => {
+                *episode = new;
+                report.episodes_rewritten += 1;
+                true
+            }
+            None => true,
+        });
+        drop(episodes);
+        self.save()?;
+
+        log::info!(
+            "[Memory] Forgot topic: {} turns removed, {} episodes rewritten, {} removed",
+            report.turns_removed, report.episodes_rewritten, report.episodes_removed
+        );
+        Ok(report)
+    }
+
+    /// Consolidated episodes, oldest first
+    pub fn episodes(&self) -> Result<Vec<Episode>> {
+        Ok(self.episodes.read()
+            .map_err(|e| anyhow::anyhow!("Lock poisoned: {}", e))?
+            .clone())
+    }
+
+    /// Write both tiers to the storage path (nothing to do if memory is not persisted)
+    pub fn save(&self) -> Result<()> {
+        let Some(path) = &self.storage_path else {
+            return Ok(());
+        };
+        let _guard = self.saving.lock()
+            .map_err(|e| anyhow::anyhow!("Lock poisoned: {}", e))?;
+        let snapshot = MemorySnapshot {
+            sessions: self.sessions.read()
+                .map_err(|e| anyhow::anyhow!("Lock poisoned: {}", e))?
+                .clone(),
+            context: self.context.read()
+                .map_err(|e| anyhow::anyhow!("Lock poisoned: {}", e))?
+                .clone(),
+            turns: self.turns.read()
+                .map_err(|e| anyhow::anyhow!("Lock poisoned: {}", e))?
+                .clone(),
+            episodes: self.episodes()?,
+            next_episode_id: *self.next_episode_id.read()
+                .map_err(|e| anyhow::anyhow!("Lock poisoned: {}", e))?,
+        };
+
+        // Replace the file in one step so a crash never leaves half of it
+        let staged = path.with_extension("json.tmp");
+        fs::write(&staged, serde_json::to_vec(&snapshot)?)?;
+        fs::rename(&staged, path)?;
+        Ok(())
+    }
+
+    fn load_from_disk(&self, path: &Path) -> Result<()> {
+        let snapshot: MemorySnapshot = serde_json::from_str(&fs::read_to_string(path)?)?;
+        log::info!("[Memory] Loading {} turns and {} episodes from {:?}",
+            snapshot.turns.len(), snapshot.episodes.len(), path);
+        *self.sessions.write().map_err(|e| anyhow::anyhow!("Lock poisoned: {}", e))? = snapshot.sessions;
+        *self.context.write().map_err(|e| anyhow::anyhow!("Lock poisoned: {}", e))? = snapshot.context;
+        *self.turns.write().map_err(|e| anyhow::anyhow!("Lock poisoned: {}", e))? = snapshot.turns;
+        *self.episodes.write().map_err(|e| anyhow::anyhow!("Lock poisoned: {}", e))? = snapshot.episodes;
+        *self.next_episode_id.write().map_err(|e| anyhow::anyhow!("Lock poisoned: {}", e))? = snapshot.next_episode_id;
+        Ok(())
+    }
+
+    /// Consolidate every `every` on a blocking thread
+    pub fn spawn_consolidation(self: Arc<Self>, every: std::time::Duration) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut timer = tokio::time::interval(every);
+            loop {
+                timer.tick().await;
+                let memory = self.clone();
+                match tokio::task::spawn_blocking(move || memory.consolidate()).await {
+                    Ok(Err(e)) => log::warn!("[Memory] {:#}", e),
+                    Err(e) => log::warn!("[Memory] Consolidation task failed: {}", e),
+                    Ok(Ok(_)) => {}
+                }
+            }
+        })
+    }
+
+    fn embed(&self, text: &str) -> Vec<f32> {
+        let Some(summarizer) = &self.summarizer else {
+            return Vec::new();
+        };
+        summarizer.embed(text).unwrap_or_else(|e| {
+            log::debug!("[Memory] Embedding unavailable: {}", e);
+            Vec::new()
+        })
+    }
+
+    /// `summary` without `topic`; the LLM's rewrite is only trusted if the topic is gone
+    fn redact(&self, summary: &str, topic: &str) -> String {
+        if let Some(summarizer) = &self.summarizer {
+            match summarizer.redact(summary, topic) {
+                Ok(rewritten) if !mentions(&rewritten, topic) => return rewritten.trim().to_string(),
+                Ok(_) => log::debug!("[Memory] Rewrite still mentions the topic, dropping sentences"),
+                Err(e) => log::debug!("[Memory] Rewrite failed: {}", e),
+            }
+        }
+        summary.split_inclusive(['.', '!', '?'])
+            .filter(|sentence| !mentions(sentence, topic))
+            .collect::<String>()
+            .trim()
+            .to_string()
+    }
+
     /// Get enriched context for reasoning
     pub fn get_enriched_context(&self, intent: &str) -> Result<Value> {
         let historical = self.retrieve_context(intent)?;
@@ -180,8 +681,13 @@ impl OracleMemory {
             0.0
         };
 
+        let episodes = self.episodes.read()
+            .map_err(|e| anyhow::anyhow!("Lock poisoned: {}", e))?
+            .len();
+
         Ok(MemoryAnalytics {
             total_sessions: total,
+            episodes,
             positive_feedback_count: positive_feedback,
             positive_feedback_rate: if total > 0 { positive_feedback as f32 / total as f32 } else { 0.0 },
             avg_confidence,
@@ -192,11 +698,62 @@ impl OracleMemory {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct MemoryAnalytics {
     pub total_sessions: usize,
+    pub episodes: usize,
     pub positive_feedback_count: usize,
     pub positive_feedback_rate: f32,
     pub avg_confidence: f32,
 }
 
+/// Fallback summary: the requests of the conversation, in order
+fn extractive_summary(turns: &[SessionRecord]) -> String {
+    let mut requests: Vec<&str> = Vec::new();
+    for turn in turns {
+        if !requests.contains(&turn.intent.as_str()) {
+            requests.push(&turn.intent);
+        }
+    }
+    requests.iter()
+        .map(|request| format!("Asked: {}.", request.trim_end_matches(['.', '?', '!'])))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Whether `text` mentions `topic` as whole words, ignoring case
+fn mentions(text: &str, topic: &str) -> bool {
+    let words = |s: &str| -> Vec<String> {
+        s.split(|c: char| !c.is_alphanumeric())
+            .filter(|w| !w.is_empty())
+            .map(str::to_lowercase)
+            .collect()
+    };
+    let (text, topic) = (words(text), words(topic));
+    !topic.is_empty() && text.windows(topic.len()).any(|window| window == topic.as_slice())
+}
+
+/// Cosine similarity of embeddings when both exist, otherwise keyword overlap
+fn episode_similarity(query: &str, query_embedding: &[f32], episode: &Episode) -> f32 {
+    if !query_embedding.is_empty() && query_embedding.len() == episode.embedding.len() {
+        let dot: f32 = query_embedding.iter().zip(&episode.embedding).map(|(a, b)| a * b).sum();
+        let norm = |v: &[f32]| v.iter().map(|x| x * x).sum::<f32>().sqrt();
+        return dot / (norm(query_embedding) * norm(&episode.embedding)).max(f32::EPSILON);
+    }
+
+    let keywords: Vec<String> = query.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| w.len() > 2)
+        .map(str::to_lowercase)
+        .collect();
+    if keywords.is_empty() {
+        return 0.0;
+    }
+    let found = keywords.iter()
+        .filter(|keyword| {
+            mentions(&episode.summary, keyword)
+                || episode.entities.iter().any(|entity| mentions(&entity.value, keyword))
+        })
+        .count();
+    found as f32 / keywords.len() as f32
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -236,4 +793,156 @@ mod tests {
         
         assert!(patterns.get("umbrella needed?").is_some());
     }
+
+    /// Summaries are the first request; embeddings are hashed bags of words
+    struct TestSummarizer;
+
+    impl MemorySummarizer for TestSummarizer {
+        fn summarize(&self, turns: &[SessionRecord]) -> Result<String> {
+            Ok(format!("User talked about {}.", turns[0].intent))
+        }
+
+        fn redact(&self, summary: &str, _topic: &str) -> Result<String> {
+            Ok(summary.to_string()) // Ignores the request: must not be trusted
+        }
+
+        fn embed(&self, text: &str) -> Result<Vec<f32>> {
+            let mut v = vec![0.0; 32];
+            for word in text.to_lowercase().split(|c: char| !c.is_alphanumeric()).filter(|w| w.len() > 2) {
+                v[word.bytes().map(|b| b as usize).sum::<usize>() % 32] += 1.0;
+            }
+            Ok(v)
+        }
+    }
+
+    /// Store `turns` as (request, response) pairs, `minutes` apart starting at `start`
+    fn store_at(memory: &OracleMemory, start: i64, minutes: i64, turns: &[(&str, &str)]) {
+        for (i, (intent, response)) in turns.iter().enumerate() {
+            memory.store_session(intent, response, 0.8, vec![]).unwrap();
+            memory.turns.write().unwrap().back_mut().unwrap().timestamp = start + i as i64 * minutes * 60;
+            memory.sessions.write().unwrap().get_mut(*intent).unwrap().timestamp = start + i as i64 * minutes * 60;
+        }
+    }
+
+    #[test]
+    fn test_consolidation_tiers() {
+        let memory = OracleMemory::new().with_summarizer(Arc::new(TestSummarizer));
+        let day = 24 * 3600;
+        let now = 100 * day;
+
+        // Two conversations a week ago (split by a long pause), then 25 turns in the last half hour
+        store_at(&memory, now - 7 * day, 2, &[
+            ("call Alice about the picnic tomorrow", "Calling Alice"),
+            ("what should I bring to the picnic", "Sandwiches and lemonade"),
+        ]);
+        store_at(&memory, now - 7 * day + 3 * 3600, 2, &[("navigate to the train station", "Starting navigation")]);
+        let recent: Vec<String> = (0..25).map(|i| format!("recent question {}", i)).collect();
+        let recent: Vec<(&str, &str)> = recent.iter().map(|q| (q.as_str(), "ok")).collect();
+        store_at(&memory, now - 30 * 60, 1, &recent);
+
+        assert_eq!(memory.consolidate_at(now).unwrap(), 2);
+        let episodes = memory.episodes().unwrap();
+        assert_eq!(episodes.iter().map(|e| e.turn_count).collect::<Vec<_>>(), vec![2, 1]);
+        assert!(episodes[0].summary.contains("picnic"));
+        assert!(episodes[0].entities.iter().any(|e| e.kind == "contact" && e.value.to_lowercase() == "alice"));
+        assert!(!episodes[0].embedding.is_empty());
+
+        assert!(!memory.sessions.read().unwrap().contains_key("call Alice about the picnic tomorrow"));
+
+        // Turns in the recent window stay verbatim; once it has passed, all but the newest 20 go
+        assert_eq!(memory.turns.read().unwrap().len(), 25);
+        assert_eq!(memory.consolidate_at(now + 7 * day).unwrap(), 1);
+        assert_eq!(memory.turns.read().unwrap().len(), RECENT_TURNS);
+        assert_eq!(memory.episodes().unwrap()[2].turn_count, 5);
+    }
+
+    #[test]
+    fn test_recall_ranking() {
+        let memory = OracleMemory::new();
+        let day = 24 * 3600;
+        let now = 100 * day;
+        store_at(&memory, now - 30 * day, 1, &[("book a table for the picnic", "Done")]);
+        store_at(&memory, now - 2 * day, 1, &[("weather for the picnic", "Sunny")]);
+        store_at(&memory, now - day, 1, &[("play some jazz", "Playing jazz")]);
+        memory.turns.write().unwrap().extend((0..RECENT_TURNS).map(|_| SessionRecord {
+            intent: "filler".into(), response: String::new(), confidence: 0.5,
+            feedback_score: 0.5, timestamp: now, tools_used: vec![],
+        }));
+        assert_eq!(memory.consolidate_at(now).unwrap(), 3);
+
+        let recalled = memory.recall_at("picnic plans", 5, now).unwrap();
+        assert_eq!(recalled.len(), 2); // Jazz is unrelated
+        assert!(recalled[0].episode.summary.contains("weather")); // More recent wins
+        assert!(recalled[0].score > recalled[1].score);
+
+        // Strong positive feedback outweighs recency
+        memory.episodes.write().unwrap()[0].importance = 1.0;
+        memory.episodes.write().unwrap()[1].importance = 0.0;
+        let recalled = memory.recall_at("picnic plans", 5, now).unwrap();
+        assert!(recalled[0].episode.summary.contains("book a table"));
+    }
+
+    #[test]
+    fn test_forget_topic() {
+        let memory = OracleMemory::new().with_summarizer(Arc::new(TestSummarizer));
+        let day = 24 * 3600;
+        let now = 100 * day;
+        store_at(&memory, now - 10 * day, 1, &[("remind me about the doctor appointment", "Reminder set")]);
+        store_at(&memory, now - 5 * day, 1, &[("call Bob", "Calling Bob")]);
+        store_at(&memory, now - 60, 0, &[("move my doctor appointment to Friday", "Moved")]);
+        memory.turns.write().unwrap().extend((0..RECENT_TURNS).map(|_| SessionRecord {
+            intent: "filler".into(), response: String::new(), confidence: 0.5,
+            feedback_score: 0.5, timestamp: now, tools_used: vec![],
+        }));
+        memory.consolidate_at(now).unwrap();
+        // Episode mentioning the topic among other things
+        memory.episodes.write().unwrap()[1].summary = "User called Bob. Bob asked about the Doctor appointment.".into();
+
+        let report = memory.forget("doctor").unwrap();
+        assert_eq!(report, ForgetReport { turns_removed: 1, episodes_rewritten: 1, episodes_removed: 1 });
+
+        let episodes = memory.episodes().unwrap();
+        assert_eq!(episodes.len(), 1);
+        assert_eq!(episodes[0].summary, "User called Bob.");
+        assert!(memory.recall_at("doctor appointment", 5, now).unwrap().is_empty());
+        assert!(memory.retrieve_context("move my doctor appointment to Friday").unwrap().is_empty());
+        assert!(!memory.get_enriched_context("x").unwrap().to_string().to_lowercase().contains("doctor"));
+    }
+
+    #[test]
+    fn test_reload_from_disk() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("memory").join("oracle.json");
+        let day = 24 * 3600;
+        let now = 100 * day;
+        {
+            let memory = OracleMemory::open(path.clone()).unwrap().with_summarizer(Arc::new(TestSummarizer));
+            store_at(&memory, now - 10 * day, 1, &[("remind me about the doctor appointment", "Reminder set")]);
+            store_at(&memory, now - 5 * day, 1, &[("call Bob about the picnic", "Calling Bob")]);
+            store_at(&memory, now - 60, 0, &[("what is the weather", "Sunny")]);
+            memory.turns.write().unwrap().extend((0..RECENT_TURNS).map(|_| SessionRecord {
+                intent: "filler".into(), response: String::new(), confidence: 0.5,
+                feedback_score: 0.5, timestamp: now, tools_used: vec![],
+            }));
+            assert_eq!(memory.consolidate_at(now).unwrap(), 2);
+            memory.forget("doctor").unwrap();
+        }
+
+        let memory = OracleMemory::open(path.clone()).unwrap().with_summarizer(Arc::new(TestSummarizer));
+        assert_eq!(memory.storage_path(), Some(path.as_path()));
+        let episodes = memory.episodes().unwrap();
+        assert_eq!(episodes.len(), 1);
+        assert_eq!(episodes[0].summary, "User talked about call Bob about the picnic.");
+        assert!(!episodes[0].embedding.is_empty());
+        assert_eq!(memory.turns.read().unwrap().len(), RECENT_TURNS + 1);
+        assert_eq!(memory.recall_at("call Bob about the picnic", 5, now).unwrap().len(), 1);
+
+        // Forgotten topics stay forgotten, and new episodes do not reuse ids
+        assert!(!std::fs::read_to_string(&path).unwrap().to_lowercase().contains("doctor"));
+        store_at(&memory, now - day, 1, &[("play some jazz", "Playing jazz")]);
+        memory.turns.write().unwrap().rotate_right(1);
+        assert_eq!(memory.consolidate_at(now).unwrap(), 1);
+        let ids: Vec<u64> = memory.episodes().unwrap().iter().map(|e| e.id).collect();
+        assert_eq!(ids, vec![episodes[0].id, 3]);
+    }
 }
@@ -7,6 +7,11 @@ pub mod adaptive_loader;  // Phase 5: Battery-aware model selection
 pub mod optimization;  // Phase 5: Inference optimization
 pub mod query_router;  // Phase 6: Intelligent query routing
 pub mod react_agent;  // Phase 6: ReAct reasoning + acting
+pub mod constrained;  // Phase 7.3: Grammar-constrained JSON decoding
+pub mod streaming;  // Phase 7.4: Streaming generation with cancellation
+pub mod prefix_cache;  // Phase 7.5: Prompt-prefix KV caching
+pub mod model_swap;  // Phase 7.6: Live model size swapping
+pub mod gen_model;  // Phase 7.6: Generative weights by architecture
 
 use anyhow::{Context, Result, anyhow};
 use candle_core::{Device, Tensor, DType, Module};
@@ -30,6 +35,11 @@ pub use adaptive_loader::{AdaptiveModelLoader, AdaptivePolicy, ModelSize, Resour
 pub use optimization::{InferenceProfiler, GenerationConfig, QuantizationLevel, InferenceBatcher};
 pub use query_router::{QueryRouter, QueryIntent, RouteDecision, ToolName};
 pub use react_agent::{ReActAgent, AgentResponse, AgentStep};
+pub use constrained::{ConstrainedDecoder, GrammarField, JsonGrammar, TokenVocabulary};
+pub use streaming::{CancellationToken, GenerationStream, StreamEvent, TokenTextStream};
+pub use prefix_cache::{PrefixCache, PrefixCacheStats, PrefixScope};
+pub use model_swap::ModelSwapper;
+pub use gen_model::GenModel;
 
 // Phase 55: Model optimization and intelligent scheduling
 pub mod distillation;
@@ -38,6 +48,10 @@ pub mod intent_scheduler;
 // TinyLlama 1.1B Chat (Quantized) - ~670MB
 const MODEL_REPO: &str = "TheBloke/TinyLlama-1.1B-Chat-v1.0-GGUF";
 const MODEL_FILE: &str = "tinyllama-1.1b-chat-v1.0.Q4_K_M.gguf";
+// KV memory per encoded token: K+V x 22 layers x 4 KV heads x 64 dims x f32
+const GEN_KV_BYTES_PER_TOKEN: usize = 2 * 22 * 4 * 64 * 4;
+// How often the KV cache budget follows free memory
+const KV_BUDGET_REFRESH_SECS: u64 = 30;
 
 /// Semantic Intent Templates for embedding-based matching
 /// Each template has a canonical phrase and associated action metadata
@@ -239,6 +253,19 @@ pub struct AIAction {
 }
 
 impl AIAction {
+    /// Phase 7.3: Grammar used to decode an action directly as JSON
+    pub fn grammar() -> JsonGrammar {
+        JsonGrammar::object(vec![
+            GrammarField::required(
+                "action",
+                JsonGrammar::string_enum(["set_config", "tune_storage", "execute_command"]),
+            ),
+            GrammarField::required("target", JsonGrammar::bounded_string(64)),
+            GrammarField::required("value", JsonGrammar::bounded_string(128)),
+            GrammarField::required("confidence", JsonGrammar::Number { integer: false }),
+        ])
+    }
+
     pub fn parse_from_text(text: &str) -> Option<Self> {
         // Try JSON first
         if let Ok(action) = serde_json::from_str::<AIAction>(text) {
@@ -294,8 +321,18 @@ pub struct KaranaAI {
     embed_model: Option<BertModel>,
     embed_tokenizer: Option<Tokenizer>,
     // Atom 3: Generative Engine (Large, load on demand)
-    gen_model: Option<QLlama>,
+    gen_model: Option<GenModel>,
     gen_tokenizer: Option<Tokenizer>,
+    // Phase 7.6: Size of the loaded generative model (None = default TinyLlama)
+    gen_model_size: Option<ModelSize>,
+    // Phase 7.3: Token texts for constrained decoding (built from gen_tokenizer on first use)
+    gen_vocabulary: Option<Arc<TokenVocabulary>>,
+    // Phase 7.5: Encoded prompt prefixes (model snapshot + last logits), reused across turns
+    prefix_cache: PrefixCache<(GenModel, Tensor)>,
+    prefix_budget_checked: Option<std::time::Instant>,
+    // Cached prefixes are only resumed by the model and session that encoded them
+    gen_model_id: u64,
+    cache_session: String,
     // Atom 3: Voice Engine (Whisper)
     whisper_model: Option<WhisperModel>,
     whisper_tokenizer: Option<Tokenizer>,
@@ -314,7 +351,7 @@ pub struct KaranaAI {
     profiler: Option<Arc<Mutex<optimization::InferenceProfiler>>>,
     // Phase 6: Agentic capabilities
     query_router: Option<QueryRouter>,
-    tool_registry: Option<Arc<agentic::ToolRegistry>>,
+    tool_registry: Option<Arc<crate::tools::ToolRegistry>>,
 }
 
 impl KaranaAI {
@@ -339,6 +376,12 @@ impl KaranaAI {
             embed_tokenizer,
             gen_model,
             gen_tokenizer,
+            gen_model_size: None,
+            gen_vocabulary: None,
+            prefix_cache: PrefixCache::new(GEN_KV_BYTES_PER_TOKEN, 0),
+            prefix_budget_checked: None,
+            gen_model_id: 0,
+            cache_session: String::new(),
             whisper_model: None,
             whisper_tokenizer: None,
             whisper_config: None,
@@ -353,7 +396,11 @@ impl KaranaAI {
             ))),
             profiler: Some(Arc::new(Mutex::new(optimization::InferenceProfiler::new()))),
             query_router: QueryRouter::new().ok(),
-            tool_registry: Some(Arc::new(agentic::ToolRegistry::new())),
+            tool_registry: Some(Arc::new({
+                let mut tools = crate::tools::ToolRegistry::new();
+                agentic::register_core_tools(&mut tools);
+                tools
+            })),
         };
 
         // Pre-compute intent template embeddings for semantic matching
@@ -686,7 +733,7 @@ impl KaranaAI {
         Ok((Some(model), Some(tokenizer)))
     }
 
-    fn load_gen_model(device: &Device) -> Result<(Option<QLlama>, Option<Tokenizer>)> {
+    fn load_gen_model(device: &Device) -> Result<(Option<GenModel>, Option<Tokenizer>)> {
         // Check local cache first
         let cache_dir = PathBuf::from("karana-cache/models");
         let model_path = cache_dir.join(MODEL_FILE);
@@ -702,12 +749,7 @@ impl KaranaAI {
         log::info!("Atom 3: Loading Generative Model from {:?}...", model_path);
         
         // Load GGUF
-        let mut file = std::fs::File::open(&model_path)?;
-        let model = candle_transformers::models::quantized_llama::ModelWeights::from_gguf(
-            candle_core::quantized::gguf_file::Content::read(&mut file)?, 
-            &mut file, // Reader
-            device
-        )?;
+        let model = GenModel::load(&model_path, device)?;
 
         // Load Tokenizer (Fetch from HF if needed, or assume it's cached)
         let api = Api::new()?;
@@ -718,6 +760,58 @@ impl KaranaAI {
         Ok((Some(model), Some(tokenizer)))
     }
 
+    /// Load a Llama GGUF file, e.g. one written by
+    /// `distillation::ModelOptimizer::quantize_model`
+    pub fn load_gguf_llama(path: &std::path::Path, device: &Device) -> Result<QLlama> {
+        let mut file = std::fs::File::open(path)
+            .with_context(|| format!("Cannot open model {:?}", path))?;
+        let content = candle_core::quantized::gguf_file::Content::read(&mut file)?;
+        Ok(QLlama::from_gguf(content, &mut file, device)?)
+    }
+
+    /// Phase 55: Swap the generative model for another GGUF (e.g. a freshly quantized one).
+    /// The tokenizer is kept, so the new model must share the current vocabulary.
+    pub fn load_gen_model_from(&mut self, path: &std::path::Path) -> Result<()> {
+        if self.gen_tokenizer.is_none() {
+            return Err(anyhow!("No generative tokenizer loaded to pair with {:?}", path));
+        }
+        let model = GenModel::load(path, &self.device)?;
+        log::info!("Atom 3: Generative model loaded from {:?}", path);
+        self.gen_model = Some(model);
+        self.gen_model_changed();
+        Ok(())
+    }
+
+    /// Phase 7.6: Replace the generative engine with a model of another size.
+    /// Returns the previous model and tokenizer so the caller can free them after
+    /// releasing the lock; cached prefixes only share the old weights, so dropping
+    /// them here is cheap.
+    pub fn install_gen_model(
+        &mut self,
+        size: ModelSize,
+        model: GenModel,
+        tokenizer: Tokenizer,
+        kv_bytes_per_token: usize,
+    ) -> Option<(GenModel, Tokenizer)> {
+        let old_model = self.gen_model.replace(model);
+        let old_tokenizer = self.gen_tokenizer.replace(tokenizer);
+        self.gen_model_size = Some(size);
+        self.prefix_cache = PrefixCache::new(kv_bytes_per_token, self.prefix_cache.stats().budget_bytes);
+        self.gen_model_changed();
+        log::info!("Atom 3: Generative engine now running the {:?} model", size);
+        old_model.zip(old_tokenizer)
+    }
+
+    /// Size of the loaded generative model, if one is loaded
+    pub fn gen_model_size(&self) -> Option<ModelSize> {
+        self.gen_model.as_ref().map(|_| self.gen_model_size.unwrap_or(ModelSize::Tiny))
+    }
+
+    /// Device models are loaded onto
+    pub fn device(&self) -> &Device {
+        &self.device
+    }
+
     pub fn download_model(&self) -> Result<String> {
         log::info!("Atom 3: Downloading {}...", MODEL_FILE);
         let api = Api::new()?;
@@ -756,6 +850,41 @@ impl KaranaAI {
     }
 
     pub fn predict(&mut self, prompt: &str, max_tokens: usize) -> Result<String> {
+        self.predict_streaming(prompt, max_tokens, &CancellationToken::new(), &mut |_| {})
+    }
+
+    /// Phase 7.4: `predict`, reporting text to `on_text` as it is decoded.
+    /// Stops after the current token once `cancel` fires and returns what was generated.
+    /// Fallback responses (no model loaded) arrive as a single chunk.
+    pub fn predict_streaming(
+        &mut self,
+        prompt: &str,
+        max_tokens: usize,
+        cancel: &CancellationToken,
+        on_text: &mut dyn FnMut(&str),
+    ) -> Result<String> {
+        if cancel.is_cancelled() {
+            return Ok(String::new());
+        }
+
+        let mut streamed = false;
+        let text = self.predict_inner(prompt, max_tokens, cancel, &mut |delta| {
+            streamed = true;
+            on_text(delta);
+        })?;
+        if !streamed && !text.is_empty() {
+            on_text(&text);
+        }
+        Ok(text)
+    }
+
+    fn predict_inner(
+        &mut self,
+        prompt: &str,
+        max_tokens: usize,
+        cancel: &CancellationToken,
+        on_text: &mut dyn FnMut(&str),
+    ) -> Result<String> {
         // Phase 5: Start timing and profiling
         let start_time = std::time::Instant::now();
         
@@ -793,6 +922,7 @@ impl KaranaAI {
                 Ok((model, tokenizer)) => {
                     self.gen_model = model;
                     self.gen_tokenizer = tokenizer;
+                    self.gen_model_changed();
                 }
                 Err(e) => {
                     log::warn!("[AI] Failed to load generative model: {}. Using enhanced semantic response.", e);
@@ -806,8 +936,9 @@ impl KaranaAI {
         }
         
         // Build contextual prompt
+        // The hint goes after the query so the prompt's prefix stays cacheable across turns
         let enhanced_prompt = if let Some((ref action, _confidence)) = intent_hint {
-            format!("User query (likely about '{}'): {}\n\nProvide a helpful, natural response:", action, prompt)
+            format!("User query: {}\n(Likely about '{}')\n\nProvide a helpful, natural response:", prompt, action)
         } else {
             format!("User query: {}\n\nProvide a helpful, natural response:", prompt)
         };
@@ -817,7 +948,7 @@ impl KaranaAI {
             profiler.lock().unwrap().start_operation("llm_generation");
         }
         
-        let result = match self.generate_with_llm(&enhanced_prompt, max_tokens) {
+        let result = match self.generate_with_llm(&enhanced_prompt, max_tokens, cancel, on_text) {
             Ok(response) => {
                 log::info!("[AI] ✓ LLM generated response ({} chars)", response.len());
                 Ok(response)
@@ -846,47 +977,245 @@ impl KaranaAI {
     }
     
     /// Core LLM generation function
-    fn generate_with_llm(&mut self, prompt: &str, max_tokens: usize) -> Result<String> {
-        let model = self.gen_model.as_mut()
-            .ok_or_else(|| anyhow!("Generative model not loaded"))?;
+    fn generate_with_llm(
+        &mut self,
+        prompt: &str,
+        max_tokens: usize,
+        cancel: &CancellationToken,
+        on_text: &mut dyn FnMut(&str),
+    ) -> Result<String> {
+        let mut logits_processor = LogitsProcessor::new(
+            299792458,  // seed
+            Some(0.8),  // temperature (0.8 for creative but coherent)
+            Some(0.95)  // top_p (nucleus sampling)
+        );
+        let decode = |tokenizer: &Tokenizer, tokens: &[u32]| {
+            tokenizer.decode(tokens, true).map_err(|e| anyhow!("Decoding error: {}", e))
+        };
+
+        // Phase 7.4: Stream text as it becomes stable
+        let mut text_stream = TokenTextStream::new();
+        let generated = self.sample_tokens(prompt, max_tokens, &mut logits_processor, None, &mut |tokenizer, token| {
+            if let Some(delta) = text_stream.push(token, |t| decode(tokenizer, t))? {
+                on_text(&delta);
+            }
+            Ok(!cancel.is_cancelled())
+        })?;
+
         let tokenizer = self.gen_tokenizer.as_ref()
             .ok_or_else(|| anyhow!("Tokenizer not loaded"))?;
+        if let Some(rest) = text_stream.finish(|t| decode(tokenizer, t))? {
+            on_text(&rest);
+        }
+        let response = decode(tokenizer, &generated)?;
         
-        // Tokenize input
-        let tokens = tokenizer.encode(prompt, true)
+        Ok(response.trim().to_string())
+    }
+
+    /// Phase 7.3: Token sampling loop shared by free and constrained generation.
+    /// The prompt is fed once (see `prefill`); after that only the newest token is
+    /// fed and the model's KV cache covers the rest. `on_token` sees every generated
+    /// token and returns false to stop early. Returns the generated tokens (without EOS).
+    fn sample_tokens(
+        &mut self,
+        prompt: &str,
+        max_tokens: usize,
+        logits_processor: &mut LogitsProcessor,
+        mut constraint: Option<&mut ConstrainedDecoder>,
+        on_token: &mut dyn FnMut(&Tokenizer, u32) -> Result<bool>,
+    ) -> Result<Vec<u32>> {
+        let prompt_tokens = self.gen_tokenizer.as_ref()
+            .ok_or_else(|| anyhow!("Tokenizer not loaded"))?
+            .encode(prompt, true)
             .map_err(|e| anyhow!("Tokenization error: {}", e))?
             .get_ids()
             .to_vec();
+        let mut logits = self.prefill(&prompt_tokens)?;
+
+        let model = self.gen_model.as_mut()
+            .ok_or_else(|| anyhow!("Generative model not loaded"))?;
+        let tokenizer = self.gen_tokenizer.as_ref()
+            .ok_or_else(|| anyhow!("Tokenizer not loaded"))?;
+        let stop_tokens = model.stop_tokens(tokenizer);
+        let mut index_pos = prompt_tokens.len();
+        let mut generated = Vec::new();
         
-        let mut generated = tokens.clone();
-        let mut logits_processor = LogitsProcessor::new(
-            299792458,  // seed
-            Some(0.8),  // temperature (0.8 for creative but coherent)
-            Some(0.95)  // top_p (nucleus sampling)
-        );
-        
-        // Generate tokens
-        for i in 0..max_tokens {
-            let input = Tensor::new(generated.as_slice(), &self.device)?
-                .unsqueeze(0)?;
-            let logits = model.forward(&input, generated.len())?;
-            let logits = logits.squeeze(0)?;
-            let next_logits = logits.get(logits.dim(0)? - 1)?;
-            let next_token = logits_processor.sample(&next_logits)?;
+        for _ in 0..max_tokens {
+            if let Some(&last) = generated.last() {
+                let x = Tensor::new(&[last], &self.device)?.unsqueeze(0)?;
+                logits = model.forward(&x, index_pos)?.squeeze(0)?;
+                index_pos += 1;
+            }
+
+            let next_token = match constraint.as_deref() {
+                Some(decoder) => logits_processor.sample(&decoder.mask_logits(&logits)?)?,
+                None => logits_processor.sample(&logits)?,
+            };
             
             // Check for end of sequence
-            if next_token == tokenizer.token_to_id("</s>").unwrap_or(2) {
+            if stop_tokens.contains(&next_token) {
                 break;
             }
-            
             generated.push(next_token);
+
+            if let Some(decoder) = constraint.as_deref_mut() {
+                decoder.accept(next_token)?;
+                if decoder.is_finished() {
+                    break;
+                }
+            }
+            if !on_token(tokenizer, next_token)? {
+                break;
+            }
         }
         
-        // Decode only the generated part (skip prompt)
-        let response = tokenizer.decode(&generated[tokens.len()..], true)
-            .map_err(|e| anyhow!("Decoding error: {}", e))?;
-        
-        Ok(response.trim().to_string())
+        Ok(generated)
+    }
+
+    /// Phase 7.5: Encode the prompt, resuming from the longest cached prefix.
+    /// Returns the logits of the last prompt position and leaves the model's KV
+    /// cache covering the whole prompt, which is then cached for later turns.
+    fn prefill(&mut self, tokens: &[u32]) -> Result<Tensor> {
+        self.refresh_prefix_budget();
+        let model = self.gen_model.as_mut()
+            .ok_or_else(|| anyhow!("Generative model not loaded"))?;
+        let forward = |model: &mut GenModel, tokens: &[u32], index_pos: usize| -> Result<Tensor> {
+            let x = Tensor::new(tokens, &self.device)?.unsqueeze(0)?;
+            Ok(model.forward(&x, index_pos)?.squeeze(0)?)
+        };
+        if !model.resumable() {
+            return forward(model, tokens, 0);
+        }
+        let scope = PrefixScope { model: self.gen_model_id, session: self.cache_session.clone() };
+
+        let (encoded, mut logits) = match self.prefix_cache.lookup(&scope, tokens) {
+            Some((len, (snapshot, logits))) => {
+                *model = snapshot;
+                (len, Some(logits))
+            }
+            None => {
+                // Checkpoint where this prompt leaves an earlier one (usually the end of
+                // the shared preamble and history) so the next turn can resume there
+                let shared = self.prefix_cache.shared_prefix_len(&scope, tokens);
+                if shared >= prefix_cache::MIN_PREFIX_TOKENS && shared < tokens.len() {
+                    let logits = forward(model, &tokens[..shared], 0)?;
+                    self.prefix_cache.insert(&scope, &tokens[..shared], (model.clone(), logits.clone()));
+                    (shared, Some(logits))
+                } else {
+                    (0, None)
+                }
+            }
+        };
+
+        if encoded == 0 {
+            logits = Some(forward(model, tokens, 0)?);
+        } else if encoded < tokens.len() {
+            // The quantized Llama only masks multi-token input correctly from position 0,
+            // so the rest of a resumed prompt goes in one token at a time
+            log::debug!("[AI] Reusing {}/{} cached prompt tokens", encoded, tokens.len());
+            for (i, token) in tokens[encoded..].iter().enumerate() {
+                logits = Some(forward(model, &[*token], encoded + i)?);
+            }
+        } else {
+            log::debug!("[AI] Prompt fully cached ({} tokens)", encoded);
+            return logits.ok_or_else(|| anyhow!("Empty prompt"));
+        }
+
+        let logits = logits.ok_or_else(|| anyhow!("Empty prompt"))?;
+        self.prefix_cache.insert(&scope, tokens, (model.clone(), logits.clone()));
+        Ok(logits)
+    }
+
+    /// Phase 7.5: Resize the KV cache budget to current free memory (rate limited,
+    /// reading system resources is not free)
+    fn refresh_prefix_budget(&mut self) {
+        if self.prefix_budget_checked
+            .is_some_and(|checked| checked.elapsed().as_secs() < KV_BUDGET_REFRESH_SECS)
+        {
+            return;
+        }
+        self.prefix_budget_checked = Some(std::time::Instant::now());
+        match adaptive_loader::ResourceStatus::current() {
+            Ok(status) => self.prefix_cache.set_budget(status.kv_cache_budget_bytes()),
+            Err(e) => log::warn!("[AI] Could not read resources for KV cache budget: {}", e),
+        }
+    }
+
+    /// Phase 7.5: Prompt-prefix cache statistics
+    pub fn prefix_cache_stats(&self) -> PrefixCacheStats {
+        self.prefix_cache.stats()
+    }
+
+    /// Load the generative model on demand
+    fn ensure_gen_model(&mut self) -> Result<()> {
+        if self.gen_model.is_none() {
+            let (model, tokenizer) = Self::load_gen_model(&self.device)?;
+            self.gen_model = model;
+            self.gen_tokenizer = tokenizer;
+            self.gen_model_changed();
+        }
+        Ok(())
+    }
+
+    /// Forget everything derived from the previous generative model
+    fn gen_model_changed(&mut self) {
+        self.gen_model_id += 1;
+        self.gen_vocabulary = None;
+        self.prefix_cache.clear();
+    }
+
+    /// Phase 7.5: Run `f` with prompt prefixes cached under `session`, so turns of one
+    /// conversation resume each other's KV caches without sharing them with others
+    pub fn in_cache_session<T>(&mut self, session: &str, f: impl FnOnce(&mut Self) -> T) -> T {
+        let previous = std::mem::replace(&mut self.cache_session, session.to_string());
+        let result = f(self);
+        self.cache_session = previous;
+        result
+    }
+
+    /// Plain completion of `prompt` by the generative model: no intent hints and no
+    /// semantic fallback, for internal tasks such as memory summarization
+    pub fn complete(&mut self, prompt: &str, max_tokens: usize) -> Result<String> {
+        self.ensure_gen_model()?;
+        self.generate_with_llm(prompt, max_tokens, &CancellationToken::new(), &mut |_| {})
+    }
+
+    /// Phase 7.3: Generate JSON that is guaranteed to match `grammar`.
+    /// Every sampling step is masked to the tokens the grammar allows, so the
+    /// result parses without scraping. Fails if no model is available or the
+    /// value is still incomplete after `max_tokens`.
+    pub fn predict_json(&mut self, prompt: &str, grammar: &Arc<JsonGrammar>, max_tokens: usize) -> Result<String> {
+        let start_time = std::time::Instant::now();
+        self.ensure_gen_model()?;
+
+        let vocabulary = match &self.gen_vocabulary {
+            Some(vocabulary) => vocabulary.clone(),
+            None => {
+                let tokenizer = self.gen_tokenizer.as_ref()
+                    .ok_or_else(|| anyhow!("Tokenizer not loaded"))?;
+                let vocabulary = Arc::new(TokenVocabulary::from_tokenizer(tokenizer));
+                self.gen_vocabulary = Some(vocabulary.clone());
+                vocabulary
+            }
+        };
+
+        let mut decoder = ConstrainedDecoder::new(vocabulary, grammar.clone());
+        // Greedy: the grammar already narrows the choice, sampling only adds noise
+        let mut logits_processor = LogitsProcessor::new(299792458, None, None);
+        self.sample_tokens(prompt, max_tokens, &mut logits_processor, Some(&mut decoder), &mut |_, _| Ok(true))?;
+
+        let duration_ms = start_time.elapsed().as_secs_f32() * 1000.0;
+        self.record_inference_time(duration_ms);
+
+        if !decoder.is_complete() {
+            return Err(anyhow!(
+                "Constrained output incomplete after {} tokens: {}",
+                max_tokens,
+                decoder.text()
+            ));
+        }
+        log::info!("[AI] ✓ Constrained JSON generated ({} chars)", decoder.text().len());
+        Ok(decoder.text().to_string())
     }
 
     /// Build a structured JSON response based on semantic match
@@ -1045,6 +1374,7 @@ impl KaranaAI {
              let (m, t) = Self::load_gen_model(&self.device).unwrap_or((None, None));
              self.gen_model = m;
              self.gen_tokenizer = t;
+             self.gen_model_changed();
         }
 
         if self.gen_model.is_none() {
@@ -1129,6 +1459,19 @@ impl KaranaAI {
             intent
         );
         
+        // Phase 7.3: Decode straight into the action schema when the model is available
+        match self.predict_json(&prompt, &Arc::new(AIAction::grammar()), 80) {
+            Ok(json) => match serde_json::from_str::<AIAction>(&json) {
+                Ok(mut action) => {
+                    action.confidence = action.confidence.clamp(0.0, 1.0);
+                    log::info!("[AI] ✓ Constrained action: {:?}", action);
+                    return Ok(action);
+                }
+                Err(e) => log::warn!("[AI] Constrained action did not deserialize: {}", e),
+            },
+            Err(e) => log::debug!("[AI] Constrained decoding unavailable: {}", e),
+        }
+        
         let response = self.predict(&prompt, 80)?;
         log::info!("[AI] Raw prediction: {}", response);
         
@@ -1193,7 +1536,11 @@ impl KaranaAI {
     pub fn set_adaptive_policy(&mut self, policy: adaptive_loader::AdaptivePolicy) {
         if let Some(loader) = &self.adaptive_loader {
             let mut loader = loader.lock().unwrap();
+            let loaded = loader.loaded_model();
             *loader = adaptive_loader::AdaptiveModelLoader::new(policy);
+            if let Some(size) = loaded {
+                loader.confirm_swap(size);
+            }
         }
     }
 
@@ -1204,6 +1551,11 @@ impl KaranaAI {
         }
     }
 
+    /// Shared adaptive loader, e.g. for `ModelSwapper` (Phase 7.6)
+    pub fn adaptive_loader(&self) -> Option<Arc<Mutex<adaptive_loader::AdaptiveModelLoader>>> {
+        self.adaptive_loader.clone()
+    }
+
     /// Record inference time for adaptive tuning (Phase 5)
     fn record_inference_time(&self, duration_ms: f32) {
         if let Some(loader) = &self.adaptive_loader {
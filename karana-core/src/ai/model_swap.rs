@@ -0,0 +1,453 @@
+// Phase 7.6: Live Model Size Swapping
+// Moves the generative engine between Tiny/Small/Medium models as battery, heat and
+// memory change, without restarting and without dropping requests.
+//
+// A swap loads the new GGUF on a blocking thread while the old model keeps serving,
+// then takes the `KaranaAI` lock. Every request holds that lock for its whole
+// generation, so acquiring it waits for in-flight requests to drain; requests that
+// arrive afterwards queue on the lock and run on the new model. The old weights are
+// freed after the lock is released. `AdaptiveModelLoader::evaluate_swap` supplies the
+// hysteresis so readings near a threshold do not make the engine flap.
+
+use anyhow::{Context, Result, anyhow};
+use candle_core::Device;
+use candle_core::quantized::gguf_file;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex as StdMutex};
+use std::time::{Duration, Instant};
+use tokenizers::{AddedToken, Tokenizer};
+use tokio::sync::broadcast::error::RecvError;
+
+use super::adaptive_loader::{AdaptiveModelLoader, AdaptivePolicy, ModelInfo, ModelSize, ResourceStatus};
+use super::gen_model::{self, GenModel};
+use super::KaranaAI;
+use crate::power::{ChargingState, PowerEvent, PowerManager};
+use crate::resource::{ResourceMonitor, ResourceSnapshot};
+
+/// After a failed load, wait this long before trying the same size again
+const RETRY_AFTER_FAILURE: Duration = Duration::from_secs(300);
+
+/// How often `spawn_power_events` asks the power manager for new events
+const POWER_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A model loaded off the serving path, ready to install
+struct LoadedModel {
+    model: GenModel,
+    tokenizer: Tokenizer,
+    kv_bytes_per_token: usize,
+}
+
+/// Swaps `KaranaAI`'s generative model when the adaptive loader picks another size
+pub struct ModelSwapper {
+    ai: Arc<StdMutex<KaranaAI>>,
+    loader: Arc<StdMutex<AdaptiveModelLoader>>,
+    resources: StdMutex<Option<ResourceStatus>>,
+    swapping: AtomicBool,
+    failed: StdMutex<Option<(ModelSize, Instant)>>,
+}
+
+impl ModelSwapper {
+    /// Watch resources on behalf of `ai`, starting from the model it has loaded
+    pub fn new(ai: Arc<StdMutex<KaranaAI>>) -> Result<Self> {
+        let (loader, loaded) = {
+            let ai = ai.lock().map_err(|_| anyhow!("AI engine lock poisoned"))?;
+            let loader = ai.adaptive_loader()
+                .ok_or_else(|| anyhow!("AI engine has no adaptive loader"))?;
+            (loader, ai.gen_model_size())
+        };
+        if let Some(size) = loaded {
+            loader.lock().unwrap().confirm_swap(size);
+        }
+
+        Ok(Self {
+            ai,
+            loader,
+            resources: StdMutex::new(None),
+            swapping: AtomicBool::new(false),
+            failed: StdMutex::new(None),
+        })
+    }
+
+    /// Feed a resource monitor snapshot; returns the size swapped to, if any
+    pub async fn on_snapshot(&self, snapshot: &ResourceSnapshot) -> Result<Option<ModelSize>> {
+        *self.resources.lock().unwrap() = Some(ResourceStatus::from(snapshot));
+        self.reconcile().await
+    }
+
+    /// Feed a power manager event; returns the size swapped to, if any
+    pub async fn on_power_event(&self, event: &PowerEvent) -> Result<Option<ModelSize>> {
+        let policy = self.loader.lock().unwrap().policy().clone();
+        {
+            let mut resources = self.resources.lock().unwrap();
+            if resources.is_none() {
+                *resources = Some(ResourceStatus::current()?);
+            }
+            if let Some(status) = resources.as_mut()
+                && !apply_power_event(status, event, &policy)
+            {
+                return Ok(None);
+            }
+        }
+        self.reconcile().await
+    }
+
+    /// Follow `monitor`'s snapshots until it stops
+    pub fn spawn_monitor(self: Arc<Self>, monitor: &ResourceMonitor) -> tokio::task::JoinHandle<()> {
+        let mut snapshots = monitor.subscribe();
+        tokio::spawn(async move {
+            loop {
+                match snapshots.recv().await {
+                    Ok(snapshot) => {
+                        if let Err(e) = self.on_snapshot(&snapshot).await {
+                            log::warn!("[ModelSwap] {}", e);
+                        }
+                    }
+                    // Only the latest readings matter
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        })
+    }
+
+    /// Poll `power` and feed its events in until the runtime shuts down.
+    /// `PowerManager::update` rate-limits itself, so polling often is cheap.
+    pub fn spawn_power_events(self: Arc<Self>, power: Arc<StdMutex<PowerManager>>) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticks = tokio::time::interval(POWER_POLL_INTERVAL);
+            loop {
+                ticks.tick().await;
+                let events = match power.lock() {
+                    Ok(mut power) => power.update(),
+                    Err(_) => break,
+                };
+                for event in &events {
+                    if let Err(e) = self.on_power_event(event).await {
+                        log::warn!("[ModelSwap] {}", e);
+                    }
+                }
+            }
+        })
+    }
+
+    /// Whether a swap is loading or waiting for requests to drain
+    pub fn is_swapping(&self) -> bool {
+        self.swapping.load(Ordering::SeqCst)
+    }
+
+    /// Swap if the loader's hysteresis says so. Readings that arrive while a swap is
+    /// in progress are only recorded; the next one re-evaluates.
+    async fn reconcile(&self) -> Result<Option<ModelSize>> {
+        let Some(status) = self.resources.lock().unwrap().clone() else {
+            return Ok(None);
+        };
+        if self.swapping.swap(true, Ordering::SeqCst) {
+            return Ok(None);
+        }
+
+        let (decision, loaded) = {
+            let mut loader = self.loader.lock().unwrap();
+            (loader.evaluate_swap(&status, Instant::now()), loader.loaded_model())
+        };
+        // A size with no shipped model falls back to the largest smaller one
+        let target = decision
+            .and_then(ModelInfo::shipped_up_to)
+            .filter(|info| Some(info.size) != loaded);
+        let result = match target {
+            Some(info) if !self.recently_failed(info.size) => self.swap_to(info).await.map(Some),
+            _ => Ok(None),
+        };
+        self.swapping.store(false, Ordering::SeqCst);
+
+        if let Err(e) = &result {
+            log::warn!("[ModelSwap] Keeping current model: {}", e);
+        }
+        result
+    }
+
+    async fn swap_to(&self, info: ModelInfo) -> Result<ModelSize> {
+        let size = info.size;
+        log::info!("[ModelSwap] Loading {:?} model from {} in the background", size, info.model_path);
+
+        let device = self.ai.lock().map_err(|_| anyhow!("AI engine lock poisoned"))?.device().clone();
+        let loaded = tokio::task::spawn_blocking(move || load_model(&info, &device)).await?;
+        let loaded = match loaded {
+            Ok(loaded) => loaded,
+            Err(e) => {
+                *self.failed.lock().unwrap() = Some((size, Instant::now()));
+                return Err(e);
+            }
+        };
+
+        // Taking the lock waits for in-flight generations; the old weights are
+        // dropped once it is released so waiting requests are not held up by it.
+        let ai = self.ai.clone();
+        let started = Instant::now();
+        tokio::task::spawn_blocking(move || -> Result<()> {
+            let old = ai.lock()
+                .map_err(|_| anyhow!("AI engine lock poisoned"))?
+                .install_gen_model(size, loaded.model, loaded.tokenizer, loaded.kv_bytes_per_token);
+            drop(old);
+            Ok(())
+        }).await??;
+
+        self.loader.lock().unwrap().confirm_swap(size);
+        *self.failed.lock().unwrap() = None;
+        log::info!("[ModelSwap] Switched to {:?} model ({:.0}ms drain)", size, started.elapsed().as_secs_f32() * 1000.0);
+        Ok(size)
+    }
+
+    fn recently_failed(&self, size: ModelSize) -> bool {
+        matches!(*self.failed.lock().unwrap(), Some((failed, at)) if failed == size && at.elapsed() < RETRY_AFTER_FAILURE)
+    }
+}
+
+/// Fold a power event into the tracked resources; false if it says nothing about them
+fn apply_power_event(status: &mut ResourceStatus, event: &PowerEvent, policy: &AdaptivePolicy) -> bool {
+    match event {
+        PowerEvent::BatteryLevelChanged(level) => status.battery_percent = *level,
+        PowerEvent::ChargingStateChanged(state) => {
+            status.is_charging = matches!(state, ChargingState::Charging | ChargingState::Full);
+        }
+        PowerEvent::LowBatteryWarning | PowerEvent::CriticalBatteryWarning => {
+            status.battery_percent = status.battery_percent.min(policy.tiny_threshold);
+        }
+        PowerEvent::ThermalWarning(temp) => status.temperature_celsius = *temp,
+        // Throttling carries no reading; treat it as just past the limit
+        PowerEvent::ThermalThrottle => {
+            status.temperature_celsius = status.temperature_celsius.max(policy.temp_threshold + 1.0);
+        }
+        PowerEvent::ProfileChanged(_) | PowerEvent::ComponentStateChanged(..) => return false,
+    }
+    true
+}
+
+/// Load the GGUF and tokenizer for a model size
+fn load_model(info: &ModelInfo, device: &Device) -> Result<LoadedModel> {
+    let path = Path::new(&info.model_path);
+    let mut file = std::fs::File::open(path)
+        .with_context(|| format!("Cannot open model {:?}", path))?;
+    let content = gguf_file::Content::read(&mut file)?;
+    let kv_bytes_per_token = gen_model::kv_bytes_per_token(&content)?;
+    let model = GenModel::from_gguf(content, &mut file, device)?;
+    let mut tokenizer = Tokenizer::from_file(&info.tokenizer_path)
+        .map_err(|e| anyhow!("Cannot load tokenizer {}: {}", info.tokenizer_path, e))?;
+    let added: Vec<AddedToken> = info.added_tokens.iter()
+        .map(|token| AddedToken::from(*token, true))
+        .collect();
+    tokenizer.add_special_tokens(&added);
+    Ok(LoadedModel { model, tokenizer, kv_bytes_per_token })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use candle_core::quantized::{GgmlDType, QTensor};
+    use candle_core::quantized::gguf_file::Value;
+    use candle_core::Tensor;
+    use tempfile::tempdir;
+
+    const VOCAB: usize = 32;
+    const EMBED: usize = 64;
+    const HEADS: usize = 4;
+    const KV_HEADS: usize = 2;
+    const FFN: usize = 128;
+
+    /// One-block GGUF of `arch` with the tensor layout its candle implementation reads
+    fn write_tiny_gguf(path: &Path, arch: &str) {
+        let head_dim = EMBED / HEADS;
+        let kv_dim = KV_HEADS * head_dim;
+        let mut shapes: Vec<(String, Vec<usize>)> = vec![
+            ("token_embd.weight".into(), vec![VOCAB, EMBED]),
+            ("output_norm.weight".into(), vec![EMBED]),
+            ("output.weight".into(), vec![VOCAB, EMBED]),
+            ("blk.0.attn_norm.weight".into(), vec![EMBED]),
+            ("blk.0.ffn_norm.weight".into(), vec![EMBED]),
+            ("blk.0.attn_output.weight".into(), vec![EMBED, EMBED]),
+            ("blk.0.ffn_down.weight".into(), vec![EMBED, FFN]),
+        ];
+        match arch {
+            "llama" => shapes.extend([
+                ("blk.0.attn_q.weight".into(), vec![EMBED, EMBED]),
+                ("blk.0.attn_k.weight".into(), vec![kv_dim, EMBED]),
+                ("blk.0.attn_v.weight".into(), vec![kv_dim, EMBED]),
+                ("blk.0.ffn_gate.weight".into(), vec![FFN, EMBED]),
+                ("blk.0.ffn_up.weight".into(), vec![FFN, EMBED]),
+            ]),
+            // Phi-3 fuses Q/K/V and gate/up
+            "phi3" => shapes.extend([
+                ("blk.0.attn_qkv.weight".into(), vec![EMBED + 2 * kv_dim, EMBED]),
+                ("blk.0.ffn_up.weight".into(), vec![2 * FFN, EMBED]),
+            ]),
+            other => panic!("no layout for {}", other),
+        }
+        let tensors: Vec<(String, QTensor)> = shapes.into_iter().map(|(name, shape)| {
+            let len = shape.iter().product::<usize>();
+            let values: Vec<f32> = if shape.len() == 1 {
+                vec![1.0; len]
+            } else {
+                (0..len).map(|i| (i as f32 * 0.37 + name.len() as f32).sin() * 0.1).collect()
+            };
+            let tensor = Tensor::from_vec(values, shape, &Device::Cpu).unwrap();
+            (name, QTensor::quantize(&tensor, GgmlDType::F32).unwrap())
+        }).collect();
+
+        let key = |k: &str| format!("{}.{}", arch, k);
+        let metadata = [
+            ("general.architecture".to_string(), Value::String(arch.into())),
+            (key("context_length"), Value::U32(32)),
+            (key("embedding_length"), Value::U32(EMBED as u32)),
+            (key("feed_forward_length"), Value::U32(FFN as u32)),
+            (key("block_count"), Value::U32(1)),
+            (key("attention.head_count"), Value::U32(HEADS as u32)),
+            (key("attention.head_count_kv"), Value::U32(KV_HEADS as u32)),
+            (key("attention.layer_norm_rms_epsilon"), Value::F32(1e-5)),
+            (key("rope.dimension_count"), Value::U32(head_dim as u32)),
+        ];
+        let metadata: Vec<(&str, &Value)> = metadata.iter().map(|(k, v)| (k.as_str(), v)).collect();
+        let tensors: Vec<(&str, &QTensor)> = tensors.iter().map(|(k, v)| (k.as_str(), v)).collect();
+        let mut file = std::fs::File::create(path).unwrap();
+        gguf_file::write(&mut file, &metadata, &tensors).unwrap();
+    }
+
+    fn write_tokenizer(path: &Path) {
+        let tokenizer = serde_json::json!({
+            "version": "1.0",
+            "truncation": null,
+            "padding": null,
+            "added_tokens": [],
+            "normalizer": null,
+            "pre_tokenizer": { "type": "WhitespaceSplit" },
+            "post_processor": null,
+            "decoder": null,
+            "model": {
+                "type": "WordLevel",
+                "vocab": { "<unk>": 0, "<s>": 1, "</s>": 2, "hello": 3, "world": 4 },
+                "unk_token": "<unk>"
+            }
+        });
+        std::fs::write(path, tokenizer.to_string()).unwrap();
+    }
+
+    #[test]
+    fn test_shipped_sizes_load_with_their_architecture() {
+        let dir = tempdir().unwrap();
+        let tokenizer_path = dir.path().join("tokenizer.json");
+        write_tokenizer(&tokenizer_path);
+
+        let shipped: Vec<ModelInfo> = [ModelSize::Tiny, ModelSize::Small, ModelSize::Medium]
+            .into_iter()
+            .filter_map(ModelInfo::for_size)
+            .collect();
+        assert!(!shipped.is_empty());
+        for mut info in shipped {
+            let arch = match info.size {
+                ModelSize::Tiny => "llama",
+                ModelSize::Small => "phi3",
+                ModelSize::Medium => "llama",
+            };
+            let model_path = dir.path().join(format!("{:?}.gguf", info.size));
+            write_tiny_gguf(&model_path, arch);
+            info.model_path = model_path.to_string_lossy().into_owned();
+            info.tokenizer_path = tokenizer_path.to_string_lossy().into_owned();
+
+            let mut loaded = load_model(&info, &Device::Cpu).unwrap();
+            assert_eq!(loaded.model.architecture(), arch);
+            assert_eq!(loaded.kv_bytes_per_token, 2 * KV_HEADS * (EMBED / HEADS) * 4);
+            for (i, token) in info.added_tokens.iter().enumerate() {
+                assert_eq!(loaded.tokenizer.token_to_id(token), Some(5 + i as u32));
+            }
+
+            // A second prompt starts from a clean KV cache
+            let prompt = Tensor::new(&[[1u32, 3, 4]], &Device::Cpu).unwrap();
+            let first = loaded.model.forward(&prompt, 0).unwrap();
+            let again = loaded.model.forward(&prompt, 0).unwrap();
+            assert_eq!(first.dims(), &[1, VOCAB]);
+            let diff = (first - again).unwrap().abs().unwrap().max_all().unwrap().to_scalar::<f32>().unwrap();
+            assert_eq!(diff, 0.0);
+        }
+    }
+
+    #[test]
+    fn test_resumed_prefill_matches_cold_prefill() {
+        let dir = tempdir().unwrap();
+        let model_path = dir.path().join("tiny.gguf");
+        let tokenizer_path = dir.path().join("tokenizer.json");
+        write_tiny_gguf(&model_path, "llama");
+        write_tokenizer(&tokenizer_path);
+        let tokenizer = Tokenizer::from_file(&tokenizer_path).unwrap();
+        let model = GenModel::load(&model_path, &Device::Cpu).unwrap();
+
+        let mut ai = KaranaAI::new().unwrap();
+        ai.install_gen_model(ModelSize::Tiny, model, tokenizer, 1);
+        ai.prefix_cache.set_budget(usize::MAX);
+        ai.prefix_budget_checked = Some(Instant::now());
+
+        let history: Vec<u32> = (0..24).map(|i| (i * 7 % VOCAB) as u32).collect();
+        let prompt: Vec<u32> = history.iter().copied().chain([3, 4, 1]).collect();
+        ai.prefill(&history).unwrap();
+        let warm = ai.prefill(&prompt).unwrap();
+        assert_eq!(ai.prefix_cache_stats().hits, 1);
+        assert_eq!(ai.prefix_cache_stats().tokens_reused, history.len() as u64);
+
+        // Another session encodes the whole prompt from scratch
+        let cold = ai.in_cache_session("other", |ai| ai.prefill(&prompt)).unwrap();
+        assert_eq!(ai.prefix_cache_stats().hits, 1);
+        let diff = (&warm - &cold).unwrap().abs().unwrap().max_all().unwrap().to_scalar::<f32>().unwrap();
+        assert!(diff < 1e-4, "resumed logits differ by {}", diff);
+        assert_eq!(
+            warm.argmax(0).unwrap().to_scalar::<u32>().unwrap(),
+            cold.argmax(0).unwrap().to_scalar::<u32>().unwrap()
+        );
+
+        // Prefixes encoded by the previous weights are not resumed after a reload
+        ai.load_gen_model_from(&model_path).unwrap();
+        ai.prefill(&prompt).unwrap();
+        assert_eq!(ai.prefix_cache_stats().hits, 1);
+    }
+
+    fn status() -> ResourceStatus {
+        ResourceStatus {
+            battery_percent: 80.0,
+            is_charging: false,
+            available_memory_mb: 4096,
+            cpu_usage_percent: 10.0,
+            temperature_celsius: 45.0,
+        }
+    }
+
+    #[test]
+    fn test_power_events_update_resources() {
+        let policy = AdaptivePolicy::default();
+        let mut status = status();
+
+        assert!(apply_power_event(&mut status, &PowerEvent::ThermalThrottle, &policy));
+        assert!(status.temperature_celsius > policy.temp_threshold);
+        assert!(apply_power_event(&mut status, &PowerEvent::ThermalWarning(62.0), &policy));
+        assert_eq!(status.temperature_celsius, 62.0);
+
+        assert!(apply_power_event(&mut status, &PowerEvent::CriticalBatteryWarning, &policy));
+        assert!(status.battery_percent <= policy.tiny_threshold);
+        assert!(apply_power_event(&mut status, &PowerEvent::ChargingStateChanged(ChargingState::Charging), &policy));
+        assert!(status.is_charging);
+
+        assert!(!apply_power_event(&mut status, &PowerEvent::ProfileChanged("eco".into()), &policy));
+    }
+
+    #[test]
+    fn test_thermal_events_drive_swaps() {
+        let policy = AdaptivePolicy::default();
+        let mut loader = AdaptiveModelLoader::new(policy.clone());
+        let mut status = status();
+        loader.confirm_swap(ModelSize::Medium);
+
+        apply_power_event(&mut status, &PowerEvent::ThermalThrottle, &policy);
+        assert_eq!(loader.evaluate_swap(&status, Instant::now()), Some(ModelSize::Small));
+        loader.confirm_swap(ModelSize::Small);
+
+        // Still warm: no swap back
+        apply_power_event(&mut status, &PowerEvent::ThermalWarning(policy.temp_threshold - 2.0), &policy);
+        assert_eq!(loader.evaluate_swap(&status, Instant::now()), None);
+    }
+}
@@ -0,0 +1,256 @@
+// Phase 7.5: Prompt-Prefix KV Caching
+// Keeps model states (KV caches) keyed by the prompt tokens they have already encoded,
+// so multi-turn prompts that share a system preamble and history skip re-encoding it.
+// Entries also belong to a `PrefixScope` (the model that encoded them and the session
+// they came from) and are only resumed within it.
+//
+// The cache is generic over the stored state; `KaranaAI` stores a snapshot of the
+// quantized Llama weights (cheap to clone: weights are shared, only the KV tensors
+// differ) together with the logits of the last encoded position.
+
+use lru::LruCache;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+/// Shorter prefixes are not worth a snapshot
+pub const MIN_PREFIX_TOKENS: usize = 16;
+
+/// Upper bound on cached prefixes (lookups scan all of them)
+const MAX_ENTRIES: usize = 32;
+
+/// Cache hit/miss counters
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PrefixCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub tokens_reused: u64,
+    pub evictions: u64,
+    pub entries: usize,
+    pub used_bytes: usize,
+    pub budget_bytes: usize,
+}
+
+/// Which model and conversation a cached prefix belongs to
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct PrefixScope {
+    /// Changes whenever different weights are loaded
+    pub model: u64,
+    /// Conversation the prompt came from (empty for prompts outside any session)
+    pub session: String,
+}
+
+struct PrefixEntry<S> {
+    scope: PrefixScope,
+    tokens: Vec<u32>,
+    state: S,
+}
+
+/// LRU cache of encoded prompt prefixes, bounded by a memory budget
+pub struct PrefixCache<S> {
+    entries: LruCache<u64, PrefixEntry<S>>,
+    bytes_per_token: usize,
+    budget_bytes: usize,
+    used_bytes: usize,
+    stats: PrefixCacheStats,
+}
+
+impl<S: Clone> PrefixCache<S> {
+    /// `bytes_per_token` is the KV memory one encoded token occupies
+    pub fn new(bytes_per_token: usize, budget_bytes: usize) -> Self {
+        Self {
+            entries: LruCache::unbounded(),
+            bytes_per_token,
+            budget_bytes,
+            used_bytes: 0,
+            stats: PrefixCacheStats::default(),
+        }
+    }
+
+    /// Longest prefix of `tokens` cached in `scope` and its state; marks it recently used
+    pub fn lookup(&mut self, scope: &PrefixScope, tokens: &[u32]) -> Option<(usize, S)> {
+        let best = self.entries.iter()
+            .filter(|(_, entry)| entry.scope == *scope && tokens.starts_with(&entry.tokens))
+            .max_by_key(|(_, entry)| entry.tokens.len())
+            .map(|(key, _)| *key);
+
+        match best.and_then(|key| self.entries.get(&key)) {
+            Some(entry) => {
+                self.stats.hits += 1;
+                self.stats.tokens_reused += entry.tokens.len() as u64;
+                Some((entry.tokens.len(), entry.state.clone()))
+            }
+            None => {
+                self.stats.misses += 1;
+                None
+            }
+        }
+    }
+
+    /// Longest prefix `tokens` shares with any entry in `scope`, cached or not.
+    /// Where a new prompt diverges from an old one is usually where the stable
+    /// part (preamble, history) ends, which makes it a good checkpoint.
+    pub fn shared_prefix_len(&self, scope: &PrefixScope, tokens: &[u32]) -> usize {
+        self.entries.iter()
+            .filter(|(_, entry)| entry.scope == *scope)
+            .map(|(_, entry)| {
+                entry.tokens.iter().zip(tokens).take_while(|(a, b)| a == b).count()
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Cache the state reached after encoding `tokens` in `scope`
+    pub fn insert(&mut self, scope: &PrefixScope, tokens: &[u32], state: S) {
+        let bytes = self.entry_bytes(tokens.len());
+        if tokens.len() < MIN_PREFIX_TOKENS || bytes > self.budget_bytes {
+            return;
+        }
+
+        let key = Self::key(scope, tokens);
+        if let Some(old) = self.entries.pop(&key) {
+            self.used_bytes -= self.entry_bytes(old.tokens.len());
+        }
+        self.used_bytes += bytes;
+        self.entries.put(key, PrefixEntry { scope: scope.clone(), tokens: tokens.to_vec(), state });
+        self.evict();
+    }
+
+    /// Change the memory budget, evicting least recently used prefixes to fit
+    pub fn set_budget(&mut self, budget_bytes: usize) {
+        self.budget_bytes = budget_bytes;
+        self.evict();
+    }
+
+    /// Drop every cached prefix (e.g. after the model changes)
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.used_bytes = 0;
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn used_bytes(&self) -> usize {
+        self.used_bytes
+    }
+
+    pub fn stats(&self) -> PrefixCacheStats {
+        PrefixCacheStats {
+            entries: self.entries.len(),
+            used_bytes: self.used_bytes,
+            budget_bytes: self.budget_bytes,
+            ..self.stats.clone()
+        }
+    }
+
+    fn evict(&mut self) {
+        while self.used_bytes > self.budget_bytes || self.entries.len() > MAX_ENTRIES {
+            let Some((_, entry)) = self.entries.pop_lru() else {
+                break;
+            };
+            self.used_bytes -= self.entry_bytes(entry.tokens.len());
+            self.stats.evictions += 1;
+            log::debug!("[PrefixCache] Evicted {}-token prefix", entry.tokens.len());
+        }
+    }
+
+    fn entry_bytes(&self, tokens: usize) -> usize {
+        tokens * self.bytes_per_token
+    }
+
+    fn key(scope: &PrefixScope, tokens: &[u32]) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        scope.hash(&mut hasher);
+        tokens.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prompt(shared: u32, own: &[u32]) -> Vec<u32> {
+        (0..shared).chain(own.iter().copied()).collect()
+    }
+
+    #[test]
+    fn test_longest_prefix_wins() {
+        let mut cache = PrefixCache::new(10, 10_000);
+        let scope = PrefixScope::default();
+        let preamble = prompt(20, &[]);
+        let turn_one = prompt(20, &[100, 101, 102]);
+
+        cache.insert(&scope, &preamble, "preamble");
+        cache.insert(&scope, &turn_one, "turn one");
+        cache.insert(&scope, &prompt(4, &[]), "too short");
+        assert_eq!(cache.len(), 2);
+
+        let turn_two = prompt(20, &[100, 101, 102, 200, 201]);
+        assert_eq!(cache.lookup(&scope, &turn_two), Some((23, "turn one")));
+
+        let other = prompt(20, &[300]);
+        assert_eq!(cache.lookup(&scope, &other), Some((20, "preamble")));
+        assert_eq!(cache.shared_prefix_len(&scope, &prompt(20, &[100, 101, 999])), 22);
+
+        assert_eq!(cache.lookup(&scope, &[9, 9, 9]), None);
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses, stats.tokens_reused), (2, 1, 43));
+    }
+
+    #[test]
+    fn test_lru_eviction_under_budget() {
+        // Room for two 20-token prefixes
+        let mut cache = PrefixCache::new(10, 450);
+        let scope = PrefixScope::default();
+        let a = prompt(20, &[1]);
+        let b = prompt(20, &[2]);
+        let c = prompt(20, &[3]);
+
+        cache.insert(&scope, &a[..20], 'a');
+        cache.insert(&scope, &b[..20], 'b'); // Same tokens as `a`: replaces it
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.used_bytes(), 200);
+
+        cache.insert(&scope, &a, 'a');
+        cache.insert(&scope, &b, 'b');
+        assert_eq!(cache.len(), 2); // Oldest (the shared 20 tokens) evicted
+        assert!(cache.lookup(&scope, &a).is_some()); // Refreshes `a`
+
+        cache.insert(&scope, &c, 'c');
+        assert!(cache.lookup(&scope, &b).is_none());
+        assert!(cache.lookup(&scope, &a).is_some());
+        assert!(cache.used_bytes() <= 450);
+
+        cache.set_budget(0);
+        assert!(cache.is_empty());
+        assert_eq!(cache.used_bytes(), 0);
+        assert_eq!(cache.stats().evictions, 4);
+    }
+
+    #[test]
+    fn test_entries_stay_in_their_scope() {
+        let mut cache = PrefixCache::new(10, 10_000);
+        let alice = PrefixScope { model: 1, session: "alice".into() };
+        let bob = PrefixScope { model: 1, session: "bob".into() };
+        let swapped = PrefixScope { model: 2, session: "alice".into() };
+        let history = prompt(20, &[100, 101]);
+
+        cache.insert(&alice, &history, "alice");
+        assert_eq!(cache.lookup(&bob, &prompt(20, &[100, 101, 102])), None);
+        assert_eq!(cache.lookup(&swapped, &prompt(20, &[100, 101, 102])), None);
+        assert_eq!(cache.shared_prefix_len(&bob, &history), 0);
+
+        // Same tokens in another scope are a separate entry
+        cache.insert(&bob, &history, "bob");
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.lookup(&alice, &history), Some((22, "alice")));
+        assert_eq!(cache.lookup(&bob, &history), Some((22, "bob")));
+    }
+}
@@ -7,8 +7,11 @@ use std::sync::Arc;
 use tokio::sync::Mutex;
 
 use super::KaranaAI;
+use super::constrained::{GrammarField, JsonGrammar};
 use super::reasoning::ReasoningContext;
-use super::agentic::{ToolRegistry, ToolOutput};
+use crate::tools::{ParamType, ToolRegistry, ToolSpec};
+
+pub use crate::tools::ToolCall;
 
 /// Maximum iterations to prevent infinite loops
 const MAX_ITERATIONS: usize = 5;
@@ -16,6 +19,28 @@ const MAX_ITERATIONS: usize = 5;
 /// Confidence threshold for early stopping
 const CONFIDENCE_THRESHOLD: f32 = 0.85;
 
+/// Longest thought the step grammar allows
+const MAX_THOUGHT_CHARS: usize = 400;
+
+/// Token budget for one constrained step
+const STEP_TOKENS: usize = 300;
+
+/// Grammar for one step: `{"thought": "...", "action": <tool call> | null}`
+fn step_grammar(specs: &[ToolSpec]) -> JsonGrammar {
+    JsonGrammar::object(vec![
+        GrammarField::required("thought", JsonGrammar::bounded_string(MAX_THOUGHT_CHARS)),
+        GrammarField::required("action", JsonGrammar::tool_call(specs).nullable()),
+    ])
+}
+
+/// One step as emitted by the model
+#[derive(Debug, Deserialize)]
+struct StepOutput {
+    thought: String,
+    #[serde(default)]
+    action: Option<ToolCall>,
+}
+
 /// ReAct agent step combining thought, action, and observation
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AgentStep {
@@ -26,13 +51,6 @@ pub struct AgentStep {
     pub confidence: f32,
 }
 
-/// Tool call specification
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ToolCall {
-    pub tool_name: String,
-    pub args: serde_json::Value,
-}
-
 /// Final agent response with reasoning chain
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AgentResponse {
@@ -47,6 +65,7 @@ pub struct AgentResponse {
 pub struct ReActAgent {
     llm: Arc<Mutex<KaranaAI>>,
     tools: Arc<ToolRegistry>,
+    step_grammar: Arc<JsonGrammar>,
     max_iterations: usize,
     confidence_threshold: f32,
 }
@@ -55,6 +74,7 @@ impl ReActAgent {
     pub fn new(llm: Arc<Mutex<KaranaAI>>, tools: Arc<ToolRegistry>) -> Self {
         Self {
             llm,
+            step_grammar: Arc::new(step_grammar(&tools.specs())),
             tools,
             max_iterations: MAX_ITERATIONS,
             confidence_threshold: CONFIDENCE_THRESHOLD,
@@ -75,7 +95,14 @@ impl ReActAgent {
             let prompt = self.build_prompt(query, context, &chain, &accumulated_observations);
             let llm_response = {
                 let mut llm = self.llm.lock().await;
-                llm.predict(&prompt, 200)?
+                // Constrained decoding always yields a valid step; free text is the fallback
+                match llm.predict_json(&prompt, &self.step_grammar, STEP_TOKENS) {
+                    Ok(json) => json,
+                    Err(e) => {
+                        log::debug!("[ReActAgent] Constrained step unavailable: {}", e);
+                        llm.predict(&prompt, 200)?
+                    }
+                }
             };
             
             // 2. Parse LLM response
@@ -87,7 +114,7 @@ impl ReActAgent {
             let observation = if let Some(ref tool_call) = action {
                 log::info!("[ReActAgent] Executing tool: {}", tool_call.tool_name);
                 
-                match self.tools.call_tool(&tool_call.tool_name, &tool_call.args).await {
+                match self.tools.call(tool_call).await.map(|r| r.output) {
                     Ok(result) => {
                         log::debug!("[ReActAgent] Tool result: {}", result);
                         accumulated_observations.push_str(&format!("\n{}: {}", tool_call.tool_name, result));
@@ -155,16 +182,15 @@ impl ReActAgent {
         
         // Available tools
         prompt.push_str("Available tools:\n");
-        for tool_name in self.tools.list_tools() {
-            prompt.push_str(&format!("- {} - Use for relevant queries\n", tool_name));
+        for spec in self.tools.specs() {
+            prompt.push_str(&format!("- {} - {}\n", spec.signature(), spec.description));
         }
         prompt.push_str("\n");
         
         // Format instructions
-        prompt.push_str("Response format:\n");
-        prompt.push_str("Thought: [your reasoning about what to do next]\n");
-        prompt.push_str("Action: [tool_name(args)] OR None if you can answer directly\n");
-        prompt.push_str("If Action is None, your Thought should be the final answer.\n\n");
+        prompt.push_str("Respond with one JSON object:\n");
+        prompt.push_str("{\"thought\": \"your reasoning about what to do next\", \"action\": {\"tool\": \"tool_name\", \"arguments\": {...}}}\n");
+        prompt.push_str("Use \"action\": null if you can answer directly; the thought is then the final answer.\n\n");
         
         // Context
         if !context.conversation_history.is_empty() {
@@ -182,12 +208,8 @@ impl ReActAgent {
         if !chain.is_empty() {
             prompt.push_str("Previous steps:\n");
             for step in chain {
-                prompt.push_str(&format!("Thought: {}\n", step.thought));
-                if let Some(ref action) = step.action {
-                    prompt.push_str(&format!("Action: {}\n", action.tool_name));
-                } else {
-                    prompt.push_str("Action: None\n");
-                }
+                let emitted = serde_json::json!({"thought": step.thought, "action": step.action});
+                prompt.push_str(&format!("{}\n", emitted));
                 if let Some(ref obs) = step.observation {
                     prompt.push_str(&format!("Observation: {}\n", obs));
                 }
@@ -203,13 +225,19 @@ impl ReActAgent {
         }
         
         // Next step
-        prompt.push_str("Thought:");
+        prompt.push_str("Next step:\n");
         
         prompt
     }
     
     /// Parse LLM response into thought and action
     fn parse_response(&self, response: &str) -> Result<(String, Option<ToolCall>)> {
+        // Constrained (and well-behaved free) output: {"thought": ..., "action": ...}
+        if let Ok(step) = serde_json::from_str::<StepOutput>(response.trim()) {
+            return Ok((step.thought, step.action));
+        }
+        
+        // Legacy line format: "Thought: ..." / "Action: ..."
         let lines: Vec<&str> = response.lines().collect();
         
         let mut thought = String::new();
@@ -251,44 +279,44 @@ impl ReActAgent {
     
     /// Parse tool call string
     fn parse_tool_call(&self, action_str: &str) -> Result<Option<ToolCall>> {
-        // Simple parsing: "tool_name(arg1, arg2)" or just "tool_name"
+        // Protocol format: {"tool": "name", "arguments": {...}}
+        if action_str.trim_start().starts_with('{') {
+            return ToolCall::from_json(action_str).map(Some);
+        }
         
+        // Legacy format: "tool_name(args)" or just "tool_name"
         if let Some(paren_pos) = action_str.find('(') {
             let tool_name = action_str[..paren_pos].trim().to_string();
-            
-            // Extract arguments (simple implementation)
             let args_str = action_str[paren_pos + 1..].trim_end_matches(')').trim();
             
-            // For now, treat all args as a single string parameter
             let args = if args_str.is_empty() {
                 serde_json::json!({})
+            } else if let Ok(json_args) = serde_json::from_str::<serde_json::Value>(args_str) {
+                json_args
             } else {
-                // Try to parse as JSON, fallback to simple string
-                if let Ok(json_args) = serde_json::from_str::<serde_json::Value>(args_str) {
-                    json_args
-                } else {
-                    // Treat as expression for calculator or location for weather
-                    if tool_name == "calculator" {
-                        serde_json::json!({ "expression": args_str })
-                    } else if tool_name == "weather" {
-                        serde_json::json!({ "location": args_str })
-                    } else {
-                        serde_json::json!({ "query": args_str })
-                    }
-                }
+                // Bare text fills the tool's primary parameter
+                let mut args = serde_json::Map::new();
+                args.insert(self.primary_parameter(&tool_name), serde_json::Value::String(args_str.to_string()));
+                serde_json::Value::Object(args)
             };
             
-            Ok(Some(ToolCall { tool_name, args }))
+            Ok(Some(ToolCall::new(tool_name, args)))
         } else {
             // Just tool name, no args
-            let tool_name = action_str.trim().to_string();
-            Ok(Some(ToolCall {
-                tool_name,
-                args: serde_json::json!({}),
-            }))
+            Ok(Some(ToolCall::new(action_str.trim(), serde_json::json!({}))))
         }
     }
     
+    /// First string parameter a tool declares (required ones preferred)
+    fn primary_parameter(&self, tool_name: &str) -> String {
+        self.tools.spec(tool_name)
+            .and_then(|spec| {
+                let strings = || spec.parameters.iter().filter(|p| p.param_type == ParamType::String);
+                strings().find(|p| p.required).or_else(|| strings().next()).map(|p| p.name.clone())
+            })
+            .unwrap_or_else(|| "query".to_string())
+    }
+    
     /// Calculate confidence based on thought and observation
     fn calculate_confidence(&self, thought: &str, observation: &Option<String>) -> f32 {
         let mut confidence: f32 = 0.5;
@@ -337,6 +365,28 @@ impl ReActAgent {
     }
 }
 
+#[cfg(test)]
+mod step_tests {
+    use super::*;
+    use crate::assistant::create_default_registry;
+
+    #[test]
+    fn test_step_grammar_accepts_only_registered_calls() {
+        let grammar = Arc::new(step_grammar(&create_default_registry().specs()));
+
+        let call = r#"{"thought": "Need the sum", "action": {"tool": "calculator", "arguments": {"expression": "15 + 25"}}}"#;
+        assert!(grammar.matches(call));
+        assert!(grammar.matches(r#"{"thought": "It is 40.", "action": null}"#));
+
+        assert!(!grammar.matches(r#"{"thought": "x", "action": {"tool": "rm_rf", "arguments": {}}}"#));
+        assert!(!grammar.matches(r#"{"thought": "x", "action": {"tool": "calculator", "arguments": {}}}"#));
+        assert!(!grammar.matches(r#"Thought: x\nAction: None"#));
+
+        let step: StepOutput = serde_json::from_str(call).unwrap();
+        assert_eq!(step.action.unwrap().tool_name, "calculator");
+    }
+}
+
 /* Tests disabled - need mock implementations
 #[cfg(test)]
 mod tests {
@@ -0,0 +1,228 @@
+// Phase 7.4: Streaming Generation
+// Delivers LLM output token by token so the HUD and TTS can start before generation ends.
+//
+// `KaranaAI::predict_streaming` is the synchronous callback form; `GenerationStream`
+// runs it on a blocking thread and exposes the result as an async `Stream` of events.
+
+use anyhow::{anyhow, Result};
+use futures::Stream;
+use serde::{Deserialize, Serialize};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex as StdMutex};
+use std::task::{Context, Poll};
+use tokio::sync::mpsc;
+
+use super::KaranaAI;
+
+/// Token budget for streamed answers when the client does not ask for one
+pub const DEFAULT_STREAM_TOKENS: usize = 200;
+
+/// Cooperative cancellation flag shared between a generation and its consumers
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ask the generation to stop after the current token
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+/// Event emitted while a response is generated
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum StreamEvent {
+    /// Newly decoded text, to be appended to what was shown so far
+    Token { delta: String },
+    /// Generation ended. `text` is the complete answer and supersedes the deltas
+    /// (fallback responses may differ from what was streamed)
+    Done { text: String, cancelled: bool },
+    /// Generation failed
+    Error { message: String },
+}
+
+/// Incremental detokenizer.
+///
+/// SentencePiece tokens cannot be decoded one at a time (leading spaces are dropped
+/// and multi-byte characters span tokens), so each step decodes a small window and
+/// emits only the text that grew.
+#[derive(Debug, Default)]
+pub struct TokenTextStream {
+    tokens: Vec<u32>,
+    prev_index: usize,
+    current_index: usize,
+}
+
+impl TokenTextStream {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a token; returns the text it completed, if any
+    pub fn push(&mut self, token: u32, decode: impl Fn(&[u32]) -> Result<String>) -> Result<Option<String>> {
+        let prev_text = decode(&self.tokens[self.prev_index..self.current_index])?;
+        self.tokens.push(token);
+        let text = decode(&self.tokens[self.prev_index..])?;
+
+        // U+FFFD means the window ends inside a multi-byte character
+        if text.len() <= prev_text.len() || text.ends_with('\u{FFFD}') {
+            return Ok(None);
+        }
+        let Some(delta) = text.get(prev_text.len()..) else {
+            return Ok(None);
+        };
+        let delta = delta.to_string();
+        self.prev_index = self.current_index;
+        self.current_index = self.tokens.len();
+        Ok(Some(delta))
+    }
+
+    /// Text still held back once generation has ended
+    pub fn finish(&mut self, decode: impl Fn(&[u32]) -> Result<String>) -> Result<Option<String>> {
+        let prev_text = decode(&self.tokens[self.prev_index..self.current_index])?;
+        let text = decode(&self.tokens[self.prev_index..])?;
+        self.prev_index = self.current_index;
+        self.current_index = self.tokens.len();
+        Ok(text.get(prev_text.len()..).filter(|rest| !rest.is_empty()).map(str::to_string))
+    }
+
+    /// All tokens seen so far
+    pub fn tokens(&self) -> &[u32] {
+        &self.tokens
+    }
+}
+
+/// Generation running on a blocking thread, observed as a stream of `StreamEvent`s.
+/// Ends after `Done` or `Error`; dropping it cancels the generation.
+pub struct GenerationStream {
+    rx: mpsc::UnboundedReceiver<StreamEvent>,
+    cancel: CancellationToken,
+}
+
+impl GenerationStream {
+    /// Start generating an answer to `prompt`
+    pub fn spawn(ai: Arc<StdMutex<KaranaAI>>, prompt: impl Into<String>, max_tokens: usize) -> Self {
+        Self::spawn_in_session(ai, "", prompt, max_tokens)
+    }
+
+    /// Like `spawn`, resuming only prompt prefixes cached for `session`
+    pub fn spawn_in_session(
+        ai: Arc<StdMutex<KaranaAI>>,
+        session: impl Into<String>,
+        prompt: impl Into<String>,
+        max_tokens: usize,
+    ) -> Self {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let cancel = CancellationToken::new();
+        let token = cancel.clone();
+        let session = session.into();
+        let prompt = prompt.into();
+
+        tokio::task::spawn_blocking(move || {
+            let result = match ai.lock() {
+                Ok(mut ai) => ai.in_cache_session(&session, |ai| {
+                    ai.predict_streaming(&prompt, max_tokens, &token, &mut |delta| {
+                        let _ = tx.send(StreamEvent::Token { delta: delta.to_string() });
+                    })
+                }),
+                Err(_) => Err(anyhow!("AI engine lock poisoned")),
+            };
+            let _ = tx.send(match result {
+                Ok(text) => StreamEvent::Done { text, cancelled: token.is_cancelled() },
+                Err(e) => StreamEvent::Error { message: e.to_string() },
+            });
+        });
+
+        Self { rx, cancel }
+    }
+
+    /// Stop the generation after the current token
+    pub fn cancel(&self) {
+        self.cancel.cancel();
+    }
+
+    /// Token that cancels this generation from elsewhere
+    pub fn cancellation_token(&self) -> CancellationToken {
+        self.cancel.clone()
+    }
+
+    /// Wait for the complete answer
+    pub async fn collect_text(mut self) -> Result<String> {
+        let mut text = String::new();
+        while let Some(event) = self.rx.recv().await {
+            match event {
+                StreamEvent::Token { delta } => text.push_str(&delta),
+                StreamEvent::Done { text, .. } => return Ok(text),
+                StreamEvent::Error { message } => return Err(anyhow!(message)),
+            }
+        }
+        Ok(text)
+    }
+}
+
+impl Stream for GenerationStream {
+    type Item = StreamEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<StreamEvent>> {
+        self.rx.poll_recv(cx)
+    }
+}
+
+impl Drop for GenerationStream {
+    fn drop(&mut self) {
+        self.cancel.cancel();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// SentencePiece-like decoding: `▁` marks a space, dropped at the start of the output
+    fn decode(tokens: &[u32]) -> Result<String> {
+        const PIECES: [&str; 5] = ["▁Hello", "▁world", ".", "▁How", "\u{FFFD}"];
+        let text: String = tokens.iter().map(|t| PIECES[*t as usize]).collect();
+        Ok(text.replace('▁', " ").trim_start().to_string())
+    }
+
+    #[test]
+    fn test_token_text_stream_keeps_spaces() {
+        let mut stream = TokenTextStream::new();
+        let deltas: Vec<Option<String>> = [0, 1, 2, 3]
+            .into_iter()
+            .map(|t| stream.push(t, decode).unwrap())
+            .collect();
+
+        assert_eq!(deltas, [Some("Hello".into()), Some(" world".into()), Some(".".into()), Some(" How".into())]);
+        assert_eq!(stream.tokens().len(), 4);
+
+        // Incomplete characters are held back until the stream ends
+        assert_eq!(stream.push(4, decode).unwrap(), None);
+        assert_eq!(stream.finish(decode).unwrap(), Some("\u{FFFD}".into()));
+        assert_eq!(stream.finish(decode).unwrap(), None);
+    }
+
+    #[test]
+    fn test_cancellation_token_is_shared() {
+        let token = CancellationToken::new();
+        let clone = token.clone();
+        assert!(!clone.is_cancelled());
+
+        token.cancel();
+        assert!(clone.is_cancelled());
+
+        let event = StreamEvent::Done { text: "Hi".into(), cancelled: true };
+        assert_eq!(serde_json::to_string(&event).unwrap(), r#"{"event":"done","text":"Hi","cancelled":true}"#);
+    }
+}
@@ -8,11 +8,16 @@ use axum::{
     http::StatusCode,
 };
 use base64::Engine;
+use futures::StreamExt;
+use std::collections::HashMap;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use crate::wallet::{KaranaWallet, get_device_id, SignedTransaction};
 use crate::api::state::AppState;
 use crate::api::types::*;
+use crate::ai::{CancellationToken, GenerationStream, StreamEvent};
+use crate::ai::streaming::DEFAULT_STREAM_TOKENS;
+use crate::assistant::TtsService;
 
 // ============================================================================
 // Wallet Handlers
@@ -647,6 +652,9 @@ async fn handle_ws(mut socket: WebSocket, state: Arc<AppState>) {
     // Create channel for this connection
     let (tx, mut rx) = tokio::sync::mpsc::channel::<String>(100);
     
+    // Phase 7.4: Streamed answers in flight on this connection
+    let mut generations: HashMap<String, CancellationToken> = HashMap::new();
+    
     // Simple ping-pong and subscribe handling
     loop {
         tokio::select! {
@@ -669,6 +677,28 @@ async fn handle_ws(mut socket: WebSocket, state: Arc<AppState>) {
                                 WsMessage::Ping => {
                                     let _ = tx.send(serde_json::to_string(&WsMessage::Pong).unwrap()).await;
                                 }
+                                WsMessage::Generate { request_id, prompt, max_tokens } => {
+                                    let max_tokens = max_tokens.unwrap_or(DEFAULT_STREAM_TOKENS);
+                                    if let Some(ref veil) = state.oracle_veil {
+                                        let stream = veil.lock().await.stream_answer(&prompt, max_tokens);
+                                        // Streams cancel their token when they finish, so this drops completed ones
+                                        generations.retain(|_, cancel| !cancel.is_cancelled());
+                                        generations.insert(request_id.clone(), stream.cancellation_token());
+                                        tokio::spawn(forward_generation(stream, request_id, tx.clone(), state.tts_service.clone()));
+                                    } else {
+                                        let error = WsMessage::OracleError {
+                                            request_id: Some(request_id),
+                                            error: "Streaming requires the Oracle AI engine".to_string(),
+                                            recoverable: false,
+                                        };
+                                        let _ = tx.send(serde_json::to_string(&error).unwrap()).await;
+                                    }
+                                }
+                                WsMessage::CancelGeneration { request_id } => {
+                                    if let Some(cancel) = generations.remove(&request_id) {
+                                        cancel.cancel();
+                                    }
+                                }
                                 _ => {}
                             }
                         }
@@ -680,9 +710,80 @@ async fn handle_ws(mut socket: WebSocket, state: Arc<AppState>) {
         }
     }
     
+    // Nobody is left to read these answers
+    for cancel in generations.values() {
+        cancel.cancel();
+    }
+    
     log::info!("[API] WebSocket connection closed");
 }
 
+/// Relay a streamed Oracle answer to one WebSocket connection, speaking it if TTS is available
+async fn forward_generation(
+    mut stream: GenerationStream,
+    request_id: String,
+    tx: tokio::sync::mpsc::Sender<String>,
+    tts: Option<Arc<TtsService>>,
+) {
+    // Speech runs on its own task so synthesis never holds back the text
+    let speech_tx = tts.map(|tts| {
+        let (speech_tx, speech_rx) = futures::channel::mpsc::unbounded();
+        tokio::spawn(speak_generation(tts, speech_rx, request_id.clone(), tx.clone()));
+        speech_tx
+    });
+    
+    while let Some(event) = stream.next().await {
+        if let Some(ref speech_tx) = speech_tx {
+            let _ = speech_tx.unbounded_send(event.clone());
+        }
+        let msg = match event {
+            StreamEvent::Token { delta } => WsMessage::OracleToken {
+                request_id: request_id.clone(),
+                delta,
+            },
+            StreamEvent::Done { text, cancelled } => WsMessage::OracleStreamEnd {
+                request_id: request_id.clone(),
+                text,
+                cancelled,
+            },
+            StreamEvent::Error { message } => WsMessage::OracleError {
+                request_id: Some(request_id.clone()),
+                error: message,
+                recoverable: true,
+            },
+        };
+        // Connection gone: dropping the stream cancels the generation
+        if tx.send(serde_json::to_string(&msg).unwrap()).await.is_err() {
+            break;
+        }
+    }
+}
+
+/// Speak a streamed Oracle answer sentence by sentence, sending the audio to the connection
+async fn speak_generation(
+    tts: Arc<TtsService>,
+    events: futures::channel::mpsc::UnboundedReceiver<StreamEvent>,
+    request_id: String,
+    tx: tokio::sync::mpsc::Sender<String>,
+) {
+    let sample_rate = tts.get_config().await.sample_rate;
+    let result = tts.speak_stream(events, |sentence, samples| {
+        let msg = WsMessage::OracleSpeech {
+            request_id: request_id.clone(),
+            text: sentence.to_string(),
+            samples,
+            sample_rate,
+        };
+        if tx.try_send(serde_json::to_string(&msg).unwrap()).is_err() {
+            log::warn!("[API] Dropped speech for '{}': connection busy or closed", sentence);
+        }
+    }).await;
+    
+    if let Err(e) = result {
+        log::warn!("[API] TTS failed for request {}: {}", request_id, e);
+    }
+}
+
 // ============================================================================
 // Confirmation Handlers
 // ============================================================================
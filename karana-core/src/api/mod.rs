@@ -24,6 +24,7 @@
 //!
 //! ### WebSocket
 //! - WS   /ws - Real-time updates for transactions, analysis, OS state
+//!   and streamed Oracle answers (`Generate` / `CancelGeneration`)
 
 pub mod server;
 pub mod routes;
@@ -7,7 +7,7 @@ use crate::wallet::KaranaWallet;
 use crate::api::types::{Transaction, OsMode, PendingAction, WhisperOverlay, ManifestState};
 use crate::oracle::command::{OracleCommand, CommandResult, CommandData, OracleChannels, MonadChannels};
 use crate::oracle::veil::OracleVeil;
-use crate::assistant::ToolRegistry;
+use crate::assistant::{create_default_registry, ToolRegistry, TtsService};
 
 /// Default expiration time for pending actions (60 seconds)
 const PENDING_ACTION_TTL_SECS: u64 = 60;
@@ -58,19 +58,16 @@ pub struct AppState {
     
     /// Tool Registry for executing Oracle intents
     pub tool_registry: Option<Arc<ToolRegistry>>,
+    
+    /// Speaks streamed Oracle answers (None for standalone API mode)
+    pub tts_service: Option<Arc<TtsService>>,
 }
 
 impl AppState {
     /// Create standalone API state (without OracleVeil)
     pub fn new() -> Arc<Self> {
         // Initialize default tool registry
-        let tool_registry = match ToolRegistry::new() {
-            Ok(registry) => Some(Arc::new(registry)),
-            Err(e) => {
-                log::warn!("[AppState] Failed to initialize ToolRegistry: {}", e);
-                None
-            }
-        };
+        let tool_registry = Some(Arc::new(create_default_registry()));
         
         Arc::new(Self {
             wallet: RwLock::new(None),
@@ -85,19 +82,14 @@ impl AppState {
             last_haptic: RwLock::new(None),
             oracle_veil: None,
             tool_registry,
+            tts_service: None,
         })
     }
     
     /// Create API state with OracleVeil (for full Monad integration)
     pub fn with_oracle_veil(veil: OracleVeil) -> Arc<Self> {
         // Initialize default tool registry
-        let tool_registry = match ToolRegistry::new() {
-            Ok(registry) => Some(Arc::new(registry)),
-            Err(e) => {
-                log::warn!("[AppState] Failed to initialize ToolRegistry: {}", e);
-                None
-            }
-        };
+        let tool_registry = Some(Arc::new(create_default_registry()));
         
         Arc::new(Self {
             wallet: RwLock::new(None),
@@ -112,6 +104,7 @@ impl AppState {
             last_haptic: RwLock::new(None),
             oracle_veil: Some(Arc::new(Mutex::new(veil))),
             tool_registry,
+            tts_service: Some(Arc::new(TtsService::new())),
         })
     }
     
@@ -399,6 +392,7 @@ impl Default for AppState {
             whispers: RwLock::new(Vec::new()),
             last_haptic: RwLock::new(None),
             oracle_veil: None,
+            tts_service: None,
         }
     }
 }
@@ -224,6 +224,27 @@ pub enum WsMessage {
         intent: OracleIntentResponse,
     },
     
+    /// Streamed Oracle answer: text to append to what is shown so far
+    OracleToken {
+        request_id: String,
+        delta: String,
+    },
+    
+    /// Streamed Oracle answer finished; `text` is the complete answer
+    OracleStreamEnd {
+        request_id: String,
+        text: String,
+        cancelled: bool,
+    },
+    
+    /// Spoken sentence of a streamed Oracle answer (mono PCM samples)
+    OracleSpeech {
+        request_id: String,
+        text: String,
+        samples: Vec<f32>,
+        sample_rate: u32,
+    },
+    
     /// Oracle error
     OracleError {
         request_id: Option<String>,
@@ -253,6 +274,17 @@ pub enum WsMessage {
     Unsubscribe {
         channel: String,
     },
+    /// Stream an answer to `prompt` back to this connection
+    Generate {
+        request_id: String,
+        prompt: String,
+        #[serde(default)]
+        max_tokens: Option<usize>,
+    },
+    /// Stop a streamed answer early
+    CancelGeneration {
+        request_id: String,
+    },
     Ping,
     Pong,
 }
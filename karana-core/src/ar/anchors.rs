@@ -1,7 +1,11 @@
 // AR Spatial Anchors for Kāraṇa OS
 // Handles world-locked content anchoring and persistence
+//
+// Anchors are owned by the spatial world-anchor service; the renderer keeps
+// only its own bookkeeping (anchor type, attached content) on top of it.
 
 use super::*;
+use crate::spatial::{self, AnchorId, CreateAnchorRequest, WorldAnchorService, WorldPosition};
 use std::collections::HashMap;
 use std::time::{Duration, Instant};
 
@@ -20,6 +24,30 @@ pub enum AnchorState {
     Paused,
 }
 
+impl From<spatial::AnchorState> for AnchorState {
+    fn from(state: spatial::AnchorState) -> Self {
+        match state {
+            spatial::AnchorState::Pending => AnchorState::Pending,
+            spatial::AnchorState::Active => AnchorState::Tracking,
+            spatial::AnchorState::Degraded => AnchorState::Limited,
+            spatial::AnchorState::Lost => AnchorState::Lost,
+            spatial::AnchorState::Archived => AnchorState::Paused,
+        }
+    }
+}
+
+impl From<AnchorState> for spatial::AnchorState {
+    fn from(state: AnchorState) -> Self {
+        match state {
+            AnchorState::Pending => spatial::AnchorState::Pending,
+            AnchorState::Tracking => spatial::AnchorState::Active,
+            AnchorState::Limited => spatial::AnchorState::Degraded,
+            AnchorState::Lost => spatial::AnchorState::Lost,
+            AnchorState::Paused => spatial::AnchorState::Archived,
+        }
+    }
+}
+
 /// Type of spatial anchor
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum AnchorType {
@@ -38,9 +66,11 @@ pub enum AnchorType {
 }
 
 /// A spatial anchor for world-locked content
+///
+/// `id` is the world-anchor ID, or 0 until the anchor is added to a manager.
 #[derive(Debug, Clone)]
 pub struct SpatialAnchor {
-    pub id: ContentId,
+    pub id: AnchorId,
     pub name: String,
     pub anchor_type: AnchorType,
     pub transform: Transform,
@@ -56,7 +86,7 @@ impl SpatialAnchor {
     pub fn new(name: &str, anchor_type: AnchorType, position: Point3<f32>) -> Self {
         let now = Instant::now();
         Self {
-            id: next_content_id(),
+            id: 0,
             name: name.to_string(),
             anchor_type,
             transform: Transform {
@@ -106,14 +136,7 @@ impl SpatialAnchor {
         self.transform = new_transform;
         self.confidence = confidence.clamp(0.0, 1.0);
         self.last_updated = Instant::now();
-        
-        self.state = if confidence > 0.8 {
-            AnchorState::Tracking
-        } else if confidence > 0.3 {
-            AnchorState::Limited
-        } else {
-            AnchorState::Lost
-        };
+        self.state = spatial::AnchorState::from_confidence(confidence).into();
     }
     
     /// Check if anchor is usable
@@ -133,22 +156,58 @@ impl SpatialAnchor {
 }
 
 /// Anchor manager for handling multiple anchors
+///
+/// Every anchor in the world-anchor service is visible here, whichever API
+/// created it; anchors added through this manager also carry renderer
+/// metadata (anchor type, cloud ID).
 pub struct AnchorManager {
-    anchors: HashMap<ContentId, SpatialAnchor>,
-    attached_content: HashMap<ContentId, Vec<ContentId>>,
+    service: WorldAnchorService,
+    anchors: HashMap<AnchorId, AnchorMeta>,
+    attached_content: HashMap<AnchorId, Vec<ContentId>>,
     max_anchors: usize,
     auto_cleanup: bool,
     stale_threshold: Duration,
+    persist_interval: Duration,
+    last_persist: Instant,
+}
+
+/// Renderer-side bookkeeping for an anchor
+#[derive(Debug, Clone)]
+struct AnchorMeta {
+    anchor_type: AnchorType,
+    created_at: Instant,
+    last_updated: Instant,
+    cloud_id: Option<String>,
+}
+
+impl AnchorMeta {
+    /// Metadata for an anchor created outside the renderer
+    fn foreign(world: &spatial::SpatialAnchor) -> Self {
+        Self {
+            anchor_type: AnchorType::Point,
+            created_at: world.created_instant(),
+            last_updated: world.updated_instant(),
+            cloud_id: None,
+        }
+    }
 }
 
 impl AnchorManager {
     pub fn new() -> Self {
+        Self::with_service(WorldAnchorService::in_memory())
+    }
+    
+    /// Create a manager on top of a shared anchor service
+    pub fn with_service(service: WorldAnchorService) -> Self {
         Self {
+            service,
             anchors: HashMap::new(),
             attached_content: HashMap::new(),
             max_anchors: 100,
             auto_cleanup: true,
             stale_threshold: Duration::from_secs(60),
+            persist_interval: Duration::from_secs(5),
+            last_persist: Instant::now(),
         }
     }
     
@@ -157,8 +216,13 @@ impl AnchorManager {
         self.max_anchors = max;
     }
     
-    /// Add a new anchor
-    pub fn add_anchor(&mut self, anchor: SpatialAnchor) -> Option<ContentId> {
+    /// Set how often tracked poses of persistent anchors are written to disk
+    pub fn set_persist_interval(&mut self, interval: Duration) {
+        self.persist_interval = interval;
+    }
+    
+    /// Add a new anchor, registering it with the world-anchor service
+    pub fn add_anchor(&mut self, anchor: SpatialAnchor) -> Option<AnchorId> {
         if self.anchors.len() >= self.max_anchors {
             if self.auto_cleanup {
                 self.cleanup_stale();
@@ -168,71 +232,119 @@ impl AnchorManager {
             }
         }
         
-        let id = anchor.id;
-        self.anchors.insert(id, anchor);
-        self.attached_content.insert(id, Vec::new());
-        Some(id)
+        let position = anchor.transform.position;
+        let request = CreateAnchorRequest {
+            position: WorldPosition::from_local(position.x, position.y, position.z),
+            orientation: Some(spatial::Quaternion::from_unit(&anchor.transform.rotation)),
+            label: Some(anchor.name),
+            state: Some(anchor.state.into()),
+            confidence: Some(anchor.confidence),
+            ..Default::default()
+        };
+        let world = match self.service.create(request, anchor.persistent) {
+            Ok(world) => world,
+            Err(e) => {
+                log::warn!("[AR] Failed to register anchor: {}", e);
+                return None;
+            }
+        };
+        
+        self.anchors.insert(world.id, AnchorMeta {
+            anchor_type: anchor.anchor_type,
+            created_at: anchor.created_at,
+            last_updated: anchor.last_updated,
+            cloud_id: anchor.cloud_id,
+        });
+        self.attached_content.insert(world.id, Vec::new());
+        Some(world.id)
     }
     
     /// Remove an anchor
-    pub fn remove_anchor(&mut self, anchor_id: ContentId) -> Option<SpatialAnchor> {
+    pub fn remove_anchor(&mut self, anchor_id: AnchorId) -> Option<SpatialAnchor> {
+        let anchor = self.get_anchor(anchor_id)?;
         self.attached_content.remove(&anchor_id);
-        self.anchors.remove(&anchor_id)
+        self.anchors.remove(&anchor_id);
+        let _ = self.service.remove(anchor_id);
+        Some(anchor)
     }
     
     /// Get anchor by ID
-    pub fn get_anchor(&self, anchor_id: ContentId) -> Option<&SpatialAnchor> {
-        self.anchors.get(&anchor_id)
+    pub fn get_anchor(&self, anchor_id: AnchorId) -> Option<SpatialAnchor> {
+        self.service.get(anchor_id).map(|world| self.view(&world))
+    }
+    
+    /// Feed a new tracked pose and confidence for an anchor
+    ///
+    /// Poses are kept in memory; persistent anchors are written to disk at
+    /// most once per persist interval.
+    pub fn update_tracking(&mut self, anchor_id: AnchorId, transform: Transform, confidence: f32) -> bool {
+        let position = transform.position;
+        let local = spatial::LocalCoord::new(position.x, position.y, position.z);
+        let orientation = spatial::Quaternion::from_unit(&transform.rotation);
+        if self.service.track_pose(anchor_id, local, orientation).is_err() {
+            return false;
+        }
+        self.service.update_confidence(anchor_id, confidence);
+        if let Some(meta) = self.anchors.get_mut(&anchor_id) {
+            meta.last_updated = Instant::now();
+        }
+        
+        if self.last_persist.elapsed() >= self.persist_interval {
+            self.flush();
+        }
+        true
     }
     
-    /// Get mutable anchor by ID
-    pub fn get_anchor_mut(&mut self, anchor_id: ContentId) -> Option<&mut SpatialAnchor> {
-        self.anchors.get_mut(&anchor_id)
+    /// Write tracked poses of persistent anchors to disk now
+    pub fn flush(&mut self) {
+        if let Err(e) = self.service.flush() {
+            log::warn!("[AR] Failed to persist anchor poses: {}", e);
+        }
+        self.last_persist = Instant::now();
     }
     
     /// Get all anchors
-    pub fn all_anchors(&self) -> impl Iterator<Item = &SpatialAnchor> {
-        self.anchors.values()
+    pub fn all_anchors(&self) -> Vec<SpatialAnchor> {
+        self.service.all().iter().map(|world| self.view(world)).collect()
     }
     
     /// Get tracking anchors
-    pub fn tracking_anchors(&self) -> impl Iterator<Item = &SpatialAnchor> {
-        self.anchors.values().filter(|a| a.is_tracking())
+    pub fn tracking_anchors(&self) -> Vec<SpatialAnchor> {
+        self.all_anchors().into_iter().filter(|a| a.is_tracking()).collect()
     }
     
     /// Attach content to an anchor
-    pub fn attach_content(&mut self, anchor_id: ContentId, content_id: ContentId) -> bool {
-        if let Some(content_list) = self.attached_content.get_mut(&anchor_id) {
-            if !content_list.contains(&content_id) {
-                content_list.push(content_id);
-            }
-            true
-        } else {
-            false
+    pub fn attach_content(&mut self, anchor_id: AnchorId, content_id: ContentId) -> bool {
+        if self.service.get(anchor_id).is_none() {
+            return false;
+        }
+        let content_list = self.attached_content.entry(anchor_id).or_default();
+        if !content_list.contains(&content_id) {
+            content_list.push(content_id);
         }
+        true
     }
     
     /// Detach content from an anchor
-    pub fn detach_content(&mut self, anchor_id: ContentId, content_id: ContentId) {
+    pub fn detach_content(&mut self, anchor_id: AnchorId, content_id: ContentId) {
         if let Some(content_list) = self.attached_content.get_mut(&anchor_id) {
             content_list.retain(|&id| id != content_id);
         }
     }
     
     /// Get content attached to an anchor
-    pub fn get_attached_content(&self, anchor_id: ContentId) -> Option<&Vec<ContentId>> {
+    pub fn get_attached_content(&self, anchor_id: AnchorId) -> Option<&Vec<ContentId>> {
         self.attached_content.get(&anchor_id)
     }
     
     /// Find anchor by name
-    pub fn find_by_name(&self, name: &str) -> Option<&SpatialAnchor> {
-        self.anchors.values().find(|a| a.name == name)
+    pub fn find_by_name(&self, name: &str) -> Option<SpatialAnchor> {
+        self.all_anchors().into_iter().find(|a| a.name == name)
     }
     
     /// Find nearest anchor to a point
-    pub fn find_nearest(&self, position: &Point3<f32>) -> Option<&SpatialAnchor> {
-        self.anchors.values()
-            .filter(|a| a.is_tracking())
+    pub fn find_nearest(&self, position: &Point3<f32>) -> Option<SpatialAnchor> {
+        self.tracking_anchors().into_iter()
             .min_by(|a, b| {
                 let dist_a = (a.transform.position - position).norm();
                 let dist_b = (b.transform.position - position).norm();
@@ -241,25 +353,22 @@ impl AnchorManager {
     }
     
     /// Find anchors within radius
-    pub fn find_within_radius(&self, position: &Point3<f32>, radius: f32) -> Vec<&SpatialAnchor> {
-        self.anchors.values()
-            .filter(|a| {
-                a.is_tracking() && 
-                (a.transform.position - position).norm() <= radius
-            })
+    pub fn find_within_radius(&self, position: &Point3<f32>, radius: f32) -> Vec<SpatialAnchor> {
+        self.tracking_anchors().into_iter()
+            .filter(|a| (a.transform.position - position).norm() <= radius)
             .collect()
     }
     
-    /// Cleanup stale anchors
+    /// Cleanup stale anchors added through this manager
     pub fn cleanup_stale(&mut self) {
         let threshold = self.stale_threshold;
-        let stale_ids: Vec<ContentId> = self.anchors.iter()
-            .filter(|(_, a)| {
+        let stale_ids: Vec<AnchorId> = self.owned_anchors().iter()
+            .filter(|a| {
                 !a.persistent && 
                 a.state == AnchorState::Lost &&
                 a.time_since_update() > threshold
             })
-            .map(|(&id, _)| id)
+            .map(|a| a.id)
             .collect();
         
         for id in stale_ids {
@@ -269,25 +378,52 @@ impl AnchorManager {
     
     /// Get anchor count
     pub fn anchor_count(&self) -> usize {
-        self.anchors.len()
+        self.service.count()
     }
     
     /// Get tracking anchor count
     pub fn tracking_count(&self) -> usize {
-        self.anchors.values().filter(|a| a.is_tracking()).count()
+        self.tracking_anchors().len()
     }
     
-    /// Clear all non-persistent anchors
+    /// Clear the non-persistent anchors added through this manager
     pub fn clear_non_persistent(&mut self) {
-        let non_persistent: Vec<ContentId> = self.anchors.iter()
-            .filter(|(_, a)| !a.persistent)
-            .map(|(&id, _)| id)
+        let non_persistent: Vec<AnchorId> = self.owned_anchors().iter()
+            .filter(|a| !a.persistent)
+            .map(|a| a.id)
             .collect();
         
         for id in non_persistent {
             self.remove_anchor(id);
         }
     }
+    
+    /// Anchors added through this manager
+    fn owned_anchors(&self) -> Vec<SpatialAnchor> {
+        self.anchors.keys().filter_map(|&id| self.get_anchor(id)).collect()
+    }
+    
+    /// Renderer view of a world anchor
+    fn view(&self, world: &spatial::SpatialAnchor) -> SpatialAnchor {
+        let meta = self.anchors.get(&world.id).cloned().unwrap_or_else(|| AnchorMeta::foreign(world));
+        let local = world.position.local;
+        SpatialAnchor {
+            id: world.id,
+            name: world.label.clone().unwrap_or_default(),
+            anchor_type: meta.anchor_type,
+            transform: Transform {
+                position: Point3::new(local.x, local.y, local.z),
+                rotation: world.orientation.to_unit(),
+                scale: Vector3::new(1.0, 1.0, 1.0),
+            },
+            state: world.state.into(),
+            confidence: world.confidence,
+            created_at: meta.created_at,
+            last_updated: meta.last_updated,
+            persistent: self.service.is_persistent(world.id),
+            cloud_id: meta.cloud_id,
+        }
+    }
 }
 
 impl Default for AnchorManager {
@@ -453,11 +589,11 @@ mod tests {
         let id2 = manager.add_anchor(anchor2).unwrap();
         
         assert_eq!(manager.anchor_count(), 2);
+        assert_ne!(id1, id2);
         
         // Update tracking
-        if let Some(anchor) = manager.get_anchor_mut(id1) {
-            anchor.update_tracking(anchor.transform, 0.9);
-        }
+        let transform = manager.get_anchor(id1).unwrap().transform;
+        assert!(manager.update_tracking(id1, transform, 0.9));
         
         assert_eq!(manager.tracking_count(), 1);
     }
@@ -546,6 +682,86 @@ mod tests {
         assert!(marker.create_anchor().is_some());
     }
     
+    #[test]
+    fn test_anchor_manager_shares_world_anchors() {
+        let service = WorldAnchorService::in_memory();
+        let mut manager = AnchorManager::with_service(service.clone());
+        
+        let anchor = SpatialAnchor::plane(
+            "table",
+            Point3::new(1.0, 0.8, -1.0),
+            Vector3::new(0.0, 0.0, 1.0),
+        ).with_persistent(true);
+        let rotation = anchor.transform.rotation;
+        let id = manager.add_anchor(anchor).unwrap();
+        
+        // The world anchor carries the renderer's pose, name and persistence
+        let world = service.get(id).unwrap();
+        assert_eq!(world.label.as_deref(), Some("table"));
+        assert_eq!(world.state, spatial::AnchorState::Pending);
+        assert!(world.orientation.to_unit().angle_to(&rotation) < 1e-5);
+        assert!(service.is_persistent(id));
+        
+        // Tracking updates from other systems reach the renderer
+        service.update_confidence(id, 0.9);
+        let view = manager.get_anchor(id).unwrap();
+        assert_eq!(view.state, AnchorState::Tracking);
+        assert_eq!(view.anchor_type, AnchorType::Plane);
+        assert!(view.persistent);
+        
+        manager.clear_non_persistent();
+        assert_eq!(manager.anchor_count(), 1);
+        
+        manager.remove_anchor(id).unwrap();
+        assert_eq!(service.count(), 0);
+    }
+    
+    #[test]
+    fn test_anchor_manager_sees_service_anchors() {
+        let service = WorldAnchorService::in_memory();
+        let manager = AnchorManager::with_service(service.clone());
+        
+        // An anchor created by another system (WebXR, SpatialSystem, restore)
+        let world = service.create(CreateAnchorRequest {
+            position: WorldPosition::from_local(2.0, 0.0, 0.0),
+            label: Some("webxr".to_string()),
+            confidence: Some(0.9),
+            ..Default::default()
+        }, false).unwrap();
+        
+        assert_eq!(manager.anchor_count(), 1);
+        assert_eq!(manager.all_anchors()[0].id, world.id);
+        assert_eq!(manager.get_anchor(world.id).unwrap().anchor_type, AnchorType::Point);
+        let nearest = manager.find_nearest(&Point3::origin()).unwrap();
+        assert_eq!(nearest.name, "webxr");
+    }
+    
+    #[test]
+    fn test_anchor_manager_tracking_keeps_world_position() {
+        let service = WorldAnchorService::in_memory();
+        let mut manager = AnchorManager::with_service(service.clone());
+        
+        let mut position = WorldPosition::from_local(0.0, 0.0, 0.0);
+        position.floor = 3;
+        position.room_id = Some(spatial::RoomId::new("lab"));
+        let world = service.create(CreateAnchorRequest { position, ..Default::default() }, true).unwrap();
+        
+        assert!(manager.update_tracking(world.id, Transform::at_position(1.0, 0.0, 0.0), 0.9));
+        let tracked = service.get(world.id).unwrap();
+        assert_eq!(tracked.position.local.x, 1.0);
+        assert_eq!(tracked.position.floor, 3);
+        assert_eq!(tracked.position.room_id, Some(spatial::RoomId::new("lab")));
+        assert_eq!(manager.get_anchor(world.id).unwrap().state, AnchorState::Tracking);
+        
+        // Anchors created elsewhere are never cleaned up by the renderer
+        service.create(CreateAnchorRequest::default(), false).unwrap();
+        manager.clear_non_persistent();
+        assert_eq!(manager.anchor_count(), 2);
+        manager.add_anchor(SpatialAnchor::point("session", 0.0, 0.0, 0.0)).unwrap();
+        manager.clear_non_persistent();
+        assert_eq!(manager.anchor_count(), 2);
+    }
+    
     #[test]
     fn test_anchor_persistent() {
         let anchor = SpatialAnchor::point("test", 0.0, 0.0, 0.0)
@@ -7,6 +7,7 @@ pub mod overlays;
 pub mod renderer;
 pub mod shaders;
 pub mod tracking;
+pub mod vio;
 pub mod plane;
 pub mod mesh;
 pub mod lighting;
@@ -20,6 +21,7 @@ pub use overlays::*;
 pub use renderer::*;
 pub use shaders::*;
 pub use tracking::*;
+pub use vio::*;
 pub use plane::*;
 pub use mesh::*;
 pub use lighting::*;
@@ -1,6 +1,8 @@
 //! AR Session Management for Kāraṇa OS
 //! 
 //! Manages AR session lifecycle, configuration, and state.
+//! Session poses come from the visual-inertial filter (`vio`), which is propagated
+//! with every IMU sample and corrected by the visual tracker.
 
 use super::*;
 use super::tracking::{WorldTracker, CameraIntrinsics, TrackingState, PoseEstimate, CameraFrame, ImuMeasurement};
@@ -8,6 +10,8 @@ use super::plane::{PlaneDetector, PlaneDetectionConfig, SurfacePlane};
 use super::mesh::{MeshReconstructor, MeshConfig, MeshChunk};
 use super::lighting::{LightEstimator, LightEstimationConfig, LightEstimate};
 use super::occlusion::{OcclusionHandler, OcclusionConfig, OcclusionResult, DepthFrame, SegmentationFrame};
+use super::vio::VisualInertialOdometry;
+use crate::hal::sensors::SensorData;
 use nalgebra::{Point3, Vector3};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
@@ -78,6 +82,8 @@ pub struct ArFrame {
     pub timestamp_ns: u64,
     /// Camera pose
     pub pose: PoseEstimate,
+    /// Pose predicted for when this frame is displayed (one frame interval later)
+    pub display_pose: PoseEstimate,
     /// Tracking state
     pub tracking_state: TrackingState,
     /// Updated plane IDs
@@ -131,6 +137,8 @@ pub struct ArSession {
     intrinsics: CameraIntrinsics,
     /// World tracker
     world_tracker: WorldTracker,
+    /// Visual-inertial filter fusing the IMU with the tracker's poses
+    vio: VisualInertialOdometry,
     /// Plane detector
     plane_detector: PlaneDetector,
     /// Mesh reconstructor
@@ -160,6 +168,7 @@ impl ArSession {
             config: config.clone(),
             intrinsics,
             world_tracker: WorldTracker::new(intrinsics),
+            vio: VisualInertialOdometry::default(),
             plane_detector: PlaneDetector::new(config.plane_config.clone()),
             mesh_reconstructor: MeshReconstructor::new(config.mesh_config.clone()),
             light_estimator: LightEstimator::new(config.light_config.clone()),
@@ -250,7 +259,12 @@ impl ArSession {
             TrackingState::NotAvailable
         };
 
-        self.current_pose = self.world_tracker.get_pose();
+        if matches!(tracking_state, TrackingState::Normal | TrackingState::Excellent) {
+            let visual_pose = self.world_tracker.get_pose();
+            self.process_visual_pose(&visual_pose);
+        } else if !self.vio.is_initialized() {
+            self.current_pose = self.world_tracker.get_pose();
+        }
 
         // Update plane detection
         let updated_planes = if self.config.plane_detection {
@@ -281,10 +295,12 @@ impl ArSession {
         let processing_time = start.elapsed();
         self.last_frame_time = Some(Instant::now());
 
+        let frame_interval_ns = 1_000_000_000 / self.config.target_fps.max(1) as u64;
         ArFrame {
             id: self.frame_count,
             timestamp_ns: frame.timestamp_ns,
             pose: self.current_pose,
+            display_pose: self.predict_pose(frame.timestamp_ns + frame_interval_ns),
             tracking_state,
             updated_planes,
             light_estimate: self.light_estimator.get_estimate().clone(),
@@ -296,9 +312,43 @@ impl ArSession {
     pub fn process_imu(&mut self, measurement: ImuMeasurement) {
         if self.config.world_tracking && self.state == SessionState::Running {
             self.world_tracker.process_imu(measurement);
+            self.vio.process_imu(measurement);
+            if let Some(pose) = self.vio.pose() {
+                self.current_pose = pose;
+            }
+        }
+    }
+
+    /// Process a `SensorHub::read_all` batch; false if it has no accelerometer and
+    /// gyroscope reading
+    pub fn process_sensor_data(&mut self, data: &[SensorData]) -> bool {
+        match ImuMeasurement::from_sensor_data(data) {
+            Some(measurement) => {
+                self.process_imu(measurement);
+                true
+            }
+            None => false,
         }
     }
 
+    /// Correct the pose with a metric visual pose, from the world tracker or an
+    /// external (stereo, depth) tracker. False if the filter rejected it.
+    pub fn process_visual_pose(&mut self, pose: &PoseEstimate) -> bool {
+        if !self.config.world_tracking || !self.vio.update_pose(pose) {
+            return false;
+        }
+        if let Some(pose) = self.vio.pose() {
+            self.current_pose = pose;
+        }
+        true
+    }
+
+    /// Pose to render with for a frame shown at `display_time_ns`, extrapolated by
+    /// the visual-inertial filter (the current pose until it has a visual fix)
+    pub fn predict_pose(&self, display_time_ns: u64) -> PoseEstimate {
+        self.vio.predict(display_time_ns).unwrap_or(self.current_pose)
+    }
+
     /// Process depth frame
     pub fn process_depth_frame(&mut self, frame: DepthFrame) {
         if self.config.occlusion {
@@ -457,6 +507,7 @@ impl ArSession {
     /// Reset session
     pub fn reset(&mut self) {
         self.world_tracker.reset();
+        self.vio.reset();
         self.plane_detector.clear();
         self.mesh_reconstructor.clear();
         self.frame_count = 0;
@@ -613,6 +664,59 @@ mod tests {
         assert_eq!(stats.frame_count, 0);
     }
 
+    #[test]
+    fn test_imu_and_visual_poses_drive_display_poses() {
+        use crate::hal::sensors::{SensorAccuracy, SensorType};
+        use crate::simulator::sensors::{MotionTrace, MotionTraceConfig, Quaternion, Vec3};
+        use nalgebra::UnitQuaternion;
+
+        let vector = |v: &Vec3| Vector3::new(v.x, v.y, v.z);
+        let rotation = |q: &Quaternion| UnitQuaternion::new_normalize(nalgebra::Quaternion::new(q.w, q.x, q.y, q.z));
+        let reading = |sensor_type, timestamp_ns, v: &Vec3| SensorData {
+            sensor_type,
+            timestamp_ns,
+            values: vec![v.x, v.y, v.z],
+            accuracy: SensorAccuracy::High,
+        };
+
+        let trace = MotionTrace::generate(&MotionTraceConfig::default());
+        let mut session = ArSession::new(SessionConfig::default(), create_test_intrinsics());
+        session.start().unwrap();
+        let mut camera = trace.camera.iter().peekable();
+        // Stop during the first fast head turn, when the camera has no pose
+        let cutoff = trace.imu.iter().position(|s| s.timestamp_ns >= 2_750_000_000).unwrap();
+        for imu in &trace.imu[..=cutoff] {
+            assert!(session.process_sensor_data(&[
+                reading(SensorType::Accelerometer, imu.timestamp_ns, &imu.acceleration),
+                reading(SensorType::Gyroscope, imu.timestamp_ns, &imu.angular_velocity),
+            ]));
+            while let Some(sample) = camera.next_if(|c| c.timestamp_ns <= imu.timestamp_ns) {
+                let pose = PoseEstimate {
+                    position: Point3::from(vector(&sample.position)),
+                    orientation: rotation(&sample.orientation),
+                    timestamp_ns: sample.timestamp_ns,
+                    ..PoseEstimate::identity()
+                };
+                assert!(session.process_visual_pose(&pose));
+            }
+        }
+
+        let now = session.get_pose();
+        let truth = trace.truth_at(now.timestamp_ns).unwrap();
+        assert_eq!(now.timestamp_ns, trace.imu[cutoff].timestamp_ns);
+        assert!((now.position.coords - vector(&truth.position)).norm() < 0.05);
+        assert!(now.orientation.angle_to(&rotation(&truth.orientation)).to_degrees() < 1.0);
+
+        let display_time = now.timestamp_ns + 20_000_000;
+        let predicted = session.predict_pose(display_time);
+        let truth = rotation(&trace.truth_at(display_time).unwrap().orientation);
+        assert_eq!(predicted.timestamp_ns, display_time);
+        assert!(predicted.orientation.angle_to(&truth) < now.orientation.angle_to(&truth) / 4.0);
+
+        session.reset();
+        assert_eq!(session.predict_pose(display_time).timestamp_ns, 0);
+    }
+
     #[test]
     fn test_session_reset() {
         let config = SessionConfig::default();
@@ -4,6 +4,7 @@
 //! 6DoF pose estimation in AR applications.
 
 use super::*;
+use crate::hal::sensors::{SensorData, SensorType};
 use nalgebra::{Matrix4, Point3, UnitQuaternion, Vector3};
 use std::collections::HashMap;
 use std::time::{Duration, Instant};
@@ -114,6 +115,24 @@ pub struct ImuMeasurement {
     pub angular_velocity: Vector3<f32>,
 }
 
+impl ImuMeasurement {
+    /// Pair the accelerometer and gyroscope readings of a `SensorHub::read_all` batch
+    pub fn from_sensor_data(data: &[SensorData]) -> Option<Self> {
+        let reading = |sensor_type: SensorType| {
+            data.iter()
+                .filter(|d| d.sensor_type == sensor_type && d.values.len() >= 3)
+                .max_by_key(|d| d.timestamp_ns)
+        };
+        let accel = reading(SensorType::Accelerometer)?;
+        let gyro = reading(SensorType::Gyroscope)?;
+        Some(Self {
+            timestamp_ns: accel.timestamp_ns.max(gyro.timestamp_ns),
+            acceleration: Vector3::new(accel.values[0], accel.values[1], accel.values[2]),
+            angular_velocity: Vector3::new(gyro.values[0], gyro.values[1], gyro.values[2]),
+        })
+    }
+}
+
 /// Camera frame with image and metadata
 #[derive(Debug, Clone)]
 pub struct CameraFrame {
@@ -685,6 +704,27 @@ mod tests {
         assert!((tracker.get_tracking_quality() - 1.0).abs() < 0.01);
     }
 
+    #[test]
+    fn test_imu_from_sensor_data() {
+        let reading = |sensor_type, timestamp_ns, values: Vec<f32>| SensorData {
+            sensor_type,
+            timestamp_ns,
+            values,
+            accuracy: crate::hal::sensors::SensorAccuracy::High,
+        };
+        let batch = vec![
+            reading(SensorType::Gyroscope, 1_000, vec![0.1, 0.2, 0.3]),
+            reading(SensorType::Temperature, 1_000, vec![25.0]),
+            reading(SensorType::Accelerometer, 1_200, vec![0.0, -9.81, 0.0]),
+        ];
+        let imu = ImuMeasurement::from_sensor_data(&batch).unwrap();
+        assert_eq!(imu.timestamp_ns, 1_200);
+        assert!((imu.angular_velocity.z - 0.3).abs() < 1e-6);
+        assert!((imu.acceleration.y + 9.81).abs() < 1e-6);
+
+        assert!(ImuMeasurement::from_sensor_data(&batch[..2]).is_none());
+    }
+
     #[test]
     fn test_imu_measurement() {
         let imu = ImuMeasurement {
@@ -0,0 +1,530 @@
+//! Visual-Inertial Odometry for Kāraṇa OS
+//!
+//! Error-state extended Kalman filter that propagates the head pose with the
+//! gyroscope and accelerometer and corrects it with (metric) visual poses. The
+//! filter estimates gyro and accelerometer biases, so the pose holds up through
+//! fast head turns where the camera blurs out, and it extrapolates the pose to
+//! display time for rendering.
+//!
+//! Conventions: orientation maps head to world, the orientation error is local
+//! (`q_true = q * exp(dθ)`), and the accelerometer reads `gravity` (in world
+//! coordinates) when level and still, like `WorldTracker` and the simulator.
+//! Visual poses may arrive late: the filter keeps the state at the last visual
+//! update and the IMU samples since, and replays them around the new update.
+
+use super::tracking::{ImuMeasurement, PoseEstimate};
+use crate::hal::sensors::SensorData;
+use nalgebra::{Matrix3, Point3, SMatrix, UnitQuaternion, Vector3};
+use std::collections::VecDeque;
+
+type Matrix15 = SMatrix<f64, 15, 15>;
+
+/// Error-state layout
+const POS: usize = 0;
+const VEL: usize = 3;
+const ROT: usize = 6;
+const BG: usize = 9;
+const BA: usize = 12;
+
+/// IMU samples kept for replaying delayed visual updates (~2.5 s at 400 Hz)
+const MAX_REPLAY: usize = 1000;
+/// Gaps longer than this are not integrated (sensor dropout)
+const MAX_IMU_GAP_NS: u64 = 100_000_000;
+/// Chi-square gate for a 6-DoF pose residual (99.9%)
+const POSE_GATE: f64 = 22.46;
+/// Longest extrapolation `predict` will do
+const MAX_PREDICTION_NS: u64 = 100_000_000;
+
+/// Noise model of the estimator
+#[derive(Debug, Clone)]
+pub struct VioConfig {
+    /// Accelerometer reading of a level, stationary device (world frame)
+    pub gravity: Vector3<f32>,
+    /// Gyroscope noise density (rad/s/√Hz)
+    pub gyro_noise: f32,
+    /// Accelerometer noise density (m/s²/√Hz)
+    pub accel_noise: f32,
+    /// Gyroscope bias random walk (rad/s²/√Hz)
+    pub gyro_bias_walk: f32,
+    /// Accelerometer bias random walk (m/s³/√Hz)
+    pub accel_bias_walk: f32,
+    /// Initial bias uncertainty (std dev)
+    pub initial_gyro_bias: f32,
+    pub initial_accel_bias: f32,
+    /// Initial velocity uncertainty (std dev, m/s)
+    pub initial_velocity: f32,
+    /// Visual pose noise used when a `PoseEstimate` reports no uncertainty
+    pub visual_position_noise: f32,
+    pub visual_orientation_noise: f32,
+}
+
+impl Default for VioConfig {
+    fn default() -> Self {
+        Self {
+            gravity: Vector3::new(0.0, -9.81, 0.0),
+            gyro_noise: 2e-4,
+            accel_noise: 2e-3,
+            gyro_bias_walk: 2e-5,
+            accel_bias_walk: 3e-3,
+            initial_gyro_bias: 0.05,
+            initial_accel_bias: 0.2,
+            initial_velocity: 1.0,
+            visual_position_noise: 0.01,
+            visual_orientation_noise: 0.01,
+        }
+    }
+}
+
+/// Nominal state and error covariance at one instant
+#[derive(Debug, Clone)]
+struct FilterState {
+    timestamp_ns: u64,
+    position: Vector3<f64>,
+    velocity: Vector3<f64>,
+    orientation: UnitQuaternion<f64>,
+    gyro_bias: Vector3<f64>,
+    accel_bias: Vector3<f64>,
+    covariance: Matrix15,
+    /// Sample at `timestamp_ns`, the start of the next integration step
+    last_imu: Option<ImuMeasurement>,
+}
+
+impl FilterState {
+    /// Integrate up to `imu` (midpoint of the previous and current reading)
+    fn propagate(&mut self, imu: &ImuMeasurement, config: &VioConfig) {
+        let previous = self.last_imu.replace(*imu);
+        if imu.timestamp_ns <= self.timestamp_ns {
+            return;
+        }
+        let gap = imu.timestamp_ns - self.timestamp_ns;
+        self.timestamp_ns = imu.timestamp_ns;
+        let Some(previous) = previous.filter(|_| gap <= MAX_IMU_GAP_NS) else {
+            return;
+        };
+        let dt = gap as f64 * 1e-9;
+
+        let omega = (to_f64(&previous.angular_velocity) + to_f64(&imu.angular_velocity)) / 2.0 - self.gyro_bias;
+        let accel = (to_f64(&previous.acceleration) + to_f64(&imu.acceleration)) / 2.0 - self.accel_bias;
+        let rotation = self.orientation.to_rotation_matrix().into_inner();
+        let world_accel = rotation * accel - to_f64(&config.gravity);
+
+        self.position += self.velocity * dt + world_accel * (0.5 * dt * dt);
+        self.velocity += world_accel * dt;
+        let delta = UnitQuaternion::from_scaled_axis(omega * dt);
+        self.orientation *= delta;
+
+        // Error-state transition (first order)
+        let mut phi = Matrix15::identity();
+        phi.fixed_view_mut::<3, 3>(POS, VEL).copy_from(&(Matrix3::identity() * dt));
+        phi.fixed_view_mut::<3, 3>(VEL, ROT).copy_from(&(-rotation * accel.cross_matrix() * dt));
+        phi.fixed_view_mut::<3, 3>(VEL, BA).copy_from(&(-rotation * dt));
+        phi.fixed_view_mut::<3, 3>(ROT, ROT).copy_from(&delta.inverse().to_rotation_matrix().into_inner());
+        phi.fixed_view_mut::<3, 3>(ROT, BG).copy_from(&(-Matrix3::identity() * dt));
+
+        let mut noise = Matrix15::zeros();
+        let diagonal = [
+            (VEL, config.accel_noise),
+            (ROT, config.gyro_noise),
+            (BG, config.gyro_bias_walk),
+            (BA, config.accel_bias_walk),
+        ];
+        for (block, density) in diagonal {
+            let variance = (density as f64).powi(2) * dt;
+            for i in 0..3 {
+                noise[(block + i, block + i)] = variance;
+            }
+        }
+        self.covariance = phi * self.covariance * phi.transpose() + noise;
+    }
+
+    /// Kalman update with a visual pose; false if the pose fails the gate
+    fn correct(&mut self, position: &Vector3<f64>, orientation: &UnitQuaternion<f64>, position_std: f64, orientation_std: f64) -> bool {
+        let mut residual = SMatrix::<f64, 6, 1>::zeros();
+        residual.fixed_rows_mut::<3>(0).copy_from(&(position - self.position));
+        residual.fixed_rows_mut::<3>(3).copy_from(&(self.orientation.inverse() * orientation).scaled_axis());
+
+        let mut h = SMatrix::<f64, 6, 15>::zeros();
+        h.fixed_view_mut::<3, 3>(0, POS).copy_from(&Matrix3::identity());
+        h.fixed_view_mut::<3, 3>(3, ROT).copy_from(&Matrix3::identity());
+        let mut r = SMatrix::<f64, 6, 6>::zeros();
+        for i in 0..3 {
+            r[(i, i)] = position_std * position_std;
+            r[(i + 3, i + 3)] = orientation_std * orientation_std;
+        }
+
+        let s = h * self.covariance * h.transpose() + r;
+        let Some(s_inv) = s.try_inverse() else {
+            return false;
+        };
+        if (residual.transpose() * s_inv * residual)[0] > POSE_GATE {
+            return false;
+        }
+        let gain = self.covariance * h.transpose() * s_inv;
+        let dx = gain * residual;
+
+        self.position += dx.fixed_rows::<3>(POS);
+        self.velocity += dx.fixed_rows::<3>(VEL);
+        self.orientation *= UnitQuaternion::from_scaled_axis(dx.fixed_rows::<3>(ROT).into_owned());
+        self.gyro_bias += dx.fixed_rows::<3>(BG);
+        self.accel_bias += dx.fixed_rows::<3>(BA);
+
+        // Joseph form keeps the covariance symmetric positive definite
+        let i_kh = Matrix15::identity() - gain * h;
+        self.covariance = i_kh * self.covariance * i_kh.transpose() + gain * r * gain.transpose();
+        true
+    }
+
+    fn to_pose(&self) -> PoseEstimate {
+        let angular_velocity = self
+            .last_imu
+            .map(|imu| to_f64(&imu.angular_velocity) - self.gyro_bias)
+            .unwrap_or_else(Vector3::zeros);
+        let block_std = |block: usize| ((0..3).map(|i| self.covariance[(block + i, block + i)]).sum::<f64>() / 3.0).sqrt();
+        PoseEstimate {
+            position: Point3::from(to_f32(&self.position)),
+            orientation: self.orientation.cast(),
+            velocity: to_f32(&self.velocity),
+            angular_velocity: to_f32(&angular_velocity),
+            position_uncertainty: block_std(POS) as f32,
+            orientation_uncertainty: block_std(ROT) as f32,
+            timestamp_ns: self.timestamp_ns,
+        }
+    }
+}
+
+/// Visual-inertial pose estimator
+#[derive(Debug)]
+pub struct VisualInertialOdometry {
+    config: VioConfig,
+    /// Latest state (at the newest IMU sample)
+    state: Option<FilterState>,
+    /// State right after the last visual update
+    checkpoint: Option<FilterState>,
+    /// IMU samples since the checkpoint
+    replay: VecDeque<ImuMeasurement>,
+    /// Newest IMU sample seen before initialization
+    pending_imu: Option<ImuMeasurement>,
+    /// Visual updates rejected by the gate
+    rejected_updates: u64,
+}
+
+impl VisualInertialOdometry {
+    pub fn new(config: VioConfig) -> Self {
+        Self {
+            config,
+            state: None,
+            checkpoint: None,
+            replay: VecDeque::new(),
+            pending_imu: None,
+            rejected_updates: 0,
+        }
+    }
+
+    /// Whether a visual pose has anchored the filter yet
+    pub fn is_initialized(&self) -> bool {
+        self.state.is_some()
+    }
+
+    /// Propagate with an IMU sample; samples must arrive in order
+    pub fn process_imu(&mut self, imu: ImuMeasurement) {
+        let Some(state) = self.state.as_mut() else {
+            self.pending_imu = Some(imu);
+            return;
+        };
+        if imu.timestamp_ns <= state.timestamp_ns {
+            return;
+        }
+        state.propagate(&imu, &self.config);
+        self.replay.push_back(imu);
+
+        // Keep the replay window bounded by moving the checkpoint forward
+        while self.replay.len() > MAX_REPLAY {
+            if let (Some(oldest), Some(checkpoint)) = (self.replay.pop_front(), self.checkpoint.as_mut()) {
+                checkpoint.propagate(&oldest, &self.config);
+            }
+        }
+    }
+
+    /// Propagate with a `SensorHub::read_all` batch; false if it has no IMU pair
+    pub fn process_sensor_data(&mut self, data: &[SensorData]) -> bool {
+        match ImuMeasurement::from_sensor_data(data) {
+            Some(imu) => {
+                self.process_imu(imu);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Correct with a visual pose taken at `pose.timestamp_ns`. Position must be
+    /// metric (stereo, depth or a scale-resolved monocular track). Returns false if
+    /// the pose is older than the last update or inconsistent with the IMU.
+    pub fn update_pose(&mut self, pose: &PoseEstimate) -> bool {
+        let position = to_f64(&pose.position.coords);
+        let orientation = pose.orientation.cast::<f64>();
+        let std = |reported: f32, fallback: f32| if reported > 0.0 { reported } else { fallback } as f64;
+        let position_std = std(pose.position_uncertainty, self.config.visual_position_noise);
+        let orientation_std = std(pose.orientation_uncertainty, self.config.visual_orientation_noise);
+
+        let Some(checkpoint) = &self.checkpoint else {
+            self.initialize(pose.timestamp_ns, position, orientation, position_std, orientation_std);
+            return true;
+        };
+        if pose.timestamp_ns < checkpoint.timestamp_ns {
+            return false;
+        }
+
+        // Rewind to the checkpoint and integrate up to the pose timestamp
+        let mut state = checkpoint.clone();
+        let split = self.replay.partition_point(|imu| imu.timestamp_ns <= pose.timestamp_ns);
+        for imu in self.replay.range(..split) {
+            state.propagate(imu, &self.config);
+        }
+        if state.timestamp_ns < pose.timestamp_ns {
+            let next = self.replay.get(split).copied();
+            if let Some(at_pose) = interpolate_imu(state.last_imu.as_ref(), next.as_ref(), pose.timestamp_ns) {
+                state.propagate(&at_pose, &self.config);
+            }
+        }
+
+        if !state.correct(&position, &orientation, position_std, orientation_std) {
+            self.rejected_updates += 1;
+            return false;
+        }
+
+        // New checkpoint; replay the newer samples to get back to the present
+        self.replay.drain(..split);
+        self.checkpoint = Some(state.clone());
+        for imu in &self.replay {
+            state.propagate(imu, &self.config);
+        }
+        self.state = Some(state);
+        true
+    }
+
+    /// Current pose (at the newest IMU sample)
+    pub fn pose(&self) -> Option<PoseEstimate> {
+        self.state.as_ref().map(|s| s.to_pose())
+    }
+
+    /// Pose extrapolated to `display_time_ns` with the current angular velocity and
+    /// acceleration, so content is rendered where the head will be when shown
+    pub fn predict(&self, display_time_ns: u64) -> Option<PoseEstimate> {
+        let state = self.state.as_ref()?;
+        let mut predicted = state.clone();
+        if let Some(imu) = state.last_imu {
+            let ahead = display_time_ns.saturating_sub(state.timestamp_ns).min(MAX_PREDICTION_NS);
+            if ahead > 0 {
+                let future = ImuMeasurement { timestamp_ns: state.timestamp_ns + ahead, ..imu };
+                predicted.propagate(&future, &self.config);
+            }
+        }
+        let mut pose = predicted.to_pose();
+        pose.timestamp_ns = display_time_ns;
+        Some(pose)
+    }
+
+    /// Estimated gyroscope bias (rad/s)
+    pub fn gyro_bias(&self) -> Vector3<f32> {
+        self.state.as_ref().map_or_else(Vector3::zeros, |s| to_f32(&s.gyro_bias))
+    }
+
+    /// Estimated accelerometer bias (m/s²)
+    pub fn accel_bias(&self) -> Vector3<f32> {
+        self.state.as_ref().map_or_else(Vector3::zeros, |s| to_f32(&s.accel_bias))
+    }
+
+    /// Visual poses rejected as inconsistent with the inertial prediction
+    pub fn rejected_updates(&self) -> u64 {
+        self.rejected_updates
+    }
+
+    pub fn reset(&mut self) {
+        self.state = None;
+        self.checkpoint = None;
+        self.replay.clear();
+        self.pending_imu = None;
+        self.rejected_updates = 0;
+    }
+
+    fn initialize(
+        &mut self,
+        timestamp_ns: u64,
+        position: Vector3<f64>,
+        orientation: UnitQuaternion<f64>,
+        position_std: f64,
+        orientation_std: f64,
+    ) {
+        let mut covariance = Matrix15::zeros();
+        let initial = [
+            (POS, position_std),
+            (VEL, self.config.initial_velocity as f64),
+            (ROT, orientation_std),
+            (BG, self.config.initial_gyro_bias as f64),
+            (BA, self.config.initial_accel_bias as f64),
+        ];
+        for (block, std) in initial {
+            for i in 0..3 {
+                covariance[(block + i, block + i)] = std * std;
+            }
+        }
+        let state = FilterState {
+            timestamp_ns,
+            position,
+            velocity: Vector3::zeros(),
+            orientation,
+            gyro_bias: Vector3::zeros(),
+            accel_bias: Vector3::zeros(),
+            covariance,
+            last_imu: self.pending_imu.take().map(|imu| ImuMeasurement { timestamp_ns, ..imu }),
+        };
+        self.checkpoint = Some(state.clone());
+        self.state = Some(state);
+        self.replay.clear();
+    }
+}
+
+impl Default for VisualInertialOdometry {
+    fn default() -> Self {
+        Self::new(VioConfig::default())
+    }
+}
+
+/// IMU reading at `timestamp_ns`, linearly interpolated (or held if no later sample)
+fn interpolate_imu(before: Option<&ImuMeasurement>, after: Option<&ImuMeasurement>, timestamp_ns: u64) -> Option<ImuMeasurement> {
+    let before = before?;
+    let Some(after) = after.filter(|a| a.timestamp_ns > before.timestamp_ns) else {
+        return Some(ImuMeasurement { timestamp_ns, ..*before });
+    };
+    let s = (timestamp_ns - before.timestamp_ns) as f32 / (after.timestamp_ns - before.timestamp_ns) as f32;
+    Some(ImuMeasurement {
+        timestamp_ns,
+        acceleration: before.acceleration.lerp(&after.acceleration, s),
+        angular_velocity: before.angular_velocity.lerp(&after.angular_velocity, s),
+    })
+}
+
+fn to_f64(v: &Vector3<f32>) -> Vector3<f64> {
+    v.cast()
+}
+
+fn to_f32(v: &Vector3<f64>) -> Vector3<f32> {
+    v.cast()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulator::sensors::{MotionTrace, MotionTraceConfig, PoseSample, Quaternion, Vec3};
+
+    fn vector(v: &Vec3) -> Vector3<f32> {
+        Vector3::new(v.x, v.y, v.z)
+    }
+
+    fn rotation(q: &Quaternion) -> UnitQuaternion<f32> {
+        UnitQuaternion::new_normalize(nalgebra::Quaternion::new(q.w, q.x, q.y, q.z))
+    }
+
+    fn visual_pose(sample: &PoseSample) -> PoseEstimate {
+        PoseEstimate {
+            position: Point3::from(vector(&sample.position)),
+            orientation: rotation(&sample.orientation),
+            timestamp_ns: sample.timestamp_ns,
+            ..PoseEstimate::identity()
+        }
+    }
+
+    /// Run a simulated trace; camera poses reach the filter `latency_ns` late.
+    /// Returns the filter and the worst position/orientation error while the
+    /// camera was out (fast head turns)
+    fn run(trace: &MotionTrace, latency_ns: u64) -> (VisualInertialOdometry, f32, f32) {
+        let mut vio = VisualInertialOdometry::default();
+        let mut camera = trace.camera.iter().peekable();
+        let (mut worst_position, mut worst_angle) = (0.0f32, 0.0f32);
+        for (imu, truth) in trace.imu.iter().zip(&trace.truth) {
+            vio.process_imu(ImuMeasurement {
+                timestamp_ns: imu.timestamp_ns,
+                acceleration: vector(&imu.acceleration),
+                angular_velocity: vector(&imu.angular_velocity),
+            });
+            while let Some(frame) = camera.next_if(|c| c.timestamp_ns + latency_ns <= imu.timestamp_ns) {
+                vio.update_pose(&visual_pose(frame));
+            }
+
+            let Some(pose) = vio.pose() else { continue };
+            let since_camera = trace.camera.iter().rev().find(|c| c.timestamp_ns <= imu.timestamp_ns)
+                .map(|c| imu.timestamp_ns - c.timestamp_ns)
+                .unwrap_or(0);
+            if imu.timestamp_ns > 2_000_000_000 && since_camera > 100_000_000 {
+                worst_position = worst_position.max((pose.position.coords - vector(&truth.position)).norm());
+                worst_angle = worst_angle.max(pose.orientation.angle_to(&rotation(&truth.orientation)));
+            }
+        }
+        (vio, worst_position, worst_angle)
+    }
+
+    #[test]
+    fn test_bridges_fast_head_turns_and_estimates_biases() {
+        let config = MotionTraceConfig::default();
+        let trace = MotionTrace::generate(&config);
+        let (vio, worst_position, worst_angle) = run(&trace, 0);
+
+        assert!(worst_position < 0.02, "drifted {worst_position:.3} m while the camera was out");
+        assert!(worst_angle.to_degrees() < 0.5, "drifted {:.2}° while the camera was out", worst_angle.to_degrees());
+
+        let gyro_error = (vio.gyro_bias() - vector(&config.gyro_bias)).norm();
+        assert!(gyro_error < 0.003, "gyro bias error {gyro_error}");
+        let accel_error = (vio.accel_bias() - vector(&config.accel_bias)).norm();
+        assert!(accel_error < 0.05, "accel bias error {accel_error}");
+    }
+
+    #[test]
+    fn test_delayed_visual_updates() {
+        let trace = MotionTrace::generate(&MotionTraceConfig::default());
+        // Visual poses arriving 50 ms late are replayed at their own timestamps
+        let (vio, worst_position, worst_angle) = run(&trace, 50_000_000);
+        assert!(worst_position < 0.05, "drifted {worst_position:.3} m");
+        assert!(worst_angle.to_degrees() < 1.0);
+        assert_eq!(vio.rejected_updates(), 0);
+    }
+
+    #[test]
+    fn test_predicts_pose_at_display_time() {
+        let trace = MotionTrace::generate(&MotionTraceConfig::default());
+        let mut vio = VisualInertialOdometry::default();
+        let mut camera = trace.camera.iter().peekable();
+        // Stop during the first head turn and predict 20 ms ahead
+        let cutoff = trace.imu.iter().position(|s| s.timestamp_ns >= 2_750_000_000).unwrap();
+        for imu in &trace.imu[..=cutoff] {
+            vio.process_imu(ImuMeasurement {
+                timestamp_ns: imu.timestamp_ns,
+                acceleration: vector(&imu.acceleration),
+                angular_velocity: vector(&imu.angular_velocity),
+            });
+            while let Some(frame) = camera.next_if(|c| c.timestamp_ns <= imu.timestamp_ns) {
+                vio.update_pose(&visual_pose(frame));
+            }
+        }
+
+        let now = vio.pose().unwrap();
+        let display_time = now.timestamp_ns + 20_000_000;
+        let predicted = vio.predict(display_time).unwrap();
+        let truth = rotation(&trace.truth_at(display_time).unwrap().orientation);
+        assert_eq!(predicted.timestamp_ns, display_time);
+        assert!(
+            predicted.orientation.angle_to(&truth) < now.orientation.angle_to(&truth) / 4.0,
+            "prediction should remove most of the display latency error"
+        );
+    }
+
+    #[test]
+    fn test_rejects_inconsistent_pose() {
+        let trace = MotionTrace::generate(&MotionTraceConfig::default());
+        let (mut vio, _, _) = run(&trace, 0);
+        let mut jump = visual_pose(trace.camera.last().unwrap());
+        jump.timestamp_ns = trace.imu.last().unwrap().timestamp_ns;
+        jump.position.x += 2.0;
+        assert!(!vio.update_pose(&jump));
+        assert_eq!(vio.rejected_updates(), 1);
+    }
+}
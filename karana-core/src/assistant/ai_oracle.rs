@@ -284,7 +284,7 @@ impl AIOracle {
         let tool_name = self.map_intent_to_tool(&intent)?;
         
         // Extract arguments from entities
-        let args = self.extract_tool_args(&intent);
+        let args = self.extract_tool_args(&tool_name, &intent);
         
         // Execute tool
         let tool_result = self.tool_registry.execute(&tool_name, args).await?;
@@ -354,12 +354,18 @@ impl AIOracle {
         }
     }
     
-    /// Extract tool arguments from intent entities
-    fn extract_tool_args(&self, intent: &ClassifiedIntent) -> ToolArgs {
+    /// Extract tool arguments from intent entities the tool declares
+    fn extract_tool_args(&self, tool_name: &str, intent: &ClassifiedIntent) -> ToolArgs {
         let mut args = ToolArgs::new();
+        let Some(spec) = self.tool_registry.spec(tool_name) else {
+            return args;
+        };
         
         for (key, value) in &intent.entities {
-            args.add(key.clone(), serde_json::Value::String(value.clone()));
+            let name = if key == "app" { "app_name" } else { key.as_str() };
+            if spec.parameter(name).is_some() {
+                args.add(name, value.clone());
+            }
         }
         
         args
@@ -513,7 +519,7 @@ mod tests {
     
     #[tokio::test]
     async fn test_oracle_basic_query() {
-        let tool_registry = Arc::new(ToolRegistry::new());
+        let tool_registry = Arc::new(crate::assistant::create_default_registry());
         let state_context = Arc::new(RwLock::new(StateContext::new()));
         
         let oracle = AIOracle::new(
@@ -2,10 +2,16 @@
 // Natural voice synthesis using system TTS or external engines
 
 use anyhow::{Result, anyhow};
+use futures::{Stream, StreamExt};
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
+use crate::ai::StreamEvent;
+
+/// Words whose trailing period does not end a sentence
+const ABBREVIATIONS: &[&str] = &["e.g", "i.e", "etc", "vs", "mr", "mrs", "ms", "dr", "st"];
+
 /// TTS voice profile
 #[derive(Debug, Clone)]
 pub struct VoiceProfile {
@@ -256,6 +262,43 @@ impl TtsService {
         Ok(audio)
     }
 
+    /// Speak a streamed LLM answer sentence by sentence, starting as soon as the
+    /// first sentence is complete. `on_audio` receives each sentence with its samples.
+    /// Returns the full answer; a cancelled stream is not spoken any further.
+    pub async fn speak_stream<S>(&self, mut events: S, mut on_audio: impl FnMut(&str, Vec<f32>)) -> Result<String>
+    where
+        S: Stream<Item = StreamEvent> + Unpin,
+    {
+        let mut splitter = SentenceSplitter::new();
+        let mut text = String::new();
+
+        while let Some(event) = events.next().await {
+            match event {
+                StreamEvent::Token { delta } => {
+                    text.push_str(&delta);
+                    for sentence in splitter.push(&delta) {
+                        let audio = self.speak_uncached(&format_for_tts(&sentence)).await?;
+                        on_audio(&sentence, audio);
+                    }
+                }
+                StreamEvent::Done { text: full, cancelled } => {
+                    if cancelled {
+                        return Ok(full);
+                    }
+                    text = full;
+                    break;
+                }
+                StreamEvent::Error { message } => return Err(anyhow!(message)),
+            }
+        }
+
+        if let Some(rest) = splitter.finish() {
+            let audio = self.speak_uncached(&format_for_tts(&rest)).await?;
+            on_audio(&rest, audio);
+        }
+        Ok(text)
+    }
+
     /// Speak text without caching (for dynamic content)
     pub async fn speak_uncached(&self, text: &str) -> Result<Vec<f32>> {
         let engine = self.engine.lock().await;
@@ -287,6 +330,69 @@ impl Default for TtsService {
     }
 }
 
+/// Splits streamed text into sentences as soon as each one is complete
+#[derive(Debug, Default)]
+pub struct SentenceSplitter {
+    buffer: String,
+}
+
+impl SentenceSplitter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append streamed text; returns the sentences it completed
+    pub fn push(&mut self, text: &str) -> Vec<String> {
+        self.buffer.push_str(text);
+
+        let mut sentences = Vec::new();
+        while let Some(end) = self.boundary() {
+            let sentence: String = self.buffer.drain(..end).collect();
+            let sentence = sentence.trim();
+            if !sentence.is_empty() {
+                sentences.push(sentence.to_string());
+            }
+        }
+        sentences
+    }
+
+    /// Whatever is left once the stream has ended
+    pub fn finish(&mut self) -> Option<String> {
+        let rest = std::mem::take(&mut self.buffer);
+        let rest = rest.trim();
+        (!rest.is_empty()).then(|| rest.to_string())
+    }
+
+    /// End of the first complete sentence. A terminator only counts once the
+    /// following whitespace has arrived ("3.5", "e.g." are not boundaries).
+    fn boundary(&self) -> Option<usize> {
+        let mut chars = self.buffer.char_indices().peekable();
+        while let Some((pos, c)) = chars.next() {
+            match c {
+                '\n' if !self.buffer[..pos].trim().is_empty() => return Some(pos + 1),
+                '.' | '!' | '?' => {
+                    let (_, next) = *chars.peek()?;
+                    if next.is_whitespace() && !(c == '.' && self.ends_with_abbreviation(pos)) {
+                        return Some(pos + 1);
+                    }
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+
+    fn ends_with_abbreviation(&self, period: usize) -> bool {
+        let word = self.buffer[..period]
+            .rsplit(char::is_whitespace)
+            .next()
+            .unwrap_or("")
+            .to_lowercase();
+        ABBREVIATIONS.contains(&word.as_str())
+            || (word.chars().count() == 1 && word.chars().all(char::is_alphabetic))
+    }
+}
+
 /// Helper to format text for better TTS
 pub fn format_for_tts(text: &str) -> String {
     let mut formatted = text.to_string();
@@ -372,6 +478,54 @@ mod tests {
         assert!(!formatted.contains("http://"));
     }
 
+    #[test]
+    fn test_sentence_splitter() {
+        let mut splitter = SentenceSplitter::new();
+
+        assert!(splitter.push("It is 3.5 km aw").is_empty());
+        assert!(splitter.push("ay, e.g. near Dr. Rao's").is_empty());
+        assert!(splitter.push(" office.").is_empty()); // Terminator without trailing space yet
+        assert_eq!(splitter.push(" Go left! Then"), [
+            "It is 3.5 km away, e.g. near Dr. Rao's office.",
+            "Go left!",
+        ]);
+        assert_eq!(splitter.push(" right\nDone"), ["Then right"]);
+        assert_eq!(splitter.finish(), Some("Done".to_string()));
+        assert_eq!(splitter.finish(), None);
+    }
+
+    #[tokio::test]
+    async fn test_speak_stream_starts_at_first_sentence() {
+        let tts = TtsService::mock();
+        let (tx, rx) = futures::channel::mpsc::unbounded();
+        let spoken = std::sync::Mutex::new(Vec::new());
+        let token = |delta: &str| StreamEvent::Token { delta: delta.to_string() };
+
+        tx.unbounded_send(token("Turn left here. Th")).unwrap();
+
+        let speaking = tts.speak_stream(rx, |sentence, audio| {
+            assert!(!audio.is_empty());
+            spoken.lock().unwrap().push(sentence.to_string());
+        });
+        let producer = async {
+            // The first sentence is spoken while generation is still running
+            while spoken.lock().unwrap().is_empty() {
+                tokio::task::yield_now().await;
+            }
+            assert_eq!(*spoken.lock().unwrap(), ["Turn left here."]);
+
+            tx.unbounded_send(token("en stop")).unwrap();
+            tx.unbounded_send(StreamEvent::Done {
+                text: "Turn left here. Then stop".to_string(),
+                cancelled: false,
+            }).unwrap();
+        };
+
+        let (text, ()) = tokio::join!(speaking, producer);
+        assert_eq!(text.unwrap(), "Turn left here. Then stop");
+        assert_eq!(*spoken.lock().unwrap(), ["Turn left here.", "Then stop"]);
+    }
+
     #[test]
     fn test_voice_gender() {
         let voice = VoiceProfile {
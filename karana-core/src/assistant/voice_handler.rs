@@ -80,8 +80,9 @@ impl VoiceCommandHandler {
         Ok(())
     }
 
-    async fn handle_direct_tool(&self, transcript: &str, _resolved: Option<String>) -> Result<()> {
-        let args = ToolArgs::new();
+    async fn handle_direct_tool(&self, transcript: &str, resolved: Option<String>) -> Result<()> {
+        let mut args = ToolArgs::new();
+        args.add("destination", resolved.unwrap_or_else(|| transcript.to_string()));
         let result = self.tool_registry.execute("navigate", args).await?;
         self.ws_server.broadcast_tool_result(&result.tool_name, &result.output, result.confidence).await?;
         Ok(())
@@ -5,8 +5,7 @@ use karana_core::network::WsServer;
 use karana_core::assistant::{create_default_registry, StateContext, TtsService};
 use karana_core::ai::KaranaAI;
 use karana_core::voice_pipeline::{VoicePipeline, VoiceConfig, VoiceToIntent};
-use std::sync::Arc;
-use tokio::sync::Mutex;
+use std::sync::{Arc, Mutex};
 use anyhow::Result;
 
 #[tokio::main]
@@ -18,10 +17,6 @@ async fn main() -> Result<()> {
     log::info!("🚀 Kāraṇa Voice AI Server Starting...");
     log::info!("=====================================");
 
-    // Create WebSocket server
-    let ws_server = Arc::new(WsServer::new());
-    log::info!("✓ WebSocket server initialized");
-
     // Create tool registry with default tools
     let tool_registry = Arc::new(create_default_registry());
     log::info!("✓ Tool registry created with {} tools", tool_registry.list_tools().len());
@@ -41,11 +36,15 @@ async fn main() -> Result<()> {
         log::warn!("⚠ TTS service unavailable (fallback mode)");
     }
 
-    // Initialize AI (for voice transcription)
+    // Initialize AI (for voice transcription and streamed answers)
     log::info!("⏳ Loading Whisper model for transcription...");
     let ai = Arc::new(Mutex::new(KaranaAI::new()?));
     log::info!("✓ AI system initialized");
 
+    // Create WebSocket server (streams answers from the same engine)
+    let ws_server = Arc::new(WsServer::new().with_ai(ai.clone()));
+    log::info!("✓ WebSocket server initialized");
+
     // Create voice pipeline
     let voice_config = VoiceConfig {
         sample_rate: 16000,
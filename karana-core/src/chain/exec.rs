@@ -0,0 +1,266 @@
+// All-or-nothing block execution
+//
+// Transactions are executed against staged views of the ledger and governance
+// state (`LedgerTxn` / `GovernanceTxn`). Each one must be signed by the key
+// its sender currently authorizes (see `Transaction::verify_signer`) and then
+// consumes the sender's next nonce, so a replayed or reordered transaction
+// fails like any other. Nothing touches disk until every
+// transaction in the block has succeeded; the first failure discards the
+// whole stage and reports its index. Committing a stage yields a `BlockUndo`
+// holding every value it replaced, which is what lets the tip be reverted
+// during a reorg.
+
+use super::state::{AttestationRecord, StateChange, StateKey};
+use super::{Transaction, TransactionData};
+use crate::economy::{KaraToken, Ledger, LedgerTxn, BLOCK_REWARD};
+use crate::gov::{Governance, GovernanceChanges, GovernanceUndo, Proposal};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// A transaction in a block could not be applied
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockExecutionError {
+    pub height: u64,
+    /// Position of the failing transaction within the block
+    pub tx_index: usize,
+    pub tx_hash: String,
+    pub reason: String,
+}
+
+impl fmt::Display for BlockExecutionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Block {} rejected: transaction #{} ({}) failed: {}",
+            self.height, self.tx_index, self.tx_hash, self.reason
+        )
+    }
+}
+
+impl std::error::Error for BlockExecutionError {}
+
+/// Everything a block changed, captured before the change was committed
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BlockUndo {
+    pub height: u64,
+    pub hash: String,
+    /// Previous ledger accounts (None = did not exist)
+    pub accounts: Vec<(String, Option<KaraToken>)>,
+    pub governance: GovernanceUndo,
+    /// Previous state trie leaves
+    pub state: Vec<StateChange>,
+}
+
+/// The staged result of executing a block's transactions
+pub struct StagedBlock {
+    pub accounts: BTreeMap<String, KaraToken>,
+    pub governance: GovernanceChanges,
+    pub attestations: Vec<(String, AttestationRecord)>,
+}
+
+impl StagedBlock {
+    /// State trie leaves this stage will write
+    pub fn state_changes(&self) -> Vec<StateChange> {
+        let mut changes = Vec::new();
+        for (did, account) in &self.accounts {
+            changes.push(leaf(&StateKey::Account(did.clone()), account));
+        }
+        for (id, proposal) in &self.governance.proposals {
+            changes.push(leaf(&StateKey::Proposal(*id), &proposal_commitment(proposal)));
+        }
+        if let Some(params) = &self.governance.params {
+            changes.push(leaf(&StateKey::GovParams, params));
+        }
+        for (id, record) in &self.attestations {
+            changes.push(leaf(&StateKey::Attestation(id.clone()), record));
+        }
+        changes
+    }
+}
+
+/// Execute `transactions` against staged copies of the ledger and governance,
+/// followed by the block's own bookkeeping: governance tallies, matured
+/// unbonds and the reward for `validator`.
+///
+/// Returns the staged changes, or the first failing transaction. The ledger
+/// and governance stores are only read.
+pub fn execute(
+    ledger: &Ledger,
+    gov: &Governance,
+    transactions: &[Transaction],
+    height: u64,
+    validator: &str,
+) -> Result<StagedBlock, BlockExecutionError> {
+    let mut ledger_txn = ledger.begin();
+    let mut gov_txn = gov.begin(height);
+    let mut attestations = Vec::new();
+
+    for (tx_index, tx) in transactions.iter().enumerate() {
+        let admitted = check_signer(&ledger_txn, tx).and_then(|_| ledger_txn.use_nonce(&tx.sender, tx.nonce));
+        if let Err(e) = admitted {
+            return Err(BlockExecutionError {
+                height,
+                tx_index,
+                tx_hash: tx.hash.clone(),
+                reason: e.to_string(),
+            });
+        }
+
+        let result = match &tx.data {
+            TransactionData::Transfer { to, amount } => {
+                ledger_txn.transfer(&tx.sender, to, *amount)
+            }
+            TransactionData::Stake { amount } => {
+                ledger_txn.stake(&tx.sender, *amount)
+            }
+            TransactionData::Unstake { amount } => {
+                ledger_txn.unstake(&tx.sender, *amount, height)
+            }
+            TransactionData::Delegate { validator, amount } => {
+                ledger_txn.delegate(&tx.sender, validator, *amount)
+            }
+            TransactionData::Undelegate { validator, amount } => {
+                ledger_txn.undelegate(&tx.sender, validator, *amount, height)
+            }
+            TransactionData::SetCommission { rate_bps } => {
+                ledger_txn.set_commission(&tx.sender, *rate_bps)
+            }
+            TransactionData::RotateKey { new_public_key, proof } => {
+                if tx.public_key.is_none() || !tx.verify() {
+                    Err(anyhow::anyhow!("Key rotation must be signed by the current key"))
+                } else if !tx.verify_rotation_proof(new_public_key, proof) {
+                    Err(anyhow::anyhow!("Key rotation proof does not verify for the new key"))
+                } else {
+                    ledger_txn.rotate_key(&tx.sender, new_public_key)
+                }
+            }
+            TransactionData::Propose { title, description, action } => {
+                gov_txn.create_proposal(&tx.sender, title, description, action.clone(), &ledger_txn).map(|_| ())
+            }
+            TransactionData::Vote { proposal_id, approve } => {
+                gov_txn.vote(*proposal_id, &tx.sender, *approve)
+            }
+            TransactionData::IntentAttestation { intent, proof_hash, result_hash, timestamp } => {
+                // Phase 7.5: Record intent completion on chain
+                log::info!("[CHAIN] ✓ Intent attested: '{}' at {} [proof: {}..., result: {}...]",
+                    intent, timestamp, &proof_hash[..8.min(proof_hash.len())], &result_hash[..8.min(result_hash.len())]);
+                attestations.push((tx.attestation_id(), AttestationRecord {
+                    sender: tx.sender.clone(),
+                    intent: intent.clone(),
+                    proof_hash: proof_hash.clone(),
+                    result_hash: result_hash.clone(),
+                    timestamp: *timestamp,
+                }));
+                Ok(())
+            }
+        };
+
+        if let Err(e) = result {
+            return Err(BlockExecutionError {
+                height,
+                tx_index,
+                tx_hash: tx.hash.clone(),
+                reason: e.to_string(),
+            });
+        }
+    }
+
+    // Proposals whose voting ends with this block are tallied after its
+    // transactions, so votes in the deadline block still count
+    gov_txn.tally(&mut ledger_txn);
+    ledger_txn.release_unbonded(height);
+    // Only bonded validators earn block rewards
+    if ledger_txn.get_account(validator).validator_stake() > 0 {
+        ledger_txn.distribute_reward(validator, BLOCK_REWARD);
+    }
+
+    Ok(StagedBlock {
+        accounts: ledger_txn.into_writes(),
+        governance: gov_txn.into_changes(),
+        attestations,
+    })
+}
+
+/// The transaction's key must be the one authorized for its sender
+fn check_signer(ledger_txn: &LedgerTxn<'_>, tx: &Transaction) -> anyhow::Result<()> {
+    let authorized = ledger_txn.get_account(&tx.sender).auth_key;
+    if tx.verify_signer(authorized.as_deref()) {
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!("'{}' did not sign with its authorized key", tx.sender))
+    }
+}
+
+fn leaf<T: Serialize>(key: &StateKey, value: &T) -> StateChange {
+    (key.path(), Some(serde_json::to_vec(value).unwrap_or_default()))
+}
+
+/// Consensus-relevant fields of a proposal
+///
+/// The AI analysis and local creation time differ between nodes, so they are
+/// kept out of the state root.
+fn proposal_commitment(proposal: &Proposal) -> serde_json::Value {
+    serde_json::json!({
+        "id": proposal.id,
+        "title": proposal.title,
+        "description": proposal.description,
+        "votes_for": proposal.votes_for,
+        "votes_against": proposal.votes_against,
+        "status": proposal.status,
+        "proposer": proposal.proposer,
+        "action": proposal.action,
+        "voting_ends": proposal.voting_ends,
+        "quorum": proposal.quorum,
+        "pass_threshold": proposal.pass_threshold,
+        "ballots": proposal.ballots,
+        "voting_power": proposal.voting_power,
+        "failure": proposal.failure,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    #[test]
+    fn test_error_display_names_index() {
+        let err = BlockExecutionError {
+            height: 7,
+            tx_index: 2,
+            tx_hash: "abc".to_string(),
+            reason: "Insufficient balance".to_string(),
+        };
+        assert_eq!(err.to_string(), "Block 7 rejected: transaction #2 (abc) failed: Insufficient balance");
+    }
+
+    #[test]
+    fn test_ledger_stage_is_isolated() {
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos();
+        let path = format!("/tmp/karana-test-exec-ledger-{}", nanos);
+        let mut ledger = Ledger::new(&path);
+        ledger.mint("alice", 100);
+
+        let mut txn = ledger.begin();
+        txn.transfer("alice", "bob", 60).unwrap();
+        assert!(txn.transfer("alice", "carol", 60).is_err());
+        drop(txn);
+
+        // Nothing was committed
+        assert_eq!(ledger.get_balance("alice"), 100);
+        assert!(ledger.try_get_account("bob").is_none());
+
+        // Commit + restore round-trips
+        let mut txn = ledger.begin();
+        txn.transfer("alice", "bob", 60).unwrap();
+        let writes = txn.into_writes();
+        let previous = ledger.commit(writes).unwrap();
+        assert_eq!(ledger.get_balance("bob"), 60);
+        ledger.restore(&previous).unwrap();
+        assert_eq!(ledger.get_balance("alice"), 100);
+        assert!(ledger.try_get_account("bob").is_none());
+
+        let _ = std::fs::remove_dir_all(&path);
+    }
+}
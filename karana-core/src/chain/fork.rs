@@ -0,0 +1,250 @@
+// Fork choice and side-chain tracking
+//
+// Blocks that do not extend the canonical tip are kept in a `ForkTree` keyed
+// by hash instead of being rejected. A block whose parent is known (on the
+// canonical chain or on a side chain) becomes part of a side branch; a block
+// whose parent is unknown waits in a bounded orphan pool until its ancestors
+// arrive through sync.
+//
+// Fork choice is longest chain: the branch with the greater height wins, and
+// equal heights go to the lower tip hash, so two nodes that saw the same
+// blocks in a different order still pick the same branch. Switching branches
+// reverts the canonical blocks back to the common ancestor (using their undo
+// records) and applies the side branch on top. Reorgs deeper than
+// `MAX_REORG_DEPTH` are refused.
+
+use super::Block;
+use std::collections::HashMap;
+
+/// Deepest reorganization a node will perform
+pub const MAX_REORG_DEPTH: u64 = 64;
+
+/// Maximum blocks held while waiting for their parents
+pub const MAX_ORPHANS: usize = 256;
+
+/// What importing a block did to the chain
+#[derive(Debug, Clone)]
+pub enum ImportOutcome {
+    /// Already on the canonical chain or a known side chain
+    Known,
+    /// Appended to the canonical tip
+    Extended,
+    /// Stored on a side branch that does not beat the canonical chain
+    SideChain,
+    /// Switched to a better branch. `reverted` holds the canonical blocks
+    /// that were rolled back, newest first; their transactions may need to
+    /// go back into the mempool.
+    Reorged { reverted: Vec<Block> },
+    /// Parent unknown; the missing ancestors have to be fetched from peers
+    Orphan { missing: String },
+}
+
+impl ImportOutcome {
+    /// Combine the outcome of a block with that of an orphan it unlocked
+    pub(crate) fn merge(self, next: ImportOutcome) -> ImportOutcome {
+        match (self, next) {
+            (ImportOutcome::Reorged { mut reverted }, ImportOutcome::Reorged { reverted: more }) => {
+                reverted.extend(more);
+                ImportOutcome::Reorged { reverted }
+            }
+            (reorg @ ImportOutcome::Reorged { .. }, _) | (_, reorg @ ImportOutcome::Reorged { .. }) => reorg,
+            (ImportOutcome::Extended, _) | (_, ImportOutcome::Extended) => ImportOutcome::Extended,
+            (ImportOutcome::SideChain, _) | (_, ImportOutcome::SideChain) => ImportOutcome::SideChain,
+            (first, _) => first,
+        }
+    }
+}
+
+/// Whether a branch tip beats the current canonical tip
+pub fn is_better(height: u64, hash: &str, tip_height: u64, tip_hash: &str) -> bool {
+    height > tip_height || (height == tip_height && hash < tip_hash)
+}
+
+/// Non-canonical blocks: side branches and orphans
+#[derive(Debug, Default)]
+pub struct ForkTree {
+    /// Side-chain blocks by hash (parent is canonical or another side block)
+    blocks: HashMap<String, Block>,
+    /// Blocks whose parent has not been seen yet, by hash
+    orphans: HashMap<String, Block>,
+}
+
+impl ForkTree {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.blocks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+
+    pub fn orphan_count(&self) -> usize {
+        self.orphans.len()
+    }
+
+    /// Known side or orphan block
+    pub fn contains(&self, hash: &str) -> bool {
+        self.blocks.contains_key(hash) || self.orphans.contains_key(hash)
+    }
+
+    pub fn get(&self, hash: &str) -> Option<&Block> {
+        self.blocks.get(hash)
+    }
+
+    pub fn insert(&mut self, block: Block) {
+        self.blocks.insert(block.hash.clone(), block);
+    }
+
+    pub fn remove(&mut self, hash: &str) -> Option<Block> {
+        self.blocks.remove(hash)
+    }
+
+    /// Park a block until its parent arrives. When the pool is full the
+    /// highest orphan (the one furthest from anything we know) is dropped.
+    pub fn insert_orphan(&mut self, block: Block) {
+        if self.orphans.len() >= MAX_ORPHANS {
+            let highest = self
+                .orphans
+                .values()
+                .max_by_key(|b| b.header.height)
+                .map(|b| b.hash.clone());
+            if let Some(hash) = highest {
+                self.orphans.remove(&hash);
+            }
+        }
+        self.orphans.insert(block.hash.clone(), block);
+    }
+
+    /// Remove and return the orphans waiting on `parent_hash`
+    pub fn take_orphans_of(&mut self, parent_hash: &str) -> Vec<Block> {
+        let hashes: Vec<String> = self
+            .orphans
+            .values()
+            .filter(|b| b.header.parent_hash == parent_hash)
+            .map(|b| b.hash.clone())
+            .collect();
+        hashes.iter().filter_map(|h| self.orphans.remove(h)).collect()
+    }
+
+    /// Earliest missing ancestor of an orphan chain ending at `hash`
+    pub fn missing_ancestor(&self, hash: &str) -> Option<String> {
+        let mut current = self.orphans.get(hash)?;
+        while let Some(parent) = self.orphans.get(&current.header.parent_hash) {
+            current = parent;
+        }
+        Some(current.header.parent_hash.clone())
+    }
+
+    /// Side blocks from the fork point up to `tip_hash`, oldest first. The
+    /// first block's parent is not in the tree (it should be canonical).
+    pub fn branch(&self, tip_hash: &str) -> Vec<Block> {
+        let mut branch = Vec::new();
+        let mut cursor = self.blocks.get(tip_hash);
+        while let Some(block) = cursor {
+            branch.push(block.clone());
+            cursor = self.blocks.get(&block.header.parent_hash);
+        }
+        branch.reverse();
+        branch
+    }
+
+    /// Drop a side block and every side block built on it
+    pub fn remove_with_descendants(&mut self, hash: &str) -> usize {
+        let mut stack = vec![hash.to_string()];
+        let mut removed = 0;
+        while let Some(hash) = stack.pop() {
+            if self.blocks.remove(&hash).is_some() {
+                removed += 1;
+            }
+            stack.extend(
+                self.blocks
+                    .values()
+                    .filter(|b| b.header.parent_hash == hash)
+                    .map(|b| b.hash.clone()),
+            );
+        }
+        removed
+    }
+
+    /// Forget side blocks at or below `height`; they can no longer win a
+    /// reorg within `MAX_REORG_DEPTH`
+    pub fn prune(&mut self, height: u64) {
+        self.blocks.retain(|_, b| b.header.height > height);
+        self.orphans.retain(|_, b| b.header.height > height);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(parent: &str, height: u64, validator: &str) -> Block {
+        Block::new(parent.to_string(), height, validator.to_string(), vec![])
+    }
+
+    #[test]
+    fn test_fork_choice_rule() {
+        assert!(is_better(5, "ff", 4, "00"));
+        assert!(!is_better(4, "00", 5, "ff"));
+        // Equal height: lower hash wins, and never against itself
+        assert!(is_better(5, "0a", 5, "0b"));
+        assert!(!is_better(5, "0b", 5, "0a"));
+        assert!(!is_better(5, "0a", 5, "0a"));
+    }
+
+    #[test]
+    fn test_branch_and_pruning() {
+        let root = "0".repeat(64);
+        let a1 = block(&root, 1, "a");
+        let a2 = block(&a1.hash, 2, "a");
+        let b2 = block(&a1.hash, 2, "b");
+        let a3 = block(&a2.hash, 3, "a");
+
+        let mut tree = ForkTree::new();
+        for b in [&a1, &a2, &b2, &a3] {
+            tree.insert(b.clone());
+        }
+
+        let branch: Vec<u64> = tree.branch(&a3.hash).iter().map(|b| b.header.height).collect();
+        assert_eq!(branch, vec![1, 2, 3]);
+        assert_eq!(tree.branch(&b2.hash).len(), 2);
+
+        // Dropping a2 takes a3 with it but leaves the sibling branch
+        assert_eq!(tree.remove_with_descendants(&a2.hash), 2);
+        assert!(tree.get(&b2.hash).is_some());
+
+        tree.prune(1);
+        assert!(tree.get(&a1.hash).is_none());
+        assert_eq!(tree.len(), 1);
+    }
+
+    #[test]
+    fn test_orphans() {
+        let root = "0".repeat(64);
+        let a1 = block(&root, 1, "a");
+        let a2 = block(&a1.hash, 2, "a");
+        let a3 = block(&a2.hash, 3, "a");
+
+        let mut tree = ForkTree::new();
+        tree.insert_orphan(a2.clone());
+        tree.insert_orphan(a3.clone());
+        assert_eq!(tree.missing_ancestor(&a3.hash), Some(a1.hash.clone()));
+
+        let children = tree.take_orphans_of(&a1.hash);
+        assert_eq!(children.len(), 1);
+        assert_eq!(children[0].hash, a2.hash);
+        assert_eq!(tree.orphan_count(), 1);
+    }
+
+    #[test]
+    fn test_merge_prefers_reorg() {
+        let merged = ImportOutcome::Extended.merge(ImportOutcome::Reorged { reverted: vec![] });
+        assert!(matches!(merged, ImportOutcome::Reorged { .. }));
+        let merged = ImportOutcome::Known.merge(ImportOutcome::SideChain);
+        assert!(matches!(merged, ImportOutcome::SideChain));
+    }
+}
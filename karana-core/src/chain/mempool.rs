@@ -0,0 +1,401 @@
+// Validated transaction pool
+//
+// Admission rules:
+// - the signature must verify and come from the sender's authorized key: the
+//   key its DID derives from, or the key it last rotated to
+// - each sender's nonces must be sequential: the next pending nonce is always
+//   `confirmed + pending + 1`, so replays (nonce already used on chain or
+//   already queued) and gaps are both rejected
+// - the pool and each sender are capped
+//
+// Selection for a block walks senders in arrival order of their next
+// transaction, so nonce order is kept per sender while the block is still
+// first-come-first-served across senders. Transactions that sit in the pool
+// longer than the TTL are evicted together with everything queued behind them.
+
+use super::{Block, Transaction};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BinaryHeap, HashMap, HashSet};
+use std::cmp::Reverse;
+use std::fmt;
+
+/// Mempool limits
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MempoolConfig {
+    /// Maximum transactions held across all senders
+    pub max_size: usize,
+    /// Maximum pending transactions per sender
+    pub max_per_sender: usize,
+    /// Seconds a transaction may wait before it is evicted
+    pub ttl_secs: u64,
+}
+
+impl Default for MempoolConfig {
+    fn default() -> Self {
+        Self {
+            max_size: 5_000,
+            max_per_sender: 64,
+            ttl_secs: 600,
+        }
+    }
+}
+
+/// Why a transaction was refused
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MempoolError {
+    /// Signature or sender DID check failed
+    BadSignature,
+    /// Nonce already used on chain or already queued
+    Replay { nonce: u64, expected: u64 },
+    /// Nonce skips ahead of the next expected one
+    NonceGap { nonce: u64, expected: u64 },
+    /// Sender already has `max_per_sender` pending
+    SenderLimit,
+    /// Pool already holds `max_size` transactions
+    PoolFull,
+}
+
+impl fmt::Display for MempoolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MempoolError::BadSignature => write!(f, "Invalid transaction signature"),
+            MempoolError::Replay { nonce, expected } => {
+                write!(f, "Replayed nonce {} (expected {})", nonce, expected)
+            }
+            MempoolError::NonceGap { nonce, expected } => {
+                write!(f, "Nonce gap: got {}, expected {}", nonce, expected)
+            }
+            MempoolError::SenderLimit => write!(f, "Too many pending transactions for sender"),
+            MempoolError::PoolFull => write!(f, "Mempool is full"),
+        }
+    }
+}
+
+impl std::error::Error for MempoolError {}
+
+#[derive(Debug, Clone)]
+struct PoolEntry {
+    tx: Transaction,
+    received_at: u64,
+    /// Arrival order, used to break timestamp ties
+    seq: u64,
+}
+
+/// Pending transactions keyed by sender and nonce
+#[derive(Debug, Default)]
+pub struct Mempool {
+    config: MempoolConfig,
+    by_sender: HashMap<String, BTreeMap<u64, PoolEntry>>,
+    len: usize,
+    next_seq: u64,
+}
+
+impl Mempool {
+    pub fn new(config: MempoolConfig) -> Self {
+        Self {
+            config,
+            ..Default::default()
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Nonce the sender's next transaction must carry, given the last nonce
+    /// confirmed on chain
+    pub fn next_nonce(&self, sender: &str, confirmed_nonce: u64) -> u64 {
+        let pending = self.by_sender.get(sender).map(|q| q.len()).unwrap_or(0) as u64;
+        confirmed_nonce + pending + 1
+    }
+
+    /// Validate and queue a transaction from a sender that never rotated keys
+    pub fn add(&mut self, tx: Transaction, confirmed_nonce: u64) -> Result<(), MempoolError> {
+        self.add_authorized(tx, confirmed_nonce, None)
+    }
+
+    /// Validate and queue a transaction, given the sender's key from its last
+    /// on-chain rotation (`Blockchain::authorized_key`)
+    pub fn add_authorized(&mut self, tx: Transaction, confirmed_nonce: u64, authorized_key: Option<&str>) -> Result<(), MempoolError> {
+        if !tx.verify_signer(authorized_key) {
+            return Err(MempoolError::BadSignature);
+        }
+        self.add_at(tx, confirmed_nonce, now_secs())
+    }
+
+    /// Queue after the signer check, which callers have already done
+    fn add_at(&mut self, tx: Transaction, confirmed_nonce: u64, now: u64) -> Result<(), MempoolError> {
+        if !tx.verify() {
+            return Err(MempoolError::BadSignature);
+        }
+
+        let expected = self.next_nonce(&tx.sender, confirmed_nonce);
+        if tx.nonce < expected {
+            return Err(MempoolError::Replay { nonce: tx.nonce, expected });
+        }
+        if tx.nonce > expected {
+            return Err(MempoolError::NonceGap { nonce: tx.nonce, expected });
+        }
+        if self.by_sender.get(&tx.sender).map(|q| q.len()).unwrap_or(0) >= self.config.max_per_sender {
+            return Err(MempoolError::SenderLimit);
+        }
+        if self.len >= self.config.max_size {
+            return Err(MempoolError::PoolFull);
+        }
+
+        let entry = PoolEntry {
+            received_at: now,
+            seq: self.next_seq,
+            tx,
+        };
+        self.next_seq += 1;
+        self.len += 1;
+        self.by_sender
+            .entry(entry.tx.sender.clone())
+            .or_default()
+            .insert(entry.tx.nonce, entry);
+        Ok(())
+    }
+
+    /// Up to `max` transactions in block order (per-sender nonce order,
+    /// otherwise first come first served). The pool is not modified.
+    pub fn select(&self, max: usize) -> Vec<Transaction> {
+        // Min-heap of each sender's next transaction by arrival
+        let queues: Vec<Vec<&PoolEntry>> = self
+            .by_sender
+            .values()
+            .map(|q| q.values().collect())
+            .collect();
+        let mut heap: BinaryHeap<Reverse<(u64, u64, usize, usize)>> = BinaryHeap::new();
+        for (qi, queue) in queues.iter().enumerate() {
+            if let Some(head) = queue.first() {
+                heap.push(Reverse((head.received_at, head.seq, qi, 0)));
+            }
+        }
+
+        let mut selected = Vec::new();
+        while let Some(Reverse((_, _, qi, pos))) = heap.pop() {
+            if selected.len() >= max {
+                break;
+            }
+            selected.push(queues[qi][pos].tx.clone());
+            if let Some(next) = queues[qi].get(pos + 1) {
+                heap.push(Reverse((next.received_at, next.seq, qi, pos + 1)));
+            }
+        }
+        selected
+    }
+
+    /// All pending transactions in block order
+    pub fn transactions(&self) -> Vec<Transaction> {
+        self.select(usize::MAX)
+    }
+
+    /// Drop a transaction and everything its sender queued after it (those
+    /// would now have a nonce gap)
+    pub fn remove(&mut self, tx: &Transaction) {
+        if let Some(queue) = self.by_sender.get_mut(&tx.sender) {
+            let dropped = queue.split_off(&tx.nonce);
+            self.len -= dropped.len();
+            if queue.is_empty() {
+                self.by_sender.remove(&tx.sender);
+            }
+        }
+    }
+
+    /// Forget transactions included in `block`, plus anything whose nonce
+    /// the chain has now consumed
+    pub fn on_block_committed(&mut self, block: &Block, confirmed_nonce: impl Fn(&str) -> u64) {
+        let senders: HashSet<&str> = block.transactions.iter().map(|tx| tx.sender.as_str()).collect();
+        for sender in senders {
+            let confirmed = confirmed_nonce(sender);
+            if let Some(queue) = self.by_sender.get_mut(sender) {
+                let keep = queue.split_off(&(confirmed + 1));
+                self.len -= queue.len();
+                *queue = keep;
+                if queue.is_empty() {
+                    self.by_sender.remove(sender);
+                }
+            }
+        }
+    }
+
+    /// Re-queue transactions from blocks a reorg rolled back and drop
+    /// anything the new chain already consumed. Every sender's queue is
+    /// rebuilt against its new confirmed nonce; transactions left behind a
+    /// nonce gap are dropped. Signers are not re-checked: every candidate was
+    /// admitted or executed before, and block execution checks the key
+    /// again. Returns how many transactions were re-queued from `reverted`.
+    pub fn on_reorg(&mut self, reverted: &[Block], confirmed_nonce: impl Fn(&str) -> u64) -> usize {
+        let now = now_secs();
+        let mut candidates: BTreeMap<(String, u64), (Transaction, u64, bool)> = BTreeMap::new();
+        for entry in self.by_sender.values().flat_map(|q| q.values()) {
+            candidates.insert((entry.tx.sender.clone(), entry.tx.nonce), (entry.tx.clone(), entry.received_at, false));
+        }
+        for tx in reverted.iter().flat_map(|b| b.transactions.iter()) {
+            candidates
+                .entry((tx.sender.clone(), tx.nonce))
+                .or_insert_with(|| (tx.clone(), now, true));
+        }
+
+        self.clear();
+        let mut requeued = 0;
+        let mut confirmed: HashMap<String, u64> = HashMap::new();
+        for ((sender, _), (tx, received_at, from_block)) in candidates {
+            let base = *confirmed
+                .entry(sender.clone())
+                .or_insert_with(|| confirmed_nonce(&sender));
+            if self.add_at(tx, base, received_at).is_ok() && from_block {
+                requeued += 1;
+            }
+        }
+        requeued
+    }
+
+    /// Evict transactions older than the TTL, along with anything queued
+    /// behind them from the same sender. Returns how many were dropped.
+    pub fn evict_stale(&mut self) -> usize {
+        self.evict_stale_at(now_secs())
+    }
+
+    fn evict_stale_at(&mut self, now: u64) -> usize {
+        let ttl = self.config.ttl_secs;
+        let before = self.len;
+        let stale: Vec<Transaction> = self
+            .by_sender
+            .values()
+            .filter_map(|queue| {
+                queue
+                    .values()
+                    .find(|e| now.saturating_sub(e.received_at) > ttl)
+                    .map(|e| e.tx.clone())
+            })
+            .collect();
+        for tx in &stale {
+            self.remove(tx);
+        }
+        before - self.len
+    }
+
+    pub fn clear(&mut self) {
+        self.by_sender.clear();
+        self.len = 0;
+    }
+}
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chain::{create_signed_transaction, TransactionData};
+    use crate::wallet::KaranaWallet;
+
+    fn legacy_tx(sender: &str, nonce: u64) -> Transaction {
+        Transaction {
+            sender: sender.to_string(),
+            data: TransactionData::Transfer { to: "bob".to_string(), amount: 1 },
+            signature: "sig".to_string(),
+            nonce,
+            public_key: None,
+            hash: format!("{}-{}", sender, nonce),
+            timestamp: 0,
+        }
+    }
+
+    #[test]
+    fn test_sequential_nonces() {
+        let mut pool = Mempool::new(MempoolConfig::default());
+        pool.add(legacy_tx("alice", 1), 0).unwrap();
+        pool.add(legacy_tx("alice", 2), 0).unwrap();
+
+        assert_eq!(pool.add(legacy_tx("alice", 2), 0), Err(MempoolError::Replay { nonce: 2, expected: 3 }));
+        assert_eq!(pool.add(legacy_tx("alice", 5), 0), Err(MempoolError::NonceGap { nonce: 5, expected: 3 }));
+        // Already confirmed on chain
+        assert_eq!(pool.add(legacy_tx("bob", 1), 4), Err(MempoolError::Replay { nonce: 1, expected: 5 }));
+        assert_eq!(pool.len(), 2);
+    }
+
+    #[test]
+    fn test_rejects_bad_signature() {
+        let wallet = KaranaWallet::generate("mempool-test").unwrap().wallet;
+        let mut tx = create_signed_transaction(&wallet, TransactionData::Stake { amount: 5 }, 1);
+        tx.sender = "did:karana:someone-else".to_string();
+
+        let mut pool = Mempool::new(MempoolConfig::default());
+        assert_eq!(pool.add(tx, 0), Err(MempoolError::BadSignature));
+
+        let tx = create_signed_transaction(&wallet, TransactionData::Stake { amount: 5 }, 1);
+        assert!(pool.add(tx, 0).is_ok());
+
+        // A key-derived DID cannot drop its key and fall back to legacy mode
+        let mut tx = legacy_tx(wallet.did(), 2);
+        assert_eq!(pool.add(tx.clone(), 1), Err(MempoolError::BadSignature));
+        tx.public_key = Some(wallet.public_key_hex());
+        assert_eq!(pool.add(tx, 1), Err(MempoolError::BadSignature));
+    }
+
+    #[test]
+    fn test_limits() {
+        let mut pool = Mempool::new(MempoolConfig { max_size: 3, max_per_sender: 2, ttl_secs: 60 });
+        pool.add(legacy_tx("alice", 1), 0).unwrap();
+        pool.add(legacy_tx("alice", 2), 0).unwrap();
+        assert_eq!(pool.add(legacy_tx("alice", 3), 0), Err(MempoolError::SenderLimit));
+        pool.add(legacy_tx("bob", 1), 0).unwrap();
+        assert_eq!(pool.add(legacy_tx("carol", 1), 0), Err(MempoolError::PoolFull));
+    }
+
+    #[test]
+    fn test_selection_order() {
+        let mut pool = Mempool::new(MempoolConfig::default());
+        pool.add_at(legacy_tx("alice", 1), 0, 10).unwrap();
+        pool.add_at(legacy_tx("bob", 1), 0, 11).unwrap();
+        pool.add_at(legacy_tx("alice", 2), 0, 12).unwrap();
+        pool.add_at(legacy_tx("bob", 2), 0, 13).unwrap();
+
+        let order: Vec<String> = pool.select(3).into_iter().map(|tx| tx.hash).collect();
+        assert_eq!(order, vec!["alice-1", "bob-1", "alice-2"]);
+    }
+
+    #[test]
+    fn test_block_commit_and_eviction() {
+        let mut pool = Mempool::new(MempoolConfig { ttl_secs: 60, ..Default::default() });
+        pool.add_at(legacy_tx("alice", 1), 0, 100).unwrap();
+        pool.add_at(legacy_tx("alice", 2), 0, 100).unwrap();
+        pool.add_at(legacy_tx("bob", 1), 0, 100).unwrap();
+        pool.add_at(legacy_tx("bob", 2), 0, 150).unwrap();
+
+        let block = Block::new("0".repeat(64), 1, "v".to_string(), vec![legacy_tx("alice", 1)]);
+        pool.on_block_committed(&block, |_| 1);
+        assert_eq!(pool.len(), 3);
+        assert_eq!(pool.next_nonce("alice", 1), 3);
+
+        // bob-1 is stale, so bob-2 goes with it; alice-2 is stale too
+        assert_eq!(pool.evict_stale_at(200), 3);
+        assert!(pool.is_empty());
+    }
+
+    #[test]
+    fn test_reorg_requeues_reverted() {
+        let mut pool = Mempool::new(MempoolConfig::default());
+        pool.add(legacy_tx("alice", 2), 1).unwrap();
+        pool.add(legacy_tx("bob", 1), 0).unwrap();
+
+        // alice-1 was in a reverted block; bob-1 made it into the new chain
+        let reverted = Block::new("0".repeat(64), 1, "v".to_string(), vec![legacy_tx("alice", 1)]);
+        let requeued = pool.on_reorg(&[reverted], |did| if did == "bob" { 1 } else { 0 });
+
+        assert_eq!(requeued, 1);
+        let order: Vec<String> = pool.transactions().into_iter().map(|tx| tx.hash).collect();
+        assert_eq!(order, vec!["alice-1", "alice-2"]);
+    }
+}
@@ -0,0 +1,1158 @@
+use serde::{Serialize, Deserialize};
+use sha2::{Sha256, Digest};
+use std::sync::{Arc, Mutex};
+use crate::economy::Ledger;
+use crate::gov::{Governance, ProposalAction};
+use std::collections::HashMap;
+use anyhow::Result;
+
+pub mod store;
+pub mod state;
+pub mod exec;
+pub mod mempool;
+pub mod fork;
+pub mod sync;
+
+pub use store::BlockStore;
+pub use state::{StateKey, StateProof, StateTrie, AttestationRecord};
+pub use exec::{BlockExecutionError, BlockUndo};
+pub use mempool::{Mempool, MempoolConfig, MempoolError};
+pub use fork::{ForkTree, ImportOutcome};
+pub use sync::{SyncProgress, SyncRequest, SyncResponse};
+
+/// Prefix of DIDs derived from a signing key (see `Transaction::verify_sender_did`)
+const DID_PREFIX: &str = "did:karana:";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum TransactionData {
+    Transfer { to: String, amount: u128 },
+    Stake { amount: u128 },
+    Propose {
+        title: String,
+        description: String,
+        /// Executed if the proposal passes (omitted for signal proposals)
+        #[serde(default, skip_serializing_if = "ProposalAction::is_signal")]
+        action: ProposalAction,
+    },
+    Vote { proposal_id: u64, approve: bool },
+    /// Start unbonding self-staked tokens
+    Unstake { amount: u128 },
+    Delegate { validator: String, amount: u128 },
+    /// Start unbonding a delegation
+    Undelegate { validator: String, amount: u128 },
+    /// Validator commission on rewards, in basis points
+    SetCommission { rate_bps: u16 },
+    /// Authorize a new signing key for the sender's DID. Must be signed by
+    /// the current key; `proof` is the new key's signature over
+    /// `key_rotation_message`.
+    RotateKey { new_public_key: String, proof: String },
+    /// Phase 7.5: Intent attestation with ZK proof
+    IntentAttestation { 
+        intent: String, 
+        proof_hash: String,  // Hash of ZK proof
+        result_hash: String, // Hash of action result
+        timestamp: u64,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Transaction {
+    pub sender: String,
+    pub data: TransactionData,
+    pub signature: String, // Hex encoded Ed25519 signature
+    pub nonce: u64,
+    /// Optional: public key for verification (hex encoded)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub public_key: Option<String>,
+    /// Transaction hash (computed)
+    #[serde(default)]
+    pub hash: String,
+    /// Timestamp
+    #[serde(default)]
+    pub timestamp: u64,
+}
+
+/// Transaction with additional metadata for querying
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransactionWithMeta {
+    pub hash: String,
+    pub from: String,
+    pub data: TransactionData,
+    pub timestamp: u64,
+}
+
+impl TransactionWithMeta {
+    /// Get recipient if this is a transfer
+    pub fn get_recipient(&self) -> Option<String> {
+        match &self.data {
+            TransactionData::Transfer { to, .. } => Some(to.clone()),
+            _ => None,
+        }
+    }
+    
+    /// Get amount if this is a transfer or stake
+    pub fn get_amount(&self) -> Option<u64> {
+        match &self.data {
+            TransactionData::Transfer { amount, .. } => Some(*amount as u64),
+            TransactionData::Stake { amount } | TransactionData::Unstake { amount } => Some(*amount as u64),
+            TransactionData::Delegate { amount, .. } | TransactionData::Undelegate { amount, .. } => Some(*amount as u64),
+            _ => None,
+        }
+    }
+}
+
+impl Transaction {
+    /// Create a new transaction with computed hash
+    pub fn new(sender: String, data: TransactionData, nonce: u64, signature: Vec<u8>) -> Self {
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        
+        let mut tx = Self {
+            sender,
+            data,
+            signature: hex::encode(&signature),
+            nonce,
+            public_key: None,
+            hash: String::new(),
+            timestamp,
+        };
+        
+        // Compute hash
+        let hash_data = serde_json::to_vec(&tx).unwrap_or_default();
+        tx.hash = hex::encode(Sha256::digest(&hash_data));
+        tx
+    }
+    
+    /// Get recipient if this is a transfer
+    pub fn get_recipient(&self) -> Option<String> {
+        match &self.data {
+            TransactionData::Transfer { to, .. } => Some(to.clone()),
+            _ => None,
+        }
+    }
+    
+    /// Get amount if this is a transfer or stake
+    pub fn get_amount(&self) -> Option<u64> {
+        match &self.data {
+            TransactionData::Transfer { amount, .. } => Some(*amount as u64),
+            TransactionData::Stake { amount } | TransactionData::Unstake { amount } => Some(*amount as u64),
+            TransactionData::Delegate { amount, .. } | TransactionData::Undelegate { amount, .. } => Some(*amount as u64),
+            _ => None,
+        }
+    }
+    
+    /// Get the message bytes that should be signed
+    pub fn signing_message(&self) -> Vec<u8> {
+        let signing_data = serde_json::json!({
+            "sender": self.sender,
+            "data": self.data,
+            "nonce": self.nonce
+        });
+        serde_json::to_vec(&signing_data).unwrap_or_default()
+    }
+    
+    /// Stable identifier for state keyed by this transaction (e.g. attestations)
+    pub fn attestation_id(&self) -> String {
+        hex::encode(Sha256::digest(self.signing_message()))
+    }
+    
+    /// Verify the transaction signature
+    /// 
+    /// If public_key is provided, performs real Ed25519 verification.
+    /// Otherwise falls back to legacy mode (signature non-empty).
+    pub fn verify(&self) -> bool {
+        // Empty signature is always invalid
+        if self.signature.is_empty() {
+            return false;
+        }
+        
+        // If we have a public key, do real verification
+        if let Some(ref pubkey_hex) = self.public_key {
+            return self.verify_ed25519(pubkey_hex);
+        }
+        
+        // Legacy mode: accept any non-empty signature
+        // (for backwards compatibility with old transactions)
+        true
+    }
+    
+    /// Perform real Ed25519 signature verification
+    fn verify_ed25519(&self, pubkey_hex: &str) -> bool {
+        use ed25519_dalek::{VerifyingKey, Signature, Verifier};
+        
+        // Decode public key
+        let pubkey_bytes = match hex::decode(pubkey_hex) {
+            Ok(bytes) => bytes,
+            Err(_) => return false,
+        };
+        
+        if pubkey_bytes.len() != 32 {
+            return false;
+        }
+        
+        let pubkey_array: [u8; 32] = match pubkey_bytes.try_into() {
+            Ok(arr) => arr,
+            Err(_) => return false,
+        };
+        
+        let verifying_key = match VerifyingKey::from_bytes(&pubkey_array) {
+            Ok(key) => key,
+            Err(_) => return false,
+        };
+        
+        // Decode signature
+        let sig_bytes = match hex::decode(&self.signature) {
+            Ok(bytes) => bytes,
+            Err(_) => return false,
+        };
+        
+        if sig_bytes.len() != 64 {
+            return false;
+        }
+        
+        let sig_array: [u8; 64] = match sig_bytes.try_into() {
+            Ok(arr) => arr,
+            Err(_) => return false,
+        };
+        
+        let signature = match Signature::from_bytes(&sig_array) {
+            sig => sig,
+        };
+        
+        // Get message to verify
+        let message = self.signing_message();
+        
+        // Verify
+        verifying_key.verify(&message, &signature).is_ok()
+    }
+    
+    /// Verify that the sender DID matches the public key
+    /// 
+    /// DIDs are of form: did:karana:<base58(sha256(pubkey)[0:20])>
+    pub fn verify_sender_did(&self) -> bool {
+        if let Some(ref pubkey_hex) = self.public_key {
+            // Decode public key
+            let pubkey_bytes = match hex::decode(pubkey_hex) {
+                Ok(bytes) => bytes,
+                Err(_) => return false,
+            };
+            
+            // Compute expected DID
+            let hash = Sha256::digest(&pubkey_bytes);
+            let did_suffix = bs58::encode(&hash[..20]).into_string();
+            let expected_did = format!("{}{}", DID_PREFIX, did_suffix);
+            
+            self.sender == expected_did
+        } else {
+            // Can't verify without public key
+            true
+        }
+    }
+    
+    /// Verify that the transaction is signed by the key its sender has
+    /// authorized: once a DID has rotated keys (`authorized_key`) only that
+    /// key may sign for it, otherwise the DID must derive from the key
+    pub fn verify_signer(&self, authorized_key: Option<&str>) -> bool {
+        match (authorized_key, &self.public_key) {
+            (Some(authorized), Some(key)) => key.eq_ignore_ascii_case(authorized),
+            (Some(_), None) => false,
+            (None, Some(_)) => self.verify_sender_did(),
+            // A key-derived DID always has a key to sign with; only legacy
+            // senders may go without one
+            (None, None) => !self.sender.starts_with(DID_PREFIX),
+        }
+    }
+    
+    /// Verify the new key's consent to a `RotateKey` transaction
+    pub fn verify_rotation_proof(&self, new_public_key: &str, proof: &str) -> bool {
+        let (Ok(public_key), Ok(signature)) = (hex::decode(new_public_key), hex::decode(proof)) else {
+            return false;
+        };
+        let message = key_rotation_message(&self.sender, new_public_key, self.nonce);
+        crate::wallet::KaranaWallet::verify_signature(&public_key, &message, &signature)
+    }
+}
+
+/// Message the new key signs to accept a rotation. It names the DID and the
+/// rotating transaction's nonce so the proof cannot be replayed elsewhere.
+pub fn key_rotation_message(did: &str, new_public_key: &str, nonce: u64) -> Vec<u8> {
+    let message = serde_json::json!({
+        "rotate_key": did,
+        "new_public_key": new_public_key.to_ascii_lowercase(),
+        "nonce": nonce
+    });
+    serde_json::to_vec(&message).unwrap_or_default()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockHeader {
+    pub parent_hash: String,
+    pub height: u64,
+    pub timestamp: u64,
+    pub state_root: String,
+    pub validator: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Block {
+    pub header: BlockHeader,
+    pub transactions: Vec<Transaction>,
+    pub hash: String,
+}
+
+impl Block {
+    pub fn new(parent_hash: String, height: u64, validator: String, transactions: Vec<Transaction>) -> Self {
+        Self::with_state_root(parent_hash, height, validator, transactions, String::new())
+    }
+
+    /// Create a block committing to the post-execution state root
+    pub fn with_state_root(parent_hash: String, height: u64, validator: String, transactions: Vec<Transaction>, state_root: String) -> Self {
+        let mut block = Self {
+            header: BlockHeader {
+                parent_hash,
+                height,
+                timestamp: std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs(),
+                state_root,
+                validator,
+            },
+            transactions,
+            hash: String::new(),
+        };
+        block.hash = block.calculate_hash();
+        block
+    }
+
+    /// Genesis block, identical on every node so peers share an ancestor
+    pub fn genesis() -> Self {
+        let mut block = Self::new("0".repeat(64), 0, "genesis".to_string(), vec![]);
+        block.header.timestamp = 0;
+        block.hash = block.calculate_hash();
+        block
+    }
+
+    pub fn calculate_hash(&self) -> String {
+        let data = serde_json::to_vec(&self.header).unwrap();
+        let mut hasher = Sha256::new();
+        hasher.update(&data);
+        for tx in &self.transactions {
+            hasher.update(serde_json::to_vec(tx).unwrap());
+        }
+        hex::encode(hasher.finalize())
+    }
+
+    pub fn validate(&self, parent_hash: &str) -> Result<()> {
+        if self.header.parent_hash != parent_hash {
+            return Err(anyhow::anyhow!("Invalid parent hash"));
+        }
+        if self.calculate_hash() != self.hash {
+            return Err(anyhow::anyhow!("Invalid block hash"));
+        }
+        for tx in &self.transactions {
+            if !tx.verify() {
+                return Err(anyhow::anyhow!("Invalid transaction signature"));
+            }
+        }
+        Ok(())
+    }
+}
+
+pub struct Blockchain {
+    ledger: Arc<Mutex<Ledger>>,
+    gov: Arc<Mutex<Governance>>,
+    /// In-memory view of the canonical chain
+    blocks: Mutex<Vec<Block>>,
+    /// Persistent block store (None for ephemeral chains)
+    store: Option<BlockStore>,
+    /// Authenticated state committed in each block header
+    state: Mutex<StateTrie>,
+    /// Undo records for applied blocks not yet handed to the store
+    /// (ephemeral chains keep them all)
+    undo: Mutex<HashMap<String, BlockUndo>>,
+    /// Side branches and orphans. Held for the whole of an import or block
+    /// production so a reorg never interleaves with either.
+    forks: Mutex<ForkTree>,
+}
+
+impl Blockchain {
+    /// Create an ephemeral, in-memory chain starting from a fresh genesis
+    pub fn new(ledger: Arc<Mutex<Ledger>>, gov: Arc<Mutex<Governance>>) -> Self {
+        Self { 
+            ledger, 
+            gov,
+            blocks: Mutex::new(vec![Self::genesis()]),
+            store: None,
+            state: Mutex::new(StateTrie::new()),
+            undo: Mutex::new(HashMap::new()),
+            forks: Mutex::new(ForkTree::new()),
+        }
+    }
+
+    /// Open a persistent chain at `path`, reloading and re-validating
+    /// every stored block. A torn write at the tip is rolled back to the
+    /// last good block; an empty store is seeded with a genesis block.
+    pub fn open(path: &str, ledger: Arc<Mutex<Ledger>>, gov: Arc<Mutex<Governance>>) -> Result<Self> {
+        let store = BlockStore::open(path)?;
+        let report = store.load()?;
+
+        if let Some(height) = report.rolled_back_from {
+            log::warn!("[CHAIN] Rolled back torn blocks from height {} (tip now {})",
+                height, report.blocks.len().saturating_sub(1));
+            // The store already restored its state leaves; bring the ledger
+            // and governance back to the same height
+            let mut gov = gov.lock().unwrap();
+            let mut ledger = ledger.lock().unwrap();
+            for undo in &report.undone {
+                ledger.restore(&undo.accounts)?;
+                gov.restore(&undo.governance)?;
+            }
+        }
+
+        let blocks = if report.blocks.is_empty() {
+            let genesis = Self::genesis();
+            store.commit_block(&genesis, &[], None)?;
+            vec![genesis]
+        } else {
+            report.blocks
+        };
+        log::info!("[CHAIN] ✓ Loaded {} blocks from {}", blocks.len(), path);
+
+        let mut state = StateTrie::from_leaves(store.load_state()?);
+        if let Some(tip) = blocks.last() {
+            let root = state.root_hex();
+            if !tip.header.state_root.is_empty() && tip.header.state_root != root {
+                return Err(anyhow::anyhow!(
+                    "State root {} does not match tip #{} header {}; the chain store at {} needs a resync",
+                    root, tip.header.height, tip.header.state_root, path
+                ));
+            }
+        }
+
+        Ok(Self {
+            ledger,
+            gov,
+            blocks: Mutex::new(blocks),
+            store: Some(store),
+            state: Mutex::new(state),
+            undo: Mutex::new(HashMap::new()),
+            forks: Mutex::new(ForkTree::new()),
+        })
+    }
+
+    fn genesis() -> Block {
+        Block::genesis()
+    }
+
+    /// Whether blocks survive a restart
+    pub fn is_persistent(&self) -> bool {
+        self.store.is_some()
+    }
+    
+    /// Get current chain height
+    pub fn height(&self) -> u64 {
+        let blocks = self.blocks.lock().unwrap();
+        blocks.last().map(|b| b.header.height).unwrap_or(0)
+    }
+    
+    /// Get the latest block
+    pub fn latest_block(&self) -> Block {
+        let blocks = self.blocks.lock().unwrap();
+        blocks.last().cloned().unwrap_or_else(Self::genesis)
+    }
+    
+    /// Get block by height
+    pub fn get_block(&self, height: u64) -> Option<Block> {
+        let blocks = self.blocks.lock().unwrap();
+        blocks.iter().find(|b| b.header.height == height).cloned()
+    }
+
+    /// Get a canonical block by hash
+    pub fn get_block_by_hash(&self, hash: &str) -> Option<Block> {
+        let blocks = self.blocks.lock().unwrap();
+        blocks.iter().rev().find(|b| b.hash == hash).cloned()
+    }
+
+    /// Hash of the canonical block at `height`
+    fn canonical_hash(&self, height: u64) -> Option<String> {
+        let blocks = self.blocks.lock().unwrap();
+        blocks.get(height as usize).map(|b| b.hash.clone())
+    }
+
+    /// Block locator for sync requests: canonical hashes from the tip back,
+    /// the ten most recent one by one and then doubling the step, always
+    /// ending at genesis
+    pub fn locator(&self) -> Vec<String> {
+        let blocks = self.blocks.lock().unwrap();
+        let mut locator = Vec::new();
+        let mut step = 1;
+        let mut index = blocks.len().saturating_sub(1);
+        while index > 0 {
+            locator.push(blocks[index].hash.clone());
+            if locator.len() >= 10 {
+                step *= 2;
+            }
+            index = index.saturating_sub(step);
+        }
+        if let Some(genesis) = blocks.first() {
+            locator.push(genesis.hash.clone());
+        }
+        locator
+    }
+
+    /// Up to `max` canonical blocks following the most recent locator hash
+    /// that is on our chain (after genesis if none is)
+    pub fn blocks_after(&self, locator: &[String], max: usize) -> Vec<Block> {
+        let blocks = self.blocks.lock().unwrap();
+        let start = locator
+            .iter()
+            .find_map(|hash| blocks.iter().rposition(|b| &b.hash == hash))
+            .map(|i| i + 1)
+            .unwrap_or(1);
+        blocks.iter().skip(start).take(max).cloned().collect()
+    }
+
+    /// Number of side-chain blocks being tracked
+    pub fn side_chain_len(&self) -> usize {
+        self.forks.lock().unwrap().len()
+    }
+
+    /// Add a new block to the chain
+    ///
+    /// For persistent chains the block is committed to disk before it
+    /// becomes visible in memory.
+    pub fn add_block(&self, block: Block) -> Result<()> {
+        let mut blocks = self.blocks.lock().unwrap();
+        let expected_height = blocks.last().map(|b| b.header.height + 1).unwrap_or(0);
+        
+        if block.header.height != expected_height {
+            return Err(anyhow::anyhow!("Invalid block height: expected {}, got {}", expected_height, block.header.height));
+        }
+        if let Some(parent) = blocks.last() {
+            block.validate(&parent.hash)?;
+        }
+        
+        if let Some(store) = &self.store {
+            let changes = self.state.lock().unwrap().take_dirty();
+            let undo = self.undo.lock().unwrap().remove(&block.hash);
+            store.commit_block(&block, &changes, undo.as_ref())?;
+        }
+        blocks.push(block);
+        Ok(())
+    }
+
+    /// Import a block received from a peer
+    ///
+    /// Unlike `add_block`, the block does not have to extend the tip: blocks
+    /// on competing branches are tracked as side chains and the chain
+    /// reorganizes onto a branch once it wins fork choice (see `fork`).
+    /// Blocks with an unknown parent are held as orphans and connected
+    /// when their ancestors arrive.
+    pub fn import_block(&self, block: Block) -> Result<ImportOutcome> {
+        let mut forks = self.forks.lock().unwrap();
+        let hash = block.hash.clone();
+        let mut outcome = self.import_locked(&mut forks, block)?;
+
+        if !matches!(outcome, ImportOutcome::Orphan { .. }) {
+            let mut waiting = forks.take_orphans_of(&hash);
+            while let Some(child) = waiting.pop() {
+                let child_hash = child.hash.clone();
+                match self.import_locked(&mut forks, child) {
+                    Ok(next) => {
+                        outcome = outcome.merge(next);
+                        waiting.extend(forks.take_orphans_of(&child_hash));
+                    }
+                    Err(e) => log::warn!("[CHAIN] Dropping orphan {}: {}", child_hash, e),
+                }
+            }
+        }
+        Ok(outcome)
+    }
+
+    fn import_locked(&self, forks: &mut ForkTree, block: Block) -> Result<ImportOutcome> {
+        // Hash and signatures; the parent link is checked below
+        block.validate(&block.header.parent_hash)?;
+
+        let height = block.header.height;
+        if height == 0 || forks.contains(&block.hash) || self.canonical_hash(height).as_deref() == Some(block.hash.as_str()) {
+            return Ok(ImportOutcome::Known);
+        }
+
+        let tip = self.latest_block();
+        if block.header.parent_hash == tip.hash {
+            self.connect_block(block)?;
+            return Ok(ImportOutcome::Extended);
+        }
+
+        let parent_height = if self.canonical_hash(height - 1).as_deref() == Some(block.header.parent_hash.as_str()) {
+            Some(height - 1)
+        } else {
+            forks.get(&block.header.parent_hash).map(|p| p.header.height)
+        };
+        match parent_height {
+            Some(parent_height) if parent_height + 1 == height => {}
+            Some(parent_height) => {
+                return Err(anyhow::anyhow!("Invalid block height: parent is #{}, got {}", parent_height, height));
+            }
+            None => {
+                let hash = block.hash.clone();
+                forks.insert_orphan(block);
+                let missing = forks.missing_ancestor(&hash).unwrap_or_default();
+                return Ok(ImportOutcome::Orphan { missing });
+            }
+        }
+
+        let hash = block.hash.clone();
+        forks.insert(block);
+        self.choose_fork(forks, &hash)
+    }
+
+    /// Reorganize onto the side branch ending at `side_tip` if it beats the
+    /// canonical chain. On failure the original chain is put back and the
+    /// offending block is dropped together with its descendants.
+    fn choose_fork(&self, forks: &mut ForkTree, side_tip: &str) -> Result<ImportOutcome> {
+        let branch = forks.branch(side_tip);
+        let (first, last) = match (branch.first(), branch.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return Ok(ImportOutcome::SideChain),
+        };
+        let tip = self.latest_block();
+        if !fork::is_better(last.header.height, &last.hash, tip.header.height, &tip.hash) {
+            return Ok(ImportOutcome::SideChain);
+        }
+
+        // The branch must hang off the canonical chain
+        let fork_height = first.header.height - 1;
+        if self.canonical_hash(fork_height).as_deref() != Some(first.header.parent_hash.as_str()) {
+            return Ok(ImportOutcome::SideChain);
+        }
+        let depth = tip.header.height - fork_height;
+        if depth > fork::MAX_REORG_DEPTH {
+            log::warn!("[CHAIN] ⚠️ Ignoring branch {} ({} blocks deep, limit {})", last.hash, depth, fork::MAX_REORG_DEPTH);
+            return Ok(ImportOutcome::SideChain);
+        }
+
+        let mut reverted = Vec::new();
+        while self.height() > fork_height {
+            match self.revert_tip() {
+                Ok(block) => reverted.push(block),
+                Err(e) => {
+                    log::warn!("[CHAIN] Could not revert to fork point #{}, restoring previous chain: {}", fork_height, e);
+                    for old in reverted.iter().rev() {
+                        self.connect_block(old.clone())?;
+                    }
+                    return Err(e);
+                }
+            }
+        }
+
+        for (applied, block) in branch.iter().enumerate() {
+            if let Err(e) = self.connect_block(block.clone()) {
+                log::warn!("[CHAIN] Branch block #{} ({}) is invalid, restoring previous chain: {}",
+                    block.header.height, block.hash, e);
+                for _ in 0..applied {
+                    self.revert_tip()?;
+                }
+                for old in reverted.iter().rev() {
+                    self.connect_block(old.clone())?;
+                }
+                forks.remove_with_descendants(&block.hash);
+                return Err(e);
+            }
+        }
+
+        for block in &branch {
+            forks.remove(&block.hash);
+        }
+        for block in &reverted {
+            forks.insert(block.clone());
+        }
+        forks.prune(self.height().saturating_sub(fork::MAX_REORG_DEPTH));
+
+        log::info!("[CHAIN] 🔀 Reorganized: reverted {} blocks, new tip #{} ({})",
+            reverted.len(), last.header.height, last.hash);
+        Ok(ImportOutcome::Reorged { reverted })
+    }
+
+    /// Apply a block that extends the tip and append it. If appending
+    /// fails after execution, the applied changes are undone.
+    fn connect_block(&self, block: Block) -> Result<()> {
+        self.apply_block(&block)?;
+        self.append_applied(block)
+    }
+
+    /// Append a block whose changes were already committed, undoing them
+    /// if the block is rejected or cannot be stored
+    fn append_applied(&self, block: Block) -> Result<()> {
+        let hash = block.hash.clone();
+        if let Err(e) = self.add_block(block) {
+            if let Some(undo) = self.undo.lock().unwrap().remove(&hash) {
+                self.restore_undo(&undo)?;
+                // Nothing reached the store, so nothing is left to write
+                self.state.lock().unwrap().take_dirty();
+            }
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Get transactions for a specific DID
+    pub fn get_transactions_for(&self, did: &str, limit: usize) -> Vec<TransactionWithMeta> {
+        let blocks = self.blocks.lock().unwrap();
+        let mut result = Vec::new();
+        
+        for block in blocks.iter().rev() {
+            for tx in &block.transactions {
+                if tx.sender == did || tx.get_recipient().as_deref() == Some(did) {
+                    result.push(TransactionWithMeta {
+                        hash: format!("0x{}", hex::encode(sha2::Sha256::digest(serde_json::to_vec(tx).unwrap_or_default()))[..16].to_string()),
+                        from: tx.sender.clone(),
+                        data: tx.data.clone(),
+                        timestamp: block.header.timestamp,
+                    });
+                    if result.len() >= limit {
+                        return result;
+                    }
+                }
+            }
+        }
+        
+        result
+    }
+
+    /// Execute a block's transactions and check the resulting state root
+    ///
+    /// Application is all-or-nothing: transactions run against a staged
+    /// snapshot of the ledger and governance, and nothing is committed unless
+    /// every one succeeds (and the state root matches). A failing transaction
+    /// is reported as a `BlockExecutionError` carrying its index. Blocks
+    /// without a state root (legacy producers) are applied unchecked.
+    pub fn apply_block(&self, block: &Block) -> Result<()> {
+        let mut gov = self.gov.lock().unwrap();
+        let mut ledger = self.ledger.lock().unwrap();
+
+        let staged = exec::execute(&ledger, &gov, &block.transactions, block.header.height, &block.header.validator)?;
+        let expected = Some(block.header.state_root.as_str()).filter(|r| !r.is_empty());
+        let (_, mut undo) = self.commit_staged(&mut ledger, &mut gov, staged, expected)?;
+
+        undo.height = block.header.height;
+        undo.hash = block.hash.clone();
+        self.undo.lock().unwrap().insert(block.hash.clone(), undo);
+        Ok(())
+    }
+
+    /// Execute `transactions` on top of the tip and append a block that
+    /// commits to the resulting state root
+    ///
+    /// Transactions that fail are left out of the block rather than
+    /// failing it, so one bad transfer cannot stall production.
+    pub fn produce_block(&self, validator: &str, transactions: Vec<Transaction>) -> Result<Block> {
+        self.produce(validator, transactions, true)
+    }
+
+    /// Like `produce_block`, but a failing transaction fails the whole block
+    /// (with its `BlockExecutionError`) and nothing is committed
+    pub fn produce_block_exact(&self, validator: &str, transactions: Vec<Transaction>) -> Result<Block> {
+        self.produce(validator, transactions, false)
+    }
+
+    fn produce(&self, validator: &str, mut transactions: Vec<Transaction>, drop_failing: bool) -> Result<Block> {
+        let _forks = self.forks.lock().unwrap();
+        let parent = self.latest_block();
+        let height = parent.header.height + 1;
+
+        // `Block::validate` rejects bad signatures, so settle them before
+        // anything is committed
+        if drop_failing {
+            transactions.retain(|tx| {
+                let valid = tx.verify();
+                if !valid {
+                    log::warn!("[CHAIN] Dropping transaction with invalid signature from {}", tx.sender);
+                }
+                valid
+            });
+        } else if let Some(i) = transactions.iter().position(|tx| !tx.verify()) {
+            return Err(anyhow::anyhow!("Transaction {} has an invalid signature", i));
+        }
+
+        let block = {
+            let mut gov = self.gov.lock().unwrap();
+            let mut ledger = self.ledger.lock().unwrap();
+
+            let staged = loop {
+                match exec::execute(&ledger, &gov, &transactions, height, validator) {
+                    Ok(staged) => break staged,
+                    Err(e) if drop_failing => {
+                        log::warn!("[CHAIN] Dropping transaction from block: {}", e);
+                        transactions.remove(e.tx_index);
+                    }
+                    Err(e) => return Err(e.into()),
+                }
+            };
+
+            let (state_root, mut undo) = self.commit_staged(&mut ledger, &mut gov, staged, None)?;
+            let block = Block::with_state_root(parent.hash.clone(), height, validator.to_string(), transactions, state_root);
+            undo.height = height;
+            undo.hash = block.hash.clone();
+            self.undo.lock().unwrap().insert(block.hash.clone(), undo);
+            block
+        };
+
+        self.append_applied(block.clone())?;
+        Ok(block)
+    }
+
+    /// Write a staged block to the trie, ledger and governance.
+    ///
+    /// The trie is updated first so the root can be checked against
+    /// `expected_root` before anything reaches disk. Returns the new root and
+    /// an undo record (height/hash left for the caller to fill in).
+    fn commit_staged(
+        &self,
+        ledger: &mut Ledger,
+        gov: &mut Governance,
+        staged: exec::StagedBlock,
+        expected_root: Option<&str>,
+    ) -> Result<(String, BlockUndo)> {
+        let mut state = self.state.lock().unwrap();
+        let prior_state = state.apply_changes(&staged.state_changes());
+        let root = state.root_hex();
+
+        if let Some(expected) = expected_root {
+            if expected != root {
+                state.apply_changes(&prior_state);
+                return Err(anyhow::anyhow!("State root mismatch: header {}, computed {}", expected, root));
+            }
+        }
+
+        let accounts = match ledger.commit(staged.accounts) {
+            Ok(previous) => previous,
+            Err(e) => {
+                state.apply_changes(&prior_state);
+                return Err(e);
+            }
+        };
+        let governance = match gov.commit(staged.governance) {
+            Ok(undo) => undo,
+            Err(e) => {
+                let _ = ledger.restore(&accounts);
+                state.apply_changes(&prior_state);
+                return Err(e);
+            }
+        };
+
+        Ok((root, BlockUndo {
+            height: 0,
+            hash: String::new(),
+            accounts,
+            governance,
+            state: prior_state,
+        }))
+    }
+
+    /// Revert the tip block, restoring the ledger, governance and state trie
+    /// to their values before it was applied. Used when reorganizing onto a
+    /// competing branch.
+    pub fn revert_tip(&self) -> Result<Block> {
+        let mut blocks = self.blocks.lock().unwrap();
+        if blocks.len() < 2 {
+            return Err(anyhow::anyhow!("Cannot revert genesis block"));
+        }
+        let tip = blocks[blocks.len() - 1].clone();
+        let parent = &blocks[blocks.len() - 2];
+
+        let cached = self.undo.lock().unwrap().remove(&tip.hash);
+        let undo = match (cached, &self.store) {
+            (Some(undo), _) => undo,
+            (None, Some(store)) => store.get_undo(&tip.hash)?
+                .ok_or_else(|| anyhow::anyhow!("No undo record for block {}", tip.hash))?,
+            (None, None) => return Err(anyhow::anyhow!("No undo record for block {}", tip.hash)),
+        };
+
+        self.restore_undo(&undo)?;
+        if let Some(store) = &self.store {
+            let changes = self.state.lock().unwrap().take_dirty();
+            store.revert_tip(&tip, parent, &changes)?;
+        }
+
+        blocks.pop();
+        log::info!("[CHAIN] ↩️ Reverted block #{} ({})", tip.header.height, tip.hash);
+        Ok(tip)
+    }
+
+    /// Put the ledger, governance and state trie back to their values
+    /// before the block behind `undo` was applied
+    fn restore_undo(&self, undo: &BlockUndo) -> Result<()> {
+        {
+            let mut gov = self.gov.lock().unwrap();
+            let mut ledger = self.ledger.lock().unwrap();
+            ledger.restore(&undo.accounts)?;
+            gov.restore(&undo.governance)?;
+        }
+        self.state.lock().unwrap().apply_changes(&undo.state);
+        Ok(())
+    }
+
+    /// Current state root (hex)
+    pub fn state_root(&self) -> String {
+        self.state.lock().unwrap().root_hex()
+    }
+
+    /// Merkle proof for any state key against `state_root()`
+    pub fn prove(&self, key: &StateKey) -> StateProof {
+        self.state.lock().unwrap().prove(key)
+    }
+
+    /// Merkle proof for a single account's balance and stake
+    pub fn prove_account(&self, did: &str) -> StateProof {
+        self.prove(&StateKey::Account(did.to_string()))
+    }
+
+    /// Nonce of the last transaction `did` got on chain
+    pub fn account_nonce(&self, did: &str) -> u64 {
+        self.ledger.lock().unwrap().get_account(did).nonce
+    }
+
+    /// Signing key `did` rotated to, if it ever did
+    pub fn authorized_key(&self, did: &str) -> Option<String> {
+        self.ledger.lock().unwrap().get_account(did).auth_key
+    }
+
+    /// Sign `data` with `wallet` and queue it in `mempool` for the next block
+    pub fn queue_signed(&self, mempool: &Mutex<Mempool>, wallet: &crate::wallet::KaranaWallet, data: TransactionData) -> Result<Transaction, MempoolError> {
+        let confirmed = self.account_nonce(wallet.did());
+        let authorized = self.authorized_key(wallet.did());
+        let mut pool = mempool.lock().unwrap();
+        let tx = create_signed_transaction(wallet, data, pool.next_nonce(wallet.did(), confirmed));
+        pool.add_authorized(tx.clone(), confirmed, authorized.as_deref())?;
+        Ok(tx)
+    }
+
+    /// Phase 7.5: Create an attestation transaction for an intent completion,
+    /// signed by `wallet`
+    pub fn attest_intent(&self, wallet: &crate::wallet::KaranaWallet, intent: &str, proof: &[u8], result: &str, nonce: u64) -> Transaction {
+        let proof_hash = hex::encode(Sha256::digest(proof));
+        let result_hash = hex::encode(Sha256::digest(result.as_bytes()));
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        
+        create_signed_transaction(
+            wallet,
+            TransactionData::IntentAttestation {
+                intent: intent.to_string(),
+                proof_hash,
+                result_hash,
+                timestamp,
+            },
+            nonce,
+        )
+    }
+}
+
+/// Helper to create a properly signed transaction
+///
+/// `nonce` must be one past the sender's last nonce (see `Mempool::next_nonce`).
+pub fn create_signed_transaction(
+    wallet: &crate::wallet::KaranaWallet,
+    data: TransactionData,
+    nonce: u64,
+) -> Transaction {
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    
+    // Create unsigned transaction first to get the message
+    let mut tx = Transaction {
+        sender: wallet.did().to_string(),
+        data,
+        signature: String::new(),
+        nonce,
+        public_key: Some(wallet.public_key_hex()),
+        hash: String::new(),
+        timestamp,
+    };
+    
+    // Get message and sign it
+    let message = tx.signing_message();
+    let signature = wallet.sign(&message);
+    tx.signature = hex::encode(signature.to_bytes());
+    
+    // Compute hash
+    let hash_data = serde_json::to_vec(&tx).unwrap_or_default();
+    tx.hash = hex::encode(Sha256::digest(&hash_data));
+    
+    tx
+}
+
+/// Helper to create a key rotation from `current` (the key the chain
+/// authorizes now) to `next`, both acting for the same DID
+pub fn create_key_rotation(
+    current: &crate::wallet::KaranaWallet,
+    next: &crate::wallet::KaranaWallet,
+    nonce: u64,
+) -> Transaction {
+    let new_public_key = next.public_key_hex();
+    let proof = next.sign_hex(&key_rotation_message(current.did(), &new_public_key, nonce));
+    create_signed_transaction(current, TransactionData::RotateKey { new_public_key, proof }, nonce)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wallet::KaranaWallet;
+    
+    #[test]
+    fn test_transaction_signing_and_verification() {
+        // Create a wallet
+        let result = KaranaWallet::generate("test-device").unwrap();
+        let wallet = result.wallet;
+        
+        // Create a signed transaction
+        let tx = create_signed_transaction(
+            &wallet,
+            TransactionData::Transfer {
+                to: "did:karana:recipient".to_string(),
+                amount: 100,
+            },
+            1,
+        );
+        
+        // Verify the transaction
+        assert!(tx.verify(), "Transaction should verify");
+        assert!(tx.verify_sender_did(), "Sender DID should match public key");
+    }
+    
+    #[test]
+    fn test_invalid_signature_rejected() {
+        // Create a wallet
+        let result = KaranaWallet::generate("test-device").unwrap();
+        let wallet = result.wallet;
+        
+        // Create a signed transaction
+        let mut tx = create_signed_transaction(
+            &wallet,
+            TransactionData::Transfer {
+                to: "did:karana:recipient".to_string(),
+                amount: 100,
+            },
+            1,
+        );
+        
+        // Tamper with the signature
+        tx.signature = "0000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000".to_string();
+        
+        // Verification should fail
+        assert!(!tx.verify(), "Tampered transaction should not verify");
+    }
+    
+    #[test]
+    fn test_wrong_sender_rejected() {
+        // Create a wallet
+        let result = KaranaWallet::generate("test-device").unwrap();
+        let wallet = result.wallet;
+        
+        // Create a signed transaction
+        let mut tx = create_signed_transaction(
+            &wallet,
+            TransactionData::Transfer {
+                to: "did:karana:recipient".to_string(),
+                amount: 100,
+            },
+            1,
+        );
+        
+        // Change the sender (impersonation attempt)
+        tx.sender = "did:karana:evil".to_string();
+        
+        // DID verification should fail
+        assert!(!tx.verify_sender_did(), "Wrong sender DID should not verify");
+    }
+    
+    #[test]
+    fn test_legacy_transaction_accepted() {
+        // Legacy transaction without public key
+        let tx = Transaction {
+            sender: "legacy:user".to_string(),
+            data: TransactionData::Transfer {
+                to: "recipient".to_string(),
+                amount: 50,
+            },
+            signature: "legacy_sig".to_string(),
+            nonce: 12345,
+            public_key: None,
+            hash: String::new(),
+            timestamp: 0,
+        };
+        
+        // Should pass verification in legacy mode
+        assert!(tx.verify(), "Legacy transaction should verify");
+    }
+    
+    #[test]
+    fn test_unsigned_transactions_never_reach_state() {
+        let nanos = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_nanos();
+        let prefix = format!("/tmp/karana-test-unsigned-{}", nanos);
+        let ai = Arc::new(Mutex::new(crate::ai::KaranaAI::new().unwrap()));
+        let ledger = Arc::new(Mutex::new(Ledger::new(&format!("{}-ledger", prefix))));
+        let gov = Arc::new(Mutex::new(Governance::new(&format!("{}-gov", prefix), ledger.clone(), ai)));
+        ledger.lock().unwrap().mint("Alice", 1000);
+        let chain = Blockchain::new(ledger.clone(), gov);
+        let unsigned = Transaction::new(
+            "Alice".to_string(),
+            TransactionData::Transfer { to: "Bob".to_string(), amount: 100 },
+            1,
+            vec![],
+        );
+        let root = chain.state_root();
+        
+        assert!(chain.produce_block_exact("node", vec![unsigned.clone()]).is_err());
+        assert_eq!(chain.height(), 0);
+        assert_eq!(chain.state_root(), root);
+        assert!(chain.undo.lock().unwrap().is_empty());
+        
+        let block = chain.produce_block("node", vec![unsigned]).unwrap();
+        assert!(block.transactions.is_empty());
+        assert_eq!(ledger.lock().unwrap().get_balance("Bob"), 0);
+    }
+    
+    #[test]
+    fn test_failed_reorg_restores_reverted_blocks() {
+        let nanos = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_nanos();
+        let ai = Arc::new(Mutex::new(crate::ai::KaranaAI::new().unwrap()));
+        let node = |name: &str| {
+            let prefix = format!("/tmp/karana-test-reorg-{}-{}", name, nanos);
+            let ledger = Arc::new(Mutex::new(Ledger::new(&format!("{}-ledger", prefix))));
+            let gov = Arc::new(Mutex::new(Governance::new(&format!("{}-gov", prefix), ledger.clone(), ai.clone())));
+            ledger.lock().unwrap().mint("Alice", 1000);
+            (Blockchain::new(ledger.clone(), gov), ledger)
+        };
+        let transfer = |amount, nonce| Transaction::new(
+            "Alice".to_string(),
+            TransactionData::Transfer { to: "Bob".to_string(), amount },
+            nonce,
+            b"sig".to_vec(),
+        );
+        let (a, ledger) = node("a");
+        let (b, _) = node("b");
+        
+        a.produce_block("node-a", vec![transfer(100, 1)]).unwrap();
+        let a2 = a.produce_block("node-a", vec![transfer(50, 2)]).unwrap();
+        let branch: Vec<Block> = (0..3).map(|_| b.produce_block("node-b", vec![]).unwrap()).collect();
+        
+        // Block 1 can no longer be reverted, so the reorg fails after reverting block 2
+        let a1_hash = a.get_block(1).unwrap().hash;
+        a.undo.lock().unwrap().remove(&a1_hash);
+        let results: Vec<_> = branch.into_iter().map(|block| a.import_block(block)).collect();
+        assert!(results.iter().any(|r| r.is_err()));
+        
+        assert_eq!(a.latest_block().hash, a2.hash);
+        assert_eq!(ledger.lock().unwrap().get_balance("Bob"), 150);
+        
+        // Block 2 was re-applied with a fresh undo record
+        a.revert_tip().unwrap();
+        assert_eq!(ledger.lock().unwrap().get_balance("Bob"), 100);
+    }
+}
@@ -0,0 +1,410 @@
+// Authenticated chain state
+//
+// A sparse Merkle tree (depth 256) over every piece of state the chain
+// touches: account balances/stakes, governance proposals and intent
+// attestations. Each entry lives at the leaf addressed by SHA-256 of its
+// key, so two nodes holding the same state always agree on the root.
+//
+// The root is committed in `BlockHeader::state_root`, and `StateProof` lets a
+// light client check a single account (or attestation) against that root
+// without replaying the chain. Proofs also cover absence: an empty leaf
+// proves the key has never been written.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashSet};
+use std::sync::OnceLock;
+
+/// Tree depth in bits (one level per bit of the key hash)
+pub const TREE_DEPTH: usize = 256;
+
+pub type Hash32 = [u8; 32];
+
+/// A change to a single leaf: `None` deletes it
+pub type StateChange = (Hash32, Option<Vec<u8>>);
+
+/// Addressable pieces of chain state
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum StateKey {
+    /// Balance/stake record for a DID
+    Account(String),
+    /// Governance proposal by id
+    Proposal(u64),
+    /// Governance parameters (absent while still at their defaults)
+    GovParams,
+    /// Intent attestation by transaction id
+    Attestation(String),
+}
+
+impl StateKey {
+    /// Leaf address for this key
+    pub fn path(&self) -> Hash32 {
+        let label = match self {
+            StateKey::Account(did) => format!("account:{}", did),
+            StateKey::Proposal(id) => format!("proposal:{}", id),
+            StateKey::GovParams => "gov:params".to_string(),
+            StateKey::Attestation(id) => format!("attestation:{}", id),
+        };
+        Sha256::digest(label.as_bytes()).into()
+    }
+}
+
+/// Attestation record committed to state
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AttestationRecord {
+    pub sender: String,
+    pub intent: String,
+    pub proof_hash: String,
+    pub result_hash: String,
+    pub timestamp: u64,
+}
+
+/// In-memory sparse Merkle tree with dirty tracking for persistence
+#[derive(Debug, Default, Clone)]
+pub struct StateTrie {
+    leaves: BTreeMap<Hash32, Vec<u8>>,
+    dirty: HashSet<Hash32>,
+    cached_root: Option<Hash32>,
+}
+
+impl StateTrie {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Rebuild a trie from persisted leaves
+    pub fn from_leaves(leaves: impl IntoIterator<Item = (Hash32, Vec<u8>)>) -> Self {
+        Self {
+            leaves: leaves.into_iter().collect(),
+            dirty: HashSet::new(),
+            cached_root: None,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.leaves.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.leaves.is_empty()
+    }
+
+    /// Raw value stored under `key`
+    pub fn get(&self, key: &StateKey) -> Option<&Vec<u8>> {
+        self.leaves.get(&key.path())
+    }
+
+    /// Set the value under `key`
+    pub fn insert(&mut self, key: &StateKey, value: Vec<u8>) {
+        let path = key.path();
+        if self.leaves.get(&path) == Some(&value) {
+            return;
+        }
+        self.leaves.insert(path, value);
+        self.dirty.insert(path);
+        self.cached_root = None;
+    }
+
+    /// Serialize `value` as JSON and store it under `key`
+    pub fn insert_json<T: Serialize>(&mut self, key: &StateKey, value: &T) {
+        let bytes = serde_json::to_vec(value).unwrap_or_default();
+        self.insert(key, bytes);
+    }
+
+    /// Remove `key` from the state
+    pub fn remove(&mut self, key: &StateKey) {
+        let path = key.path();
+        if self.leaves.remove(&path).is_some() {
+            self.dirty.insert(path);
+            self.cached_root = None;
+        }
+    }
+
+    /// Write raw leaf changes, returning the values they replaced so the
+    /// same call can undo them
+    pub fn apply_changes(&mut self, changes: &[StateChange]) -> Vec<StateChange> {
+        let mut previous = Vec::with_capacity(changes.len());
+        for (path, value) in changes {
+            let old = match value {
+                Some(value) => self.leaves.insert(*path, value.clone()),
+                None => self.leaves.remove(path),
+            };
+            previous.push((*path, old));
+            self.dirty.insert(*path);
+        }
+        // Undo in reverse so repeated paths restore their earliest value
+        previous.reverse();
+        self.cached_root = None;
+        previous
+    }
+
+    /// Drain leaves modified since the last call (for persistence)
+    pub fn take_dirty(&mut self) -> Vec<StateChange> {
+        let mut changes: Vec<StateChange> = self
+            .dirty
+            .drain()
+            .map(|path| (path, self.leaves.get(&path).cloned()))
+            .collect();
+        changes.sort_by(|a, b| a.0.cmp(&b.0));
+        changes
+    }
+
+    /// Current state root
+    pub fn root(&mut self) -> Hash32 {
+        if let Some(root) = self.cached_root {
+            return root;
+        }
+        let leaves: Vec<(&Hash32, &Vec<u8>)> = self.leaves.iter().collect();
+        let root = subtree_hash(&leaves, 0);
+        self.cached_root = Some(root);
+        root
+    }
+
+    /// Current state root, hex encoded (as stored in block headers)
+    pub fn root_hex(&mut self) -> String {
+        hex::encode(self.root())
+    }
+
+    /// Build an inclusion (or exclusion) proof for `key`
+    pub fn prove(&self, key: &StateKey) -> StateProof {
+        let path = key.path();
+        let leaves: Vec<(&Hash32, &Vec<u8>)> = self.leaves.iter().collect();
+
+        // Walk from the root towards the leaf, recording the sibling subtree at each level
+        let mut siblings_top_down = Vec::with_capacity(TREE_DEPTH);
+        let mut slice: &[(&Hash32, &Vec<u8>)] = &leaves;
+        for depth in 0..TREE_DEPTH {
+            let split = slice.partition_point(|(p, _)| !bit(p, depth));
+            let (left, right) = slice.split_at(split);
+            if bit(&path, depth) {
+                siblings_top_down.push(subtree_hash(left, depth + 1));
+                slice = right;
+            } else {
+                siblings_top_down.push(subtree_hash(right, depth + 1));
+                slice = left;
+            }
+        }
+
+        let defaults = default_hashes();
+        let mut bitmap = [0u8; 32];
+        let mut siblings = Vec::new();
+        for (depth, sibling) in siblings_top_down.into_iter().enumerate() {
+            if sibling != defaults[depth + 1] {
+                bitmap[depth / 8] |= 0x80 >> (depth % 8);
+                siblings.push(sibling);
+            }
+        }
+
+        StateProof {
+            key: key.clone(),
+            value: self.leaves.get(&path).cloned(),
+            bitmap,
+            siblings,
+        }
+    }
+}
+
+/// Merkle proof for a single state key
+///
+/// Only non-empty siblings are carried; `bitmap` marks which of the 256
+/// levels (root first) have one.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StateProof {
+    pub key: StateKey,
+    /// Value at the key, or None for a proof of absence
+    pub value: Option<Vec<u8>>,
+    pub bitmap: [u8; 32],
+    pub siblings: Vec<Hash32>,
+}
+
+impl StateProof {
+    /// Recompute the root implied by this proof
+    pub fn compute_root(&self) -> Option<Hash32> {
+        let path = self.key.path();
+        let defaults = default_hashes();
+
+        let mut node = match &self.value {
+            Some(value) => leaf_hash(&path, value),
+            None => defaults[TREE_DEPTH],
+        };
+
+        let mut remaining = self.siblings.iter().rev();
+        for depth in (0..TREE_DEPTH).rev() {
+            let sibling = if self.bitmap[depth / 8] & (0x80 >> (depth % 8)) != 0 {
+                *remaining.next()?
+            } else {
+                defaults[depth + 1]
+            };
+            node = if bit(&path, depth) {
+                node_hash(&sibling, &node)
+            } else {
+                node_hash(&node, &sibling)
+            };
+        }
+
+        // Every carried sibling must have been consumed
+        if remaining.next().is_some() {
+            return None;
+        }
+        Some(node)
+    }
+
+    /// Check this proof against a hex-encoded state root from a block header
+    pub fn verify(&self, state_root_hex: &str) -> bool {
+        match (self.compute_root(), hex::decode(state_root_hex)) {
+            (Some(root), Ok(expected)) => root.as_slice() == expected.as_slice(),
+            _ => false,
+        }
+    }
+
+    /// Decode the proven value as JSON (e.g. a `KaraToken` for accounts)
+    pub fn decode_value<T: serde::de::DeserializeOwned>(&self) -> Option<T> {
+        self.value.as_ref().and_then(|v| serde_json::from_slice(v).ok())
+    }
+}
+
+fn bit(path: &Hash32, depth: usize) -> bool {
+    path[depth / 8] & (0x80 >> (depth % 8)) != 0
+}
+
+fn leaf_hash(path: &Hash32, value: &[u8]) -> Hash32 {
+    let mut hasher = Sha256::new();
+    hasher.update([0x00]);
+    hasher.update(path);
+    hasher.update(value);
+    hasher.finalize().into()
+}
+
+fn node_hash(left: &Hash32, right: &Hash32) -> Hash32 {
+    let mut hasher = Sha256::new();
+    hasher.update([0x01]);
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+/// Hash of an empty subtree rooted at each depth (index 256 = empty leaf)
+fn default_hashes() -> &'static [Hash32; TREE_DEPTH + 1] {
+    static DEFAULTS: OnceLock<[Hash32; TREE_DEPTH + 1]> = OnceLock::new();
+    DEFAULTS.get_or_init(|| {
+        let mut defaults = [[0u8; 32]; TREE_DEPTH + 1];
+        for depth in (0..TREE_DEPTH).rev() {
+            defaults[depth] = node_hash(&defaults[depth + 1], &defaults[depth + 1]);
+        }
+        defaults
+    })
+}
+
+/// Hash of the subtree at `depth` containing exactly `leaves` (sorted by path)
+fn subtree_hash(leaves: &[(&Hash32, &Vec<u8>)], depth: usize) -> Hash32 {
+    if leaves.is_empty() {
+        return default_hashes()[depth];
+    }
+    if depth == TREE_DEPTH {
+        let (path, value) = leaves[0];
+        return leaf_hash(path, value);
+    }
+    let split = leaves.partition_point(|(p, _)| !bit(p, depth));
+    let (left, right) = leaves.split_at(split);
+    node_hash(&subtree_hash(left, depth + 1), &subtree_hash(right, depth + 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::economy::KaraToken;
+
+    fn account(balance: u128) -> KaraToken {
+        KaraToken { balance, reputation: 1.0, ..Default::default() }
+    }
+
+    #[test]
+    fn test_empty_root_is_default() {
+        let mut trie = StateTrie::new();
+        assert_eq!(trie.root(), default_hashes()[0]);
+    }
+
+    #[test]
+    fn test_root_is_order_independent() {
+        let mut a = StateTrie::new();
+        a.insert_json(&StateKey::Account("alice".into()), &account(10));
+        a.insert_json(&StateKey::Account("bob".into()), &account(20));
+
+        let mut b = StateTrie::new();
+        b.insert_json(&StateKey::Account("bob".into()), &account(20));
+        b.insert_json(&StateKey::Account("alice".into()), &account(10));
+
+        assert_eq!(a.root(), b.root());
+
+        b.insert_json(&StateKey::Account("alice".into()), &account(11));
+        assert_ne!(a.root(), b.root());
+    }
+
+    #[test]
+    fn test_inclusion_proof() {
+        let mut trie = StateTrie::new();
+        for i in 0..20 {
+            trie.insert_json(&StateKey::Account(format!("did:karana:{}", i)), &account(i));
+        }
+        trie.insert_json(&StateKey::Proposal(1), &"proposal");
+        let root = trie.root_hex();
+
+        let proof = trie.prove(&StateKey::Account("did:karana:7".into()));
+        assert!(proof.verify(&root));
+        assert_eq!(proof.decode_value::<KaraToken>().unwrap().balance, 7);
+
+        // Tampered value no longer matches the root
+        let mut forged = proof.clone();
+        forged.value = Some(serde_json::to_vec(&account(1_000_000)).unwrap());
+        assert!(!forged.verify(&root));
+    }
+
+    #[test]
+    fn test_exclusion_proof() {
+        let mut trie = StateTrie::new();
+        trie.insert_json(&StateKey::Account("alice".into()), &account(5));
+        let root = trie.root_hex();
+
+        let proof = trie.prove(&StateKey::Account("mallory".into()));
+        assert!(proof.value.is_none());
+        assert!(proof.verify(&root));
+    }
+
+    #[test]
+    fn test_apply_changes_round_trip() {
+        let mut trie = StateTrie::new();
+        trie.insert_json(&StateKey::Account("alice".into()), &account(5));
+        let before = trie.root();
+
+        let alice = StateKey::Account("alice".into()).path();
+        let bob = StateKey::Account("bob".into()).path();
+        let previous = trie.apply_changes(&[
+            (alice, Some(b"1".to_vec())),
+            (bob, Some(b"2".to_vec())),
+            (alice, None),
+        ]);
+        assert_ne!(trie.root(), before);
+
+        trie.apply_changes(&previous);
+        assert_eq!(trie.root(), before);
+    }
+
+    #[test]
+    fn test_dirty_tracking() {
+        let mut trie = StateTrie::new();
+        trie.insert_json(&StateKey::Account("alice".into()), &account(5));
+        trie.insert_json(&StateKey::Attestation("tx1".into()), &"ok");
+        assert_eq!(trie.take_dirty().len(), 2);
+
+        trie.remove(&StateKey::Attestation("tx1".into()));
+        let changes = trie.take_dirty();
+        assert_eq!(changes.len(), 1);
+        assert!(changes[0].1.is_none());
+
+        let mut restored = StateTrie::from_leaves(vec![(
+            StateKey::Account("alice".into()).path(),
+            serde_json::to_vec(&account(5)).unwrap(),
+        )]);
+        assert_eq!(restored.root(), trie.root());
+    }
+}
@@ -0,0 +1,484 @@
+// Persistent block store for the sovereign chain
+//
+// Layout (RocksDB column families):
+// - "blocks":  block hash   -> sealed(JSON block)
+// - "heights": height (BE)  -> block hash
+// - "meta":    "tip"        -> sealed(JSON TipRecord)
+// - "state":   leaf path    -> state trie leaf value
+// - "undo":    block hash   -> sealed(JSON BlockUndo), for reverting the tip
+//
+// Every value is "sealed": prefixed with the SHA-256 of its payload so that a
+// torn or bit-rotted record is detected on reload. A block, its height index,
+// the state trie leaves it changed, its undo record and the new tip are written
+// in a single synced WriteBatch, and on open the chain is re-validated from genesis.
+// Anything past the last block that links and verifies cleanly is rolled back:
+// the state leaves are restored from the blocks' undo records in the same batch,
+// and the records are handed back so the ledger and governance can follow.
+
+use super::exec::BlockUndo;
+use super::state::{Hash32, StateChange};
+use super::Block;
+use anyhow::{Context, Result};
+use rocksdb::{ColumnFamilyDescriptor, Options, WriteBatch, WriteOptions, DB};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+const CF_BLOCKS: &str = "blocks";
+const CF_HEIGHTS: &str = "heights";
+const CF_META: &str = "meta";
+const CF_STATE: &str = "state";
+const CF_UNDO: &str = "undo";
+const TIP_KEY: &[u8] = b"tip";
+
+/// Pointer to the last committed block
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TipRecord {
+    pub height: u64,
+    pub hash: String,
+}
+
+/// Outcome of reloading the chain from disk
+#[derive(Debug, Clone)]
+pub struct LoadReport {
+    /// Blocks that survived validation, ordered by height
+    pub blocks: Vec<Block>,
+    /// Height of the first block that was discarded, if any were
+    pub rolled_back_from: Option<u64>,
+    /// Undo records of the discarded blocks, newest first, for restoring the
+    /// ledger and governance (their state leaves are already restored)
+    pub undone: Vec<BlockUndo>,
+}
+
+/// Crash-safe on-disk block store
+pub struct BlockStore {
+    db: DB,
+}
+
+impl BlockStore {
+    /// Open (or create) a block store at `path`
+    pub fn open(path: &str) -> Result<Self> {
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        opts.create_missing_column_families(true);
+
+        let cfs = [CF_BLOCKS, CF_HEIGHTS, CF_META, CF_STATE, CF_UNDO]
+            .iter()
+            .map(|name| ColumnFamilyDescriptor::new(*name, Options::default()));
+
+        let db = DB::open_cf_descriptors(&opts, path, cfs)
+            .map_err(|e| anyhow::anyhow!("Block store open failed: {}", e))?;
+
+        Ok(Self { db })
+    }
+
+    /// Atomically persist a block together with its height index, the state
+    /// leaves it changed, its undo record and the new tip
+    pub fn commit_block(&self, block: &Block, state: &[StateChange], undo: Option<&BlockUndo>) -> Result<()> {
+        let mut batch = WriteBatch::default();
+        self.stage_block(&mut batch, block)?;
+        self.stage_state(&mut batch, state)?;
+        if let Some(undo) = undo {
+            batch.put_cf(self.cf(CF_UNDO)?, block.hash.as_bytes(), seal(&serde_json::to_vec(undo)?));
+        }
+        self.write_synced(batch)
+    }
+
+    /// Atomically drop the tip block, restoring `state` and moving the tip
+    /// back to `parent`
+    pub fn revert_tip(&self, tip: &Block, parent: &Block, state: &[StateChange]) -> Result<()> {
+        let parent_tip = TipRecord {
+            height: parent.header.height,
+            hash: parent.hash.clone(),
+        };
+        let mut batch = WriteBatch::default();
+        batch.delete_cf(self.cf(CF_HEIGHTS)?, tip.header.height.to_be_bytes());
+        batch.delete_cf(self.cf(CF_BLOCKS)?, tip.hash.as_bytes());
+        batch.delete_cf(self.cf(CF_UNDO)?, tip.hash.as_bytes());
+        batch.put_cf(self.cf(CF_META)?, TIP_KEY, seal(&serde_json::to_vec(&parent_tip)?));
+        self.stage_state(&mut batch, state)?;
+        self.write_synced(batch)
+    }
+
+    /// Undo record written alongside a block
+    pub fn get_undo(&self, hash: &str) -> Result<Option<BlockUndo>> {
+        let cf = self.cf(CF_UNDO)?;
+        match self.db.get_cf(cf, hash.as_bytes())? {
+            Some(raw) => {
+                let payload = unseal(&raw)
+                    .with_context(|| format!("Undo record {} checksum mismatch", hash))?;
+                Ok(Some(serde_json::from_slice(payload)?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Load every persisted state trie leaf
+    pub fn load_state(&self) -> Result<Vec<(Hash32, Vec<u8>)>> {
+        let cf = self.cf(CF_STATE)?;
+        let mut leaves = Vec::new();
+        for item in self.db.iterator_cf(cf, rocksdb::IteratorMode::Start) {
+            let (key, value) = item.map_err(|e| anyhow::anyhow!("State scan failed: {}", e))?;
+            if let Ok(path) = <Hash32>::try_from(&key[..]) {
+                leaves.push((path, value.to_vec()));
+            }
+        }
+        Ok(leaves)
+    }
+
+    /// Read the current tip record, if the store has one
+    pub fn tip(&self) -> Result<Option<TipRecord>> {
+        let cf = self.cf(CF_META)?;
+        match self.db.get_cf(cf, TIP_KEY)? {
+            Some(raw) => {
+                let payload = unseal(&raw).context("Tip record checksum mismatch")?;
+                Ok(Some(serde_json::from_slice(payload)?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Fetch a block by hash, verifying its checksum
+    pub fn get_block(&self, hash: &str) -> Result<Option<Block>> {
+        let cf = self.cf(CF_BLOCKS)?;
+        match self.db.get_cf(cf, hash.as_bytes())? {
+            Some(raw) => {
+                let payload = unseal(&raw)
+                    .with_context(|| format!("Block {} checksum mismatch", hash))?;
+                Ok(Some(serde_json::from_slice(payload)?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Fetch the hash indexed at `height`
+    pub fn hash_at(&self, height: u64) -> Result<Option<String>> {
+        let cf = self.cf(CF_HEIGHTS)?;
+        Ok(self
+            .db
+            .get_cf(cf, height.to_be_bytes())?
+            .map(|v| String::from_utf8_lossy(&v).to_string()))
+    }
+
+    /// Reload the chain, re-validating every block from genesis.
+    ///
+    /// The walk follows the height index up to the highest indexed height (not
+    /// just the recorded tip) so orphaned index entries are also caught. The
+    /// first block that is missing, fails its checksum, or does not link to its
+    /// parent ends the chain; it and everything above it are rolled back.
+    pub fn load(&self) -> Result<LoadReport> {
+        let highest = self.highest_indexed()?;
+        let tip = self.tip().unwrap_or(None);
+
+        let mut blocks: Vec<Block> = Vec::new();
+        let mut rolled_back_from = None;
+        let mut undone = Vec::new();
+
+        if let Some(highest) = highest {
+            for height in 0..=highest {
+                match self.load_verified(height, blocks.last()) {
+                    Some(block) => blocks.push(block),
+                    None => {
+                        rolled_back_from = Some(height);
+                        break;
+                    }
+                }
+            }
+        }
+
+        // A tip that disagrees with the last good block is itself torn
+        let last_good = blocks.last().map(|b| TipRecord {
+            height: b.header.height,
+            hash: b.hash.clone(),
+        });
+        if rolled_back_from.is_none() && tip != last_good {
+            rolled_back_from = Some(last_good.as_ref().map(|t| t.height + 1).unwrap_or(0));
+        }
+
+        if let Some(from) = rolled_back_from {
+            log::warn!(
+                "[CHAIN] ⚠️ Block store inconsistent at height {}; rolling back to {:?}",
+                from,
+                last_good.as_ref().map(|t| t.height)
+            );
+            undone = self.truncate(from, highest.unwrap_or(from), last_good.as_ref())?;
+        }
+
+        Ok(LoadReport { blocks, rolled_back_from, undone })
+    }
+
+    /// Load and verify the block at `height` against its parent
+    fn load_verified(&self, height: u64, parent: Option<&Block>) -> Option<Block> {
+        let hash = self.hash_at(height).ok()??;
+        let block = self.get_block(&hash).ok()??;
+
+        if block.hash != hash || block.header.height != height {
+            return None;
+        }
+        let result = match parent {
+            Some(parent) => block.validate(&parent.hash),
+            // Genesis only needs to hash correctly
+            None if block.calculate_hash() == block.hash => Ok(()),
+            None => Err(anyhow::anyhow!("Invalid genesis hash")),
+        };
+        result.ok().map(|_| block)
+    }
+
+    /// Remove heights `from..=to`, undo their state leaves and reset the tip
+    /// to `last_good`. Returns the undo records found, newest first.
+    fn truncate(&self, from: u64, to: u64, last_good: Option<&TipRecord>) -> Result<Vec<BlockUndo>> {
+        let heights = self.cf(CF_HEIGHTS)?;
+        let blocks = self.cf(CF_BLOCKS)?;
+        let meta = self.cf(CF_META)?;
+        let undo_cf = self.cf(CF_UNDO)?;
+
+        let mut batch = WriteBatch::default();
+        let mut undone = Vec::new();
+        // Newest first, so each leaf ends at its value before the oldest block
+        for height in (from..=to).rev() {
+            if let Ok(Some(hash)) = self.hash_at(height) {
+                match self.get_undo(&hash) {
+                    Ok(Some(undo)) => {
+                        self.stage_state(&mut batch, &undo.state)?;
+                        undone.push(undo);
+                    }
+                    Ok(None) => {}
+                    Err(e) => log::warn!("[CHAIN] ⚠️ Cannot undo block {} at height {}: {}", hash, height, e),
+                }
+                batch.delete_cf(blocks, hash.as_bytes());
+                batch.delete_cf(undo_cf, hash.as_bytes());
+            }
+            batch.delete_cf(heights, height.to_be_bytes());
+        }
+        match last_good {
+            Some(tip) => batch.put_cf(meta, TIP_KEY, seal(&serde_json::to_vec(tip)?)),
+            None => batch.delete_cf(meta, TIP_KEY),
+        }
+        self.write_synced(batch)?;
+        Ok(undone)
+    }
+
+    /// Highest height present in the height index
+    fn highest_indexed(&self) -> Result<Option<u64>> {
+        let cf = self.cf(CF_HEIGHTS)?;
+        let mut iter = self.db.iterator_cf(cf, rocksdb::IteratorMode::End);
+        match iter.next() {
+            Some(Ok((key, _))) if key.len() == 8 => {
+                Ok(Some(u64::from_be_bytes(key[..8].try_into().unwrap())))
+            }
+            Some(Err(e)) => Err(anyhow::anyhow!("Height index scan failed: {}", e)),
+            _ => Ok(None),
+        }
+    }
+
+    fn stage_block(&self, batch: &mut WriteBatch, block: &Block) -> Result<()> {
+        let tip = TipRecord {
+            height: block.header.height,
+            hash: block.hash.clone(),
+        };
+        batch.put_cf(self.cf(CF_BLOCKS)?, block.hash.as_bytes(), seal(&serde_json::to_vec(block)?));
+        batch.put_cf(self.cf(CF_HEIGHTS)?, block.header.height.to_be_bytes(), block.hash.as_bytes());
+        batch.put_cf(self.cf(CF_META)?, TIP_KEY, seal(&serde_json::to_vec(&tip)?));
+        Ok(())
+    }
+
+    fn stage_state(&self, batch: &mut WriteBatch, state: &[StateChange]) -> Result<()> {
+        let cf = self.cf(CF_STATE)?;
+        for (path, value) in state {
+            match value {
+                Some(value) => batch.put_cf(cf, path, value),
+                None => batch.delete_cf(cf, path),
+            }
+        }
+        Ok(())
+    }
+
+    fn write_synced(&self, batch: WriteBatch) -> Result<()> {
+        let mut write_opts = WriteOptions::default();
+        write_opts.set_sync(true);
+        self.db
+            .write_opt(batch, &write_opts)
+            .map_err(|e| anyhow::anyhow!("Block store write failed: {}", e))
+    }
+
+    fn cf(&self, name: &str) -> Result<&rocksdb::ColumnFamily> {
+        self.db
+            .cf_handle(name)
+            .ok_or_else(|| anyhow::anyhow!("Column family '{}' not found", name))
+    }
+}
+
+/// Prefix a payload with its SHA-256 checksum
+fn seal(payload: &[u8]) -> Vec<u8> {
+    let mut out = Sha256::digest(payload).to_vec();
+    out.extend_from_slice(payload);
+    out
+}
+
+/// Strip and verify the checksum written by `seal`
+fn unseal(raw: &[u8]) -> Result<&[u8]> {
+    if raw.len() < 32 {
+        return Err(anyhow::anyhow!("Record too short"));
+    }
+    let (checksum, payload) = raw.split_at(32);
+    if Sha256::digest(payload).as_slice() != checksum {
+        return Err(anyhow::anyhow!("Checksum mismatch"));
+    }
+    Ok(payload)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn temp_path(name: &str) -> String {
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos();
+        format!("/tmp/karana-test-blockstore-{}-{}", name, nanos)
+    }
+
+    fn build_chain(len: u64) -> Vec<Block> {
+        let mut blocks = vec![Block::new("0".repeat(64), 0, "genesis".to_string(), vec![])];
+        for height in 1..len {
+            let parent = blocks.last().unwrap().hash.clone();
+            blocks.push(Block::new(parent, height, "validator".to_string(), vec![]));
+        }
+        blocks
+    }
+
+    #[test]
+    fn test_commit_and_reload() {
+        let path = temp_path("reload");
+        {
+            let store = BlockStore::open(&path).unwrap();
+            for block in build_chain(4) {
+                store.commit_block(&block, &[], None).unwrap();
+            }
+        }
+
+        let store = BlockStore::open(&path).unwrap();
+        let report = store.load().unwrap();
+        assert_eq!(report.blocks.len(), 4);
+        assert!(report.rolled_back_from.is_none());
+        assert_eq!(store.tip().unwrap().unwrap().height, 3);
+
+        let _ = std::fs::remove_dir_all(&path);
+    }
+
+    #[test]
+    fn test_torn_block_rolls_back() {
+        let path = temp_path("torn");
+        let chain = build_chain(4);
+        let store = BlockStore::open(&path).unwrap();
+        for block in &chain {
+            store.commit_block(block, &[], None).unwrap();
+        }
+
+        // Simulate a torn write: height 2's record is truncated on disk
+        let cf = store.cf(CF_BLOCKS).unwrap();
+        let raw = store.db.get_cf(cf, chain[2].hash.as_bytes()).unwrap().unwrap();
+        store.db.put_cf(cf, chain[2].hash.as_bytes(), &raw[..raw.len() / 2]).unwrap();
+
+        let report = store.load().unwrap();
+        assert_eq!(report.rolled_back_from, Some(2));
+        assert_eq!(report.blocks.len(), 2);
+        assert_eq!(store.tip().unwrap().unwrap().hash, chain[1].hash);
+        assert!(store.hash_at(3).unwrap().is_none());
+
+        let _ = std::fs::remove_dir_all(&path);
+    }
+
+    #[test]
+    fn test_orphan_index_without_tip_rolls_back() {
+        let path = temp_path("orphan");
+        let chain = build_chain(3);
+        let store = BlockStore::open(&path).unwrap();
+        store.commit_block(&chain[0], &[], None).unwrap();
+        store.commit_block(&chain[1], &[], None).unwrap();
+
+        // Height index written but the block body never made it
+        let cf = store.cf(CF_HEIGHTS).unwrap();
+        store.db.put_cf(cf, 2u64.to_be_bytes(), chain[2].hash.as_bytes()).unwrap();
+
+        let report = store.load().unwrap();
+        assert_eq!(report.rolled_back_from, Some(2));
+        assert_eq!(report.blocks.len(), 2);
+        assert!(store.hash_at(2).unwrap().is_none());
+
+        let _ = std::fs::remove_dir_all(&path);
+    }
+
+    #[test]
+    fn test_rollback_applies_undo() {
+        let path = temp_path("rollback-undo");
+        let chain = build_chain(4);
+        let store = BlockStore::open(&path).unwrap();
+        store.commit_block(&chain[0], &[], None).unwrap();
+        store.commit_block(&chain[1], &[([1u8; 32], Some(b"one".to_vec()))], None).unwrap();
+        for (height, prior, value) in [(2usize, b"one", b"two"), (3, b"two", b"six")] {
+            let undo = BlockUndo {
+                height: height as u64,
+                hash: chain[height].hash.clone(),
+                state: vec![([1u8; 32], Some(prior.to_vec()))],
+                ..Default::default()
+            };
+            store.commit_block(&chain[height], &[([1u8; 32], Some(value.to_vec()))], Some(&undo)).unwrap();
+        }
+
+        // Height 2 is torn, so 2 and 3 go and the leaf returns to block 1's value
+        let cf = store.cf(CF_BLOCKS).unwrap();
+        let raw = store.db.get_cf(cf, chain[2].hash.as_bytes()).unwrap().unwrap();
+        store.db.put_cf(cf, chain[2].hash.as_bytes(), &raw[..raw.len() / 2]).unwrap();
+
+        let report = store.load().unwrap();
+        assert_eq!(report.rolled_back_from, Some(2));
+        let undone: Vec<u64> = report.undone.iter().map(|u| u.height).collect();
+        assert_eq!(undone, vec![3, 2]);
+        assert_eq!(store.load_state().unwrap(), vec![([1u8; 32], b"one".to_vec())]);
+        assert!(store.get_undo(&chain[3].hash).unwrap().is_none());
+
+        let _ = std::fs::remove_dir_all(&path);
+    }
+
+    #[test]
+    fn test_state_leaves_commit_with_block() {
+        let path = temp_path("state");
+        let chain = build_chain(2);
+        let store = BlockStore::open(&path).unwrap();
+        store.commit_block(&chain[0], &[([1u8; 32], Some(b"alice".to_vec()))], None).unwrap();
+        store.commit_block(&chain[1], &[([1u8; 32], None), ([2u8; 32], Some(b"bob".to_vec()))], None).unwrap();
+
+        let leaves = store.load_state().unwrap();
+        assert_eq!(leaves, vec![([2u8; 32], b"bob".to_vec())]);
+
+        let _ = std::fs::remove_dir_all(&path);
+    }
+
+    #[test]
+    fn test_revert_tip() {
+        let path = temp_path("revert");
+        let chain = build_chain(3);
+        let store = BlockStore::open(&path).unwrap();
+        store.commit_block(&chain[0], &[], None).unwrap();
+        store.commit_block(&chain[1], &[], None).unwrap();
+        let undo = BlockUndo { height: 2, hash: chain[2].hash.clone(), ..Default::default() };
+        store.commit_block(&chain[2], &[([3u8; 32], Some(b"new".to_vec()))], Some(&undo)).unwrap();
+        assert!(store.get_undo(&chain[2].hash).unwrap().is_some());
+
+        store.revert_tip(&chain[2], &chain[1], &[([3u8; 32], None)]).unwrap();
+        assert_eq!(store.tip().unwrap().unwrap().hash, chain[1].hash);
+        assert!(store.get_undo(&chain[2].hash).unwrap().is_none());
+        assert!(store.load_state().unwrap().is_empty());
+        assert_eq!(store.load().unwrap().blocks.len(), 2);
+
+        let _ = std::fs::remove_dir_all(&path);
+    }
+
+    #[test]
+    fn test_seal_detects_corruption() {
+        let mut sealed = seal(b"payload");
+        assert_eq!(unseal(&sealed).unwrap(), b"payload");
+        let last = sealed.len() - 1;
+        sealed[last] ^= 0xff;
+        assert!(unseal(&sealed).is_err());
+    }
+}
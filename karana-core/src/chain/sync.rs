@@ -0,0 +1,93 @@
+// Block catch-up between peers
+//
+// A node that is behind, or that receives a block whose parent it does not
+// know, sends a `SyncRequest` carrying a block locator: hashes of its
+// canonical chain, dense near the tip and exponentially sparser towards
+// genesis. The peer answers with the canonical blocks following the most
+// recent hash both chains share, so a single round trip finds the fork point
+// however far the chains have diverged. Received blocks go through
+// `Blockchain::import_block`, which applies fork choice and reorganizes when
+// the peer's branch wins.
+//
+// The messages are transport-agnostic; `net::KaranaSwarm` carries them over
+// gossipsub.
+
+use super::{Block, Blockchain, ImportOutcome};
+use serde::{Deserialize, Serialize};
+
+/// Most blocks sent in one response (keeps gossip messages small)
+pub const MAX_BLOCKS_PER_RESPONSE: usize = 32;
+
+/// Ask peers for the blocks we are missing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncRequest {
+    pub request_id: String,
+    /// Our canonical hashes, newest first (see `Blockchain::locator`)
+    pub locator: Vec<String>,
+    pub max_blocks: usize,
+}
+
+/// Blocks following the requester's best shared hash
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncResponse {
+    pub request_id: String,
+    pub blocks: Vec<Block>,
+    /// Responder's tip, so the requester knows whether to ask again
+    pub tip_height: u64,
+    pub tip_hash: String,
+}
+
+/// Result of importing a `SyncResponse`
+#[derive(Debug, Clone, Default)]
+pub struct SyncProgress {
+    /// Blocks that were new to us (canonical or side chain)
+    pub imported: u64,
+    /// Canonical blocks rolled back by reorgs, newest first
+    pub reverted: Vec<Block>,
+    /// We are at least as far along as the responder
+    pub caught_up: bool,
+}
+
+/// Build a request for the blocks after our tip
+pub fn request(chain: &Blockchain) -> SyncRequest {
+    SyncRequest {
+        request_id: uuid::Uuid::new_v4().to_string(),
+        locator: chain.locator(),
+        max_blocks: MAX_BLOCKS_PER_RESPONSE,
+    }
+}
+
+/// Answer a peer's request from our canonical chain
+pub fn serve(chain: &Blockchain, req: &SyncRequest) -> SyncResponse {
+    let max = req.max_blocks.clamp(1, MAX_BLOCKS_PER_RESPONSE);
+    let tip = chain.latest_block();
+    SyncResponse {
+        request_id: req.request_id.clone(),
+        blocks: chain.blocks_after(&req.locator, max),
+        tip_height: tip.header.height,
+        tip_hash: tip.hash,
+    }
+}
+
+/// Import the blocks in a response, in order. Stops at the first invalid
+/// block since everything after it builds on it.
+pub fn apply(chain: &Blockchain, resp: &SyncResponse) -> SyncProgress {
+    let mut progress = SyncProgress::default();
+    for block in &resp.blocks {
+        let (height, hash) = (block.header.height, block.hash.clone());
+        match chain.import_block(block.clone()) {
+            Ok(ImportOutcome::Known) | Ok(ImportOutcome::Orphan { .. }) => {}
+            Ok(ImportOutcome::Reorged { reverted }) => {
+                progress.imported += 1;
+                progress.reverted.extend(reverted);
+            }
+            Ok(_) => progress.imported += 1,
+            Err(e) => {
+                log::warn!("[SYNC] Rejected block #{} ({}) from peer: {}", height, hash, e);
+                break;
+            }
+        }
+    }
+    progress.caught_up = chain.height() >= resp.tip_height;
+    progress
+}
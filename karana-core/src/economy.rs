@@ -1,16 +1,105 @@
 use std::sync::{Arc, Mutex};
 use anyhow::Result;
-use rocksdb::{DB, Options};
+use rocksdb::{DB, IteratorMode, Options, WriteBatch};
+use std::collections::BTreeMap;
 use serde::{Serialize, Deserialize};
-use crate::ai::KaranaAI;
 
 // Atom 4: The Sovereign Economy
 
+// Governance lives in `gov`; re-exported here for existing callers
+pub use crate::gov::{Governance, GovernanceChanges, GovernanceTxn, GovernanceUndo, Proposal, ProposalStatus};
+
+// Staking
+//
+// `staked` is an account's self-bond. Stake can also be delegated to a
+// validator: the amount is recorded on both sides (`delegations` on the
+// delegator, `delegators` on the validator) so rewards and slashes can be
+// applied from either end. Bonding is immediate; unbonding (unstake or
+// undelegate) moves the tokens into escrow on `UNBONDING_ACCOUNT` for
+// `UNBONDING_PERIOD` blocks, during which they can still be slashed for the
+// validator they were bonded to. Matured entries are paid out at the start of
+// each block's bookkeeping (`LedgerTxn::release_unbonded`).
+//
+// Rewards go through `LedgerTxn::distribute_reward`: the validator keeps its
+// commission, and the remainder is split across self-bond and delegations in
+// proportion to stake. Block rewards and proof-of-storage rewards both use it.
+
+/// Escrow account holding the unbonding queue
+pub const UNBONDING_ACCOUNT: &str = "staking:unbonding";
+/// Blocks between unbonding and the tokens becoming spendable
+pub const UNBONDING_PERIOD: u64 = 100;
+/// KARA minted per block for a bonded validator
+pub const BLOCK_REWARD: u128 = 2;
+/// Commission is expressed in basis points of a reward
+pub const MAX_COMMISSION_BPS: u16 = 10_000;
+
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct KaraToken {
     pub balance: u128,
     pub staked: u128,
     pub reputation: f32, // 0.0 - 1.0
+    /// Nonce of the last transaction this account sent on chain
+    #[serde(default)]
+    pub nonce: u64,
+    /// Stake this account has delegated, by validator
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub delegations: BTreeMap<String, u128>,
+    /// Stake delegated to this account as a validator, by delegator
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub delegators: BTreeMap<String, u128>,
+    /// Validator's cut of each reward, in basis points
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub commission_bps: u16,
+    /// Pending unbonds (only kept on `UNBONDING_ACCOUNT`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unbonding: Vec<Unbonding>,
+    /// Signing key (hex) set by the latest key rotation; until then the
+    /// account signs with the key its DID was derived from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth_key: Option<String>,
+}
+
+impl KaraToken {
+    /// Stake delegated out to validators
+    pub fn delegated(&self) -> u128 {
+        self.delegations.values().sum()
+    }
+
+    /// Stake backing this account as a validator (self-bond + delegations)
+    pub fn validator_stake(&self) -> u128 {
+        self.staked + self.delegators.values().sum::<u128>()
+    }
+
+    /// Governance weight: liquid balance plus everything the account has bonded
+    pub fn voting_power(&self) -> u128 {
+        self.balance + self.staked + self.delegated()
+    }
+}
+
+fn is_zero(value: &u16) -> bool {
+    *value == 0
+}
+
+/// Tokens waiting out the unbonding period
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Unbonding {
+    pub owner: String,
+    /// Validator the stake was bonded to (the owner itself for a self-bond)
+    pub validator: String,
+    pub amount: u128,
+    /// First height at which the tokens are released
+    pub release_height: u64,
+}
+
+/// `amount * part / total`, rounded down
+fn pro_rata(amount: u128, part: u128, total: u128) -> u128 {
+    if total == 0 {
+        return 0;
+    }
+    match amount.checked_mul(part) {
+        Some(product) => product / total,
+        None => amount / total * part,
+    }
 }
 
 pub struct Ledger {
@@ -30,11 +119,228 @@ impl Ledger {
         }
     }
 
+    /// Start a staged set of account changes against the current state.
+    ///
+    /// Nothing is written until the changes are passed to `commit`.
+    pub fn begin(&self) -> LedgerTxn<'_> {
+        LedgerTxn { ledger: self, writes: BTreeMap::new() }
+    }
+
+    /// Atomically write staged account changes, returning each account's
+    /// previous value (None if it did not exist) for undo.
+    pub fn commit(&mut self, writes: BTreeMap<String, KaraToken>) -> Result<Vec<(String, Option<KaraToken>)>> {
+        let mut batch = WriteBatch::default();
+        let mut previous = Vec::with_capacity(writes.len());
+        for (id, account) in writes {
+            previous.push((id.clone(), self.try_get_account(&id)));
+            batch.put(id.as_bytes(), serde_json::to_vec(&account)?);
+        }
+        self.db.write(batch).map_err(|e| anyhow::anyhow!("Ledger commit failed: {}", e))?;
+        Ok(previous)
+    }
+
+    /// Restore account values captured by `commit` (None removes the account)
+    pub fn restore(&mut self, previous: &[(String, Option<KaraToken>)]) -> Result<()> {
+        let mut batch = WriteBatch::default();
+        for (id, account) in previous {
+            match account {
+                Some(account) => batch.put(id.as_bytes(), serde_json::to_vec(account)?),
+                None => batch.delete(id.as_bytes()),
+            }
+        }
+        self.db.write(batch).map_err(|e| anyhow::anyhow!("Ledger restore failed: {}", e))
+    }
+
+    pub fn mint(&mut self, recipient: &str, amount: u128) {
+        let mut txn = self.begin();
+        txn.mint(recipient, amount);
+        let writes = txn.into_writes();
+        self.commit(writes).expect("Failed to save account");
+    }
+
+    pub fn transfer(&mut self, sender: &str, recipient: &str, amount: u128) -> Result<()> {
+        let mut txn = self.begin();
+        txn.transfer(sender, recipient, amount)?;
+        let writes = txn.into_writes();
+        self.commit(writes)?;
+        Ok(())
+    }
+    
+    /// Debit (subtract) from an account's balance
+    pub fn debit(&mut self, account_id: &str, amount: u64) {
+        let mut txn = self.begin();
+        txn.debit(account_id, amount);
+        let writes = txn.into_writes();
+        self.commit(writes).expect("Failed to save account");
+    }
+    
+    /// Credit (add) to an account's balance
+    pub fn credit(&mut self, account_id: &str, amount: u64) {
+        let mut txn = self.begin();
+        txn.credit(account_id, amount);
+        let writes = txn.into_writes();
+        self.commit(writes).expect("Failed to save account");
+    }
+    
+    /// Start unbonding self-staked tokens; they are released at
+    /// `height + UNBONDING_PERIOD`
+    pub fn unstake(&mut self, account_id: &str, amount: u128, height: u64) -> Result<()> {
+        let mut txn = self.begin();
+        txn.unstake(account_id, amount, height)?;
+        let writes = txn.into_writes();
+        self.commit(writes)?;
+        Ok(())
+    }
+
+    pub fn delegate(&mut self, delegator: &str, validator: &str, amount: u128) -> Result<()> {
+        let mut txn = self.begin();
+        txn.delegate(delegator, validator, amount)?;
+        let writes = txn.into_writes();
+        self.commit(writes)?;
+        Ok(())
+    }
+
+    pub fn undelegate(&mut self, delegator: &str, validator: &str, amount: u128, height: u64) -> Result<()> {
+        let mut txn = self.begin();
+        txn.undelegate(delegator, validator, amount, height)?;
+        let writes = txn.into_writes();
+        self.commit(writes)?;
+        Ok(())
+    }
+
+    pub fn set_commission(&mut self, validator: &str, rate_bps: u16) -> Result<()> {
+        let mut txn = self.begin();
+        txn.set_commission(validator, rate_bps)?;
+        let writes = txn.into_writes();
+        self.commit(writes)?;
+        Ok(())
+    }
+
+    /// Mint `amount` as a reward for `validator` and split it with its delegators
+    pub fn distribute_reward(&mut self, validator: &str, amount: u128) {
+        let mut txn = self.begin();
+        txn.distribute_reward(validator, amount);
+        let writes = txn.into_writes();
+        self.commit(writes).expect("Failed to save rewards");
+    }
+
+    /// Pay out unbonding entries that have matured by `height`
+    pub fn release_unbonded(&mut self, height: u64) -> Result<u128> {
+        let mut txn = self.begin();
+        let released = txn.release_unbonded(height);
+        let writes = txn.into_writes();
+        self.commit(writes)?;
+        Ok(released)
+    }
+
+    /// Pending unbonds owned by `owner`
+    pub fn unbonding_of(&self, owner: &str) -> Vec<Unbonding> {
+        self.get_account(UNBONDING_ACCOUNT).unbonding.into_iter()
+            .filter(|u| u.owner == owner)
+            .collect()
+    }
+
+    pub fn stake(&mut self, account_id: &str, amount: u128) -> Result<()> {
+        let mut txn = self.begin();
+        txn.stake(account_id, amount)?;
+        let writes = txn.into_writes();
+        self.commit(writes)?;
+        Ok(())
+    }
+
+    pub fn slash(&mut self, account_id: &str, amount: u128, reason: &str) {
+        let mut txn = self.begin();
+        txn.slash(account_id, amount, reason);
+        let writes = txn.into_writes();
+        self.commit(writes).expect("Failed to save account");
+    }
+
+    pub fn get_balance(&self, account: &str) -> u128 {
+        self.get_account(account).balance
+    }
+
+    pub fn get_account(&self, recipient: &str) -> KaraToken {
+        self.try_get_account(recipient)
+            .unwrap_or(KaraToken { reputation: 1.0, ..Default::default() })
+    }
+
+    /// Stored account, or None if it has never been written
+    pub fn try_get_account(&self, recipient: &str) -> Option<KaraToken> {
+        match self.db.get(recipient.as_bytes()) {
+            Ok(Some(data)) => Some(serde_json::from_slice(&data).unwrap_or_default()),
+            _ => None,
+        }
+    }
+
+    /// Every stored account, in key order
+    pub fn accounts(&self) -> Vec<(String, KaraToken)> {
+        self.db
+            .iterator(IteratorMode::Start)
+            .map_while(|item| item.ok())
+            .map(|(key, value)| {
+                (String::from_utf8_lossy(&key).into_owned(), serde_json::from_slice(&value).unwrap_or_default())
+            })
+            .collect()
+    }
+}
+
+/// Staged account changes over a `Ledger`
+///
+/// Reads see earlier staged writes, so several operations can be chained and
+/// then committed (or dropped) as a unit.
+pub struct LedgerTxn<'a> {
+    ledger: &'a Ledger,
+    writes: BTreeMap<String, KaraToken>,
+}
+
+impl<'a> LedgerTxn<'a> {
+    pub fn get_account(&self, id: &str) -> KaraToken {
+        self.writes.get(id).cloned().unwrap_or_else(|| self.ledger.get_account(id))
+    }
+
+    /// Consume `nonce` for `sender`; it must be exactly one past the last
+    /// confirmed nonce, which rules out replays and reordering
+    pub fn use_nonce(&mut self, sender: &str, nonce: u64) -> Result<()> {
+        let mut account = self.get_account(sender);
+        let expected = account.nonce + 1;
+        if nonce != expected {
+            return Err(anyhow::anyhow!("Invalid nonce {} for '{}' (expected {})", nonce, sender, expected));
+        }
+        account.nonce = nonce;
+        self.put(sender, account);
+        Ok(())
+    }
+
+    fn put(&mut self, id: &str, account: KaraToken) {
+        self.writes.insert(id.to_string(), account);
+    }
+
+    /// Account ids touched so far
+    pub fn touched(&self) -> impl Iterator<Item = &String> {
+        self.writes.keys()
+    }
+
+    /// Voting power of every account that has any, as staged so far
+    pub fn voting_powers(&self) -> BTreeMap<String, u64> {
+        let mut accounts: BTreeMap<String, KaraToken> = self.ledger.accounts().into_iter().collect();
+        accounts.extend(self.writes.iter().map(|(id, account)| (id.clone(), account.clone())));
+        accounts
+            .into_iter()
+            .map(|(id, account)| (id, u64::try_from(account.voting_power()).unwrap_or(u64::MAX)))
+            .filter(|&(_, power)| power > 0)
+            .collect()
+    }
+
+    /// Hand the staged writes to `Ledger::commit`
+    pub fn into_writes(self) -> BTreeMap<String, KaraToken> {
+        self.writes
+    }
+
     pub fn mint(&mut self, recipient: &str, amount: u128) {
         let mut account = self.get_account(recipient);
         account.balance += amount;
-        self.save_account(recipient, &account);
         log::info!("Atom 4 (Economy): 🪙 Minted {} KARA to Node '{}'. Balance: {} | Staked: {}", amount, recipient, account.balance, account.staked);
+        self.put(recipient, account);
     }
 
     pub fn transfer(&mut self, sender: &str, recipient: &str, amount: u128) -> Result<()> {
@@ -42,43 +348,41 @@ impl Ledger {
         if sender_acc.balance < amount {
             return Err(anyhow::anyhow!("Insufficient balance"));
         }
-        let mut recipient_acc = self.get_account(recipient);
-        
         sender_acc.balance -= amount;
+        self.put(sender, sender_acc);
+
+        // Re-read so a self-transfer sees the debit
+        let mut recipient_acc = self.get_account(recipient);
         recipient_acc.balance += amount;
-        
-        self.save_account(sender, &sender_acc);
-        self.save_account(recipient, &recipient_acc);
+        self.put(recipient, recipient_acc);
         log::info!("Atom 4 (Economy): 💸 Transferred {} KARA from '{}' to '{}'", amount, sender, recipient);
         Ok(())
     }
-    
-    /// Debit (subtract) from an account's balance
+
     pub fn debit(&mut self, account_id: &str, amount: u64) {
         let mut account = self.get_account(account_id);
         account.balance = account.balance.saturating_sub(amount as u128);
-        self.save_account(account_id, &account);
+        self.put(account_id, account);
         log::debug!("[LEDGER] Debited {} from {}", amount, account_id);
     }
-    
-    /// Credit (add) to an account's balance
+
     pub fn credit(&mut self, account_id: &str, amount: u64) {
         let mut account = self.get_account(account_id);
         account.balance += amount as u128;
-        self.save_account(account_id, &account);
+        self.put(account_id, account);
         log::debug!("[LEDGER] Credited {} to {}", amount, account_id);
     }
-    
-    /// Unstake tokens
-    pub fn unstake(&mut self, account_id: &str, amount: u64) -> Result<()> {
+
+    /// Move self-staked tokens into the unbonding queue
+    pub fn unstake(&mut self, account_id: &str, amount: u128, height: u64) -> Result<()> {
         let mut account = self.get_account(account_id);
-        if account.staked < amount as u128 {
+        if account.staked < amount {
             return Err(anyhow::anyhow!("Insufficient staked balance"));
         }
-        account.staked -= amount as u128;
-        account.balance += amount as u128;
-        self.save_account(account_id, &account);
-        log::info!("Atom 4 (Economy): 🔓 Node '{}' unstaked {} KARA.", account_id, amount);
+        account.staked -= amount;
+        self.put(account_id, account);
+        self.queue_unbonding(account_id, account_id, amount, height);
+        log::info!("Atom 4 (Economy): 🔓 Node '{}' unbonding {} KARA until block {}.", account_id, amount, height + UNBONDING_PERIOD);
         Ok(())
     }
 
@@ -89,34 +393,190 @@ impl Ledger {
         }
         account.balance -= amount;
         account.staked += amount;
-        self.save_account(account_id, &account);
+        self.put(account_id, account);
         log::info!("Atom 4 (Economy): 🔒 Node '{}' staked {} KARA.", account_id, amount);
         Ok(())
     }
 
-    pub fn slash(&mut self, account_id: &str, amount: u128, reason: &str) {
-        let mut account = self.get_account(account_id);
-        let slashed = std::cmp::min(account.staked, amount);
-        account.staked -= slashed;
-        account.reputation *= 0.8; // Reputation hit
-        self.save_account(account_id, &account);
-        log::info!("Atom 4 (Economy): ⚔️ SLASHED Node '{}' for {}. Burned {} KARA. Reputation: {:.2}", account_id, reason, slashed, account.reputation);
+    pub fn delegate(&mut self, delegator: &str, validator: &str, amount: u128) -> Result<()> {
+        if delegator == validator {
+            return Err(anyhow::anyhow!("Use stake to bond to yourself"));
+        }
+        if amount == 0 {
+            return Err(anyhow::anyhow!("Delegation amount must be positive"));
+        }
+        let mut account = self.get_account(delegator);
+        if account.balance < amount {
+            return Err(anyhow::anyhow!("Insufficient balance to delegate"));
+        }
+        account.balance -= amount;
+        *account.delegations.entry(validator.to_string()).or_default() += amount;
+        self.put(delegator, account);
+
+        let mut target = self.get_account(validator);
+        *target.delegators.entry(delegator.to_string()).or_default() += amount;
+        self.put(validator, target);
+        log::info!("Atom 4 (Economy): 🤝 '{}' delegated {} KARA to validator '{}'.", delegator, amount, validator);
+        Ok(())
     }
 
-    pub fn get_balance(&self, account: &str) -> u128 {
-        self.get_account(account).balance
+    /// Withdraw a delegation into the unbonding queue
+    pub fn undelegate(&mut self, delegator: &str, validator: &str, amount: u128, height: u64) -> Result<()> {
+        let mut account = self.get_account(delegator);
+        let bonded = account.delegations.get(validator).copied().unwrap_or(0);
+        if amount == 0 || bonded < amount {
+            return Err(anyhow::anyhow!("'{}' has only {} KARA delegated to '{}'", delegator, bonded, validator));
+        }
+        reduce(&mut account.delegations, validator, amount);
+        self.put(delegator, account);
+
+        let mut target = self.get_account(validator);
+        reduce(&mut target.delegators, delegator, amount);
+        self.put(validator, target);
+
+        self.queue_unbonding(delegator, validator, amount, height);
+        log::info!("Atom 4 (Economy): 🔓 '{}' undelegating {} KARA from '{}' until block {}.", delegator, amount, validator, height + UNBONDING_PERIOD);
+        Ok(())
     }
 
-    pub fn get_account(&self, recipient: &str) -> KaraToken {
-        match self.db.get(recipient.as_bytes()) {
-            Ok(Some(data)) => serde_json::from_slice(&data).unwrap_or_default(),
-            _ => KaraToken { balance: 0, staked: 0, reputation: 1.0 },
+    pub fn set_commission(&mut self, validator: &str, rate_bps: u16) -> Result<()> {
+        if rate_bps > MAX_COMMISSION_BPS {
+            return Err(anyhow::anyhow!("Commission {} bps exceeds {}", rate_bps, MAX_COMMISSION_BPS));
+        }
+        let mut account = self.get_account(validator);
+        account.commission_bps = rate_bps;
+        self.put(validator, account);
+        Ok(())
+    }
+
+    /// Authorize `new_public_key` (hex Ed25519) to sign for `did`
+    pub fn rotate_key(&mut self, did: &str, new_public_key: &str) -> Result<()> {
+        if hex::decode(new_public_key).map(|k| k.len()).ok() != Some(32) {
+            return Err(anyhow::anyhow!("Invalid public key for rotation"));
+        }
+        let mut account = self.get_account(did);
+        account.auth_key = Some(new_public_key.to_ascii_lowercase());
+        self.put(did, account);
+        log::info!("Atom 4 (Economy): 🔑 '{}' rotated to key {}...", did, &new_public_key[..8]);
+        Ok(())
+    }
+
+    fn queue_unbonding(&mut self, owner: &str, validator: &str, amount: u128, height: u64) {
+        let mut queue = self.get_account(UNBONDING_ACCOUNT);
+        queue.balance += amount;
+        queue.unbonding.push(Unbonding {
+            owner: owner.to_string(),
+            validator: validator.to_string(),
+            amount,
+            release_height: height + UNBONDING_PERIOD,
+        });
+        self.put(UNBONDING_ACCOUNT, queue);
+    }
+
+    /// Pay out unbonding entries that have matured by `height`, returning
+    /// the total released
+    pub fn release_unbonded(&mut self, height: u64) -> u128 {
+        let mut queue = self.get_account(UNBONDING_ACCOUNT);
+        if !queue.unbonding.iter().any(|u| u.release_height <= height) {
+            return 0;
+        }
+        let (due, pending): (Vec<_>, Vec<_>) = queue.unbonding.drain(..)
+            .partition(|u| u.release_height <= height);
+        queue.unbonding = pending;
+
+        let mut released = 0;
+        for entry in due {
+            queue.balance -= entry.amount;
+            let mut owner = self.get_account(&entry.owner);
+            owner.balance += entry.amount;
+            self.put(&entry.owner, owner);
+            released += entry.amount;
+        }
+        self.put(UNBONDING_ACCOUNT, queue);
+        released
+    }
+
+    /// Mint `amount` for `validator`: it keeps its commission, and the rest
+    /// is shared between its self-bond and its delegators by stake. Rounding
+    /// dust stays with the validator, as does everything when nothing is
+    /// bonded to it.
+    pub fn distribute_reward(&mut self, validator: &str, amount: u128) {
+        let mut account = self.get_account(validator);
+        let total = account.validator_stake();
+        let pool = amount - pro_rata(amount, account.commission_bps as u128, MAX_COMMISSION_BPS as u128);
+
+        let mut paid = 0;
+        for (delegator, stake) in &account.delegators {
+            let share = pro_rata(pool, *stake, total);
+            if share == 0 {
+                continue;
+            }
+            let mut delegator_acc = self.get_account(delegator);
+            delegator_acc.balance += share;
+            self.put(delegator, delegator_acc);
+            paid += share;
         }
+
+        account.balance += amount - paid;
+        log::debug!("[LEDGER] Reward {} KARA for '{}': {} to delegators", amount, validator, paid);
+        self.put(validator, account);
     }
 
-    fn save_account(&self, recipient: &str, account: &KaraToken) {
-        let data = serde_json::to_vec(account).unwrap();
-        self.db.put(recipient.as_bytes(), data).expect("Failed to save account");
+    /// Burn up to `amount` of the stake backing `validator`, including
+    /// delegations and stake still unbonding from it, in proportion to each
+    /// part. Returns the amount burned.
+    pub fn slash(&mut self, validator: &str, amount: u128, reason: &str) -> u128 {
+        let mut account = self.get_account(validator);
+        let mut queue = self.get_account(UNBONDING_ACCOUNT);
+        let unbonding: u128 = queue.unbonding.iter()
+            .filter(|u| u.validator == validator)
+            .map(|u| u.amount)
+            .sum();
+        let exposure = account.validator_stake() + unbonding;
+        let target = amount.min(exposure);
+
+        let own = pro_rata(account.staked, target, exposure);
+        account.staked -= own;
+        let mut burned = own;
+
+        let delegators: Vec<(String, u128)> = account.delegators.iter().map(|(d, s)| (d.clone(), *s)).collect();
+        for (delegator, stake) in delegators {
+            let cut = pro_rata(stake, target, exposure);
+            if cut == 0 {
+                continue;
+            }
+            reduce(&mut account.delegators, &delegator, cut);
+            let mut delegator_acc = self.get_account(&delegator);
+            reduce(&mut delegator_acc.delegations, validator, cut);
+            self.put(&delegator, delegator_acc);
+            burned += cut;
+        }
+
+        if unbonding > 0 {
+            for entry in queue.unbonding.iter_mut().filter(|u| u.validator == validator) {
+                let cut = pro_rata(entry.amount, target, exposure);
+                entry.amount -= cut;
+                queue.balance -= cut;
+                burned += cut;
+            }
+            queue.unbonding.retain(|u| u.amount > 0);
+            self.put(UNBONDING_ACCOUNT, queue);
+        }
+
+        account.reputation *= 0.8; // Reputation hit
+        log::info!("Atom 4 (Economy): ⚔️ SLASHED Node '{}' for {}. Burned {} KARA. Reputation: {:.2}", validator, reason, burned, account.reputation);
+        self.put(validator, account);
+        burned
+    }
+}
+
+/// Subtract from a stake map entry, dropping it once empty
+fn reduce(map: &mut BTreeMap<String, u128>, key: &str, amount: u128) {
+    if let Some(value) = map.get_mut(key) {
+        *value -= amount.min(*value);
+        if *value == 0 {
+            map.remove(key);
+        }
     }
 }
 
@@ -143,7 +603,8 @@ impl ProofOfStorage {
 
         if is_valid {
             log::info!("Atom 4 (Economy): ✅ ZK Proof Accepted. Issuing Reward.");
-            self.ledger.lock().unwrap().mint(node_id, 10); // 10 KARA reward
+            // Shared with the node's delegators like a block reward
+            self.ledger.lock().unwrap().distribute_reward(node_id, 10); // 10 KARA reward
         } else {
             log::info!("Atom 4 (Economy): ❌ ZK Proof Rejected.");
         }
@@ -152,142 +613,89 @@ impl ProofOfStorage {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Proposal {
-    pub id: u64,
-    pub title: String,
-    pub description: String,
-    pub votes_for: u64,
-    pub votes_against: u64,
-    pub status: ProposalStatus,
-    pub ai_analysis: String,
-    pub created_at: u64,
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{SystemTime, UNIX_EPOCH};
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
-pub enum ProposalStatus {
-    Active,
-    Passed,
-    Rejected,
-}
+    fn ledger(name: &str) -> (Ledger, String) {
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos();
+        let path = format!("/tmp/karana-test-economy-{}-{}", name, nanos);
+        (Ledger::new(&path), path)
+    }
 
-pub struct Governance {
-    ledger: Arc<Mutex<Ledger>>,
-    db: DB,
-    next_id: u64,
-    ai: Arc<Mutex<KaranaAI>>,
-}
+    #[test]
+    fn test_unbonding_can_be_slashed_until_released() {
+        let (mut ledger, path) = ledger("unbond");
+        ledger.mint("val", 1000);
+        ledger.mint("dan", 500);
+        ledger.stake("val", 600).unwrap();
+        ledger.delegate("dan", "val", 400).unwrap();
+        assert_eq!(ledger.get_account("dan").voting_power(), 500);
+        assert_eq!(ledger.get_account("val").validator_stake(), 1000);
 
-impl Governance {
-    pub fn new(path: &str, ledger: Arc<Mutex<Ledger>>, ai: Arc<Mutex<KaranaAI>>) -> Self {
-        let mut opts = Options::default();
-        opts.create_if_missing(true);
-        let db = DB::open(&opts, path).expect("Failed to open Governance DB");
-        
-        // Load next_id
-        let next_id = match db.get(b"next_id") {
-            Ok(Some(val)) => u64::from_le_bytes(val.try_into().unwrap()),
-            _ => 1,
-        };
+        ledger.unstake("val", 200, 10).unwrap();
+        assert_eq!(ledger.get_account("val").staked, 400);
+        assert_eq!(ledger.get_balance("val"), 400);
+        assert_eq!(ledger.unbonding_of("val")[0].release_height, 10 + UNBONDING_PERIOD);
 
-        Self {
-            ledger,
-            db,
-            next_id,
-            ai,
-        }
+        // Exposure is 400 own + 400 delegated + 200 unbonding; half is burned
+        ledger.slash("val", 500, "test");
+        assert_eq!(ledger.get_account("val").staked, 200);
+        assert_eq!(ledger.get_account("val").delegators["dan"], 200);
+        assert_eq!(ledger.get_account("dan").delegations["val"], 200);
+        assert_eq!(ledger.unbonding_of("val")[0].amount, 100);
+        assert_eq!(ledger.get_balance(UNBONDING_ACCOUNT), 100);
+
+        assert_eq!(ledger.release_unbonded(10 + UNBONDING_PERIOD - 1).unwrap(), 0);
+        assert_eq!(ledger.release_unbonded(10 + UNBONDING_PERIOD).unwrap(), 100);
+        assert_eq!(ledger.get_balance("val"), 500);
+        assert!(ledger.unbonding_of("val").is_empty());
+        assert_eq!(ledger.get_balance(UNBONDING_ACCOUNT), 0);
+
+        let _ = std::fs::remove_dir_all(&path);
     }
 
-    pub fn create_proposal(&mut self, description: &str) -> u64 {
-        let id = self.next_id;
-        self.next_id += 1;
-        
-        // Save next_id
-        self.db.put(b"next_id", self.next_id.to_le_bytes()).expect("Failed to save next_id");
-
-        // Atom 4: AI Analysis of Proposal
-        let prompt = format!("Analyze this governance proposal for risks/benefits: '{}'. Keep it short.", description);
-        let analysis = self.ai.lock().unwrap().predict(&prompt, 30).unwrap_or_else(|_| "Analysis failed".to_string());
-
-        let proposal = Proposal {
-            id,
-            title: description.to_string(), // Use description as title for now
-            description: description.to_string(),
-            votes_for: 0,
-            votes_against: 0,
-            status: ProposalStatus::Active,
-            ai_analysis: analysis.trim().to_string(),
-            created_at: std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_secs(),
-        };
-        
-        self.save_proposal(&proposal);
-        log::info!("Atom 4 (Governance): 📜 Proposal #{} Created: '{}'", id, description);
-        log::info!("Atom 4 (Governance): 🤖 AI Analysis: {}", proposal.ai_analysis);
-        id
-    }
-
-    pub fn vote(&mut self, proposal_id: u64, voter: &str, approve: bool) -> Result<()> {
-        let _balance = self.ledger.lock().unwrap().get_balance(voter);
-        // Allow voting even with 0 balance for demo, or check stake?
-        // Let's say voting power = balance + stake
-        let account = self.ledger.lock().unwrap().get_account(voter);
-        let power = account.balance + account.staked;
-
-        if power == 0 {
-            log::info!("Atom 4 (Governance): ⚠️ Node '{}' has no Power (KARA/Stake) to vote.", voter);
-            return Ok(());
-        }
+    #[test]
+    fn test_rewards_split_by_stake_after_commission() {
+        let (mut ledger, path) = ledger("rewards");
+        ledger.mint("val", 300);
+        ledger.mint("a", 600);
+        ledger.mint("b", 100);
+        ledger.stake("val", 300).unwrap();
+        ledger.delegate("a", "val", 600).unwrap();
+        ledger.delegate("b", "val", 100).unwrap();
+        ledger.set_commission("val", 1_000).unwrap();
 
-        if let Some(mut proposal) = self.get_proposal(proposal_id) {
-            if proposal.status != ProposalStatus::Active {
-                log::info!("Atom 4 (Governance): ⚠️ Proposal #{} is closed.", proposal_id);
-                return Ok(());
-            }
+        // 10 commission, then 90 split 30/60/10
+        ledger.distribute_reward("val", 100);
+        assert_eq!(ledger.get_balance("a"), 54);
+        assert_eq!(ledger.get_balance("b"), 9);
+        assert_eq!(ledger.get_balance("val"), 37);
 
-            if approve {
-                proposal.votes_for += power as u64;
-            } else {
-                proposal.votes_against += power as u64;
-            }
-            
-            self.save_proposal(&proposal);
-            log::info!("Atom 4 (Governance): 🗳️ Node '{}' voted {} with power {}.", voter, if approve { "YES" } else { "NO" }, power);
-        } else {
-             log::info!("Atom 4 (Governance): ⚠️ Proposal #{} not found.", proposal_id);
-        }
-        Ok(())
-    }
-    
-    /// Get all active proposals
-    pub fn get_active_proposals(&self) -> Vec<Proposal> {
-        let mut proposals = Vec::new();
-        
-        // Iterate through all proposals (simple approach)
-        for id in 1..self.next_id {
-            if let Some(p) = self.get_proposal(id) {
-                if p.status == ProposalStatus::Active {
-                    proposals.push(p);
-                }
-            }
-        }
-        
-        proposals
-    }
+        // Nothing bonded: the whole reward goes to the node
+        ledger.distribute_reward("solo", 10);
+        assert_eq!(ledger.get_balance("solo"), 10);
 
-    fn save_proposal(&self, proposal: &Proposal) {
-        let key = format!("proposal:{}", proposal.id);
-        let data = serde_json::to_vec(proposal).unwrap();
-        self.db.put(key.as_bytes(), data).expect("Failed to save proposal");
+        let _ = std::fs::remove_dir_all(&path);
     }
 
-    pub fn get_proposal(&self, id: u64) -> Option<Proposal> {
-        let key = format!("proposal:{}", id);
-        match self.db.get(key.as_bytes()) {
-            Ok(Some(data)) => serde_json::from_slice(&data).ok(),
-            _ => None,
-        }
+    #[test]
+    fn test_staking_rejects_invalid_requests() {
+        let (mut ledger, path) = ledger("invalid");
+        ledger.mint("dan", 100);
+        assert!(ledger.delegate("dan", "dan", 10).is_err());
+        assert!(ledger.delegate("dan", "val", 200).is_err());
+        ledger.delegate("dan", "val", 50).unwrap();
+        assert!(ledger.undelegate("dan", "val", 60, 1).is_err());
+        assert!(ledger.undelegate("dan", "other", 10, 1).is_err());
+        assert!(ledger.set_commission("val", MAX_COMMISSION_BPS + 1).is_err());
+        assert!(ledger.unstake("dan", 1, 1).is_err());
+
+        ledger.undelegate("dan", "val", 50, 1).unwrap();
+        assert!(ledger.get_account("val").delegators.is_empty());
+        assert!(ledger.get_account("dan").delegations.is_empty());
+
+        let _ = std::fs::remove_dir_all(&path);
     }
 }
@@ -1,8 +1,9 @@
 use clap::Parser;
 use anyhow::Result;
 use crate::ai::KaranaAI;
-use crate::gov::KaranaDAO;
-use alloy_primitives::U256;
+use crate::economy::Ledger;
+use crate::gov::{Governance, ProposalAction};
+use std::sync::{Arc, Mutex};
 
 #[derive(Parser, Debug)]
 #[command(name = "karana")]
@@ -43,11 +44,13 @@ pub fn run_install(mode: String) -> Result<()> {
     let intent = ai.predict("Probe hardware & suggest setup", 20)?;
     println!("\n[AI Probe] {}", intent); 
 
-    // Phase 4: DAO / Token Setup
-    let mut dao = KaranaDAO::default();
+    // Phase 4: DAO / Token Setup (scratch ledger, discarded after install)
+    let setup_dir = std::env::temp_dir().join(format!("karana-install-{}", std::process::id()));
+    let ledger = Arc::new(Mutex::new(Ledger::new(&setup_dir.join("ledger").to_string_lossy())));
+    let mut gov = Governance::new(&setup_dir.join("governance").to_string_lossy(), ledger.clone(), Arc::new(Mutex::new(ai)));
     println!("\n[Identity] Generating DID... Done.");
     println!("[Economy] Minting initial KARA to 'new-user'...");
-    dao.token.mint("new-user", U256::from(100u64));
+    ledger.lock().unwrap().mint("new-user", 100);
     println!("          Balance: 100 KARA");
 
     if mode == "disk" {
@@ -58,14 +61,20 @@ pub fn run_install(mode: String) -> Result<()> {
 
     // Phase 6: DAO Vote on Setup
     println!("\n[Governance] Proposing 'Initial System Tune' based on AI probe...");
-    let prop_id = dao.propose("Initial Tune", &intent);
-    println!("             Proposal ID: {}", prop_id);
-    
-    if dao.vote("new-user", prop_id, true).unwrap() {
-        dao.execute_if_passed(prop_id, &mut |_id| {
-            println!("[Exec] DAO Passed! System tuned for '{}'.", intent);
-        });
-    }
+    // No chain runs during install, so the scratch DAO stages its own block
+    let changes = {
+        let ledger = ledger.lock().unwrap();
+        let mut txn = gov.begin(gov.height());
+        let prop_id = txn.create_proposal("new-user", "Initial Tune", &intent, ProposalAction::Signal, &ledger.begin())?;
+        println!("             Proposal ID: {}", prop_id);
+        txn.vote(prop_id, "new-user", true)?;
+        txn.into_changes()
+    };
+    gov.commit(changes)?;
+    println!("[Exec] Vote cast. System will tune for '{}' once the network tallies it.", intent);
+    drop(gov);
+    drop(ledger);
+    let _ = std::fs::remove_dir_all(&setup_dir);
 
     println!("\n>>> Installation Complete. Reboot to ignite. <<<");
     Ok(())
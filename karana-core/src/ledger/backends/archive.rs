@@ -0,0 +1,273 @@
+// Ledger archive: the export/import format shared by every backend
+//
+// An archive is a backend-neutral copy of a whole ledger, so data exported
+// from any `LedgerBackend` can be imported into any other (e.g. moving a
+// personal device from the signed log to the file log, or onto the chain).
+//
+// Layout (version 1), all integers little-endian:
+//
+//   magic        8 bytes   b"KARANALA"
+//   version      u16       ARCHIVE_VERSION
+//   flags        u16       reserved, 0
+//   source_len   u16       length of the source backend name
+//   source       bytes     backend name (UTF-8), e.g. "file-log"
+//   count        u64       number of records
+//   records      count x { len: u32, payload: JSON(ArchiveRecord) }
+//   digest       32 bytes  SHA-256 of everything before it
+//
+// Records hold blocks in height order starting at genesis, each linked to its
+// predecessor by parent hash. JSON is used for the payload because `Block`
+// relies on serde attributes that a non-self-describing encoding cannot
+// round-trip. `attestation` carries backend-specific proof bytes (the signed
+// log's entry signature); backends that have none leave it empty and
+// importers that do not understand it ignore it.
+//
+// Readers reject archives with a newer version; fields may only be added to
+// `ArchiveRecord` with `#[serde(default)]`, which keeps older readers working
+// within the same version.
+
+use crate::chain::Block;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+pub const ARCHIVE_MAGIC: &[u8; 8] = b"KARANALA";
+pub const ARCHIVE_VERSION: u16 = 1;
+
+/// One block plus any backend-specific proof
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveRecord {
+    pub block: Block,
+    #[serde(default, with = "hex_bytes")]
+    pub attestation: Vec<u8>,
+}
+
+/// A full ledger in transit between backends
+#[derive(Debug, Clone)]
+pub struct LedgerArchive {
+    /// Name of the backend that produced the archive
+    pub source: String,
+    pub records: Vec<ArchiveRecord>,
+}
+
+impl LedgerArchive {
+    pub fn new(source: &str) -> Self {
+        Self {
+            source: source.to_string(),
+            records: Vec::new(),
+        }
+    }
+
+    /// Archive of plain blocks (no attestations)
+    pub fn from_blocks(source: &str, blocks: Vec<Block>) -> Self {
+        let mut archive = Self::new(source);
+        for block in blocks {
+            archive.push(block, Vec::new());
+        }
+        archive
+    }
+
+    pub fn push(&mut self, block: Block, attestation: Vec<u8>) {
+        self.records.push(ArchiveRecord { block, attestation });
+    }
+
+    pub fn blocks(&self) -> impl Iterator<Item = &Block> {
+        self.records.iter().map(|r| &r.block)
+    }
+
+    pub fn into_blocks(self) -> Vec<Block> {
+        self.records.into_iter().map(|r| r.block).collect()
+    }
+
+    /// Height of the last block
+    pub fn tip_height(&self) -> Option<u64> {
+        self.records.last().map(|r| r.block.header.height)
+    }
+
+    /// Serialize to the versioned binary format
+    pub fn encode(&self) -> Result<Vec<u8>> {
+        self.check_links()?;
+        let source = self.source.as_bytes();
+        if source.len() > u16::MAX as usize {
+            return Err(anyhow::anyhow!("Archive source name too long"));
+        }
+
+        let mut out = Vec::new();
+        out.extend_from_slice(ARCHIVE_MAGIC);
+        out.extend_from_slice(&ARCHIVE_VERSION.to_le_bytes());
+        out.extend_from_slice(&0u16.to_le_bytes());
+        out.extend_from_slice(&(source.len() as u16).to_le_bytes());
+        out.extend_from_slice(source);
+        out.extend_from_slice(&(self.records.len() as u64).to_le_bytes());
+        for record in &self.records {
+            let payload = serde_json::to_vec(record)?;
+            out.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+            out.extend_from_slice(&payload);
+        }
+        let digest = Sha256::digest(&out);
+        out.extend_from_slice(&digest);
+        Ok(out)
+    }
+
+    /// Parse and verify an archive: magic, version, digest, and that the
+    /// blocks form a single chain from genesis
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        if !Self::is_archive(data) {
+            return Err(anyhow::anyhow!("Not a ledger archive"));
+        }
+        if data.len() < ARCHIVE_MAGIC.len() + 32 {
+            return Err(anyhow::anyhow!("Archive truncated"));
+        }
+        let (body, digest) = data.split_at(data.len() - 32);
+        if Sha256::digest(body).as_slice() != digest {
+            return Err(anyhow::anyhow!("Archive checksum mismatch"));
+        }
+
+        let mut reader = Reader { data: body, pos: ARCHIVE_MAGIC.len() };
+        let version = u16::from_le_bytes(reader.take_array()?);
+        if version > ARCHIVE_VERSION {
+            return Err(anyhow::anyhow!("Unsupported archive version {} (max {})", version, ARCHIVE_VERSION));
+        }
+        let _flags = u16::from_le_bytes(reader.take_array()?);
+        let source_len = u16::from_le_bytes(reader.take_array()?) as usize;
+        let source = String::from_utf8(reader.take(source_len)?.to_vec())
+            .context("Archive source name is not UTF-8")?;
+        let count = u64::from_le_bytes(reader.take_array()?);
+
+        let mut archive = Self::new(&source);
+        for i in 0..count {
+            let len = u32::from_le_bytes(reader.take_array()?) as usize;
+            let record: ArchiveRecord = serde_json::from_slice(reader.take(len)?)
+                .with_context(|| format!("Archive record {} is malformed", i))?;
+            archive.records.push(record);
+        }
+        if reader.pos != body.len() {
+            return Err(anyhow::anyhow!("Trailing bytes after archive records"));
+        }
+
+        archive.check_links()?;
+        Ok(archive)
+    }
+
+    /// Whether `data` starts with the archive magic
+    pub fn is_archive(data: &[u8]) -> bool {
+        data.starts_with(ARCHIVE_MAGIC)
+    }
+
+    /// Blocks must start at genesis and link by height and parent hash
+    fn check_links(&self) -> Result<()> {
+        let mut prev: Option<&Block> = None;
+        for block in self.blocks() {
+            match prev {
+                None if block.header.height != 0 => {
+                    return Err(anyhow::anyhow!("Archive must start at genesis, found height {}", block.header.height));
+                }
+                Some(parent) if block.header.height != parent.header.height + 1 => {
+                    return Err(anyhow::anyhow!("Archive height gap after {}", parent.header.height));
+                }
+                Some(parent) if block.header.parent_hash != parent.hash => {
+                    return Err(anyhow::anyhow!("Archive block {} does not link to its parent", block.header.height));
+                }
+                _ => {}
+            }
+            prev = Some(block);
+        }
+        Ok(())
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        let end = self.pos.checked_add(len).filter(|end| *end <= self.data.len())
+            .ok_or_else(|| anyhow::anyhow!("Archive truncated"))?;
+        let bytes = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn take_array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        let mut out = [0u8; N];
+        out.copy_from_slice(self.take(N)?);
+        Ok(out)
+    }
+}
+
+mod hex_bytes {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_str(&hex::encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Vec<u8>, D::Error> {
+        let s = String::deserialize(d)?;
+        hex::decode(s).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chain::{Transaction, TransactionData};
+
+    fn chain(len: u64) -> Vec<Block> {
+        let mut blocks = vec![Block::genesis()];
+        for height in 1..len {
+            let tx = Transaction {
+                sender: "alice".to_string(),
+                data: TransactionData::Transfer { to: "bob".to_string(), amount: height as u128 },
+                signature: "sig".to_string(),
+                nonce: height,
+                public_key: None,
+                hash: format!("tx-{}", height),
+                timestamp: 0,
+            };
+            let parent = blocks.last().unwrap().hash.clone();
+            blocks.push(Block::new(parent, height, "v".to_string(), vec![tx]));
+        }
+        blocks
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut archive = LedgerArchive::from_blocks("test", chain(4));
+        archive.records[2].attestation = vec![1, 2, 3];
+        let data = archive.encode().unwrap();
+
+        let decoded = LedgerArchive::decode(&data).unwrap();
+        assert_eq!(decoded.source, "test");
+        assert_eq!(decoded.tip_height(), Some(3));
+        assert_eq!(decoded.records[2].attestation, vec![1, 2, 3]);
+        assert_eq!(decoded.records[3].block.hash, archive.records[3].block.hash);
+        assert!(decoded.records[1].block.transactions[0].public_key.is_none());
+    }
+
+    #[test]
+    fn test_rejects_corruption() {
+        let mut data = LedgerArchive::from_blocks("test", chain(3)).encode().unwrap();
+        let mid = data.len() / 2;
+        data[mid] ^= 0xff;
+        assert!(LedgerArchive::decode(&data).is_err());
+
+        assert!(LedgerArchive::decode(b"not an archive").is_err());
+    }
+
+    #[test]
+    fn test_rejects_newer_version_and_broken_links() {
+        let mut data = LedgerArchive::from_blocks("test", chain(2)).encode().unwrap();
+        data[8..10].copy_from_slice(&(ARCHIVE_VERSION + 1).to_le_bytes());
+        let body_len = data.len() - 32;
+        let digest = Sha256::digest(&data[..body_len]);
+        data[body_len..].copy_from_slice(&digest);
+        assert!(LedgerArchive::decode(&data).unwrap_err().to_string().contains("version"));
+
+        let mut blocks = chain(3);
+        blocks.remove(1);
+        assert!(LedgerArchive::from_blocks("test", blocks).encode().is_err());
+    }
+}
@@ -5,22 +5,34 @@
 // - Celestia DA integration
 // - Block production and validation
 // - Complete transaction history
+// - Fork choice and block catch-up from swarm peers (see `chain::sync`)
 
+use super::archive::LedgerArchive;
 use super::{LedgerBackend, BackendStats, BackendConfig, SyncResult};
-use crate::chain::{Block, Transaction, Blockchain};
+use crate::chain::{Block, Transaction, Blockchain, ImportOutcome, Mempool, MempoolConfig, SyncProgress};
+use crate::chain::sync as chain_sync;
 use crate::economy::{Ledger, Governance};
 use crate::ai::KaranaAI;
+use crate::net::{KaranaSwarm, KaranaSwarmEvent};
 use anyhow::{Result, Context};
 use async_trait::async_trait;
 use std::sync::{Arc, Mutex};
-use tokio::time::{interval, Duration};
+use tokio::sync::mpsc;
+use tokio::time::{interval, timeout, Duration, Instant};
+
+/// How long `sync` waits for a peer to answer one request
+const SYNC_TIMEOUT: Duration = Duration::from_secs(10);
 
 /// Blockchain backend - full consensus
 pub struct BlockchainBackend {
     config: BackendConfig,
     blockchain: Arc<Mutex<Blockchain>>,
-    pending_txs: Arc<Mutex<Vec<Transaction>>>,
+    pending_txs: Arc<Mutex<Mempool>>,
     running: Arc<Mutex<bool>>,
+    /// Swarm used for block gossip and catch-up (None = offline)
+    swarm: Arc<Mutex<Option<KaranaSwarm>>>,
+    /// Progress of imported sync responses, tagged with their request id
+    sync_rx: Option<mpsc::UnboundedReceiver<(String, SyncProgress)>>,
 }
 
 impl BlockchainBackend {
@@ -31,21 +43,90 @@ impl BlockchainBackend {
         let ledger = Arc::new(Mutex::new(Ledger::new(&ledger_path)));
         // Governance requires path, ledger, and AI - creating with default path
         let ai = Arc::new(Mutex::new(KaranaAI::new().context("Failed to initialize AI")?));
-        let gov = Arc::new(Mutex::new(Governance::new("governance.db", ledger.clone(), ai)));
+        let gov_path = format!("{}/governance.db", config.data_dir);
+        let gov = Arc::new(Mutex::new(Governance::new(&gov_path, ledger.clone(), ai)));
         
-        // Create blockchain
-        let blockchain = Arc::new(Mutex::new(Blockchain::new(
-            ledger.clone(),
-            gov.clone(),
-        )));
+        // Open the persistent chain next to the ledger so both survive a restart together
+        let chain_path = format!("{}/chain", config.data_dir);
+        let blockchain = Arc::new(Mutex::new(
+            Blockchain::open(&chain_path, ledger.clone(), gov.clone())
+                .context("Failed to open block store")?,
+        ));
         
         Ok(Self {
             config,
             blockchain,
-            pending_txs: Arc::new(Mutex::new(Vec::new())),
+            pending_txs: Arc::new(Mutex::new(Mempool::new(MempoolConfig::default()))),
             running: Arc::new(Mutex::new(false)),
+            swarm: Arc::new(Mutex::new(None)),
+            sync_rx: None,
         })
     }
+
+    /// Join a swarm: produced blocks are gossiped, received blocks go
+    /// through fork choice, and peers' sync requests are answered.
+    ///
+    /// Spawns a task that consumes the swarm's events for as long as the
+    /// backend lives.
+    pub fn attach_swarm(&mut self, swarm: KaranaSwarm) {
+        *self.swarm.lock().unwrap() = Some(swarm.clone());
+        let (sync_tx, sync_rx) = mpsc::unbounded_channel();
+        self.sync_rx = Some(sync_rx);
+
+        let blockchain = self.blockchain.clone();
+        let pending_txs = self.pending_txs.clone();
+        tokio::spawn(async move {
+            while !sync_tx.is_closed() {
+                let Some(event) = swarm.poll_event() else {
+                    tokio::time::sleep(Duration::from_millis(50)).await;
+                    continue;
+                };
+                match event {
+                    KaranaSwarmEvent::BlockReceived(block) => {
+                        let request = {
+                            let chain = blockchain.lock().unwrap();
+                            let received = block.clone();
+                            match chain.import_block(block) {
+                                Ok(ImportOutcome::Extended) => {
+                                    pending_txs.lock().unwrap().on_block_committed(&received, |did| chain.account_nonce(did));
+                                    None
+                                }
+                                Ok(ImportOutcome::Reorged { reverted }) => {
+                                    pending_txs.lock().unwrap().on_reorg(&reverted, |did| chain.account_nonce(did));
+                                    None
+                                }
+                                Ok(ImportOutcome::Orphan { .. }) => Some(chain_sync::request(&chain)),
+                                Ok(_) => None,
+                                Err(e) => {
+                                    log::warn!("[LEDGER] Rejected block #{} from peer: {}", received.header.height, e);
+                                    None
+                                }
+                            }
+                        };
+                        if let Some(req) = request {
+                            let _ = swarm.request_sync(req).await;
+                        }
+                    }
+                    KaranaSwarmEvent::SyncRequested(req) => {
+                        let res = chain_sync::serve(&blockchain.lock().unwrap(), &req);
+                        if !res.blocks.is_empty() {
+                            let _ = swarm.send_sync_response(res).await;
+                        }
+                    }
+                    KaranaSwarmEvent::SyncReceived(res) => {
+                        let progress = {
+                            let chain = blockchain.lock().unwrap();
+                            let progress = chain_sync::apply(&chain, &res);
+                            pending_txs.lock().unwrap().on_reorg(&progress.reverted, |did| chain.account_nonce(did));
+                            progress
+                        };
+                        let _ = sync_tx.send((res.request_id, progress));
+                    }
+                    _ => {}
+                }
+            }
+        });
+    }
     
     /// Start block production loop
     pub async fn start_block_production(&self) -> Result<()> {
@@ -59,7 +140,10 @@ impl BlockchainBackend {
         let blockchain = self.blockchain.clone();
         let pending_txs = self.pending_txs.clone();
         let running = self.running.clone();
+        let swarm = self.swarm.clone();
         let block_time = self.config.block_time_secs;
+        // Rough cap so a block stays under max_block_size (~1KB per tx)
+        let max_txs_per_block = (self.config.max_block_size / 1024).max(1);
         
         tokio::spawn(async move {
             let mut ticker = interval(Duration::from_secs(block_time));
@@ -72,38 +156,41 @@ impl BlockchainBackend {
                     break;
                 }
                 
-                // Collect pending transactions
+                // Collect pending transactions in nonce/arrival order
                 let transactions = {
                     let mut txs = pending_txs.lock().unwrap();
-                    let collected: Vec<Transaction> = txs.drain(..).collect();
-                    collected
+                    let evicted = txs.evict_stale();
+                    if evicted > 0 {
+                        log::info!("[LEDGER] Evicted {} stale transactions from mempool", evicted);
+                    }
+                    txs.select(max_txs_per_block)
                 };
                 
                 if transactions.is_empty() {
                     continue;
                 }
                 
-                // Create and add block
-                if transactions.is_empty() {
-                    continue;
-                }
-                
+                // Execute and seal the block with its state root
                 let chain = blockchain.lock().unwrap();
-                let latest = chain.latest_block();
-                let new_height = latest.header.height + 1;
-                
-                let new_block = Block::new(
-                    latest.hash.clone(),
-                    new_height,
-                    "local".to_string(),
-                    transactions,
-                );
-                
-                if let Err(e) = chain.add_block(new_block.clone()) {
-                    eprintln!("Failed to add block: {}", e);
-                } else {
-                    // Apply block to update state
-                    let _ = chain.apply_block(&new_block);
+                match chain.produce_block("local", transactions.clone()) {
+                    Ok(block) => {
+                        let mut txs = pending_txs.lock().unwrap();
+                        // Anything selected but left out failed execution
+                        for tx in transactions.iter().filter(|tx| !block.transactions.iter().any(|b| b.sender == tx.sender && b.nonce == tx.nonce)) {
+                            txs.remove(tx);
+                        }
+                        txs.on_block_committed(&block, |did| chain.account_nonce(did));
+                        drop(txs);
+                        drop(chain);
+
+                        let peer_swarm = swarm.lock().unwrap().clone();
+                        if let Some(peer_swarm) = peer_swarm {
+                            if let Err(e) = peer_swarm.broadcast_chain_block(&block).await {
+                                log::warn!("[LEDGER] Failed to gossip block #{}: {}", block.header.height, e);
+                            }
+                        }
+                    }
+                    Err(e) => log::warn!("[LEDGER] Failed to produce block: {}", e),
                 }
             }
         });
@@ -143,26 +230,7 @@ impl LedgerBackend for BlockchainBackend {
     
     async fn get_block_by_hash(&self, hash: &str) -> Result<Option<Block>> {
         let chain = self.blockchain.lock().unwrap();
-        
-        // Iterate through all blocks to find matching hash
-        let mut current_height = chain.height();
-        while current_height > 0 {
-            if let Some(block) = chain.get_block(current_height) {
-                if block.hash == hash {
-                    return Ok(Some(block));
-                }
-            }
-            current_height = current_height.saturating_sub(1);
-        }
-        
-        // Check genesis
-        if let Some(block) = chain.get_block(0) {
-            if block.hash == hash {
-                return Ok(Some(block));
-            }
-        }
-        
-        Ok(None)
+        Ok(chain.get_block_by_hash(hash))
     }
     
     async fn get_height(&self) -> Result<u64> {
@@ -176,19 +244,19 @@ impl LedgerBackend for BlockchainBackend {
     }
     
     async fn add_transaction(&mut self, tx: Transaction) -> Result<()> {
-        // Verify transaction before adding
-        if !tx.verify() {
-            return Err(anyhow::anyhow!("Invalid transaction signature"));
-        }
-        
+        // Signature, nonce and pool limits are checked by the mempool
+        let (confirmed, authorized) = {
+            let chain = self.blockchain.lock().unwrap();
+            (chain.account_nonce(&tx.sender), chain.authorized_key(&tx.sender))
+        };
         let mut pending = self.pending_txs.lock().unwrap();
-        pending.push(tx);
+        pending.add_authorized(tx, confirmed, authorized.as_deref())?;
         Ok(())
     }
     
     async fn get_pending_transactions(&self) -> Result<Vec<Transaction>> {
         let pending = self.pending_txs.lock().unwrap();
-        Ok(pending.clone())
+        Ok(pending.transactions())
     }
     
     async fn clear_pending_transactions(&mut self) -> Result<()> {
@@ -248,13 +316,59 @@ impl LedgerBackend for BlockchainBackend {
     }
     
     async fn sync(&mut self) -> Result<SyncResult> {
-        // TODO: Implement P2P sync
-        // For now, return "already synced"
+        let start = Instant::now();
+        let swarm = self.swarm.lock().unwrap().clone();
+        let (swarm, sync_rx) = match (swarm, self.sync_rx.as_mut()) {
+            (Some(swarm), Some(rx)) if swarm.has_peers() => (swarm, rx),
+            // Offline: nothing to catch up with
+            _ => {
+                return Ok(SyncResult {
+                    synced: true,
+                    blocks_downloaded: 0,
+                    peers_contacted: 0,
+                    time_ms: start.elapsed().as_millis() as u64,
+                });
+            }
+        };
+        let peers_contacted = swarm.peer_count() as usize;
+
+        let mut blocks_downloaded = 0;
+        let synced = loop {
+            let req = chain_sync::request(&self.blockchain.lock().unwrap());
+            let request_id = req.request_id.clone();
+            swarm.request_sync(req).await?;
+
+            // Responses to other nodes' requests are imported too; wait for ours
+            let wait = async {
+                while let Some((id, progress)) = sync_rx.recv().await {
+                    if id == request_id {
+                        return Some(progress);
+                    }
+                }
+                None
+            };
+            let progress = match timeout(SYNC_TIMEOUT, wait).await {
+                Ok(Some(progress)) => progress,
+                _ => break false,
+            };
+
+            blocks_downloaded += progress.imported;
+            if progress.caught_up {
+                break true;
+            }
+            if progress.imported == 0 {
+                break false;
+            }
+        };
+
+        log::info!("[LEDGER] Sync {}: {} blocks from {} peers, tip #{}",
+            if synced { "complete" } else { "incomplete" },
+            blocks_downloaded, peers_contacted, self.blockchain.lock().unwrap().height());
         Ok(SyncResult {
-            synced: true,
-            blocks_downloaded: 0,
-            peers_contacted: 0,
-            time_ms: 0,
+            synced,
+            blocks_downloaded,
+            peers_contacted,
+            time_ms: start.elapsed().as_millis() as u64,
         })
     }
     
@@ -269,31 +383,45 @@ impl LedgerBackend for BlockchainBackend {
             }
         }
         
-        bincode::serialize(&blocks)
+        LedgerArchive::from_blocks(self.name(), blocks).encode()
             .context("Failed to serialize blockchain for export")
     }
     
+    /// Import an archive from any backend
+    ///
+    /// Archives that share our genesis are chain data: every block is
+    /// validated and imported through fork choice. Anything else (e.g. a
+    /// signed log, whose hashes are not block hashes) is replayed: its
+    /// transactions are executed into new blocks on top of our tip. A
+    /// transaction that fails to replay fails the import, and the blocks
+    /// replayed before it are reverted.
     async fn import(&mut self, data: Vec<u8>) -> Result<()> {
-        let blocks: Vec<Block> = bincode::deserialize(&data)
+        let archive = LedgerArchive::decode(&data)
             .context("Failed to deserialize blockchain import")?;
         
-        // Validate all blocks
-        for (i, block) in blocks.iter().enumerate() {
-            if i == 0 {
-                // Genesis block
-                continue;
-            }
-            
-            let parent_hash = &blocks[i - 1].hash;
-            block.validate(parent_hash)
-                .context(format!("Invalid block at height {}", block.header.height))?;
-        }
-        
-        // Import blocks one by one
         let chain = self.blockchain.lock().unwrap();
-        for block in blocks.into_iter().skip(1) {  // Skip genesis as it already exists
-            chain.add_block(block.clone())?;
-            chain.apply_block(&block)?;
+        let genesis = chain.get_block(0).map(|b| b.hash);
+        let native = archive.blocks().next().map(|b| Some(b.hash.clone()) == genesis).unwrap_or(false);
+        
+        if native {
+            for block in archive.into_blocks().into_iter().skip(1) {  // Skip genesis as it already exists
+                let height = block.header.height;
+                chain.import_block(block)
+                    .context(format!("Invalid block at height {}", height))?;
+            }
+        } else {
+            log::info!("[LEDGER] Replaying {} blocks from {} archive", archive.records.len(), archive.source);
+            let mut replayed = 0;
+            for block in archive.into_blocks().into_iter().filter(|b| !b.transactions.is_empty()) {
+                let height = block.header.height;
+                if let Err(e) = chain.produce_block_exact("import", block.transactions) {
+                    for _ in 0..replayed {
+                        chain.revert_tip()?;
+                    }
+                    return Err(e.context(format!("Failed to replay archived block {}", height)));
+                }
+                replayed += 1;
+            }
         }
         
         Ok(())
@@ -305,9 +433,17 @@ mod tests {
     use super::*;
     use crate::chain::TransactionData;
     
+    fn temp_config(name: &str) -> BackendConfig {
+        let nanos = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_nanos();
+        BackendConfig {
+            data_dir: format!("/tmp/karana-test-{}-{}", name, nanos),
+            ..BackendConfig::default()
+        }
+    }
+    
     #[tokio::test]
     async fn test_blockchain_backend_init() {
-        let config = BackendConfig::default();
+        let config = temp_config("init");
         let mut backend = BlockchainBackend::new(config).unwrap();
         
         backend.init().await.unwrap();
@@ -320,7 +456,7 @@ mod tests {
     
     #[tokio::test]
     async fn test_add_transaction() {
-        let config = BackendConfig::default();
+        let config = temp_config("add-tx");
         let mut backend = BlockchainBackend::new(config).unwrap();
         
         let tx = Transaction {
@@ -344,7 +480,7 @@ mod tests {
     
     #[tokio::test]
     async fn test_get_stats() {
-        let config = BackendConfig::default();
+        let config = temp_config("stats");
         let backend = BlockchainBackend::new(config).unwrap();
         
         let stats = backend.get_stats().await.unwrap();
@@ -353,4 +489,115 @@ mod tests {
         assert_eq!(stats.total_blocks, 1); // Genesis
         assert_eq!(stats.pending_transactions, 0);
     }
+
+    #[tokio::test]
+    async fn test_blocks_survive_restart() {
+        let config = temp_config("restart");
+        let tip = {
+            let backend = BlockchainBackend::new(config.clone()).unwrap();
+            for _ in 0..2 {
+                backend.blockchain.lock().unwrap().produce_block("local", vec![]).unwrap();
+            }
+            backend.get_latest_block().await.unwrap().unwrap()
+        };
+        
+        let reopened = BlockchainBackend::new(config).unwrap();
+        assert_eq!(reopened.get_height().await.unwrap(), 2);
+        assert_eq!(reopened.get_latest_block().await.unwrap().unwrap().hash, tip.hash);
+    }
+
+    #[tokio::test]
+    async fn test_migrate_through_file_log() {
+        use crate::ledger::backends::file_log::FileLogBackend;
+        
+        let nanos = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_nanos();
+        let config = |node: &str| BackendConfig {
+            data_dir: format!("/tmp/karana-test-migrate-{}-{}", node, nanos),
+            ..BackendConfig::default()
+        };
+        let source = BlockchainBackend::new(config("chain")).unwrap();
+        for _ in 0..2 {
+            source.blockchain.lock().unwrap().produce_block("local", vec![]).unwrap();
+        }
+        let tip = source.get_latest_block().await.unwrap().unwrap();
+        
+        // chain -> file-log -> fresh chain keeps the same blocks
+        let mut file_log = FileLogBackend::new(config("file")).unwrap();
+        file_log.import(source.export().await.unwrap()).await.unwrap();
+        assert_eq!(file_log.get_latest_block().await.unwrap().unwrap().hash, tip.hash);
+        
+        let mut target = BlockchainBackend::new(config("target")).unwrap();
+        target.import(file_log.export().await.unwrap()).await.unwrap();
+        assert_eq!(target.get_height().await.unwrap(), 2);
+        assert_eq!(target.get_latest_block().await.unwrap().unwrap().hash, tip.hash);
+    }
+    
+    #[tokio::test]
+    async fn test_replay_rejects_failing_transactions() {
+        let nanos = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_nanos();
+        let mut backend = BlockchainBackend::new(BackendConfig {
+            data_dir: format!("/tmp/karana-test-replay-{}", nanos),
+            ..BackendConfig::default()
+        }).unwrap();
+        
+        // An unfunded transfer from a foreign log cannot apply here
+        let tx = Transaction::new(
+            "alice".to_string(),
+            TransactionData::Transfer { to: "bob".to_string(), amount: 1_000 },
+            1,
+            vec![],
+        );
+        let genesis = Block::new("0".repeat(64), 0, "log".to_string(), vec![]);
+        let block = Block::new(genesis.hash.clone(), 1, "log".to_string(), vec![tx]);
+        let archive = LedgerArchive::from_blocks("signed-log", vec![genesis, block]);
+        
+        let err = backend.import(archive.encode().unwrap()).await.unwrap_err();
+        assert!(err.to_string().contains("archived block 1"), "{}", err);
+        assert_eq!(backend.get_height().await.unwrap(), 0);
+    }
+    
+    #[tokio::test]
+    async fn test_sync_between_two_swarms() {
+        let nanos = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_nanos();
+        let config = |node: &str| BackendConfig {
+            data_dir: format!("/tmp/karana-test-sync-{}-{}", node, nanos),
+            enable_celestia: false,
+            ..BackendConfig::default()
+        };
+        let mut a = BlockchainBackend::new(config("a")).unwrap();
+        let mut b = BlockchainBackend::new(config("b")).unwrap();
+
+        // Both nodes produce offline; A's branch is longer
+        for _ in 0..3 {
+            a.blockchain.lock().unwrap().produce_block("node-a", vec![]).unwrap();
+        }
+        let b1 = b.blockchain.lock().unwrap().produce_block("node-b", vec![]).unwrap();
+
+        let ai = Arc::new(Mutex::new(KaranaAI::new().unwrap()));
+        let swarm_a = KaranaSwarm::new(ai.clone(), 47311, None).await.unwrap();
+        let swarm_b = KaranaSwarm::new(ai, 47312, Some("/ip4/127.0.0.1/tcp/47311".to_string())).await.unwrap();
+        a.attach_swarm(swarm_a.clone());
+        b.attach_swarm(swarm_b.clone());
+
+        for _ in 0..100 {
+            if swarm_a.has_peers() && swarm_b.has_peers() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        // Let the gossipsub subscriptions propagate
+        tokio::time::sleep(Duration::from_secs(1)).await;
+
+        let result = b.sync().await.unwrap();
+        assert!(result.synced);
+        assert_eq!(result.blocks_downloaded, 3);
+
+        // B reorganized onto A's chain and kept its own block as a side branch
+        let tip_a = a.get_latest_block().await.unwrap().unwrap();
+        let tip_b = b.get_latest_block().await.unwrap().unwrap();
+        assert_eq!(tip_b.hash, tip_a.hash);
+        assert_eq!(b.get_height().await.unwrap(), 3);
+        assert!(b.get_block_by_hash(&b1.hash).await.unwrap().is_none());
+        assert_eq!(b.blockchain.lock().unwrap().side_chain_len(), 1);
+    }
 }
@@ -0,0 +1,787 @@
+// File Log Backend
+//
+// Embedded append-only ledger for constrained devices, with no RocksDB:
+// - Blocks are appended to segment files under `<data_dir>/file-log/`
+// - Every record carries a checksum so a torn write at the tail is detected
+//   and truncated on open
+// - A segment is sealed with a footer holding the SHA-256 of its contents
+//   before the next one is started, so bit rot in old segments is caught too
+// - A sparse in-memory index (every INDEX_INTERVAL blocks) maps heights and
+//   hashes to file offsets; lookups seek to the nearest entry and scan
+//
+// Segment layout, integers little-endian:
+//
+//   header   magic b"KRNLSEG1" | first_height: u64
+//   record   len: u32 | JSON(Block) | SHA-256(JSON)[..8]
+//   footer   0xFFFFFFFF | SHA-256(header + records)       (sealed only)
+//
+// Segments are named `seg-<first_height>.log`. Only the last segment is
+// open for appends.
+//
+// An import writes a whole new log directory (`file-log.<generation>`) and then
+// switches to it by atomically renaming a pointer file, `file-log.current`,
+// into place. Without a pointer file the log lives in `file-log/`.
+
+use super::archive::LedgerArchive;
+use super::{LedgerBackend, BackendStats, BackendConfig, SyncResult};
+use crate::chain::{Block, Transaction, TransactionData};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+const SEGMENT_MAGIC: &[u8; 8] = b"KRNLSEG1";
+const HEADER_LEN: u64 = 16;
+const FOOTER_MARKER: u32 = u32::MAX;
+const RECORD_CHECKSUM_LEN: usize = 8;
+/// Start a new segment once the active one reaches this size
+const SEGMENT_MAX_BYTES: u64 = 4 * 1024 * 1024;
+/// Heights between sparse index entries
+const INDEX_INTERVAL: u64 = 32;
+/// Pending transactions that trigger sealing a block
+const AUTO_SEAL_TXS: usize = 10;
+/// Log directory used before any import
+const LOG_DIR: &str = "file-log";
+/// Names the active log directory, when it is not `LOG_DIR`
+const CURRENT_FILE: &str = "file-log.current";
+
+#[derive(Debug, Clone)]
+struct Segment {
+    first_height: u64,
+    path: PathBuf,
+    len: u64,
+    sealed: bool,
+}
+
+/// Sparse index entry: where the block at `height` starts
+#[derive(Debug, Clone)]
+struct IndexEntry {
+    height: u64,
+    hash: String,
+    segment: usize,
+    offset: u64,
+}
+
+/// Open segments and index for one log directory
+struct SegmentLog {
+    dir: PathBuf,
+    segments: Vec<Segment>,
+    index: Vec<IndexEntry>,
+    /// Running digest of the active segment, for its footer
+    active_digest: Sha256,
+    tip: Block,
+    total_transactions: u64,
+    /// Keys senders rotated to in logged blocks, for checking new transactions
+    auth_keys: HashMap<String, String>,
+    segment_max_bytes: u64,
+}
+
+impl SegmentLog {
+    /// Open the log in `dir`, verifying every segment. A torn or corrupt
+    /// record truncates the log at that point; an empty log is seeded with
+    /// the genesis block.
+    fn open(dir: &Path, segment_max_bytes: u64) -> Result<Self> {
+        fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+
+        let mut paths: Vec<(u64, PathBuf)> = fs::read_dir(dir)?
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let name = entry.file_name().to_string_lossy().to_string();
+                let height = name.strip_prefix("seg-")?.strip_suffix(".log")?.parse().ok()?;
+                Some((height, entry.path()))
+            })
+            .collect();
+        paths.sort();
+
+        let mut log = Self::empty(dir, segment_max_bytes);
+
+        let mut prev: Option<Block> = None;
+        let mut stop_at = None;
+        for (i, (first_height, path)) in paths.iter().enumerate() {
+            let expected = prev.as_ref().map(|b| b.header.height + 1).unwrap_or(0);
+            let scan = if *first_height == expected {
+                log.scan_segment(*first_height, path, &mut prev)?
+            } else {
+                None
+            };
+            match scan {
+                Some(segment) if segment.sealed || i + 1 == paths.len() => log.segments.push(segment),
+                Some(segment) => {
+                    // Unsealed segment followed by others: nothing after it can be trusted
+                    log.segments.push(segment);
+                    stop_at = Some(i + 1);
+                    break;
+                }
+                None => {
+                    stop_at = Some(i);
+                    break;
+                }
+            }
+        }
+        if let Some(from) = stop_at {
+            for (height, path) in &paths[from..] {
+                log::warn!("[FILE-LOG] Discarding segment starting at height {} after corruption", height);
+                fs::remove_file(path)?;
+            }
+        }
+
+        match prev {
+            Some(tip) => {
+                log.tip = tip;
+                // Crashed between sealing a segment and starting the next
+                if log.segments.last().map(|s| s.sealed).unwrap_or(false) {
+                    log.start_segment(log.tip.header.height + 1)?;
+                }
+            }
+            None => {
+                log.segments.clear();
+                log.index.clear();
+                log.total_transactions = 0;
+                log.auth_keys.clear();
+                for (_, path) in &paths {
+                    let _ = fs::remove_file(path);
+                }
+                log.start_segment(0)?;
+                let genesis = Block::genesis();
+                log.append(&genesis)?;
+            }
+        }
+        Ok(log)
+    }
+
+    fn empty(dir: &Path, segment_max_bytes: u64) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+            segments: Vec::new(),
+            index: Vec::new(),
+            active_digest: Sha256::new(),
+            tip: Block::genesis(),
+            total_transactions: 0,
+            auth_keys: HashMap::new(),
+            segment_max_bytes,
+        }
+    }
+
+    /// Verify one segment and add its blocks to the index. Returns None if the
+    /// header is unusable. A bad record truncates the file there and leaves the
+    /// segment unsealed.
+    fn scan_segment(&mut self, first_height: u64, path: &Path, prev: &mut Option<Block>) -> Result<Option<Segment>> {
+        let data = fs::read(path)?;
+        if data.len() < HEADER_LEN as usize
+            || &data[..8] != SEGMENT_MAGIC
+            || u64::from_le_bytes(data[8..16].try_into().unwrap()) != first_height
+        {
+            return Ok(None);
+        }
+
+        let segment_index = self.segments.len();
+        let mut pos = HEADER_LEN as usize;
+        let mut sealed = false;
+        let mut torn = false;
+        while pos < data.len() {
+            if data.len() - pos < 4 {
+                torn = true;
+                break;
+            }
+            let len = u32::from_le_bytes(data[pos..pos + 4].try_into().unwrap());
+            if len == FOOTER_MARKER {
+                let footer_end = pos + 4 + 32;
+                if footer_end == data.len() && Sha256::digest(&data[..pos]).as_slice() == &data[pos + 4..] {
+                    sealed = true;
+                } else {
+                    torn = true;
+                }
+                break;
+            }
+            let record_end = pos + 4 + len as usize + RECORD_CHECKSUM_LEN;
+            if record_end > data.len() {
+                torn = true;
+                break;
+            }
+            let payload = &data[pos + 4..pos + 4 + len as usize];
+            if Sha256::digest(payload)[..RECORD_CHECKSUM_LEN] != data[record_end - RECORD_CHECKSUM_LEN..record_end] {
+                torn = true;
+                break;
+            }
+            let block: Block = match serde_json::from_slice(payload) {
+                Ok(block) => block,
+                Err(_) => {
+                    torn = true;
+                    break;
+                }
+            };
+            let links = match prev.as_ref() {
+                Some(parent) => block.header.height == parent.header.height + 1 && block.header.parent_hash == parent.hash,
+                None => block.header.height == 0,
+            };
+            if !links {
+                torn = true;
+                break;
+            }
+
+            self.note_block(&block, segment_index, pos as u64);
+            *prev = Some(block);
+            pos = record_end;
+        }
+
+        if torn {
+            log::warn!("[FILE-LOG] Truncating torn segment {} at offset {}", path.display(), pos);
+            let file = OpenOptions::new().write(true).open(path)?;
+            file.set_len(pos as u64)?;
+            file.sync_all()?;
+        }
+        if !sealed {
+            self.active_digest = Sha256::new();
+            self.active_digest.update(&data[..pos]);
+        }
+        Ok(Some(Segment {
+            first_height,
+            path: path.to_path_buf(),
+            len: if sealed { data.len() as u64 } else { pos as u64 },
+            sealed,
+        }))
+    }
+
+    /// Update index and counters for a block stored at `offset`
+    fn note_block(&mut self, block: &Block, segment: usize, offset: u64) {
+        // The first block of every segment is indexed so scans never cross a segment
+        if block.header.height % INDEX_INTERVAL == 0 || offset == HEADER_LEN {
+            self.index.push(IndexEntry {
+                height: block.header.height,
+                hash: block.hash.clone(),
+                segment,
+                offset,
+            });
+        }
+        self.total_transactions += block.transactions.len() as u64;
+        for tx in &block.transactions {
+            let TransactionData::RotateKey { new_public_key, proof } = &tx.data else { continue };
+            let authorized = self.auth_keys.get(&tx.sender).map(String::as_str);
+            if tx.public_key.is_some() && tx.verify_signer(authorized) && tx.verify_rotation_proof(new_public_key, proof) {
+                self.auth_keys.insert(tx.sender.clone(), new_public_key.to_ascii_lowercase());
+            }
+        }
+    }
+
+    fn start_segment(&mut self, first_height: u64) -> Result<()> {
+        let path = self.dir.join(format!("seg-{:020}.log", first_height));
+        let mut header = Vec::with_capacity(HEADER_LEN as usize);
+        header.extend_from_slice(SEGMENT_MAGIC);
+        header.extend_from_slice(&first_height.to_le_bytes());
+
+        let mut file = OpenOptions::new().create(true).write(true).truncate(true).open(&path)?;
+        file.write_all(&header)?;
+        file.sync_all()?;
+
+        self.active_digest = Sha256::new();
+        self.active_digest.update(&header);
+        self.segments.push(Segment {
+            first_height,
+            path,
+            len: HEADER_LEN,
+            sealed: false,
+        });
+        Ok(())
+    }
+
+    /// Write the footer of the active segment
+    fn seal_active(&mut self) -> Result<()> {
+        let segment = self.segments.last_mut().ok_or_else(|| anyhow::anyhow!("No active segment"))?;
+        let digest = self.active_digest.clone().finalize();
+        let mut file = OpenOptions::new().append(true).open(&segment.path)?;
+        file.write_all(&FOOTER_MARKER.to_le_bytes())?;
+        file.write_all(&digest)?;
+        file.sync_all()?;
+        segment.len += 4 + 32;
+        segment.sealed = true;
+        Ok(())
+    }
+
+    /// Durably append a block, rolling to a new segment when the active one is full
+    fn append(&mut self, block: &Block) -> Result<()> {
+        let payload = serde_json::to_vec(block)?;
+        let mut frame = Vec::with_capacity(4 + payload.len() + RECORD_CHECKSUM_LEN);
+        frame.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        frame.extend_from_slice(&payload);
+        frame.extend_from_slice(&Sha256::digest(&payload)[..RECORD_CHECKSUM_LEN]);
+
+        let active = self.segments.last().ok_or_else(|| anyhow::anyhow!("No active segment"))?;
+        if active.len > HEADER_LEN && active.len + frame.len() as u64 > self.segment_max_bytes {
+            self.seal_active()?;
+            self.start_segment(block.header.height)?;
+        }
+
+        let segment_index = self.segments.len() - 1;
+        let segment = &mut self.segments[segment_index];
+        let offset = segment.len;
+        let mut file = OpenOptions::new().append(true).open(&segment.path)?;
+        file.write_all(&frame)?;
+        file.sync_data()?;
+        segment.len += frame.len() as u64;
+        self.active_digest.update(&frame);
+
+        self.note_block(block, segment_index, offset);
+        self.tip = block.clone();
+        Ok(())
+    }
+
+    /// Read the block at `height` by seeking to the nearest index entry
+    fn read_at(&self, height: u64) -> Result<Option<Block>> {
+        if height > self.tip.header.height {
+            return Ok(None);
+        }
+        let entry_pos = self.index.partition_point(|e| e.height <= height);
+        let entry = match entry_pos.checked_sub(1).map(|i| &self.index[i]) {
+            Some(entry) => entry,
+            None => return Ok(None),
+        };
+        let segment = &self.segments[entry.segment];
+        let mut file = File::open(&segment.path)?;
+        file.seek(SeekFrom::Start(entry.offset))?;
+
+        let mut current = entry.height;
+        loop {
+            let mut len = [0u8; 4];
+            file.read_exact(&mut len)?;
+            let len = u32::from_le_bytes(len) as usize;
+            if current == height {
+                let mut payload = vec![0u8; len];
+                file.read_exact(&mut payload)?;
+                return Ok(Some(serde_json::from_slice(&payload)?));
+            }
+            file.seek(SeekFrom::Current((len + RECORD_CHECKSUM_LEN) as i64))?;
+            current += 1;
+        }
+    }
+
+    /// Find a block by hash: tip and index entries first, then a full scan
+    fn find_hash(&self, hash: &str) -> Result<Option<Block>> {
+        if self.tip.hash == hash {
+            return Ok(Some(self.tip.clone()));
+        }
+        if let Some(entry) = self.index.iter().find(|e| e.hash == hash) {
+            return self.read_at(entry.height);
+        }
+        Ok(self.read_all()?.into_iter().find(|b| b.hash == hash))
+    }
+
+    /// Every block in height order
+    fn read_all(&self) -> Result<Vec<Block>> {
+        let mut blocks = Vec::new();
+        for segment in &self.segments {
+            let data = fs::read(&segment.path)?;
+            let mut pos = HEADER_LEN as usize;
+            while pos + 4 <= data.len() {
+                let len = u32::from_le_bytes(data[pos..pos + 4].try_into().unwrap());
+                if len == FOOTER_MARKER {
+                    break;
+                }
+                let record_end = pos + 4 + len as usize + RECORD_CHECKSUM_LEN;
+                if record_end > data.len() {
+                    return Err(anyhow::anyhow!("Segment {} is truncated at offset {}", segment.path.display(), pos));
+                }
+                let payload = &data[pos + 4..record_end - RECORD_CHECKSUM_LEN];
+                if Sha256::digest(payload)[..RECORD_CHECKSUM_LEN] != data[record_end - RECORD_CHECKSUM_LEN..record_end] {
+                    return Err(anyhow::anyhow!("Segment {} is corrupt at offset {}", segment.path.display(), pos));
+                }
+                blocks.push(serde_json::from_slice(payload)?);
+                pos = record_end;
+            }
+        }
+        Ok(blocks)
+    }
+
+    fn storage_bytes(&self) -> u64 {
+        self.segments.iter().map(|s| s.len).sum()
+    }
+}
+
+/// Append-only file log backend - no consensus, no RocksDB
+pub struct FileLogBackend {
+    config: BackendConfig,
+    log: Arc<Mutex<SegmentLog>>,
+    pending_txs: Arc<Mutex<Vec<Transaction>>>,
+}
+
+impl FileLogBackend {
+    /// Open (or create) the log under `<data_dir>`
+    pub fn new(config: BackendConfig) -> Result<Self> {
+        let log = SegmentLog::open(&Self::log_dir(&config), SEGMENT_MAX_BYTES)?;
+        Ok(Self {
+            config,
+            log: Arc::new(Mutex::new(log)),
+            pending_txs: Arc::new(Mutex::new(Vec::new())),
+        })
+    }
+
+    /// Active log directory, as named by the pointer file
+    fn log_dir(config: &BackendConfig) -> PathBuf {
+        let root = Path::new(&config.data_dir);
+        match fs::read_to_string(root.join(CURRENT_FILE)) {
+            Ok(name) if !name.trim().is_empty() => root.join(name.trim()),
+            _ => root.join(LOG_DIR),
+        }
+    }
+
+    /// Import generation of a log directory (`file-log.<n>`; 0 for `file-log`)
+    fn generation(dir: &Path) -> u64 {
+        dir.file_name()
+            .and_then(|name| name.to_str()?.strip_prefix(LOG_DIR)?.strip_prefix('.')?.parse().ok())
+            .unwrap_or(0)
+    }
+
+    /// Seal pending transactions into a block appended to the log
+    pub fn seal_pending(&self) -> Result<Option<Block>> {
+        let mut pending = self.pending_txs.lock().unwrap();
+        if pending.is_empty() {
+            return Ok(None);
+        }
+        let mut log = self.log.lock().unwrap();
+        let block = Block::new(
+            log.tip.hash.clone(),
+            log.tip.header.height + 1,
+            "device".to_string(),
+            pending.clone(),
+        );
+        log.append(&block)?;
+        pending.clear();
+        Ok(Some(block))
+    }
+
+    /// Blocks from any producer are accepted as long as they extend the tip
+    /// and their transactions verify. The block hash is taken as given
+    /// (signed-log entries hash a different preimage); record checksums
+    /// protect what was written.
+    fn check_extends(tip: &Block, block: &Block) -> Result<()> {
+        if block.header.height != tip.header.height + 1 {
+            return Err(anyhow::anyhow!("Invalid block height: expected {}, got {}", tip.header.height + 1, block.header.height));
+        }
+        if block.header.parent_hash != tip.hash {
+            return Err(anyhow::anyhow!("Invalid parent hash"));
+        }
+        if block.transactions.iter().any(|tx| !tx.verify()) {
+            return Err(anyhow::anyhow!("Invalid transaction signature"));
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl LedgerBackend for FileLogBackend {
+    fn name(&self) -> &str {
+        "file-log"
+    }
+
+    async fn init(&mut self) -> Result<()> {
+        // The log is opened and verified in `new`
+        Ok(())
+    }
+
+    async fn add_block(&mut self, block: Block) -> Result<()> {
+        let mut log = self.log.lock().unwrap();
+        Self::check_extends(&log.tip, &block)?;
+        log.append(&block)
+    }
+
+    async fn get_block(&self, height: u64) -> Result<Option<Block>> {
+        self.log.lock().unwrap().read_at(height)
+    }
+
+    async fn get_block_by_hash(&self, hash: &str) -> Result<Option<Block>> {
+        self.log.lock().unwrap().find_hash(hash)
+    }
+
+    async fn get_height(&self) -> Result<u64> {
+        Ok(self.log.lock().unwrap().tip.header.height)
+    }
+
+    async fn get_latest_block(&self) -> Result<Option<Block>> {
+        Ok(Some(self.log.lock().unwrap().tip.clone()))
+    }
+
+    async fn add_transaction(&mut self, tx: Transaction) -> Result<()> {
+        let authorized = self.log.lock().unwrap().auth_keys.get(&tx.sender).cloned();
+        if !tx.verify() || !tx.verify_signer(authorized.as_deref()) {
+            return Err(anyhow::anyhow!("Invalid transaction signature"));
+        }
+
+        let queued = {
+            let mut pending = self.pending_txs.lock().unwrap();
+            pending.push(tx);
+            pending.len()
+        };
+        if queued >= AUTO_SEAL_TXS {
+            self.seal_pending()?;
+        }
+        Ok(())
+    }
+
+    async fn get_pending_transactions(&self) -> Result<Vec<Transaction>> {
+        Ok(self.pending_txs.lock().unwrap().clone())
+    }
+
+    async fn clear_pending_transactions(&mut self) -> Result<()> {
+        self.pending_txs.lock().unwrap().clear();
+        Ok(())
+    }
+
+    async fn validate_block(&self, block: &Block) -> Result<bool> {
+        let log = self.log.lock().unwrap();
+        Ok(Self::check_extends(&log.tip, block).is_ok())
+    }
+
+    async fn get_stats(&self) -> Result<BackendStats> {
+        let log = self.log.lock().unwrap();
+        Ok(BackendStats {
+            backend_type: "file-log".to_string(),
+            total_blocks: log.tip.header.height + 1,
+            total_transactions: log.total_transactions,
+            pending_transactions: self.pending_txs.lock().unwrap().len(),
+            storage_size_mb: log.storage_bytes() as f64 / 1_048_576.0,
+            last_block_time: Some(log.tip.header.timestamp),
+        })
+    }
+
+    async fn sync(&mut self) -> Result<SyncResult> {
+        // Local-only log: nothing to sync
+        Ok(SyncResult {
+            synced: true,
+            blocks_downloaded: 0,
+            peers_contacted: 0,
+            time_ms: 0,
+        })
+    }
+
+    async fn export(&self) -> Result<Vec<u8>> {
+        let blocks = self.log.lock().unwrap().read_all()?;
+        LedgerArchive::from_blocks(self.name(), blocks).encode()
+    }
+
+    /// Replace the log with the archive's blocks. The new log is written to a
+    /// fresh directory and switched to by one atomic rename of the pointer
+    /// file, so a failed or interrupted import leaves the old log in use.
+    async fn import(&mut self, data: Vec<u8>) -> Result<()> {
+        let archive = LedgerArchive::decode(&data).context("Failed to decode ledger archive")?;
+        if archive.blocks().flat_map(|b| &b.transactions).any(|tx| !tx.verify()) {
+            return Err(anyhow::anyhow!("Archive contains an invalid transaction signature"));
+        }
+
+        let root = PathBuf::from(&self.config.data_dir);
+        let (current, segment_max_bytes) = {
+            let log = self.log.lock().unwrap();
+            (log.dir.clone(), log.segment_max_bytes)
+        };
+        let name = format!("{}.{}", LOG_DIR, Self::generation(&current) + 1);
+        let staged_dir = root.join(&name);
+        let _ = fs::remove_dir_all(&staged_dir);
+        fs::create_dir_all(&staged_dir)?;
+        let mut staged = SegmentLog::empty(&staged_dir, segment_max_bytes);
+        staged.start_segment(0)?;
+        for block in archive.blocks() {
+            staged.append(block)?;
+        }
+
+        let pointer = root.join(CURRENT_FILE);
+        let pending = root.join(format!("{}.tmp", CURRENT_FILE));
+        let mut file = File::create(&pending)?;
+        file.write_all(name.as_bytes())?;
+        file.sync_all()?;
+
+        let mut log = self.log.lock().unwrap();
+        fs::rename(&pending, &pointer)?;
+        File::open(&root)?.sync_all()?;
+        *log = SegmentLog::open(&staged_dir, segment_max_bytes)?;
+        if let Err(e) = fs::remove_dir_all(&current) {
+            log::warn!("[FILE-LOG] Could not remove replaced log {}: {}", current.display(), e);
+        }
+        log::info!("[FILE-LOG] Imported {} blocks from {} archive", log.tip.header.height + 1, archive.source);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chain::TransactionData;
+    use crate::ledger::backends::signed_log::SignedLogBackend;
+
+    fn test_config(name: &str) -> BackendConfig {
+        let nanos = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_nanos();
+        BackendConfig {
+            data_dir: format!("/tmp/karana-test-file-log-{}-{}", name, nanos),
+            ..BackendConfig::personal()
+        }
+    }
+
+    fn tx(nonce: u64) -> Transaction {
+        Transaction {
+            sender: "alice".to_string(),
+            data: TransactionData::Transfer { to: "bob".to_string(), amount: 1 },
+            signature: "sig".to_string(),
+            nonce,
+            public_key: None,
+            hash: format!("tx-{}", nonce),
+            timestamp: 0,
+        }
+    }
+
+    async fn append_blocks(backend: &mut FileLogBackend, count: u64) {
+        for _ in 0..count {
+            let tip = backend.get_latest_block().await.unwrap().unwrap();
+            let height = tip.header.height + 1;
+            let block = Block::new(tip.hash, height, "device".to_string(), vec![tx(height)]);
+            backend.add_block(block).await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_append_and_reopen() {
+        let config = test_config("reopen");
+        let mut backend = FileLogBackend::new(config.clone()).unwrap();
+        assert_eq!(backend.get_height().await.unwrap(), 0);
+        assert_eq!(backend.get_latest_block().await.unwrap().unwrap().hash, Block::genesis().hash);
+
+        append_blocks(&mut backend, 40).await;
+        let b7 = backend.get_block(7).await.unwrap().unwrap();
+        let tip = backend.get_latest_block().await.unwrap().unwrap();
+        drop(backend);
+
+        let backend = FileLogBackend::new(config.clone()).unwrap();
+        assert_eq!(backend.get_height().await.unwrap(), 40);
+        assert_eq!(backend.get_block(7).await.unwrap().unwrap().hash, b7.hash);
+        assert_eq!(backend.get_block_by_hash(&b7.hash).await.unwrap().unwrap().header.height, 7);
+        assert_eq!(backend.get_block_by_hash(&tip.hash).await.unwrap().unwrap().header.height, 40);
+        assert!(backend.get_block(41).await.unwrap().is_none());
+        assert_eq!(backend.get_stats().await.unwrap().total_transactions, 40);
+
+        let _ = fs::remove_dir_all(&config.data_dir);
+    }
+
+    #[tokio::test]
+    async fn test_segments_roll_and_seal() {
+        let config = test_config("segments");
+        let mut backend = FileLogBackend::new(config.clone()).unwrap();
+        backend.log.lock().unwrap().segment_max_bytes = 2048;
+        append_blocks(&mut backend, 30).await;
+
+        let segments = backend.log.lock().unwrap().segments.clone();
+        assert!(segments.len() > 2);
+        assert!(segments[..segments.len() - 1].iter().all(|s| s.sealed));
+        assert!(!segments.last().unwrap().sealed);
+        let b25 = backend.get_block(25).await.unwrap().unwrap();
+        drop(backend);
+
+        // Reopens cleanly across segments
+        let backend = FileLogBackend::new(config.clone()).unwrap();
+        assert_eq!(backend.get_height().await.unwrap(), 30);
+        assert_eq!(backend.get_block(25).await.unwrap().unwrap().hash, b25.hash);
+
+        // Bit rot in a sealed segment rolls the log back to before it
+        let second = segments[1].clone();
+        drop(backend);
+        let mut data = fs::read(&second.path).unwrap();
+        let last = data.len() - 40;
+        data[last] ^= 0xff;
+        fs::write(&second.path, data).unwrap();
+
+        // (the flipped byte is in the checksum of the segment's last record)
+        let backend = FileLogBackend::new(config.clone()).unwrap();
+        let height = backend.get_height().await.unwrap();
+        assert_eq!(height, segments[2].first_height - 2);
+        assert!(backend.get_block(height + 1).await.unwrap().is_none());
+        assert_eq!(backend.log.lock().unwrap().segments.len(), 2);
+
+        let _ = fs::remove_dir_all(&config.data_dir);
+    }
+
+    #[tokio::test]
+    async fn test_torn_tail_is_truncated() {
+        let config = test_config("torn");
+        let mut backend = FileLogBackend::new(config.clone()).unwrap();
+        append_blocks(&mut backend, 3).await;
+        let path = backend.log.lock().unwrap().segments.last().unwrap().path.clone();
+        drop(backend);
+
+        // Half-written fourth record
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(&500u32.to_le_bytes()).unwrap();
+        file.write_all(b"{\"header\":").unwrap();
+        drop(file);
+
+        let mut backend = FileLogBackend::new(config.clone()).unwrap();
+        assert_eq!(backend.get_height().await.unwrap(), 3);
+        append_blocks(&mut backend, 1).await;
+        drop(backend);
+        let backend = FileLogBackend::new(config.clone()).unwrap();
+        assert_eq!(backend.get_height().await.unwrap(), 4);
+
+        let _ = fs::remove_dir_all(&config.data_dir);
+    }
+
+    #[tokio::test]
+    async fn test_read_all_reports_truncation() {
+        let config = test_config("read-all");
+        let mut backend = FileLogBackend::new(config.clone()).unwrap();
+        append_blocks(&mut backend, 3).await;
+        let segment = backend.log.lock().unwrap().segments.last().unwrap().clone();
+
+        // Cut into the last record behind the open log's back
+        let file = OpenOptions::new().write(true).open(&segment.path).unwrap();
+        file.set_len(segment.len - 20).unwrap();
+        drop(file);
+        assert!(backend.export().await.is_err());
+
+        let _ = fs::remove_dir_all(&config.data_dir);
+    }
+
+    #[tokio::test]
+    async fn test_rejects_non_extending_block() {
+        let config = test_config("reject");
+        let mut backend = FileLogBackend::new(config.clone()).unwrap();
+        let orphan = Block::new("f".repeat(64), 1, "device".to_string(), vec![]);
+        assert!(!backend.validate_block(&orphan).await.unwrap());
+        assert!(backend.add_block(orphan).await.is_err());
+
+        let _ = fs::remove_dir_all(&config.data_dir);
+    }
+
+    #[tokio::test]
+    async fn test_migrate_with_signed_log() {
+        let config = test_config("migrate");
+        let mut file_log = FileLogBackend::new(config.clone()).unwrap();
+        append_blocks(&mut file_log, 5).await;
+        let exported = file_log.export().await.unwrap();
+
+        // file-log -> signed-log -> file-log
+        let mut signed = SignedLogBackend::new(BackendConfig::personal()).unwrap();
+        signed.import(exported).await.unwrap();
+        assert_eq!(signed.get_height().await.unwrap(), 5);
+
+        let mut restored = FileLogBackend::new(test_config("migrate-back")).unwrap();
+        restored.import(signed.export().await.unwrap()).await.unwrap();
+        assert_eq!(restored.get_height().await.unwrap(), 5);
+        assert_eq!(
+            restored.get_latest_block().await.unwrap().unwrap().hash,
+            file_log.get_latest_block().await.unwrap().unwrap().hash,
+        );
+
+        // A corrupt archive leaves the existing log untouched
+        let mut bad = file_log.export().await.unwrap();
+        bad[20] ^= 0xff;
+        assert!(restored.import(bad).await.is_err());
+        assert_eq!(restored.get_height().await.unwrap(), 5);
+
+        // The pointer file survives a reopen and the replaced log is gone
+        let root = Path::new(&restored.config.data_dir);
+        assert!(!root.join(LOG_DIR).exists());
+        let reopened = FileLogBackend::new(restored.config.clone()).unwrap();
+        assert_eq!(reopened.get_height().await.unwrap(), 5);
+
+        let _ = fs::remove_dir_all(&config.data_dir);
+        let _ = fs::remove_dir_all(&restored.config.data_dir);
+    }
+}
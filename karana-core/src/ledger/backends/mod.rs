@@ -3,11 +3,16 @@
 // Provides abstraction over storage backends:
 // 1. BlockchainBackend - Full consensus-based chain (current)
 // 2. SignedLogBackend - Simple cryptographic log (personal devices)
+// 3. FileLogBackend - Segmented append-only files, no RocksDB (minimal devices)
 //
-// This allows "no-blockchain" mode for devices that never join a swarm
+// This allows "no-blockchain" mode for devices that never join a swarm.
+// Every backend exports and imports the same `archive::LedgerArchive`
+// format, so data can be migrated between any two of them.
 
+pub mod archive;
 pub mod blockchain;
 pub mod signed_log;
+pub mod file_log;
 
 use crate::chain::{Block, Transaction};
 use anyhow::Result;
@@ -56,10 +61,10 @@ pub trait LedgerBackend: Send + Sync {
     /// Sync with peers (for distributed backends)
     async fn sync(&mut self) -> Result<SyncResult>;
     
-    /// Export data for backup
+    /// Export data for backup, as a `LedgerArchive`
     async fn export(&self) -> Result<Vec<u8>>;
     
-    /// Import data from backup
+    /// Import a `LedgerArchive` exported by any backend
     async fn import(&mut self, data: Vec<u8>) -> Result<()>;
 }
 
@@ -96,6 +101,10 @@ impl BackendFactory {
                 let backend = signed_log::SignedLogBackend::new(config)?;
                 Ok(Box::new(backend))
             }
+            "file-log" | "filelog" | "file" => {
+                let backend = file_log::FileLogBackend::new(config)?;
+                Ok(Box::new(backend))
+            }
             _ => {
                 Err(anyhow::anyhow!("Unknown backend type: {}", backend_type))
             }
@@ -156,4 +165,17 @@ mod tests {
         assert!(!config.enable_celestia);
         assert_eq!(config.block_time_secs, 60);
     }
+    
+    #[tokio::test]
+    async fn test_factory_creates_file_log() {
+        let nanos = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_nanos();
+        let config = BackendConfig {
+            data_dir: format!("/tmp/karana-test-factory-{}", nanos),
+            ..BackendConfig::personal()
+        };
+        let backend = BackendFactory::create(crate::ledger::BackendType::FileLog.as_str(), config.clone()).await.unwrap();
+        assert_eq!(backend.name(), "file-log");
+        assert_eq!(backend.get_height().await.unwrap(), 0);
+        let _ = std::fs::remove_dir_all(&config.data_dir);
+    }
 }
@@ -6,6 +6,7 @@
 // - Simple append-only signed entries
 // - Perfect for personal devices that never join swarms
 
+use super::archive::LedgerArchive;
 use super::{LedgerBackend, BackendStats, BackendConfig, SyncResult};
 use crate::chain::{Block, Transaction, BlockHeader};
 use anyhow::{Result, Context};
@@ -51,14 +52,7 @@ impl LogEntry {
             .as_secs();
         
         // Calculate hash (without signature)
-        let mut hasher = Sha256::new();
-        hasher.update(index.to_le_bytes());
-        hasher.update(timestamp.to_le_bytes());
-        hasher.update(prev_hash.as_bytes());
-        for tx in &transactions {
-            hasher.update(tx.hash.as_bytes());
-        }
-        let hash = hex::encode(hasher.finalize());
+        let hash = Self::compute_hash(index, timestamp, &prev_hash, &transactions);
         
         // Sign the hash
         let signature_bytes = signing_key.sign(hash.as_bytes());
@@ -74,6 +68,18 @@ impl LogEntry {
         }
     }
     
+    /// Hash of an entry's contents, which is what gets signed
+    fn compute_hash(index: u64, timestamp: u64, prev_hash: &str, transactions: &[Transaction]) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(index.to_le_bytes());
+        hasher.update(timestamp.to_le_bytes());
+        hasher.update(prev_hash.as_bytes());
+        for tx in transactions {
+            hasher.update(tx.hash.as_bytes());
+        }
+        hex::encode(hasher.finalize())
+    }
+
     /// Verify entry signature
     pub fn verify(&self, verifying_key: &VerifyingKey) -> Result<bool> {
         let sig_bytes = hex::decode(&self.signature)
@@ -123,18 +129,8 @@ impl SignedLogBackend {
         let signing_key = SigningKey::generate(&mut csprng);
         let verifying_key = signing_key.verifying_key();
         
-        // Create genesis entry
-        let genesis = LogEntry {
-            index: 0,
-            timestamp: std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_secs(),
-            prev_hash: "0".repeat(64),
-            transactions: vec![],
-            hash: hex::encode(Sha256::digest(b"genesis")),
-            signature: hex::encode(signing_key.sign(b"genesis").to_bytes()),
-        };
+        // Create genesis entry, signed like any other so it survives export
+        let genesis = LogEntry::new(0, "0".repeat(64), vec![], &signing_key);
         
         Ok(Self {
             config,
@@ -312,23 +308,42 @@ impl LedgerBackend for SignedLogBackend {
     
     async fn export(&self) -> Result<Vec<u8>> {
         let entries = self.entries.lock().unwrap();
-        bincode::serialize(&*entries)
+        let mut archive = LedgerArchive::new(self.name());
+        for entry in entries.iter() {
+            // Entry signatures travel as the record attestation
+            let signature = hex::decode(&entry.signature).unwrap_or_default();
+            archive.push(entry.to_block("device".to_string()), signature);
+        }
+        archive.encode()
             .context("Failed to serialize log for export")
     }
     
     async fn import(&mut self, data: Vec<u8>) -> Result<()> {
-        let entries: Vec<LogEntry> = bincode::deserialize(&data)
+        let archive = LedgerArchive::decode(&data)
             .context("Failed to deserialize log import")?;
         
-        // Verify all entries
-        for (i, entry) in entries.iter().enumerate() {
-            if i == 0 {
-                continue; // Genesis
-            }
+        let mut entries = Vec::with_capacity(archive.records.len());
+        for record in archive.records {
+            let block = record.block;
+            let mut entry = LogEntry {
+                index: block.header.height,
+                timestamp: block.header.timestamp,
+                prev_hash: block.header.parent_hash,
+                transactions: block.transactions,
+                hash: block.hash,
+                signature: hex::encode(&record.attestation),
+            };
             
-            if !entry.verify(&self.verifying_key)? {
-                return Err(anyhow::anyhow!("Invalid entry signature at index {}", entry.index));
+            // Unsigned blocks from other backends are marked imported, as in
+            // add_block; a signature must be ours and cover the entry as given
+            if record.attestation.is_empty() {
+                entry.signature = "imported".to_string();
+            } else if entry.hash != LogEntry::compute_hash(entry.index, entry.timestamp, &entry.prev_hash, &entry.transactions)
+                || !entry.verify(&self.verifying_key)?
+            {
+                return Err(anyhow::anyhow!("Log entry {} has an invalid signature", entry.index));
             }
+            entries.push(entry);
         }
         
         let mut log = self.entries.lock().unwrap();
@@ -414,6 +429,24 @@ mod tests {
         assert_eq!(pending.len(), 1);
     }
     
+    #[tokio::test]
+    async fn test_import_rejects_bad_signature() {
+        let mut backend = SignedLogBackend::new(BackendConfig::personal()).unwrap();
+        let exported = backend.export().await.unwrap();
+        backend.import(exported.clone()).await.unwrap();
+        assert_eq!(backend.get_height().await.unwrap(), 0);
+
+        let mut archive = LedgerArchive::decode(&exported).unwrap();
+        archive.records[0].attestation[0] ^= 0xff;
+        assert!(backend.import(archive.encode().unwrap()).await.is_err());
+
+        // Re-dated contents no longer match the signed hash
+        let mut archive = LedgerArchive::decode(&exported).unwrap();
+        archive.records[0].block.header.timestamp += 1;
+        assert!(backend.import(archive.encode().unwrap()).await.is_err());
+        assert_eq!(backend.get_height().await.unwrap(), 0);
+    }
+    
     #[tokio::test]
     async fn test_signed_log_stats() {
         let config = BackendConfig::personal();
@@ -2,7 +2,7 @@
 //
 // This module provides:
 // 1. Ledger pruning and checkpointing
-// 2. Pluggable backend architecture (blockchain vs signed-log)
+// 2. Pluggable backend architecture (blockchain, signed-log, file-log)
 // 3. Storage optimization
 //
 // Goals:
@@ -22,7 +22,7 @@ use std::sync::Arc;
 /// Configuration for ledger behavior
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LedgerConfig {
-    /// Backend type: "blockchain" or "signed-log"
+    /// Backend type: "blockchain", "signed-log" or "file-log"
     pub backend_type: BackendType,
     
     /// Pruning settings
@@ -41,6 +41,19 @@ pub enum BackendType {
     Blockchain,
     /// Simple signed log without consensus (for personal devices)
     SignedLog,
+    /// Segmented append-only file log without RocksDB (for minimal devices)
+    FileLog,
+}
+
+impl BackendType {
+    /// Name understood by `backends::BackendFactory::create`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BackendType::Blockchain => "blockchain",
+            BackendType::SignedLog => "signed-log",
+            BackendType::FileLog => "file-log",
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -125,7 +138,7 @@ impl LedgerConfig {
     /// Minimal configuration for constrained devices
     pub fn minimal() -> Self {
         Self {
-            backend_type: BackendType::SignedLog,
+            backend_type: BackendType::FileLog,
             pruning: PruningConfig {
                 enabled: true,
                 retention_days: 30,
@@ -202,7 +215,7 @@ mod tests {
     #[test]
     fn test_minimal_config() {
         let config = LedgerConfig::minimal();
-        assert_eq!(config.backend_type, BackendType::SignedLog);
+        assert_eq!(config.backend_type, BackendType::FileLog);
         assert_eq!(config.pruning.retention_days, 30);
         assert_eq!(config.optimization.max_storage_mb, 50);
     }
@@ -159,3 +159,6 @@ pub mod drivers;
 // Phase 52: Distributed Compute - edge cloud integration
 pub mod distributed;
 
+// Phase 54.2: Unified tool registry and tool-calling protocol
+pub mod tools;
+
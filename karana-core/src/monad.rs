@@ -12,11 +12,15 @@ use crate::ui::KaranaUI;
 use crate::vigil::KaranaVeil;
 use crate::storage::KaranaStorage;
 use crate::net::{KaranaSwarm, KaranaSwarmEvent};
-use crate::ai::KaranaAI;
+use crate::ai::{KaranaAI, ModelSwapper};
+use crate::ai::memory::OracleMemory;
+use crate::power::PowerManager;
+use crate::resource::ResourceMonitor;
 use crate::zk::setup_zk;
-use crate::economy::{Ledger, ProofOfStorage, Governance};
-use crate::gov::KaranaDAO;
-use crate::chain::{Blockchain, Transaction, TransactionData, Block};
+use crate::economy::{Ledger, ProofOfStorage};
+use crate::gov::{Governance, ProposalAction};
+use crate::chain::{Blockchain, ImportOutcome, Mempool, MempoolConfig, Transaction, TransactionData};
+use crate::chain::sync as chain_sync;
 use crate::state::KaranaPersist;
 use crate::hardware::KaranaHardware;
 use crate::hardware::haptic::HapticPattern;
@@ -24,7 +28,6 @@ use crate::identity::KaranaIdentity;
 use crate::ipc;
 use crate::oracle::KaranaOracle;
 use crate::wallet::KaranaWallet;
-use alloy_primitives::U256;
 
 // Oracle Veil v1.1 imports
 use crate::oracle::{
@@ -45,6 +48,24 @@ use crate::zk::setup_intent_proofs;
 /// Real output directory for intent actions
 const REAL_OUTPUT_DIR: &str = "/tmp/karana";
 
+/// How often the oracle's memory consolidates old turns into episodes (s)
+const MEMORY_CONSOLIDATION_SECS: u64 = 15 * 60;
+
+/// Next nonce for `did`, counting transactions it already has queued
+fn next_nonce(chain: &Blockchain, mempool: &Mutex<Mempool>, did: &str) -> u64 {
+    let confirmed = chain.account_nonce(did);
+    mempool.lock().unwrap().next_nonce(did, confirmed)
+}
+
+/// Queue a transaction for the next block, logging mempool rejections
+fn queue_transaction(chain: &Blockchain, mempool: &Mutex<Mempool>, tx: Transaction) {
+    let confirmed = chain.account_nonce(&tx.sender);
+    let authorized = chain.authorized_key(&tx.sender);
+    if let Err(e) = mempool.lock().unwrap().add_authorized(tx, confirmed, authorized.as_deref()) {
+        log::warn!("[CHAIN] Transaction rejected by mempool: {}", e);
+    }
+}
+
 /// Backend handle for async command processing
 /// Contains clones of the Monad's atoms for use in spawned tasks
 #[derive(Clone)]
@@ -54,7 +75,7 @@ struct MonadBackend {
     storage: Arc<KaranaStorage>,
     chain: Arc<Blockchain>,
     swarm: Arc<KaranaSwarm>,
-    mempool: Arc<Mutex<Vec<Transaction>>>,
+    mempool: Arc<Mutex<Mempool>>,
     hardware: Arc<KaranaHardware>,
     wallet: Arc<Mutex<KaranaWallet>>,
     tab_manager: Arc<Mutex<TabManager>>,
@@ -122,18 +143,20 @@ impl MonadBackend {
                         
                         // Create transaction for chain
                         let tx_hash = format!("0x{}", hex::encode(&zk_proof[..16.min(zk_proof.len())]));
-                        let tx = Transaction::new(
-                            from_did.clone(),
-                            TransactionData::Transfer { 
-                                to: to.clone(), 
-                                amount, // u128
-                            },
-                            0, // nonce
-                            zk_proof.clone(),
-                        );
+                        let tx = {
+                            let wallet = self.wallet.lock().unwrap();
+                            crate::chain::create_signed_transaction(
+                                &wallet,
+                                TransactionData::Transfer { 
+                                    to: to.clone(), 
+                                    amount, // u128
+                                },
+                                next_nonce(&self.chain, &self.mempool, &from_did),
+                            )
+                        };
                         
                         // Add to mempool
-                        self.mempool.lock().unwrap().push(tx);
+                        queue_transaction(&self.chain, &self.mempool, tx);
                         
                         log::info!("[MONAD-BACKEND] Transfer: {} KARA from {} to {} ({})", 
                             amount, from_did, to, tx_hash);
@@ -153,24 +176,36 @@ impl MonadBackend {
                     }
                     
                     TransactionPayload::Unstake { amount } => {
-                        let did = self.wallet.lock().unwrap().did().to_string();
-                        match self.ledger.lock().unwrap().unstake(&did, amount as u64) {
-                            Ok(_) => {
-                                log::info!("[MONAD-BACKEND] Unstaked {} KARA for {}", amount, did);
-                                CommandResult::success(&cmd_id, CommandData::Text(format!("Unstaked {} KARA", amount)))
-                            }
-                            Err(e) => CommandResult::failure(&cmd_id, e.to_string(), false)
+                        // Unbonding runs on chain heights, so it goes through a block
+                        let wallet = self.wallet.lock().unwrap();
+                        let did = wallet.did().to_string();
+                        if self.ledger.lock().unwrap().get_account(&did).staked < amount {
+                            return CommandResult::failure(&cmd_id, "Insufficient staked balance", false);
                         }
+                        let nonce = next_nonce(&self.chain, &self.mempool, &did);
+                        let tx = crate::chain::create_signed_transaction(
+                            &wallet,
+                            TransactionData::Unstake { amount },
+                            nonce,
+                        );
+                        drop(wallet);
+                        queue_transaction(&self.chain, &self.mempool, tx);
+                        log::info!("[MONAD-BACKEND] Unstake of {} KARA queued for {}", amount, did);
+                        CommandResult::success(&cmd_id, CommandData::Text(format!(
+                            "Unbonding {} KARA; available {} blocks after confirmation",
+                            amount, crate::economy::UNBONDING_PERIOD
+                        )))
                     }
                     
                     TransactionPayload::Vote { proposal_id, approve } => {
-                        let did = self.wallet.lock().unwrap().did().to_string();
-                        match self.gov.lock().unwrap().vote(proposal_id, &did, approve) {
+                        // Ballots are cast on chain, against the proposal's power snapshot
+                        let wallet = self.wallet.lock().unwrap();
+                        match self.chain.queue_signed(&self.mempool, &wallet, TransactionData::Vote { proposal_id, approve }) {
                             Ok(_) => {
-                                log::info!("[MONAD-BACKEND] Vote {} on proposal {} by {}", 
-                                    if approve { "YES" } else { "NO" }, proposal_id, did);
+                                log::info!("[MONAD-BACKEND] Vote {} on proposal {} queued by {}", 
+                                    if approve { "YES" } else { "NO" }, proposal_id, wallet.did());
                                 CommandResult::success(&cmd_id, CommandData::Text(
-                                    format!("Voted {} on proposal {}", if approve { "YES" } else { "NO" }, proposal_id)
+                                    format!("Vote {} on proposal {} queued for the next block", if approve { "YES" } else { "NO" }, proposal_id)
                                 ))
                             }
                             Err(e) => CommandResult::failure(&cmd_id, e.to_string(), false)
@@ -178,18 +213,30 @@ impl MonadBackend {
                     }
                     
                     TransactionPayload::CreateProposal { title, description } => {
-                        let proposal_id = self.gov.lock().unwrap().create_proposal(&title);
-                        log::info!("[MONAD-BACKEND] Created proposal {}: {}", proposal_id, title);
-                        CommandResult::success(&cmd_id, CommandData::Text(
-                            format!("Created proposal #{}: {}", proposal_id, title)
-                        ))
+                        let wallet = self.wallet.lock().unwrap();
+                        let data = TransactionData::Propose {
+                            title: title.clone(),
+                            description,
+                            action: ProposalAction::Signal,
+                        };
+                        match self.chain.queue_signed(&self.mempool, &wallet, data) {
+                            Ok(_) => {
+                                log::info!("[MONAD-BACKEND] Proposal queued: {}", title);
+                                CommandResult::success(&cmd_id, CommandData::Text(
+                                    format!("Proposal '{}' queued for the next block", title)
+                                ))
+                            }
+                            Err(e) => CommandResult::failure(&cmd_id, e.to_string(), false)
+                        }
                     }
                     
                     TransactionPayload::StoreAttestation { data_hash, proof } => {
                         // Store attestation on chain
-                        let did = self.wallet.lock().unwrap().did().to_string();
-                        let tx = self.chain.attest_intent(&did, "store_attestation", &proof, &hex::encode(&data_hash));
-                        self.mempool.lock().unwrap().push(tx);
+                        let wallet = self.wallet.lock().unwrap();
+                        let nonce = next_nonce(&self.chain, &self.mempool, wallet.did());
+                        let tx = self.chain.attest_intent(&wallet, "store_attestation", &proof, &hex::encode(&data_hash), nonce);
+                        drop(wallet);
+                        queue_transaction(&self.chain, &self.mempool, tx);
                         log::info!("[MONAD-BACKEND] Attestation stored: {}", hex::encode(&data_hash[..8.min(data_hash.len())]));
                         CommandResult::success(&cmd_id, CommandData::StoredHash(data_hash))
                     }
@@ -775,9 +822,8 @@ pub struct KaranaMonad {
     ledger: Arc<Mutex<Ledger>>,
     pos: Arc<ProofOfStorage>,
     gov: Arc<Mutex<Governance>>,
-    dao: Arc<Mutex<KaranaDAO>>,
     chain: Arc<Blockchain>,
-    mempool: Arc<Mutex<Vec<Transaction>>>,
+    mempool: Arc<Mutex<Mempool>>,
     persist: Arc<KaranaPersist>,
     hardware: Arc<KaranaHardware>,
     #[allow(dead_code)]
@@ -915,14 +961,16 @@ impl KaranaMonad {
                         let mut ledger = self.ledger.lock().unwrap();
                         match ledger.transfer(&user_did, &to, amount) {
                             Ok(_) => {
+                                drop(ledger);
                                 let wallet = self.wallet.lock().unwrap();
+                                let nonce = next_nonce(&self.chain, &self.mempool, wallet.did());
                                 let tx = crate::chain::create_signed_transaction(
                                     &wallet,
                                     TransactionData::Transfer { to: to.clone(), amount },
+                                    nonce,
                                 );
                                 drop(wallet);
-                                drop(ledger);
-                                self.mempool.lock().unwrap().push(tx);
+                                queue_transaction(&self.chain, &self.mempool, tx);
                                 let _ = self.hardware.haptic.lock().unwrap().play_pattern(HapticPattern::Success);
                                 CommandResult::success(&cmd_id, CommandData::TxHash(format!("tx_{}", cmd_id)))
                             }
@@ -1116,11 +1164,12 @@ impl KaranaMonad {
         };
         
         // Phase 7.5: Chain attestation
-        let attest_tx = self.chain.attest_intent("Node-Alpha", intent, &proof, &result);
-        {
-            let mut pool = self.mempool.lock().unwrap();
-            pool.push(attest_tx);
-        }
+        let attest_tx = {
+            let wallet = self.wallet.lock().unwrap();
+            let nonce = next_nonce(&self.chain, &self.mempool, wallet.did());
+            self.chain.attest_intent(&wallet, intent, &proof, &result, nonce)
+        };
+        queue_transaction(&self.chain, &self.mempool, attest_tx);
         log::info!("[CHAIN] ✓ Intent attestation queued for next block");
         
         Ok(format!("{}\n[ZK] ✓ Proof: {} bytes\n{}\n[CHAIN] ✓ Attestation queued", result, proof.len(), haptic_msg))
@@ -1144,6 +1193,18 @@ impl KaranaMonad {
         log::info!("Igniting Karana AI (Phi-3 Simulated)...");
         let ai = Arc::new(Mutex::new(KaranaAI::new().context("AI Ignition failed")?));
 
+        // Phase 7.6: Follow battery, heat and memory with the generative model size
+        match ModelSwapper::new(ai.clone()) {
+            Ok(swapper) => {
+                let swapper = Arc::new(swapper);
+                let monitor = ResourceMonitor::new();
+                monitor.start_monitoring().await;
+                swapper.clone().spawn_monitor(&monitor);
+                swapper.spawn_power_events(Arc::new(Mutex::new(PowerManager::new())));
+            }
+            Err(e) => log::warn!("[ModelSwap] Live model swapping disabled: {}", e),
+        }
+
         // Atom 8: Identity (Phase 4)
         let identity = Arc::new(Mutex::new(KaranaIdentity::new()?));
 
@@ -1164,25 +1225,23 @@ impl KaranaMonad {
              hardware.start_simulation();
         }
         
-        let ui = Arc::new(KaranaUI::new(&runtime, &swarm, ai.clone(), hardware.clone(), identity.clone())?);
-        
         // Atom 4: Economy (Persistent Ledger)
         let ledger_path = format!("{}/karana-ledger", base_path);
         let ledger = Arc::new(Mutex::new(Ledger::new(&ledger_path)));
         let pos = Arc::new(ProofOfStorage::new(ledger.clone()));
         
+        // Atom 4: Governance (shared by the chain and the UI)
         let gov_path = format!("{}/karana-governance", base_path);
         let gov = Arc::new(Mutex::new(Governance::new(&gov_path, ledger.clone(), ai.clone())));
-        
-        // Atom 4: DAO (Phase 4)
-        let dao = Arc::new(Mutex::new(KaranaDAO::default()));
 
         // Atom 7: Vigil (Needs Ledger for Slashing)
         let vigil = Arc::new(KaranaVeil::new(ai.clone(), &runtime, ledger.clone())?);
 
         // Phase 7: Sovereign Chain State (Persistent)
-        let chain = Arc::new(Blockchain::new(ledger.clone(), gov.clone()));
-        let mempool = Arc::new(Mutex::new(Vec::new()));
+        let chain_path = format!("{}/karana-chain", base_path);
+        let chain = Arc::new(Blockchain::open(&chain_path, ledger.clone(), gov.clone())
+            .context("Chain store open failed")?);
+        let mempool = Arc::new(Mutex::new(Mempool::new(MempoolConfig::default())));
 
         // Phase v1.0: Persistent State
         let persist = Arc::new(KaranaPersist::new("/dev/sda1")); // Stub root dev
@@ -1217,6 +1276,12 @@ impl KaranaMonad {
         };
         let wallet = Arc::new(Mutex::new(wallet));
 
+        // The UI submits proposals and votes as signed transactions
+        let ui = Arc::new(
+            KaranaUI::new(&runtime, &swarm, ai.clone(), gov.clone(), hardware.clone(), identity.clone())?
+                .with_chain(chain.clone(), mempool.clone(), wallet.clone()),
+        );
+
         // Phase 8: Real AI ↔ Blockchain Oracle (with wallet for signing)
         // Connects AI intent understanding to REAL blockchain operations
         // NOTE: Oracle needs its own wallet instance since it wraps in Arc<Mutex<>>
@@ -1267,6 +1332,18 @@ impl KaranaMonad {
             MinimalManifest::default()
         ));
         
+        // Phase 7.7: Oracle long-term memory, summarized by the local AI and kept on disk
+        let memory_path = std::path::PathBuf::from(format!("{}/karana-memory.json", base_path));
+        let oracle_memory = match OracleMemory::open(memory_path) {
+            Ok(memory) => memory,
+            Err(e) => {
+                log::warn!("[ORACLE-VEIL] Starting with empty memory: {:#}", e);
+                OracleMemory::new()
+            }
+        };
+        let oracle_memory = Arc::new(oracle_memory.with_summarizer(ai.clone()));
+        oracle_memory.clone().spawn_consolidation(std::time::Duration::from_secs(MEMORY_CONSOLIDATION_SECS));
+        
         // Create Oracle Veil - THE sole user interface
         // Uses local Phi-3 AI (via KaranaAI) for intent parsing - NO cloud APIs
         let oracle_veil = match OracleVeil::new(
@@ -1276,7 +1353,7 @@ impl KaranaMonad {
         ) {
             Ok(veil) => {
                 log::info!("[ORACLE-VEIL] ✓ OracleVeil initialized with local AI");
-                Some(Arc::new(tokio::sync::Mutex::new(veil)))
+                Some(Arc::new(tokio::sync::Mutex::new(veil.with_memory(oracle_memory))))
             }
             Err(e) => {
                 log::warn!("[ORACLE-VEIL] Failed to initialize OracleVeil: {} (using legacy Oracle)", e);
@@ -1303,7 +1380,6 @@ impl KaranaMonad {
             ledger,
             pos,
             gov,
-            dao,
             chain,
             mempool,
             persist,
@@ -1376,16 +1452,14 @@ impl KaranaMonad {
         // Atom 4: DAO Ignition (Phase 4)
         log::info!("Step 4/8: DAO Ignition...");
         {
-            let mut dao = self.dao.lock().unwrap();
-            dao.token.mint("genesis", U256::from(1000u64));
-            let prop = dao.propose("Ignite AI Governance", "Enable on-chain votes for all tunes");
-            log::info!("Atom 4 (DAO): Proposed '{}' (ID: {})", "Ignite AI Governance", prop);
-            
-            if dao.vote("genesis", prop, true).unwrap() {
-                log::info!("Atom 4 (DAO): Vote Passed! Executing Governance Ignition...");
-                // Runtime effect (simulated)
-                // self.runtime.ignite_governance().await?; 
-            }
+            let wallet = self.wallet.lock().unwrap();
+            let data = TransactionData::Propose {
+                title: "Ignite AI Governance".to_string(),
+                description: "Enable on-chain votes for all tunes".to_string(),
+                action: ProposalAction::Signal,
+            };
+            let tx = self.chain.queue_signed(&self.mempool, &wallet, data)?;
+            log::info!("Atom 4 (DAO): Proposed '{}' (tx {}), voting opens once it is in a block", "Ignite AI Governance", tx.hash);
         }
         log::info!("Step 4/8: DAO Ignition [OK]");
 
@@ -1443,8 +1517,12 @@ impl KaranaMonad {
 
         // Atom 4: Governance Simulation
         log::info!("Atom 4: Simulating Governance...");
-        let proposal_id = self.gov.lock().unwrap().create_proposal("Upgrade Storage Sharding");
-        self.gov.lock().unwrap().vote(proposal_id, "Node-Alpha", true)?;
+        let data = TransactionData::Propose {
+            title: "Upgrade Storage Sharding".to_string(),
+            description: "Upgrade Storage Sharding".to_string(),
+            action: ProposalAction::Signal,
+        };
+        self.chain.queue_signed(&self.mempool, &self.wallet.lock().unwrap(), data)?;
 
         // Atom 5: Verify Tiered Storage Read
         log::info!("Atom 5: Verifying Tiered Storage Read...");
@@ -1472,8 +1550,8 @@ impl KaranaMonad {
 
         log::info!("=== SYSTEM READY: Entering Consensus Loop ===");
         
-        let mut height = 1;
-        let mut parent_hash = "0000000000000000000000000000000000000000000000000000000000000000".to_string();
+        // Resume block production from the persisted tip
+        let mut height = self.chain.height() + 1;
 
         let mut last_block_time = std::time::Instant::now();
 
@@ -1483,7 +1561,49 @@ impl KaranaMonad {
                 match event {
                     KaranaSwarmEvent::BlockReceived(block) => {
                         log::info!("Atom 6 (P2P): Received Block #{} from Swarm", block.header.height);
-                        // In a real node, we would validate and add to chain if it's the next block
+                        let received = block.clone();
+                        match self.chain.import_block(block) {
+                            Ok(ImportOutcome::Extended) => {
+                                self.mempool.lock().unwrap().on_block_committed(&received, |did| self.chain.account_nonce(did));
+                            }
+                            Ok(ImportOutcome::Reorged { reverted }) => {
+                                let requeued = self.mempool.lock().unwrap().on_reorg(&reverted, |did| self.chain.account_nonce(did));
+                                log::info!("Atom 1 (Chain): Reorg dropped {} blocks, re-queued {} txs", reverted.len(), requeued);
+                            }
+                            Ok(ImportOutcome::Orphan { missing }) => {
+                                log::info!("Atom 1 (Chain): Missing ancestor {}, requesting sync", missing);
+                                if let Err(e) = self.swarm.request_sync(chain_sync::request(&self.chain)).await {
+                                    log::error!("Atom 6 (P2P): Failed to request sync: {}", e);
+                                }
+                            }
+                            Ok(_) => {}
+                            Err(e) => log::warn!("Atom 1 (Chain): Rejected Block #{}: {}", received.header.height, e),
+                        }
+                        self.ui.update_height(self.chain.height());
+                        height = self.chain.height() + 1;
+                    },
+                    KaranaSwarmEvent::SyncRequested(req) => {
+                        let res = chain_sync::serve(&self.chain, &req);
+                        if !res.blocks.is_empty() {
+                            if let Err(e) = self.swarm.send_sync_response(res).await {
+                                log::error!("Atom 6 (P2P): Failed to serve sync: {}", e);
+                            }
+                        }
+                    },
+                    KaranaSwarmEvent::SyncReceived(res) => {
+                        let progress = chain_sync::apply(&self.chain, &res);
+                        if progress.imported > 0 {
+                            let requeued = self.mempool.lock().unwrap().on_reorg(&progress.reverted, |did| self.chain.account_nonce(did));
+                            log::info!("Atom 1 (Chain): Synced {} blocks (tip #{}, {} txs re-queued)",
+                                progress.imported, self.chain.height(), requeued);
+                            self.ui.update_height(self.chain.height());
+                            height = self.chain.height() + 1;
+                        }
+                        if !progress.caught_up && progress.imported > 0 {
+                            if let Err(e) = self.swarm.request_sync(chain_sync::request(&self.chain)).await {
+                                log::error!("Atom 6 (P2P): Failed to request sync: {}", e);
+                            }
+                        }
                     },
                     KaranaSwarmEvent::GenericMessage(msg) => {
                         log::info!("Atom 6 (P2P): Message: {}", msg);
@@ -1546,6 +1666,10 @@ impl KaranaMonad {
                     KaranaSwarmEvent::PeerConnected(peer_id) => {
                         log::info!("[SWARM] ✓ New peer joined: {}", peer_id);
                         log::info!("[SWARM] {}", self.swarm.stats.summary());
+                        // Catch up with whatever the new peer has
+                        if let Err(e) = self.swarm.request_sync(chain_sync::request(&self.chain)).await {
+                            log::error!("Atom 6 (P2P): Failed to request sync: {}", e);
+                        }
                     }
                 }
             }
@@ -1625,11 +1749,12 @@ impl KaranaMonad {
                             let _ = self.ui.render_intent(response, vec![]).await;
                             
                             // Attest the query to chain
-                            let attest_tx = self.chain.attest_intent(&user_did, &intent, &[], "oracle_query");
-                            {
-                                let mut pool = self.mempool.lock().unwrap();
-                                pool.push(attest_tx);
-                            }
+                            let attest_tx = {
+                                let wallet = self.wallet.lock().unwrap();
+                                let nonce = next_nonce(&self.chain, &self.mempool, wallet.did());
+                                self.chain.attest_intent(&wallet, &intent, &[], "oracle_query", nonce)
+                            };
+                            queue_transaction(&self.chain, &self.mempool, attest_tx);
                             continue;
                         },
                         Err(e) => {
@@ -1677,57 +1802,60 @@ impl KaranaMonad {
             if last_block_time.elapsed() >= std::time::Duration::from_secs(5) {
                 last_block_time = std::time::Instant::now();
                 
-                let mut txs = Vec::new();
-                {
-                    let mut pool = self.mempool.lock().unwrap();
-                    // Create a REAL signed transaction for liveness every other block
-                    if height % 2 == 0 {
-                        let wallet = self.wallet.lock().unwrap();
-                        let tx = crate::chain::create_signed_transaction(
-                            &wallet,
-                            TransactionData::Transfer { 
-                                to: "Node-Beta".to_string(), 
-                                amount: 10u128 
-                            },
-                        );
-                        log::info!("[CHAIN] Created signed tx: {} → Node-Beta (10 KARA) [Ed25519 ✓]", 
-                            &wallet.did()[..20]);
-                        txs.push(tx);
-                    }
-                    txs.append(&mut pool);
+                // Create a REAL signed transaction for liveness every other block
+                if height % 2 == 0 {
+                    let wallet = self.wallet.lock().unwrap();
+                    let nonce = next_nonce(&self.chain, &self.mempool, wallet.did());
+                    let tx = crate::chain::create_signed_transaction(
+                        &wallet,
+                        TransactionData::Transfer { 
+                            to: "Node-Beta".to_string(), 
+                            amount: 10u128 
+                        },
+                        nonce,
+                    );
+                    log::info!("[CHAIN] Created signed tx: {} → Node-Beta (10 KARA) [Ed25519 ✓]", 
+                        &wallet.did()[..20]);
+                    drop(wallet);
+                    queue_transaction(&self.chain, &self.mempool, tx);
                 }
+
+                // Pull transactions in nonce/arrival order, dropping stale ones
+                let txs = {
+                    let mut pool = self.mempool.lock().unwrap();
+                    pool.evict_stale();
+                    pool.transactions()
+                };
                 
-                // Create Block (use our DID as proposer)
+                // Create Block (use our DID as proposer): execute, seal with state root, persist
                 let proposer = self.wallet.lock().unwrap().did().to_string();
-                let block = Block::new(parent_hash.clone(), height, proposer, txs.clone());
-                log::info!("Atom 1 (Chain): Produced Block #{} [Hash: {}] with {} txs", height, block.hash, txs.len());
-                
-                // Update UI
-                self.ui.update_height(height);
+                match self.chain.produce_block(&proposer, txs.clone()) {
+                    Ok(block) => {
+                        log::info!("Atom 1 (Chain): Produced Block #{} [Hash: {}, State: {}] with {} txs",
+                            block.header.height, block.hash, &block.header.state_root[..16], block.transactions.len());
 
-                // Validate Block
-                if let Err(e) = block.validate(&parent_hash) {
-                    log::error!("Atom 1 (Chain): Block Validation Failed: {}", e);
-                    continue;
-                }
+                        // Forget included transactions and any the block had to drop
+                        {
+                            let mut pool = self.mempool.lock().unwrap();
+                            for tx in txs.iter().filter(|tx| !block.transactions.iter().any(|b| b.sender == tx.sender && b.nonce == tx.nonce)) {
+                                pool.remove(tx);
+                            }
+                            pool.on_block_committed(&block, |did| self.chain.account_nonce(did));
+                        }
 
-                // Apply Block
-                if let Err(e) = self.chain.apply_block(&block) {
-                    log::error!("Atom 1 (Chain): Block Application Failed: {}", e);
-                } else {
-                    log::info!("Atom 1 (Chain): Block #{} Applied Successfully. Hash: {}", height, block.hash);
-                    // Persist Block
-                    if let Err(e) = self.storage.persist_block(&block) {
-                        log::error!("Atom 1 (Chain): Failed to persist block: {}", e);
+                        // Update UI
+                        self.ui.update_height(block.header.height);
+
+                        // Broadcast Block
+                        if let Err(e) = self.swarm.broadcast_chain_block(&block).await {
+                            log::error!("Atom 6 (P2P): Failed to broadcast block: {}", e);
+                        }
+                        height = block.header.height + 1;
                     }
-                    // Broadcast Block
-                    if let Err(e) = self.swarm.broadcast_chain_block(&block).await {
-                        log::error!("Atom 6 (P2P): Failed to broadcast block: {}", e);
+                    Err(e) => {
+                        log::error!("Atom 1 (Chain): Block Production Failed at #{}: {}", height, e);
                     }
                 }
-                
-                parent_hash = block.hash;
-                height += 1;
             }
 
             tokio::time::sleep(std::time::Duration::from_millis(100)).await;
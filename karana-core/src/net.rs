@@ -1,6 +1,7 @@
 use anyhow::Result;
 use crate::storage::StorageBlob;
 use crate::chain::Block as ChainBlock;
+use crate::chain::{SyncRequest, SyncResponse};
 use crate::ai::KaranaAI;
 use std::sync::{Arc, Mutex};
 use std::sync::atomic::{AtomicU64, Ordering};
@@ -73,6 +74,13 @@ pub struct ClipboardSync {
     pub timestamp: u64,
 }
 
+/// Block catch-up messages (see `chain::sync`)
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum ChainSyncMessage {
+    Request(SyncRequest),
+    Response(SyncResponse),
+}
+
 #[derive(NetworkBehaviour)]
 struct KaranaBehaviour {
     gossipsub: gossipsub::Behaviour,
@@ -88,6 +96,7 @@ enum SwarmCmd {
     SendAIResponse(AIComputeResponse),
     SyncClipboard(ClipboardSync),
     SendEcho(SwarmEcho),
+    ChainSync(ChainSyncMessage),
 }
 
 #[derive(Debug, Clone)]
@@ -99,6 +108,8 @@ pub enum KaranaSwarmEvent {
     ClipboardReceived(ClipboardSync),
     EchoReceived(SwarmEcho),
     PeerConnected(String),
+    SyncRequested(SyncRequest),
+    SyncReceived(SyncResponse),
 }
 
 #[derive(Clone)]
@@ -135,6 +146,8 @@ impl KaranaSwarm {
                     .heartbeat_interval(Duration::from_secs(10))
                     .validation_mode(gossipsub::ValidationMode::Strict)
                     .message_id_fn(message_id_fn)
+                    // Sync responses carry batches of blocks
+                    .max_transmit_size(4 * 1024 * 1024)
                     .build()
                     .map_err(|msg| std::io::Error::new(std::io::ErrorKind::Other, msg))?;
 
@@ -207,6 +220,15 @@ impl KaranaSwarm {
                                 continue;
                             }
                             
+                            if let Ok(msg) = serde_json::from_slice::<ChainSyncMessage>(&message.data) {
+                                let event = match msg {
+                                    ChainSyncMessage::Request(req) => KaranaSwarmEvent::SyncRequested(req),
+                                    ChainSyncMessage::Response(res) => KaranaSwarmEvent::SyncReceived(res),
+                                };
+                                let _ = event_tx.send(event).await;
+                                continue;
+                            }
+                            
                             // Try to deserialize as Block
                             if let Ok(block) = serde_json::from_slice::<ChainBlock>(&message.data) {
                                 // Send echo back
@@ -236,6 +258,8 @@ impl KaranaSwarm {
                         SwarmEvent::ConnectionEstablished { peer_id, .. } => {
                             stats_clone.peers_connected.fetch_add(1, Ordering::Relaxed);
                             log::info!("[SWARM] ✓ Peer connected: {:?}", peer_id);
+                            // Dialed peers get gossip directly, like mDNS-discovered ones
+                            swarm.behaviour_mut().gossipsub.add_explicit_peer(&peer_id);
                             let _ = event_tx.send(KaranaSwarmEvent::PeerConnected(peer_id.to_string())).await;
                         },
                         SwarmEvent::ConnectionClosed { peer_id, .. } => {
@@ -295,6 +319,14 @@ impl KaranaSwarm {
                                     }
                                 }
                             },
+                            SwarmCmd::ChainSync(msg) => {
+                                let topic = gossipsub::IdentTopic::new("karana-blocks");
+                                if let Ok(data) = serde_json::to_vec(&msg) {
+                                    if let Err(e) = swarm.behaviour_mut().gossipsub.publish(topic, data) {
+                                        log::info!("Atom 6 (P2P): Chain sync publish error: {:?}", e);
+                                    }
+                                }
+                            },
                             SwarmCmd::ZkDial { peer, proof: _ } => {
                                 // log::info!("Atom 6 (P2P): ZK-Dialing peer: {:?}", peer);
                                 match swarm.dial(peer.clone()) {
@@ -350,6 +382,20 @@ impl KaranaSwarm {
         Ok(())
     }
 
+    /// Ask peers for the blocks following our chain
+    pub async fn request_sync(&self, req: SyncRequest) -> Result<()> {
+        log::info!("[SWARM] Requesting blocks after {} locator hashes", req.locator.len());
+        self.cmd_tx.send(SwarmCmd::ChainSync(ChainSyncMessage::Request(req))).await?;
+        Ok(())
+    }
+
+    /// Answer a peer's sync request
+    pub async fn send_sync_response(&self, res: SyncResponse) -> Result<()> {
+        log::info!("[SWARM] Serving {} blocks for sync {}", res.blocks.len(), res.request_id);
+        self.cmd_tx.send(SwarmCmd::ChainSync(ChainSyncMessage::Response(res))).await?;
+        Ok(())
+    }
+
     /// Phase 7.3: Broadcast with echo confirmation - returns message ID for tracking
     pub async fn broadcast_with_tracking(&self, data: Vec<u8>, label: &str) -> Result<String> {
         let msg_id = format!("{}-{}", label, uuid::Uuid::new_v4().to_string()[..8].to_string());
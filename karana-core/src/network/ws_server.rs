@@ -2,7 +2,7 @@
 // Enables instant UI feedback for voice commands and tool execution
 
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex as StdMutex};
 use tokio::sync::{Mutex, RwLock, broadcast};
 use tokio::net::{TcpListener, TcpStream};
 use tokio_tungstenite::{accept_async, tungstenite::Message};
@@ -10,6 +10,9 @@ use futures_util::{StreamExt, SinkExt};
 use serde::{Serialize, Deserialize};
 use anyhow::{Result, anyhow};
 
+use crate::ai::{CancellationToken, GenerationStream, KaranaAI, StreamEvent};
+use crate::ai::streaming::DEFAULT_STREAM_TOKENS;
+
 /// WebSocket message types
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type")]
@@ -38,6 +41,28 @@ pub enum WsMessage {
         app_state: String,
         visible_elements: Vec<String>,
     },
+    /// Request a streamed answer (client -> server)
+    Generate {
+        request_id: String,
+        prompt: String,
+        #[serde(default)]
+        max_tokens: Option<usize>,
+    },
+    /// Stop a streamed answer early (client -> server)
+    CancelGeneration {
+        request_id: String,
+    },
+    /// Partial answer: text to append to what is shown so far
+    GenerationToken {
+        request_id: String,
+        delta: String,
+    },
+    /// Streamed answer finished; `text` is the complete answer
+    GenerationDone {
+        request_id: String,
+        text: String,
+        cancelled: bool,
+    },
     /// Error notification
     Error {
         message: String,
@@ -66,6 +91,10 @@ pub struct WsServer {
     clients: Arc<RwLock<HashMap<String, tokio::sync::mpsc::UnboundedSender<Message>>>>,
     broadcast_tx: broadcast::Sender<WsMessage>,
     broadcast_rx: broadcast::Receiver<WsMessage>,
+    /// Engine for streamed answers (optional)
+    ai: Option<Arc<StdMutex<KaranaAI>>>,
+    /// Streamed answers in flight, keyed by "client_id/request_id"
+    generations: Arc<Mutex<HashMap<String, CancellationToken>>>,
 }
 
 impl WsServer {
@@ -76,9 +105,17 @@ impl WsServer {
             clients: Arc::new(RwLock::new(HashMap::new())),
             broadcast_tx,
             broadcast_rx,
+            ai: None,
+            generations: Arc::new(Mutex::new(HashMap::new())),
         }
     }
 
+    /// Enable streamed answers (`Generate` messages) using this engine
+    pub fn with_ai(mut self, ai: Arc<StdMutex<KaranaAI>>) -> Self {
+        self.ai = Some(ai);
+        self
+    }
+
     /// Start WebSocket server
     pub async fn start(self: Arc<Self>, addr: &str) -> Result<()> {
         let listener = TcpListener::bind(addr).await?;
@@ -110,6 +147,7 @@ impl WsServer {
         let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
 
         // Register client
+        let forward_tx = tx.clone();
         {
             let mut clients = self.clients.write().await;
             clients.insert(client_id.clone(), tx);
@@ -133,7 +171,7 @@ impl WsServer {
         tokio::spawn(async move {
             while let Ok(msg) = broadcast_rx.recv().await {
                 if let Ok(json) = serde_json::to_string(&msg) {
-                    if let Err(e) = rx.send(Message::Text(json)) {
+                    if let Err(e) = forward_tx.send(Message::Text(json)) {
                         log::debug!("[WS] Client {} channel closed: {}", client_id_clone, e);
                         break;
                     }
@@ -181,6 +219,13 @@ impl WsServer {
             let mut clients = self.clients.write().await;
             clients.remove(&client_id);
         }
+        let prefix = format!("{}/", client_id);
+        self.generations.lock().await.retain(|key, cancel| {
+            if key.starts_with(&prefix) {
+                cancel.cancel();
+            }
+            !key.starts_with(&prefix)
+        });
         log::info!("[WS] Client {} disconnected", client_id);
 
         Ok(())
@@ -198,6 +243,21 @@ impl WsServer {
                 // Respond with pong
                 self.send_to_client(client_id, WsMessage::Pong).await?;
             }
+            WsMessage::Generate { request_id, prompt, max_tokens } => {
+                let max_tokens = max_tokens.unwrap_or(DEFAULT_STREAM_TOKENS);
+                if let Err(e) = self.start_generation(client_id, request_id, &prompt, max_tokens).await {
+                    self.send_to_client(client_id, WsMessage::Error {
+                        message: e.to_string(),
+                        code: "generation_unavailable".to_string(),
+                    }).await?;
+                }
+            }
+            WsMessage::CancelGeneration { request_id } => {
+                let key = format!("{}/{}", client_id, request_id);
+                if let Some(cancel) = self.generations.lock().await.remove(&key) {
+                    cancel.cancel();
+                }
+            }
             _ => {
                 log::debug!("[WS] Received {:?} from client", msg);
             }
@@ -206,6 +266,57 @@ impl WsServer {
         Ok(())
     }
 
+    /// Stream an answer to one client as `GenerationToken`s followed by `GenerationDone`
+    async fn start_generation(
+        &self,
+        client_id: &str,
+        request_id: String,
+        prompt: &str,
+        max_tokens: usize,
+    ) -> Result<()> {
+        let ai = self.ai.clone()
+            .ok_or_else(|| anyhow!("No AI engine attached to the WebSocket server"))?;
+        let mut stream = GenerationStream::spawn_in_session(ai, client_id, prompt, max_tokens);
+
+        let key = format!("{}/{}", client_id, request_id);
+        self.generations.lock().await.insert(key.clone(), stream.cancellation_token());
+
+        let clients = self.clients.clone();
+        let generations = self.generations.clone();
+        let client_id = client_id.to_string();
+        tokio::spawn(async move {
+            while let Some(event) = stream.next().await {
+                let msg = match event {
+                    StreamEvent::Token { delta } => WsMessage::GenerationToken {
+                        request_id: request_id.clone(),
+                        delta,
+                    },
+                    StreamEvent::Done { text, cancelled } => WsMessage::GenerationDone {
+                        request_id: request_id.clone(),
+                        text,
+                        cancelled,
+                    },
+                    StreamEvent::Error { message } => WsMessage::Error {
+                        message,
+                        code: "generation_failed".to_string(),
+                    },
+                };
+                let Ok(json) = serde_json::to_string(&msg) else { break };
+                let sent = match clients.read().await.get(&client_id) {
+                    Some(tx) => tx.send(Message::Text(json)).is_ok(),
+                    None => false,
+                };
+                // Client gone: dropping the stream cancels the generation
+                if !sent {
+                    break;
+                }
+            }
+            generations.lock().await.remove(&key);
+        });
+
+        Ok(())
+    }
+
     /// Broadcast message to all connected clients
     pub async fn broadcast(&self, msg: WsMessage) -> Result<()> {
         log::debug!("[WS] Broadcasting {:?}", msg);
@@ -328,6 +439,25 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[tokio::test]
+    async fn test_generate_without_ai_reports_error() {
+        let server = WsServer::new();
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        server.clients.write().await.insert("client_1".to_string(), tx);
+
+        let request = r#"{"type": "Generate", "request_id": "r1", "prompt": "Hi"}"#;
+        server.handle_client_message("client_1", request).await.unwrap();
+
+        let Some(Message::Text(reply)) = rx.recv().await else {
+            panic!("expected a text reply");
+        };
+        match serde_json::from_str::<WsMessage>(&reply).unwrap() {
+            WsMessage::Error { code, .. } => assert_eq!(code, "generation_unavailable"),
+            other => panic!("Wrong message type: {:?}", other),
+        }
+        assert!(server.generations.lock().await.is_empty());
+    }
+
     #[test]
     fn test_ws_message_serialization() {
         let msg = WsMessage::ToolResult {
@@ -2,7 +2,19 @@
 // Phase 42: Real vector embeddings using sentence transformers
 
 use anyhow::{Result, anyhow};
+use candle_core::{Device, DType, Tensor};
+use candle_nn::VarBuilder;
+use candle_transformers::models::bert::{BertModel, Config as BertConfig};
 use std::path::Path;
+use tokenizers::{PaddingParams, PaddingStrategy, Tokenizer, TruncationParams};
+
+use super::cache::EmbeddingCache;
+
+/// Texts per forward pass in `embed_batch`
+pub const EMBED_BATCH_SIZE: usize = 32;
+
+/// Embeddings kept in the generator's cache
+const EMBED_CACHE_CAPACITY: usize = 500;
 
 /// Embedding generator using sentence transformer models
 pub struct EmbeddingGenerator {
@@ -12,6 +24,10 @@ pub struct EmbeddingGenerator {
     pub dim: usize,
     /// Whether model is loaded
     loaded: bool,
+    /// Transformer backend (None for `Stub`)
+    backend: Option<BertEmbedder>,
+    /// Recently embedded texts
+    cache: EmbeddingCache,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -40,24 +56,41 @@ impl EmbeddingGenerator {
             model,
             dim,
             loaded: false,
+            backend: None,
+            cache: EmbeddingCache::new(EMBED_CACHE_CAPACITY),
         })
     }
 
+    /// Create a generator and load it from `model_path`
+    pub fn from_dir(model: EmbeddingModel, model_path: &Path) -> Result<Self> {
+        let mut generator = Self::new(model)?;
+        generator.load(model_path)?;
+        Ok(generator)
+    }
+
     /// Load the model from disk
-    pub fn load(&mut self, _model_path: &Path) -> Result<()> {
-        // TODO: Implement model loading via ONNX or Candle
-        // For now, mark as loaded for stub model
+    ///
+    /// `model_path` is a sentence-transformers export: `config.json`,
+    /// `tokenizer.json` and `model.safetensors`. Nothing is fetched from the
+    /// network. The dimension is taken from the model's hidden size.
+    pub fn load(&mut self, model_path: &Path) -> Result<()> {
         if self.model == EmbeddingModel::Stub {
             self.loaded = true;
             return Ok(());
         }
 
-        // Real implementation would:
-        // 1. Load tokenizer from model_path/tokenizer.json
-        // 2. Load ONNX model from model_path/model.onnx
-        // 3. Initialize inference session
-        
-        Err(anyhow!("Real model loading not yet implemented. Use EmbeddingModel::Stub for now."))
+        let backend = BertEmbedder::load(model_path)?;
+        log::info!(
+            "[Embeddings] Loaded {:?} from {} ({} dims)",
+            self.model,
+            model_path.display(),
+            backend.hidden_size
+        );
+        self.dim = backend.hidden_size;
+        self.backend = Some(backend);
+        self.cache.clear();
+        self.loaded = true;
+        Ok(())
     }
 
     /// Check if model is loaded
@@ -70,34 +103,51 @@ impl EmbeddingGenerator {
         if !self.loaded {
             return Err(anyhow!("Model not loaded. Call load() first."));
         }
-
-        match self.model {
-            EmbeddingModel::Stub => {
-                // Stub: Generate deterministic pseudo-embedding based on text hash
-                Ok(self.stub_embed(text))
-            }
-            _ => {
-                // TODO: Real embedding via ONNX/Candle
-                // 1. Tokenize text
-                // 2. Run through transformer
-                // 3. Mean pooling
-                // 4. Normalize
-                Err(anyhow!("Real embedding not yet implemented"))
-            }
+        if let Some(cached) = self.cache.get(text) {
+            return Ok(cached);
         }
+
+        let embedding = match &self.backend {
+            Some(backend) => backend
+                .embed_batch(&[text])?
+                .pop()
+                .ok_or_else(|| anyhow!("Embedding model returned no output"))?,
+            None => self.stub_embed(text),
+        };
+        self.cache.put(text.to_string(), embedding.clone());
+        Ok(embedding)
     }
 
     /// Generate embeddings for a batch of texts (more efficient)
+    ///
+    /// Cached texts are skipped; the rest run through the model
+    /// `EMBED_BATCH_SIZE` at a time.
     pub fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
         if !self.loaded {
             return Err(anyhow!("Model not loaded. Call load() first."));
         }
 
-        // For now, just call embed() for each text
-        // TODO: Implement true batching for efficiency
-        texts.iter()
-            .map(|text| self.embed(text))
-            .collect()
+        let mut embeddings: Vec<Option<Vec<f32>>> = texts.iter().map(|t| self.cache.get(t)).collect();
+        let missing: Vec<usize> = (0..texts.len()).filter(|&i| embeddings[i].is_none()).collect();
+
+        for chunk in missing.chunks(EMBED_BATCH_SIZE) {
+            let batch: Vec<&str> = chunk.iter().map(|&i| texts[i].as_str()).collect();
+            let computed = match &self.backend {
+                Some(backend) => backend.embed_batch(&batch)?,
+                None => batch.iter().map(|t| self.stub_embed(t)).collect(),
+            };
+            for (&i, embedding) in chunk.iter().zip(computed) {
+                self.cache.put(texts[i].clone(), embedding.clone());
+                embeddings[i] = Some(embedding);
+            }
+        }
+
+        Ok(embeddings.into_iter().flatten().collect())
+    }
+
+    /// Drop all cached embeddings
+    pub fn clear_cache(&self) {
+        self.cache.clear();
     }
 
     /// Stub embedding: deterministic hash-based pseudo-embedding
@@ -141,6 +191,85 @@ impl Default for EmbeddingGenerator {
     }
 }
 
+/// BERT encoder with mean pooling, as used by sentence-transformers
+struct BertEmbedder {
+    model: BertModel,
+    tokenizer: Tokenizer,
+    device: Device,
+    hidden_size: usize,
+}
+
+impl BertEmbedder {
+    fn load(model_path: &Path) -> Result<Self> {
+        let file = |name: &str| {
+            let path = model_path.join(name);
+            if path.is_file() {
+                Ok(path)
+            } else {
+                Err(anyhow!("Embedding model file missing: {}", path.display()))
+            }
+        };
+        let device = Device::Cpu;
+
+        let config: BertConfig = serde_json::from_str(&std::fs::read_to_string(file("config.json")?)?)?;
+
+        let mut tokenizer = Tokenizer::from_file(file("tokenizer.json")?).map_err(|e| anyhow!(e))?;
+        let pad_id = tokenizer.token_to_id("[PAD]").unwrap_or(config.pad_token_id as u32);
+        tokenizer.with_padding(Some(PaddingParams {
+            strategy: PaddingStrategy::BatchLongest,
+            pad_id,
+            pad_token: "[PAD]".to_string(),
+            ..Default::default()
+        }));
+        tokenizer
+            .with_truncation(Some(TruncationParams {
+                max_length: config.max_position_embeddings,
+                ..Default::default()
+            }))
+            .map_err(|e| anyhow!(e))?;
+
+        let vb = unsafe { VarBuilder::from_mmaped_safetensors(&[file("model.safetensors")?], DType::F32, &device)? };
+        let model = BertModel::load(vb, &config)?;
+
+        Ok(Self {
+            model,
+            tokenizer,
+            device,
+            hidden_size: config.hidden_size,
+        })
+    }
+
+    /// One padded forward pass; returns unit-length mean-pooled embeddings
+    fn embed_batch(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+        if texts.is_empty() {
+            return Ok(Vec::new());
+        }
+        let encodings = self.tokenizer.encode_batch(texts.to_vec(), true).map_err(|e| anyhow!(e))?;
+
+        let mut ids = Vec::with_capacity(encodings.len());
+        let mut masks = Vec::with_capacity(encodings.len());
+        for encoding in &encodings {
+            ids.push(Tensor::new(encoding.get_ids(), &self.device)?);
+            masks.push(Tensor::new(encoding.get_attention_mask(), &self.device)?);
+        }
+        let token_ids = Tensor::stack(&ids, 0)?;
+        let attention_mask = Tensor::stack(&masks, 0)?;
+        let token_type_ids = token_ids.zeros_like()?;
+
+        // (batch, seq, hidden)
+        let hidden = self.model.forward(&token_ids, &token_type_ids, Some(&attention_mask))?;
+
+        // Mean over real tokens only; padding must not dilute short texts
+        let mask = attention_mask.to_dtype(DType::F32)?.unsqueeze(2)?;
+        let summed = hidden.broadcast_mul(&mask)?.sum(1)?;
+        let counts = mask.sum(1)?.clamp(1.0, f64::MAX)?;
+        let mean = summed.broadcast_div(&counts)?;
+
+        let norms = mean.sqr()?.sum_keepdim(1)?.sqrt()?.clamp(1e-12, f64::MAX)?;
+        Ok(mean.broadcast_div(&norms)?.to_vec2::<f32>()?)
+    }
+}
+
 /// Compute cosine similarity between two embeddings
 pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
     if a.len() != b.len() {
@@ -251,4 +380,49 @@ mod tests {
         assert_eq!(embeddings[0].len(), 384);
         assert_eq!(embeddings[1].len(), 384);
     }
+
+    fn tiny_model() -> EmbeddingGenerator {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/tiny-bert");
+        EmbeddingGenerator::from_dir(EmbeddingModel::MiniLM, &dir).unwrap()
+    }
+
+    #[test]
+    fn test_model_embedding_shape_and_ordering() {
+        let generator = tiny_model();
+        assert_eq!(generator.dim, 32);
+
+        let query = generator.embed("The cat sat on the mat.").unwrap();
+        assert_eq!(query.len(), 32);
+        let norm: f32 = query.iter().map(|x| x * x).sum::<f32>().sqrt();
+        assert!((norm - 1.0).abs() < 0.001);
+
+        let close = generator.embed("a cat sat on a rug").unwrap();
+        let far = generator.embed("stocks fell sharply today").unwrap();
+        assert!(cosine_similarity(&query, &close) > cosine_similarity(&query, &far));
+    }
+
+    #[test]
+    fn test_model_batch_matches_single() {
+        let generator = tiny_model();
+        let texts = vec![
+            "dog".to_string(),
+            "the dog ran in the park".to_string(),
+            "glasses battery low".to_string(),
+        ];
+        let batch = generator.embed_batch(&texts).unwrap();
+
+        // Padding the short texts must not change their embeddings
+        generator.clear_cache();
+        for (text, batched) in texts.iter().zip(&batch) {
+            let single = generator.embed(text).unwrap();
+            assert!(l2_distance(&single, batched) < 1e-4, "{text}");
+        }
+    }
+
+    #[test]
+    fn test_missing_model_files() {
+        let mut generator = EmbeddingGenerator::new(EmbeddingModel::BGESmall).unwrap();
+        assert!(generator.load(Path::new("/nonexistent/karana-model")).is_err());
+        assert!(!generator.is_loaded());
+    }
 }
@@ -0,0 +1,778 @@
+// Document ingestion for the personal knowledge base
+// Phase 47: Point the glasses at a folder of manuals and ask about them
+//
+// Files are extracted into sections (the text under one heading, or one PDF
+// page), sections are cut into overlapping chunks on sentence boundaries, and
+// the chunks go into `KnowledgeManager` with their provenance. A manifest next
+// to the knowledge store records each file's content hash and the chunks it
+// produced, so re-ingesting only touches files that changed, and a chunk that
+// appears in several files is stored once.
+
+use anyhow::{Result, anyhow};
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use super::knowledge_manager::{ChunkInput, ChunkProvenance, KnowledgeManager, PrivacyLevel};
+use crate::vision::TextBlock;
+
+/// Supported document formats
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DocumentKind {
+    PlainText,
+    Markdown,
+    Html,
+    Pdf,
+    /// Text read by `vision::ocr`
+    Ocr,
+}
+
+impl DocumentKind {
+    /// Guess the format from a file extension
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "txt" | "text" => Some(Self::PlainText),
+            "md" | "markdown" => Some(Self::Markdown),
+            "html" | "htm" => Some(Self::Html),
+            "pdf" => Some(Self::Pdf),
+            _ => None,
+        }
+    }
+
+    /// Tag attached to chunks of this format
+    pub fn tag(self) -> &'static str {
+        match self {
+            Self::PlainText => "text",
+            Self::Markdown => "markdown",
+            Self::Html => "html",
+            Self::Pdf => "pdf",
+            Self::Ocr => "ocr",
+        }
+    }
+}
+
+/// Text under one heading (or one PDF page)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Section {
+    /// Enclosing headings, outermost first, joined with " > "
+    pub heading: Option<String>,
+    pub text: String,
+    /// Byte offset of `text` in the extracted document (page text for PDFs)
+    pub offset: usize,
+    /// 1-based PDF page
+    pub page: Option<u32>,
+}
+
+/// Chunk sizes, in bytes of UTF-8
+#[derive(Debug, Clone)]
+pub struct ChunkerConfig {
+    /// Upper bound on a chunk's body
+    pub max_bytes: usize,
+    /// Trailing sentences of one chunk repeated at the start of the next
+    pub overlap_bytes: usize,
+}
+
+impl Default for ChunkerConfig {
+    fn default() -> Self {
+        Self {
+            max_bytes: 800,
+            overlap_bytes: 160,
+        }
+    }
+}
+
+/// A chunk ready to embed
+#[derive(Debug, Clone, PartialEq)]
+pub struct DocumentChunk {
+    /// Heading path (if any), a blank line, then the body
+    pub text: String,
+    pub heading: Option<String>,
+    pub offset: usize,
+    pub page: Option<u32>,
+    /// SHA-256 of the whitespace-normalized text, for de-duplication
+    pub hash: String,
+}
+
+/// Split a document into sections
+pub fn extract_sections(kind: DocumentKind, bytes: &[u8]) -> Result<Vec<Section>> {
+    match kind {
+        DocumentKind::PlainText | DocumentKind::Ocr => {
+            let text = String::from_utf8_lossy(bytes);
+            Ok(whole_text_section(&text).into_iter().collect())
+        }
+        DocumentKind::Markdown => Ok(markdown_sections(&String::from_utf8_lossy(bytes))),
+        DocumentKind::Html => Ok(markdown_sections(&html_to_text(&String::from_utf8_lossy(bytes)))),
+        DocumentKind::Pdf => {
+            let pages = pdf_extract::extract_text_from_mem_by_pages(bytes)
+                .map_err(|e| anyhow!("PDF extraction failed: {}", e))?;
+            Ok(pages
+                .iter()
+                .enumerate()
+                .filter_map(|(i, page)| {
+                    whole_text_section(page).map(|s| Section { page: Some(i as u32 + 1), ..s })
+                })
+                .collect())
+        }
+    }
+}
+
+/// OCR blocks in reading order (top to bottom, then left to right)
+pub fn ocr_text(blocks: &[TextBlock]) -> String {
+    let mut blocks: Vec<&TextBlock> = blocks.iter().filter(|b| !b.text.trim().is_empty()).collect();
+    blocks.sort_by(|a, b| a.bounding_box.y.total_cmp(&b.bounding_box.y));
+
+    // Blocks whose tops are within half a line of a row's first block join that row
+    let mut rows: Vec<Vec<&TextBlock>> = Vec::new();
+    for block in blocks {
+        match rows.last_mut() {
+            Some(row) if block.bounding_box.y - row[0].bounding_box.y
+                <= row[0].bounding_box.height.min(block.bounding_box.height) / 2.0 => row.push(block),
+            _ => rows.push(vec![block]),
+        }
+    }
+    for row in &mut rows {
+        row.sort_by(|a, b| a.bounding_box.x.total_cmp(&b.bounding_box.x));
+    }
+    rows.iter().flatten().map(|b| b.text.trim()).collect::<Vec<_>>().join("\n")
+}
+
+fn whole_text_section(text: &str) -> Option<Section> {
+    let body = text.trim();
+    (!body.is_empty()).then(|| Section {
+        heading: None,
+        text: body.to_string(),
+        offset: text.len() - text.trim_start().len(),
+        page: None,
+    })
+}
+
+/// Sections split at ATX headings (`#` .. `######`), ignoring fenced code
+fn markdown_sections(text: &str) -> Vec<Section> {
+    let mut sections = Vec::new();
+    let mut headings: Vec<(usize, String)> = Vec::new();
+    let mut start = 0;
+    let mut offset = 0;
+    let mut in_fence = false;
+
+    for line in text.split_inclusive('\n') {
+        let trimmed = line.trim();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+        } else if !in_fence && let Some((level, title)) = atx_heading(trimmed) {
+            push_section(&mut sections, &headings, text, start, offset);
+            headings.retain(|(l, _)| *l < level);
+            headings.push((level, title));
+            start = offset + line.len();
+        }
+        offset += line.len();
+    }
+    push_section(&mut sections, &headings, text, start, text.len());
+    sections
+}
+
+fn push_section(sections: &mut Vec<Section>, headings: &[(usize, String)], text: &str, start: usize, end: usize) {
+    if let Some(section) = whole_text_section(&text[start..end]) {
+        let heading = (!headings.is_empty())
+            .then(|| headings.iter().map(|(_, t)| t.as_str()).collect::<Vec<_>>().join(" > "));
+        sections.push(Section {
+            heading,
+            offset: start + section.offset,
+            ..section
+        });
+    }
+}
+
+fn atx_heading(line: &str) -> Option<(usize, String)> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    let rest = &line[level..];
+    if level == 0 || level > 6 || !(rest.is_empty() || rest.starts_with(' ')) {
+        return None;
+    }
+    let title = rest.trim().trim_end_matches('#').trim();
+    (!title.is_empty()).then(|| (level, title.to_string()))
+}
+
+/// HTML to text, with `<h1>`..`<h6>` rewritten as Markdown headings so
+/// `markdown_sections` can split on them
+fn html_to_text(html: &str) -> String {
+    let text = Regex::new(r"(?is)<(script|style|noscript|head|template)\b[^>]*>.*?</(script|style|noscript|head|template)\s*>")
+        .unwrap()
+        .replace_all(html, "");
+    let text = Regex::new(r"(?s)<!--.*?-->").unwrap().replace_all(&text, "");
+
+    let tags = Regex::new(r"<[^>]*>").unwrap();
+    let text = Regex::new(r"(?is)<h([1-6])\b[^>]*>(.*?)</h[1-6]\s*>")
+        .unwrap()
+        .replace_all(&text, |caps: &Captures| {
+            let level: usize = caps[1].parse().unwrap_or(1);
+            let title = tags.replace_all(&caps[2], "");
+            format!("\n\n{} {}\n\n", "#".repeat(level), collapse_whitespace(&title))
+        });
+
+    let text = Regex::new(r"(?i)<(br|hr|/?p|/?div|/?li|/?tr|/?ul|/?ol|/?table|/?section|/?article|/?blockquote|/?pre)\b[^>]*>")
+        .unwrap()
+        .replace_all(&text, "\n");
+    let text = decode_entities(&tags.replace_all(&text, ""));
+
+    // Tidy whitespace line by line, keeping paragraph breaks
+    let lines: Vec<String> = text.lines().map(collapse_whitespace).collect();
+    Regex::new(r"\n{3,}")
+        .unwrap()
+        .replace_all(&lines.join("\n"), "\n\n")
+        .trim()
+        .to_string()
+}
+
+fn decode_entities(text: &str) -> String {
+    Regex::new(r"&(#[0-9]+|#[xX][0-9a-fA-F]+|[a-zA-Z]+);")
+        .unwrap()
+        .replace_all(text, |caps: &Captures| {
+            let entity = &caps[1];
+            let decoded = match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                "nbsp" => Some(' '),
+                _ if entity.starts_with("#x") || entity.starts_with("#X") => {
+                    u32::from_str_radix(&entity[2..], 16).ok().and_then(char::from_u32)
+                }
+                _ if entity.starts_with('#') => entity[1..].parse().ok().and_then(char::from_u32),
+                _ => None,
+            };
+            decoded.map_or_else(|| caps[0].to_string(), String::from)
+        })
+        .into_owned()
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Cut sections into overlapping chunks that end on sentence boundaries
+pub fn chunk_sections(sections: &[Section], config: &ChunkerConfig) -> Vec<DocumentChunk> {
+    let max = config.max_bytes.max(1);
+    let mut chunks = Vec::new();
+
+    for section in sections {
+        let sentences = sentence_spans(&section.text, max);
+        let mut first = 0;
+        while first < sentences.len() {
+            // Greedily take sentences while the chunk fits
+            let mut end = first + 1;
+            while end < sentences.len() && sentences[end].1 - sentences[first].0 <= max {
+                end += 1;
+            }
+
+            let start = sentences[first].0;
+            let body = &section.text[start..sentences[end - 1].1];
+            let text = match &section.heading {
+                Some(heading) => format!("{}\n\n{}", heading, body),
+                None => body.to_string(),
+            };
+            chunks.push(DocumentChunk {
+                hash: content_hash(&text),
+                text,
+                heading: section.heading.clone(),
+                offset: section.offset + start,
+                page: section.page,
+            });
+
+            if end == sentences.len() {
+                break;
+            }
+            // Back up over trailing sentences that fit in the overlap, but
+            // always move forward by at least one
+            let mut next = end;
+            while next > first + 1 && sentences[end - 1].1 - sentences[next - 1].0 <= config.overlap_bytes {
+                next -= 1;
+            }
+            first = next;
+        }
+    }
+    chunks
+}
+
+/// Byte ranges of the sentences in `text`, trimmed; sentences end at
+/// `.`/`!`/`?` followed by whitespace or at a line break. Anything longer
+/// than `max` is split between words.
+fn sentence_spans(text: &str, max: usize) -> Vec<(usize, usize)> {
+    let mut spans = Vec::new();
+    let mut start = 0;
+    for (i, c) in text.char_indices() {
+        let end = i + c.len_utf8();
+        let boundary = c == '\n'
+            || (matches!(c, '.' | '!' | '?') && text[end..].chars().next().is_none_or(char::is_whitespace));
+        if boundary {
+            push_span(&mut spans, text, start, end, max);
+            start = end;
+        }
+    }
+    push_span(&mut spans, text, start, text.len(), max);
+    spans
+}
+
+fn push_span(spans: &mut Vec<(usize, usize)>, text: &str, start: usize, end: usize, max: usize) {
+    let piece = &text[start..end];
+    let mut start = start + (piece.len() - piece.trim_start().len());
+    let end = end - (piece.len() - piece.trim_end().len());
+
+    while end > start {
+        if end - start <= max {
+            spans.push((start, end));
+            return;
+        }
+        // Cut at the last space that fits, or mid-word if there is none
+        let window = &text[start..end];
+        let mut cut = window
+            .char_indices()
+            .take_while(|(i, _)| *i <= max)
+            .filter(|(_, c)| c.is_whitespace())
+            .map(|(i, _)| i)
+            .last()
+            .filter(|&i| i > 0)
+            .unwrap_or_else(|| {
+                window.char_indices().map(|(i, _)| i).take_while(|&i| i <= max).last().unwrap_or(0)
+            });
+        if cut == 0 {
+            // A single character wider than `max`
+            cut = window.chars().next().map_or(window.len(), char::len_utf8);
+        }
+        spans.push((start, start + window[..cut].trim_end().len()));
+        start += cut + (window[cut..].len() - window[cut..].trim_start().len());
+    }
+}
+
+fn content_hash(text: &str) -> String {
+    hex::encode(Sha256::digest(collapse_whitespace(text).as_bytes()))
+}
+
+/// How ingested chunks are stored
+#[derive(Debug, Clone)]
+pub struct IngestConfig {
+    pub chunker: ChunkerConfig,
+    pub category: String,
+    /// Tags on every chunk, in addition to the format tag
+    pub tags: Vec<String>,
+    pub privacy: PrivacyLevel,
+}
+
+impl Default for IngestConfig {
+    fn default() -> Self {
+        Self {
+            chunker: ChunkerConfig::default(),
+            category: "documents".to_string(),
+            tags: Vec::new(),
+            privacy: PrivacyLevel::Private,
+        }
+    }
+}
+
+/// What an ingest call changed
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct IngestReport {
+    pub files_ingested: usize,
+    pub files_unchanged: usize,
+    pub files_removed: usize,
+    pub chunks_added: usize,
+    pub chunks_removed: usize,
+    /// Chunks already stored from another file
+    pub duplicates_skipped: usize,
+    /// Files that could not be read or parsed
+    pub errors: Vec<(PathBuf, String)>,
+}
+
+impl IngestReport {
+    fn merge(&mut self, other: IngestReport) {
+        self.files_ingested += other.files_ingested;
+        self.files_unchanged += other.files_unchanged;
+        self.files_removed += other.files_removed;
+        self.chunks_added += other.chunks_added;
+        self.chunks_removed += other.chunks_removed;
+        self.duplicates_skipped += other.duplicates_skipped;
+        self.errors.extend(other.errors);
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ChunkRef {
+    hash: String,
+    provenance: ChunkProvenance,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct FileRecord {
+    /// SHA-256 of the raw file (or OCR text)
+    content_hash: String,
+    chunks: Vec<ChunkRef>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredChunk {
+    id: u64,
+    /// File whose provenance the stored chunk carries
+    owner: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct IngestManifest {
+    files: HashMap<String, FileRecord>,
+    /// Content hash -> stored chunk
+    chunks: HashMap<String, StoredChunk>,
+}
+
+/// Feeds documents into a `KnowledgeManager`, incrementally
+pub struct DocumentIngestor {
+    manager: Arc<KnowledgeManager>,
+    manifest_path: PathBuf,
+    manifest: IngestManifest,
+    config: IngestConfig,
+}
+
+impl DocumentIngestor {
+    /// Create an ingestor; `manifest_path` is loaded if it exists
+    pub fn new(manager: Arc<KnowledgeManager>, manifest_path: PathBuf, config: IngestConfig) -> Result<Self> {
+        let manifest = if manifest_path.exists() {
+            serde_json::from_str(&fs::read_to_string(&manifest_path)?)?
+        } else {
+            IngestManifest::default()
+        };
+        Ok(Self {
+            manager,
+            manifest_path,
+            manifest,
+            config,
+        })
+    }
+
+    /// Ingestor whose manifest sits next to the manager's chunk store
+    pub fn for_manager(manager: Arc<KnowledgeManager>, config: IngestConfig) -> Result<Self> {
+        let manifest_path = manager.storage_path().with_extension("ingest.json");
+        Self::new(manager, manifest_path, config)
+    }
+
+    /// Files currently tracked
+    pub fn files(&self) -> Vec<String> {
+        self.manifest.files.keys().cloned().collect()
+    }
+
+    /// Ingest one file; unchanged files are skipped
+    pub async fn ingest_file(&mut self, path: &Path) -> Result<IngestReport> {
+        let kind = DocumentKind::from_path(path)
+            .ok_or_else(|| anyhow!("Unsupported document type: {}", path.display()))?;
+        let key = fs::canonicalize(path)?.to_string_lossy().into_owned();
+        let bytes = fs::read(path)?;
+
+        let content_hash = hex::encode(Sha256::digest(&bytes));
+        if self.is_unchanged(&key, &content_hash) {
+            return Ok(IngestReport { files_unchanged: 1, ..Default::default() });
+        }
+
+        let sections = extract_sections(kind, &bytes)?;
+        self.sync(key, content_hash, kind, &sections).await
+    }
+
+    /// Ingest every supported file under `dir` (recursively, skipping hidden
+    /// entries), and forget files that have disappeared from it. Per-file
+    /// failures are reported rather than aborting the walk.
+    pub async fn ingest_dir(&mut self, dir: &Path) -> Result<IngestReport> {
+        let root = fs::canonicalize(dir)?;
+        let mut files = Vec::new();
+        collect_files(&root, &mut files)?;
+        files.sort();
+
+        let mut report = IngestReport::default();
+        let mut seen = HashSet::new();
+        for file in files {
+            seen.insert(file.to_string_lossy().into_owned());
+            match self.ingest_file(&file).await {
+                Ok(r) => report.merge(r),
+                Err(e) => {
+                    log::warn!("[Ingest] Skipping {}: {}", file.display(), e);
+                    report.errors.push((file, e.to_string()));
+                }
+            }
+        }
+
+        let vanished: Vec<String> = self
+            .manifest
+            .files
+            .keys()
+            .filter(|k| Path::new(k).starts_with(&root) && !seen.contains(*k))
+            .cloned()
+            .collect();
+        for key in vanished {
+            report.merge(self.remove(&key).await?);
+        }
+        Ok(report)
+    }
+
+    /// Ingest OCR output under `ocr:<label>`; capturing the same label again
+    /// replaces the previous text
+    pub async fn ingest_ocr(&mut self, label: &str, blocks: &[TextBlock]) -> Result<IngestReport> {
+        let key = format!("ocr:{}", label);
+        let text = ocr_text(blocks);
+        let content_hash = hex::encode(Sha256::digest(text.as_bytes()));
+        if self.is_unchanged(&key, &content_hash) {
+            return Ok(IngestReport { files_unchanged: 1, ..Default::default() });
+        }
+
+        let sections = extract_sections(DocumentKind::Ocr, text.as_bytes())?;
+        self.sync(key, content_hash, DocumentKind::Ocr, &sections).await
+    }
+
+    /// Forget a file and delete the chunks only it contributed
+    pub async fn remove_file(&mut self, path: &Path) -> Result<IngestReport> {
+        let key = fs::canonicalize(path)
+            .map(|p| p.to_string_lossy().into_owned())
+            .unwrap_or_else(|_| path.to_string_lossy().into_owned());
+        self.remove(&key).await
+    }
+
+    fn is_unchanged(&self, key: &str, content_hash: &str) -> bool {
+        self.manifest.files.get(key).is_some_and(|r| r.content_hash == content_hash)
+    }
+
+    async fn remove(&mut self, key: &str) -> Result<IngestReport> {
+        let Some(old) = self.manifest.files.remove(key) else {
+            return Ok(IngestReport::default());
+        };
+        let mut report = IngestReport { files_removed: 1, ..Default::default() };
+        report.chunks_removed = match self.release(key, old.chunks.clone()).await {
+            Ok(removed) => removed,
+            Err(e) => {
+                self.manifest.files.insert(key.to_string(), old);
+                return Err(e);
+            }
+        };
+        self.save_manifest()?;
+        Ok(report)
+    }
+
+    /// Bring the stored chunks of `key` in line with `sections`
+    async fn sync(&mut self, key: String, content_hash: String, kind: DocumentKind, sections: &[Section]) -> Result<IngestReport> {
+        let mut report = IngestReport { files_ingested: 1, ..Default::default() };
+        self.forget_deleted_chunks().await;
+
+        let mut refs: Vec<ChunkRef> = Vec::new();
+        let mut texts = HashMap::new();
+        for chunk in chunk_sections(sections, &self.config.chunker) {
+            if texts.contains_key(&chunk.hash) {
+                report.duplicates_skipped += 1;
+                continue;
+            }
+            refs.push(ChunkRef {
+                hash: chunk.hash.clone(),
+                provenance: ChunkProvenance {
+                    file: key.clone(),
+                    offset: chunk.offset,
+                    page: chunk.page,
+                    heading: chunk.heading,
+                },
+            });
+            texts.insert(chunk.hash, chunk.text);
+        }
+
+        // The old record stays in the manifest until the new chunks are stored,
+        // so a failure below leaves the file as it was
+        let old = self.manifest.files.get(&key).cloned();
+        let old_refs: HashMap<&str, &ChunkProvenance> = old
+            .iter()
+            .flat_map(|r| &r.chunks)
+            .map(|r| (r.hash.as_str(), &r.provenance))
+            .collect();
+
+        let mut inputs = Vec::new();
+        let mut new_hashes = Vec::new();
+        let mut moved = HashMap::new();
+        for r in &refs {
+            match self.manifest.chunks.get(&r.hash) {
+                None => {
+                    let mut tags = self.config.tags.clone();
+                    tags.push(kind.tag().to_string());
+                    inputs.push(ChunkInput {
+                        text: texts.remove(&r.hash).unwrap_or_default(),
+                        source: key.clone(),
+                        category: self.config.category.clone(),
+                        tags,
+                        privacy: self.config.privacy,
+                        provenance: Some(r.provenance.clone()),
+                    });
+                    new_hashes.push(r.hash.clone());
+                }
+                // Ours already; only its position may have changed
+                Some(stored) if stored.owner == key => {
+                    if old_refs.get(r.hash.as_str()) != Some(&&r.provenance) {
+                        moved.insert(stored.id, r.provenance.clone());
+                    }
+                }
+                Some(_) => {
+                    if !old_refs.contains_key(r.hash.as_str()) {
+                        report.duplicates_skipped += 1;
+                    }
+                }
+            }
+        }
+
+        let ids = self.manager.add_chunks(inputs).await?;
+        report.chunks_added = ids.len();
+        for (hash, id) in new_hashes.into_iter().zip(ids) {
+            self.manifest.chunks.insert(hash, StoredChunk { id, owner: key.clone() });
+        }
+        self.manager.update_provenance(moved).await?;
+
+        self.manifest.files.insert(key.clone(), FileRecord { content_hash, chunks: refs });
+        if let Some(old) = old {
+            report.chunks_removed = self.release(&key, old.chunks).await?;
+        }
+        self.save_manifest()?;
+
+        log::info!(
+            "[Ingest] {}: +{} -{} chunks ({} duplicates)",
+            key, report.chunks_added, report.chunks_removed, report.duplicates_skipped
+        );
+        Ok(report)
+    }
+
+    /// After `key` stopped referencing `released`, delete chunks no file
+    /// references any more and hand the rest to a file that still does.
+    /// Returns the number deleted.
+    async fn release(&mut self, key: &str, released: Vec<ChunkRef>) -> Result<usize> {
+        // Prefer `key`'s own reference so a chunk it still has stays its own
+        let mut referenced: HashMap<&str, &ChunkRef> = self
+            .manifest
+            .files
+            .values()
+            .flat_map(|r| &r.chunks)
+            .map(|r| (r.hash.as_str(), r))
+            .collect();
+        for r in self.manifest.files.get(key).iter().flat_map(|r| &r.chunks) {
+            referenced.insert(r.hash.as_str(), r);
+        }
+
+        let mut deleted = Vec::new();
+        let mut moved = HashMap::new();
+        for r in released {
+            match referenced.get(r.hash.as_str()) {
+                None => {
+                    if let Some(stored) = self.manifest.chunks.remove(&r.hash) {
+                        deleted.push(stored.id);
+                    }
+                }
+                Some(other) => {
+                    if let Some(stored) = self.manifest.chunks.get_mut(&r.hash)
+                        && stored.owner == key
+                        && other.provenance.file != key
+                    {
+                        stored.owner = other.provenance.file.clone();
+                        moved.insert(stored.id, other.provenance.clone());
+                    }
+                }
+            }
+        }
+
+        self.manager.update_provenance(moved).await?;
+        self.manager.delete_chunks(&deleted).await
+    }
+
+    /// Chunks the user deleted by hand are re-added on the next ingest
+    async fn forget_deleted_chunks(&mut self) {
+        let live: HashSet<u64> = self.manager.get_all_chunks().await.iter().map(|c| c.id).collect();
+        self.manifest.chunks.retain(|_, stored| live.contains(&stored.id));
+    }
+
+    fn save_manifest(&self) -> Result<()> {
+        fs::write(&self.manifest_path, serde_json::to_string_pretty(&self.manifest)?)?;
+        Ok(())
+    }
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+        let path = entry.path();
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            collect_files(&path, files)?;
+        } else if file_type.is_file() && DocumentKind::from_path(&path).is_some() {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vision::BoundingBox;
+
+    #[test]
+    fn test_markdown_sections_follow_headings() {
+        let md = "Intro line.\n\n# Setup\nCharge it.\n\n## Pairing\nHold the button.\n```\n# not a heading\n```\n# Care\nWipe the lens.\n";
+        let sections = markdown_sections(md);
+        let headings: Vec<_> = sections.iter().map(|s| s.heading.as_deref()).collect();
+        assert_eq!(headings, vec![None, Some("Setup"), Some("Setup > Pairing"), Some("Care")]);
+        assert!(sections[2].text.contains("# not a heading"));
+        assert_eq!(&md[sections[3].offset..sections[3].offset + 14], "Wipe the lens.");
+    }
+
+    #[test]
+    fn test_html_headings_and_entities() {
+        let html = "<html><head><title>x</title><style>p{}</style></head><body>\
+            <h1>Manual</h1><p>Fish &amp; chips&nbsp;&#8212; tasty.</p><h2>Battery <b>care</b></h2>\
+            <p>Keep it <i>cool</i>.</p><script>alert(1)</script></body></html>";
+        let sections = markdown_sections(&html_to_text(html));
+        assert_eq!(sections.len(), 2);
+        assert_eq!(sections[0].heading.as_deref(), Some("Manual"));
+        assert_eq!(sections[0].text, "Fish & chips — tasty.");
+        assert_eq!(sections[1].heading.as_deref(), Some("Manual > Battery care"));
+        assert_eq!(sections[1].text, "Keep it cool.");
+    }
+
+    #[test]
+    fn test_chunks_overlap_on_sentence_boundaries() {
+        let text = (1..=12).map(|i| format!("Sentence number {} is here.", i)).collect::<Vec<_>>().join(" ");
+        let sections = vec![Section { heading: Some("Guide".to_string()), text: text.clone(), offset: 10, page: Some(3) }];
+        let config = ChunkerConfig { max_bytes: 100, overlap_bytes: 30 };
+        let chunks = chunk_sections(&sections, &config);
+
+        assert!(chunks.len() > 1);
+        for pair in chunks.windows(2) {
+            let body = |c: &DocumentChunk| c.text.trim_start_matches("Guide\n\n").to_string();
+            let (a, b) = (body(&pair[0]), body(&pair[1]));
+            assert!(a.len() <= 100 && a.ends_with('.') && b.starts_with("Sentence"));
+            // The last sentence of each chunk opens the next one
+            let last = a.rsplit_once(". ").map_or(a.as_str(), |(_, s)| s);
+            assert!(b.starts_with(last), "{a:?} / {b:?}");
+        }
+        let first = &chunks[1];
+        let body = first.text.trim_start_matches("Guide\n\n");
+        assert_eq!(&text[first.offset - 10..first.offset - 10 + body.len()], body);
+        assert!(chunks.iter().all(|c| c.page == Some(3)));
+    }
+
+    #[test]
+    fn test_long_sentences_split_between_words() {
+        let text = "word ".repeat(100);
+        let spans = sentence_spans(text.trim(), 42);
+        assert!(spans.len() > 1);
+        assert!(spans.iter().all(|(s, e)| e - s <= 42 && !text[*s..*e].ends_with(' ')));
+        assert!(sentence_spans("ééééé", 3).iter().all(|(s, e)| e > s));
+    }
+
+    #[test]
+    fn test_ocr_reading_order() {
+        let block = |text: &str, x, y| TextBlock::new(text.to_string(), BoundingBox::new(x, y, 50.0, 20.0), 0.9);
+        let blocks = vec![block("world", 60.0, 2.0), block("second line", 0.0, 40.0), block("hello", 0.0, 0.0)];
+        assert_eq!(ocr_text(&blocks), "hello\nworld\nsecond line");
+    }
+}
@@ -449,6 +449,7 @@ mod tests {
             modified_at: 1000 + id * 100,
             privacy: super::super::knowledge_manager::PrivacyLevel::Private,
             pinned: false,
+            provenance: None,
         }
     }
 
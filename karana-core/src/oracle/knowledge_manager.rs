@@ -3,7 +3,7 @@
 
 use anyhow::{Result, anyhow};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
@@ -11,11 +11,85 @@ use tokio::sync::RwLock;
 
 use super::embeddings::EmbeddingGenerator;
 use super::universal::RagChunk;
+use super::vector_index::{HnswConfig, HnswIndex, IndexMetadata, SearchFilter};
+
+/// Knowledge chunks with lookups by ID and by pin state
+#[derive(Debug, Default)]
+struct ChunkStore {
+    chunks: Vec<UserKnowledgeChunk>,
+
+    /// Chunk ID -> position in `chunks`
+    positions: HashMap<u64, usize>,
+
+    /// IDs of pinned chunks
+    pinned: HashSet<u64>,
+}
+
+impl ChunkStore {
+    fn new(chunks: Vec<UserKnowledgeChunk>) -> Self {
+        let mut store = Self { chunks, ..Self::default() };
+        store.reindex();
+        store
+    }
+
+    fn reindex(&mut self) {
+        self.positions = self.chunks.iter().enumerate().map(|(i, c)| (c.id, i)).collect();
+        self.pinned = self.chunks.iter().filter(|c| c.pinned).map(|c| c.id).collect();
+    }
+
+    fn get(&self, id: u64) -> Option<&UserKnowledgeChunk> {
+        self.positions.get(&id).map(|&i| &self.chunks[i])
+    }
+
+    /// Mutable access to a chunk; change `pinned` only through `toggle_pin`
+    fn get_mut(&mut self, id: u64) -> Option<&mut UserKnowledgeChunk> {
+        self.positions.get(&id).map(|&i| &mut self.chunks[i])
+    }
+
+    fn extend(&mut self, chunks: Vec<UserKnowledgeChunk>) {
+        for chunk in chunks {
+            self.positions.insert(chunk.id, self.chunks.len());
+            if chunk.pinned {
+                self.pinned.insert(chunk.id);
+            }
+            self.chunks.push(chunk);
+        }
+    }
+
+    /// Remove the chunks whose IDs are in `ids`, returning them
+    fn remove(&mut self, ids: &HashSet<u64>) -> Vec<UserKnowledgeChunk> {
+        let (removed, kept): (Vec<_>, Vec<_>) = std::mem::take(&mut self.chunks)
+            .into_iter()
+            .partition(|c| ids.contains(&c.id));
+        self.chunks = kept;
+        if !removed.is_empty() {
+            self.reindex();
+        }
+        removed
+    }
+
+    /// Flip a chunk's pin; returns the new state, or None if there is no such chunk
+    fn toggle_pin(&mut self, id: u64) -> Option<bool> {
+        let chunk = self.get_mut(id)?;
+        chunk.pinned = !chunk.pinned;
+        let pinned = chunk.pinned;
+        if pinned {
+            self.pinned.insert(id);
+        } else {
+            self.pinned.remove(&id);
+        }
+        Some(pinned)
+    }
+
+    fn pinned(&self) -> impl Iterator<Item = &UserKnowledgeChunk> {
+        self.pinned.iter().filter_map(|&id| self.get(id))
+    }
+}
 
 /// User knowledge manager
 pub struct KnowledgeManager {
     /// User's personal knowledge chunks
-    chunks: Arc<RwLock<Vec<UserKnowledgeChunk>>>,
+    chunks: Arc<RwLock<ChunkStore>>,
     
     /// Embedding generator for new chunks
     embedding_gen: Arc<EmbeddingGenerator>,
@@ -31,6 +105,12 @@ pub struct KnowledgeManager {
     
     /// Next chunk ID
     next_id: Arc<RwLock<u64>>,
+
+    /// ANN index over chunk embeddings, keyed by chunk ID
+    index: Arc<RwLock<HnswIndex>>,
+
+    /// Index file, next to the chunk store
+    index_path: PathBuf,
 }
 
 /// User knowledge chunk with metadata
@@ -66,6 +146,60 @@ pub struct UserKnowledgeChunk {
     
     /// Privacy level
     pub privacy: PrivacyLevel,
+
+    /// Where an ingested document chunk came from
+    #[serde(default)]
+    pub provenance: Option<ChunkProvenance>,
+}
+
+/// Location of a chunk inside an ingested document
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChunkProvenance {
+    /// Source file (or `ocr:<label>` for OCR captures)
+    pub file: String,
+    /// Byte offset of the chunk in the extracted text (page text for PDFs)
+    pub offset: usize,
+    /// 1-based PDF page
+    pub page: Option<u32>,
+    /// Enclosing headings, outermost first, joined with " > "
+    pub heading: Option<String>,
+}
+
+impl ChunkProvenance {
+    /// Short human-readable reference, e.g. "manual.pdf, p. 12"
+    pub fn citation(&self) -> String {
+        let name = Path::new(&self.file)
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| self.file.clone());
+        match (self.page, &self.heading) {
+            (Some(page), _) => format!("{}, p. {}", name, page),
+            (None, Some(heading)) => format!("{}, {}", name, heading),
+            (None, None) => name,
+        }
+    }
+}
+
+/// A chunk to add with `KnowledgeManager::add_chunks`
+#[derive(Debug, Clone)]
+pub struct ChunkInput {
+    pub text: String,
+    pub source: String,
+    pub category: String,
+    pub tags: Vec<String>,
+    pub privacy: PrivacyLevel,
+    pub provenance: Option<ChunkProvenance>,
+}
+
+impl UserKnowledgeChunk {
+    /// Filterable fields stored in the vector index
+    pub fn index_metadata(&self) -> IndexMetadata {
+        IndexMetadata {
+            category: self.category.clone(),
+            tags: self.tags.clone(),
+            privacy: self.privacy,
+        }
+    }
 }
 
 /// Privacy level for knowledge chunks
@@ -88,20 +222,34 @@ pub struct KnowledgeOpResult {
 }
 
 impl KnowledgeManager {
-    /// Create a new knowledge manager
+    /// Create a new knowledge manager with the stub embedder
     pub fn new(user_did: String, storage_path: PathBuf) -> Result<Self> {
+        Self::with_embedding_generator(user_did, storage_path, Arc::new(EmbeddingGenerator::default()))
+    }
+
+    /// Create a knowledge manager that embeds with `embedding_gen`, e.g. a model
+    /// loaded via `EmbeddingGenerator::from_dir`. Stored chunks embedded by a
+    /// different model are re-embedded on load.
+    pub fn with_embedding_generator(
+        user_did: String,
+        storage_path: PathBuf,
+        embedding_gen: Arc<EmbeddingGenerator>,
+    ) -> Result<Self> {
         // Create storage directory if it doesn't exist
         if let Some(parent) = storage_path.parent() {
             fs::create_dir_all(parent)?;
         }
 
+        let index = Self::empty_index(&embedding_gen);
         let manager = Self {
-            chunks: Arc::new(RwLock::new(Vec::new())),
-            embedding_gen: Arc::new(EmbeddingGenerator::default()),
+            chunks: Arc::new(RwLock::new(ChunkStore::default())),
+            embedding_gen,
             user_did,
+            index_path: storage_path.with_extension("hnsw"),
             storage_path,
             categories: Arc::new(RwLock::new(HashMap::new())),
             next_id: Arc::new(RwLock::new(1)),
+            index: Arc::new(RwLock::new(index)),
         };
 
         // Load existing knowledge if available
@@ -112,6 +260,11 @@ impl KnowledgeManager {
         Ok(manager)
     }
 
+    /// Path of the JSON chunk store
+    pub fn storage_path(&self) -> &Path {
+        &self.storage_path
+    }
+
     /// Add a new knowledge chunk
     pub async fn add_chunk(
         &self,
@@ -121,14 +274,37 @@ impl KnowledgeManager {
         tags: Vec<String>,
         privacy: PrivacyLevel,
     ) -> Result<KnowledgeOpResult> {
-        // Generate embedding
-        let embedding = self.embedding_gen.embed(&text)?;
+        let ids = self.add_chunks(vec![ChunkInput {
+            text,
+            source,
+            category,
+            tags,
+            privacy,
+            provenance: None,
+        }]).await?;
+
+        Ok(KnowledgeOpResult {
+            success: true,
+            message: format!("Added knowledge chunk #{}", ids[0]),
+            chunk_id: Some(ids[0]),
+        })
+    }
 
-        // Get next ID
-        let id = {
+    /// Add many chunks with one batched embedding pass and one save
+    pub async fn add_chunks(&self, inputs: Vec<ChunkInput>) -> Result<Vec<u64>> {
+        if inputs.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // Generate embeddings
+        let texts: Vec<String> = inputs.iter().map(|i| i.text.clone()).collect();
+        let embeddings = self.embedding_gen.embed_batch(&texts)?;
+
+        // Reserve IDs
+        let first_id = {
             let mut next_id = self.next_id.write().await;
             let id = *next_id;
-            *next_id += 1;
+            *next_id += inputs.len() as u64;
             id
         };
 
@@ -136,40 +312,48 @@ impl KnowledgeManager {
             .duration_since(std::time::UNIX_EPOCH)?
             .as_secs();
 
-        // Create chunk
-        let chunk = UserKnowledgeChunk {
-            id,
-            text: text.clone(),
-            embedding,
-            source,
-            category: category.clone(),
-            tags,
-            created_at: now,
-            modified_at: now,
-            pinned: false,
-            privacy,
-        };
+        // Create chunks
+        let new_chunks: Vec<UserKnowledgeChunk> = inputs.into_iter()
+            .zip(embeddings)
+            .enumerate()
+            .map(|(i, (input, embedding))| UserKnowledgeChunk {
+                id: first_id + i as u64,
+                text: input.text,
+                embedding,
+                source: input.source,
+                category: input.category,
+                tags: input.tags,
+                created_at: now,
+                modified_at: now,
+                pinned: false,
+                privacy: input.privacy,
+                provenance: input.provenance,
+            })
+            .collect();
+        let ids: Vec<u64> = new_chunks.iter().map(|c| c.id).collect();
 
-        // Add to chunks
+        // Index, then add to chunks
         {
-            let mut chunks = self.chunks.write().await;
-            chunks.push(chunk);
+            let mut index = self.index.write().await;
+            for chunk in &new_chunks {
+                index.insert(chunk.id, &chunk.embedding, chunk.index_metadata())?;
+            }
         }
 
         // Update category index
         {
             let mut categories = self.categories.write().await;
-            categories.entry(category).or_insert_with(Vec::new).push(id);
+            for chunk in &new_chunks {
+                categories.entry(chunk.category.clone()).or_insert_with(Vec::new).push(chunk.id);
+            }
         }
 
+        self.chunks.write().await.extend(new_chunks);
+
         // Persist
         self.save_to_disk().await?;
 
-        Ok(KnowledgeOpResult {
-            success: true,
-            message: format!("Added knowledge chunk #{}", id),
-            chunk_id: Some(id),
-        })
+        Ok(ids)
     }
 
     /// Update an existing chunk
@@ -183,19 +367,20 @@ impl KnowledgeManager {
     ) -> Result<KnowledgeOpResult> {
         let mut chunks = self.chunks.write().await;
         
-        let chunk = chunks.iter_mut()
-            .find(|c| c.id == id)
+        let chunk = chunks.get_mut(id)
             .ok_or_else(|| anyhow!("Chunk #{} not found", id))?;
 
         let mut changed = false;
         let mut category_change = None;
+        let mut new_embedding = None;
 
         // Update text and regenerate embedding if changed
         if let Some(new_text) = text {
             if new_text != chunk.text {
-                let new_embedding = self.embedding_gen.embed(&new_text)?;
+                let embedding = self.embedding_gen.embed(&new_text)?;
                 chunk.text = new_text;
-                chunk.embedding = new_embedding;
+                chunk.embedding = embedding.clone();
+                new_embedding = Some(embedding);
                 changed = true;
             }
         }
@@ -226,10 +411,21 @@ impl KnowledgeManager {
                 .duration_since(std::time::UNIX_EPOCH)?
                 .as_secs();
         }
+        let meta = chunk.index_metadata();
 
         // Release chunks lock
         drop(chunks);
 
+        if changed {
+            let mut index = self.index.write().await;
+            match new_embedding {
+                Some(embedding) => index.insert(id, &embedding, meta)?,
+                None => {
+                    index.set_metadata(id, meta);
+                }
+            }
+        }
+
         // Update category index if needed
         if let Some((old_cat, new_cat)) = category_change {
             let mut categories = self.categories.write().await;
@@ -256,24 +452,10 @@ impl KnowledgeManager {
 
     /// Delete a chunk
     pub async fn delete_chunk(&self, id: u64) -> Result<KnowledgeOpResult> {
-        let mut chunks = self.chunks.write().await;
-        
-        let idx = chunks.iter()
-            .position(|c| c.id == id)
-            .ok_or_else(|| anyhow!("Chunk #{} not found", id))?;
-
-        let chunk = chunks.remove(idx);
-
-        // Update category index
-        drop(chunks);
-        let mut categories = self.categories.write().await;
-        if let Some(cat_ids) = categories.get_mut(&chunk.category) {
-            cat_ids.retain(|&cid| cid != id);
+        if self.delete_chunks(&[id]).await? == 0 {
+            return Err(anyhow!("Chunk #{} not found", id));
         }
 
-        drop(categories);
-        self.save_to_disk().await?;
-
         Ok(KnowledgeOpResult {
             success: true,
             message: format!("Deleted chunk #{}", id),
@@ -281,17 +463,61 @@ impl KnowledgeManager {
         })
     }
 
+    /// Delete every chunk in `ids` that exists; returns how many were removed
+    pub async fn delete_chunks(&self, ids: &[u64]) -> Result<usize> {
+        let ids: HashSet<u64> = ids.iter().copied().collect();
+        let removed: Vec<(u64, String)> = self.chunks.write().await
+            .remove(&ids)
+            .into_iter()
+            .map(|c| (c.id, c.category))
+            .collect();
+        if removed.is_empty() {
+            return Ok(0);
+        }
+
+        // Update vector and category indexes
+        {
+            let mut index = self.index.write().await;
+            for (id, _) in &removed {
+                index.remove(*id);
+            }
+        }
+        {
+            let mut categories = self.categories.write().await;
+            for (id, category) in &removed {
+                if let Some(cat_ids) = categories.get_mut(category) {
+                    cat_ids.retain(|cid| cid != id);
+                }
+            }
+        }
+
+        self.save_to_disk().await?;
+        Ok(removed.len())
+    }
+
+    /// Replace chunk provenance (e.g. after a document was edited);
+    /// unknown IDs are ignored
+    pub async fn update_provenance(&self, updates: HashMap<u64, ChunkProvenance>) -> Result<()> {
+        if updates.is_empty() {
+            return Ok(());
+        }
+        {
+            let mut chunks = self.chunks.write().await;
+            for (id, provenance) in updates {
+                if let Some(chunk) = chunks.get_mut(id) {
+                    chunk.provenance = Some(provenance);
+                }
+            }
+        }
+        self.save_to_disk().await
+    }
+
     /// Pin/unpin a chunk (high priority)
     pub async fn toggle_pin(&self, id: u64) -> Result<KnowledgeOpResult> {
-        let mut chunks = self.chunks.write().await;
-        
-        let chunk = chunks.iter_mut()
-            .find(|c| c.id == id)
+        self.chunks.write().await
+            .toggle_pin(id)
             .ok_or_else(|| anyhow!("Chunk #{} not found", id))?;
 
-        chunk.pinned = !chunk.pinned;
-        
-        drop(chunks);
         self.save_to_disk().await?;
 
         Ok(KnowledgeOpResult {
@@ -303,22 +529,36 @@ impl KnowledgeManager {
 
     /// Search user's knowledge
     pub async fn search(&self, query: &str, limit: usize) -> Result<Vec<UserKnowledgeChunk>> {
+        self.search_filtered(query, limit, &SearchFilter::default()).await
+    }
+
+    /// Search user's knowledge, restricted to chunks matching `filter`
+    pub async fn search_filtered(
+        &self,
+        query: &str,
+        limit: usize,
+        filter: &SearchFilter,
+    ) -> Result<Vec<UserKnowledgeChunk>> {
         let query_embedding = self.embedding_gen.embed(query)?;
+        let hits = self.index.read().await.search(&query_embedding, limit, Some(filter));
         let chunks = self.chunks.read().await;
 
-        let mut scored: Vec<(f32, UserKnowledgeChunk)> = chunks.iter()
-            .map(|chunk| {
-                let similarity = super::embeddings::cosine_similarity(&query_embedding, &chunk.embedding);
-                // Boost pinned chunks
-                let score = if chunk.pinned { similarity * 1.2 } else { similarity };
-                (score, chunk.clone())
-            })
+        let mut scored: Vec<(f32, UserKnowledgeChunk)> = hits.into_iter()
+            .filter(|(id, _)| !chunks.pinned.contains(id))
+            .filter_map(|(id, similarity)| Some((similarity, chunks.get(id)?.clone())))
             .collect();
 
+        // Pinned chunks are boosted, so score them all exactly; the index
+        // only ranks by raw similarity
+        for chunk in chunks.pinned().filter(|c| filter.matches(&c.index_metadata())) {
+            let similarity = super::embeddings::cosine_similarity(&query_embedding, &chunk.embedding);
+            scored.push((similarity * 1.2, chunk.clone()));
+        }
+
         // Sort by score descending
-        scored.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap());
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
 
-        Ok(scored.iter().take(limit).map(|(_, c)| c.clone()).collect())
+        Ok(scored.into_iter().take(limit).map(|(_, c)| c).collect())
     }
 
     /// Get chunks by category
@@ -328,8 +568,8 @@ impl KnowledgeManager {
             .ok_or_else(|| anyhow!("Category '{}' not found", category))?;
 
         let chunks = self.chunks.read().await;
-        Ok(chunks.iter()
-            .filter(|c| chunk_ids.contains(&c.id))
+        Ok(chunk_ids.iter()
+            .filter_map(|&id| chunks.get(id))
             .cloned()
             .collect())
     }
@@ -337,7 +577,7 @@ impl KnowledgeManager {
     /// Get chunks by tag
     pub async fn get_by_tag(&self, tag: &str) -> Result<Vec<UserKnowledgeChunk>> {
         let chunks = self.chunks.read().await;
-        Ok(chunks.iter()
+        Ok(chunks.chunks.iter()
             .filter(|c| c.tags.contains(&tag.to_string()))
             .cloned()
             .collect())
@@ -351,13 +591,15 @@ impl KnowledgeManager {
 
     /// Get all chunks (for export/backup)
     pub async fn get_all_chunks(&self) -> Vec<UserKnowledgeChunk> {
-        self.chunks.read().await.clone()
+        self.chunks.read().await.chunks.clone()
     }
 
     /// Get pinned chunks
     pub async fn get_pinned(&self) -> Vec<UserKnowledgeChunk> {
         let chunks = self.chunks.read().await;
-        chunks.iter().filter(|c| c.pinned).cloned().collect()
+        let mut pinned: Vec<UserKnowledgeChunk> = chunks.pinned().cloned().collect();
+        pinned.sort_by_key(|c| c.id);
+        pinned
     }
 
     /// Get statistics
@@ -365,13 +607,13 @@ impl KnowledgeManager {
         let chunks = self.chunks.read().await;
         let categories = self.categories.read().await;
 
-        let total_chunks = chunks.len();
-        let pinned_chunks = chunks.iter().filter(|c| c.pinned).count();
+        let total_chunks = chunks.chunks.len();
+        let pinned_chunks = chunks.pinned.len();
         let total_categories = categories.len();
         
         let privacy_breakdown = {
             let mut map = HashMap::new();
-            for chunk in chunks.iter() {
+            for chunk in &chunks.chunks {
                 *map.entry(chunk.privacy).or_insert(0) += 1;
             }
             map
@@ -389,7 +631,7 @@ impl KnowledgeManager {
     pub async fn to_rag_chunks(&self, privacy_filter: Option<PrivacyLevel>) -> Vec<RagChunk> {
         let chunks = self.chunks.read().await;
         
-        chunks.iter()
+        chunks.chunks.iter()
             .filter(|c| {
                 if let Some(filter) = privacy_filter {
                     c.privacy == filter || c.privacy == PrivacyLevel::Public
@@ -410,20 +652,58 @@ impl KnowledgeManager {
     /// Save to disk
     async fn save_to_disk(&self) -> Result<()> {
         let chunks = self.chunks.read().await;
-        let json = serde_json::to_string_pretty(&*chunks)?;
+        let json = serde_json::to_string_pretty(&chunks.chunks)?;
         fs::write(&self.storage_path, json)?;
+        self.index.read().await.save(&self.index_path)?;
         Ok(())
     }
 
+    fn empty_index(embedding_gen: &EmbeddingGenerator) -> HnswIndex {
+        HnswIndex::new(embedding_gen.dim, HnswConfig::default())
+            .with_model(format!("{:?}/{}", embedding_gen.model, embedding_gen.dim))
+    }
+
     /// Load from disk
     fn load_from_disk(&self) -> Result<()> {
         let json = fs::read_to_string(&self.storage_path)?;
         let mut chunks: Vec<UserKnowledgeChunk> = serde_json::from_str(&json)?;
-        
-        // Regenerate embeddings (not serialized)
-        for chunk in &mut chunks {
-            chunk.embedding = self.embedding_gen.embed(&chunk.text)?;
-        }
+
+        // Embeddings are not in the JSON. Take them from the persisted index
+        // when it was built by this model over exactly these chunks;
+        // otherwise regenerate them and rebuild the index
+        let empty = Self::empty_index(&self.embedding_gen);
+        let persisted = if self.index_path.exists() {
+            HnswIndex::load(&self.index_path)
+                .map_err(|e| log::warn!("[Knowledge] Rebuilding vector index: {}", e))
+                .ok()
+                .filter(|index| {
+                    index.model() == empty.model()
+                        && index.len() == chunks.len()
+                        && chunks.iter().all(|c| index.contains(c.id))
+                })
+        } else {
+            None
+        };
+
+        let index = match persisted {
+            Some(index) => {
+                for chunk in &mut chunks {
+                    chunk.embedding = index.vector(chunk.id).unwrap_or_default().to_vec();
+                }
+                index
+            }
+            None => {
+                let texts: Vec<String> = chunks.iter().map(|c| c.text.clone()).collect();
+                let embeddings = self.embedding_gen.embed_batch(&texts)?;
+                let mut index = empty;
+                for (chunk, embedding) in chunks.iter_mut().zip(embeddings) {
+                    index.insert(chunk.id, &embedding, chunk.index_metadata())?;
+                    chunk.embedding = embedding;
+                }
+                index.save(&self.index_path)?;
+                index
+            }
+        };
 
         // Rebuild category index
         let mut categories = HashMap::new();
@@ -438,12 +718,11 @@ impl KnowledgeManager {
             }
         }
 
-        // Update state
-        tokio::runtime::Runtime::new()?.block_on(async {
-            *self.chunks.write().await = chunks;
-            *self.categories.write().await = categories;
-            *self.next_id.write().await = max_id + 1;
-        });
+        // Update state (nothing else holds the locks during construction)
+        *self.chunks.try_write()? = ChunkStore::new(chunks);
+        *self.categories.try_write()? = categories;
+        *self.next_id.try_write()? = max_id + 1;
+        *self.index.try_write()? = index;
 
         Ok(())
     }
@@ -461,6 +740,7 @@ pub struct KnowledgeStats {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::oracle::embeddings::EmbeddingModel;
     use tempfile::TempDir;
 
     #[tokio::test]
@@ -512,6 +792,34 @@ mod tests {
         assert!(!results.is_empty());
     }
 
+    #[tokio::test]
+    async fn test_injected_embedder_reembeds_stored_chunks() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage_path = temp_dir.path().join("knowledge.json");
+        {
+            let manager = KnowledgeManager::new("did:karana:test".to_string(), storage_path.clone()).unwrap();
+            manager.add_chunk(
+                "Rust is great for systems programming".to_string(),
+                "test".to_string(),
+                "programming".to_string(),
+                vec![],
+                PrivacyLevel::Private,
+            ).await.unwrap();
+        }
+
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/tiny-bert");
+        let generator = Arc::new(EmbeddingGenerator::from_dir(EmbeddingModel::MiniLM, &dir).unwrap());
+        let manager = KnowledgeManager::with_embedding_generator(
+            "did:karana:test".to_string(),
+            storage_path,
+            generator.clone(),
+        ).unwrap();
+
+        let results = manager.search("Rust is great for systems programming", 1).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].embedding.len(), generator.dim);
+    }
+
     #[tokio::test]
     async fn test_update_chunk() {
         let temp_dir = TempDir::new().unwrap();
@@ -580,6 +888,42 @@ mod tests {
         assert_eq!(pinned.len(), 1);
     }
 
+    #[tokio::test]
+    async fn test_lookups_follow_deletes_and_pins() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage_path = temp_dir.path().join("knowledge.json");
+        let manager = KnowledgeManager::new("did:karana:test".to_string(), storage_path).unwrap();
+
+        let mut ids = Vec::new();
+        for text in ["Rust ownership", "Rust borrowing", "Rust lifetimes"] {
+            let result = manager.add_chunk(
+                text.to_string(),
+                "source".to_string(),
+                "rust".to_string(),
+                vec![],
+                PrivacyLevel::Private,
+            ).await.unwrap();
+            ids.push(result.chunk_id.unwrap());
+        }
+        manager.toggle_pin(ids[2]).await.unwrap();
+        manager.delete_chunk(ids[0]).await.unwrap();
+
+        // Later chunks moved; updates must still land on the right one
+        manager.update_chunk(ids[2], None, Some("notes".to_string()), None, None).await.unwrap();
+        let remaining = manager.get_by_category("rust").await.unwrap();
+        assert_eq!(remaining.iter().map(|c| c.id).collect::<Vec<_>>(), vec![ids[1], ids[2]]);
+        assert_eq!(remaining[1].source, "notes");
+
+        // The pinned chunk is scored once, not once from the index and once as pinned
+        let results = manager.search("Rust", 10).await.unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results.iter().filter(|c| c.id == ids[2]).count(), 1);
+
+        manager.toggle_pin(ids[2]).await.unwrap();
+        assert!(manager.get_pinned().await.is_empty());
+        assert_eq!(manager.get_stats().await.pinned_chunks, 0);
+    }
+
     #[tokio::test]
     async fn test_get_stats() {
         let temp_dir = TempDir::new().unwrap();
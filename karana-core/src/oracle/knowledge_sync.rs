@@ -341,6 +341,7 @@ impl KnowledgeSyncManager {
                         .as_secs(),
                     privacy: local.privacy,
                     pinned: local.pinned || remote.pinned,
+                    provenance: local.provenance.clone(),
                 };
                 Ok(ConflictResolutionResult::Merged(merged))
             }
@@ -417,6 +418,7 @@ mod tests {
             modified_at,
             privacy: PrivacyLevel::Trusted,
             pinned: false,
+            provenance: None,
         }
     }
 
@@ -50,6 +50,8 @@ pub mod knowledge_sync; // Phase 45: Cross-device sync
 pub mod web_search; // Phase 3: Real-time web search
 pub mod knowledge_base; // Phase 3: Offline Wikipedia indexing
 pub mod cache; // Phase 3 & 5: Search result caching
+pub mod vector_index; // Phase 46: HNSW index for knowledge search
+pub mod ingest; // Phase 47: Document ingestion
 
 // Legacy exports
 pub use intent::*;
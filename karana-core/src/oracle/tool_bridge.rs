@@ -2,7 +2,7 @@
 // Connects Oracle intents to actual tool execution
 
 use crate::oracle::OracleIntent;
-use crate::assistant::{ToolRegistry, ToolArgs};
+use crate::tools::{ToolRegistry, ToolArgs};
 use anyhow::Result;
 
 /// Execute Oracle intent using tool registry
@@ -35,7 +35,7 @@ pub async fn execute_intent(
         OracleIntent::Transfer { amount, recipient, memo } => {
             let mut args = ToolArgs::new();
             args.add("action", "transfer");
-            args.add("amount", amount.to_string());
+            args.add("amount", *amount);
             args.add("recipient", recipient.clone());
             if let Some(m) = memo {
                 args.add("memo", m.clone());
@@ -168,10 +168,23 @@ mod tests {
     
     #[tokio::test]
     async fn test_execute_navigate_intent() {
-        let registry = ToolRegistry::new();
+        let registry = crate::assistant::create_default_registry();
         let intent = OracleIntent::Navigate { destination: "home".to_string() };
         
         let result = execute_intent(&intent, &registry).await;
         assert!(result.is_ok());
     }
+    
+    #[tokio::test]
+    async fn test_execute_transfer_intent() {
+        let registry = crate::assistant::create_default_registry();
+        let intent = OracleIntent::Transfer {
+            amount: 25,
+            recipient: "alice".to_string(),
+            memo: Some("lunch".to_string()),
+        };
+        
+        let result = execute_intent(&intent, &registry).await.unwrap();
+        assert_eq!(result, "Transfer of 25 KARA to alice prepared");
+    }
 }
@@ -8,11 +8,12 @@ use anyhow::{Result, bail};
 
 use super::embeddings::{EmbeddingGenerator, cosine_similarity};
 use super::swarm_knowledge::SwarmKnowledge;
-use super::knowledge_manager::KnowledgeManager;
+use super::knowledge_manager::{KnowledgeManager, PrivacyLevel};
+use super::vector_index::{HnswConfig, HnswIndex, IndexMetadata};
 use super::knowledge_graph::{KnowledgeGraphBuilder, KnowledgeGraph};
 use super::web_search::{WebSearchEngine, WebSearchResult};
 use super::knowledge_base::{OfflineKnowledgeBase, WikiArticle};
-use super::cache::{SearchCache, EmbeddingCache, CachedResult};
+use super::cache::{SearchCache, CachedResult};
 
 /// Universal query response with provenance
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -69,7 +70,6 @@ pub struct UniversalOracle {
     web_search: Option<Arc<WebSearchEngine>>,  // Phase 3: Web search
     offline_kb: Option<Arc<StdMutex<OfflineKnowledgeBase>>>,  // Phase 3: Wikipedia
     search_cache: Arc<SearchCache>,  // Phase 5: Result caching
-    embedding_cache: Arc<EmbeddingCache>,  // Phase 5: Embedding caching
 }
 
 impl UniversalOracle {
@@ -88,7 +88,6 @@ impl UniversalOracle {
             web_search: Some(Arc::new(WebSearchEngine::new())),  // Phase 3: Enabled by default
             offline_kb: None,  // Set via set_offline_kb()
             search_cache: Arc::new(SearchCache::new(1000)),  // Phase 5: 1000 cached queries
-            embedding_cache: Arc::new(EmbeddingCache::new(500)),  // Phase 5: 500 cached embeddings
         })
     }
     
@@ -96,7 +95,17 @@ impl UniversalOracle {
     pub fn set_knowledge_manager(&mut self, manager: Arc<KnowledgeManager>) {
         self.knowledge_manager = Some(manager);
     }
-    
+
+    /// Replace the stub embedder, e.g. with a model loaded via `EmbeddingGenerator::from_dir`.
+    /// The local knowledge base is re-embedded so chunks and queries share one space.
+    pub fn set_embedding_generator(&mut self, generator: Arc<EmbeddingGenerator>) -> Result<()> {
+        self.local_knowledge = Arc::new(self.local_knowledge.reembed(&generator)?);
+        self.embedding_dim = generator.dim;
+        self.embedding_gen = generator;
+        self.search_cache.clear();
+        Ok(())
+    }
+
     /// Set AI instance for LLM-based synthesis
     pub fn set_ai(&mut self, ai: Arc<StdMutex<crate::ai::KaranaAI>>) {
         self.ai = Some(ai);
@@ -213,21 +222,9 @@ impl UniversalOracle {
         self.generate_fallback(query)
     }
 
-    /// Embed query text to vector (with caching)
+    /// Embed query text to vector (the generator caches recent texts)
     fn embed_query(&self, text: &str) -> Result<Vec<f32>> {
-        // Check cache first
-        if let Some(cached_embedding) = self.embedding_cache.get(text) {
-            log::debug!("[UniversalOracle] Embedding cache hit");
-            return Ok(cached_embedding);
-        }
-        
-        // Generate embedding
-        let embedding = self.embedding_gen.embed(text)?;
-        
-        // Cache it
-        self.embedding_cache.put(text.to_string(), embedding.clone());
-        
-        Ok(embedding)
+        self.embedding_gen.embed(text)
     }
 
     /// Query user's personal knowledge base
@@ -252,8 +249,14 @@ impl UniversalOracle {
             return Ok(None);
         }
 
+        // Ingested documents say where the answer came from
+        let answer = match &best.provenance {
+            Some(provenance) => format!("{}\n\n({})", best.text, provenance.citation()),
+            None => best.text.clone(),
+        };
+
         Ok(Some(UniversalResponse {
-            answer: best.text.clone(),
+            answer,
             source: ResponseSource::LocalKnowledge,
             confidence: similarity,
             proof: None,
@@ -577,13 +580,35 @@ pub struct QueryContext {
 /// Local knowledge base using vector search
 pub struct LocalKnowledgeBase {
     chunks: Vec<RagChunk>,
+    /// ANN index keyed by position in `chunks`
+    index: HnswIndex,
 }
 
 impl LocalKnowledgeBase {
     pub fn new() -> Result<Self> {
-        Ok(Self {
-            chunks: Self::load_default_knowledge()?,
-        })
+        let mut kb = Self::empty(EmbeddingGenerator::default().dim);
+        for chunk in Self::load_default_knowledge()? {
+            kb.add_chunk(chunk)?;
+        }
+        Ok(kb)
+    }
+
+    fn empty(dim: usize) -> Self {
+        Self {
+            chunks: Vec::new(),
+            index: HnswIndex::new(dim, HnswConfig::default()),
+        }
+    }
+
+    /// The same chunks embedded by `generator`, in an index of its dimension
+    pub fn reembed(&self, generator: &EmbeddingGenerator) -> Result<Self> {
+        let texts: Vec<String> = self.chunks.iter().map(|c| c.text.clone()).collect();
+        let embeddings = generator.embed_batch(&texts)?;
+        let mut kb = Self::empty(generator.dim);
+        for (chunk, embedding) in self.chunks.iter().zip(embeddings) {
+            kb.add_chunk(RagChunk { embedding, ..chunk.clone() })?;
+        }
+        Ok(kb)
     }
 
     /// Load default knowledge chunks
@@ -612,11 +637,10 @@ impl LocalKnowledgeBase {
         Ok(chunks)
     }
 
-    /// Search for similar chunks using cosine similarity
+    /// Search for similar chunks using cosine similarity (best first)
     pub fn search(&self, embedding: &[f32], k: usize) -> Result<Vec<RagChunk>> {
-        // Compute cosine similarity for each chunk
-        let mut results: Vec<RagChunk> = self.chunks.iter().map(|chunk| {
-            let similarity = cosine_similarity(embedding, &chunk.embedding);
+        Ok(self.index.search(embedding, k, None).into_iter().map(|(id, similarity)| {
+            let chunk = &self.chunks[id as usize];
             RagChunk {
                 text: chunk.text.clone(),
                 embedding: chunk.embedding.clone(),
@@ -624,18 +648,17 @@ impl LocalKnowledgeBase {
                 source_doc: chunk.source_doc.clone(),
                 timestamp: chunk.timestamp,
             }
-        }).collect();
-        
-        // Sort by similarity (descending)
-        results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap());
-        
-        // Return top-k
-        results.truncate(k);
-        Ok(results)
+        }).collect())
     }
 
     /// Add new knowledge chunk
     pub fn add_chunk(&mut self, chunk: RagChunk) -> Result<()> {
+        let meta = IndexMetadata {
+            category: chunk.source_doc.clone(),
+            tags: Vec::new(),
+            privacy: PrivacyLevel::Public,
+        };
+        self.index.insert(self.chunks.len() as u64, &chunk.embedding, meta)?;
         self.chunks.push(chunk);
         Ok(())
     }
@@ -678,6 +701,7 @@ fn parse_sqrt(text: &str) -> Option<f64> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::oracle::embeddings::EmbeddingModel;
 
     #[tokio::test]
     async fn test_universal_oracle_creation() {
@@ -722,6 +746,23 @@ mod tests {
         assert!(kb.len() >= 4);
     }
 
+    #[test]
+    fn test_swapping_embedder_reembeds_local_knowledge() {
+        let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/tiny-bert");
+        let generator = Arc::new(EmbeddingGenerator::from_dir(EmbeddingModel::MiniLM, &dir).unwrap());
+        let mut oracle = UniversalOracle::new().unwrap();
+        let chunks = oracle.local_knowledge.len();
+
+        oracle.set_embedding_generator(generator.clone()).unwrap();
+        assert_eq!(oracle.embedding_dim, generator.dim);
+        assert_eq!(oracle.local_knowledge.len(), chunks);
+
+        let query = generator.embed("The capital of France is Paris, located on the Seine River in northern France.").unwrap();
+        let results = oracle.local_knowledge.search(&query, 1).unwrap();
+        assert_eq!(results[0].embedding.len(), generator.dim);
+        assert_eq!(results[0].source_doc, "geography");
+    }
+
     #[test]
     fn test_parse_addition() {
         assert_eq!(parse_addition("what is 10 + 5"), Some(15.0));
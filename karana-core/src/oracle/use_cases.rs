@@ -8,7 +8,6 @@
 
 use anyhow::Result;
 use serde::{Serialize, Deserialize};
-use sha2::{Sha256, Digest};
 use std::fs;
 use std::path::Path;
 
@@ -65,8 +64,8 @@ impl ProductivityHandler {
         log::info!("[PRODUCTIVITY] ✓ Code saved: {} ({} bytes)", filename, ai_snippet.len());
         
         // Generate ZK proof for snippet hash
-        let hash = Sha256::digest(ai_snippet.as_bytes());
-        let proof = crate::zk::prove_data_hash(ai_snippet.as_bytes(), hash.into())?;
+        let hash = crate::zk::compute_hash(ai_snippet.as_bytes());
+        let proof = crate::zk::prove_data_hash(ai_snippet.as_bytes(), hash)?;
         log::info!("[PRODUCTIVITY] ✓ ZK proof: {} bytes", proof.len());
         
         // Create manifest output
@@ -156,8 +155,8 @@ impl HealthHandler {
         
         // ZK proof for pace without revealing raw IMU
         let pace_bytes = pace_kmh.to_le_bytes();
-        let commitment = Sha256::digest(&pace_bytes);
-        let zk_proof = crate::zk::prove_data_hash(&pace_bytes, commitment.into())?;
+        let commitment = crate::zk::compute_hash(&pace_bytes);
+        let zk_proof = crate::zk::prove_data_hash(&pace_bytes, commitment)?;
         
         // Determine alert level
         let (haptic, advice) = if pace_kmh < 4.0 {
@@ -380,8 +379,8 @@ impl NavigationHandler {
         
         // ZK proof for location privacy (prove "within 100m of destination" without revealing exact location)
         let loc_bytes = format!("{:.4},{:.4}", current_gps.0, current_gps.1);
-        let commitment = Sha256::digest(loc_bytes.as_bytes());
-        let _proof = crate::zk::prove_data_hash(loc_bytes.as_bytes(), commitment.into())?;
+        let commitment = crate::zk::compute_hash(loc_bytes.as_bytes());
+        let _proof = crate::zk::prove_data_hash(loc_bytes.as_bytes(), commitment)?;
         
         // Log navigation to file
         fs::create_dir_all(REAL_OUTPUT_DIR)?;
@@ -0,0 +1,707 @@
+// Approximate nearest-neighbour index for RAG chunks
+// Phase 46: HNSW so knowledge search stays fast past tens of thousands of notes
+//
+// A hierarchical navigable small world graph (Malkov & Yashunin): every vector
+// lives on layer 0, and on each higher layer with probability 1/M. Queries
+// descend greedily from the sparse top layer and run a beam search of width
+// `ef` on layer 0. Similarity is the dot product of unit vectors, i.e. cosine.
+//
+// Deletes leave a tombstone that still routes searches but is never returned;
+// once tombstones pass `COMPACT_FRACTION` of the graph it is rebuilt from the
+// live vectors.
+
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use super::knowledge_manager::PrivacyLevel;
+
+/// Index file header
+const INDEX_MAGIC: &[u8; 8] = b"KRNHNSW1";
+
+/// Rebuild once this fraction of the nodes are tombstones
+const COMPACT_FRACTION: f32 = 0.25;
+
+/// Filters matching fewer than 1 in this many vectors are searched exactly
+const EXACT_FILTER_RATIO: usize = 10;
+
+/// Cap on the random layer a node is assigned
+const MAX_LEVEL: usize = 16;
+
+/// HNSW build and search parameters
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HnswConfig {
+    /// Links per node on upper layers (twice this on layer 0)
+    pub m: usize,
+    /// Beam width while inserting
+    pub ef_construction: usize,
+    /// Default beam width while searching
+    pub ef_search: usize,
+    /// Seed for layer assignment, so rebuilds are reproducible
+    pub seed: u64,
+}
+
+impl Default for HnswConfig {
+    fn default() -> Self {
+        Self {
+            m: 16,
+            ef_construction: 100,
+            ef_search: 64,
+            seed: 0x6b61_7261_6e61,
+        }
+    }
+}
+
+/// Metadata stored next to each vector for filtered search
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IndexMetadata {
+    pub category: String,
+    pub tags: Vec<String>,
+    pub privacy: PrivacyLevel,
+}
+
+/// Restricts a search to vectors whose metadata matches every set field
+#[derive(Debug, Clone, Default)]
+pub struct SearchFilter {
+    /// Exact category
+    pub category: Option<String>,
+    /// Tags that must all be present
+    pub tags: Vec<String>,
+    /// Allowed privacy levels
+    pub privacy: Option<Vec<PrivacyLevel>>,
+}
+
+impl SearchFilter {
+    pub fn category(mut self, category: impl Into<String>) -> Self {
+        self.category = Some(category.into());
+        self
+    }
+
+    pub fn tag(mut self, tag: impl Into<String>) -> Self {
+        self.tags.push(tag.into());
+        self
+    }
+
+    pub fn privacy(mut self, levels: &[PrivacyLevel]) -> Self {
+        self.privacy = Some(levels.to_vec());
+        self
+    }
+
+    /// True when no field is set
+    pub fn is_empty(&self) -> bool {
+        self.category.is_none() && self.tags.is_empty() && self.privacy.is_none()
+    }
+
+    pub fn matches(&self, meta: &IndexMetadata) -> bool {
+        self.category.as_ref().is_none_or(|c| *c == meta.category)
+            && self.tags.iter().all(|t| meta.tags.contains(t))
+            && self.privacy.as_ref().is_none_or(|p| p.contains(&meta.privacy))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Node {
+    id: u64,
+    /// Unit-length vector
+    vector: Vec<f32>,
+    meta: IndexMetadata,
+    /// Neighbour slots, one list per layer the node lives on
+    links: Vec<Vec<u32>>,
+    deleted: bool,
+}
+
+/// Search candidate ordered by distance (`1 - similarity`)
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Candidate {
+    dist: f32,
+    slot: u32,
+}
+
+impl Eq for Candidate {}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.dist.total_cmp(&other.dist).then(self.slot.cmp(&other.slot))
+    }
+}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// HNSW index keyed by caller-chosen `u64` ids
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HnswIndex {
+    config: HnswConfig,
+    dim: usize,
+    /// Embedding model the vectors came from; a mismatch means rebuild
+    model: String,
+    nodes: Vec<Node>,
+    entry: Option<u32>,
+    deleted: usize,
+    rng: u64,
+    #[serde(skip)]
+    slots: HashMap<u64, u32>,
+}
+
+impl HnswIndex {
+    pub fn new(dim: usize, config: HnswConfig) -> Self {
+        Self {
+            rng: config.seed,
+            config,
+            dim,
+            model: String::new(),
+            nodes: Vec::new(),
+            entry: None,
+            deleted: 0,
+            slots: HashMap::new(),
+        }
+    }
+
+    /// Tag the index with the embedding model that produced its vectors
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = model.into();
+        self
+    }
+
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
+    pub fn dim(&self) -> usize {
+        self.dim
+    }
+
+    /// Number of live vectors
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    pub fn contains(&self, id: u64) -> bool {
+        self.slots.contains_key(&id)
+    }
+
+    /// Ids of all live vectors
+    pub fn ids(&self) -> impl Iterator<Item = u64> + '_ {
+        self.slots.keys().copied()
+    }
+
+    /// Stored (unit-length) vector for `id`
+    pub fn vector(&self, id: u64) -> Option<&[f32]> {
+        self.slots.get(&id).map(|&s| self.nodes[s as usize].vector.as_slice())
+    }
+
+    pub fn metadata(&self, id: u64) -> Option<&IndexMetadata> {
+        self.slots.get(&id).map(|&s| &self.nodes[s as usize].meta)
+    }
+
+    /// Change the default search beam width
+    pub fn set_ef_search(&mut self, ef: usize) {
+        self.config.ef_search = ef.max(1);
+    }
+
+    /// Insert `vector` under `id`, replacing any existing entry
+    pub fn insert(&mut self, id: u64, vector: &[f32], meta: IndexMetadata) -> Result<()> {
+        if vector.len() != self.dim {
+            return Err(anyhow!("Vector has {} dimensions, index expects {}", vector.len(), self.dim));
+        }
+        let vector = normalized(vector).ok_or_else(|| anyhow!("Cannot index a zero vector"))?;
+        self.remove(id);
+
+        let slot = self.nodes.len() as u32;
+        let level = self.random_level();
+        self.nodes.push(Node {
+            id,
+            vector,
+            meta,
+            links: vec![Vec::new(); level + 1],
+            deleted: false,
+        });
+        self.slots.insert(id, slot);
+
+        let Some(entry) = self.entry else {
+            self.entry = Some(slot);
+            return Ok(());
+        };
+
+        let query = self.nodes[slot as usize].vector.clone();
+        let top = self.nodes[entry as usize].links.len() - 1;
+        let mut ep = self.descend(&query, entry, top, level + 1);
+
+        for layer in (0..=level.min(top)).rev() {
+            let candidates = self.search_layer(&query, ep, self.config.ef_construction, layer);
+            let neighbours = self.select_neighbours(&candidates, self.max_links(layer));
+            for &n in &neighbours {
+                self.link(n, slot, layer);
+            }
+            self.nodes[slot as usize].links[layer] = neighbours;
+            ep = candidates[0];
+        }
+
+        if level > top {
+            self.entry = Some(slot);
+        }
+        Ok(())
+    }
+
+    /// Remove `id`; returns whether it was present
+    pub fn remove(&mut self, id: u64) -> bool {
+        let Some(slot) = self.slots.remove(&id) else {
+            return false;
+        };
+        self.nodes[slot as usize].deleted = true;
+        self.deleted += 1;
+
+        if self.slots.is_empty() {
+            self.clear();
+        } else if self.deleted as f32 > self.nodes.len() as f32 * COMPACT_FRACTION {
+            self.compact();
+        }
+        true
+    }
+
+    /// Replace the metadata of `id`; returns whether it was present
+    pub fn set_metadata(&mut self, id: u64, meta: IndexMetadata) -> bool {
+        match self.slots.get(&id) {
+            Some(&slot) => {
+                self.nodes[slot as usize].meta = meta;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Drop every vector
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+        self.slots.clear();
+        self.entry = None;
+        self.deleted = 0;
+        self.rng = self.config.seed;
+    }
+
+    /// Rebuild the graph from live vectors, dropping tombstones
+    pub fn compact(&mut self) {
+        let live: Vec<Node> = self.nodes.drain(..).filter(|n| !n.deleted).collect();
+        self.clear();
+        for node in live {
+            // Already normalized and of the right dimension
+            let _ = self.insert(node.id, &node.vector, node.meta);
+        }
+    }
+
+    /// Top `k` ids by cosine similarity, best first
+    pub fn search(&self, query: &[f32], k: usize, filter: Option<&SearchFilter>) -> Vec<(u64, f32)> {
+        self.search_with_ef(query, k, self.config.ef_search, filter)
+    }
+
+    /// `search` with an explicit beam width
+    pub fn search_with_ef(&self, query: &[f32], k: usize, ef: usize, filter: Option<&SearchFilter>) -> Vec<(u64, f32)> {
+        let (Some(entry), Some(query)) = (self.entry, normalized(query)) else {
+            return Vec::new();
+        };
+        if k == 0 || query.len() != self.dim {
+            return Vec::new();
+        }
+        let filter = filter.filter(|f| !f.is_empty());
+
+        // Selective filters would need a huge beam to surface k matches;
+        // scanning just the matches is cheaper
+        if let Some(filter) = filter {
+            let matching = self.live_nodes().filter(|n| filter.matches(&n.meta)).count();
+            if matching * EXACT_FILTER_RATIO < self.len() {
+                return self.exact_search(&query, k, Some(filter));
+            }
+        }
+
+        let top = self.nodes[entry as usize].links.len() - 1;
+        let ep = self.descend(&query, entry, top, 1);
+        let mut ef = ef.max(k);
+        loop {
+            let hits: Vec<(u64, f32)> = self
+                .search_layer(&query, ep, ef, 0)
+                .into_iter()
+                .map(|c| &self.nodes[c.slot as usize])
+                .filter(|n| !n.deleted && filter.is_none_or(|f| f.matches(&n.meta)))
+                .take(k)
+                .map(|n| (n.id, dot(&query, &n.vector)))
+                .collect();
+            if hits.len() >= k.min(self.len()) || ef >= self.nodes.len() {
+                return hits;
+            }
+            // Tombstones or filtered-out nodes crowded the beam
+            ef *= 2;
+        }
+    }
+
+    /// Brute-force top `k`, the ground truth for `search`
+    pub fn exact_search(&self, query: &[f32], k: usize, filter: Option<&SearchFilter>) -> Vec<(u64, f32)> {
+        let Some(query) = normalized(query) else {
+            return Vec::new();
+        };
+        let mut scored: Vec<(u64, f32)> = self
+            .live_nodes()
+            .filter(|n| filter.is_none_or(|f| f.matches(&n.meta)))
+            .map(|n| (n.id, dot(&query, &n.vector)))
+            .collect();
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+        scored.truncate(k);
+        scored
+    }
+
+    /// Write the index to `path` (atomically, via a temp file)
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let mut bytes = INDEX_MAGIC.to_vec();
+        bytes.extend(bincode::serialize(self)?);
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, bytes)?;
+        fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    /// Read an index written by `save`
+    pub fn load(path: &Path) -> Result<Self> {
+        let bytes = fs::read(path)?;
+        let payload = bytes
+            .strip_prefix(INDEX_MAGIC.as_slice())
+            .ok_or_else(|| anyhow!("{} is not a vector index", path.display()))?;
+        let mut index: Self = bincode::deserialize(payload)?;
+
+        index.slots = index
+            .nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, n)| !n.deleted)
+            .map(|(slot, n)| (n.id, slot as u32))
+            .collect();
+        if !index.is_consistent() {
+            return Err(anyhow!("Vector index {} is corrupt", path.display()));
+        }
+        Ok(index)
+    }
+
+    /// Whether a deserialized graph can be searched without indexing out of bounds
+    fn is_consistent(&self) -> bool {
+        let slots = self.nodes.len();
+        let live = self.nodes.iter().filter(|n| !n.deleted).count();
+        let entry_ok = match self.entry {
+            Some(e) => (e as usize) < slots,
+            None => slots == 0,
+        };
+
+        entry_ok
+            && self.slots.len() == live
+            && self.nodes.iter().all(|n| {
+                n.vector.len() == self.dim
+                    && (1..=MAX_LEVEL + 1).contains(&n.links.len())
+                    && n.links.iter().enumerate().all(|(layer, links)| {
+                        // Every neighbour must exist and live on this layer too
+                        links.len() <= self.max_links(layer)
+                            && links.iter().all(|&l| {
+                                self.nodes.get(l as usize).is_some_and(|other| other.links.len() > layer)
+                            })
+                    })
+            })
+    }
+
+    fn live_nodes(&self) -> impl Iterator<Item = &Node> {
+        self.nodes.iter().filter(|n| !n.deleted)
+    }
+
+    fn max_links(&self, layer: usize) -> usize {
+        if layer == 0 { self.config.m * 2 } else { self.config.m }
+    }
+
+    /// Geometric layer with mean 1/ln(M), as in the paper
+    fn random_level(&mut self) -> usize {
+        // xorshift64*
+        self.rng ^= self.rng >> 12;
+        self.rng ^= self.rng << 25;
+        self.rng ^= self.rng >> 27;
+        let bits = self.rng.wrapping_mul(0x2545_f491_4f6c_dd1d) >> 11;
+        let uniform = (bits as f64 + 1.0) / (1u64 << 53) as f64;
+        let ml = 1.0 / (self.config.m.max(2) as f64).ln();
+        ((-uniform.ln() * ml) as usize).min(MAX_LEVEL)
+    }
+
+    fn distance(&self, query: &[f32], slot: u32) -> f32 {
+        1.0 - dot(query, &self.nodes[slot as usize].vector)
+    }
+
+    /// Greedy walk from layer `from` down to (and including) layer `to`
+    fn descend(&self, query: &[f32], entry: u32, from: usize, to: usize) -> Candidate {
+        let mut best = Candidate { dist: self.distance(query, entry), slot: entry };
+        for layer in (to..=from).rev() {
+            let mut improved = true;
+            while improved {
+                improved = false;
+                for &n in &self.nodes[best.slot as usize].links[layer] {
+                    let dist = self.distance(query, n);
+                    if dist < best.dist {
+                        best = Candidate { dist, slot: n };
+                        improved = true;
+                    }
+                }
+            }
+        }
+        best
+    }
+
+    /// Beam search on one layer; returns up to `ef` candidates, nearest first
+    fn search_layer(&self, query: &[f32], entry: Candidate, ef: usize, layer: usize) -> Vec<Candidate> {
+        let mut visited = HashSet::from([entry.slot]);
+        let mut frontier = BinaryHeap::from([Reverse(entry)]);
+        let mut results = BinaryHeap::from([entry]);
+
+        while let Some(Reverse(current)) = frontier.pop() {
+            let worst = results.peek().map_or(f32::INFINITY, |c| c.dist);
+            if current.dist > worst && results.len() >= ef {
+                break;
+            }
+            for &n in &self.nodes[current.slot as usize].links[layer] {
+                if !visited.insert(n) {
+                    continue;
+                }
+                let candidate = Candidate { dist: self.distance(query, n), slot: n };
+                let worst = results.peek().map_or(f32::INFINITY, |c| c.dist);
+                if results.len() < ef || candidate.dist < worst {
+                    frontier.push(Reverse(candidate));
+                    results.push(candidate);
+                    if results.len() > ef {
+                        results.pop();
+                    }
+                }
+            }
+        }
+        results.into_sorted_vec()
+    }
+
+    /// Neighbour heuristic: skip candidates closer to an already chosen
+    /// neighbour than to the query, then top up with the skipped ones
+    fn select_neighbours(&self, candidates: &[Candidate], m: usize) -> Vec<u32> {
+        let mut chosen: Vec<Candidate> = Vec::with_capacity(m);
+        let mut skipped = Vec::new();
+        for &c in candidates {
+            if chosen.len() >= m {
+                break;
+            }
+            let vector = &self.nodes[c.slot as usize].vector;
+            if chosen.iter().all(|s| self.distance(vector, s.slot) > c.dist) {
+                chosen.push(c);
+            } else {
+                skipped.push(c);
+            }
+        }
+        chosen.extend(skipped.into_iter().take(m - chosen.len()));
+        chosen.into_iter().map(|c| c.slot).collect()
+    }
+
+    /// Add the edge `from -> to`, pruning `from` back to its link budget
+    fn link(&mut self, from: u32, to: u32, layer: usize) {
+        self.nodes[from as usize].links[layer].push(to);
+        let max = self.max_links(layer);
+        if self.nodes[from as usize].links[layer].len() <= max {
+            return;
+        }
+        let vector = self.nodes[from as usize].vector.clone();
+        let mut candidates: Vec<Candidate> = self.nodes[from as usize].links[layer]
+            .iter()
+            .map(|&slot| Candidate { dist: self.distance(&vector, slot), slot })
+            .collect();
+        candidates.sort();
+        self.nodes[from as usize].links[layer] = self.select_neighbours(&candidates, max);
+    }
+}
+
+/// Result of comparing `search` against `exact_search`
+#[derive(Debug, Clone)]
+pub struct RecallReport {
+    /// Fraction of the exact top-k that the index returned
+    pub recall: f32,
+    pub queries: usize,
+    pub ann_time: Duration,
+    pub exact_time: Duration,
+}
+
+/// Recall@k of `index` over `queries` at beam width `ef`
+pub fn measure_recall(
+    index: &HnswIndex,
+    queries: &[Vec<f32>],
+    k: usize,
+    ef: usize,
+    filter: Option<&SearchFilter>,
+) -> RecallReport {
+    let mut found = 0;
+    let mut expected = 0;
+    let mut ann_time = Duration::ZERO;
+    let mut exact_time = Duration::ZERO;
+
+    for query in queries {
+        let start = Instant::now();
+        let approx = index.search_with_ef(query, k, ef, filter);
+        ann_time += start.elapsed();
+
+        let start = Instant::now();
+        let exact = index.exact_search(query, k, filter);
+        exact_time += start.elapsed();
+
+        expected += exact.len();
+        found += exact.iter().filter(|(id, _)| approx.iter().any(|(a, _)| a == id)).count();
+    }
+
+    RecallReport {
+        recall: if expected == 0 { 1.0 } else { found as f32 / expected as f32 },
+        queries: queries.len(),
+        ann_time,
+        exact_time,
+    }
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+fn normalized(v: &[f32]) -> Option<Vec<f32>> {
+    let norm = dot(v, v).sqrt();
+    (norm > 0.0 && norm.is_finite()).then(|| v.iter().map(|x| x / norm).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    fn meta(category: &str, privacy: PrivacyLevel) -> IndexMetadata {
+        IndexMetadata { category: category.to_string(), tags: Vec::new(), privacy }
+    }
+
+    fn random_vectors(n: usize, dim: usize, seed: u64) -> Vec<Vec<f32>> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        (0..n).map(|_| (0..dim).map(|_| rng.gen_range(-1.0..1.0)).collect()).collect()
+    }
+
+    fn build(vectors: &[Vec<f32>]) -> HnswIndex {
+        let config = HnswConfig { m: 8, ef_construction: 48, ..HnswConfig::default() };
+        let mut index = HnswIndex::new(vectors[0].len(), config);
+        for (i, v) in vectors.iter().enumerate() {
+            let category = if i % 2 == 0 { "even" } else { "odd" };
+            index.insert(i as u64, v, meta(category, PrivacyLevel::Private)).unwrap();
+        }
+        index
+    }
+
+    #[test]
+    fn test_recall_against_exact() {
+        let index = build(&random_vectors(1000, 16, 1));
+        let queries = random_vectors(50, 16, 2);
+
+        let report = measure_recall(&index, &queries, 10, 64, None);
+        assert!(report.recall >= 0.9, "recall {}", report.recall);
+
+        let filter = SearchFilter::default().category("odd");
+        let report = measure_recall(&index, &queries, 10, 64, Some(&filter));
+        assert!(report.recall >= 0.9, "filtered recall {}", report.recall);
+    }
+
+    #[test]
+    fn test_insert_delete_and_filters() {
+        let vectors = random_vectors(300, 16, 3);
+        let mut index = build(&vectors);
+        assert_eq!(index.len(), 300);
+
+        // A vector finds itself
+        let hits = index.search(&vectors[42], 1, None);
+        assert_eq!(hits[0].0, 42);
+        assert!((hits[0].1 - 1.0).abs() < 1e-5);
+
+        // Deleted ids are never returned, even past compaction
+        for id in 0..150 {
+            assert!(index.remove(id));
+        }
+        assert!(!index.remove(0));
+        assert_eq!(index.len(), 150);
+        assert!(index.search(&vectors[42], 10, None).iter().all(|(id, _)| *id >= 150));
+        assert_eq!(index.search(&vectors[200], 1, None)[0].0, 200);
+
+        // Re-inserting replaces the vector
+        index.insert(200, &vectors[7], meta("even", PrivacyLevel::Public)).unwrap();
+        assert_eq!(index.search(&vectors[7], 1, None)[0].0, 200);
+
+        let public = SearchFilter::default().privacy(&[PrivacyLevel::Public]);
+        let hits = index.search(&vectors[3], 5, Some(&public));
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].0, 200);
+        assert!(index.search(&vectors[3], 5, Some(&SearchFilter::default().tag("missing"))).is_empty());
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("chunks.hnsw");
+        let vectors = random_vectors(200, 16, 4);
+        let mut index = build(&vectors).with_model("Stub/16");
+        index.remove(5);
+        index.save(&path).unwrap();
+
+        let loaded = HnswIndex::load(&path).unwrap();
+        assert_eq!(loaded.model(), "Stub/16");
+        assert_eq!(loaded.len(), 199);
+        assert!(!loaded.contains(5));
+        assert_eq!(loaded.search(&vectors[9], 3, None), index.search(&vectors[9], 3, None));
+
+        fs::write(&path, b"not an index").unwrap();
+        assert!(HnswIndex::load(&path).is_err());
+    }
+
+    #[test]
+    fn test_load_rejects_malformed_graph() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("chunks.hnsw");
+        let index = build(&random_vectors(50, 8, 5));
+        let top = index.nodes.iter().map(|n| n.links.len()).max().unwrap();
+        assert!(top > 1, "fixture needs an upper layer");
+
+        let corruptions: [fn(&mut HnswIndex); 5] = [
+            // Neighbour slot past the end
+            |index| {
+                let past_end = index.nodes.len() as u32;
+                index.nodes[0].links[0].push(past_end);
+            },
+            // Neighbour that does not live on the upper layer
+            |index| {
+                let ground = index.nodes.iter().position(|n| n.links.len() == 1).unwrap() as u32;
+                let upper = index.nodes.iter_mut().find(|n| n.links.len() > 1).unwrap();
+                upper.links[1].push(ground);
+            },
+            // More links than the layer allows
+            |index| {
+                let max = index.max_links(0);
+                index.nodes[0].links[0] = vec![1; max + 1];
+            },
+            // Node on no layer at all
+            |index| index.nodes[3].links.clear(),
+            // Nodes but no entry point
+            |index| index.entry = None,
+        ];
+        for corrupt in corruptions {
+            let mut broken = index.clone();
+            corrupt(&mut broken);
+            broken.save(&path).unwrap();
+            assert!(HnswIndex::load(&path).is_err());
+        }
+
+        index.save(&path).unwrap();
+        assert!(HnswIndex::load(&path).is_ok());
+    }
+}
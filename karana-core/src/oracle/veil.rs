@@ -21,7 +21,8 @@ use std::sync::{Arc, Mutex as StdMutex};
 use std::time::{Duration, Instant};
 use tokio::sync::{mpsc, Mutex, RwLock};
 
-use crate::ai::KaranaAI;
+use crate::ai::{GenerationStream, KaranaAI};
+use crate::ai::memory::OracleMemory;
 use crate::oracle::command::{
     AROverlay, AROverlayType, ChainQuery, CommandData, CommandResult, HapticPattern,
     OracleChannels, OracleCommand, TransactionPayload, WhisperStyle,
@@ -78,6 +79,9 @@ pub struct OracleVeil {
     
     /// Output manifest for AR whispers and haptic feedback
     manifest: Arc<Mutex<MinimalManifest>>,
+    
+    /// Long-term memory of mediated turns, summarized into episodes by the local AI
+    memory: Arc<OracleMemory>,
 }
 
 /// Context maintained across the conversation
@@ -644,6 +648,8 @@ impl OracleVeil {
         let universal_oracle = crate::oracle::universal::UniversalOracle::new()
             .map_err(|e| anyhow!("Failed to create universal oracle: {}", e))?;
         
+        let memory = Arc::new(OracleMemory::new().with_summarizer(ai.clone()));
+        
         Ok(Self {
             ai,
             legacy_oracle: Arc::new(StdMutex::new(Oracle::new())),
@@ -656,9 +662,29 @@ impl OracleVeil {
             pending_commands: Arc::new(Mutex::new(HashMap::new())),
             user_did: Arc::new(RwLock::new(None)),
             manifest: Arc::new(Mutex::new(MinimalManifest::new())),
+            memory,
         })
     }
     
+    /// Use `memory` (e.g. one opened with `OracleMemory::open`) instead of the
+    /// in-memory default
+    pub fn with_memory(mut self, memory: Arc<OracleMemory>) -> Self {
+        self.memory = memory;
+        self
+    }
+    
+    /// Long-term memory of mediated turns
+    pub fn memory(&self) -> Arc<OracleMemory> {
+        self.memory.clone()
+    }
+    
+    /// Phase 7.4: Stream an answer token by token so the HUD and TTS can start early
+    pub fn stream_answer(&self, prompt: &str, max_tokens: usize) -> GenerationStream {
+        // Cached prompt prefixes are kept per user
+        let session = self.user_did.try_read().ok().and_then(|did| did.clone()).unwrap_or_default();
+        GenerationStream::spawn_in_session(self.ai.clone(), session, prompt, max_tokens)
+    }
+    
     /// Set the user's DID (call after wallet is connected)
     pub async fn set_user_did(&self, did: String) {
         let mut user_did = self.user_did.write().await;
@@ -831,6 +857,9 @@ impl OracleVeil {
                     .as_secs(),
             });
         }
+        if let Err(e) = self.memory.store_session(intent, &response.whisper, parsed.confidence, Vec::new()) {
+            log::warn!("[ORACLE] Failed to remember turn: {}", e);
+        }
         
         Ok(response)
     }
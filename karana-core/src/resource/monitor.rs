@@ -5,7 +5,7 @@ use anyhow::{Result, anyhow};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
-use tokio::sync::RwLock;
+use tokio::sync::{RwLock, broadcast};
 
 /// System resource state snapshot
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -191,6 +191,7 @@ pub struct ResourceMonitor {
     current: Arc<RwLock<ResourceSnapshot>>,
     history: Arc<RwLock<ResourceHistory>>,
     update_interval: Duration,
+    snapshots: broadcast::Sender<ResourceSnapshot>,
 }
 
 impl ResourceMonitor {
@@ -200,8 +201,14 @@ impl ResourceMonitor {
             current: Arc::new(RwLock::new(Self::get_initial_snapshot())),
             history: Arc::new(RwLock::new(ResourceHistory::new(300))), // 5 min at 1s intervals
             update_interval: Duration::from_secs(1),
+            snapshots: broadcast::channel(16).0,
         }
     }
+
+    /// Receive every snapshot the monitoring loop captures
+    pub fn subscribe(&self) -> broadcast::Receiver<ResourceSnapshot> {
+        self.snapshots.subscribe()
+    }
     
     /// Get initial resource snapshot
     fn get_initial_snapshot() -> ResourceSnapshot {
@@ -250,6 +257,7 @@ impl ResourceMonitor {
         let current = self.current.clone();
         let history = self.history.clone();
         let interval = self.update_interval;
+        let snapshots = self.snapshots.clone();
         
         tokio::spawn(async move {
             let mut interval_timer = tokio::time::interval(interval);
@@ -266,6 +274,9 @@ impl ResourceMonitor {
                     *current_lock = snapshot.clone();
                 }
                 
+                // Notify subscribers (none listening is fine)
+                let _ = snapshots.send(snapshot.clone());
+                
                 // Add to history
                 {
                     let mut history_lock = history.write().await;
@@ -1,39 +1,65 @@
 //! Spatial Anchor Management
 //!
 //! Persistent spatial anchors for AR content placement.
+//!
+//! The scene engine's anchors live in the world-anchor service; this manager
+//! presents the nalgebra view of every anchor in the service and keeps
+//! scene-only metadata for the anchors it created.
 
+use anyhow::Result;
 use nalgebra::{Point3, UnitQuaternion, Matrix4, Vector3};
-use uuid::Uuid;
 use std::collections::HashMap;
 use std::time::{Duration, Instant};
 
-use super::SceneId;
+use crate::spatial::{
+    self, AnchorId, CreateAnchorRequest, LocalCoord, Quaternion, WorldAnchorService, WorldPosition,
+};
 
 /// Manages spatial anchors in the scene
 #[derive(Debug)]
 pub struct AnchorManager {
-    anchors: HashMap<SceneId, SpatialAnchor>,
+    service: WorldAnchorService,
+    anchors: HashMap<AnchorId, AnchorMeta>,
     persistence_enabled: bool,
-    last_save: Instant,
-    save_interval: Duration,
+}
+
+/// Scene-side bookkeeping for an anchor
+#[derive(Debug, Clone)]
+struct AnchorMeta {
+    cloud_id: Option<String>,
+    created_at: Instant,
+    last_updated: Instant,
+}
+
+impl AnchorMeta {
+    /// Metadata for an anchor created outside the scene engine
+    fn foreign(anchor: &spatial::SpatialAnchor) -> Self {
+        Self {
+            cloud_id: None,
+            created_at: anchor.created_instant(),
+            last_updated: anchor.updated_instant(),
+        }
+    }
 }
 
 impl AnchorManager {
+    /// Create a manager with its own in-memory anchor service
     pub fn new(persistence_enabled: bool) -> Self {
+        Self::with_service(WorldAnchorService::in_memory(), persistence_enabled)
+    }
+    
+    /// Create a manager on top of a shared anchor service
+    pub fn with_service(service: WorldAnchorService, persistence_enabled: bool) -> Self {
         Self {
+            service,
             anchors: HashMap::new(),
             persistence_enabled,
-            last_save: Instant::now(),
-            save_interval: Duration::from_secs(30),
         }
     }
     
     /// Create a new anchor at position
-    pub fn create_anchor(&mut self, position: Point3<f32>, name: Option<String>) -> SpatialAnchor {
-        let anchor = SpatialAnchor::new(position, name);
-        let id = anchor.id;
-        self.anchors.insert(id, anchor.clone());
-        anchor
+    pub fn create_anchor(&mut self, position: Point3<f32>, name: Option<String>) -> Result<SpatialAnchor> {
+        self.create_anchor_with_transform(position, UnitQuaternion::identity(), name)
     }
     
     /// Create anchor with full transform
@@ -42,62 +68,75 @@ impl AnchorManager {
         position: Point3<f32>,
         rotation: UnitQuaternion<f32>,
         name: Option<String>,
-    ) -> SpatialAnchor {
-        let mut anchor = SpatialAnchor::new(position, name);
-        anchor.rotation = rotation;
-        let id = anchor.id;
-        self.anchors.insert(id, anchor.clone());
-        anchor
+    ) -> Result<SpatialAnchor> {
+        let anchor = self.service.create(CreateAnchorRequest {
+            position: WorldPosition::from_local(position.x, position.y, position.z),
+            orientation: Some(Quaternion::from_unit(&rotation)),
+            label: name,
+            ..Default::default()
+        }, self.persistence_enabled)?;
+        
+        let now = Instant::now();
+        let meta = AnchorMeta {
+            cloud_id: None,
+            created_at: now,
+            last_updated: now,
+        };
+        let view = SpatialAnchor::from_world(&anchor, &meta);
+        self.anchors.insert(anchor.id, meta);
+        Ok(view)
     }
     
     /// Get anchor by ID
-    pub fn get_anchor(&self, id: SceneId) -> Option<&SpatialAnchor> {
-        self.anchors.get(&id)
-    }
-    
-    /// Get mutable anchor
-    pub fn get_anchor_mut(&mut self, id: SceneId) -> Option<&mut SpatialAnchor> {
-        self.anchors.get_mut(&id)
+    pub fn get_anchor(&self, id: AnchorId) -> Option<SpatialAnchor> {
+        self.service.get(id).map(|anchor| self.view(&anchor))
     }
     
     /// Update anchor position
-    pub fn update_anchor_position(&mut self, id: SceneId, position: Point3<f32>) -> bool {
-        if let Some(anchor) = self.anchors.get_mut(&id) {
-            anchor.position = position;
-            anchor.last_updated = Instant::now();
-            true
-        } else {
-            false
+    pub fn update_anchor_position(&mut self, id: AnchorId, position: Point3<f32>) -> bool {
+        let Some(anchor) = self.service.get(id) else {
+            return false;
+        };
+        
+        let mut world = anchor.position.clone();
+        world.local = LocalCoord::new(position.x, position.y, position.z);
+        if self.service.update_pose(id, world, anchor.orientation).is_err() {
+            return false;
+        }
+        if let Some(meta) = self.anchors.get_mut(&id) {
+            meta.last_updated = Instant::now();
         }
+        true
     }
     
     /// Remove an anchor
-    pub fn remove_anchor(&mut self, id: SceneId) -> bool {
-        self.anchors.remove(&id).is_some()
+    pub fn remove_anchor(&mut self, id: AnchorId) -> bool {
+        self.anchors.remove(&id);
+        self.service.remove(id).is_ok()
     }
     
     /// Get all anchors
-    pub fn all_anchors(&self) -> Vec<&SpatialAnchor> {
-        self.anchors.values().collect()
+    pub fn all_anchors(&self) -> Vec<SpatialAnchor> {
+        self.service.all().iter().map(|anchor| self.view(anchor)).collect()
     }
     
     /// Get anchors by state
-    pub fn anchors_by_state(&self, state: AnchorState) -> Vec<&SpatialAnchor> {
-        self.anchors.values()
+    pub fn anchors_by_state(&self, state: AnchorState) -> Vec<SpatialAnchor> {
+        self.all_anchors().into_iter()
             .filter(|a| a.state == state)
             .collect()
     }
     
     /// Get anchors in radius
-    pub fn anchors_in_radius(&self, center: Point3<f32>, radius: f32) -> Vec<&SpatialAnchor> {
-        self.anchors.values()
+    pub fn anchors_in_radius(&self, center: Point3<f32>, radius: f32) -> Vec<SpatialAnchor> {
+        self.all_anchors().into_iter()
             .filter(|a| (a.position - center).norm() <= radius)
             .collect()
     }
     
     /// Find nearest anchor to point
-    pub fn find_nearest(&self, point: &Point3<f32>) -> Option<&SpatialAnchor> {
-        self.anchors.values()
+    pub fn find_nearest(&self, point: &Point3<f32>) -> Option<SpatialAnchor> {
+        self.all_anchors().into_iter()
             .min_by(|a, b| {
                 let dist_a = (a.position - point).norm();
                 let dist_b = (b.position - point).norm();
@@ -105,19 +144,21 @@ impl AnchorManager {
             })
     }
     
-    /// Update tracking state for all anchors
+    /// Update tracking state for the anchors this manager created
     pub fn update_tracking(&mut self) {
         let now = Instant::now();
         
-        for anchor in self.anchors.values_mut() {
+        for (&id, meta) in &self.anchors {
             // Mark as limited if not updated recently
-            let age = now.duration_since(anchor.last_updated);
-            if age > Duration::from_secs(5) {
-                anchor.state = AnchorState::Limited;
-            }
-            if age > Duration::from_secs(30) {
-                anchor.state = AnchorState::NotTracking;
-            }
+            let age = now.duration_since(meta.last_updated);
+            let state = if age > Duration::from_secs(30) {
+                AnchorState::NotTracking
+            } else if age > Duration::from_secs(5) {
+                AnchorState::Limited
+            } else {
+                continue;
+            };
+            let _ = self.service.set_state(id, state.into());
         }
     }
     
@@ -125,85 +166,44 @@ impl AnchorManager {
     pub fn relocalize(&mut self, _relocalization_data: &[u8]) -> usize {
         let mut count = 0;
         
-        for anchor in self.anchors.values_mut() {
+        for anchor in self.all_anchors() {
             if anchor.state == AnchorState::NotTracking {
                 // Placeholder: Real implementation would use relocalization data
-                anchor.state = AnchorState::Limited;
-                count += 1;
+                if self.service.set_state(anchor.id, AnchorState::Limited.into()).is_ok() {
+                    count += 1;
+                }
             }
         }
         
         count
     }
     
-    /// Serialize anchors for persistence
-    pub fn serialize(&self) -> Vec<AnchorData> {
-        self.anchors.values()
-            .map(|a| AnchorData {
-                id: a.id,
-                name: a.name.clone(),
-                position: [a.position.x, a.position.y, a.position.z],
-                rotation: [
-                    a.rotation.quaternion().i,
-                    a.rotation.quaternion().j,
-                    a.rotation.quaternion().k,
-                    a.rotation.quaternion().w,
-                ],
-                cloud_id: a.cloud_id.clone(),
-            })
-            .collect()
-    }
-    
-    /// Deserialize and restore anchors
-    pub fn deserialize(&mut self, data: &[AnchorData]) {
-        for anchor_data in data {
-            let position = Point3::new(
-                anchor_data.position[0],
-                anchor_data.position[1],
-                anchor_data.position[2],
-            );
-            
-            let rotation = UnitQuaternion::from_quaternion(
-                nalgebra::Quaternion::new(
-                    anchor_data.rotation[3],
-                    anchor_data.rotation[0],
-                    anchor_data.rotation[1],
-                    anchor_data.rotation[2],
-                )
-            );
-            
-            let mut anchor = SpatialAnchor {
-                id: anchor_data.id,
-                name: anchor_data.name.clone(),
-                position,
-                rotation,
-                state: AnchorState::NotTracking,
-                confidence: 0.0,
-                cloud_id: anchor_data.cloud_id.clone(),
-                created_at: Instant::now(),
-                last_updated: Instant::now(),
-            };
-            
-            self.anchors.insert(anchor.id, anchor);
-        }
-    }
-    
     /// Count of anchors
     pub fn count(&self) -> usize {
-        self.anchors.len()
+        self.service.count()
     }
     
-    /// Clear all anchors
+    /// Clear the anchors this manager created
     pub fn clear(&mut self) {
-        self.anchors.clear();
+        for (id, _) in self.anchors.drain() {
+            let _ = self.service.remove(id);
+        }
+    }
+    
+    /// Scene view of a world anchor
+    fn view(&self, anchor: &spatial::SpatialAnchor) -> SpatialAnchor {
+        match self.anchors.get(&anchor.id) {
+            Some(meta) => SpatialAnchor::from_world(anchor, meta),
+            None => SpatialAnchor::from_world(anchor, &AnchorMeta::foreign(anchor)),
+        }
     }
 }
 
 /// A spatial anchor for AR content placement
 #[derive(Debug, Clone)]
 pub struct SpatialAnchor {
-    /// Unique identifier
-    pub id: SceneId,
+    /// World-anchor ID
+    pub id: AnchorId,
     /// Optional name
     pub name: Option<String>,
     /// Position in world space
@@ -223,17 +223,19 @@ pub struct SpatialAnchor {
 }
 
 impl SpatialAnchor {
-    pub fn new(position: Point3<f32>, name: Option<String>) -> Self {
+    /// Scene view of a world anchor
+    fn from_world(anchor: &spatial::SpatialAnchor, meta: &AnchorMeta) -> Self {
+        let local = anchor.position.local;
         Self {
-            id: Uuid::new_v4(),
-            name,
-            position,
-            rotation: UnitQuaternion::identity(),
-            state: AnchorState::Tracking,
-            confidence: 1.0,
-            cloud_id: None,
-            created_at: Instant::now(),
-            last_updated: Instant::now(),
+            id: anchor.id,
+            name: anchor.label.clone(),
+            position: Point3::new(local.x, local.y, local.z),
+            rotation: anchor.orientation.to_unit(),
+            state: anchor.state.into(),
+            confidence: anchor.confidence,
+            cloud_id: meta.cloud_id.clone(),
+            created_at: meta.created_at,
+            last_updated: meta.last_updated,
         }
     }
     
@@ -295,20 +297,47 @@ pub enum AnchorState {
     Pending,
 }
 
-/// Serializable anchor data
-#[derive(Debug, Clone)]
-pub struct AnchorData {
-    pub id: SceneId,
-    pub name: Option<String>,
-    pub position: [f32; 3],
-    pub rotation: [f32; 4], // quaternion [x, y, z, w]
-    pub cloud_id: Option<String>,
+impl From<spatial::AnchorState> for AnchorState {
+    fn from(state: spatial::AnchorState) -> Self {
+        match state {
+            spatial::AnchorState::Active => AnchorState::Tracking,
+            spatial::AnchorState::Degraded => AnchorState::Limited,
+            spatial::AnchorState::Lost | spatial::AnchorState::Archived => AnchorState::NotTracking,
+            spatial::AnchorState::Pending => AnchorState::Pending,
+        }
+    }
+}
+
+impl From<AnchorState> for spatial::AnchorState {
+    fn from(state: AnchorState) -> Self {
+        match state {
+            AnchorState::Tracking => spatial::AnchorState::Active,
+            AnchorState::Limited => spatial::AnchorState::Degraded,
+            AnchorState::NotTracking => spatial::AnchorState::Lost,
+            AnchorState::Pending => spatial::AnchorState::Pending,
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     
+    fn view(position: Point3<f32>) -> SpatialAnchor {
+        let now = Instant::now();
+        SpatialAnchor {
+            id: 1,
+            name: None,
+            position,
+            rotation: UnitQuaternion::identity(),
+            state: AnchorState::Tracking,
+            confidence: 1.0,
+            cloud_id: None,
+            created_at: now,
+            last_updated: now,
+        }
+    }
+    
     #[test]
     fn test_anchor_manager_creation() {
         let manager = AnchorManager::new(true);
@@ -319,7 +348,7 @@ mod tests {
     fn test_create_anchor() {
         let mut manager = AnchorManager::new(false);
         
-        let anchor = manager.create_anchor(Point3::new(1.0, 2.0, 3.0), Some("Test".to_string()));
+        let anchor = manager.create_anchor(Point3::new(1.0, 2.0, 3.0), Some("Test".to_string())).unwrap();
         
         assert_eq!(manager.count(), 1);
         assert_eq!(anchor.name, Some("Test".to_string()));
@@ -330,7 +359,7 @@ mod tests {
     fn test_get_anchor() {
         let mut manager = AnchorManager::new(false);
         
-        let anchor = manager.create_anchor(Point3::new(0.0, 0.0, 0.0), None);
+        let anchor = manager.create_anchor(Point3::new(0.0, 0.0, 0.0), None).unwrap();
         let id = anchor.id;
         
         let retrieved = manager.get_anchor(id);
@@ -342,7 +371,7 @@ mod tests {
     fn test_remove_anchor() {
         let mut manager = AnchorManager::new(false);
         
-        let anchor = manager.create_anchor(Point3::new(0.0, 0.0, 0.0), None);
+        let anchor = manager.create_anchor(Point3::new(0.0, 0.0, 0.0), None).unwrap();
         let id = anchor.id;
         
         assert!(manager.remove_anchor(id));
@@ -354,7 +383,7 @@ mod tests {
     fn test_update_anchor_position() {
         let mut manager = AnchorManager::new(false);
         
-        let anchor = manager.create_anchor(Point3::new(0.0, 0.0, 0.0), None);
+        let anchor = manager.create_anchor(Point3::new(0.0, 0.0, 0.0), None).unwrap();
         let id = anchor.id;
         
         assert!(manager.update_anchor_position(id, Point3::new(5.0, 5.0, 5.0)));
@@ -367,9 +396,9 @@ mod tests {
     fn test_anchors_in_radius() {
         let mut manager = AnchorManager::new(false);
         
-        manager.create_anchor(Point3::new(0.0, 0.0, 0.0), None);
-        manager.create_anchor(Point3::new(1.0, 0.0, 0.0), None);
-        manager.create_anchor(Point3::new(10.0, 0.0, 0.0), None);
+        manager.create_anchor(Point3::new(0.0, 0.0, 0.0), None).unwrap();
+        manager.create_anchor(Point3::new(1.0, 0.0, 0.0), None).unwrap();
+        manager.create_anchor(Point3::new(10.0, 0.0, 0.0), None).unwrap();
         
         let nearby = manager.anchors_in_radius(Point3::origin(), 2.0);
         assert_eq!(nearby.len(), 2);
@@ -379,8 +408,8 @@ mod tests {
     fn test_find_nearest() {
         let mut manager = AnchorManager::new(false);
         
-        manager.create_anchor(Point3::new(0.0, 0.0, 0.0), Some("A".to_string()));
-        manager.create_anchor(Point3::new(5.0, 0.0, 0.0), Some("B".to_string()));
+        manager.create_anchor(Point3::new(0.0, 0.0, 0.0), Some("A".to_string())).unwrap();
+        manager.create_anchor(Point3::new(5.0, 0.0, 0.0), Some("B".to_string())).unwrap();
         
         let nearest = manager.find_nearest(&Point3::new(1.0, 0.0, 0.0));
         assert!(nearest.is_some());
@@ -389,7 +418,7 @@ mod tests {
     
     #[test]
     fn test_anchor_transform() {
-        let anchor = SpatialAnchor::new(Point3::new(10.0, 0.0, 0.0), None);
+        let anchor = view(Point3::new(10.0, 0.0, 0.0));
         
         let local_point = Point3::new(1.0, 0.0, 0.0);
         let world_point = anchor.transform_point(local_point);
@@ -399,7 +428,7 @@ mod tests {
     
     #[test]
     fn test_anchor_inverse_transform() {
-        let anchor = SpatialAnchor::new(Point3::new(10.0, 0.0, 0.0), None);
+        let anchor = view(Point3::new(10.0, 0.0, 0.0));
         
         let world_point = Point3::new(15.0, 0.0, 0.0);
         let local_point = anchor.inverse_transform_point(world_point);
@@ -409,7 +438,7 @@ mod tests {
     
     #[test]
     fn test_anchor_state() {
-        let mut anchor = SpatialAnchor::new(Point3::origin(), None);
+        let mut anchor = view(Point3::origin());
         
         assert!(anchor.is_tracking());
         assert!(anchor.is_usable());
@@ -423,24 +452,64 @@ mod tests {
     }
     
     #[test]
-    fn test_serialize_deserialize() {
-        let mut manager = AnchorManager::new(false);
+    fn test_anchors_shared_through_service() {
+        let service = WorldAnchorService::in_memory();
+        let mut manager = AnchorManager::with_service(service.clone(), true);
         
-        manager.create_anchor(Point3::new(1.0, 2.0, 3.0), Some("Test".to_string()));
-        manager.create_anchor(Point3::new(4.0, 5.0, 6.0), None);
+        let rotation = UnitQuaternion::from_euler_angles(0.0, 0.5, 0.0);
+        let anchor = manager
+            .create_anchor_with_transform(Point3::new(1.0, 2.0, 3.0), rotation, Some("Test".to_string()))
+            .unwrap();
         
-        let data = manager.serialize();
-        assert_eq!(data.len(), 2);
+        let world = service.get(anchor.id).unwrap();
+        assert!(service.is_persistent(anchor.id));
+        assert_eq!(world.label.as_deref(), Some("Test"));
+        assert!(world.orientation.to_unit().angle_to(&rotation) < 1e-5);
         
-        let mut new_manager = AnchorManager::new(false);
-        new_manager.deserialize(&data);
+        // Tracking changes made elsewhere show up in the scene view
+        service.update_confidence(anchor.id, 0.5);
+        assert_eq!(manager.get_anchor(anchor.id).unwrap().state, AnchorState::Limited);
+        assert_eq!(manager.relocalize(&[]), 0);
         
-        assert_eq!(new_manager.count(), 2);
+        service.update_confidence(anchor.id, 0.0);
+        assert_eq!(manager.relocalize(&[]), 1);
+        
+        manager.clear();
+        assert_eq!(service.count(), 0);
+    }
+    
+    #[test]
+    fn test_service_anchors_visible() {
+        let service = WorldAnchorService::in_memory();
+        let mut manager = AnchorManager::with_service(service.clone(), false);
+        manager.create_anchor(Point3::new(5.0, 0.0, 0.0), Some("scene".to_string())).unwrap();
+        
+        // Anchors from WebXR, the spatial system or a restore show up too
+        let mut position = WorldPosition::from_local(1.0, 0.0, 0.0);
+        position.floor = 1;
+        let other = service.create(CreateAnchorRequest {
+            position,
+            label: Some("restored".to_string()),
+            ..Default::default()
+        }, false).unwrap();
+        
+        assert_eq!(manager.count(), 2);
+        assert_eq!(manager.all_anchors().len(), 2);
+        assert_eq!(manager.find_nearest(&Point3::origin()).unwrap().id, other.id);
+        
+        // Moving it keeps its floor
+        assert!(manager.update_anchor_position(other.id, Point3::new(2.0, 0.0, 0.0)));
+        assert_eq!(service.get(other.id).unwrap().position.floor, 1);
+        
+        // Clearing the scene leaves anchors it did not create
+        manager.clear();
+        assert_eq!(manager.count(), 1);
+        assert!(manager.get_anchor(other.id).is_some());
     }
     
     #[test]
     fn test_anchor_distance() {
-        let anchor = SpatialAnchor::new(Point3::new(0.0, 0.0, 0.0), None);
+        let anchor = view(Point3::new(0.0, 0.0, 0.0));
         
         let distance = anchor.distance_to(&Point3::new(3.0, 4.0, 0.0));
         assert!((distance - 5.0).abs() < 0.001);
@@ -6,9 +6,12 @@
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
+use anyhow::Result;
 use nalgebra::{Point3, Vector3, Matrix4, UnitQuaternion};
 use uuid::Uuid;
 
+use crate::spatial::{AnchorId, WorldAnchorService};
+
 pub mod analyzer;
 pub mod surfaces;
 pub mod lighting;
@@ -353,7 +356,7 @@ pub enum SceneNodeType {
     /// Object reference
     Object(SceneId),
     /// Anchor reference
-    Anchor(SceneId),
+    Anchor(AnchorId),
     /// Light source
     Light(SceneId),
     /// Virtual content
@@ -379,6 +382,14 @@ impl SceneEngine {
         }
     }
     
+    /// Create an engine whose anchors live in a shared world-anchor service
+    pub fn with_anchor_service(config: SceneConfig, anchors: WorldAnchorService) -> Self {
+        let persistence = config.anchor_persistence;
+        let mut engine = Self::new(config);
+        engine.anchor_manager = AnchorManager::with_service(anchors, persistence);
+        engine
+    }
+    
     /// Process a depth frame for scene understanding
     pub fn process_depth_frame(&mut self, depth_data: &DepthFrame) -> &SceneState {
         let start = Instant::now();
@@ -435,16 +446,16 @@ impl SceneEngine {
     }
     
     /// Create a spatial anchor at a location
-    pub fn create_anchor(&mut self, position: Point3<f32>, name: Option<String>) -> SceneId {
-        let anchor = self.anchor_manager.create_anchor(position, name);
+    pub fn create_anchor(&mut self, position: Point3<f32>, name: Option<String>) -> Result<AnchorId> {
+        let anchor = self.anchor_manager.create_anchor(position, name)?;
         let id = anchor.id;
         self.state.anchors.push(anchor);
         self.state.stats.anchor_count = self.state.anchors.len();
-        id
+        Ok(id)
     }
     
     /// Remove a spatial anchor
-    pub fn remove_anchor(&mut self, id: SceneId) -> bool {
+    pub fn remove_anchor(&mut self, id: AnchorId) -> bool {
         if self.anchor_manager.remove_anchor(id) {
             self.state.anchors.retain(|a| a.id != id);
             self.state.stats.anchor_count = self.state.anchors.len();
@@ -734,7 +745,7 @@ mod tests {
     fn test_anchor_creation() {
         let mut engine = SceneEngine::new(SceneConfig::default());
         
-        let id = engine.create_anchor(Point3::new(1.0, 0.5, 2.0), Some("Test".to_string()));
+        let id = engine.create_anchor(Point3::new(1.0, 0.5, 2.0), Some("Test".to_string())).unwrap();
         assert_eq!(engine.state.anchors.len(), 1);
         assert_eq!(engine.state.stats.anchor_count, 1);
         
@@ -400,6 +400,176 @@ impl Default for VirtualSensors {
     }
 }
 
+/// Settings for a simulated head-motion trace (IMU + camera poses)
+#[derive(Debug, Clone)]
+pub struct MotionTraceConfig {
+    pub duration: Duration,
+    pub imu_rate_hz: u32,
+    pub camera_rate_hz: u32,
+    /// Constant gyroscope bias (rad/s)
+    pub gyro_bias: Vec3,
+    /// Constant accelerometer bias (m/s²)
+    pub accel_bias: Vec3,
+    /// Per-sample gyroscope noise std dev (rad/s)
+    pub gyro_noise: f32,
+    /// Per-sample accelerometer noise std dev (m/s²)
+    pub accel_noise: f32,
+    /// Camera position noise std dev (m)
+    pub camera_position_noise: f32,
+    /// Camera orientation noise std dev (rad)
+    pub camera_orientation_noise: f32,
+    /// Above this head rotation rate (rad/s) the camera loses tracking to motion blur
+    pub camera_max_rate: f32,
+    pub seed: u64,
+}
+
+impl Default for MotionTraceConfig {
+    fn default() -> Self {
+        Self {
+            duration: Duration::from_secs(8),
+            imu_rate_hz: 400,
+            camera_rate_hz: 30,
+            gyro_bias: Vec3::new(0.01, -0.02, 0.015),
+            accel_bias: Vec3::new(0.05, -0.08, 0.06),
+            gyro_noise: 0.004,
+            accel_noise: 0.04,
+            camera_position_noise: 0.005,
+            camera_orientation_noise: 0.003,
+            camera_max_rate: 1.5,
+            seed: 7,
+        }
+    }
+}
+
+/// Accelerometer + gyroscope sample in the head frame. The accelerometer reads
+/// (0, -9.81, 0) when level and still, like `VirtualSensors`.
+#[derive(Debug, Clone, Copy)]
+pub struct ImuSample {
+    pub timestamp_ns: u64,
+    pub acceleration: Vec3,
+    pub angular_velocity: Vec3,
+}
+
+/// Head pose in world coordinates (y up)
+#[derive(Debug, Clone, Copy)]
+pub struct PoseSample {
+    pub timestamp_ns: u64,
+    pub position: Vec3,
+    pub orientation: Quaternion,
+    pub velocity: Vec3,
+}
+
+/// Walking with head bob and two fast head turns: IMU samples, the noisy camera
+/// poses visual tracking would report (none while turning fast), and ground truth
+/// at the IMU rate
+#[derive(Debug, Clone)]
+pub struct MotionTrace {
+    pub imu: Vec<ImuSample>,
+    pub camera: Vec<PoseSample>,
+    pub truth: Vec<PoseSample>,
+}
+
+impl MotionTrace {
+    pub fn generate(config: &MotionTraceConfig) -> Self {
+        use nalgebra::{UnitQuaternion, Vector3};
+
+        const GRAVITY: f64 = 9.81;
+        const H: f64 = 1e-3;
+        let position = |t: f64| Vector3::new(0.2 * (0.7 * t).sin(), 0.03 * (2.0 * std::f64::consts::PI * 1.8 * t).sin(), -0.8 * t);
+        let orientation = |t: f64| {
+            // Quick turn of `angle` starting at `start`, eased over `length` seconds
+            let turn = |start: f64, length: f64, angle: f64| {
+                let u = ((t - start) / length).clamp(0.0, 1.0);
+                angle * 0.5 * (1.0 - (std::f64::consts::PI * u).cos())
+            };
+            let yaw = 0.3 * (0.5 * t).sin() + turn(2.5, 0.5, 1.2) + turn(5.5, 0.6, -1.2);
+            UnitQuaternion::from_axis_angle(&Vector3::y_axis(), yaw)
+                * UnitQuaternion::from_axis_angle(&Vector3::x_axis(), 0.1 * (1.3 * t).sin())
+                * UnitQuaternion::from_axis_angle(&Vector3::z_axis(), 0.05 * (0.9 * t).sin())
+        };
+        let to_vec3 = |v: Vector3<f64>| Vec3::new(v.x as f32, v.y as f32, v.z as f32);
+        let to_quat = |q: UnitQuaternion<f64>| Quaternion { w: q.w as f32, x: q.i as f32, y: q.j as f32, z: q.k as f32 };
+
+        let mut rng = TraceRng::new(config.seed);
+        let mut noise = |std: f32| Vector3::new(rng.gaussian(), rng.gaussian(), rng.gaussian()) * std as f64;
+        let bias = |v: &Vec3| Vector3::new(v.x as f64, v.y as f64, v.z as f64);
+
+        let mut trace = MotionTrace { imu: Vec::new(), camera: Vec::new(), truth: Vec::new() };
+        let imu_period = 1e9 / config.imu_rate_hz.max(1) as f64;
+        let camera_period = 1e9 / config.camera_rate_hz.max(1) as f64;
+        let end_ns = config.duration.as_nanos() as f64;
+        let mut next_camera_ns = 0.0;
+        let mut k = 0u64;
+        loop {
+            let timestamp = (k as f64 * imu_period).round();
+            if timestamp > end_ns {
+                break;
+            }
+            k += 1;
+            let t = timestamp / 1e9;
+            let q = orientation(t);
+            let angular_velocity = (orientation(t - H).inverse() * orientation(t + H)).scaled_axis() / (2.0 * H);
+            let acceleration = (position(t + H) - 2.0 * position(t) + position(t - H)) / (H * H);
+            let velocity = (position(t + H) - position(t - H)) / (2.0 * H);
+            let specific_force = q.inverse() * (acceleration - Vector3::y() * GRAVITY);
+
+            let truth = PoseSample {
+                timestamp_ns: timestamp as u64,
+                position: to_vec3(position(t)),
+                orientation: to_quat(q),
+                velocity: to_vec3(velocity),
+            };
+            trace.imu.push(ImuSample {
+                timestamp_ns: timestamp as u64,
+                acceleration: to_vec3(specific_force + bias(&config.accel_bias) + noise(config.accel_noise)),
+                angular_velocity: to_vec3(angular_velocity + bias(&config.gyro_bias) + noise(config.gyro_noise)),
+            });
+
+            if timestamp >= next_camera_ns {
+                next_camera_ns += camera_period;
+                if angular_velocity.norm() <= config.camera_max_rate as f64 {
+                    let error = UnitQuaternion::from_scaled_axis(noise(config.camera_orientation_noise));
+                    trace.camera.push(PoseSample {
+                        position: to_vec3(position(t) + noise(config.camera_position_noise)),
+                        orientation: to_quat(q * error),
+                        ..truth
+                    });
+                }
+            }
+            trace.truth.push(truth);
+        }
+        trace
+    }
+
+    /// Ground truth at (or just before) a timestamp
+    pub fn truth_at(&self, timestamp_ns: u64) -> Option<&PoseSample> {
+        let index = self.truth.partition_point(|s| s.timestamp_ns <= timestamp_ns);
+        index.checked_sub(1).map(|i| &self.truth[i])
+    }
+}
+
+/// Seeded generator so traces are reproducible (unlike `rand_simple`)
+struct TraceRng(u64);
+
+impl TraceRng {
+    fn new(seed: u64) -> Self {
+        Self(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1)
+    }
+
+    fn uniform(&mut self) -> f64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        ((self.0 >> 11) as f64 + 0.5) / (1u64 << 53) as f64
+    }
+
+    /// Standard normal (Box-Muller)
+    fn gaussian(&mut self) -> f64 {
+        let (u1, u2) = (self.uniform(), self.uniform());
+        (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+    }
+}
+
 /// Simple pseudo-random number (0.0 to 1.0)
 fn rand_simple() -> f32 {
     use std::time::SystemTime;
@@ -442,6 +612,21 @@ mod tests {
         assert!((sensors.location.value.latitude - 40.7128).abs() < 0.001);
     }
 
+    #[test]
+    fn test_motion_trace_at_rest_reads_gravity() {
+        let trace = MotionTrace::generate(&MotionTraceConfig::default());
+        assert_eq!(trace.imu.len(), trace.truth.len());
+        assert!(trace.camera.len() < trace.imu.len() / 10);
+
+        // Head turns are fast enough that the camera drops out
+        let gap = trace.camera.windows(2).map(|w| w[1].timestamp_ns - w[0].timestamp_ns).max().unwrap();
+        assert!(gap > 200_000_000);
+
+        // Specific force averages out to gravity over the trace
+        let mean_magnitude = trace.imu.iter().map(|s| s.acceleration.magnitude()).sum::<f32>() / trace.imu.len() as f32;
+        assert!((mean_magnitude - 9.81).abs() < 0.2);
+    }
+
     #[test]
     fn test_vec3_magnitude() {
         let v = Vec3::new(3.0, 4.0, 0.0);
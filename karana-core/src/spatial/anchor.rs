@@ -5,9 +5,10 @@
 //! to a location.
 
 use anyhow::{anyhow, Result};
+use nalgebra::UnitQuaternion;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use super::world_coords::WorldPosition;
 
@@ -47,6 +48,27 @@ pub struct SpatialAnchor {
     pub label: Option<String>,
 }
 
+impl SpatialAnchor {
+    /// Creation time as a monotonic instant (second resolution)
+    pub fn created_instant(&self) -> Instant {
+        instant_at(self.created_at)
+    }
+
+    /// Last update as a monotonic instant (second resolution)
+    pub fn updated_instant(&self) -> Instant {
+        instant_at(self.updated_at)
+    }
+}
+
+/// The instant at a UNIX timestamp in seconds, clamped to now
+fn instant_at(timestamp: u64) -> Instant {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+    let now_instant = Instant::now();
+    now_instant
+        .checked_sub(Duration::from_secs(now.saturating_sub(timestamp)))
+        .unwrap_or(now_instant)
+}
+
 /// Quaternion for 3D rotation
 #[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
 pub struct Quaternion {
@@ -78,6 +100,17 @@ impl Quaternion {
             z: cr * cp * sy - sr * sp * cy,
         }
     }
+    
+    /// Convert from a nalgebra rotation
+    pub fn from_unit(rotation: &UnitQuaternion<f32>) -> Self {
+        let q = rotation.quaternion();
+        Self { x: q.i, y: q.j, z: q.k, w: q.w }
+    }
+    
+    /// Convert to a nalgebra rotation (renormalized)
+    pub fn to_unit(&self) -> UnitQuaternion<f32> {
+        UnitQuaternion::from_quaternion(nalgebra::Quaternion::new(self.w, self.x, self.y, self.z))
+    }
 }
 
 /// Visual signature for relocalization matching
@@ -89,6 +122,8 @@ pub type ContentHash = [u8; 32];
 /// State of a spatial anchor
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum AnchorState {
+    /// Anchor has been placed but tracking has not localized it yet
+    Pending,
     /// Anchor is active and being tracked
     Active,
     /// Anchor is visible but tracking quality is low
@@ -99,6 +134,19 @@ pub enum AnchorState {
     Archived,
 }
 
+impl AnchorState {
+    /// Tracking state implied by a confidence value
+    pub fn from_confidence(confidence: f32) -> Self {
+        if confidence > 0.7 {
+            AnchorState::Active
+        } else if confidence > 0.3 {
+            AnchorState::Degraded
+        } else {
+            AnchorState::Lost
+        }
+    }
+}
+
 /// Types of content that can be anchored
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum AnchorContent {
@@ -149,6 +197,11 @@ pub enum AnchorContent {
         app_id: String,
         state: Vec<u8>,
     },
+    
+    /// Anchor placed by a web page through the WebXR Anchors API
+    WebXR {
+        origin_domain: String,
+    },
 }
 
 impl AnchorContent {
@@ -163,6 +216,7 @@ impl AnchorContent {
             AnchorContent::Model3D { .. } => "model",
             AnchorContent::Waypoint { .. } => "waypoint",
             AnchorContent::Custom { .. } => "custom",
+            AnchorContent::WebXR { .. } => "webxr",
         }
     }
     
@@ -261,6 +315,10 @@ pub struct CreateAnchorRequest {
     pub label: Option<String>,
     /// Owner DID
     pub owner_did: Option<String>,
+    /// Initial tracking state (defaults to active)
+    pub state: Option<AnchorState>,
+    /// Initial tracking confidence (defaults to 1.0)
+    pub confidence: Option<f32>,
 }
 
 impl Default for AnchorContent {
@@ -296,8 +354,8 @@ impl SpatialAnchorRegistry {
             visual_signature: request.visual_signature.unwrap_or([0u8; 32]),
             content_hash: request.content.hash(),
             content: request.content,
-            state: AnchorState::Active,
-            confidence: 1.0,
+            state: request.state.unwrap_or(AnchorState::Active),
+            confidence: request.confidence.unwrap_or(1.0).clamp(0.0, 1.0),
             created_at: now,
             updated_at: now,
             owner_did: request.owner_did,
@@ -310,6 +368,16 @@ impl SpatialAnchorRegistry {
         Ok(anchor)
     }
     
+    /// Re-register a previously created anchor under its original ID
+    pub fn insert(&mut self, anchor: SpatialAnchor) {
+        self.next_id = self.next_id.max(anchor.id + 1);
+        if let Some(previous) = self.anchors.remove(&anchor.id) {
+            self.spatial_index.remove(previous.id, &previous.position);
+        }
+        self.spatial_index.insert(anchor.id, &anchor.position);
+        self.anchors.insert(anchor.id, anchor);
+    }
+    
     /// Get an anchor by ID
     pub fn get(&self, id: AnchorId) -> Option<&SpatialAnchor> {
         self.anchors.get(&id)
@@ -345,13 +413,7 @@ impl SpatialAnchorRegistry {
                 .as_secs();
             
             // Update state based on confidence
-            anchor.state = if confidence > 0.7 {
-                AnchorState::Active
-            } else if confidence > 0.3 {
-                AnchorState::Degraded
-            } else {
-                AnchorState::Lost
-            };
+            anchor.state = AnchorState::from_confidence(confidence);
         }
     }
     
@@ -506,6 +568,29 @@ mod tests {
         assert!(rotated.w < 1.0); // Should have changed
     }
     
+    #[test]
+    fn test_insert_keeps_id() {
+        let mut registry = SpatialAnchorRegistry::new();
+        
+        let restored = registry.create(CreateAnchorRequest::default()).unwrap();
+        let mut fresh = SpatialAnchorRegistry::new();
+        fresh.insert(SpatialAnchor { id: 7, ..restored });
+        
+        assert!(fresh.get(7).is_some());
+        assert_eq!(fresh.find_nearby(&WorldPosition::default(), 0.5).len(), 1);
+        
+        // New anchors never reuse a restored ID
+        let next = fresh.create(CreateAnchorRequest::default()).unwrap();
+        assert_eq!(next.id, 8);
+    }
+    
+    #[test]
+    fn test_quaternion_roundtrip() {
+        let rotation = UnitQuaternion::from_euler_angles(0.1, 0.4, -0.2);
+        let back = Quaternion::from_unit(&rotation).to_unit();
+        assert!(rotation.angle_to(&back) < 1e-5);
+    }
+    
     #[test]
     fn test_content_hash() {
         let content1 = AnchorContent::Text { text: "Hello".to_string() };
@@ -0,0 +1,402 @@
+//! World Anchor Service
+//!
+//! The single owner of every anchor on the device. The AR renderer, the scene
+//! engine and the WebXR bridge are thin adapters over this service, so an
+//! anchor has one ID, one pose, one tracking state and one persisted record
+//! no matter which API created it.
+//!
+//! The service is a cheap, cloneable handle; all clones share the same state.
+
+use anyhow::{anyhow, Result};
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::{Arc, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use super::anchor::{
+    AnchorId, AnchorState, CreateAnchorRequest, Quaternion, SpatialAnchor, SpatialAnchorRegistry,
+};
+use super::persistence::{AnchorStore, PersistenceMode};
+use super::world_coords::{LocalCoord, WorldPosition};
+
+// ============================================================================
+// WORLD ANCHOR SERVICE
+// ============================================================================
+
+/// Shared handle to the device-wide anchor service
+#[derive(Clone)]
+pub struct WorldAnchorService {
+    inner: Arc<RwLock<ServiceState>>,
+}
+
+struct ServiceState {
+    /// Live anchors (pose, tracking state, content)
+    registry: SpatialAnchorRegistry,
+    /// Backing store; `None` for in-memory services
+    store: Option<AnchorStore>,
+    /// Anchors that outlive the session that created them
+    persistent: HashSet<AnchorId>,
+    /// Persistent anchors whose tracked pose has not been written yet
+    dirty: HashSet<AnchorId>,
+}
+
+impl WorldAnchorService {
+    /// Create a service that keeps anchors in memory only
+    pub fn in_memory() -> Self {
+        Self::with_store(None)
+    }
+
+    /// Open a service backed by an on-disk store, restoring persisted anchors.
+    ///
+    /// Restored anchors come back as `Lost` until relocalization finds them.
+    pub fn open(path: impl Into<PathBuf>) -> Self {
+        let mut store = AnchorStore::new(path);
+        if let Err(e) = store.load_all() {
+            log::warn!("[SPATIAL] Failed to load persisted anchors: {}", e);
+        }
+
+        let mut registry = SpatialAnchorRegistry::new();
+        let mut persistent = HashSet::new();
+        for stored in store.stored() {
+            let mut anchor = stored.anchor.clone();
+            anchor.state = AnchorState::Lost;
+            anchor.confidence = 0.0;
+            persistent.insert(anchor.id);
+            registry.insert(anchor);
+        }
+
+        if !persistent.is_empty() {
+            log::info!("[SPATIAL] Restored {} persisted anchors", persistent.len());
+        }
+
+        Self {
+            inner: Arc::new(RwLock::new(ServiceState {
+                registry,
+                store: Some(store),
+                persistent,
+                dirty: HashSet::new(),
+            })),
+        }
+    }
+
+    fn with_store(store: Option<AnchorStore>) -> Self {
+        Self {
+            inner: Arc::new(RwLock::new(ServiceState {
+                registry: SpatialAnchorRegistry::new(),
+                store,
+                persistent: HashSet::new(),
+                dirty: HashSet::new(),
+            })),
+        }
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, ServiceState> {
+        self.inner.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, ServiceState> {
+        self.inner.write().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Create an anchor; `persist` keeps it across restarts
+    pub fn create(&self, request: CreateAnchorRequest, persist: bool) -> Result<SpatialAnchor> {
+        let mut state = self.write();
+        let anchor = state.registry.create(request)?;
+
+        if persist {
+            if let Err(e) = state.save(&anchor) {
+                state.registry.remove(anchor.id)?;
+                return Err(e);
+            }
+            state.persistent.insert(anchor.id);
+        }
+
+        Ok(anchor)
+    }
+
+    /// Get a snapshot of an anchor
+    pub fn get(&self, id: AnchorId) -> Option<SpatialAnchor> {
+        self.read().registry.get(id).cloned()
+    }
+
+    /// Snapshot of every anchor
+    pub fn all(&self) -> Vec<SpatialAnchor> {
+        self.read().registry.get_all().into_iter().cloned().collect()
+    }
+
+    /// Snapshot of the anchors that survive restarts
+    pub fn persisted(&self) -> Vec<SpatialAnchor> {
+        let state = self.read();
+        state.persistent
+            .iter()
+            .filter_map(|&id| state.registry.get(id).cloned())
+            .collect()
+    }
+
+    /// Find anchors near a position
+    pub fn find_nearby(&self, position: &WorldPosition, radius_m: f32) -> Vec<SpatialAnchor> {
+        self.read().registry.find_nearby(position, radius_m)
+    }
+
+    /// Move an anchor (drift correction or app-driven placement)
+    pub fn update_pose(
+        &self,
+        id: AnchorId,
+        position: WorldPosition,
+        orientation: Quaternion,
+    ) -> Result<()> {
+        let mut state = self.write();
+        state.registry.update_position(id, position)?;
+
+        let anchor = state.registry.get_mut(id).ok_or(anyhow!("Anchor not found"))?;
+        anchor.orientation = orientation;
+        let anchor = anchor.clone();
+
+        if state.persistent.contains(&id) {
+            state.save(&anchor)?;
+            state.dirty.remove(&id);
+        }
+        Ok(())
+    }
+
+    /// Feed a tracked pose for an anchor.
+    ///
+    /// Only the local coordinates move; room, GPS and floor are kept. The
+    /// change stays in memory until `flush`, so per-frame tracking never
+    /// touches the store.
+    pub fn track_pose(&self, id: AnchorId, local: LocalCoord, orientation: Quaternion) -> Result<()> {
+        let mut state = self.write();
+        let mut position = state.registry.get(id).ok_or(anyhow!("Anchor not found"))?.position.clone();
+        position.local = local;
+        state.registry.update_position(id, position)?;
+
+        let anchor = state.registry.get_mut(id).ok_or(anyhow!("Anchor not found"))?;
+        anchor.orientation = orientation;
+
+        if state.persistent.contains(&id) {
+            state.dirty.insert(id);
+        }
+        Ok(())
+    }
+
+    /// Write tracked poses of persistent anchors to the store.
+    ///
+    /// Returns how many anchors were written.
+    pub fn flush(&self) -> Result<usize> {
+        self.write().flush()
+    }
+
+    /// Update tracking confidence; the state follows the confidence.
+    ///
+    /// Tracking state is transient and is not written to the store.
+    pub fn update_confidence(&self, id: AnchorId, confidence: f32) {
+        self.write().registry.update_confidence(id, confidence);
+    }
+
+    /// Set the tracking state directly
+    pub fn set_state(&self, id: AnchorId, anchor_state: AnchorState) -> Result<()> {
+        let mut state = self.write();
+        let anchor = state.registry.get_mut(id).ok_or(anyhow!("Anchor not found"))?;
+        anchor.state = anchor_state;
+        Ok(())
+    }
+
+    /// Promote a session anchor to a persistent one.
+    ///
+    /// Returns false if the anchor was already persistent.
+    pub fn persist(&self, id: AnchorId) -> Result<bool> {
+        let mut state = self.write();
+        if state.persistent.contains(&id) {
+            return Ok(false);
+        }
+
+        let anchor = state.registry.get(id).cloned().ok_or(anyhow!("Anchor not found"))?;
+        state.save(&anchor)?;
+        state.persistent.insert(id);
+        Ok(true)
+    }
+
+    /// Whether an anchor survives restarts
+    pub fn is_persistent(&self, id: AnchorId) -> bool {
+        self.read().persistent.contains(&id)
+    }
+
+    /// Remove an anchor everywhere, including the store
+    pub fn remove(&self, id: AnchorId) -> Result<SpatialAnchor> {
+        let mut state = self.write();
+        let anchor = state.registry.remove(id)?;
+
+        state.dirty.remove(&id);
+        if state.persistent.remove(&id)
+            && let Some(store) = state.store.as_mut()
+        {
+            store.delete(id)?;
+        }
+        Ok(anchor)
+    }
+
+    /// Number of live anchors
+    pub fn count(&self) -> usize {
+        self.read().registry.count()
+    }
+}
+
+impl ServiceState {
+    fn save(&mut self, anchor: &SpatialAnchor) -> Result<()> {
+        match self.store.as_mut() {
+            Some(store) => store.save(anchor.clone(), PersistenceMode::Local),
+            None => Ok(()),
+        }
+    }
+
+    fn flush(&mut self) -> Result<usize> {
+        let dirty: Vec<AnchorId> = self.dirty.drain().collect();
+        let mut written = 0;
+        for id in dirty {
+            if let Some(anchor) = self.registry.get(id).cloned() {
+                if let Err(e) = self.save(&anchor) {
+                    self.dirty.insert(id);
+                    return Err(e);
+                }
+                written += 1;
+            }
+        }
+        Ok(written)
+    }
+}
+
+impl Drop for ServiceState {
+    fn drop(&mut self) {
+        if let Err(e) = self.flush() {
+            log::warn!("[SPATIAL] Failed to write tracked anchor poses: {}", e);
+        }
+    }
+}
+
+impl Default for WorldAnchorService {
+    fn default() -> Self {
+        Self::in_memory()
+    }
+}
+
+impl std::fmt::Debug for WorldAnchorService {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = self.read();
+        f.debug_struct("WorldAnchorService")
+            .field("anchors", &state.registry.count())
+            .field("persistent", &state.persistent.len())
+            .field("backed", &state.store.is_some())
+            .finish()
+    }
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spatial::anchor::AnchorContent;
+    use crate::spatial::world_coords::RoomId;
+
+    fn temp_store(name: &str) -> PathBuf {
+        let path = std::env::temp_dir()
+            .join(format!("karana_anchor_service_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        path
+    }
+
+    fn note(text: &str, x: f32) -> CreateAnchorRequest {
+        CreateAnchorRequest {
+            position: WorldPosition::from_local(x, 0.0, 0.0),
+            content: AnchorContent::Text { text: text.to_string() },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_clones_share_state() {
+        let service = WorldAnchorService::in_memory();
+        let other = service.clone();
+
+        let anchor = service.create(note("shared", 0.0), false).unwrap();
+
+        assert_eq!(other.count(), 1);
+        assert_eq!(other.get(anchor.id).unwrap().id, anchor.id);
+    }
+
+    #[test]
+    fn test_restart_restores_only_persistent() {
+        let path = temp_store("restart");
+
+        let (kept, updated) = {
+            let service = WorldAnchorService::open(&path);
+            let kept = service.create(note("kept", 0.0), true).unwrap();
+            service.create(note("session", 1.0), false).unwrap();
+            let promoted = service.create(note("promoted", 2.0), false).unwrap();
+            assert!(service.persist(promoted.id).unwrap());
+            assert!(!service.persist(promoted.id).unwrap());
+
+            let moved = WorldPosition::from_local(4.0, 0.0, 0.0);
+            service.update_pose(promoted.id, moved, Quaternion::identity()).unwrap();
+            (kept.id, promoted.id)
+        };
+
+        let service = WorldAnchorService::open(&path);
+        assert_eq!(service.count(), 2);
+        assert_eq!(service.get(kept).unwrap().state, AnchorState::Lost);
+        assert!(service.is_persistent(updated));
+        assert_eq!(service.find_nearby(&WorldPosition::from_local(4.0, 0.0, 0.0), 0.5).len(), 1);
+
+        // IDs keep counting past restored anchors
+        let fresh = service.create(note("fresh", 0.0), false).unwrap();
+        assert!(fresh.id > kept.max(updated));
+
+        let _ = std::fs::remove_dir_all(&path);
+    }
+
+    #[test]
+    fn test_tracked_poses_written_on_flush() {
+        let path = temp_store("track");
+
+        let service = WorldAnchorService::open(&path);
+        let mut request = note("desk", 0.0);
+        request.position.floor = 2;
+        request.position.room_id = Some(RoomId::new("office"));
+        let anchor = service.create(request, true).unwrap();
+
+        service.track_pose(anchor.id, LocalCoord::new(3.0, 0.0, 0.0), Quaternion::identity()).unwrap();
+        let tracked = service.get(anchor.id).unwrap();
+        assert_eq!(tracked.position.local.x, 3.0);
+        assert_eq!(tracked.position.floor, 2);
+        assert_eq!(tracked.position.room_id, Some(RoomId::new("office")));
+
+        // Nothing reaches the store until a flush
+        let on_disk = || WorldAnchorService::open(&path).get(anchor.id).unwrap().position.local.x;
+        assert_eq!(on_disk(), 0.0);
+        assert_eq!(service.flush().unwrap(), 1);
+        assert_eq!(service.flush().unwrap(), 0);
+        assert_eq!(on_disk(), 3.0);
+
+        // Dropping the last handle writes whatever is still pending
+        service.track_pose(anchor.id, LocalCoord::new(6.0, 0.0, 0.0), Quaternion::identity()).unwrap();
+        drop(service);
+        assert_eq!(on_disk(), 6.0);
+
+        let _ = std::fs::remove_dir_all(&path);
+    }
+
+    #[test]
+    fn test_remove_deletes_from_store() {
+        let path = temp_store("remove");
+
+        let service = WorldAnchorService::open(&path);
+        let anchor = service.create(note("gone", 0.0), true).unwrap();
+        service.remove(anchor.id).unwrap();
+        drop(service);
+
+        assert_eq!(WorldAnchorService::open(&path).count(), 0);
+
+        let _ = std::fs::remove_dir_all(&path);
+    }
+}
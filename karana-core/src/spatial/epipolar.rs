@@ -0,0 +1,409 @@
+//! Two-View Geometry
+//!
+//! Relative camera motion from matched features: the essential matrix is fitted
+//! with the normalized eight-point algorithm inside RANSAC, then decomposed into
+//! rotation and translation direction, keeping the solution that places the
+//! triangulated points in front of both cameras. That motion is finally refined
+//! by Levenberg-Marquardt on the Sampson error of the inliers, since the
+//! eight-point fit only minimises an algebraic error.
+//!
+//! Points are normalized image coordinates (`CameraIntrinsics::normalize`); the
+//! motion maps first-camera coordinates to second-camera ones: `X2 = R * X1 + t`.
+//! Monocular translation is only known up to scale, so `t` is a unit vector.
+
+use nalgebra::{Matrix3, Rotation3, SMatrix, SymmetricEigen, UnitQuaternion, Vector2, Vector3};
+use rand::rngs::StdRng;
+use rand::seq::index::sample;
+use rand::SeedableRng;
+
+/// Correspondences needed for one essential matrix hypothesis
+const SAMPLE_SIZE: usize = 8;
+/// Levenberg-Marquardt iterations when refining the motion
+const REFINE_ITERATIONS: usize = 20;
+
+/// RANSAC settings for `estimate_relative_pose`
+#[derive(Debug, Clone)]
+pub struct RansacConfig {
+    /// Upper bound on hypotheses tried
+    pub max_iterations: usize,
+    /// Inlier threshold on the Sampson distance, in normalized image units
+    /// (pixels / focal length)
+    pub threshold: f64,
+    /// Stop once an all-inlier sample has been drawn with this probability
+    pub confidence: f64,
+    /// Fewer inliers than this is a failure
+    pub min_inliers: usize,
+    /// Seed for sampling, so results are reproducible
+    pub seed: u64,
+}
+
+impl Default for RansacConfig {
+    fn default() -> Self {
+        Self {
+            max_iterations: 1000,
+            threshold: 2.0 / 500.0, // ~2 px at the default focal length
+            confidence: 0.999,
+            min_inliers: 15,
+            seed: 0x5EED,
+        }
+    }
+}
+
+/// Motion between two views
+#[derive(Debug, Clone)]
+pub struct RelativePose {
+    pub rotation: UnitQuaternion<f64>,
+    /// Unit translation direction
+    pub translation: Vector3<f64>,
+    pub essential: Matrix3<f64>,
+    /// Which correspondences agree with the motion
+    pub inliers: Vec<bool>,
+    pub inlier_count: usize,
+}
+
+/// Relative pose from normalized correspondences `points1[i] <-> points2[i]`
+pub fn estimate_relative_pose(
+    points1: &[Vector2<f64>],
+    points2: &[Vector2<f64>],
+    config: &RansacConfig,
+) -> Option<RelativePose> {
+    let n = points1.len().min(points2.len());
+    if n < SAMPLE_SIZE.max(config.min_inliers) {
+        return None;
+    }
+
+    let mut rng = StdRng::seed_from_u64(config.seed);
+    let mut best: Option<(Matrix3<f64>, usize)> = None;
+    let mut iterations = config.max_iterations;
+    let mut i = 0;
+    while i < iterations {
+        i += 1;
+        let indices = sample(&mut rng, n, SAMPLE_SIZE).into_vec();
+        let Some(essential) = eight_point(points1, points2, &indices) else {
+            continue;
+        };
+        let count = (0..n)
+            .filter(|&j| sampson_distance(&essential, &points1[j], &points2[j]) < config.threshold)
+            .count();
+        if best.is_none_or(|(_, best_count)| count > best_count) {
+            best = Some((essential, count));
+            let inlier_ratio = count as f64 / n as f64;
+            let all_inliers = inlier_ratio.powi(SAMPLE_SIZE as i32);
+            if all_inliers > 1.0 - 1e-12 {
+                break;
+            }
+            let needed = ((1.0 - config.confidence).ln() / (1.0 - all_inliers).ln()).ceil();
+            if needed.is_finite() && needed >= 0.0 {
+                iterations = iterations.min(needed as usize);
+            }
+        }
+    }
+
+    let (mut essential, _) = best?;
+    let inliers_of = |e: &Matrix3<f64>| -> Vec<bool> {
+        (0..n).map(|j| sampson_distance(e, &points1[j], &points2[j]) < config.threshold).collect()
+    };
+
+    // Refit on all inliers; keep the refit only if it does not lose support
+    let inliers = inliers_of(&essential);
+    let inlier_indices: Vec<usize> = (0..n).filter(|&j| inliers[j]).collect();
+    if let Some(refined) = eight_point(points1, points2, &inlier_indices)
+        && inliers_of(&refined).iter().filter(|&&b| b).count() >= inlier_indices.len()
+    {
+        essential = refined;
+    }
+    let inliers = inliers_of(&essential);
+
+    let (mut rotation, mut translation, mut inliers) = recover_pose(&essential, points1, points2, &inliers)?;
+    // Refine, then re-select inliers against the refined motion and refine once more
+    for _ in 0..2 {
+        (rotation, translation) = refine_motion(rotation, translation, points1, points2, &inliers);
+        let essential = essential_from_motion(&rotation, &translation);
+        inliers = (0..n)
+            .map(|j| {
+                sampson_distance(&essential, &points1[j], &points2[j]) < config.threshold
+                    && triangulate(rotation.matrix(), &translation, &points1[j], &points2[j]).is_some()
+            })
+            .collect();
+    }
+    let essential = essential_from_motion(&rotation, &translation);
+    let inlier_count = inliers.iter().filter(|&&b| b).count();
+    if inlier_count < config.min_inliers {
+        return None;
+    }
+    Some(RelativePose {
+        rotation: UnitQuaternion::from_rotation_matrix(&rotation),
+        translation,
+        essential,
+        inliers,
+        inlier_count,
+    })
+}
+
+/// Normalized eight-point algorithm over `indices`, projected onto the essential
+/// manifold (two equal singular values, one zero)
+fn eight_point(points1: &[Vector2<f64>], points2: &[Vector2<f64>], indices: &[usize]) -> Option<Matrix3<f64>> {
+    if indices.len() < SAMPLE_SIZE {
+        return None;
+    }
+    let (t1, t2) = (hartley(points1, indices), hartley(points2, indices));
+
+    // Least-squares null vector of A (rows: x2^T E x1 = 0) via the eigenvectors of A^T A
+    let mut ata = SMatrix::<f64, 9, 9>::zeros();
+    for &i in indices {
+        let a = t1 * points1[i].push(1.0);
+        let b = t2 * points2[i].push(1.0);
+        let row = SMatrix::<f64, 1, 9>::from_row_slice(&[
+            b.x * a.x, b.x * a.y, b.x * a.z,
+            b.y * a.x, b.y * a.y, b.y * a.z,
+            b.z * a.x, b.z * a.y, b.z * a.z,
+        ]);
+        ata += row.transpose() * row;
+    }
+    let eigen = SymmetricEigen::new(ata);
+    let smallest = eigen.eigenvalues.imin();
+    let e: Vec<f64> = eigen.eigenvectors.column(smallest).iter().copied().collect();
+    let normalized = Matrix3::from_row_slice(&e);
+
+    // Undo the normalization, then enforce the essential constraints
+    let essential = t2.transpose() * normalized * t1;
+    let svd = essential.svd(true, true);
+    let (u, v_t) = (svd.u?, svd.v_t?);
+    let s = (svd.singular_values[0] + svd.singular_values[1]) / 2.0;
+    if s < f64::EPSILON {
+        return None;
+    }
+    let essential = u * Matrix3::from_diagonal(&Vector3::new(1.0, 1.0, 0.0)) * v_t;
+    Some(essential / essential.norm())
+}
+
+/// Similarity moving the selected points to zero mean and mean distance sqrt(2)
+fn hartley(points: &[Vector2<f64>], indices: &[usize]) -> Matrix3<f64> {
+    let n = indices.len() as f64;
+    let mean = indices.iter().map(|&i| points[i]).sum::<Vector2<f64>>() / n;
+    let spread = indices.iter().map(|&i| (points[i] - mean).norm()).sum::<f64>() / n;
+    let scale = if spread > f64::EPSILON { std::f64::consts::SQRT_2 / spread } else { 1.0 };
+    Matrix3::new(
+        scale, 0.0, -scale * mean.x,
+        0.0, scale, -scale * mean.y,
+        0.0, 0.0, 1.0,
+    )
+}
+
+/// First-order geometric error of a correspondence under `essential`
+fn sampson_distance(essential: &Matrix3<f64>, p1: &Vector2<f64>, p2: &Vector2<f64>) -> f64 {
+    let (x1, x2) = (p1.push(1.0), p2.push(1.0));
+    let ex1 = essential * x1;
+    let etx2 = essential.transpose() * x2;
+    let residual = x2.dot(&ex1);
+    let denominator = ex1.x * ex1.x + ex1.y * ex1.y + etx2.x * etx2.x + etx2.y * etx2.y;
+    if denominator < f64::EPSILON {
+        return f64::INFINITY;
+    }
+    (residual * residual / denominator).sqrt()
+}
+
+/// E = [t]x R
+fn essential_from_motion(rotation: &Rotation3<f64>, translation: &Vector3<f64>) -> Matrix3<f64> {
+    translation.cross_matrix() * rotation.matrix()
+}
+
+/// Signed Sampson error, the residual minimised by `refine_motion`
+fn sampson_residual(essential: &Matrix3<f64>, p1: &Vector2<f64>, p2: &Vector2<f64>) -> f64 {
+    let (x1, x2) = (p1.push(1.0), p2.push(1.0));
+    let ex1 = essential * x1;
+    let etx2 = essential.transpose() * x2;
+    let denominator = ex1.x * ex1.x + ex1.y * ex1.y + etx2.x * etx2.x + etx2.y * etx2.y;
+    if denominator < f64::EPSILON {
+        return 0.0;
+    }
+    x2.dot(&ex1) / denominator.sqrt()
+}
+
+/// Levenberg-Marquardt over the five motion degrees of freedom (a rotation
+/// increment and two directions tangent to the unit translation), with a
+/// forward-difference Jacobian
+fn refine_motion(
+    mut rotation: Rotation3<f64>,
+    mut translation: Vector3<f64>,
+    points1: &[Vector2<f64>],
+    points2: &[Vector2<f64>],
+    inliers: &[bool],
+) -> (Rotation3<f64>, Vector3<f64>) {
+    let used: Vec<usize> = (0..inliers.len()).filter(|&i| inliers[i]).collect();
+    if used.len() < SAMPLE_SIZE {
+        return (rotation, translation);
+    }
+    let residuals = |rotation: &Rotation3<f64>, translation: &Vector3<f64>| -> Vec<f64> {
+        let essential = essential_from_motion(rotation, translation);
+        used.iter().map(|&i| sampson_residual(&essential, &points1[i], &points2[i])).collect()
+    };
+    let perturb = |rotation: &Rotation3<f64>, translation: &Vector3<f64>, delta: &SMatrix<f64, 5, 1>| {
+        let helper = if translation.x.abs() < 0.9 { Vector3::x() } else { Vector3::y() };
+        let b1 = translation.cross(&helper).normalize();
+        let b2 = translation.cross(&b1);
+        let rotation = Rotation3::new(Vector3::new(delta[0], delta[1], delta[2])) * rotation;
+        let translation = (translation + b1 * delta[3] + b2 * delta[4]).normalize();
+        (rotation, translation)
+    };
+
+    const STEP: f64 = 1e-7;
+    let mut current = residuals(&rotation, &translation);
+    let mut cost: f64 = current.iter().map(|r| r * r).sum();
+    let mut lambda = 1e-3;
+    for _ in 0..REFINE_ITERATIONS {
+        let mut jacobian = vec![[0.0; 5]; used.len()];
+        for k in 0..5 {
+            let mut delta = SMatrix::<f64, 5, 1>::zeros();
+            delta[k] = STEP;
+            let (r, t) = perturb(&rotation, &translation, &delta);
+            for (row, (moved, base)) in jacobian.iter_mut().zip(residuals(&r, &t).iter().zip(&current)) {
+                row[k] = (moved - base) / STEP;
+            }
+        }
+        let mut jtj = SMatrix::<f64, 5, 5>::zeros();
+        let mut jtr = SMatrix::<f64, 5, 1>::zeros();
+        for (row, r) in jacobian.iter().zip(&current) {
+            let j = SMatrix::<f64, 5, 1>::from_column_slice(row);
+            jtj += j * j.transpose();
+            jtr += j * *r;
+        }
+
+        let mut improved = false;
+        while lambda < 1e10 {
+            let mut damped = jtj;
+            for k in 0..5 {
+                damped[(k, k)] += lambda * jtj[(k, k)].max(1e-12);
+            }
+            let Some(delta) = damped.cholesky().map(|c| c.solve(&-jtr)) else {
+                lambda *= 10.0;
+                continue;
+            };
+            let (r, t) = perturb(&rotation, &translation, &delta);
+            let candidate = residuals(&r, &t);
+            let candidate_cost: f64 = candidate.iter().map(|r| r * r).sum();
+            if candidate_cost < cost {
+                (rotation, translation, current, cost) = (r, t, candidate, candidate_cost);
+                lambda = (lambda / 10.0).max(1e-9);
+                improved = true;
+                break;
+            }
+            lambda *= 10.0;
+        }
+        if !improved {
+            break;
+        }
+    }
+    (rotation, translation)
+}
+
+/// Pick the decomposition of `essential` with the most inliers triangulated in front
+/// of both cameras; returns it with the inliers that pass that check
+fn recover_pose(
+    essential: &Matrix3<f64>,
+    points1: &[Vector2<f64>],
+    points2: &[Vector2<f64>],
+    inliers: &[bool],
+) -> Option<(Rotation3<f64>, Vector3<f64>, Vec<bool>)> {
+    let svd = essential.svd(true, true);
+    let (mut u, mut v_t) = (svd.u?, svd.v_t?);
+    if u.determinant() < 0.0 {
+        u = -u;
+    }
+    if v_t.determinant() < 0.0 {
+        v_t = -v_t;
+    }
+    let w = Matrix3::new(0.0, -1.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0);
+    let t = u.column(2).into_owned();
+
+    let mut best: Option<(Rotation3<f64>, Vector3<f64>, Vec<bool>)> = None;
+    let mut best_count = 0;
+    for rotation in [u * w * v_t, u * w.transpose() * v_t] {
+        for translation in [t, -t] {
+            let in_front: Vec<bool> = (0..inliers.len())
+                .map(|i| inliers[i] && triangulate(&rotation, &translation, &points1[i], &points2[i]).is_some())
+                .collect();
+            let count = in_front.iter().filter(|&&b| b).count();
+            if best.is_none() || count > best_count {
+                let rotation = Rotation3::from_matrix_unchecked(rotation);
+                best = Some((rotation, translation, in_front));
+                best_count = count;
+            }
+        }
+    }
+    best
+}
+
+/// Midpoint triangulation of a correspondence in first-camera coordinates;
+/// None unless the point lies in front of both cameras
+pub fn triangulate(
+    rotation: &Matrix3<f64>,
+    translation: &Vector3<f64>,
+    p1: &Vector2<f64>,
+    p2: &Vector2<f64>,
+) -> Option<Vector3<f64>> {
+    // Rays: X = a * d1 (camera 1) and X = c2 + b * d2 with camera 2 centre c2 = -R^T t
+    let d1 = p1.push(1.0);
+    let d2 = rotation.transpose() * p2.push(1.0);
+    let c2 = -(rotation.transpose() * translation);
+
+    let (aa, ab, bb) = (d1.dot(&d1), d1.dot(&d2), d2.dot(&d2));
+    let denominator = aa * bb - ab * ab;
+    if denominator.abs() < 1e-12 {
+        return None; // Parallel rays: no depth
+    }
+    let (e, f) = (d1.dot(&c2), d2.dot(&c2));
+    let a = (e * bb - f * ab) / denominator;
+    let b = (e * ab - f * aa) / denominator;
+    let point = (d1 * a + c2 + d2 * b) / 2.0;
+
+    let depth2 = (rotation * point + translation).z;
+    (a > 0.0 && b > 0.0 && point.z > 0.0 && depth2 > 0.0).then_some(point)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    #[test]
+    fn test_recovers_motion_with_outliers() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let rotation = UnitQuaternion::from_euler_angles(0.02, -0.08, 0.01);
+        let translation = Vector3::new(0.3, 0.05, -0.1);
+
+        let (mut points1, mut points2) = (Vec::new(), Vec::new());
+        for i in 0..150 {
+            let x = Vector3::new(rng.gen_range(-2.0..2.0), rng.gen_range(-1.5..1.5), rng.gen_range(2.0..8.0));
+            let y = rotation * x + translation;
+            let noise = |rng: &mut StdRng| Vector2::new(rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0)) / 500.0;
+            points1.push(Vector2::new(x.x / x.z, x.y / x.z) + noise(&mut rng));
+            if i % 5 == 0 {
+                // 20% gross mismatches
+                points2.push(Vector2::new(rng.gen_range(-0.5..0.5), rng.gen_range(-0.4..0.4)));
+            } else {
+                points2.push(Vector2::new(y.x / y.z, y.y / y.z) + noise(&mut rng));
+            }
+        }
+
+        let pose = estimate_relative_pose(&points1, &points2, &RansacConfig::default()).unwrap();
+        assert!(pose.rotation.angle_to(&rotation).to_degrees() < 0.5);
+        assert!(pose.translation.angle(&translation.normalize()).to_degrees() < 3.0);
+        assert!(pose.inlier_count >= 110);
+        assert!((0..150).step_by(5).filter(|&i| pose.inliers[i]).count() <= 3);
+    }
+
+    #[test]
+    fn test_triangulation_cheirality() {
+        let rotation = Matrix3::identity();
+        let translation = Vector3::new(-1.0, 0.0, 0.0);
+        let point = Vector3::new(0.5, 0.2, 4.0);
+        let project = |x: Vector3<f64>| Vector2::new(x.x / x.z, x.y / x.z);
+
+        let p2 = project(point + translation);
+        let recovered = triangulate(&rotation, &translation, &project(point), &p2).unwrap();
+        assert!((recovered - point).norm() < 1e-9);
+        // The same rays with the baseline flipped meet behind the cameras
+        assert!(triangulate(&rotation, &-translation, &project(point), &p2).is_none());
+    }
+}
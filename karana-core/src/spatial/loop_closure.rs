@@ -0,0 +1,360 @@
+//! Loop Closure
+//!
+//! Corrects the drift that accumulates while a SLAM session walks away from its
+//! origin. Every keyframe becomes a pose-graph node, linked to the previous one
+//! by its tracked motion. Place recognition (`place_recognition`) proposes older
+//! keyframes that look like the newest one; a candidate is accepted once enough
+//! of their triangulated points agree on a rigid transform. That transform
+//! becomes a loop edge, and the pose graph (`pose_graph`) is optimized so the
+//! trajectory closes.
+
+use nalgebra::{Isometry3, Matrix3, Point3, Translation3, UnitQuaternion, Vector3};
+use rand::rngs::StdRng;
+use rand::seq::index::sample;
+use rand::SeedableRng;
+
+use super::orb::match_descriptors;
+use super::place_recognition::{PlaceIndex, Vocabulary, VocabularyConfig};
+use super::pose_graph::{EdgeKind, PoseGraph, PoseGraphConfig};
+use super::slam::{CameraPose, FeatureDescriptor, Keyframe};
+use super::world_coords::LocalCoord;
+
+/// Loop detection and correction settings
+#[derive(Debug, Clone)]
+pub struct LoopClosureConfig {
+    /// Vocabulary tree shape
+    pub vocabulary: VocabularyConfig,
+    /// Keyframes whose descriptors train the vocabulary
+    pub training_keyframes: usize,
+    /// Keyframes this recent are never loop candidates
+    pub min_keyframe_gap: u64,
+    /// Minimum bag-of-words similarity for a candidate
+    pub min_score: f32,
+    /// Candidates geometrically verified per keyframe
+    pub max_candidates: usize,
+    /// Max Hamming distance for two descriptors to match
+    pub max_descriptor_distance: u32,
+    /// Nearest match must beat the second nearest by this ratio
+    pub match_ratio: f32,
+    /// Points that must agree on the loop transform
+    pub min_inliers: usize,
+    /// Inlier distance after alignment (meters)
+    pub inlier_threshold: f64,
+    /// RANSAC iterations for the loop transform
+    pub ransac_iterations: usize,
+    /// RNG seed, so runs are reproducible
+    pub seed: u64,
+    /// Pose graph optimizer settings
+    pub optimizer: PoseGraphConfig,
+}
+
+impl Default for LoopClosureConfig {
+    fn default() -> Self {
+        Self {
+            vocabulary: VocabularyConfig::default(),
+            training_keyframes: 5,
+            min_keyframe_gap: 15,
+            min_score: 0.1,
+            max_candidates: 3,
+            max_descriptor_distance: 64,
+            match_ratio: 0.8,
+            min_inliers: 20,
+            inlier_threshold: 0.1,
+            ransac_iterations: 200,
+            seed: 0x100B,
+            optimizer: PoseGraphConfig::default(),
+        }
+    }
+}
+
+/// An accepted loop
+#[derive(Debug, Clone)]
+pub struct LoopClosure {
+    /// Newest keyframe
+    pub keyframe_id: u64,
+    /// Older keyframe of the same place
+    pub matched_keyframe_id: u64,
+    /// Bag-of-words similarity
+    pub score: f32,
+    /// Points agreeing on the relative pose
+    pub inliers: usize,
+    /// Pose of the newest keyframe in the frame of the matched one
+    pub relative_pose: Isometry3<f64>,
+    /// Correction applied to the newest keyframe (new pose * old pose^-1)
+    pub correction: Isometry3<f64>,
+}
+
+/// Detects loops over a session's keyframes and closes them
+#[derive(Debug, Clone)]
+pub struct LoopCloser {
+    config: LoopClosureConfig,
+    /// `None` until enough keyframes have been seen to train the vocabulary
+    index: Option<PlaceIndex>,
+    /// Descriptors of keyframes seen before the vocabulary was trained
+    untrained: Vec<(u64, Vec<FeatureDescriptor>)>,
+    /// One node per keyframe, in keyframe order
+    graph: PoseGraph,
+    closures: Vec<LoopClosure>,
+}
+
+impl LoopCloser {
+    pub fn new(config: LoopClosureConfig) -> Self {
+        Self {
+            config,
+            index: None,
+            untrained: Vec::new(),
+            graph: PoseGraph::new(),
+            closures: Vec::new(),
+        }
+    }
+
+    /// Loops closed so far
+    pub fn closures(&self) -> &[LoopClosure] {
+        &self.closures
+    }
+
+    /// The pose graph over the keyframes
+    pub fn graph(&self) -> &PoseGraph {
+        &self.graph
+    }
+
+    /// Forget every keyframe and loop
+    pub fn reset(&mut self) {
+        self.index = None;
+        self.untrained.clear();
+        self.graph.clear();
+        self.closures.clear();
+    }
+
+    /// Register keyframes added since the last call, then look for a loop from
+    /// the newest one. When a loop is found the graph is optimized and the
+    /// corrected poses (and triangulated feature positions) are written back.
+    pub fn process(&mut self, keyframes: &mut [Keyframe]) -> Option<LoopClosure> {
+        if self.graph.len() > keyframes.len() {
+            self.reset();
+        }
+        if self.graph.len() == keyframes.len() {
+            return None;
+        }
+
+        let newest = keyframes.len() - 1;
+        let detection = self.detect(keyframes);
+
+        for i in self.graph.len()..keyframes.len() {
+            self.register(&keyframes[i], i.checked_sub(1).map(|p| &keyframes[p]));
+        }
+
+        let (matched, score, relative_pose, inliers) = detection?;
+        self.graph.add_edge(matched, newest, relative_pose, EdgeKind::Loop);
+
+        let before: Vec<Isometry3<f64>> = self.graph.nodes().to_vec();
+        self.graph.optimize(&self.config.optimizer);
+        for ((keyframe, old), new) in keyframes.iter_mut().zip(&before).zip(self.graph.nodes()) {
+            apply_correction(keyframe, &(new * old.inverse()));
+        }
+
+        let closure = LoopClosure {
+            keyframe_id: keyframes[newest].id,
+            matched_keyframe_id: keyframes[matched].id,
+            score,
+            inliers,
+            relative_pose,
+            correction: self.graph.nodes()[newest] * before[newest].inverse(),
+        };
+        log::info!(
+            "[SLAM] Loop closed: keyframe {} -> {} ({} inliers, score {:.2})",
+            closure.keyframe_id, closure.matched_keyframe_id, inliers, score
+        );
+        self.closures.push(closure.clone());
+        Some(closure)
+    }
+
+    /// Add a keyframe to the graph and the place index
+    fn register(&mut self, keyframe: &Keyframe, previous: Option<&Keyframe>) {
+        let pose = keyframe.pose.to_isometry();
+        let node = self.graph.add_node(pose);
+        if let Some(previous) = previous {
+            let odometry = previous.pose.to_isometry().inverse() * pose;
+            self.graph.add_edge(node - 1, node, odometry, EdgeKind::Odometry);
+        }
+
+        let descriptors = descriptors(keyframe);
+        match self.index.as_mut() {
+            Some(index) => index.add(keyframe.id, &descriptors),
+            None => {
+                self.untrained.push((keyframe.id, descriptors));
+                if self.untrained.len() >= self.config.training_keyframes {
+                    self.train();
+                }
+            }
+        }
+    }
+
+    fn train(&mut self) {
+        let training: Vec<FeatureDescriptor> = self.untrained.iter().flat_map(|(_, d)| d.iter().cloned()).collect();
+        let mut index = PlaceIndex::new(Vocabulary::train(&training, &self.config.vocabulary));
+        for (id, descriptors) in self.untrained.drain(..) {
+            index.add(id, &descriptors);
+        }
+        log::debug!("[SLAM] Place vocabulary trained: {} words", index.vocabulary().word_count());
+        self.index = Some(index);
+    }
+
+    /// Best verified loop candidate for the newest keyframe:
+    /// (node, score, relative pose, inliers)
+    fn detect(&self, keyframes: &[Keyframe]) -> Option<(usize, f32, Isometry3<f64>, usize)> {
+        let index = self.index.as_ref()?;
+        let newest = keyframes.last()?;
+        let gap = self.config.min_keyframe_gap;
+        let candidates = index.query(
+            &descriptors(newest),
+            self.config.min_score,
+            self.config.max_candidates,
+            |id| id + gap > newest.id,
+        );
+
+        candidates.into_iter().find_map(|candidate| {
+            // Keyframe IDs are handed out in order
+            let node = keyframes.binary_search_by_key(&candidate.keyframe_id, |k| k.id).ok()?;
+            let (relative_pose, inliers) = relative_pose(&keyframes[node], newest, &self.config)?;
+            Some((node, candidate.score, relative_pose, inliers))
+        })
+    }
+}
+
+/// Pose of `query` in the camera frame of `candidate`, and the number of points
+/// agreeing on it, if enough do. Points are compared in each keyframe's own
+/// camera frame, so the drift between the two poses (or the two keyframes
+/// coming from different maps) does not matter.
+pub(crate) fn relative_pose(
+    candidate: &Keyframe,
+    query: &Keyframe,
+    config: &LoopClosureConfig,
+) -> Option<(Isometry3<f64>, usize)> {
+    let (candidate_points, candidate_descriptors) = camera_points(candidate);
+    let (query_points, query_descriptors) = camera_points(query);
+    let matches = match_descriptors(
+        &query_descriptors,
+        &candidate_descriptors,
+        config.max_descriptor_distance,
+        config.match_ratio,
+    );
+    if matches.len() < config.min_inliers {
+        return None;
+    }
+
+    let from: Vec<Vector3<f64>> = matches.iter().map(|m| query_points[m.query]).collect();
+    let to: Vec<Vector3<f64>> = matches.iter().map(|m| candidate_points[m.train]).collect();
+    let threshold = config.inlier_threshold;
+    let inliers_of = |transform: &Isometry3<f64>| -> Vec<usize> {
+        (0..from.len())
+            .filter(|&i| (transform * Point3::from(from[i]) - Point3::from(to[i])).norm() < threshold)
+            .collect()
+    };
+
+    let mut rng = StdRng::seed_from_u64(config.seed);
+    let mut best: Vec<usize> = Vec::new();
+    for _ in 0..config.ransac_iterations {
+        let indices = sample(&mut rng, from.len(), 3).into_vec();
+        let Some(transform) = align_points(&from, &to, &indices) else {
+            continue;
+        };
+        let inliers = inliers_of(&transform);
+        if inliers.len() > best.len() {
+            best = inliers;
+        }
+    }
+    if best.len() < config.min_inliers {
+        return None;
+    }
+
+    let transform = align_points(&from, &to, &best)?;
+    let inliers = inliers_of(&transform).len();
+    (inliers >= config.min_inliers).then_some((transform, inliers))
+}
+
+/// Triangulated features of a keyframe in its camera frame, with their descriptors
+fn camera_points(keyframe: &Keyframe) -> (Vec<Vector3<f64>>, Vec<FeatureDescriptor>) {
+    let world_to_camera = keyframe.pose.to_isometry().inverse();
+    keyframe
+        .features
+        .iter()
+        .filter_map(|f| {
+            let p = f.world_pos?;
+            let local = world_to_camera * Point3::new(p.x as f64, p.y as f64, p.z as f64);
+            Some((local.coords, f.descriptor.clone()))
+        })
+        .unzip()
+}
+
+/// Rigid transform taking `from[i]` onto `to[i]` over `indices` (Kabsch)
+fn align_points(from: &[Vector3<f64>], to: &[Vector3<f64>], indices: &[usize]) -> Option<Isometry3<f64>> {
+    if indices.len() < 3 {
+        return None;
+    }
+    let n = indices.len() as f64;
+    let from_centre = indices.iter().map(|&i| from[i]).sum::<Vector3<f64>>() / n;
+    let to_centre = indices.iter().map(|&i| to[i]).sum::<Vector3<f64>>() / n;
+
+    let covariance: Matrix3<f64> = indices
+        .iter()
+        .map(|&i| (from[i] - from_centre) * (to[i] - to_centre).transpose())
+        .sum();
+    let svd = covariance.svd(true, true);
+    let (u, v_t) = (svd.u?, svd.v_t?);
+    // Degenerate (collinear) samples leave the rotation undetermined
+    if svd.singular_values[1] < 1e-9 {
+        return None;
+    }
+
+    let mut correction = Matrix3::identity();
+    if (v_t.transpose() * u.transpose()).determinant() < 0.0 {
+        correction[(2, 2)] = -1.0;
+    }
+    let rotation = v_t.transpose() * correction * u.transpose();
+    let rotation = UnitQuaternion::from_matrix(&rotation);
+    let translation = to_centre - rotation * from_centre;
+    Some(Isometry3::from_parts(Translation3::from(translation), rotation))
+}
+
+/// Descriptors of a keyframe's features
+fn descriptors(keyframe: &Keyframe) -> Vec<FeatureDescriptor> {
+    keyframe.features.iter().map(|f| f.descriptor.clone()).collect()
+}
+
+/// Move a keyframe and its triangulated features by a world-frame correction
+fn apply_correction(keyframe: &mut Keyframe, correction: &Isometry3<f64>) {
+    keyframe.pose = CameraPose::from_isometry(&(correction * keyframe.pose.to_isometry()));
+    for feature in &mut keyframe.features {
+        if let Some(p) = feature.world_pos.as_mut() {
+            let moved = correction * Point3::new(p.x as f64, p.y as f64, p.z as f64);
+            *p = LocalCoord::new(moved.x as f32, moved.y as f32, moved.z as f32);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    #[test]
+    fn test_align_points_recovers_transform() {
+        let mut rng = StdRng::seed_from_u64(5);
+        let truth = Isometry3::new(Vector3::new(0.4, -0.1, 1.2), Vector3::new(0.1, 0.6, -0.2));
+        let from: Vec<Vector3<f64>> = (0..30)
+            .map(|_| Vector3::new(rng.gen_range(-3.0..3.0), rng.gen_range(-1.0..2.0), rng.gen_range(1.0..6.0)))
+            .collect();
+        let to: Vec<Vector3<f64>> = from.iter().map(|p| (truth * Point3::from(*p)).coords).collect();
+
+        let all: Vec<usize> = (0..from.len()).collect();
+        let estimate = align_points(&from, &to, &all).unwrap();
+
+        assert!((estimate.translation.vector - truth.translation.vector).norm() < 1e-9);
+        assert!(estimate.rotation.angle_to(&truth.rotation) < 1e-9);
+
+        // Three points on a line do not fix the rotation
+        let line: Vec<Vector3<f64>> = (0..3).map(|i| Vector3::x() * i as f64).collect();
+        assert!(align_points(&line, &line, &[0, 1, 2]).is_none());
+    }
+}
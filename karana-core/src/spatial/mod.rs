@@ -29,20 +29,30 @@
 //! ## Key Concepts
 //!
 //! - **SpatialAnchor**: A point in the real world where AR content is pinned
+//! - **WorldAnchorService**: The one owner of anchors; AR, scene and WebXR
+//!   anchors are views onto it
 //! - **WorldPosition**: Combines GPS (outdoor) and SLAM (indoor) coordinates
 //! - **ReferenceFrame**: Visual signature of a location for relocalization
 //! - **Relocalization**: Finding anchors when returning to a location
 
 pub mod anchor;
+pub mod anchor_service;
 pub mod world_coords;
 pub mod slam;
 pub mod relocalize;
 pub mod persistence;
+pub mod orb;
+pub mod epipolar;
+pub mod place_recognition;
+pub mod pose_graph;
+pub mod loop_closure;
+pub mod room_map;
 
 pub use anchor::{
     SpatialAnchor, AnchorId, AnchorState, AnchorContent,
     SpatialAnchorRegistry, CreateAnchorRequest, Quaternion,
 };
+pub use anchor_service::WorldAnchorService;
 pub use world_coords::{
     WorldPosition, GpsCoord, LocalCoord, ReferenceFrame,
     CoordinateFusion, FusionConfig, RoomId, CoordinateTransform,
@@ -50,12 +60,18 @@ pub use world_coords::{
 pub use slam::{
     SlamEngine, SlamConfig, SlamState, Pose, KeyFrame,
     VisualFeatures, SlamMap, VisualFeature, CameraPose,
-    SlamSession, SlamManager, FeatureDescriptor,
+    SlamSession, SlamManager, FeatureDescriptor, AnchorCorrection,
 };
 pub use relocalize::{
     RelocalizeEngine, RelocatedAnchor, RelocalizeResult,
     RelocalizeConfig,
 };
+pub use orb::{OrbExtractor, OrbConfig, OrbFeatures, Keypoint, GrayImage, DescriptorMatch, match_descriptors};
+pub use epipolar::{RelativePose, RansacConfig, estimate_relative_pose};
+pub use place_recognition::{Vocabulary, VocabularyConfig, PlaceIndex, PlaceMatch};
+pub use pose_graph::{PoseGraph, PoseGraphConfig, PoseEdge, EdgeKind};
+pub use loop_closure::{LoopCloser, LoopClosure, LoopClosureConfig};
+pub use room_map::{RoomMap, MapKeyframe, MapFeature, MapAnchor, RoomAlignment};
 pub use persistence::{
     AnchorStore, AnchorProof, StoredAnchor,
     AnchorIntegrityProof, PersistenceMode, SyncStatus,
@@ -71,16 +87,14 @@ use tokio::sync::RwLock;
 
 /// Main manager for the spatial persistence system
 pub struct SpatialSystem {
-    /// Anchor registry
-    pub anchors: Arc<RwLock<SpatialAnchorRegistry>>,
+    /// World-anchor service (registry and persistent store)
+    pub anchors: WorldAnchorService,
     /// SLAM engine for tracking
     pub slam: Arc<RwLock<SlamEngine>>,
     /// Coordinate fusion
     pub fusion: Arc<RwLock<CoordinateFusion>>,
     /// Relocalization engine
     pub relocalize: Arc<RwLock<RelocalizeEngine>>,
-    /// Persistent anchor store
-    pub store: Arc<RwLock<AnchorStore>>,
     /// Configuration
     config: SpatialConfig,
 }
@@ -116,15 +130,19 @@ impl SpatialSystem {
     /// Create a new spatial system
     pub fn new(config: SpatialConfig) -> Self {
         Self {
-            anchors: Arc::new(RwLock::new(SpatialAnchorRegistry::new())),
+            anchors: WorldAnchorService::open(&config.persistence_path),
             slam: Arc::new(RwLock::new(SlamEngine::new(SlamConfig::default()))),
             fusion: Arc::new(RwLock::new(CoordinateFusion::new(FusionConfig::default()))),
             relocalize: Arc::new(RwLock::new(RelocalizeEngine::new(RelocalizeConfig::default()))),
-            store: Arc::new(RwLock::new(AnchorStore::new(&config.persistence_path))),
             config,
         }
     }
     
+    /// Handle to the anchor service, for the AR, scene and WebXR adapters
+    pub fn anchor_service(&self) -> WorldAnchorService {
+        self.anchors.clone()
+    }
+    
     /// Create a new anchor at the current position
     pub async fn create_anchor(&self, content: AnchorContent) -> Result<SpatialAnchor> {
         // Get current position from SLAM
@@ -139,20 +157,17 @@ impl SpatialSystem {
             fusion.fuse_position(&pose)
         };
         
-        // Create anchor
-        let anchor = {
-            let mut registry = self.anchors.write().await;
-            registry.create(CreateAnchorRequest {
-                position,
-                content,
-                ..Default::default()
-            })?
+        // Create and store persistently
+        let request = CreateAnchorRequest {
+            position,
+            content,
+            ..Default::default()
         };
+        let anchor = self.anchors.create(request, true)?;
         
-        // Store persistently
-        {
-            let mut store = self.store.write().await;
-            store.save_async(&anchor).await?;
+        // Move the anchor along when loop closures correct the map
+        if let Err(e) = self.slam.write().await.attach_anchor(&anchor) {
+            log::debug!("[SPATIAL] Anchor {} not attached to the SLAM map: {}", anchor.id, e);
         }
         
         log::info!("[SPATIAL] Created anchor {} at {:?}", anchor.id, anchor.position);
@@ -161,34 +176,35 @@ impl SpatialSystem {
     
     /// Get all anchors at a location
     pub async fn get_anchors_at(&self, position: &WorldPosition, radius_m: f32) -> Vec<SpatialAnchor> {
-        let registry = self.anchors.read().await;
-        registry.find_nearby(position, radius_m)
+        self.anchors.find_nearby(position, radius_m)
     }
     
     /// Update SLAM with a new camera frame
     pub async fn process_frame(&self, frame: &CameraFrame) -> Result<Pose> {
         let mut slam = self.slam.write().await;
-        slam.track(frame)
+        let pose = slam.track(frame)?;
+        
+        // Loop closures move attached anchors; hand the new poses to the service
+        match slam.apply_anchor_corrections(&self.anchors) {
+            Ok(0) => {}
+            Ok(moved) => log::info!("[SPATIAL] Loop closure moved {} anchors", moved),
+            Err(e) => log::warn!("[SPATIAL] Failed to apply anchor corrections: {}", e),
+        }
+        Ok(pose)
     }
     
     /// Try to relocalize (find known anchors in current view)
     pub async fn relocalize(&self, frame: &CameraFrame) -> Result<RelocalizeResult> {
         // Get all stored anchors
-        let stored = {
-            let store = self.store.read().await;
-            store.get_all().await?
-        };
+        let stored = self.anchors.persisted();
         
         // Try to match current view with known anchors
         let mut relocalize = self.relocalize.write().await;
         let result = relocalize.attempt(frame, &stored).await?;
         
         // Update registry with relocated anchors
-        if !result.matched_anchors.is_empty() {
-            let mut registry = self.anchors.write().await;
-            for anchor in &result.matched_anchors {
-                registry.update_confidence(anchor.id, anchor.confidence);
-            }
+        for anchor in &result.matched_anchors {
+            self.anchors.update_confidence(anchor.id, anchor.confidence);
         }
         
         log::info!("[SPATIAL] Relocalized {} anchors", result.matched_anchors.len());
@@ -206,14 +222,8 @@ impl SpatialSystem {
     
     /// Delete an anchor
     pub async fn delete_anchor(&self, id: AnchorId) -> Result<()> {
-        {
-            let mut registry = self.anchors.write().await;
-            registry.remove(id)?;
-        }
-        {
-            let mut store = self.store.write().await;
-            store.delete_async(id).await?;
-        }
+        self.anchors.remove(id)?;
+        self.slam.write().await.detach_anchor(id);
         log::info!("[SPATIAL] Deleted anchor {}", id);
         Ok(())
     }
@@ -247,6 +257,13 @@ pub struct CameraIntrinsics {
     pub cy: f32,
 }
 
+impl CameraIntrinsics {
+    /// Pixel coordinates to normalized image coordinates
+    pub fn normalize(&self, x: f32, y: f32) -> nalgebra::Vector2<f64> {
+        nalgebra::Vector2::new(((x - self.cx) / self.fx) as f64, ((y - self.cy) / self.fy) as f64)
+    }
+}
+
 impl Default for CameraIntrinsics {
     fn default() -> Self {
         // Typical smartphone/glasses camera
@@ -267,9 +284,20 @@ impl Default for CameraIntrinsics {
 mod tests {
     use super::*;
     
+    /// Config with a fresh store so tests don't see each other's anchors
+    fn test_config(name: &str) -> SpatialConfig {
+        let path = std::env::temp_dir()
+            .join(format!("karana_spatial_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        SpatialConfig {
+            persistence_path: path.to_string_lossy().into_owned(),
+            ..Default::default()
+        }
+    }
+    
     #[tokio::test]
     async fn test_spatial_system_creation() {
-        let system = SpatialSystem::new(SpatialConfig::default());
+        let system = SpatialSystem::new(test_config("creation"));
         let position = system.get_current_position().await;
         
         // Default position should be origin
@@ -280,7 +308,7 @@ mod tests {
     
     #[tokio::test]
     async fn test_anchor_creation() {
-        let system = SpatialSystem::new(SpatialConfig::default());
+        let system = SpatialSystem::new(test_config("anchor"));
         
         let content = AnchorContent::Text {
             text: "Test Note".to_string(),
@@ -294,7 +322,7 @@ mod tests {
     
     #[tokio::test]
     async fn test_find_nearby_anchors() {
-        let system = SpatialSystem::new(SpatialConfig::default());
+        let system = SpatialSystem::new(test_config("nearby"));
         
         // Create a few anchors
         for i in 0..3 {
@@ -310,4 +338,26 @@ mod tests {
         
         assert_eq!(nearby.len(), 3);
     }
+    
+    #[tokio::test]
+    async fn test_anchors_survive_restart() {
+        let config = test_config("restart");
+        
+        let anchor = {
+            let system = SpatialSystem::new(config.clone());
+            let content = AnchorContent::Text { text: "Keys".to_string() };
+            system.create_anchor(content).await.unwrap()
+        };
+        
+        let system = SpatialSystem::new(config.clone());
+        let nearby = system.get_anchors_at(&WorldPosition::default(), 1.0).await;
+        assert_eq!(nearby.len(), 1);
+        assert_eq!(nearby[0].id, anchor.id);
+        
+        system.delete_anchor(anchor.id).await.unwrap();
+        let system = SpatialSystem::new(config.clone());
+        assert!(system.get_anchors_at(&WorldPosition::default(), 1.0).await.is_empty());
+        
+        let _ = std::fs::remove_dir_all(&config.persistence_path);
+    }
 }
@@ -0,0 +1,566 @@
+//! ORB Feature Extraction
+//!
+//! FAST-9 corners over an image pyramid, ranked by Harris response, spread
+//! across the image and refined to sub-pixel accuracy, oriented by intensity centroid and described with rotated
+//! BRIEF (256 binary intensity tests on a smoothed 31x31 patch). Descriptors are
+//! matched by Hamming distance with Lowe's ratio test.
+
+use anyhow::{anyhow, Result};
+
+use super::slam::FeatureDescriptor;
+use super::CameraFrame;
+
+/// Contiguous circle pixels that must all be brighter or darker
+const FAST_ARC: u32 = 9;
+/// Radius of the orientation patch
+const PATCH_RADIUS: i32 = 15;
+/// BRIEF test coordinates lie within +/- this of the keypoint (before rotation)
+const PATTERN_RADIUS: i32 = 13;
+/// Keypoints closer than this to the border cannot be described
+const EDGE: usize = 20;
+/// Grid cell size used to spread keypoints over the image (pixels)
+const CELL_SIZE: usize = 32;
+/// Half-size of the window used for sub-pixel corner refinement
+const REFINE_RADIUS: i32 = 4;
+
+/// FAST circle offsets, clockwise from the top
+const CIRCLE: [(i32, i32); 16] = [
+    (0, -3), (1, -3), (2, -2), (3, -1), (3, 0), (3, 1), (2, 2), (1, 3),
+    (0, 3), (-1, 3), (-2, 2), (-3, 1), (-3, 0), (-3, -1), (-2, -2), (-1, -3),
+];
+
+/// 8-bit grayscale image
+#[derive(Debug, Clone)]
+pub struct GrayImage {
+    pub width: usize,
+    pub height: usize,
+    pub data: Vec<u8>,
+}
+
+impl GrayImage {
+    pub fn new(width: usize, height: usize, data: Vec<u8>) -> Result<Self> {
+        if data.len() != width * height {
+            return Err(anyhow!("Expected {}x{} pixels, got {} bytes", width, height, data.len()));
+        }
+        Ok(Self { width, height, data })
+    }
+
+    /// Grayscale view of a camera frame (gray, RGB or RGBA data)
+    pub fn from_frame(frame: &CameraFrame) -> Result<Self> {
+        let (width, height) = (frame.width as usize, frame.height as usize);
+        let pixels = width * height;
+        let channels = frame.data.len().checked_div(pixels).unwrap_or(0);
+        if pixels == 0 || frame.data.len() != pixels * channels {
+            return Err(anyhow!("Frame data does not match {}x{}", width, height));
+        }
+
+        let data = match channels {
+            1 => frame.data.clone(),
+            3 | 4 => frame.data
+                .chunks_exact(channels)
+                .map(|px| ((77 * px[0] as u32 + 150 * px[1] as u32 + 29 * px[2] as u32) >> 8) as u8)
+                .collect(),
+            _ => return Err(anyhow!("Unsupported frame format: {} bytes per pixel", channels)),
+        };
+        Ok(Self { width, height, data })
+    }
+
+    #[inline]
+    fn at(&self, x: usize, y: usize) -> u8 {
+        self.data[y * self.width + x]
+    }
+
+    #[inline]
+    fn at_offset(&self, x: usize, y: usize, dx: i32, dy: i32) -> i32 {
+        self.at((x as i32 + dx) as usize, (y as i32 + dy) as usize) as i32
+    }
+
+    /// Bilinear downscale by `factor` (> 1)
+    fn downscale(&self, factor: f32) -> Self {
+        let width = (self.width as f32 / factor).round() as usize;
+        let height = (self.height as f32 / factor).round() as usize;
+        let mut data = Vec::with_capacity(width * height);
+        for y in 0..height {
+            let sy = ((y as f32 + 0.5) * factor - 0.5).clamp(0.0, (self.height - 1) as f32);
+            let (y0, fy) = (sy.floor() as usize, sy.fract());
+            let y1 = (y0 + 1).min(self.height - 1);
+            for x in 0..width {
+                let sx = ((x as f32 + 0.5) * factor - 0.5).clamp(0.0, (self.width - 1) as f32);
+                let (x0, fx) = (sx.floor() as usize, sx.fract());
+                let x1 = (x0 + 1).min(self.width - 1);
+                let top = self.at(x0, y0) as f32 * (1.0 - fx) + self.at(x1, y0) as f32 * fx;
+                let bottom = self.at(x0, y1) as f32 * (1.0 - fx) + self.at(x1, y1) as f32 * fx;
+                data.push((top * (1.0 - fy) + bottom * fy).round() as u8);
+            }
+        }
+        Self { width, height, data }
+    }
+
+    /// 7-tap Gaussian blur (sigma 2), as applied before the BRIEF tests
+    fn blur(&self) -> Self {
+        const KERNEL: [f32; 7] = [0.0702, 0.1311, 0.1907, 0.2160, 0.1907, 0.1311, 0.0702];
+        let (w, h) = (self.width as i32, self.height as i32);
+        let mut horizontal = vec![0.0f32; self.data.len()];
+        for y in 0..h {
+            for x in 0..w {
+                horizontal[(y * w + x) as usize] = KERNEL.iter().enumerate()
+                    .map(|(k, weight)| {
+                        let sx = (x + k as i32 - 3).clamp(0, w - 1);
+                        weight * self.data[(y * w + sx) as usize] as f32
+                    })
+                    .sum();
+            }
+        }
+        let mut data = vec![0u8; self.data.len()];
+        for y in 0..h {
+            for x in 0..w {
+                let value: f32 = KERNEL.iter().enumerate()
+                    .map(|(k, weight)| {
+                        let sy = (y + k as i32 - 3).clamp(0, h - 1);
+                        weight * horizontal[(sy * w + x) as usize]
+                    })
+                    .sum();
+                data[(y * w + x) as usize] = value.round().clamp(0.0, 255.0) as u8;
+            }
+        }
+        Self { width: self.width, height: self.height, data }
+    }
+}
+
+/// ORB extractor settings
+#[derive(Debug, Clone)]
+pub struct OrbConfig {
+    /// Keypoints to keep over all pyramid levels
+    pub max_features: usize,
+    /// Pyramid levels
+    pub levels: usize,
+    /// Scale between pyramid levels
+    pub scale_factor: f32,
+    /// Intensity difference for a FAST circle pixel to count as brighter/darker
+    pub fast_threshold: u8,
+}
+
+impl Default for OrbConfig {
+    fn default() -> Self {
+        Self {
+            max_features: 500,
+            levels: 4,
+            scale_factor: 1.2,
+            fast_threshold: 20,
+        }
+    }
+}
+
+/// Detected keypoint, in level-0 pixel coordinates
+#[derive(Debug, Clone, Copy)]
+pub struct Keypoint {
+    pub x: f32,
+    pub y: f32,
+    /// Pyramid level it was detected on
+    pub level: usize,
+    /// Orientation (radians)
+    pub angle: f32,
+    /// Harris corner response
+    pub response: f32,
+}
+
+/// Keypoints and their descriptors (same order)
+#[derive(Debug, Clone, Default)]
+pub struct OrbFeatures {
+    pub keypoints: Vec<Keypoint>,
+    pub descriptors: Vec<FeatureDescriptor>,
+}
+
+/// Match between a query and a train descriptor
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DescriptorMatch {
+    pub query: usize,
+    pub train: usize,
+    pub distance: u32,
+}
+
+/// ORB keypoint detector and descriptor extractor
+pub struct OrbExtractor {
+    config: OrbConfig,
+    /// BRIEF test pairs ((x1, y1), (x2, y2)) around the keypoint
+    pattern: Vec<[(f32, f32); 2]>,
+}
+
+impl OrbExtractor {
+    pub fn new(config: OrbConfig) -> Self {
+        Self { config, pattern: brief_pattern() }
+    }
+
+    /// Detect keypoints and compute their descriptors
+    pub fn extract(&self, image: &GrayImage) -> OrbFeatures {
+        let mut features = OrbFeatures::default();
+        let levels = self.config.levels.max(1);
+        let inv_scale = 1.0 / self.config.scale_factor;
+        // Share of `max_features` for each level, proportional to its area (ORB-SLAM)
+        let first = self.config.max_features as f32 * (1.0 - inv_scale) / (1.0 - inv_scale.powi(levels as i32));
+
+        let mut level_image = image.clone();
+        for level in 0..levels {
+            if level > 0 {
+                level_image = image.downscale(self.config.scale_factor.powi(level as i32));
+            }
+            if level_image.width <= 2 * EDGE || level_image.height <= 2 * EDGE {
+                break;
+            }
+            let wanted = (first * inv_scale.powi(level as i32)).round() as usize;
+            let scale = self.config.scale_factor.powi(level as i32);
+            let smoothed = level_image.blur();
+
+            for (x, y, response) in self.detect(&level_image, wanted) {
+                let angle = orientation(&level_image, x, y);
+                let (sub_x, sub_y) = refine_corner(&level_image, x, y);
+                features.descriptors.push(self.describe(&smoothed, x, y, angle));
+                features.keypoints.push(Keypoint {
+                    x: sub_x * scale,
+                    y: sub_y * scale,
+                    level,
+                    angle,
+                    response,
+                });
+            }
+        }
+        features
+    }
+
+    /// FAST corners with non-maximum suppression, the best `wanted` by Harris
+    /// response, at most a fair share per grid cell
+    fn detect(&self, image: &GrayImage, wanted: usize) -> Vec<(usize, usize, f32)> {
+        let (w, h) = (image.width, image.height);
+        let threshold = self.config.fast_threshold as i32;
+        let mut scores = vec![0i32; w * h];
+        for y in EDGE..h - EDGE {
+            for x in EDGE..w - EDGE {
+                scores[y * w + x] = fast_score(image, x, y, threshold);
+            }
+        }
+
+        let mut corners = Vec::new();
+        for y in EDGE..h - EDGE {
+            for x in EDGE..w - EDGE {
+                let score = scores[y * w + x];
+                if score == 0 {
+                    continue;
+                }
+                let is_max = (-1i32..=1).all(|dy| (-1i32..=1).all(|dx| {
+                    let other = scores[(y as i32 + dy) as usize * w + (x as i32 + dx) as usize];
+                    // Ties go to the first pixel in scan order
+                    other < score || (other == score && (dy, dx) >= (0, 0))
+                }));
+                if is_max {
+                    corners.push((x, y, harris_response(image, x, y)));
+                }
+            }
+        }
+
+        let cols = w.div_ceil(CELL_SIZE);
+        let cells = cols * h.div_ceil(CELL_SIZE);
+        let per_cell = (2 * wanted).div_ceil(cells).max(1);
+        corners.sort_by(|a, b| b.2.total_cmp(&a.2));
+        let mut taken = vec![0usize; cells];
+        corners.retain(|&(x, y, _)| {
+            let cell = (y / CELL_SIZE) * cols + x / CELL_SIZE;
+            taken[cell] += 1;
+            taken[cell] <= per_cell
+        });
+        corners.truncate(wanted);
+        corners
+    }
+
+    /// Rotated BRIEF: bit i is set if the first point of test i is darker than the second
+    fn describe(&self, smoothed: &GrayImage, x: usize, y: usize, angle: f32) -> FeatureDescriptor {
+        let (sin, cos) = angle.sin_cos();
+        let sample = |(px, py): (f32, f32)| {
+            let rx = (px * cos - py * sin).round() as i32;
+            let ry = (px * sin + py * cos).round() as i32;
+            smoothed.at_offset(x, y, rx, ry)
+        };
+        let mut data = [0u8; 32];
+        for (i, [a, b]) in self.pattern.iter().enumerate() {
+            if sample(*a) < sample(*b) {
+                data[i / 8] |= 1 << (i % 8);
+            }
+        }
+        FeatureDescriptor { data }
+    }
+}
+
+impl Default for OrbExtractor {
+    fn default() -> Self {
+        Self::new(OrbConfig::default())
+    }
+}
+
+/// FAST-9 score: 0 if (x, y) is not a corner, else the summed contrast beyond the threshold
+fn fast_score(image: &GrayImage, x: usize, y: usize, threshold: i32) -> i32 {
+    let center = image.at(x, y) as i32;
+    // Quick rejection: a 9-arc covers at least two of the four compass points
+    let compass = [0, 4, 8, 12].map(|i| image.at_offset(x, y, CIRCLE[i].0, CIRCLE[i].1));
+    let brighter = compass.iter().filter(|&&v| v > center + threshold).count();
+    let darker = compass.iter().filter(|&&v| v < center - threshold).count();
+    if brighter < 2 && darker < 2 {
+        return 0;
+    }
+
+    let (mut bright_mask, mut dark_mask, mut score) = (0u32, 0u32, 0);
+    for (i, (dx, dy)) in CIRCLE.iter().enumerate() {
+        let diff = image.at_offset(x, y, *dx, *dy) - center;
+        if diff > threshold {
+            bright_mask |= 1 << i;
+        } else if diff < -threshold {
+            dark_mask |= 1 << i;
+        }
+        score += (diff.abs() - threshold).max(0);
+    }
+    if longest_arc(bright_mask) >= FAST_ARC || longest_arc(dark_mask) >= FAST_ARC {
+        score
+    } else {
+        0
+    }
+}
+
+/// Longest run of set bits in a 16-bit circular mask
+fn longest_arc(mask: u32) -> u32 {
+    if mask == 0xFFFF {
+        return 16;
+    }
+    let mut doubled = mask | (mask << 16);
+    let mut longest = 0;
+    while doubled != 0 {
+        doubled &= doubled << 1;
+        longest += 1;
+    }
+    longest
+}
+
+/// Sobel gradient at an offset from (x, y)
+fn sobel(image: &GrayImage, x: usize, y: usize, dx: i32, dy: i32) -> (f32, f32) {
+    let p = |ox: i32, oy: i32| image.at_offset(x, y, dx + ox, dy + oy) as f32;
+    let gx = (p(1, -1) + 2.0 * p(1, 0) + p(1, 1)) - (p(-1, -1) + 2.0 * p(-1, 0) + p(-1, 1));
+    let gy = (p(-1, 1) + 2.0 * p(0, 1) + p(1, 1)) - (p(-1, -1) + 2.0 * p(0, -1) + p(1, -1));
+    (gx, gy)
+}
+
+/// Harris corner response over a 7x7 block of Sobel gradients (k = 0.04)
+fn harris_response(image: &GrayImage, x: usize, y: usize) -> f32 {
+    let (mut xx, mut yy, mut xy) = (0.0f32, 0.0f32, 0.0f32);
+    for dy in -3..=3 {
+        for dx in -3..=3 {
+            let (gx, gy) = sobel(image, x, y, dx, dy);
+            xx += gx * gx;
+            yy += gy * gy;
+            xy += gx * gy;
+        }
+    }
+    xx * yy - xy * xy - 0.04 * (xx + yy) * (xx + yy)
+}
+
+/// Sub-pixel corner position (Förstner): the point q minimising the sum over the
+/// window of (g . (p - q))^2, i.e. where the edges through the window intersect.
+/// FAST responds beside the corner rather than on it, which alone costs about a
+/// pixel; falls back to (x, y) if the window has no corner or the fit wanders off.
+fn refine_corner(image: &GrayImage, x: usize, y: usize) -> (f32, f32) {
+    let (mut a, mut b, mut c, mut bx, mut by) = (0.0f32, 0.0f32, 0.0f32, 0.0f32, 0.0f32);
+    for dy in -REFINE_RADIUS..=REFINE_RADIUS {
+        for dx in -REFINE_RADIUS..=REFINE_RADIUS {
+            let (gx, gy) = sobel(image, x, y, dx, dy);
+            let (px, py) = (dx as f32, dy as f32);
+            a += gx * gx;
+            b += gx * gy;
+            c += gy * gy;
+            bx += gx * gx * px + gx * gy * py;
+            by += gx * gy * px + gy * gy * py;
+        }
+    }
+    let det = a * c - b * b;
+    if det <= 1e-6 * (a + c) * (a + c) {
+        return (x as f32, y as f32);
+    }
+    let ox = (c * bx - b * by) / det;
+    let oy = (a * by - b * bx) / det;
+    if ox.abs() > 2.0 || oy.abs() > 2.0 {
+        return (x as f32, y as f32);
+    }
+    (x as f32 + ox, y as f32 + oy)
+}
+
+/// Orientation from the intensity centroid of the circular patch
+fn orientation(image: &GrayImage, x: usize, y: usize) -> f32 {
+    let (mut m10, mut m01) = (0i64, 0i64);
+    for dy in -PATCH_RADIUS..=PATCH_RADIUS {
+        for dx in -PATCH_RADIUS..=PATCH_RADIUS {
+            if dx * dx + dy * dy <= PATCH_RADIUS * PATCH_RADIUS {
+                let value = image.at_offset(x, y, dx, dy) as i64;
+                m10 += dx as i64 * value;
+                m01 += dy as i64 * value;
+            }
+        }
+    }
+    (m01 as f32).atan2(m10 as f32)
+}
+
+/// 256 test pairs drawn from an isotropic Gaussian (sigma = patch/5, as in BRIEF),
+/// from a fixed seed so descriptors are comparable across devices and runs
+fn brief_pattern() -> Vec<[(f32, f32); 2]> {
+    let mut state = 0x9E37_79B9_7F4A_7C15u64;
+    let mut gaussian = || {
+        // Sum of uniforms: close enough to normal for sampling positions
+        let sum: f32 = (0..4)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                (state >> 40) as f32 / (1u64 << 24) as f32
+            })
+            .sum();
+        let sigma = (2 * PATTERN_RADIUS + 5) as f32 / 5.0;
+        ((sum - 2.0) * 3.0f32.sqrt() * sigma)
+            .round()
+            .clamp(-PATTERN_RADIUS as f32, PATTERN_RADIUS as f32)
+    };
+    (0..256)
+        .map(|_| [(gaussian(), gaussian()), (gaussian(), gaussian())])
+        .collect()
+}
+
+/// Brute-force Hamming matching. A query matches its nearest train descriptor if
+/// that is within `max_distance` and clearly better than the second nearest
+/// (`best < ratio * second`); each train descriptor is used at most once.
+pub fn match_descriptors(
+    query: &[FeatureDescriptor],
+    train: &[FeatureDescriptor],
+    max_distance: u32,
+    ratio: f32,
+) -> Vec<DescriptorMatch> {
+    let mut best_for_train: Vec<Option<DescriptorMatch>> = vec![None; train.len()];
+    for (q, descriptor) in query.iter().enumerate() {
+        let (mut best, mut second) = ((u32::MAX, 0), u32::MAX);
+        for (t, other) in train.iter().enumerate() {
+            let distance = descriptor.distance_to(other);
+            if distance < best.0 {
+                second = best.0;
+                best = (distance, t);
+            } else if distance < second {
+                second = distance;
+            }
+        }
+
+        let (distance, t) = best;
+        if distance > max_distance || (second != u32::MAX && distance as f32 >= ratio * second as f32) {
+            continue;
+        }
+        if best_for_train[t].is_none_or(|m| distance < m.distance) {
+            best_for_train[t] = Some(DescriptorMatch { query: q, train: t, distance });
+        }
+    }
+
+    let mut matches: Vec<DescriptorMatch> = best_for_train.into_iter().flatten().collect();
+    matches.sort_by_key(|m| m.query);
+    matches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Random-intensity blocks: plenty of corners, none alike
+    fn blocks(width: usize, height: usize, block: usize, seed: u64) -> GrayImage {
+        let data = (0..width * height)
+            .map(|i| {
+                let (bx, by) = ((i % width) / block, (i / width) / block);
+                let mut h = (bx as u64 * 73_856_093) ^ (by as u64 * 19_349_663) ^ seed;
+                h ^= h >> 13;
+                h = h.wrapping_mul(0x5bd1_e995);
+                h ^= h >> 15;
+                (h % 200 + 28) as u8
+            })
+            .collect();
+        GrayImage::new(width, height, data).unwrap()
+    }
+
+    fn rotate_90(image: &GrayImage) -> GrayImage {
+        let (w, h) = (image.width, image.height);
+        let data = (0..w * h)
+            .map(|i| {
+                let (x, y) = (i % h, i / h);
+                image.at(y, h - 1 - x)
+            })
+            .collect();
+        GrayImage::new(h, w, data).unwrap()
+    }
+
+    #[test]
+    fn test_fast_finds_square_corners() {
+        let mut data = vec![40u8; 100 * 100];
+        for y in 40..60 {
+            for x in 40..60 {
+                data[y * 100 + x] = 200;
+            }
+        }
+        let image = GrayImage::new(100, 100, data).unwrap();
+        let extractor = OrbExtractor::new(OrbConfig { levels: 1, ..OrbConfig::default() });
+        let features = extractor.extract(&image);
+
+        assert_eq!(features.keypoints.len(), 4);
+        for kp in &features.keypoints {
+            let near = |v: f32, c: f32| (v - c).abs() <= 2.0;
+            assert!((near(kp.x, 40.0) || near(kp.x, 59.0)) && (near(kp.y, 40.0) || near(kp.y, 59.0)));
+        }
+    }
+
+    #[test]
+    fn test_descriptors_survive_rotation() {
+        let image = blocks(160, 160, 9, 1);
+        let rotated = rotate_90(&image);
+        let extractor = OrbExtractor::default();
+        let a = extractor.extract(&image);
+        let b = extractor.extract(&rotated);
+        assert!(a.keypoints.len() > 50);
+
+        let matches = match_descriptors(&a.descriptors, &b.descriptors, 64, 0.8);
+        let correct = matches.iter()
+            .filter(|m| {
+                let (p, q) = (a.keypoints[m.query], b.keypoints[m.train]);
+                // (x, y) -> (159 - y, x) under the rotation
+                ((159.0 - p.y) - q.x).abs() < 3.0 && (p.x - q.y).abs() < 3.0
+            })
+            .count();
+        assert!(matches.len() >= 20, "{} matches", matches.len());
+        assert!(correct as f32 / matches.len() as f32 > 0.8, "{} of {} correct", correct, matches.len());
+    }
+
+    #[test]
+    fn test_ratio_test_rejects_ambiguous() {
+        let d = |bits: &[usize]| {
+            let mut data = [0u8; 32];
+            for &b in bits {
+                data[b / 8] |= 1 << (b % 8);
+            }
+            FeatureDescriptor { data }
+        };
+        let train = vec![d(&[]), d(&[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]), d(&[100, 101])];
+        // Clear winner, ambiguous between 0 and 2, too far from everything
+        let query = vec![d(&[1, 2, 3, 4, 5, 6, 7, 8, 9]), d(&[100]), d(&(200..256).collect::<Vec<_>>())];
+
+        let matches = match_descriptors(&query, &train, 40, 0.8);
+        assert_eq!(matches, vec![DescriptorMatch { query: 0, train: 1, distance: 1 }]);
+    }
+
+    #[test]
+    fn test_gray_from_rgb_frame() {
+        let frame = CameraFrame {
+            data: [255u8, 0, 0, 0, 255, 0].repeat(2),
+            width: 2,
+            height: 2,
+            timestamp: 0,
+            intrinsics: None,
+        };
+        let gray = GrayImage::from_frame(&frame).unwrap();
+        assert_eq!(gray.data, vec![76, 149, 76, 149]);
+        assert!(GrayImage::from_frame(&CameraFrame { data: vec![0; 5], ..frame }).is_err());
+    }
+}
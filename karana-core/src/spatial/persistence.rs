@@ -311,6 +311,11 @@ impl AnchorStore {
         Ok(())
     }
     
+    /// Iterate over every cached anchor
+    pub fn stored(&self) -> impl Iterator<Item = &StoredAnchor> {
+        self.cache.values()
+    }
+    
     /// Get all anchors in a room
     pub fn get_by_room(&self, room_id: &RoomId) -> Vec<&StoredAnchor> {
         self.cache
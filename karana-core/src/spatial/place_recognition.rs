@@ -0,0 +1,352 @@
+//! Place Recognition
+//!
+//! Bag-of-words index over keyframe descriptors, used to notice when the camera
+//! is back somewhere it has already mapped. Binary ORB descriptors are quantized
+//! into visual words by a vocabulary tree (hierarchical k-majority clustering);
+//! each keyframe becomes a TF-IDF weighted word histogram, and candidates for a
+//! query come from an inverted index from words to the keyframes containing them.
+
+use std::collections::HashMap;
+
+use super::slam::FeatureDescriptor;
+
+/// Index of a leaf in the vocabulary tree
+pub type WordId = u32;
+
+/// Vocabulary tree shape
+#[derive(Debug, Clone, Copy)]
+pub struct VocabularyConfig {
+    /// Children per node
+    pub branching: usize,
+    /// Levels below the root; the tree has up to `branching^depth` words
+    pub depth: usize,
+    /// k-majority iterations per node
+    pub iterations: usize,
+}
+
+impl Default for VocabularyConfig {
+    fn default() -> Self {
+        Self {
+            branching: 32,
+            depth: 2,
+            iterations: 8,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct VocabularyNode {
+    centre: [u8; 32],
+    children: Vec<usize>,
+    word: Option<WordId>,
+}
+
+/// Vocabulary tree mapping descriptors to visual words
+#[derive(Debug, Clone)]
+pub struct Vocabulary {
+    nodes: Vec<VocabularyNode>,
+    words: usize,
+}
+
+impl Vocabulary {
+    /// Train a vocabulary by recursively clustering `descriptors`
+    pub fn train(descriptors: &[FeatureDescriptor], config: &VocabularyConfig) -> Self {
+        let mut vocabulary = Self {
+            nodes: vec![VocabularyNode { centre: [0; 32], children: Vec::new(), word: None }],
+            words: 0,
+        };
+        let data: Vec<[u8; 32]> = descriptors.iter().map(|d| d.data).collect();
+        vocabulary.split(0, data, config.depth, config);
+        vocabulary
+    }
+
+    fn split(&mut self, node: usize, data: Vec<[u8; 32]>, depth: usize, config: &VocabularyConfig) {
+        if depth == 0 || data.len() <= 1 {
+            self.nodes[node].word = Some(self.words as WordId);
+            self.words += 1;
+            return;
+        }
+
+        for (centre, members) in k_majority(data, config.branching, config.iterations) {
+            let child = self.nodes.len();
+            self.nodes.push(VocabularyNode { centre, children: Vec::new(), word: None });
+            self.nodes[node].children.push(child);
+            self.split(child, members, depth - 1, config);
+        }
+    }
+
+    /// Number of visual words
+    pub fn word_count(&self) -> usize {
+        self.words
+    }
+
+    /// Visual word of a descriptor
+    pub fn word(&self, descriptor: &FeatureDescriptor) -> WordId {
+        let mut node = 0;
+        loop {
+            let current = &self.nodes[node];
+            if let Some(word) = current.word {
+                return word;
+            }
+            node = *current
+                .children
+                .iter()
+                .min_by_key(|&&child| hamming(&self.nodes[child].centre, &descriptor.data))
+                .expect("inner vocabulary nodes have children");
+        }
+    }
+
+    /// Term frequencies of the words in a set of descriptors
+    pub fn bow(&self, descriptors: &[FeatureDescriptor]) -> BowVector {
+        let mut counts: HashMap<WordId, f32> = HashMap::new();
+        for descriptor in descriptors {
+            *counts.entry(self.word(descriptor)).or_default() += 1.0;
+        }
+        let total = descriptors.len().max(1) as f32;
+        for tf in counts.values_mut() {
+            *tf /= total;
+        }
+        BowVector(counts)
+    }
+}
+
+/// Sparse word histogram (term frequencies)
+#[derive(Debug, Clone, Default)]
+pub struct BowVector(pub HashMap<WordId, f32>);
+
+/// A keyframe that looks like the query
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PlaceMatch {
+    pub keyframe_id: u64,
+    /// Similarity in [0, 1]
+    pub score: f32,
+}
+
+/// Inverted index from visual words to keyframes
+#[derive(Debug, Clone)]
+pub struct PlaceIndex {
+    vocabulary: Vocabulary,
+    inverted: HashMap<WordId, Vec<u64>>,
+    entries: HashMap<u64, BowVector>,
+}
+
+impl PlaceIndex {
+    pub fn new(vocabulary: Vocabulary) -> Self {
+        Self {
+            vocabulary,
+            inverted: HashMap::new(),
+            entries: HashMap::new(),
+        }
+    }
+
+    pub fn vocabulary(&self) -> &Vocabulary {
+        &self.vocabulary
+    }
+
+    /// Add a keyframe's descriptors to the index
+    pub fn add(&mut self, keyframe_id: u64, descriptors: &[FeatureDescriptor]) {
+        let bow = self.vocabulary.bow(descriptors);
+        for &word in bow.0.keys() {
+            self.inverted.entry(word).or_default().push(keyframe_id);
+        }
+        self.entries.insert(keyframe_id, bow);
+    }
+
+    /// Number of indexed keyframes
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Indexed keyframes most similar to `descriptors`, best first.
+    ///
+    /// Keyframes for which `exclude` returns true are never returned (e.g. the
+    /// recent keyframes that trivially look like the current view).
+    pub fn query(
+        &self,
+        descriptors: &[FeatureDescriptor],
+        min_score: f32,
+        max_results: usize,
+        exclude: impl Fn(u64) -> bool,
+    ) -> Vec<PlaceMatch> {
+        let query = self.weighted(&self.vocabulary.bow(descriptors));
+
+        let mut candidates: Vec<u64> = query
+            .keys()
+            .filter_map(|word| self.inverted.get(word))
+            .flatten()
+            .copied()
+            .filter(|&id| !exclude(id))
+            .collect();
+        candidates.sort_unstable();
+        candidates.dedup();
+
+        let mut matches: Vec<PlaceMatch> = candidates
+            .into_iter()
+            .map(|keyframe_id| {
+                let entry = self.weighted(&self.entries[&keyframe_id]);
+                PlaceMatch { keyframe_id, score: l1_score(&query, &entry) }
+            })
+            .filter(|m| m.score >= min_score)
+            .collect();
+        matches.sort_by(|a, b| b.score.total_cmp(&a.score));
+        matches.truncate(max_results);
+        matches
+    }
+
+    /// Inverse document frequency of a word over the indexed keyframes
+    fn idf(&self, word: WordId) -> f32 {
+        let containing = self.inverted.get(&word).map_or(0, Vec::len);
+        if containing == 0 {
+            return 0.0;
+        }
+        (self.entries.len() as f32 / containing as f32).ln()
+    }
+
+    /// TF-IDF weights, L1-normalized
+    fn weighted(&self, bow: &BowVector) -> HashMap<WordId, f32> {
+        let mut weights: HashMap<WordId, f32> = bow
+            .0
+            .iter()
+            .map(|(&word, &tf)| (word, tf * self.idf(word)))
+            .filter(|&(_, w)| w > 0.0)
+            .collect();
+        let norm: f32 = weights.values().sum();
+        if norm > 0.0 {
+            for w in weights.values_mut() {
+                *w /= norm;
+            }
+        }
+        weights
+    }
+}
+
+/// `1 - |a - b|_1 / 2` for L1-normalized vectors; only shared words contribute
+fn l1_score(a: &HashMap<WordId, f32>, b: &HashMap<WordId, f32>) -> f32 {
+    let shared: f32 = a
+        .iter()
+        .filter_map(|(word, &va)| b.get(word).map(|&vb| va + vb - (va - vb).abs()))
+        .sum();
+    (shared / 2.0).clamp(0.0, 1.0)
+}
+
+fn hamming(a: &[u8; 32], b: &[u8; 32]) -> u32 {
+    a.iter().zip(b).map(|(x, y)| (x ^ y).count_ones()).sum()
+}
+
+/// Hamming k-means: centres are the bitwise majority of their members.
+/// Seeds are spread evenly through the data, so training is deterministic.
+fn k_majority(data: Vec<[u8; 32]>, k: usize, iterations: usize) -> Vec<([u8; 32], Vec<[u8; 32]>)> {
+    let k = k.min(data.len());
+    let mut centres: Vec<[u8; 32]> = (0..k).map(|i| data[i * data.len() / k]).collect();
+    let mut assignment = vec![usize::MAX; data.len()];
+
+    for _ in 0..iterations.max(1) {
+        let mut changed = false;
+        for (d, slot) in data.iter().zip(assignment.iter_mut()) {
+            let nearest = (0..k).min_by_key(|&c| hamming(&centres[c], d)).unwrap_or(0);
+            changed |= *slot != nearest;
+            *slot = nearest;
+        }
+        if !changed {
+            break;
+        }
+
+        let mut votes = vec![[0u32; 256]; k];
+        let mut sizes = vec![0u32; k];
+        for (d, &c) in data.iter().zip(&assignment) {
+            sizes[c] += 1;
+            for bit in 0..256 {
+                votes[c][bit] += ((d[bit / 8] >> (bit % 8)) & 1) as u32;
+            }
+        }
+        for c in 0..k {
+            if sizes[c] == 0 {
+                continue;
+            }
+            let mut centre = [0u8; 32];
+            for bit in 0..256 {
+                if 2 * votes[c][bit] > sizes[c] {
+                    centre[bit / 8] |= 1 << (bit % 8);
+                }
+            }
+            centres[c] = centre;
+        }
+    }
+
+    let mut clusters: Vec<([u8; 32], Vec<[u8; 32]>)> = centres.into_iter().map(|c| (c, Vec::new())).collect();
+    for (d, c) in data.into_iter().zip(assignment) {
+        clusters[c].1.push(d);
+    }
+    clusters.retain(|(_, members)| !members.is_empty());
+    clusters
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    fn random_descriptor(rng: &mut StdRng) -> FeatureDescriptor {
+        let mut data = [0u8; 32];
+        rng.fill(&mut data);
+        FeatureDescriptor { data }
+    }
+
+    /// Flip a few random bits, as re-observing the same corner would
+    fn noisy(descriptor: &FeatureDescriptor, bits: usize, rng: &mut StdRng) -> FeatureDescriptor {
+        let mut data = descriptor.data;
+        for _ in 0..bits {
+            let bit = rng.gen_range(0..256);
+            data[bit / 8] ^= 1 << (bit % 8);
+        }
+        FeatureDescriptor { data }
+    }
+
+    #[test]
+    fn test_vocabulary_words_are_stable() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let descriptors: Vec<FeatureDescriptor> = (0..500).map(|_| random_descriptor(&mut rng)).collect();
+        let vocabulary = Vocabulary::train(&descriptors, &VocabularyConfig::default());
+
+        assert!(vocabulary.word_count() > 100);
+        assert!(vocabulary.word_count() <= 32 * 32);
+
+        // Training descriptors map back to the same word after a little noise
+        let stable = descriptors
+            .iter()
+            .filter(|d| vocabulary.word(d) == vocabulary.word(&noisy(d, 2, &mut rng)))
+            .count();
+        assert!(stable > 400, "only {} stable words", stable);
+    }
+
+    #[test]
+    fn test_query_finds_revisited_place() {
+        let mut rng = StdRng::seed_from_u64(2);
+        let places: Vec<Vec<FeatureDescriptor>> = (0..10)
+            .map(|_| (0..150).map(|_| random_descriptor(&mut rng)).collect())
+            .collect();
+        let training: Vec<FeatureDescriptor> = places.iter().take(4).flatten().cloned().collect();
+        let mut index = PlaceIndex::new(Vocabulary::train(&training, &VocabularyConfig::default()));
+        for (id, place) in places.iter().enumerate() {
+            index.add(id as u64, place);
+        }
+        assert_eq!(index.len(), 10);
+
+        // A second look at place 2, with most of the same corners
+        let mut revisit: Vec<FeatureDescriptor> = places[2].iter().take(120).map(|d| noisy(d, 2, &mut rng)).collect();
+        revisit.extend((0..30).map(|_| random_descriptor(&mut rng)));
+        let matches = index.query(&revisit, 0.05, 3, |_| false);
+        assert_eq!(matches[0].keyframe_id, 2);
+        assert!(matches[0].score > 0.3);
+        assert!(matches.get(1).is_none_or(|m| m.score < matches[0].score / 2.0));
+
+        // Excluded keyframes are never returned
+        let matches = index.query(&revisit, 0.05, 3, |id| id == 2);
+        assert!(matches.iter().all(|m| m.keyframe_id != 2));
+    }
+}
@@ -0,0 +1,349 @@
+//! Pose Graph Optimization
+//!
+//! Keyframe poses linked by relative-pose constraints: odometry between
+//! consecutive keyframes, and loop closures between a keyframe and an older one
+//! of the same place. Optimizing the graph spreads the error that a loop closure
+//! exposes back along the trajectory.
+//!
+//! Gauss-Newton on SE(3), with the first node held fixed. Each step solves the
+//! sparse normal equations with block-Jacobi preconditioned conjugate gradients,
+//! so the cost per iteration grows with the number of edges, not keyframes cubed.
+
+use nalgebra::{Isometry3, Matrix6, Translation3, UnitQuaternion, Vector3, Vector6};
+use std::collections::HashMap;
+
+/// Step used for numeric Jacobians
+const JACOBIAN_EPS: f64 = 1e-6;
+
+/// Where a constraint came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EdgeKind {
+    /// Consecutive keyframes, from tracking
+    Odometry,
+    /// Revisit of an earlier keyframe, from place recognition
+    Loop,
+}
+
+/// Measured pose of node `to` in the frame of node `from`
+#[derive(Debug, Clone)]
+pub struct PoseEdge {
+    pub from: usize,
+    pub to: usize,
+    pub measurement: Isometry3<f64>,
+    pub kind: EdgeKind,
+}
+
+/// Optimizer settings
+#[derive(Debug, Clone, Copy)]
+pub struct PoseGraphConfig {
+    /// Gauss-Newton iterations
+    pub iterations: usize,
+    /// Weight of rotation residuals (1 / rad^2)
+    pub rotation_weight: f64,
+    /// Weight of translation residuals (1 / m^2)
+    pub translation_weight: f64,
+    /// Conjugate gradient iterations per step
+    pub max_cg_iterations: usize,
+}
+
+impl Default for PoseGraphConfig {
+    fn default() -> Self {
+        Self {
+            iterations: 20,
+            rotation_weight: 2500.0,
+            translation_weight: 400.0,
+            max_cg_iterations: 2000,
+        }
+    }
+}
+
+/// Keyframe poses (camera to world) and the constraints between them
+#[derive(Debug, Clone, Default)]
+pub struct PoseGraph {
+    nodes: Vec<Isometry3<f64>>,
+    edges: Vec<PoseEdge>,
+}
+
+impl PoseGraph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a node, returning its index
+    pub fn add_node(&mut self, pose: Isometry3<f64>) -> usize {
+        self.nodes.push(pose);
+        self.nodes.len() - 1
+    }
+
+    /// Constrain the pose of `to` relative to `from`
+    pub fn add_edge(&mut self, from: usize, to: usize, measurement: Isometry3<f64>, kind: EdgeKind) {
+        self.edges.push(PoseEdge { from, to, measurement, kind });
+    }
+
+    pub fn nodes(&self) -> &[Isometry3<f64>] {
+        &self.nodes
+    }
+
+    pub fn edges(&self) -> &[PoseEdge] {
+        &self.edges
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+        self.edges.clear();
+    }
+
+    /// Weighted squared error over all edges
+    pub fn cost(&self, config: &PoseGraphConfig) -> f64 {
+        let weights = weights(config);
+        self.edges
+            .iter()
+            .map(|e| {
+                let r = residual(&self.nodes[e.from], &self.nodes[e.to], &e.measurement);
+                r.component_mul(&weights).dot(&r)
+            })
+            .sum()
+    }
+
+    /// Optimize every node but the first; returns the final cost
+    pub fn optimize(&mut self, config: &PoseGraphConfig) -> f64 {
+        let mut cost = self.cost(config);
+        if self.nodes.len() < 2 {
+            return cost;
+        }
+
+        for _ in 0..config.iterations {
+            let Some(step) = self.gauss_newton_step(config) else {
+                break;
+            };
+
+            let previous = self.nodes.clone();
+            for (node, delta) in self.nodes.iter_mut().zip(&step).skip(1) {
+                *node = perturb(node, delta);
+            }
+
+            let updated = self.cost(config);
+            if updated > cost {
+                self.nodes = previous;
+                break;
+            }
+            let converged = cost - updated < 1e-9 * cost.max(1e-12);
+            cost = updated;
+            if converged {
+                break;
+            }
+        }
+        cost
+    }
+
+    /// Solve the linearized problem; `None` if it has no usable solution
+    fn gauss_newton_step(&self, config: &PoseGraphConfig) -> Option<Vec<Vector6<f64>>> {
+        let n = self.nodes.len();
+        let weights = Matrix6::from_diagonal(&weights(config));
+
+        let mut diagonal = vec![Matrix6::<f64>::zeros(); n];
+        let mut off_diagonal: HashMap<(usize, usize), Matrix6<f64>> = HashMap::new();
+        let mut gradient = vec![Vector6::<f64>::zeros(); n];
+
+        for edge in &self.edges {
+            let (a, b) = (&self.nodes[edge.from], &self.nodes[edge.to]);
+            let r = residual(a, b, &edge.measurement);
+
+            let mut ja = Matrix6::<f64>::zeros();
+            let mut jb = Matrix6::<f64>::zeros();
+            for k in 0..6 {
+                let delta = Vector6::ith(k, JACOBIAN_EPS);
+                ja.set_column(k, &((residual(&perturb(a, &delta), b, &edge.measurement) - r) / JACOBIAN_EPS));
+                jb.set_column(k, &((residual(a, &perturb(b, &delta), &edge.measurement) - r) / JACOBIAN_EPS));
+            }
+
+            let wa = ja.transpose() * weights;
+            let wb = jb.transpose() * weights;
+            diagonal[edge.from] += wa * ja;
+            diagonal[edge.to] += wb * jb;
+            *off_diagonal.entry((edge.from, edge.to)).or_insert_with(Matrix6::zeros) += wa * jb;
+            *off_diagonal.entry((edge.to, edge.from)).or_insert_with(Matrix6::zeros) += wb * ja;
+            gradient[edge.from] += wa * r;
+            gradient[edge.to] += wb * r;
+        }
+
+        // Node 0 fixes the gauge: drop its rows and columns
+        gradient[0] = Vector6::zeros();
+        off_diagonal.retain(|&(i, j), _| i != 0 && j != 0);
+        let preconditioner: Vec<Matrix6<f64>> = diagonal
+            .iter()
+            .enumerate()
+            .map(|(i, block)| {
+                if i == 0 {
+                    Matrix6::zeros()
+                } else {
+                    (block + Matrix6::identity() * 1e-9).try_inverse().unwrap_or_else(Matrix6::zeros)
+                }
+            })
+            .collect();
+
+        let multiply = |x: &[Vector6<f64>]| -> Vec<Vector6<f64>> {
+            let mut out: Vec<Vector6<f64>> = (0..n)
+                .map(|i| if i == 0 { Vector6::zeros() } else { diagonal[i] * x[i] })
+                .collect();
+            for (&(i, j), block) in &off_diagonal {
+                out[i] += block * x[j];
+            }
+            out
+        };
+
+        let rhs: Vec<Vector6<f64>> = gradient.iter().map(|g| -g).collect();
+        let step = conjugate_gradient(multiply, &preconditioner, &rhs, config.max_cg_iterations);
+        step.iter().all(|s| s.iter().all(|v| v.is_finite())).then_some(step)
+    }
+}
+
+/// Rotation (axis-angle) and translation of `from^-1 * to` relative to the measurement
+fn residual(from: &Isometry3<f64>, to: &Isometry3<f64>, measurement: &Isometry3<f64>) -> Vector6<f64> {
+    let error = measurement.inverse() * from.inverse() * to;
+    let rotation = error.rotation.scaled_axis();
+    let translation = error.translation.vector;
+    Vector6::new(rotation.x, rotation.y, rotation.z, translation.x, translation.y, translation.z)
+}
+
+/// Rotate a pose about its own position and shift it, both in world axes
+fn perturb(pose: &Isometry3<f64>, delta: &Vector6<f64>) -> Isometry3<f64> {
+    let rotation = UnitQuaternion::from_scaled_axis(Vector3::new(delta[0], delta[1], delta[2])) * pose.rotation;
+    let translation = pose.translation.vector + Vector3::new(delta[3], delta[4], delta[5]);
+    Isometry3::from_parts(Translation3::from(translation), rotation)
+}
+
+fn weights(config: &PoseGraphConfig) -> Vector6<f64> {
+    let (r, t) = (config.rotation_weight, config.translation_weight);
+    Vector6::new(r, r, r, t, t, t)
+}
+
+fn dot(a: &[Vector6<f64>], b: &[Vector6<f64>]) -> f64 {
+    a.iter().zip(b).map(|(x, y)| x.dot(y)).sum()
+}
+
+/// Preconditioned conjugate gradients for a symmetric positive definite block system
+fn conjugate_gradient(
+    multiply: impl Fn(&[Vector6<f64>]) -> Vec<Vector6<f64>>,
+    preconditioner: &[Matrix6<f64>],
+    rhs: &[Vector6<f64>],
+    max_iterations: usize,
+) -> Vec<Vector6<f64>> {
+    let precondition = |r: &[Vector6<f64>]| -> Vec<Vector6<f64>> {
+        r.iter().zip(preconditioner).map(|(v, m)| m * v).collect()
+    };
+
+    let mut x = vec![Vector6::<f64>::zeros(); rhs.len()];
+    let mut r = rhs.to_vec();
+    let mut z = precondition(&r);
+    let mut p = z.clone();
+    let mut rz = dot(&r, &z);
+    let tolerance = 1e-20 * dot(rhs, rhs).max(1e-30);
+
+    for _ in 0..max_iterations {
+        if dot(&r, &r) <= tolerance {
+            break;
+        }
+        let ap = multiply(&p);
+        let pap = dot(&p, &ap);
+        if pap <= 0.0 {
+            break;
+        }
+        let alpha = rz / pap;
+        for i in 0..x.len() {
+            x[i] += p[i] * alpha;
+            r[i] -= ap[i] * alpha;
+        }
+        z = precondition(&r);
+        let rz_next = dot(&r, &z);
+        let beta = rz_next / rz;
+        rz = rz_next;
+        for i in 0..p.len() {
+            p[i] = z[i] + p[i] * beta;
+        }
+    }
+    x
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pose(x: f64, z: f64, yaw: f64) -> Isometry3<f64> {
+        Isometry3::new(Vector3::new(x, 0.0, z), Vector3::y() * yaw)
+    }
+
+    /// Walk a square, 1 m per side, with a biased odometry that turns a little
+    /// too far at every step
+    fn drifting_square() -> (PoseGraph, Vec<Isometry3<f64>>) {
+        let truth: Vec<Isometry3<f64>> = (0..16)
+            .map(|i| {
+                let side = i / 4;
+                let along = (i % 4) as f64 * 0.25;
+                let corner = [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)][side];
+                let direction = [(1.0, 0.0), (0.0, 1.0), (-1.0, 0.0), (0.0, -1.0)][side];
+                pose(corner.0 + direction.0 * along, corner.1 + direction.1 * along, 0.0)
+            })
+            .collect();
+
+        let drift = pose(0.0, 0.0, 0.02);
+        let mut graph = PoseGraph::new();
+        let mut estimate = truth[0];
+        graph.add_node(estimate);
+        for i in 1..truth.len() {
+            let odometry = truth[i - 1].inverse() * truth[i] * drift;
+            estimate *= odometry;
+            graph.add_node(estimate);
+            graph.add_edge(i - 1, i, odometry, EdgeKind::Odometry);
+        }
+        (graph, truth)
+    }
+
+    fn max_error(graph: &PoseGraph, truth: &[Isometry3<f64>]) -> f64 {
+        graph
+            .nodes()
+            .iter()
+            .zip(truth)
+            .map(|(a, b)| (a.translation.vector - b.translation.vector).norm())
+            .fold(0.0, f64::max)
+    }
+
+    #[test]
+    fn test_odometry_only_graph_is_already_optimal() {
+        let (mut graph, _) = drifting_square();
+        let before = graph.nodes().to_vec();
+
+        let cost = graph.optimize(&PoseGraphConfig::default());
+
+        assert!(cost < 1e-12);
+        for (a, b) in graph.nodes().iter().zip(&before) {
+            assert!((a.translation.vector - b.translation.vector).norm() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_loop_edge_removes_drift() {
+        let (mut graph, truth) = drifting_square();
+        let config = PoseGraphConfig::default();
+        let drifted = max_error(&graph, &truth);
+
+        // The last pose sees the first one again, 0.25 m ahead
+        let last = graph.len() - 1;
+        graph.add_edge(last, 0, truth[last].inverse() * truth[0], EdgeKind::Loop);
+        let before = graph.cost(&config);
+        let after = graph.optimize(&config);
+
+        assert!(after < before / 10.0);
+        let corrected = max_error(&graph, &truth);
+        assert!(corrected < drifted / 3.0, "drift {} -> {}", drifted, corrected);
+        assert_eq!(graph.nodes()[0], truth[0]);
+    }
+}
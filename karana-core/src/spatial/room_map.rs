@@ -0,0 +1,429 @@
+//! Shareable Room Maps
+//!
+//! A room mapped on one pair of glasses can be handed to another: the keyframes
+//! (pose plus triangulated, described features), map points, room signature and
+//! anchor poses are packed into a signed, versioned blob. The receiver aligns
+//! the map against its own keyframes, which gives the transform between the two
+//! devices' coordinate frames, and can then relocalize in the room and place the
+//! anchors where the first device left them.
+//!
+//! ## Wire format
+//!
+//! ```text
+//! "KRMP" | version: u16 LE | signer: ed25519 key (32) | signature (64) | zstd(bincode(RoomMap))
+//! ```
+//!
+//! The signature covers everything before it and the compressed payload.
+
+use anyhow::{anyhow, bail, Context, Result};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use nalgebra::{Isometry3, UnitQuaternion};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::anchor::{AnchorContent, AnchorId, CreateAnchorRequest, Quaternion, SpatialAnchor};
+use super::loop_closure::{relative_pose, LoopClosureConfig};
+use super::place_recognition::{PlaceIndex, Vocabulary};
+use super::slam::{CameraPose, FeatureDescriptor, Keyframe, RoomSignature, SlamMap, VisualFeature};
+use super::world_coords::{CoordinateTransform, LocalCoord, RoomId, WorldPosition};
+
+/// Leading bytes of every room map
+pub const ROOM_MAP_MAGIC: [u8; 4] = *b"KRMP";
+/// Current format version
+pub const ROOM_MAP_VERSION: u16 = 1;
+/// Largest payload accepted once decompressed
+const MAX_PAYLOAD_BYTES: usize = 64 * 1024 * 1024;
+/// zstd compression level
+const COMPRESSION_LEVEL: i32 = 3;
+/// Magic, version, key and signature
+const HEADER_LEN: usize = 4 + 2 + 32 + 64;
+
+/// A triangulated feature of a shared keyframe
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MapFeature {
+    pub descriptor: [u8; 32],
+    pub position: LocalCoord,
+    pub is_landmark: bool,
+}
+
+/// A keyframe as shared; features without a 3D position are dropped
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MapKeyframe {
+    pub id: u64,
+    pub pose: CameraPose,
+    pub features: Vec<MapFeature>,
+}
+
+/// An anchor pose in the map's frame
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MapAnchor {
+    /// Anchor ID on the device that exported the map
+    pub id: AnchorId,
+    pub position: LocalCoord,
+    pub orientation: Quaternion,
+    pub floor: i8,
+    pub content: AnchorContent,
+    pub label: Option<String>,
+}
+
+/// Everything another device needs to relocalize in a room
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoomMap {
+    pub room_id: RoomId,
+    /// When the map was exported
+    pub created_at: u64,
+    pub keyframes: Vec<MapKeyframe>,
+    pub map_points: Vec<(u64, LocalCoord)>,
+    pub signature: RoomSignature,
+    pub anchors: Vec<MapAnchor>,
+}
+
+/// Where an imported map sits in the local frame
+#[derive(Debug, Clone)]
+pub struct RoomAlignment {
+    /// Map coordinates to local coordinates
+    pub transform: CoordinateTransform,
+    /// Map keyframe the local session was matched against
+    pub map_keyframe_id: u64,
+    /// Local keyframe that matched it
+    pub local_keyframe_id: u64,
+    /// Points agreeing on the transform
+    pub inliers: usize,
+}
+
+impl RoomMap {
+    /// Snapshot a SLAM map, its room signature and the anchors placed in it
+    pub fn new(room_id: RoomId, map: &SlamMap, signature: RoomSignature, anchors: &[SpatialAnchor]) -> Self {
+        let keyframes = map.keyframes
+            .iter()
+            .map(|kf| MapKeyframe {
+                id: kf.id,
+                pose: kf.pose,
+                features: kf.features
+                    .iter()
+                    .filter_map(|f| {
+                        Some(MapFeature {
+                            descriptor: f.descriptor.data,
+                            position: f.world_pos?,
+                            is_landmark: f.is_landmark,
+                        })
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        let mut map_points: Vec<(u64, LocalCoord)> = map.map_points.iter().map(|(&id, &p)| (id, p)).collect();
+        map_points.sort_by_key(|(id, _)| *id);
+
+        let anchors = anchors
+            .iter()
+            .map(|a| MapAnchor {
+                id: a.id,
+                position: a.position.local,
+                orientation: a.orientation,
+                floor: a.position.floor,
+                content: a.content.clone(),
+                label: a.label.clone(),
+            })
+            .collect();
+
+        Self {
+            room_id,
+            created_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+            keyframes,
+            map_points,
+            signature,
+            anchors,
+        }
+    }
+
+    /// The map as keyframes, map points and room signature
+    pub fn slam_map(&self) -> SlamMap {
+        SlamMap {
+            keyframes: self.keyframes.iter().map(|kf| kf.to_keyframe(&self.room_id)).collect(),
+            map_points: self.map_points.iter().copied().collect(),
+            room_signatures: HashMap::from([(self.room_id.clone(), self.signature.clone())]),
+        }
+    }
+
+    /// Serialize, compress and sign
+    pub fn encode(&self, signing_key: &SigningKey) -> Result<Vec<u8>> {
+        let payload = bincode::serialize(self).context("Failed to serialize room map")?;
+        let payload = zstd::encode_all(&payload[..], COMPRESSION_LEVEL).context("Failed to compress room map")?;
+
+        let mut bytes = Vec::with_capacity(HEADER_LEN + payload.len());
+        bytes.extend_from_slice(&ROOM_MAP_MAGIC);
+        bytes.extend_from_slice(&ROOM_MAP_VERSION.to_le_bytes());
+        bytes.extend_from_slice(signing_key.verifying_key().as_bytes());
+        let signature = signing_key.sign(&signed_bytes(&bytes, &payload));
+        bytes.extend_from_slice(&signature.to_bytes());
+        bytes.extend_from_slice(&payload);
+        Ok(bytes)
+    }
+
+    /// Check the signature and unpack, returning the map and who signed it
+    pub fn decode(bytes: &[u8]) -> Result<(Self, VerifyingKey)> {
+        if bytes.len() < HEADER_LEN || bytes[..4] != ROOM_MAP_MAGIC {
+            bail!("Not a room map");
+        }
+        let version = u16::from_le_bytes([bytes[4], bytes[5]]);
+        if version != ROOM_MAP_VERSION {
+            bail!("Unsupported room map version {} (expected {})", version, ROOM_MAP_VERSION);
+        }
+
+        let key: [u8; 32] = bytes[6..38].try_into()?;
+        let signer = VerifyingKey::from_bytes(&key).map_err(|e| anyhow!("Invalid signer key: {}", e))?;
+        let signature: [u8; 64] = bytes[38..HEADER_LEN].try_into()?;
+        let payload = &bytes[HEADER_LEN..];
+        signer
+            .verify(&signed_bytes(&bytes[..38], payload), &Signature::from_bytes(&signature))
+            .map_err(|_| anyhow!("Room map signature is invalid"))?;
+
+        let payload = zstd::bulk::decompress(payload, MAX_PAYLOAD_BYTES).context("Failed to decompress room map")?;
+        let map = bincode::deserialize(&payload).context("Failed to parse room map")?;
+        Ok((map, signer))
+    }
+
+    /// Unpack a map, requiring it to be signed by `signer`
+    pub fn decode_trusted(bytes: &[u8], signer: &VerifyingKey) -> Result<Self> {
+        let (map, actual) = Self::decode(bytes)?;
+        if actual != *signer {
+            bail!("Room map was signed by an untrusted key");
+        }
+        Ok(map)
+    }
+
+    /// Find the map in a set of local keyframes: recognise a map keyframe from
+    /// a local one and align their triangulated points. Local keyframes are
+    /// tried newest first.
+    pub fn align(&self, local: &[Keyframe]) -> Option<RoomAlignment> {
+        let config = LoopClosureConfig::default();
+        let keyframes: Vec<Keyframe> = self.keyframes.iter().map(|kf| kf.to_keyframe(&self.room_id)).collect();
+
+        let training: Vec<FeatureDescriptor> = keyframes.iter().flat_map(descriptors).collect();
+        if training.is_empty() {
+            return None;
+        }
+        let mut index = PlaceIndex::new(Vocabulary::train(&training, &config.vocabulary));
+        for kf in &keyframes {
+            index.add(kf.id, &descriptors(kf).collect::<Vec<_>>());
+        }
+
+        local.iter().rev().find_map(|local_kf| {
+            let query: Vec<FeatureDescriptor> = descriptors(local_kf).collect();
+            index
+                .query(&query, config.min_score, config.max_candidates, |_| false)
+                .into_iter()
+                .find_map(|candidate| {
+                    let map_kf = keyframes.iter().find(|kf| kf.id == candidate.keyframe_id)?;
+                    let (relative, inliers) = relative_pose(map_kf, local_kf, &config)?;
+
+                    // Local keyframe in map coordinates, composed with its local pose
+                    let local_to_map = map_kf.pose.to_isometry() * relative * local_kf.pose.to_isometry().inverse();
+                    Some(RoomAlignment {
+                        transform: to_coordinate_transform(&local_to_map.inverse()),
+                        map_keyframe_id: map_kf.id,
+                        local_keyframe_id: local_kf.id,
+                        inliers,
+                    })
+                })
+        })
+    }
+
+    /// Requests to recreate the map's anchors in local coordinates
+    pub fn anchor_requests(&self, transform: &CoordinateTransform, local_room: &RoomId) -> Vec<CreateAnchorRequest> {
+        let rotation = UnitQuaternion::from_quaternion(nalgebra::Quaternion::new(
+            transform.rotation[3], transform.rotation[0], transform.rotation[1], transform.rotation[2],
+        ));
+        self.anchors
+            .iter()
+            .map(|a| CreateAnchorRequest {
+                position: WorldPosition {
+                    local: transform.apply(&a.position),
+                    room_id: Some(local_room.clone()),
+                    floor: a.floor,
+                    ..Default::default()
+                },
+                orientation: Some(Quaternion::from_unit(&(rotation * a.orientation.to_unit()))),
+                content: a.content.clone(),
+                label: a.label.clone(),
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    /// The room signature re-expressed in local coordinates
+    pub fn local_signature(&self, transform: &CoordinateTransform) -> RoomSignature {
+        let mut signature = self.signature.clone();
+        for landmark in &mut signature.landmarks {
+            if let Some(p) = landmark.world_pos.as_mut() {
+                *p = transform.apply(p);
+            }
+        }
+        signature.centroid = transform.apply(&signature.centroid);
+
+        // Bounding box of the transformed corners
+        let (lo, hi) = self.signature.bounds;
+        let mut min = LocalCoord::new(f32::MAX, f32::MAX, f32::MAX);
+        let mut max = LocalCoord::new(f32::MIN, f32::MIN, f32::MIN);
+        for corner in 0..8 {
+            let p = transform.apply(&LocalCoord::new(
+                if corner & 1 == 0 { lo.x } else { hi.x },
+                if corner & 2 == 0 { lo.y } else { hi.y },
+                if corner & 4 == 0 { lo.z } else { hi.z },
+            ));
+            min = LocalCoord::new(min.x.min(p.x), min.y.min(p.y), min.z.min(p.z));
+            max = LocalCoord::new(max.x.max(p.x), max.y.max(p.y), max.z.max(p.z));
+        }
+        signature.bounds = (min, max);
+        signature
+    }
+}
+
+impl MapKeyframe {
+    fn to_keyframe(&self, room_id: &RoomId) -> Keyframe {
+        Keyframe {
+            id: self.id,
+            pose: self.pose,
+            features: self.features
+                .iter()
+                .map(|f| VisualFeature {
+                    image_pos: (0.0, 0.0),
+                    world_pos: Some(f.position),
+                    descriptor: FeatureDescriptor { data: f.descriptor },
+                    track_length: 1,
+                    is_landmark: f.is_landmark,
+                })
+                .collect(),
+            timestamp: 0,
+            room_id: Some(room_id.clone()),
+        }
+    }
+}
+
+/// Header fields and payload, as signed
+fn signed_bytes(header: &[u8], payload: &[u8]) -> Vec<u8> {
+    [&header[..38], payload].concat()
+}
+
+fn descriptors(keyframe: &Keyframe) -> impl Iterator<Item = FeatureDescriptor> + '_ {
+    keyframe.features.iter().map(|f| f.descriptor.clone())
+}
+
+fn to_coordinate_transform(pose: &Isometry3<f64>) -> CoordinateTransform {
+    let t = &pose.translation.vector;
+    let q = pose.rotation.into_inner();
+    CoordinateTransform {
+        translation: LocalCoord::new(t.x as f32, t.y as f32, t.z as f32),
+        rotation: [q.i as f32, q.j as f32, q.k as f32, q.w as f32],
+        scale: 1.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spatial::WorldAnchorService;
+    use nalgebra::{Point3, Translation3};
+
+    fn sample_map() -> RoomMap {
+        let features: Vec<VisualFeature> = (0..40u8)
+            .map(|i| VisualFeature {
+                image_pos: (0.5, 0.5),
+                world_pos: (i % 4 != 0).then(|| LocalCoord::new(i as f32 * 0.1, 1.0, 3.0)),
+                descriptor: FeatureDescriptor { data: [i; 32] },
+                track_length: 4,
+                is_landmark: true,
+            })
+            .collect();
+        let map = SlamMap {
+            keyframes: vec![Keyframe {
+                id: 1,
+                pose: CameraPose::identity(),
+                features: features.clone(),
+                timestamp: 0,
+                room_id: None,
+            }],
+            map_points: HashMap::from([(7, LocalCoord::new(1.0, 2.0, 3.0))]),
+            room_signatures: HashMap::new(),
+        };
+        let signature = RoomSignature {
+            room_id: RoomId::new("lab"),
+            landmarks: features,
+            centroid: LocalCoord::default(),
+            bounds: (LocalCoord::new(-1.0, -1.0, -1.0), LocalCoord::new(1.0, 1.0, 1.0)),
+            created_at: 0,
+            reloc_count: 0,
+        };
+        let anchor = WorldAnchorService::in_memory()
+            .create(CreateAnchorRequest {
+                position: WorldPosition::from_local(0.5, 1.0, 2.0),
+                label: Some("whiteboard".to_string()),
+                ..Default::default()
+            }, false)
+            .unwrap();
+        RoomMap::new(RoomId::new("lab"), &map, signature, &[anchor])
+    }
+
+    #[test]
+    fn test_encode_decode_roundtrip() {
+        let key = SigningKey::from_bytes(&[7; 32]);
+        let map = sample_map();
+        assert_eq!(map.keyframes[0].features.len(), 30);
+
+        let bytes = map.encode(&key).unwrap();
+        assert_eq!(&bytes[..4], b"KRMP");
+
+        let (decoded, signer) = RoomMap::decode(&bytes).unwrap();
+        assert_eq!(signer, key.verifying_key());
+        assert_eq!(decoded.room_id, map.room_id);
+        assert_eq!(decoded.keyframes[0].features.len(), 30);
+        assert_eq!(decoded.anchors[0].label.as_deref(), Some("whiteboard"));
+
+        let slam_map = decoded.slam_map();
+        assert_eq!(slam_map.keyframes.len(), 1);
+        assert_eq!(slam_map.map_points.len(), 1);
+        assert!(slam_map.room_signatures.contains_key(&RoomId::new("lab")));
+    }
+
+    #[test]
+    fn test_rejects_tampering_and_unknown_signers() {
+        let key = SigningKey::from_bytes(&[7; 32]);
+        let bytes = sample_map().encode(&key).unwrap();
+
+        let mut tampered = bytes.clone();
+        let last = tampered.len() - 1;
+        tampered[last] ^= 1;
+        assert!(RoomMap::decode(&tampered).is_err());
+
+        let mut future = bytes.clone();
+        future[4] = 2;
+        assert!(RoomMap::decode(&future).unwrap_err().to_string().contains("version"));
+
+        let stranger = SigningKey::from_bytes(&[9; 32]).verifying_key();
+        assert!(RoomMap::decode_trusted(&bytes, &stranger).is_err());
+        assert!(RoomMap::decode_trusted(&bytes, &key.verifying_key()).is_ok());
+    }
+
+    #[test]
+    fn test_anchor_requests_follow_transform() {
+        let map = sample_map();
+        let quarter_turn = UnitQuaternion::from_euler_angles(0.0, std::f64::consts::FRAC_PI_2, 0.0);
+        let transform = to_coordinate_transform(&Isometry3::from_parts(
+            Translation3::new(1.0, 0.0, 0.0),
+            quarter_turn,
+        ));
+
+        let requests = map.anchor_requests(&transform, &RoomId::new("here"));
+        let expected = Isometry3::from_parts(Translation3::new(1.0, 0.0, 0.0), quarter_turn) * Point3::new(0.5, 1.0, 2.0);
+        let local = requests[0].position.local;
+        assert!((local.x as f64 - expected.x).abs() < 1e-5);
+        assert!((local.z as f64 - expected.z).abs() < 1e-5);
+        assert_eq!(requests[0].position.room_id, Some(RoomId::new("here")));
+        assert_eq!(requests[0].label.as_deref(), Some("whiteboard"));
+    }
+}
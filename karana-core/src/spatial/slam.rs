@@ -2,14 +2,38 @@
 //!
 //! Integrates with visual SLAM systems (ORB-SLAM3, ARCore, ARKit)
 //! to provide spatial tracking and relocalization.
+//!
+//! `SlamEngine` runs its own monocular front end: ORB features (`orb`) are matched
+//! against the previous frame and the camera motion is recovered from the essential
+//! matrix (`epipolar`).
+//!
+//! `SlamSession` closes loops over its keyframes (`loop_closure`): when the camera
+//! comes back to a mapped place the pose graph is optimized, and anchors attached
+//! to the session are moved with the keyframes they were placed near.
 
 use anyhow::{anyhow, Result};
+use nalgebra::{Isometry3, Translation3, UnitQuaternion, Vector2, Vector3};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::time::{SystemTime, UNIX_EPOCH};
 
+use super::anchor::{AnchorId, Quaternion, SpatialAnchor};
+use super::anchor_service::WorldAnchorService;
+use super::epipolar::{estimate_relative_pose, RansacConfig, RelativePose};
+use super::loop_closure::{LoopCloser, LoopClosure, LoopClosureConfig};
+use super::orb::{match_descriptors, GrayImage, OrbExtractor};
+use super::room_map::{RoomAlignment, RoomMap};
 use super::world_coords::{CoordinateTransform, LocalCoord, RoomId, WorldPosition};
 
+/// Max Hamming distance for two ORB descriptors to match
+const MATCH_MAX_DISTANCE: u32 = 64;
+/// Nearest match must beat the second nearest by this ratio
+const MATCH_RATIO: f32 = 0.8;
+/// RANSAC inlier threshold on the epipolar error (pixels)
+const RANSAC_THRESHOLD_PX: f32 = 1.5;
+/// Frames a feature must be tracked for to become a landmark
+const LANDMARK_TRACK_LENGTH: u32 = 3;
+
 // ============================================================================
 // SLAM STATES
 // ============================================================================
@@ -94,17 +118,10 @@ impl RoomSignature {
     /// Match quality against a set of observed features
     /// Returns (matched_count, total_landmarks, confidence)
     pub fn match_features(&self, observed: &[VisualFeature]) -> (usize, usize, f32) {
-        const MATCH_THRESHOLD: u32 = 64; // Max hamming distance for match
-        
-        let mut matched = 0;
-        for landmark in &self.landmarks {
-            for obs in observed {
-                if landmark.descriptor.distance_to(&obs.descriptor) < MATCH_THRESHOLD {
-                    matched += 1;
-                    break;
-                }
-            }
-        }
+        // A landmark counts if its nearest observed descriptor is close and unambiguous
+        let landmarks: Vec<FeatureDescriptor> = self.landmarks.iter().map(|f| f.descriptor.clone()).collect();
+        let observed: Vec<FeatureDescriptor> = observed.iter().map(|f| f.descriptor.clone()).collect();
+        let matched = match_descriptors(&landmarks, &observed, MATCH_MAX_DISTANCE, MATCH_RATIO).len();
         
         let total = self.landmarks.len();
         let confidence = if total > 0 {
@@ -157,6 +174,45 @@ impl CameraPose {
             orientation: [0.0, 0.0, 0.0, 1.0],
         }
     }
+    
+    /// Orientation as a rotation from camera to world coordinates
+    pub fn rotation(&self) -> UnitQuaternion<f64> {
+        let [x, y, z, w] = self.orientation.map(|v| v as f64);
+        UnitQuaternion::from_quaternion(nalgebra::Quaternion::new(w, x, y, z))
+    }
+    
+    /// Camera-to-world transform
+    pub fn to_isometry(&self) -> Isometry3<f64> {
+        let p = &self.position;
+        Isometry3::from_parts(Translation3::new(p.x as f64, p.y as f64, p.z as f64), self.rotation())
+    }
+    
+    /// Pose from a camera-to-world transform
+    pub fn from_isometry(pose: &Isometry3<f64>) -> Self {
+        let t = &pose.translation.vector;
+        let q = pose.rotation.into_inner();
+        Self {
+            position: LocalCoord::new(t.x as f32, t.y as f32, t.z as f32),
+            orientation: [q.i as f32, q.j as f32, q.k as f32, q.w as f32],
+        }
+    }
+    
+    /// Pose after the camera moves by `motion` (previous-to-current camera
+    /// coordinates), scaling its unit translation to `baseline_m` metres
+    pub fn compose_motion(&self, motion: &RelativePose, baseline_m: f32) -> Self {
+        let rotation = self.rotation() * motion.rotation.inverse();
+        let centre_offset: Vector3<f64> = -(motion.rotation.inverse() * motion.translation) * baseline_m as f64;
+        let offset = self.rotation() * centre_offset;
+        let q = rotation.into_inner();
+        Self {
+            position: LocalCoord::new(
+                self.position.x + offset.x as f32,
+                self.position.y + offset.y as f32,
+                self.position.z + offset.z as f32,
+            ),
+            orientation: [q.i as f32, q.j as f32, q.k as f32, q.w as f32],
+        }
+    }
 }
 
 /// Alias for CameraPose for API compatibility
@@ -202,6 +258,8 @@ pub struct SlamConfig {
     pub keyframe_distance: f32,
     /// Enable loop closure detection
     pub enable_loop_closure: bool,
+    /// ORB features extracted per frame
+    pub max_features: usize,
 }
 
 impl Default for SlamConfig {
@@ -212,6 +270,7 @@ impl Default for SlamConfig {
             min_init_features: 50,
             keyframe_distance: 0.5,
             enable_loop_closure: true,
+            max_features: 500,
         }
     }
 }
@@ -220,6 +279,13 @@ impl Default for SlamConfig {
 // SLAM ENGINE
 // ============================================================================
 
+/// Features of the previous frame, kept for frame-to-frame matching
+struct TrackedFrame {
+    /// Normalized image coordinates
+    points: Vec<Vector2<f64>>,
+    features: Vec<VisualFeature>,
+}
+
 /// Main SLAM engine that wraps SlamSession with additional features
 pub struct SlamEngine {
     /// Configuration
@@ -228,15 +294,30 @@ pub struct SlamEngine {
     session: SlamSession,
     /// Current SLAM map
     map: SlamMap,
+    /// ORB front end
+    extractor: OrbExtractor,
+    ransac: RansacConfig,
+    last_frame: Option<TrackedFrame>,
+    last_motion: Option<RelativePose>,
 }
 
 impl SlamEngine {
     /// Create a new SLAM engine
     pub fn new(config: SlamConfig) -> Self {
+        let extractor = OrbExtractor::new(super::orb::OrbConfig {
+            max_features: config.max_features,
+            ..Default::default()
+        });
+        let mut session = SlamSession::new();
+        session.set_loop_closure(config.enable_loop_closure);
         Self {
             config,
-            session: SlamSession::new(),
+            session,
             map: SlamMap::default(),
+            extractor,
+            ransac: RansacConfig::default(),
+            last_frame: None,
+            last_motion: None,
         }
     }
     
@@ -245,10 +326,45 @@ impl SlamEngine {
         self.session.current_pose
     }
     
-    /// Track a camera frame
+    /// Track a camera frame. Monocular tracking recovers rotation, but translation
+    /// only up to scale, so the position is left alone; use `track_with_baseline`
+    /// when the distance moved is known (e.g. from the IMU).
     pub fn track(&mut self, frame: &super::CameraFrame) -> Result<Pose> {
-        // Extract features from frame (simplified)
-        let features = self.extract_features(frame);
+        self.track_frame(frame, None)
+    }
+    
+    /// Track a camera frame, moving `baseline_m` metres along the recovered direction
+    pub fn track_with_baseline(&mut self, frame: &super::CameraFrame, baseline_m: f32) -> Result<Pose> {
+        self.track_frame(frame, Some(baseline_m))
+    }
+    
+    fn track_frame(&mut self, frame: &super::CameraFrame, baseline_m: Option<f32>) -> Result<Pose> {
+        let (points, mut features) = self.extract_features(frame)?;
+        self.ransac.threshold = (RANSAC_THRESHOLD_PX / frame.intrinsics.unwrap_or_default().fx) as f64;
+        
+        // Frame-to-frame motion from the essential matrix
+        self.last_motion = None;
+        if let Some(last) = &self.last_frame {
+            let current: Vec<FeatureDescriptor> = features.iter().map(|f| f.descriptor.clone()).collect();
+            let previous: Vec<FeatureDescriptor> = last.features.iter().map(|f| f.descriptor.clone()).collect();
+            let matches = match_descriptors(&current, &previous, MATCH_MAX_DISTANCE, MATCH_RATIO);
+            
+            let points1: Vec<Vector2<f64>> = matches.iter().map(|m| last.points[m.train]).collect();
+            let points2: Vec<Vector2<f64>> = matches.iter().map(|m| points[m.query]).collect();
+            if let Some(motion) = estimate_relative_pose(&points1, &points2, &self.ransac) {
+                for (m, inlier) in matches.iter().zip(&motion.inliers) {
+                    if *inlier {
+                        let feature = &mut features[m.query];
+                        feature.track_length = last.features[m.train].track_length + 1;
+                        feature.is_landmark = feature.track_length >= LANDMARK_TRACK_LENGTH;
+                    }
+                }
+                self.session.current_pose = self.session.current_pose.compose_motion(&motion, baseline_m.unwrap_or(0.0));
+                self.last_motion = Some(motion);
+            }
+        }
+        
+        self.last_frame = Some(TrackedFrame { points, features: features.clone() });
         
         // Process through session
         self.session.process_frame(features)?;
@@ -256,33 +372,48 @@ impl SlamEngine {
         Ok(self.session.current_pose)
     }
     
-    /// Extract features from a camera frame
-    fn extract_features(&self, frame: &super::CameraFrame) -> Vec<VisualFeature> {
-        // Simplified feature extraction
-        // Real implementation would use ORB-SLAM3 or similar
-        let num_features = (frame.width * frame.height / 1000) as usize;
-        let mut features = Vec::with_capacity(num_features.min(500));
+    /// ORB features of a camera frame, with their normalized image coordinates
+    fn extract_features(&self, frame: &super::CameraFrame) -> Result<(Vec<Vector2<f64>>, Vec<VisualFeature>)> {
+        let image = GrayImage::from_frame(frame)?;
+        let intrinsics = frame.intrinsics.unwrap_or_default();
+        let orb = self.extractor.extract(&image);
         
-        for i in 0..num_features.min(500) {
-            let x = (i % frame.width as usize) as f32 / frame.width as f32;
-            let y = (i / frame.width as usize) as f32 / frame.height as f32;
-            
-            let mut descriptor = [0u8; 32];
-            let idx = i * 3;
-            if idx + 32 <= frame.data.len() {
-                descriptor.copy_from_slice(&frame.data[idx..idx + 32]);
-            }
-            
-            features.push(VisualFeature {
-                image_pos: (x, y),
+        let points = orb.keypoints.iter().map(|kp| intrinsics.normalize(kp.x, kp.y)).collect();
+        let features = orb.keypoints.iter().zip(orb.descriptors)
+            .map(|(kp, descriptor)| VisualFeature {
+                image_pos: (kp.x / frame.width as f32, kp.y / frame.height as f32),
                 world_pos: None,
-                descriptor: FeatureDescriptor { data: descriptor },
+                descriptor,
                 track_length: 1,
-                is_landmark: i % 10 == 0, // Every 10th is a landmark
-            });
-        }
-        
-        features
+                is_landmark: false,
+            })
+            .collect();
+        Ok((points, features))
+    }
+    
+    /// Attach an anchor to the nearest keyframe, so loop closures move it along
+    pub fn attach_anchor(&mut self, anchor: &SpatialAnchor) -> Result<()> {
+        self.session.attach_anchor(anchor)
+    }
+    
+    /// Stop moving an anchor with the map
+    pub fn detach_anchor(&mut self, anchor_id: AnchorId) -> bool {
+        self.session.detach_anchor(anchor_id)
+    }
+    
+    /// Number of attached anchors
+    pub fn attached_anchor_count(&self) -> usize {
+        self.session.attached_anchor_count()
+    }
+    
+    /// Push anchor corrections from loop closures into the anchor service
+    pub fn apply_anchor_corrections(&mut self, service: &WorldAnchorService) -> Result<usize> {
+        self.session.apply_anchor_corrections(service)
+    }
+    
+    /// Motion recovered for the last tracked frame, if any
+    pub fn last_motion(&self) -> Option<&RelativePose> {
+        self.last_motion.as_ref()
     }
     
     /// Get SLAM state
@@ -299,6 +430,8 @@ impl SlamEngine {
     pub fn reset(&mut self) {
         self.session.reset();
         self.map = SlamMap::default();
+        self.last_frame = None;
+        self.last_motion = None;
     }
 }
 
@@ -312,6 +445,25 @@ impl Default for SlamEngine {
 // SLAM SESSION
 // ============================================================================
 
+/// An anchor pinned to the keyframe it was placed nearest to
+#[derive(Debug, Clone)]
+struct AnchorAttachment {
+    /// Index into the session's keyframes
+    keyframe: usize,
+    /// Anchor pose in the keyframe's camera frame
+    offset: Isometry3<f64>,
+    /// World position the anchor was attached with (room, GPS, floor)
+    position: WorldPosition,
+}
+
+/// New pose of an anchor after a loop closure moved its keyframe
+#[derive(Debug, Clone)]
+pub struct AnchorCorrection {
+    pub anchor_id: AnchorId,
+    pub position: WorldPosition,
+    pub orientation: Quaternion,
+}
+
 /// A SLAM mapping session
 pub struct SlamSession {
     /// Current state
@@ -332,6 +484,12 @@ pub struct SlamSession {
     room_signatures: HashMap<RoomId, RoomSignature>,
     /// Session start time
     started_at: u64,
+    /// Loop detection and pose-graph correction; `None` when disabled
+    loop_closer: Option<LoopCloser>,
+    /// Anchors that follow keyframe corrections
+    anchors: HashMap<AnchorId, AnchorAttachment>,
+    /// Corrections not yet picked up by the owner of the anchors
+    anchor_corrections: HashMap<AnchorId, AnchorCorrection>,
 }
 
 impl Default for SlamSession {
@@ -356,9 +514,26 @@ impl SlamSession {
                 .duration_since(UNIX_EPOCH)
                 .unwrap()
                 .as_secs(),
+            loop_closer: Some(LoopCloser::new(LoopClosureConfig::default())),
+            anchors: HashMap::new(),
+            anchor_corrections: HashMap::new(),
+        }
+    }
+    
+    /// Enable or disable loop closure
+    pub fn set_loop_closure(&mut self, enabled: bool) {
+        if !enabled {
+            self.loop_closer = None;
+        } else if self.loop_closer.is_none() {
+            self.loop_closer = Some(LoopCloser::new(LoopClosureConfig::default()));
         }
     }
     
+    /// Enable loop closure with custom settings
+    pub fn set_loop_closure_config(&mut self, config: LoopClosureConfig) {
+        self.loop_closer = Some(LoopCloser::new(config));
+    }
+    
     /// Process a camera frame
     /// In real implementation, this would call into ORB-SLAM3 or similar
     pub fn process_frame(&mut self, features: Vec<VisualFeature>) -> Result<()> {
@@ -450,9 +625,108 @@ impl SlamSession {
         self.keyframes.push(keyframe);
         self.next_keyframe_id += 1;
         
+        if let Some(closure) = self.loop_closer.as_mut().and_then(|c| c.process(&mut self.keyframes)) {
+            // Tracking continues from the corrected pose
+            self.current_pose = CameraPose::from_isometry(&(closure.correction * self.current_pose.to_isometry()));
+            self.reproject_anchors();
+        }
+        
+        Ok(())
+    }
+    
+    /// Recompute attached anchor poses from their (corrected) keyframes
+    fn reproject_anchors(&mut self) {
+        for (&anchor_id, attachment) in &self.anchors {
+            let Some(keyframe) = self.keyframes.get(attachment.keyframe) else {
+                continue;
+            };
+            let pose = keyframe.pose.to_isometry() * attachment.offset;
+            let t = &pose.translation.vector;
+            let mut position = attachment.position.clone();
+            position.local = LocalCoord::new(t.x as f32, t.y as f32, t.z as f32);
+            let orientation = Quaternion::from_unit(&nalgebra::convert(pose.rotation));
+            self.anchor_corrections.insert(anchor_id, AnchorCorrection { anchor_id, position, orientation });
+        }
+    }
+    
+    /// Attach an anchor to the nearest keyframe, so loop closures move it along
+    /// with the map
+    pub fn attach_anchor(&mut self, anchor: &SpatialAnchor) -> Result<()> {
+        let local = anchor.position.local;
+        let (index, keyframe) = self.keyframes
+            .iter()
+            .enumerate()
+            .min_by(|(_, a), (_, b)| {
+                a.pose.position.distance_to(&local).total_cmp(&b.pose.position.distance_to(&local))
+            })
+            .ok_or_else(|| anyhow!("No keyframes to attach anchor to"))?;
+        
+        let anchor_pose = Isometry3::from_parts(
+            Translation3::new(local.x as f64, local.y as f64, local.z as f64),
+            nalgebra::convert(anchor.orientation.to_unit()),
+        );
+        self.anchors.insert(anchor.id, AnchorAttachment {
+            keyframe: index,
+            offset: keyframe.pose.to_isometry().inverse() * anchor_pose,
+            position: anchor.position.clone(),
+        });
         Ok(())
     }
     
+    /// Stop moving an anchor with the map
+    pub fn detach_anchor(&mut self, anchor_id: AnchorId) -> bool {
+        self.anchor_corrections.remove(&anchor_id);
+        self.anchors.remove(&anchor_id).is_some()
+    }
+    
+    /// Number of attached anchors
+    pub fn attached_anchor_count(&self) -> usize {
+        self.anchors.len()
+    }
+    
+    /// Anchor poses changed by loop closures since the last call
+    pub fn take_anchor_corrections(&mut self) -> Vec<AnchorCorrection> {
+        let mut corrections: Vec<AnchorCorrection> = self.anchor_corrections.drain().map(|(_, c)| c).collect();
+        corrections.sort_by_key(|c| c.anchor_id);
+        corrections
+    }
+    
+    /// Push pending anchor corrections into the anchor service.
+    ///
+    /// Returns how many anchors were moved; anchors the service no longer has
+    /// are detached.
+    pub fn apply_anchor_corrections(&mut self, service: &WorldAnchorService) -> Result<usize> {
+        let mut moved = 0;
+        for correction in self.take_anchor_corrections() {
+            if service.get(correction.anchor_id).is_none() {
+                self.anchors.remove(&correction.anchor_id);
+                continue;
+            }
+            service.update_pose(correction.anchor_id, correction.position, correction.orientation)?;
+            moved += 1;
+        }
+        Ok(moved)
+    }
+    
+    /// Loops closed in this session
+    pub fn loop_closures(&self) -> &[LoopClosure] {
+        self.loop_closer.as_ref().map_or(&[], |c| c.closures())
+    }
+    
+    /// Keyframes in this session
+    pub fn keyframes(&self) -> &[Keyframe] {
+        &self.keyframes
+    }
+    
+    /// Snapshot of the session's map
+    pub fn map(&self) -> SlamMap {
+        SlamMap {
+            keyframes: self.keyframes.clone(),
+            map_points: self.map_points.clone(),
+            room_signatures: self.room_signatures.clone(),
+        }
+    }
+    
     /// Try to relocalize using known room signatures
     fn try_relocalize(&mut self, features: &[VisualFeature]) -> Option<RoomId> {
         let mut best_match: Option<(RoomId, f32)> = None;
@@ -521,6 +795,11 @@ impl SlamSession {
         self.map_points.clear();
         self.current_room = None;
         self.tracking_quality = 0;
+        if let Some(closer) = self.loop_closer.as_mut() {
+            closer.reset();
+        }
+        self.anchors.clear();
+        self.anchor_corrections.clear();
         self.started_at = SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .unwrap()
@@ -533,19 +812,22 @@ impl SlamSession {
             return None;
         }
         
-        // Collect all landmark features
+        // Collect up to 200 landmark features, once per landmark: a corner seen
+        // from several keyframes would otherwise fill the signature with copies
         let mut landmarks: Vec<VisualFeature> = Vec::new();
-        for kf in &self.keyframes {
+        'keyframes: for kf in &self.keyframes {
             for feat in &kf.features {
-                if feat.is_landmark {
+                let seen = landmarks.iter()
+                    .any(|l| l.descriptor.distance_to(&feat.descriptor) <= MATCH_MAX_DISTANCE / 2);
+                if feat.is_landmark && !seen {
                     landmarks.push(feat.clone());
+                    if landmarks.len() == 200 {
+                        break 'keyframes;
+                    }
                 }
             }
         }
         
-        // Limit to top 200 most reliable landmarks
-        landmarks.truncate(200);
-        
         if landmarks.is_empty() {
             return None;
         }
@@ -659,6 +941,36 @@ impl SlamManager {
     pub fn register_room_transform(&mut self, from: RoomId, to: RoomId, transform: CoordinateTransform) {
         self.room_transforms.insert((from, to), transform);
     }
+    
+    /// Save the current room and package it, with the anchors placed in it,
+    /// for another device
+    pub fn export_room(&mut self, room_id: RoomId, anchors: &[SpatialAnchor]) -> Result<RoomMap> {
+        self.save_room(room_id.clone())?;
+        let signature = self.known_rooms[&room_id].clone();
+        Ok(RoomMap::new(room_id, &self.session.map(), signature, anchors))
+    }
+    
+    /// Align a room mapped by another device with the current session.
+    ///
+    /// The room becomes known, so the session can relocalize in it, and the
+    /// transforms between the map's room and `local_room` (the session's frame)
+    /// are registered. `local_room` also becomes the session's room if it had none.
+    pub fn import_room(&mut self, map: &RoomMap, local_room: RoomId) -> Result<RoomAlignment> {
+        let alignment = map.align(&self.session.keyframes)
+            .ok_or_else(|| anyhow!("Room {} does not overlap the current session", map.room_id.0))?;
+        
+        let signature = map.local_signature(&alignment.transform);
+        self.known_rooms.insert(map.room_id.clone(), signature.clone());
+        self.session.register_room(map.room_id.clone(), signature);
+        
+        self.register_room_transform(map.room_id.clone(), local_room.clone(), alignment.transform.clone());
+        self.register_room_transform(local_room.clone(), map.room_id.clone(), alignment.transform.inverse());
+        if self.session.current_room.is_none() {
+            self.session.current_room = Some(local_room);
+        }
+        
+        Ok(alignment)
+    }
 }
 
 // ============================================================================
@@ -668,6 +980,10 @@ impl SlamManager {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use super::super::CameraIntrinsics;
+    use crate::spatial::CreateAnchorRequest;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
     
     fn make_feature(x: f32, y: f32, landmark: bool) -> VisualFeature {
         VisualFeature {
@@ -762,6 +1078,102 @@ mod tests {
         assert!((confidence - 1.0).abs() < 0.001);
     }
     
+    /// Ray-cast a textured room (y down, z forward at identity) as seen by a
+    /// camera at `position` with camera-to-world rotation `rotation`
+    fn render_room(rotation: &UnitQuaternion<f64>, position: &Vector3<f64>, intrinsics: &CameraIntrinsics) -> super::super::CameraFrame {
+        const WIDTH: u32 = 320;
+        const HEIGHT: u32 = 240;
+        const HALF: [f64; 3] = [1.2, 0.8, 3.0];
+        const CELL: f64 = 0.2;
+        
+        let shade = |axis: usize, side: i64, a: f64, b: f64| -> f64 {
+            let (i, j) = ((a / CELL).floor() as i64, (b / CELL).floor() as i64);
+            let mut h = (axis as i64 * 7919 + side * 104_729 + i * 73_856_093 + j * 19_349_663) as u64;
+            h ^= h >> 13;
+            h = h.wrapping_mul(0x5bd1_e995);
+            h ^= h >> 15;
+            30.0 + (h % 200) as f64
+        };
+        
+        let mut data = Vec::with_capacity((WIDTH * HEIGHT * 3) as usize);
+        for v in 0..HEIGHT {
+            for u in 0..WIDTH {
+                // 2x2 supersampling keeps cell edges from aliasing into spurious corners
+                let mut sum = 0.0;
+                for (du, dv) in [(-0.25, -0.25), (0.25, -0.25), (-0.25, 0.25), (0.25, 0.25)] {
+                    let ray = Vector3::new(
+                        ((u as f64 + du) - intrinsics.cx as f64) / intrinsics.fx as f64,
+                        ((v as f64 + dv) - intrinsics.cy as f64) / intrinsics.fy as f64,
+                        1.0,
+                    );
+                    let dir = rotation * ray;
+                    let mut nearest = (f64::INFINITY, 0.0);
+                    for axis in 0..3 {
+                        for sign in [-1.0, 1.0] {
+                            let t = (sign * HALF[axis] - position[axis]) / dir[axis];
+                            if t > 0.0 && t < nearest.0 {
+                                let hit = position + dir * t;
+                                let (a, b) = (hit[(axis + 1) % 3], hit[(axis + 2) % 3]);
+                                nearest = (t, shade(axis, sign as i64, a, b));
+                            }
+                        }
+                    }
+                    sum += nearest.1;
+                }
+                let gray = (sum / 4.0) as u8;
+                data.extend_from_slice(&[gray, gray, gray]);
+            }
+        }
+        
+        super::super::CameraFrame { data, width: WIDTH, height: HEIGHT, timestamp: 0, intrinsics: Some(*intrinsics) }
+    }
+    
+    #[test]
+    fn test_tracks_synthetic_sequence() {
+        let intrinsics = CameraIntrinsics { fx: 260.0, fy: 260.0, cx: 160.0, cy: 120.0 };
+        // Walking towards the far right corner while slowly panning
+        let poses: Vec<(UnitQuaternion<f64>, Vector3<f64>)> = (0..5)
+            .map(|k| {
+                let k = k as f64;
+                let rotation = UnitQuaternion::from_euler_angles(0.05, 0.3 + 0.03 * k, 0.01 * k);
+                let forward = rotation * Vector3::z();
+                (rotation, Vector3::new(-0.3 + 0.05 * k, 0.02 * k, -1.5) + forward * (0.2 * k))
+            })
+            .collect();
+        
+        let mut engine = SlamEngine::new(SlamConfig::default());
+        for (k, (rotation, position)) in poses.iter().enumerate() {
+            let frame = render_room(rotation, position, &intrinsics);
+            let baseline = if k == 0 { 0.0 } else { (position - poses[k - 1].1).norm() as f32 };
+            engine.track_with_baseline(&frame, baseline).unwrap();
+            if k == 0 {
+                assert_eq!(engine.state(), SlamState::Tracking);
+                continue;
+            }
+            
+            // Ground truth relative motion, X2 = R X1 + t
+            let (r1, p1) = &poses[k - 1];
+            let expected_rotation = rotation.inverse() * r1;
+            let expected_direction = (rotation.inverse() * (p1 - position)).normalize();
+            
+            let motion = engine.last_motion().expect("motion recovered");
+            assert!(motion.inlier_count >= 50, "frame {k}: {} inliers", motion.inlier_count);
+            let rotation_error = motion.rotation.angle_to(&expected_rotation).to_degrees();
+            assert!(rotation_error < 1.0, "frame {k}: rotation error {rotation_error:.2}°");
+            let direction_error = motion.translation.angle(&expected_direction).to_degrees();
+            assert!(direction_error < 8.0, "frame {k}: translation direction error {direction_error:.2}°");
+        }
+        
+        // Camera-to-world pose relative to the first frame
+        let (r0, p0) = &poses[0];
+        let (r_last, p_last) = poses.last().unwrap();
+        let expected_position = r0.inverse() * (p_last - p0);
+        let pose = engine.get_current_pose();
+        let position = Vector3::new(pose.position.x as f64, pose.position.y as f64, pose.position.z as f64);
+        assert!((position - expected_position).norm() < 0.05, "drifted to {position:?}, expected {expected_position:?}");
+        assert!(pose.rotation().angle_to(&(r0.inverse() * r_last)).to_degrees() < 2.0);
+    }
+    
     #[test]
     fn test_slam_manager() {
         let mut manager = SlamManager::new();
@@ -777,4 +1189,218 @@ mod tests {
         
         assert_eq!(manager.state(), SlamState::Tracking);
     }
+    
+    #[test]
+    fn test_engine_attaches_anchors() {
+        let mut engine = SlamEngine::default();
+        let service = WorldAnchorService::in_memory();
+        let anchor = service.create(CreateAnchorRequest::default(), false).unwrap();
+        
+        // Nothing to attach to before the first keyframe
+        assert!(engine.attach_anchor(&anchor).is_err());
+        
+        let features: Vec<VisualFeature> = (0..60)
+            .map(|i| make_feature(i as f32 / 60.0, 0.0, true))
+            .collect();
+        engine.session.process_frame(features).unwrap();
+        engine.attach_anchor(&anchor).unwrap();
+        assert_eq!(engine.attached_anchor_count(), 1);
+        assert_eq!(engine.apply_anchor_corrections(&service).unwrap(), 0);
+        
+        assert!(engine.detach_anchor(anchor.id));
+        assert_eq!(engine.attached_anchor_count(), 0);
+    }
+    
+    /// Landmarks on the wall of a round room, each with its own descriptor
+    fn round_room(landmarks: usize, rng: &mut StdRng) -> Vec<(Vector3<f64>, FeatureDescriptor)> {
+        (0..landmarks)
+            .map(|i| {
+                let angle = i as f64 / landmarks as f64 * std::f64::consts::TAU;
+                let radius = rng.gen_range(7.0..8.0);
+                let mut data = [0u8; 32];
+                rng.fill(&mut data);
+                (Vector3::new(radius * angle.cos(), rng.gen_range(-1.0..2.0), radius * angle.sin()), FeatureDescriptor { data })
+            })
+            .collect()
+    }
+    
+    /// Camera on a 4 m circle at `angle`, looking out at the wall
+    fn circle_pose(angle: f64) -> Isometry3<f64> {
+        Isometry3::new(
+            Vector3::new(4.0 * angle.cos(), 0.0, 4.0 * angle.sin()),
+            Vector3::y() * (std::f64::consts::FRAC_PI_2 - angle),
+        )
+    }
+    
+    /// What the camera at `truth` sees, triangulated from where the session thinks it is
+    fn observe(
+        room: &[(Vector3<f64>, FeatureDescriptor)],
+        truth: &Isometry3<f64>,
+        estimate: &Isometry3<f64>,
+        rng: &mut StdRng,
+    ) -> Vec<VisualFeature> {
+        room.iter()
+            .filter_map(|(landmark, descriptor)| {
+                let p = truth.inverse() * nalgebra::Point3::from(*landmark);
+                if p.z < 0.5 || p.x.abs() > 0.7 * p.z {
+                    return None;
+                }
+                let noise = Vector3::new(rng.gen_range(-0.005..0.005), rng.gen_range(-0.005..0.005), rng.gen_range(-0.005..0.005));
+                let world = estimate * (p + noise);
+                let mut data = descriptor.data;
+                for _ in 0..2 {
+                    let bit = rng.gen_range(0..256);
+                    data[bit / 8] ^= 1 << (bit % 8);
+                }
+                Some(VisualFeature {
+                    image_pos: ((p.x / p.z) as f32, (p.y / p.z) as f32),
+                    world_pos: Some(LocalCoord::new(world.x as f32, world.y as f32, world.z as f32)),
+                    descriptor: FeatureDescriptor { data },
+                    track_length: 5,
+                    is_landmark: true,
+                })
+            })
+            .collect()
+    }
+    
+    struct LoopWalk {
+        /// Distance between the final estimated and true camera positions
+        pose_error: f64,
+        /// Distance between the anchor (as the service has it) and where it was placed
+        anchor_error: f64,
+        closures: usize,
+        keyframes: usize,
+    }
+    
+    /// Walk once round the room and a little past the start, with odometry that
+    /// turns slightly too far every step. An anchor is placed halfway round.
+    fn walk_round_room(loop_closure: bool) -> LoopWalk {
+        let mut rng = StdRng::seed_from_u64(21);
+        let room = round_room(1000, &mut rng);
+        let service = WorldAnchorService::in_memory();
+        let mut session = SlamSession::new();
+        session.set_loop_closure(loop_closure);
+        
+        let step = 0.25 / 4.0;
+        let drift = Isometry3::rotation(Vector3::y() * 0.005);
+        let frames = ((std::f64::consts::TAU + 0.6) / step) as usize;
+        let halfway = frames / 2;
+        
+        session.current_pose = CameraPose::from_isometry(&circle_pose(0.0));
+        let mut anchor = None;
+        for i in 0..=frames {
+            let truth = circle_pose(i as f64 * step);
+            if i > 0 {
+                let odometry = circle_pose((i - 1) as f64 * step).inverse() * truth * drift;
+                session.current_pose = CameraPose::from_isometry(&(session.current_pose.to_isometry() * odometry));
+            }
+            let features = observe(&room, &truth, &session.current_pose.to_isometry(), &mut rng);
+            session.process_frame(features).unwrap();
+            
+            if i == halfway {
+                // A note one metre in front of the camera
+                let placed = session.current_pose.to_isometry() * nalgebra::Point3::new(0.0, 0.0, 1.0);
+                let created = service.create(CreateAnchorRequest {
+                    position: WorldPosition::from_local(placed.x as f32, placed.y as f32, placed.z as f32),
+                    ..Default::default()
+                }, false).unwrap();
+                session.attach_anchor(&created).unwrap();
+                anchor = Some((created.id, truth * nalgebra::Point3::new(0.0, 0.0, 1.0)));
+            }
+        }
+        session.apply_anchor_corrections(&service).unwrap();
+        
+        let truth = circle_pose(frames as f64 * step);
+        let (anchor_id, anchor_truth) = anchor.unwrap();
+        let anchor_local = service.get(anchor_id).unwrap().position.local;
+        LoopWalk {
+            pose_error: (session.current_pose.to_isometry().translation.vector - truth.translation.vector).norm(),
+            anchor_error: (Vector3::new(anchor_local.x as f64, anchor_local.y as f64, anchor_local.z as f64) - anchor_truth.coords).norm(),
+            closures: session.loop_closures().len(),
+            keyframes: session.keyframe_count(),
+        }
+    }
+    
+    #[test]
+    fn test_loop_closure_corrects_drift() {
+        let drifting = walk_round_room(false);
+        let closed = walk_round_room(true);
+        
+        assert_eq!(drifting.closures, 0);
+        assert!(drifting.pose_error > 1.5, "drift only {:.2} m", drifting.pose_error);
+        assert!(closed.closures >= 1);
+        assert_eq!(closed.keyframes, drifting.keyframes);
+        assert!(closed.pose_error < 0.2, "pose still {:.2} m off", closed.pose_error);
+    }
+    
+    #[test]
+    fn test_loop_closure_moves_attached_anchors() {
+        let drifting = walk_round_room(false);
+        let closed = walk_round_room(true);
+        
+        assert!(drifting.anchor_error > 1.0, "anchor drift only {:.2} m", drifting.anchor_error);
+        assert!(
+            closed.anchor_error < drifting.anchor_error / 3.0,
+            "anchor {:.2} m -> {:.2} m", drifting.anchor_error, closed.anchor_error
+        );
+    }
+    
+    /// Walk from `from` to `to` radians round the room without drift. `origin`
+    /// is where this device's session frame sits in the room.
+    fn survey(session: &mut SlamSession, room: &[(Vector3<f64>, FeatureDescriptor)], origin: &Isometry3<f64>, from: f64, to: f64, rng: &mut StdRng) {
+        let step = 0.25 / 4.0;
+        let frames = ((to - from) / step) as usize;
+        for i in 0..=frames {
+            let truth = circle_pose(from + i as f64 * step);
+            session.current_pose = CameraPose::from_isometry(&(origin.inverse() * truth));
+            let features = observe(room, &truth, &session.current_pose.to_isometry(), rng);
+            session.process_frame(features).unwrap();
+        }
+    }
+    
+    #[test]
+    fn test_second_device_relocalizes_in_shared_room() {
+        let mut rng = StdRng::seed_from_u64(25);
+        // Dense enough that one view covers most of the room signature
+        let room = round_room(2000, &mut rng);
+        let key = ed25519_dalek::SigningKey::from_bytes(&[5; 32]);
+        let lounge = RoomId::new("lounge");
+        
+        // The first device maps half the room and leaves a note on the wall
+        let mut first = SlamManager::new();
+        survey(&mut first.session, &room, &Isometry3::identity(), 0.0, std::f64::consts::PI, &mut rng);
+        let note = WorldAnchorService::in_memory().create(CreateAnchorRequest {
+            position: WorldPosition::from_local(0.0, 1.0, 7.0),
+            label: Some("note".to_string()),
+            ..Default::default()
+        }, false).unwrap();
+        let bytes = first.export_room(lounge.clone(), &[note]).unwrap().encode(&key).unwrap();
+        
+        // The second device starts somewhere else, facing another way
+        let origin = Isometry3::new(Vector3::new(1.5, 0.0, -2.0), Vector3::y() * 0.7);
+        let mut second = SlamManager::new();
+        survey(&mut second.session, &room, &origin, 1.0, 1.8, &mut rng);
+        
+        let map = RoomMap::decode_trusted(&bytes, &key.verifying_key()).unwrap();
+        let here = RoomId::new("second-device");
+        let alignment = second.import_room(&map, here.clone()).unwrap();
+        assert!(alignment.inliers >= 20);
+        assert!(second.get_room_transform(&lounge, &here).is_some());
+        assert_eq!(second.current_position().room_id, Some(here.clone()));
+        
+        // The note lands where it really is, in the second device's frame
+        let request = &map.anchor_requests(&alignment.transform, &here)[0];
+        let expected = origin.inverse() * nalgebra::Point3::new(0.0, 1.0, 7.0);
+        let local = request.position.local;
+        let error = (Vector3::new(local.x as f64, local.y as f64, local.z as f64) - expected.coords).norm();
+        assert!(error < 0.05, "note {:.3} m off", error);
+        
+        // After losing tracking, the second device relocalizes in the shared room
+        second.session.state = SlamState::Relocalization;
+        let truth = circle_pose(0.2);
+        let features = observe(&room, &truth, &(origin.inverse() * truth), &mut rng);
+        second.process_frame(features).unwrap();
+        assert_eq!(second.state(), SlamState::Tracking);
+        assert_eq!(second.current_position().room_id, Some(lounge));
+    }
 }
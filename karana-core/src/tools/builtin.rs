@@ -0,0 +1,272 @@
+// Phase 54.2: Built-in universal tools
+// Simulated OS, web, app, creative, memory and sensor tools used by the agentic reasoner
+use anyhow::Result;
+use async_trait::async_trait;
+
+use crate::privacy::Permission;
+use super::protocol::{Tool, ToolArgs, ToolOutput};
+use super::registry::ToolRegistry;
+use super::schema::{ParamType, ToolParameter};
+
+/// Register the built-in universal tools
+pub fn register_builtin_tools(registry: &mut ToolRegistry) {
+    registry.register(OSExecTool);
+    registry.register(WebAPITool);
+    registry.register(AppProxyTool);
+    registry.register(GenCreativeTool);
+    registry.register(MemoryRAGTool);
+    registry.register(HealthSensorTool);
+}
+
+fn query_param() -> ToolParameter {
+    ToolParameter::optional("query", ParamType::String, "Natural language request")
+}
+
+fn context_param() -> ToolParameter {
+    ToolParameter::optional("context", ParamType::String, "Context from the user or a previous step")
+}
+
+pub struct OSExecTool;
+
+#[async_trait]
+impl Tool for OSExecTool {
+    fn name(&self) -> &str { "os_exec" }
+    fn description(&self) -> &str { "Execute OS-level commands and system configuration" }
+
+    fn parameters(&self) -> Vec<ToolParameter> {
+        vec![
+            query_param(),
+            ToolParameter::optional("intent", ParamType::String, "System action to perform"),
+            context_param(),
+        ]
+    }
+
+    fn permissions(&self) -> Vec<Permission> {
+        vec![Permission::SystemSettings]
+    }
+
+    async fn execute(&self, args: &ToolArgs) -> Result<ToolOutput> {
+        let intent = args.get_string("query")
+            .or_else(|| args.get_string("intent"))
+            .unwrap_or_else(|| "unknown".to_string());
+
+        // Simplified OS execution (in production: integrate with Monad)
+        let output = if intent.contains("battery") {
+            "Battery optimization enabled - Expected +15% runtime".to_string()
+        } else if intent.contains("brightness") {
+            "Brightness adjusted to 70% - Adaptive mode enabled".to_string()
+        } else if intent.contains("volume") {
+            "Volume set to 60%".to_string()
+        } else {
+            format!("OS action queued: {}", intent)
+        };
+        Ok(ToolOutput::new(output, 0.9))
+    }
+}
+
+pub struct WebAPITool;
+
+#[async_trait]
+impl Tool for WebAPITool {
+    fn name(&self) -> &str { "web_api" }
+    fn description(&self) -> &str { "Search web or query external APIs" }
+
+    fn parameters(&self) -> Vec<ToolParameter> {
+        vec![query_param(), context_param()]
+    }
+
+    fn permissions(&self) -> Vec<Permission> {
+        vec![Permission::Internet]
+    }
+
+    async fn execute(&self, args: &ToolArgs) -> Result<ToolOutput> {
+        let query = args.get_string("query")
+            .or_else(|| args.get_string("context"))
+            .unwrap_or_default();
+
+        // Simplified web search (in production: integrate with Brave API or local RAG)
+        let output = if query.to_lowercase().contains("weather") {
+            "Paris: 15°C, 80% chance of rain, wind 12 km/h".to_string()
+        } else if query.to_lowercase().contains("quantum") {
+            "Quantum computing ethics debate: Balance progress with safety, governance needed".to_string()
+        } else {
+            format!("Web search results for: {}", query)
+        };
+        Ok(ToolOutput::new(output, 0.8))
+    }
+}
+
+pub struct AppProxyTool;
+
+#[async_trait]
+impl Tool for AppProxyTool {
+    fn name(&self) -> &str { "app_proxy" }
+    fn description(&self) -> &str { "Launch and proxy applications (PWA/native)" }
+
+    fn parameters(&self) -> Vec<ToolParameter> {
+        vec![
+            ToolParameter::optional("name", ParamType::String, "Application to launch"),
+            query_param(),
+            context_param(),
+        ]
+    }
+
+    async fn execute(&self, args: &ToolArgs) -> Result<ToolOutput> {
+        let name = args.get_string("name")
+            .or_else(|| args.get_string("query"))
+            .unwrap_or_else(|| "unknown".to_string());
+
+        // Simplified app opening (in production: integrate with Bazaar/PWA system)
+        let output = if name.to_lowercase().contains("code") || name.to_lowercase().contains("vscode") {
+            "VS Code opened in PWA container - Ready for development".to_string()
+        } else {
+            format!("App '{}' launched", name)
+        };
+        Ok(ToolOutput::new(output, 0.9))
+    }
+}
+
+pub struct GenCreativeTool;
+
+#[async_trait]
+impl Tool for GenCreativeTool {
+    fn name(&self) -> &str { "gen_creative" }
+    fn description(&self) -> &str { "Generate creative content (poems, stories, ideas)" }
+
+    fn parameters(&self) -> Vec<ToolParameter> {
+        vec![
+            query_param(),
+            ToolParameter::optional("topic", ParamType::String, "Subject of the piece"),
+            context_param(),
+        ]
+    }
+
+    async fn execute(&self, args: &ToolArgs) -> Result<ToolOutput> {
+        let topic = args.get_string("query")
+            .or_else(|| args.get_string("topic"))
+            .unwrap_or_else(|| "creativity".to_string());
+
+        // Simplified creative generation (in production: integrate with Phi-3)
+        let output = if topic.to_lowercase().contains("love") {
+            "Roses bloom in crimson light, Hearts entwined through day and night, Love's embrace, forever bright, Two souls merged in pure delight.".to_string()
+        } else if topic.to_lowercase().contains("quantum") {
+            "In superposition's dance we dwell, Where particles their secrets tell, Entangled states that weave and swell, Reality's enigmatic spell.".to_string()
+        } else {
+            format!("Creative content generated on: {}", topic)
+        };
+        Ok(ToolOutput::new(output, 0.75))
+    }
+}
+
+pub struct MemoryRAGTool;
+
+#[async_trait]
+impl Tool for MemoryRAGTool {
+    fn name(&self) -> &str { "memory_rag" }
+    fn description(&self) -> &str { "Retrieve relevant context from user history" }
+
+    fn parameters(&self) -> Vec<ToolParameter> {
+        vec![query_param(), context_param()]
+    }
+
+    fn permissions(&self) -> Vec<Permission> {
+        vec![Permission::Storage]
+    }
+
+    async fn execute(&self, args: &ToolArgs) -> Result<ToolOutput> {
+        let query = args.get_string("query")
+            .or_else(|| args.get_string("context"))
+            .unwrap_or_default();
+
+        // Simplified RAG retrieval (in production: integrate with RocksDB vector store)
+        if query.to_lowercase().contains("umbrella") {
+            Ok(ToolOutput::new("Historical context: Rain pattern - 3/5 days last week in Paris. User has allergy notes.", 0.85))
+        } else {
+            Ok(ToolOutput::new("No relevant historical context found", 0.5))
+        }
+    }
+}
+
+pub struct HealthSensorTool;
+
+#[async_trait]
+impl Tool for HealthSensorTool {
+    fn name(&self) -> &str { "health_sensor" }
+    fn description(&self) -> &str { "Read health and biometric sensors" }
+
+    fn parameters(&self) -> Vec<ToolParameter> {
+        vec![
+            ToolParameter::optional("sensor", ParamType::String, "Sensor to read (heart_rate, steps, ...)"),
+            query_param(),
+        ]
+    }
+
+    fn permissions(&self) -> Vec<Permission> {
+        vec![Permission::HealthData]
+    }
+
+    async fn execute(&self, args: &ToolArgs) -> Result<ToolOutput> {
+        let sensor = args.get_string("sensor")
+            .or_else(|| args.get_string("query"))
+            .unwrap_or_else(|| "heart_rate".to_string());
+
+        // Simplified sensor reading (in production: integrate with IMU/biosensors)
+        let output = if sensor.contains("heart") {
+            "Heart rate: 72 bpm (normal range)".to_string()
+        } else if sensor.contains("step") {
+            "Steps today: 8,432 - 67% of daily goal".to_string()
+        } else {
+            format!("Sensor '{}' reading: nominal", sensor)
+        };
+        Ok(ToolOutput::new(output, 0.95))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::ToolError;
+    use serde_json::json;
+
+    fn registry() -> ToolRegistry {
+        let mut registry = ToolRegistry::new();
+        register_builtin_tools(&mut registry);
+        registry
+    }
+
+    #[tokio::test]
+    async fn test_tool_registry() {
+        let registry = registry();
+
+        let result = registry.call_tool("web_api", &json!({
+            "query": "weather Paris"
+        })).await.unwrap();
+
+        assert!(result.contains("Paris"));
+        assert!(result.contains("°C"));
+    }
+
+    #[tokio::test]
+    async fn test_creative_generation() {
+        let registry = registry();
+
+        let result = registry.call_tool("gen_creative", &json!({
+            "query": "poem about love"
+        })).await.unwrap();
+
+        assert!(result.len() > 50); // Substantial content
+        assert!(result.to_lowercase().contains("love") || result.to_lowercase().contains("heart"));
+    }
+
+    #[tokio::test]
+    async fn test_health_sensor_requires_consent() {
+        let registry = registry();
+
+        let err = registry.call_tool("health_sensor", &json!({})).await.unwrap_err();
+        assert!(matches!(err.downcast_ref::<ToolError>(), Some(ToolError::PermissionDenied { .. })));
+
+        registry.grant_permission(Permission::HealthData);
+        let result = registry.call_tool("health_sensor", &json!({"sensor": "steps"})).await.unwrap();
+        assert!(result.contains("Steps today"));
+    }
+}
@@ -1,4 +1,12 @@
 // Tool registry module
+// One tool-calling protocol shared by the ReAct agent, the agentic reasoner,
+// the Oracle tool bridge and the voice assistant
+pub mod protocol;
+pub mod schema;
 pub mod registry;
+pub mod builtin;
 
-pub use registry::{ToolRegistry, ToolExecutor};
+pub use protocol::{Tool, ToolArgs, ToolCall, ToolError, ToolOutput, ToolResult, UndoState};
+pub use schema::{ParamType, ToolParameter, ToolSpec};
+pub use registry::{ToolRegistry, DEFAULT_TOOL_TIMEOUT};
+pub use builtin::register_builtin_tools;
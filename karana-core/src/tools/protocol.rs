@@ -0,0 +1,270 @@
+// Phase 54.2: Tool-calling protocol
+// The single `Tool` trait plus the argument, call, result and error types
+// shared by every caller of the registry
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::fmt;
+use std::time::Duration;
+
+use crate::privacy::Permission;
+use super::schema::{ToolParameter, ToolSpec};
+
+/// Tool trait - implement once, register once, callable from every agent
+#[async_trait]
+pub trait Tool: Send + Sync {
+    /// Tool name (used for routing)
+    fn name(&self) -> &str;
+
+    /// Human-readable description, shown to language models
+    fn description(&self) -> &str;
+
+    /// Parameter declarations, validated before `execute` is called
+    fn parameters(&self) -> Vec<ToolParameter> {
+        Vec::new()
+    }
+
+    /// Permissions that must be granted for the tool to run
+    fn permissions(&self) -> Vec<Permission> {
+        Vec::new()
+    }
+
+    /// Execution limit overriding the registry default
+    fn timeout(&self) -> Option<Duration> {
+        None
+    }
+
+    /// Execute the tool with validated arguments
+    async fn execute(&self, args: &ToolArgs) -> Result<ToolOutput>;
+
+    /// Undo a previous execution (optional)
+    async fn undo(&self, _undo_state: &UndoState) -> Result<ToolOutput> {
+        Err(anyhow!("Undo not supported for this tool"))
+    }
+
+    /// Check if tool supports undo
+    fn supports_undo(&self) -> bool {
+        false
+    }
+
+    /// Full declaration of the tool
+    fn spec(&self) -> ToolSpec {
+        ToolSpec {
+            name: self.name().to_string(),
+            description: self.description().to_string(),
+            parameters: self.parameters(),
+            permissions: self.permissions(),
+            supports_undo: self.supports_undo(),
+            timeout: self.timeout(),
+        }
+    }
+}
+
+/// Tool arguments (a JSON object keyed by parameter name)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ToolArgs {
+    pub params: Map<String, Value>,
+}
+
+impl ToolArgs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Build from a JSON object; `null` gives empty arguments
+    pub fn from_value(value: Value) -> Result<Self> {
+        match value {
+            Value::Object(params) => Ok(Self { params }),
+            Value::Null => Ok(Self::new()),
+            other => Err(anyhow!("Tool arguments must be a JSON object, got {}", other)),
+        }
+    }
+
+    pub fn add<T: Serialize>(&mut self, name: impl Into<String>, value: T) {
+        self.params.insert(
+            name.into(),
+            serde_json::to_value(value).unwrap_or(Value::Null),
+        );
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.params.get(name)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.params.contains_key(name)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.params.is_empty()
+    }
+
+    pub fn get_string(&self, name: &str) -> Option<String> {
+        self.params.get(name)?.as_str().map(|s| s.to_string())
+    }
+
+    pub fn get_f64(&self, name: &str) -> Option<f64> {
+        self.params.get(name)?.as_f64()
+    }
+
+    pub fn get_i64(&self, name: &str) -> Option<i64> {
+        self.params.get(name)?.as_i64()
+    }
+
+    pub fn get_bool(&self, name: &str) -> Option<bool> {
+        self.params.get(name)?.as_bool()
+    }
+
+    /// String argument that must be present
+    pub fn require_string(&self, name: &str) -> Result<String> {
+        self.get_string(name)
+            .ok_or_else(|| anyhow!("Missing or invalid argument: {}", name))
+    }
+
+    pub fn to_value(&self) -> Value {
+        Value::Object(self.params.clone())
+    }
+
+    pub fn into_map(self) -> Map<String, Value> {
+        self.params
+    }
+}
+
+/// A request to run a tool, as produced by agents and language models.
+///
+/// Wire format is `{"tool": "weather", "arguments": {"location": "Paris"}}`;
+/// `tool_name`/`name` and `args`/`parameters` are accepted when parsing.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolCall {
+    #[serde(rename = "tool", alias = "tool_name", alias = "name")]
+    pub tool_name: String,
+    #[serde(rename = "arguments", alias = "args", alias = "parameters", default)]
+    pub args: Value,
+}
+
+impl ToolCall {
+    pub fn new(tool_name: impl Into<String>, args: Value) -> Self {
+        Self {
+            tool_name: tool_name.into(),
+            args,
+        }
+    }
+
+    /// Parse a JSON tool call
+    pub fn from_json(text: &str) -> Result<Self> {
+        serde_json::from_str(text.trim())
+            .map_err(|e| anyhow!("Invalid tool call '{}': {}", text.trim(), e))
+    }
+}
+
+/// What a tool returns; the registry turns it into a `ToolResult`
+#[derive(Debug, Clone)]
+pub struct ToolOutput {
+    pub output: String,
+    pub confidence: f32,
+    pub undo_state: Option<UndoState>,
+}
+
+impl ToolOutput {
+    pub fn new(output: impl Into<String>, confidence: f32) -> Self {
+        Self {
+            output: output.into(),
+            confidence,
+            undo_state: None,
+        }
+    }
+
+    /// Record how to reverse this execution
+    pub fn with_undo(mut self, operation: &str, previous_value: Value) -> Self {
+        self.undo_state = Some(UndoState {
+            tool_name: String::new(), // Filled in by the registry
+            previous_value,
+            operation: operation.to_string(),
+        });
+        self
+    }
+}
+
+/// Tool execution result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolResult {
+    pub success: bool,
+    pub output: String,
+    pub confidence: f32,
+    pub execution_id: String,
+    pub tool_name: String,
+    pub undo_state: Option<UndoState>,
+    pub timestamp: u64,
+}
+
+impl ToolResult {
+    /// Whether this execution can be undone
+    pub fn supports_undo(&self) -> bool {
+        self.undo_state.is_some()
+    }
+}
+
+/// State needed to undo a tool execution
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UndoState {
+    pub tool_name: String,
+    pub previous_value: Value,
+    pub operation: String,
+}
+
+/// Protocol-level failures, distinguishable from errors raised by the tool itself
+/// (use `anyhow::Error::downcast_ref::<ToolError>()`)
+#[derive(Debug, Clone, PartialEq)]
+pub enum ToolError {
+    NotFound(String),
+    InvalidArguments { tool: String, reason: String },
+    PermissionDenied { tool: String, permission: Permission },
+    Timeout { tool: String, after: Duration },
+    NothingToUndo,
+    UndoUnsupported(String),
+}
+
+impl fmt::Display for ToolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ToolError::NotFound(tool) => write!(f, "Tool '{}' not found", tool),
+            ToolError::InvalidArguments { tool, reason } => {
+                write!(f, "Invalid arguments for '{}': {}", tool, reason)
+            }
+            ToolError::PermissionDenied { tool, permission } => {
+                write!(f, "Tool '{}' requires permission {:?}", tool, permission)
+            }
+            ToolError::Timeout { tool, after } => {
+                write!(f, "Tool '{}' timed out after {:?}", tool, after)
+            }
+            ToolError::NothingToUndo => write!(f, "No executions to undo"),
+            ToolError::UndoUnsupported(tool) => write!(f, "Tool '{}' does not support undo", tool),
+        }
+    }
+}
+
+impl std::error::Error for ToolError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_tool_call_parsing() {
+        let call = ToolCall::from_json(r#"{"tool": "weather", "arguments": {"location": "Paris"}}"#).unwrap();
+        assert_eq!(call, ToolCall::new("weather", json!({"location": "Paris"})));
+
+        let call = ToolCall::from_json(r#"{"name": "navigate"}"#).unwrap();
+        assert_eq!(call.tool_name, "navigate");
+        assert!(call.args.is_null());
+
+        let call = ToolCall::new("calculator", json!({"expression": "2 + 2"}));
+        assert_eq!(serde_json::to_string(&call).unwrap(), r#"{"tool":"calculator","arguments":{"expression":"2 + 2"}}"#);
+
+        assert!(ToolCall::from_json("weather(Paris)").is_err());
+        assert!(ToolArgs::from_value(json!(["Paris"])).is_err());
+    }
+}
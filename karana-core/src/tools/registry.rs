@@ -1,247 +1,420 @@
 // Phase 54.2: Universal Tool Registry with ZK Attestation
+// Validates arguments, enforces permissions and timeouts, and keeps an undo history
 use anyhow::{Result, Context};
-use serde_json::{json, Value};
+use serde_json::Value;
 use sha2::{Sha256, Digest};
 use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::Mutex;
 
+use crate::privacy::{Permission, PermissionManager, PermissionState};
+use super::protocol::{Tool, ToolArgs, ToolCall, ToolError, ToolOutput, ToolResult, UndoState};
+use super::schema::ToolSpec;
+
+/// Execution limit for tools that don't declare their own
+pub const DEFAULT_TOOL_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Executions kept for undo
+const MAX_HISTORY: usize = 100;
+
+/// Tool registry - the one place tools are registered and executed
 pub struct ToolRegistry {
-    tools: HashMap<String, Box<dyn ToolExecutor>>,
+    tools: HashMap<String, Arc<dyn Tool>>,
+    permissions: Arc<RwLock<PermissionManager>>,
+    default_timeout: Duration,
     enable_zk_attestation: bool,
-}
-
-#[async_trait::async_trait]
-pub trait ToolExecutor: Send + Sync {
-    async fn execute(&self, params: &Value) -> Result<String>;
-    fn name(&self) -> &str;
-    fn description(&self) -> &str;
+    execution_history: Mutex<Vec<ToolResult>>,
 }
 
 impl ToolRegistry {
+    /// Empty registry with system default permissions
     pub fn new() -> Self {
-        let mut registry = Self {
+        Self {
             tools: HashMap::new(),
+            permissions: Arc::new(RwLock::new(PermissionManager::new())),
+            default_timeout: DEFAULT_TOOL_TIMEOUT,
             enable_zk_attestation: true,
-        };
-        
-        // Register built-in tools
-        registry.register(Box::new(OSExecTool));
-        registry.register(Box::new(WebAPITool));
-        registry.register(Box::new(AppProxyTool));
-        registry.register(Box::new(GenCreativeTool));
-        registry.register(Box::new(MemoryRAGTool));
-        registry.register(Box::new(HealthSensorTool));
-        
-        registry
+            execution_history: Mutex::new(Vec::new()),
+        }
     }
 
-    pub fn new_mock() -> Self {
-        Self {
-            tools: HashMap::new(),
-            enable_zk_attestation: false,
+    /// Check tool permissions against a shared permission manager
+    pub fn with_permission_manager(mut self, permissions: Arc<RwLock<PermissionManager>>) -> Self {
+        self.permissions = permissions;
+        self
+    }
+
+    /// Execution limit for tools without their own timeout
+    pub fn with_default_timeout(mut self, timeout: Duration) -> Self {
+        self.default_timeout = timeout;
+        self
+    }
+
+    /// Toggle input/output hashing of tool executions
+    pub fn with_attestation(mut self, enabled: bool) -> Self {
+        self.enable_zk_attestation = enabled;
+        self
+    }
+
+    /// Register a tool (replaces any tool with the same name)
+    pub fn register<T: Tool + 'static>(&mut self, tool: T) {
+        self.register_tool(Arc::new(tool));
+    }
+
+    /// Register a shared tool instance
+    pub fn register_tool(&mut self, tool: Arc<dyn Tool>) {
+        let name = tool.name().to_string();
+        if self.tools.insert(name.clone(), tool).is_some() {
+            log::warn!("[TOOLS] Replaced existing tool '{}'", name);
         }
     }
 
-    pub fn register(&mut self, tool: Box<dyn ToolExecutor>) {
-        self.tools.insert(tool.name().to_string(), tool);
+    /// Get tool by name
+    pub fn get_tool(&self, name: &str) -> Option<Arc<dyn Tool>> {
+        self.tools.get(name).cloned()
     }
 
-    /// Universal tool execution with ZK attestation
-    pub async fn call_tool(&self, tool_name: &str, params: &Value) -> Result<String> {
-        // ZK-prove input
-        let input_hash = if self.enable_zk_attestation {
-            let hash = Sha256::digest(params.to_string().as_bytes());
-            Some(hex::encode(hash))
-        } else {
-            None
+    /// List all registered tools (sorted)
+    pub fn list_tools(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.tools.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// Declaration of a single tool
+    pub fn spec(&self, name: &str) -> Option<ToolSpec> {
+        self.tools.get(name).map(|tool| tool.spec())
+    }
+
+    /// Declarations of all tools (sorted by name), for prompts and grammars
+    pub fn specs(&self) -> Vec<ToolSpec> {
+        self.list_tools()
+            .iter()
+            .filter_map(|name| self.spec(name))
+            .collect()
+    }
+
+    /// Shared permission manager consulted before each execution
+    pub fn permission_manager(&self) -> Arc<RwLock<PermissionManager>> {
+        self.permissions.clone()
+    }
+
+    /// Grant a permission to all tools
+    pub fn grant_permission(&self, permission: Permission) {
+        if let Ok(mut manager) = self.permissions.write() {
+            manager.set_global_override(permission, PermissionState::Granted);
+        }
+    }
+
+    /// Deny a permission to all tools
+    pub fn revoke_permission(&self, permission: Permission) {
+        if let Ok(mut manager) = self.permissions.write() {
+            manager.set_global_override(permission, PermissionState::Denied);
+        }
+    }
+
+    /// Permissions the tool needs that are not currently granted
+    pub fn missing_permissions(&self, name: &str) -> Vec<Permission> {
+        let Some(tool) = self.tools.get(name) else {
+            return Vec::new();
         };
+        let Ok(manager) = self.permissions.read() else {
+            return tool.permissions();
+        };
+        tool.permissions()
+            .into_iter()
+            .filter(|p| !manager.check(*p))
+            .collect()
+    }
+
+    /// Execute a tool by name: validate → check permissions → run with timeout → record
+    pub async fn execute(&self, tool_name: &str, args: ToolArgs) -> Result<ToolResult> {
+        let tool = self.get_tool(tool_name)
+            .ok_or_else(|| ToolError::NotFound(tool_name.to_string()))?;
 
-        // Execute tool
-        let tool = self.tools.get(tool_name)
-            .ok_or_else(|| anyhow::anyhow!("Tool '{}' not found", tool_name))?;
-        
-        let output = tool.execute(params).await
+        let spec = tool.spec();
+        let args = spec.validate(args)?;
+
+        if let Some(&permission) = self.missing_permissions(tool_name).first() {
+            return Err(ToolError::PermissionDenied {
+                tool: tool_name.to_string(),
+                permission,
+            }.into());
+        }
+
+        // ZK-prove input
+        let input_hash = self.enable_zk_attestation
+            .then(|| hex::encode(Sha256::digest(args.to_value().to_string().as_bytes())));
+
+        let limit = spec.timeout.unwrap_or(self.default_timeout);
+        let output = tokio::time::timeout(limit, tool.execute(&args))
+            .await
+            .map_err(|_| ToolError::Timeout { tool: tool_name.to_string(), after: limit })?
             .context(format!("Tool '{}' execution failed", tool_name))?;
 
         // ZK-prove output
-        if self.enable_zk_attestation {
-            let output_hash = Sha256::digest(output.as_bytes());
-            let output_hash_hex = hex::encode(output_hash);
-            
+        if let Some(input_hash) = input_hash {
+            let output_hash = hex::encode(Sha256::digest(output.output.as_bytes()));
+
             // In production: submit to DAO for attestation
-            log::debug!("ZK Attestation - Input: {:?}, Output: {}", input_hash, output_hash_hex);
+            log::debug!("ZK Attestation - Tool: {}, Input: {}, Output: {}", tool_name, input_hash, output_hash);
         }
 
-        Ok(output)
+        let result = Self::into_result(tool_name, output)?;
+
+        let mut history = self.execution_history.lock().await;
+        history.push(result.clone());
+        if history.len() > MAX_HISTORY {
+            history.remove(0);
+        }
+
+        Ok(result)
     }
 
-    pub fn list_tools(&self) -> Vec<&str> {
-        self.tools.keys().map(|s| s.as_str()).collect()
+    /// Execute a parsed tool call
+    pub async fn call(&self, call: &ToolCall) -> Result<ToolResult> {
+        self.execute(&call.tool_name, ToolArgs::from_value(call.args.clone())?).await
     }
-}
 
-// Tool Implementations
-
-struct OSExecTool;
-
-#[async_trait::async_trait]
-impl ToolExecutor for OSExecTool {
-    async fn execute(&self, params: &Value) -> Result<String> {
-        let intent = params["query"].as_str()
-            .or_else(|| params["intent"].as_str())
-            .unwrap_or("unknown");
-        
-        // Simplified OS execution (in production: integrate with Monad)
-        if intent.contains("battery") {
-            Ok("Battery optimization enabled - Expected +15% runtime".to_string())
-        } else if intent.contains("brightness") {
-            Ok("Brightness adjusted to 70% - Adaptive mode enabled".to_string())
-        } else if intent.contains("volume") {
-            Ok("Volume set to 60%".to_string())
-        } else {
-            Ok(format!("OS action queued: {}", intent))
-        }
+    /// Execute with JSON input, returning only the output text
+    pub async fn call_tool(&self, tool_name: &str, input: &Value) -> Result<String> {
+        let result = self.execute(tool_name, ToolArgs::from_value(input.clone())?).await?;
+        Ok(result.output)
     }
 
-    fn name(&self) -> &str { "os_exec" }
-    fn description(&self) -> &str { "Execute OS-level commands and system configuration" }
-}
+    /// Undo the most recent execution. It stays in the history if undoing fails.
+    pub async fn undo_last(&self) -> Result<ToolResult> {
+        let last = self.execution_history.lock().await
+            .pop()
+            .ok_or(ToolError::NothingToUndo)?;
 
-struct WebAPITool;
-
-#[async_trait::async_trait]
-impl ToolExecutor for WebAPITool {
-    async fn execute(&self, params: &Value) -> Result<String> {
-        let query = params["query"].as_str()
-            .or_else(|| params["context"].as_str())
-            .unwrap_or("");
-        
-        // Simplified web search (in production: integrate with Brave API or local RAG)
-        if query.to_lowercase().contains("weather") {
-            Ok("Paris: 15°C, 80% chance of rain, wind 12 km/h".to_string())
-        } else if query.to_lowercase().contains("quantum") {
-            Ok("Quantum computing ethics debate: Balance progress with safety, governance needed".to_string())
-        } else {
-            Ok(format!("Web search results for: {}", query))
-        }
+        self.undo_entry(last).await
     }
 
-    fn name(&self) -> &str { "web_api" }
-    fn description(&self) -> &str { "Search web or query external APIs" }
-}
+    /// Undo a specific execution from the history. It stays there if undoing fails.
+    pub async fn undo(&self, execution_id: &str) -> Result<ToolResult> {
+        let entry = {
+            let mut history = self.execution_history.lock().await;
+            let index = history.iter()
+                .position(|r| r.execution_id == execution_id)
+                .ok_or(ToolError::NothingToUndo)?;
+            history.remove(index)
+        };
 
-struct AppProxyTool;
-
-#[async_trait::async_trait]
-impl ToolExecutor for AppProxyTool {
-    async fn execute(&self, params: &Value) -> Result<String> {
-        let name = params["name"].as_str()
-            .or_else(|| params["query"].as_str())
-            .unwrap_or("unknown");
-        
-        // Simplified app opening (in production: integrate with Bazaar/PWA system)
-        if name.to_lowercase().contains("code") || name.to_lowercase().contains("vscode") {
-            Ok("VS Code opened in PWA container - Ready for development".to_string())
-        } else {
-            Ok(format!("App '{}' launched", name))
+        self.undo_entry(entry).await
+    }
+
+    /// Undo `entry`, already taken out of the history so it cannot be undone twice
+    /// concurrently; on failure it goes back in its place
+    async fn undo_entry(&self, entry: ToolResult) -> Result<ToolResult> {
+        let result = self.run_undo(&entry).await;
+        if result.is_err() {
+            let mut history = self.execution_history.lock().await;
+            let index = history.partition_point(|r| r.timestamp <= entry.timestamp);
+            history.insert(index, entry);
+            if history.len() > MAX_HISTORY {
+                history.remove(0);
+            }
         }
+        result
     }
 
-    fn name(&self) -> &str { "app_proxy" }
-    fn description(&self) -> &str { "Launch and proxy applications (PWA/native)" }
-}
+    async fn run_undo(&self, entry: &ToolResult) -> Result<ToolResult> {
+        let undo_state = entry.undo_state.as_ref()
+            .ok_or_else(|| ToolError::UndoUnsupported(entry.tool_name.clone()))?;
 
-struct GenCreativeTool;
-
-#[async_trait::async_trait]
-impl ToolExecutor for GenCreativeTool {
-    async fn execute(&self, params: &Value) -> Result<String> {
-        let topic = params["query"].as_str()
-            .or_else(|| params["topic"].as_str())
-            .unwrap_or("creativity");
-        
-        // Simplified creative generation (in production: integrate with Phi-3)
-        if topic.to_lowercase().contains("love") {
-            Ok("Roses bloom in crimson light, Hearts entwined through day and night, Love's embrace, forever bright, Two souls merged in pure delight.".to_string())
-        } else if topic.to_lowercase().contains("quantum") {
-            Ok("In superposition's dance we dwell, Where particles their secrets tell, Entangled states that weave and swell, Reality's enigmatic spell.".to_string())
-        } else {
-            Ok(format!("Creative content generated on: {}", topic))
-        }
+        let tool = self.get_tool(&entry.tool_name)
+            .ok_or_else(|| ToolError::NotFound(entry.tool_name.clone()))?;
+
+        let limit = tool.timeout().unwrap_or(self.default_timeout);
+        let output = tokio::time::timeout(limit, tool.undo(undo_state))
+            .await
+            .map_err(|_| ToolError::Timeout { tool: entry.tool_name.clone(), after: limit })??;
+
+        Self::into_result(&entry.tool_name, output)
     }
 
-    fn name(&self) -> &str { "gen_creative" }
-    fn description(&self) -> &str { "Generate creative content (poems, stories, ideas)" }
-}
+    fn into_result(tool_name: &str, output: ToolOutput) -> Result<ToolResult> {
+        let undo_state = output.undo_state.map(|state| UndoState {
+            tool_name: tool_name.to_string(),
+            ..state
+        });
+
+        Ok(ToolResult {
+            success: true,
+            output: output.output,
+            confidence: output.confidence,
+            execution_id: format!("{}_{}", tool_name, uuid::Uuid::new_v4()),
+            tool_name: tool_name.to_string(),
+            undo_state,
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)?
+                .as_millis() as u64,
+        })
+    }
 
-struct MemoryRAGTool;
-
-#[async_trait::async_trait]
-impl ToolExecutor for MemoryRAGTool {
-    async fn execute(&self, params: &Value) -> Result<String> {
-        let query = params["query"].as_str()
-            .or_else(|| params["context"].as_str())
-            .unwrap_or("");
-        
-        // Simplified RAG retrieval (in production: integrate with RocksDB vector store)
-        if query.to_lowercase().contains("umbrella") {
-            Ok("Historical context: Rain pattern - 3/5 days last week in Paris. User has allergy notes.".to_string())
-        } else {
-            Ok("No relevant historical context found".to_string())
-        }
+    /// Get execution history (most recent first)
+    pub async fn get_history(&self, limit: usize) -> Vec<ToolResult> {
+        let history = self.execution_history.lock().await;
+        history.iter()
+            .rev()
+            .take(limit)
+            .cloned()
+            .collect()
     }
 
-    fn name(&self) -> &str { "memory_rag" }
-    fn description(&self) -> &str { "Retrieve relevant context from user history" }
+    /// Clear execution history
+    pub async fn clear_history(&self) {
+        self.execution_history.lock().await.clear();
+    }
 }
 
-struct HealthSensorTool;
-
-#[async_trait::async_trait]
-impl ToolExecutor for HealthSensorTool {
-    async fn execute(&self, params: &Value) -> Result<String> {
-        let sensor = params["sensor"].as_str()
-            .or_else(|| params["query"].as_str())
-            .unwrap_or("heart_rate");
-        
-        // Simplified sensor reading (in production: integrate with IMU/biosensors)
-        if sensor.contains("heart") {
-            Ok("Heart rate: 72 bpm (normal range)".to_string())
-        } else if sensor.contains("step") {
-            Ok("Steps today: 8,432 - 67% of daily goal".to_string())
-        } else {
-            Ok(format!("Sensor '{}' reading: nominal", sensor))
-        }
+impl Default for ToolRegistry {
+    fn default() -> Self {
+        Self::new()
     }
-
-    fn name(&self) -> &str { "health_sensor" }
-    fn description(&self) -> &str { "Read health and biometric sensors" }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::tools::{ParamType, ToolParameter};
+    use async_trait::async_trait;
+    use serde_json::json;
+
+    /// Counter that can be incremented and undone
+    struct CounterTool {
+        value: std::sync::Mutex<i64>,
+    }
+
+    #[async_trait]
+    impl Tool for CounterTool {
+        fn name(&self) -> &str { "counter" }
+        fn description(&self) -> &str { "Add to a counter" }
+
+        fn parameters(&self) -> Vec<ToolParameter> {
+            vec![ToolParameter::optional("by", ParamType::Integer, "Increment").with_default(1)]
+        }
+
+        async fn execute(&self, args: &ToolArgs) -> Result<ToolOutput> {
+            let by = args.get_i64("by").unwrap_or(1);
+            let mut value = self.value.lock().unwrap();
+            let previous = *value;
+            *value += by;
+            Ok(ToolOutput::new(value.to_string(), 1.0).with_undo("restore", json!(previous)))
+        }
+
+        async fn undo(&self, undo_state: &UndoState) -> Result<ToolOutput> {
+            let previous = undo_state.previous_value.as_i64().unwrap_or(0);
+            *self.value.lock().unwrap() = previous;
+            Ok(ToolOutput::new(previous.to_string(), 1.0))
+        }
+
+        fn supports_undo(&self) -> bool { true }
+    }
+
+    /// Tool that needs the camera and never finishes in time
+    struct SlowCameraTool;
+
+    #[async_trait]
+    impl Tool for SlowCameraTool {
+        fn name(&self) -> &str { "snapshot" }
+        fn description(&self) -> &str { "Take a picture" }
+        fn permissions(&self) -> Vec<Permission> { vec![Permission::Camera] }
+        fn timeout(&self) -> Option<Duration> { Some(Duration::from_millis(20)) }
+
+        async fn execute(&self, _args: &ToolArgs) -> Result<ToolOutput> {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            Ok(ToolOutput::new("photo", 1.0))
+        }
+    }
+
+    /// Tool whose executions cannot be undone
+    struct NoteTool;
+
+    #[async_trait]
+    impl Tool for NoteTool {
+        fn name(&self) -> &str { "note" }
+        fn description(&self) -> &str { "Write a note" }
+
+        async fn execute(&self, _args: &ToolArgs) -> Result<ToolOutput> {
+            Ok(ToolOutput::new("noted", 1.0))
+        }
+    }
+
+    fn registry() -> ToolRegistry {
+        let mut registry = ToolRegistry::new();
+        registry.register(CounterTool { value: std::sync::Mutex::new(0) });
+        registry.register(SlowCameraTool);
+        registry
+    }
+
+    fn tool_error(err: &anyhow::Error) -> &ToolError {
+        err.downcast_ref::<ToolError>().expect("protocol error")
+    }
+
+    #[tokio::test]
+    async fn test_execute_validates_and_undoes() {
+        let registry = registry();
+
+        let result = registry.call(&ToolCall::new("counter", json!({"by": "5"}))).await.unwrap();
+        assert_eq!(result.output, "5");
+        assert_eq!(result.undo_state.as_ref().unwrap().tool_name, "counter");
+        assert_eq!(registry.call_tool("counter", &json!({})).await.unwrap(), "6");
+
+        let err = registry.call_tool("counter", &json!({"by": "lots"})).await.unwrap_err();
+        assert!(matches!(tool_error(&err), ToolError::InvalidArguments { .. }));
+        let err = registry.call_tool("missing", &json!({})).await.unwrap_err();
+        assert_eq!(tool_error(&err), &ToolError::NotFound("missing".to_string()));
+
+        assert_eq!(registry.undo_last().await.unwrap().output, "5");
+        assert_eq!(registry.undo(&result.execution_id).await.unwrap().output, "0");
+        let err = registry.undo_last().await.unwrap_err();
+        assert_eq!(tool_error(&err), &ToolError::NothingToUndo);
+    }
 
     #[tokio::test]
-    async fn test_tool_registry() {
-        let registry = ToolRegistry::new();
-        
-        let result = registry.call_tool("web_api", &json!({
-            "query": "weather Paris"
-        })).await.unwrap();
-        
-        assert!(result.contains("Paris"));
-        assert!(result.contains("°C"));
+    async fn test_permissions_and_timeout() {
+        let registry = registry();
+        assert_eq!(registry.missing_permissions("snapshot"), vec![Permission::Camera]);
+
+        let err = registry.call_tool("snapshot", &json!({})).await.unwrap_err();
+        assert!(matches!(tool_error(&err), ToolError::PermissionDenied { permission: Permission::Camera, .. }));
+
+        registry.grant_permission(Permission::Camera);
+        let err = registry.call_tool("snapshot", &json!({})).await.unwrap_err();
+        assert!(matches!(tool_error(&err), ToolError::Timeout { .. }));
+        assert!(registry.get_history(10).await.is_empty());
     }
 
     #[tokio::test]
-    async fn test_creative_generation() {
-        let registry = ToolRegistry::new();
-        
-        let result = registry.call_tool("gen_creative", &json!({
-            "query": "poem about love"
-        })).await.unwrap();
-        
-        assert!(result.len() > 50); // Substantial content
-        assert!(result.to_lowercase().contains("love") || result.to_lowercase().contains("heart"));
+    async fn test_failed_undo_keeps_history() {
+        let mut registry = registry();
+        registry.register(NoteTool);
+        registry.call_tool("counter", &json!({"by": 2})).await.unwrap();
+        registry.call_tool("note", &json!({})).await.unwrap();
+
+        let err = registry.undo_last().await.unwrap_err();
+        assert_eq!(tool_error(&err), &ToolError::UndoUnsupported("note".to_string()));
+        let note = registry.get_history(1).await.remove(0);
+        let err = registry.undo(&note.execution_id).await.unwrap_err();
+        assert_eq!(tool_error(&err), &ToolError::UndoUnsupported("note".to_string()));
+
+        let history = registry.get_history(10).await;
+        let names: Vec<&str> = history.iter().map(|r| r.tool_name.as_str()).collect();
+        assert_eq!(names, ["note", "counter"]);
+
+        assert_eq!(registry.undo(&history[1].execution_id).await.unwrap().output, "0");
+        assert_eq!(registry.get_history(10).await.len(), 1);
+    }
+
+    #[test]
+    fn test_specs() {
+        let specs = registry().specs();
+        let names: Vec<&str> = specs.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["counter", "snapshot"]);
+        assert!(specs[0].supports_undo);
+        assert_eq!(specs[1].permissions, vec![Permission::Camera]);
     }
 }
@@ -0,0 +1,303 @@
+// Phase 54.2: Tool parameter schemas
+// JSON-Schema declarations for tool arguments and validation of incoming calls
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::time::Duration;
+
+use crate::privacy::Permission;
+use super::protocol::{ToolArgs, ToolError};
+
+/// JSON-Schema primitive types supported for tool parameters
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ParamType {
+    String,
+    Number,
+    Integer,
+    Boolean,
+    Array,
+    Object,
+}
+
+impl ParamType {
+    /// Type name as used in JSON Schema
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ParamType::String => "string",
+            ParamType::Number => "number",
+            ParamType::Integer => "integer",
+            ParamType::Boolean => "boolean",
+            ParamType::Array => "array",
+            ParamType::Object => "object",
+        }
+    }
+
+    /// Check whether a value already has this type
+    pub fn matches(&self, value: &Value) -> bool {
+        match self {
+            ParamType::String => value.is_string(),
+            ParamType::Number => value.is_number(),
+            ParamType::Integer => value.is_i64() || value.is_u64(),
+            ParamType::Boolean => value.is_boolean(),
+            ParamType::Array => value.is_array(),
+            ParamType::Object => value.is_object(),
+        }
+    }
+
+    /// Convert a value to this type where the intent is unambiguous.
+    ///
+    /// Language models and voice entities frequently produce `"42"` for a
+    /// number or `"true"` for a flag, so numeric and boolean strings are
+    /// accepted. Scalars are accepted for string parameters.
+    fn coerce(&self, value: &Value) -> Option<Value> {
+        if self.matches(value) {
+            return Some(value.clone());
+        }
+        match (self, value) {
+            (ParamType::String, Value::Number(n)) => Some(Value::String(n.to_string())),
+            (ParamType::String, Value::Bool(b)) => Some(Value::String(b.to_string())),
+            (ParamType::Number, Value::String(s)) => {
+                s.trim().parse::<f64>().ok().and_then(serde_json::Number::from_f64).map(Value::Number)
+            }
+            (ParamType::Integer, Value::String(s)) => s.trim().parse::<i64>().ok().map(Value::from),
+            (ParamType::Integer, Value::Number(n)) => n
+                .as_f64()
+                .filter(|f| f.fract() == 0.0 && f.abs() < i64::MAX as f64)
+                .map(|f| Value::from(f as i64)),
+            (ParamType::Boolean, Value::String(s)) => match s.trim().to_lowercase().as_str() {
+                "true" | "yes" => Some(Value::Bool(true)),
+                "false" | "no" => Some(Value::Bool(false)),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+/// Tool parameter definition
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolParameter {
+    pub name: String,
+    pub description: String,
+    pub param_type: ParamType,
+    pub required: bool,
+    /// Value used when the argument is omitted
+    pub default: Option<Value>,
+    /// Allowed values (JSON Schema `enum`)
+    pub allowed: Option<Vec<Value>>,
+}
+
+impl ToolParameter {
+    /// A parameter that must be supplied
+    pub fn required(name: &str, param_type: ParamType, description: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            description: description.to_string(),
+            param_type,
+            required: true,
+            default: None,
+            allowed: None,
+        }
+    }
+
+    /// A parameter that may be omitted
+    pub fn optional(name: &str, param_type: ParamType, description: &str) -> Self {
+        Self {
+            required: false,
+            ..Self::required(name, param_type, description)
+        }
+    }
+
+    /// Set the value used when the argument is omitted (makes it optional)
+    pub fn with_default(mut self, value: impl Into<Value>) -> Self {
+        self.default = Some(value.into());
+        self.required = false;
+        self
+    }
+
+    /// Restrict the argument to a fixed set of values
+    pub fn one_of<V: Into<Value>>(mut self, values: impl IntoIterator<Item = V>) -> Self {
+        self.allowed = Some(values.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// JSON Schema for this parameter
+    pub fn json_schema(&self) -> Value {
+        let mut schema = json!({
+            "type": self.param_type.as_str(),
+            "description": self.description,
+        });
+        if let Some(default) = &self.default {
+            schema["default"] = default.clone();
+        }
+        if let Some(allowed) = &self.allowed {
+            schema["enum"] = Value::Array(allowed.clone());
+        }
+        schema
+    }
+}
+
+/// Everything a caller needs to know about a tool before invoking it
+#[derive(Debug, Clone)]
+pub struct ToolSpec {
+    pub name: String,
+    pub description: String,
+    pub parameters: Vec<ToolParameter>,
+    /// Permissions that must be granted before the tool runs
+    pub permissions: Vec<Permission>,
+    pub supports_undo: bool,
+    /// Per-tool execution limit (registry default when `None`)
+    pub timeout: Option<Duration>,
+}
+
+impl ToolSpec {
+    /// Look up a declared parameter
+    pub fn parameter(&self, name: &str) -> Option<&ToolParameter> {
+        self.parameters.iter().find(|p| p.name == name)
+    }
+
+    /// JSON Schema of the argument object
+    pub fn parameters_schema(&self) -> Value {
+        let properties: Map<String, Value> = self.parameters
+            .iter()
+            .map(|p| (p.name.clone(), p.json_schema()))
+            .collect();
+        let required: Vec<&str> = self.parameters
+            .iter()
+            .filter(|p| p.required)
+            .map(|p| p.name.as_str())
+            .collect();
+
+        json!({
+            "type": "object",
+            "properties": properties,
+            "required": required,
+            "additionalProperties": false,
+        })
+    }
+
+    /// Function-calling declaration: name, description and argument schema
+    pub fn json_schema(&self) -> Value {
+        json!({
+            "name": self.name,
+            "description": self.description,
+            "parameters": self.parameters_schema(),
+        })
+    }
+
+    /// Compact one-line signature for prompts, e.g. `weather(location?: string)`
+    pub fn signature(&self) -> String {
+        let params: Vec<String> = self.parameters
+            .iter()
+            .map(|p| format!("{}{}: {}", p.name, if p.required { "" } else { "?" }, p.param_type.as_str()))
+            .collect();
+        format!("{}({})", self.name, params.join(", "))
+    }
+
+    /// Check arguments against the declared parameters.
+    ///
+    /// Rejects unknown and missing arguments, coerces scalars where the
+    /// intent is unambiguous and fills in defaults. Null counts as omitted.
+    pub fn validate(&self, args: ToolArgs) -> Result<ToolArgs, ToolError> {
+        let invalid = |reason: String| ToolError::InvalidArguments {
+            tool: self.name.clone(),
+            reason,
+        };
+
+        let mut supplied = args.into_map();
+        if let Some(unknown) = supplied.keys().find(|k| self.parameter(k).is_none()) {
+            return Err(invalid(format!("unknown argument '{}'", unknown)));
+        }
+
+        let mut validated = ToolArgs::new();
+        for param in &self.parameters {
+            let value = match supplied.remove(&param.name).filter(|v| !v.is_null()) {
+                Some(value) => param.param_type.coerce(&value).ok_or_else(|| {
+                    invalid(format!("argument '{}' must be of type {}", param.name, param.param_type.as_str()))
+                })?,
+                None => match &param.default {
+                    Some(default) => default.clone(),
+                    None if param.required => {
+                        return Err(invalid(format!("missing required argument '{}'", param.name)));
+                    }
+                    None => continue,
+                },
+            };
+
+            if let Some(allowed) = &param.allowed
+                && !allowed.contains(&value)
+            {
+                return Err(invalid(format!(
+                    "argument '{}' must be one of {}",
+                    param.name,
+                    Value::Array(allowed.clone())
+                )));
+            }
+            validated.add(param.name.as_str(), value);
+        }
+
+        Ok(validated)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec() -> ToolSpec {
+        ToolSpec {
+            name: "wallet".to_string(),
+            description: "Wallet operations".to_string(),
+            parameters: vec![
+                ToolParameter::optional("action", ParamType::String, "Operation")
+                    .with_default("balance")
+                    .one_of(["balance", "transfer"]),
+                ToolParameter::optional("amount", ParamType::Integer, "Amount in KARA"),
+                ToolParameter::required("account", ParamType::String, "Account"),
+            ],
+            permissions: Vec::new(),
+            supports_undo: false,
+            timeout: None,
+        }
+    }
+
+    fn args(value: Value) -> ToolArgs {
+        ToolArgs::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_validate_defaults_and_coercion() {
+        let validated = spec().validate(args(json!({"account": "main", "amount": "25"}))).unwrap();
+        assert_eq!(validated.get_string("action").as_deref(), Some("balance"));
+        assert_eq!(validated.get_i64("amount"), Some(25));
+
+        let validated = spec().validate(args(json!({"account": 7, "amount": null}))).unwrap();
+        assert_eq!(validated.get_string("account").as_deref(), Some("7"));
+        assert!(!validated.contains("amount"));
+    }
+
+    #[test]
+    fn test_validate_rejects_bad_arguments() {
+        let reason = |value: Value| match spec().validate(args(value)) {
+            Err(ToolError::InvalidArguments { reason, .. }) => reason,
+            other => panic!("expected invalid arguments, got {:?}", other),
+        };
+
+        assert!(reason(json!({})).contains("missing required argument 'account'"));
+        assert!(reason(json!({"account": "a", "to": "b"})).contains("unknown argument 'to'"));
+        assert!(reason(json!({"account": "a", "amount": 2.5})).contains("type integer"));
+        assert!(reason(json!({"account": "a", "action": "stake"})).contains("one of"));
+    }
+
+    #[test]
+    fn test_json_schema() {
+        let spec = spec();
+        let schema = spec.json_schema();
+        assert_eq!(schema["name"], "wallet");
+        assert_eq!(schema["parameters"]["required"], json!(["account"]));
+        assert_eq!(schema["parameters"]["properties"]["amount"]["type"], "integer");
+        assert_eq!(schema["parameters"]["properties"]["action"]["enum"], json!(["balance", "transfer"]));
+        assert_eq!(spec.signature(), "wallet(action?: string, amount?: integer, account: string)");
+    }
+}
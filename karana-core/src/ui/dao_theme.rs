@@ -1,25 +1,24 @@
-use crate::gov::KaranaDAO;
+use crate::gov::{Governance, ProposalStatus};
 use std::sync::{Arc, Mutex};
 
 // Stubbing Druid types for headless compilation
 // use druid::{theme, Color};
 
 pub struct DaoTheme {
-    dao: Arc<Mutex<KaranaDAO>>,
+    gov: Arc<Mutex<Governance>>,
 }
 
 impl DaoTheme {
-    pub fn new(dao: Arc<Mutex<KaranaDAO>>) -> Self {
-        Self { dao }
+    pub fn new(gov: Arc<Mutex<Governance>>) -> Self {
+        Self { gov }
     }
 
     // Returns a color hex string for now to avoid Druid dependency in core logic
-    pub fn apply_voted_theme(&self, id: u32) -> String {
-        let dao = self.dao.lock().unwrap();
-        // Check if proposal passed
-        // Simplified logic
-        if let Some(prop) = dao.proposals.get(&id) {
-             if prop.yes_votes > prop.no_votes {
+    pub fn apply_voted_theme(&self, id: u64) -> String {
+        let gov = self.gov.lock().unwrap();
+        // Only a tallied, passed proposal switches the theme
+        if let Some(prop) = gov.get_proposal(id) {
+             if prop.status == ProposalStatus::Passed {
                  return "#1a1a1a".to_string(); // Dark Neural
              }
         }
@@ -5,7 +5,9 @@ use actix::prelude::*;
 use crate::runtime::KaranaActor;
 use crate::net::KaranaSwarm;
 use crate::ai::KaranaAI;
-use crate::gov::KaranaDAO;
+use crate::gov::{Governance, ProposalAction};
+use crate::chain::{Blockchain, Mempool, TransactionData};
+use crate::wallet::KaranaWallet;
 use crate::market::KaranaBazaar;
 use crate::pkg::AppBundle;
 use crate::identity::KaranaIdentity;
@@ -13,7 +15,6 @@ use super::compositor::ARCompositor;
 use std::sync::{Arc, Mutex, mpsc};
 use std::process::Command;
 use std::fs;
-use alloy_primitives::U256;
 use ratatui::{
     backend::CrosstermBackend,
     layout::{Constraint, Direction, Layout, Alignment},
@@ -58,7 +59,7 @@ pub struct KaranaUI {
     ai_render: Arc<Mutex<KaranaAI>>,
     #[allow(dead_code)]
     runtime: Arc<KaranaActor>,
-    dao: Arc<Mutex<KaranaDAO>>,
+    gov: Arc<Mutex<Governance>>,
     market: Arc<Mutex<KaranaBazaar>>,
     bundle: AppBundle,
     state: Arc<Mutex<UiState>>,
@@ -67,14 +68,18 @@ pub struct KaranaUI {
     hardware: Arc<crate::hardware::KaranaHardware>,
     identity: Arc<Mutex<KaranaIdentity>>,
     compositor: Arc<ARCompositor>,
+    /// Where governance actions are submitted as transactions
+    chain: Option<ChainHandle>,
 }
 
-impl KaranaUI {
-    pub fn new(runtime: &Arc<KaranaActor>, swarm: &KaranaSwarm, ai: Arc<Mutex<KaranaAI>>, hardware: Arc<crate::hardware::KaranaHardware>, identity: Arc<Mutex<KaranaIdentity>>) -> anyhow::Result<Self> {
-        let mut dao = KaranaDAO::default();
-        // Test mint for user
-        dao.token.mint("user", U256::from(200u64));
+struct ChainHandle {
+    chain: Arc<Blockchain>,
+    mempool: Arc<Mutex<Mempool>>,
+    wallet: Arc<Mutex<KaranaWallet>>,
+}
 
+impl KaranaUI {
+    pub fn new(runtime: &Arc<KaranaActor>, swarm: &KaranaSwarm, ai: Arc<Mutex<KaranaAI>>, gov: Arc<Mutex<Governance>>, hardware: Arc<crate::hardware::KaranaHardware>, identity: Arc<Mutex<KaranaIdentity>>) -> anyhow::Result<Self> {
         let market = Arc::new(Mutex::new(KaranaBazaar::new(ai.clone())));
         let bundle = AppBundle::new();
         let compositor = Arc::new(ARCompositor::new());
@@ -119,7 +124,7 @@ impl KaranaUI {
             swarm: swarm.clone(),
             ai_render: ai,
             runtime: runtime.clone(),
-            dao: Arc::new(Mutex::new(dao)),
+            gov,
             market,
             bundle,
             state,
@@ -128,9 +133,23 @@ impl KaranaUI {
             hardware,
             identity,
             compositor,
+            chain: None,
         })
     }
 
+    /// Submit proposals and votes as transactions signed by `wallet`
+    pub fn with_chain(mut self, chain: Arc<Blockchain>, mempool: Arc<Mutex<Mempool>>, wallet: Arc<Mutex<KaranaWallet>>) -> Self {
+        self.chain = Some(ChainHandle { chain, mempool, wallet });
+        self
+    }
+
+    /// Queue a governance transaction for the next block
+    fn submit(&self, data: TransactionData) -> anyhow::Result<()> {
+        let handle = self.chain.as_ref().ok_or_else(|| anyhow::anyhow!("No chain attached"))?;
+        handle.chain.queue_signed(&handle.mempool, &handle.wallet.lock().unwrap(), data)?;
+        Ok(())
+    }
+
     pub fn get_intent_sender(&self) -> mpsc::Sender<String> {
         self.intent_tx.clone()
     }
@@ -478,33 +497,29 @@ impl KaranaUI {
             format!("Symbiotic Guide:\n{}", tutorial)
         } else if input.starts_with("report bug") {
              let proof = input.replace("report bug", "").trim().to_string();
-             let (prop_id, bounty) = {
-                 let mut dao = self.dao.lock().unwrap();
-                 let id = dao.propose_bounty(&proof, 5); 
-                 (id, 50)
+             let bounty = 50;
+             let action = ProposalAction::TreasurySpend { to: "user".to_string(), amount: bounty };
+             let data = TransactionData::Propose {
+                 title: format!("Bounty: {}", proof),
+                 description: "Claim if fixed".to_string(),
+                 action,
              };
-             format!("Bug Reported! Proposal ID: {}. Potential Bounty: {} KARA. DAO is voting...", prop_id, bounty)
+             match self.submit(data) {
+                 Ok(()) => format!("Bug Reported! Bounty proposal queued for the next block. Potential Bounty: {} KARA.", bounty),
+                 Err(e) => format!("Bug report failed: {}", e),
+             }
         } else if input.starts_with("vote") {
             // vote <id> <yes/no>
             let parts: Vec<&str> = input.split_whitespace().collect();
             if parts.len() < 3 {
                 return Ok("Usage: vote <proposal_id> <yes/no>".to_string());
             }
-            let prop_id = parts[1].parse::<u32>().unwrap_or(0);
+            let prop_id = parts[1].parse::<u64>().unwrap_or(0);
             let decision = parts[2].to_lowercase() == "yes";
             
-            let mut dao = self.dao.lock().unwrap();
-            match dao.vote("user", prop_id, decision) {
-                Ok(passed) => {
-                    if passed {
-                         dao.execute_if_passed(prop_id, &mut |id| {
-                            log::info!("Executed: Prop {} passed!", id);
-                        });
-                        format!("Voted on Prop {}. Result: PASSED & EXECUTED.", prop_id)
-                    } else {
-                        format!("Voted on Prop {}. Result: Pending/Failed.", prop_id)
-                    }
-                },
+            let ends = self.gov.lock().unwrap().get_proposal(prop_id).map(|p| p.voting_ends).unwrap_or_default();
+            match self.submit(TransactionData::Vote { proposal_id: prop_id, approve: decision }) {
+                Ok(()) => format!("Vote on Prop {} queued. Voting closes at block {}.", prop_id, ends),
                 Err(e) => format!("Vote Failed: {}", e),
             }
         } else if input.starts_with("haptic") || input.starts_with("hud") || input.starts_with("power") || input.starts_with("gaze") {
@@ -552,17 +567,15 @@ impl KaranaUI {
 
         // Phase 6: Live DAO Integration (Boot Sim)
         if input.contains("optimize storage") || input.contains("boot") {
-             let prop_id = {
-                let mut dao = self.dao.lock().unwrap();
-                dao.propose(&format!("AI Suggestion: {}", view.chars().take(20).collect::<String>()), "Live vote on boot")
+            let title = format!("AI Suggestion: {}", view.chars().take(20).collect::<String>());
+            let data = TransactionData::Propose {
+                title,
+                description: "Live vote on boot".to_string(),
+                action: ProposalAction::Signal,
             };
-            log::info!("BOOT DAO: Vote yes/no on Prop {}? (Sim: yes)", prop_id);
-            
-            let mut dao = self.dao.lock().unwrap();
-            if dao.vote("user", prop_id, true).unwrap() {
-                dao.execute_if_passed(prop_id, &mut |id| {
-                    log::info!("Executed: Prop {} live – Runtime boosted!", id);
-                });
+            match self.submit(data) {
+                Ok(()) => log::info!("BOOT DAO: Proposal queued; vote once it is in a block"),
+                Err(e) => log::info!("BOOT DAO: Proposal not queued: {}", e),
             }
         }
 
@@ -6,9 +6,19 @@
 //! - BIP-39 mnemonic seed phrase generation
 //! - Ed25519 signing keys
 //! - Device-specific key derivation
+//! - HD accounts: several DIDs from one phrase via SLIP-0010 paths
 //! - Secure key storage with encryption
 //! - DID (Decentralized Identifier) derivation
 //!
+//! ## HD Accounts
+//! `HdWallet` derives every key from the BIP-39 seed along
+//! `m/44'/8888'/<account>'/0'/<key>'`. An account's DID comes from its key 0
+//! and never changes; later key indices are replacements that the chain
+//! accepts after a `RotateKey` transaction, so a lost or leaked device key
+//! is recovered from the phrase without giving up the identity.
+//! `KaranaWallet::generate`/`from_mnemonic` predate HD derivation and keep
+//! using the raw seed, so existing DIDs still restore.
+//!
 //! ## Security Model
 //! - Private keys never leave the device
 //! - Keys are zeroized when dropped
@@ -18,8 +28,9 @@
 use anyhow::{Result, anyhow};
 use ed25519_dalek::{SigningKey, VerifyingKey, Signature, Signer, Verifier};
 use bip39::Mnemonic;
-use sha2::{Sha256, Digest};
-use zeroize::{Zeroize, ZeroizeOnDrop};
+use hmac::{Hmac, Mac};
+use sha2::{Sha256, Sha512, Digest};
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
 use serde::{Serialize, Deserialize};
 use rand::RngCore;
 use std::fs;
@@ -28,8 +39,22 @@ use std::path::Path;
 /// Number of words in the recovery mnemonic
 pub const MNEMONIC_WORD_COUNT: usize = 24;
 
-/// Derivation path for Kāraṇa wallets
-pub const KARANA_DERIVATION_PATH: &str = "m/44'/8888'/0'/0/0";
+/// SLIP-0044 coin type used in Kāraṇa derivation paths
+pub const KARANA_COIN_TYPE: u32 = 8888;
+
+/// Derivation path of the first key of the first HD account
+pub const KARANA_DERIVATION_PATH: &str = "m/44'/8888'/0'/0'/0'";
+
+/// Current `EncryptedWallet` format (HD seed + accounts)
+pub const ENCRYPTED_WALLET_VERSION: u8 = 2;
+
+/// PBKDF2-HMAC-SHA256 rounds for version 2 wallet files
+pub const WALLET_KDF_ROUNDS: u32 = 100_000;
+
+/// How many key indices `HdWallet::sync_authorized_key` searches
+pub const KEY_SCAN_LIMIT: u32 = 64;
+
+const HARDENED_OFFSET: u32 = 0x8000_0000;
 
 /// The core wallet structure - holds the user's sovereign identity
 #[derive(ZeroizeOnDrop)]
@@ -83,11 +108,16 @@ impl RecoveryPhrase {
 }
 
 /// Encrypted wallet format for storage
+///
+/// Version 1 holds a single signing key encrypted under SHA-256(password ||
+/// salt). Version 2 holds the 64-byte BIP-39 seed of an `HdWallet`, encrypted
+/// under a PBKDF2 key, with the account list bound to the ciphertext as
+/// associated data so labels and key indices cannot be edited on disk.
 #[derive(Serialize, Deserialize)]
 pub struct EncryptedWallet {
     /// Version for future compatibility
     pub version: u8,
-    /// The DID (not secret)
+    /// The DID (not secret); the first account's DID in version 2
     pub did: String,
     /// Device ID this wallet is bound to
     pub device_id: String,
@@ -97,6 +127,143 @@ pub struct EncryptedWallet {
     pub salt: Vec<u8>,
     /// Nonce for encryption
     pub nonce: Vec<u8>,
+    /// PBKDF2 rounds for the encryption key (0 in version 1 files)
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub kdf_rounds: u32,
+    /// HD accounts (version 2)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub accounts: Vec<WalletAccount>,
+}
+
+fn is_zero(value: &u32) -> bool {
+    *value == 0
+}
+
+impl EncryptedWallet {
+    /// Header fields authenticated along with the ciphertext (version 2)
+    fn associated_data(&self) -> Vec<u8> {
+        if self.version < 2 {
+            return Vec::new();
+        }
+        serde_json::to_vec(&serde_json::json!({
+            "version": self.version,
+            "did": self.did,
+            "device_id": self.device_id,
+            "kdf_rounds": self.kdf_rounds,
+            "accounts": self.accounts,
+        }))
+        .unwrap_or_default()
+    }
+
+    fn encrypt(&mut self, password: &str, plaintext: &[u8]) -> Result<()> {
+        use aes_gcm::{Aes256Gcm, KeyInit, Nonce, aead::{Aead, Payload}};
+
+        self.salt = vec![0u8; 32];
+        rand::thread_rng().fill_bytes(&mut self.salt);
+        self.nonce = vec![0u8; 12];
+        rand::thread_rng().fill_bytes(&mut self.nonce);
+
+        let key = wallet_cipher_key(password, &self.salt, self.kdf_rounds);
+        let cipher = Aes256Gcm::new_from_slice(&key[..])
+            .map_err(|e| anyhow!("Failed to create cipher: {}", e))?;
+        let aad = self.associated_data();
+        self.encrypted_seed = cipher
+            .encrypt(Nonce::from_slice(&self.nonce), Payload { msg: plaintext, aad: &aad })
+            .map_err(|e| anyhow!("Encryption failed: {}", e))?;
+        Ok(())
+    }
+
+    fn decrypt(&self, password: &str) -> Result<Zeroizing<Vec<u8>>> {
+        use aes_gcm::{Aes256Gcm, KeyInit, Nonce, aead::{Aead, Payload}};
+
+        if self.nonce.len() != 12 {
+            return Err(anyhow!("Wallet file is corrupt"));
+        }
+        let key = wallet_cipher_key(password, &self.salt, self.kdf_rounds);
+        let cipher = Aes256Gcm::new_from_slice(&key[..])
+            .map_err(|e| anyhow!("Failed to create cipher: {}", e))?;
+        let aad = self.associated_data();
+        cipher
+            .decrypt(Nonce::from_slice(&self.nonce), Payload { msg: &self.encrypted_seed, aad: &aad })
+            .map(Zeroizing::new)
+            .map_err(|_| anyhow!("Decryption failed - wrong password?"))
+    }
+
+    fn write(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// Encryption key for a wallet file: PBKDF2-HMAC-SHA256, or the single
+/// SHA-256 pass version 1 files used when `rounds` is 0
+fn wallet_cipher_key(password: &str, salt: &[u8], rounds: u32) -> Zeroizing<[u8; 32]> {
+    let mut key = Zeroizing::new([0u8; 32]);
+    if rounds == 0 {
+        let mut hasher = Sha256::new();
+        hasher.update(password.as_bytes());
+        hasher.update(salt);
+        key.copy_from_slice(&hasher.finalize());
+    } else {
+        pbkdf2::pbkdf2_hmac::<Sha256>(password.as_bytes(), salt, rounds, &mut key[..]);
+    }
+    key
+}
+
+/// Derivation path of `key_index` within HD account `account`
+pub fn derivation_path(account: u32, key_index: u32) -> String {
+    format!("m/44'/{}'/{}'/0'/{}'", KARANA_COIN_TYPE, account, key_index)
+}
+
+/// Parse a path like `m/44'/8888'/0'/0'/0'` into child indices. SLIP-0010
+/// only defines hardened derivation for Ed25519, so every segment must be
+/// hardened (`'` or `h`).
+pub fn parse_derivation_path(path: &str) -> Result<Vec<u32>> {
+    let mut segments = path.split('/');
+    if segments.next() != Some("m") {
+        return Err(anyhow!("Derivation path must start with 'm': {}", path));
+    }
+    segments
+        .map(|segment| {
+            let index = segment
+                .strip_suffix('\'')
+                .or_else(|| segment.strip_suffix('h'))
+                .ok_or_else(|| anyhow!("Ed25519 derivation needs hardened indices, got '{}'", segment))?;
+            let index: u32 = index
+                .parse()
+                .map_err(|_| anyhow!("Invalid derivation index '{}'", segment))?;
+            if index >= HARDENED_OFFSET {
+                return Err(anyhow!("Derivation index {} out of range", index));
+            }
+            Ok(index | HARDENED_OFFSET)
+        })
+        .collect()
+}
+
+/// SLIP-0010 Ed25519 private key at `path` below a BIP-39 seed
+pub fn derive_ed25519_key(seed: &[u8], path: &str) -> Result<Zeroizing<[u8; 32]>> {
+    type HmacSha512 = Hmac<Sha512>;
+
+    let indices = parse_derivation_path(path)?;
+    let mut mac = HmacSha512::new_from_slice(b"ed25519 seed").expect("HMAC takes keys of any length");
+    mac.update(seed);
+    let mut node = Zeroizing::new([0u8; 64]);
+    node.copy_from_slice(&mac.finalize().into_bytes());
+
+    for index in indices {
+        let mut mac = HmacSha512::new_from_slice(&node[32..]).expect("HMAC takes keys of any length");
+        mac.update(&[0]);
+        mac.update(&node[..32]);
+        mac.update(&index.to_be_bytes());
+        node.copy_from_slice(&mac.finalize().into_bytes());
+    }
+
+    let mut key = Zeroizing::new([0u8; 32]);
+    key.copy_from_slice(&node[..32]);
+    Ok(key)
 }
 
 /// Wallet creation result
@@ -152,28 +319,37 @@ impl KaranaWallet {
         // Create signing key from seed
         let mut key_bytes = [0u8; 32];
         key_bytes.copy_from_slice(&seed_bytes[..32]);
+        let wallet = Self::from_key(&key_bytes, None, device_id);
+        key_bytes.zeroize();
         
-        let signing_key = SigningKey::from_bytes(&key_bytes);
+        Ok(wallet)
+    }
+    
+    /// Wallet signing with `key_bytes` on behalf of `did`, which defaults to
+    /// the DID derived from the key (a rotated key keeps its original DID)
+    fn from_key(key_bytes: &[u8; 32], did: Option<String>, device_id: &str) -> Self {
+        let signing_key = SigningKey::from_bytes(key_bytes);
         let verifying_key = signing_key.verifying_key();
+        let did = did.unwrap_or_else(|| Self::derive_did(&verifying_key));
         
-        // Derive DID from public key
-        let did = Self::derive_did(&verifying_key);
-        
-        key_bytes.zeroize();
-        
-        Ok(Self {
+        Self {
             signing_key,
             verifying_key,
             did,
             device_id: device_id.to_string(),
-        })
+        }
     }
     
     /// Derive a DID from the public key
     fn derive_did(verifying_key: &VerifyingKey) -> String {
-        let pubkey_bytes = verifying_key.to_bytes();
+        Self::did_for_public_key(&verifying_key.to_bytes())
+    }
+    
+    /// DID of an account whose first key is `public_key`:
+    /// did:karana:<base58(sha256(pubkey)[0:20])>
+    pub fn did_for_public_key(public_key: &[u8]) -> String {
         let mut hasher = Sha256::new();
-        hasher.update(&pubkey_bytes);
+        hasher.update(public_key);
         let hash = hasher.finalize();
         
         // Use multibase encoding (base58btc)
@@ -235,74 +411,46 @@ impl KaranaWallet {
     }
     
     /// Save wallet encrypted to disk
+    ///
+    /// Writes the single-key version 1 format; use `HdWallet::save_encrypted`
+    /// to keep HD accounts.
     pub fn save_encrypted(&self, path: &Path, password: &str) -> Result<()> {
-        use aes_gcm::{Aes256Gcm, KeyInit, Nonce, aead::Aead};
-        
-        // Generate salt
-        let mut salt = vec![0u8; 32];
-        rand::thread_rng().fill_bytes(&mut salt);
-        
-        // Derive encryption key from password + salt
-        let mut hasher = Sha256::new();
-        hasher.update(password.as_bytes());
-        hasher.update(&salt);
-        let key_bytes = hasher.finalize();
-        
-        // Generate nonce
-        let mut nonce_bytes = vec![0u8; 12];
-        rand::thread_rng().fill_bytes(&mut nonce_bytes);
-        let nonce = Nonce::from_slice(&nonce_bytes);
-        
-        // Encrypt the signing key bytes
-        let cipher = Aes256Gcm::new_from_slice(&key_bytes)
-            .map_err(|e| anyhow!("Failed to create cipher: {}", e))?;
-        
-        let seed_bytes = self.signing_key.to_bytes();
-        let encrypted_seed = cipher.encrypt(nonce, seed_bytes.as_ref())
-            .map_err(|e| anyhow!("Encryption failed: {}", e))?;
-        
-        let encrypted_wallet = EncryptedWallet {
+        let mut encrypted_wallet = EncryptedWallet {
             version: 1,
             did: self.did.clone(),
             device_id: self.device_id.clone(),
-            encrypted_seed,
-            salt,
-            nonce: nonce_bytes,
+            encrypted_seed: Vec::new(),
+            salt: Vec::new(),
+            nonce: Vec::new(),
+            kdf_rounds: 0,
+            accounts: Vec::new(),
         };
         
-        // Ensure parent directory exists
-        if let Some(parent) = path.parent() {
-            fs::create_dir_all(parent)?;
-        }
-        
-        let json = serde_json::to_string_pretty(&encrypted_wallet)?;
-        fs::write(path, json)?;
+        // Encrypt the signing key bytes
+        let seed_bytes = Zeroizing::new(self.signing_key.to_bytes());
+        encrypted_wallet.encrypt(password, &seed_bytes[..])?;
+        encrypted_wallet.write(path)?;
         
         log::info!("[WALLET] 💾 Wallet saved to {}", path.display());
         Ok(())
     }
     
     /// Load wallet from encrypted file
+    ///
+    /// Version 2 (HD) files load as the signer of their first account.
     pub fn load_encrypted(path: &Path, password: &str) -> Result<Self> {
-        use aes_gcm::{Aes256Gcm, KeyInit, Nonce, aead::Aead};
-        
         let json = fs::read_to_string(path)?;
         let encrypted_wallet: EncryptedWallet = serde_json::from_str(&json)?;
         
-        // Derive decryption key
-        let mut hasher = Sha256::new();
-        hasher.update(password.as_bytes());
-        hasher.update(&encrypted_wallet.salt);
-        let key_bytes = hasher.finalize();
-        
-        let cipher = Aes256Gcm::new_from_slice(&key_bytes)
-            .map_err(|e| anyhow!("Failed to create cipher: {}", e))?;
-        
-        let nonce = Nonce::from_slice(&encrypted_wallet.nonce);
-        
-        let seed_bytes = cipher.decrypt(nonce, encrypted_wallet.encrypted_seed.as_ref())
-            .map_err(|_| anyhow!("Decryption failed - wrong password?"))?;
+        if encrypted_wallet.version >= 2 {
+            let hd = HdWallet::decrypt(&encrypted_wallet, password)?;
+            let primary = hd.accounts.first()
+                .ok_or_else(|| anyhow!("Wallet has no accounts"))?
+                .index;
+            return hd.signer(primary);
+        }
         
+        let seed_bytes = encrypted_wallet.decrypt(password)?;
         let wallet = Self::from_seed(&seed_bytes, &encrypted_wallet.device_id)?;
         
         // Verify DID matches
@@ -328,6 +476,207 @@ impl KaranaWallet {
     }
 }
 
+/// One identity derived from an `HdWallet`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WalletAccount {
+    /// Account segment of the derivation path
+    pub index: u32,
+    pub label: String,
+    /// DID derived from the account's key 0
+    pub did: String,
+    /// Key currently authorized on chain for the DID (0 until rotated)
+    #[serde(default)]
+    pub key_index: u32,
+}
+
+/// Hierarchical deterministic wallet: many accounts, and many keys per
+/// account, from one recovery phrase
+#[derive(ZeroizeOnDrop)]
+pub struct HdWallet {
+    /// BIP-39 seed (no passphrase)
+    seed: [u8; 64],
+    device_id: String,
+    #[zeroize(skip)]
+    accounts: Vec<WalletAccount>,
+}
+
+impl HdWallet {
+    /// Generate a new HD wallet with one account
+    ///
+    /// Returns the wallet and the recovery phrase that MUST be backed up
+    pub fn generate(device_id: &str) -> Result<(Self, RecoveryPhrase)> {
+        let mut entropy = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut entropy);
+        let mnemonic = Mnemonic::from_entropy(&entropy)
+            .map_err(|e| anyhow!("Failed to generate mnemonic: {}", e))?;
+        entropy.zeroize();
+        
+        let phrase = RecoveryPhrase::new(mnemonic.words().map(|s| s.to_string()).collect());
+        let wallet = Self::from_mnemonic(&phrase.as_string(), device_id)?;
+        log::info!("[WALLET] 🔐 New HD wallet generated: {}", wallet.accounts[0].did);
+        Ok((wallet, phrase))
+    }
+    
+    /// Restore from a recovery phrase with its first account. Further
+    /// accounts are recovered by adding them again: indices are sequential,
+    /// so the same DIDs come back in the same order.
+    pub fn from_mnemonic(phrase: &str, device_id: &str) -> Result<Self> {
+        let mnemonic = Mnemonic::parse(phrase)
+            .map_err(|e| anyhow!("Invalid mnemonic phrase: {}", e))?;
+        let mut wallet = Self {
+            seed: mnemonic.to_seed(""),
+            device_id: device_id.to_string(),
+            accounts: Vec::new(),
+        };
+        wallet.add_account("Primary")?;
+        Ok(wallet)
+    }
+    
+    pub fn accounts(&self) -> &[WalletAccount] {
+        &self.accounts
+    }
+    
+    pub fn account(&self, index: u32) -> Result<&WalletAccount> {
+        self.accounts.iter().find(|a| a.index == index)
+            .ok_or_else(|| anyhow!("No wallet account {}", index))
+    }
+    
+    fn account_mut(&mut self, index: u32) -> Result<&mut WalletAccount> {
+        self.accounts.iter_mut().find(|a| a.index == index)
+            .ok_or_else(|| anyhow!("No wallet account {}", index))
+    }
+    
+    /// Derive the next account and give it `label`
+    pub fn add_account(&mut self, label: &str) -> Result<WalletAccount> {
+        let index = self.accounts.iter().map(|a| a.index + 1).max().unwrap_or(0);
+        let first_key = self.key_bytes(index, 0)?;
+        let did = KaranaWallet::from_key(&first_key, None, &self.device_id).did().to_string();
+        
+        let account = WalletAccount {
+            index,
+            label: label.to_string(),
+            did,
+            key_index: 0,
+        };
+        self.accounts.push(account.clone());
+        log::info!("[WALLET] ➕ Account {} '{}': {}", index, label, account.did);
+        Ok(account)
+    }
+    
+    pub fn set_label(&mut self, index: u32, label: &str) -> Result<()> {
+        self.account_mut(index)?.label = label.to_string();
+        Ok(())
+    }
+    
+    fn key_bytes(&self, account: u32, key_index: u32) -> Result<Zeroizing<[u8; 32]>> {
+        derive_ed25519_key(&self.seed, &derivation_path(account, key_index))
+    }
+    
+    /// Signer for key `key_index` of an account, acting as the account's DID
+    pub fn derive_key(&self, index: u32, key_index: u32) -> Result<KaranaWallet> {
+        let did = self.account(index)?.did.clone();
+        let key = self.key_bytes(index, key_index)?;
+        Ok(KaranaWallet::from_key(&key, Some(did), &self.device_id))
+    }
+    
+    /// Signer for the account's currently authorized key
+    pub fn signer(&self, index: u32) -> Result<KaranaWallet> {
+        self.derive_key(index, self.account(index)?.key_index)
+    }
+    
+    /// The key a rotation should move the account to
+    pub fn next_key(&self, index: u32) -> Result<KaranaWallet> {
+        let key_index = self.account(index)?.key_index.checked_add(1)
+            .filter(|k| *k < HARDENED_OFFSET)
+            .ok_or_else(|| anyhow!("Account {} has no keys left", index))?;
+        self.derive_key(index, key_index)
+    }
+    
+    /// Record that the chain now authorizes `key_index` for the account
+    pub fn set_key_index(&mut self, index: u32, key_index: u32) -> Result<()> {
+        self.account_mut(index)?.key_index = key_index;
+        Ok(())
+    }
+    
+    /// Find which of the account's keys the chain authorizes (`None` means
+    /// never rotated) and sign with it from now on. Needed after restoring
+    /// from the phrase, which does not remember rotations.
+    pub fn sync_authorized_key(&mut self, index: u32, authorized_key: Option<&str>) -> Result<u32> {
+        let Some(authorized) = authorized_key else {
+            self.set_key_index(index, 0)?;
+            return Ok(0);
+        };
+        for key_index in 0..KEY_SCAN_LIMIT {
+            if self.derive_key(index, key_index)?.public_key_hex().eq_ignore_ascii_case(authorized) {
+                self.set_key_index(index, key_index)?;
+                return Ok(key_index);
+            }
+        }
+        Err(anyhow!("Authorized key for account {} is not among its first {} keys", index, KEY_SCAN_LIMIT))
+    }
+    
+    pub fn device_id(&self) -> &str {
+        &self.device_id
+    }
+    
+    /// Save the seed and account list encrypted to disk (version 2 format)
+    pub fn save_encrypted(&self, path: &Path, password: &str) -> Result<()> {
+        let primary = self.accounts.first().ok_or_else(|| anyhow!("Wallet has no accounts"))?;
+        let mut encrypted_wallet = EncryptedWallet {
+            version: ENCRYPTED_WALLET_VERSION,
+            did: primary.did.clone(),
+            device_id: self.device_id.clone(),
+            encrypted_seed: Vec::new(),
+            salt: Vec::new(),
+            nonce: Vec::new(),
+            kdf_rounds: WALLET_KDF_ROUNDS,
+            accounts: self.accounts.clone(),
+        };
+        encrypted_wallet.encrypt(password, &self.seed)?;
+        encrypted_wallet.write(path)?;
+        
+        log::info!("[WALLET] 💾 HD wallet with {} account(s) saved to {}", self.accounts.len(), path.display());
+        Ok(())
+    }
+    
+    /// Load an HD wallet saved with `save_encrypted`
+    pub fn load_encrypted(path: &Path, password: &str) -> Result<Self> {
+        let json = fs::read_to_string(path)?;
+        let encrypted_wallet: EncryptedWallet = serde_json::from_str(&json)?;
+        Self::decrypt(&encrypted_wallet, password)
+    }
+    
+    fn decrypt(encrypted_wallet: &EncryptedWallet, password: &str) -> Result<Self> {
+        match encrypted_wallet.version {
+            2 => {}
+            1 => return Err(anyhow!("Version 1 wallet holds a single key; load it with KaranaWallet")),
+            v => return Err(anyhow!("Unsupported wallet version {}", v)),
+        }
+        
+        let seed_bytes = encrypted_wallet.decrypt(password)?;
+        if seed_bytes.len() != 64 {
+            return Err(anyhow!("Wallet integrity check failed"));
+        }
+        let mut wallet = Self {
+            seed: [0u8; 64],
+            device_id: encrypted_wallet.device_id.clone(),
+            accounts: encrypted_wallet.accounts.clone(),
+        };
+        wallet.seed.copy_from_slice(&seed_bytes);
+        
+        // Every account DID must still come from this seed
+        for account in &wallet.accounts {
+            let key = wallet.key_bytes(account.index, 0)?;
+            if KaranaWallet::from_key(&key, None, &wallet.device_id).did() != account.did {
+                return Err(anyhow!("Wallet integrity check failed"));
+            }
+        }
+        
+        log::info!("[WALLET] 🔓 HD wallet loaded: {} account(s)", wallet.accounts.len());
+        Ok(wallet)
+    }
+}
+
 /// Represents a signed transaction
 #[derive(Clone, Serialize, Deserialize)]
 pub struct SignedTransaction {
@@ -495,4 +844,69 @@ mod tests {
         // Cleanup
         fs::remove_file(&path).ok();
     }
+    
+    #[test]
+    fn test_slip10_ed25519_vectors() {
+        // SLIP-0010 test vector 1 for ed25519
+        let seed = hex::decode("000102030405060708090a0b0c0d0e0f").unwrap();
+        let master = derive_ed25519_key(&seed, "m").unwrap();
+        assert_eq!(hex::encode(&master[..]), "2b4be7f19ee27bbf30c667b642d5f4aa69fd169872f8fc3059c08ebae2eb19e7");
+        let child = derive_ed25519_key(&seed, "m/0'").unwrap();
+        assert_eq!(hex::encode(&child[..]), "68e0fe46dfb67e368c75379acec591dad19df3cde26e63b93a8e704f1dade7a3");
+        let grandchild = derive_ed25519_key(&seed, "m/0h/1h").unwrap();
+        assert_eq!(hex::encode(&grandchild[..]), "b1d0bad404bf35da785a64ca1ac54b2617211d2777696fbffaf208f746ae84f2");
+        
+        assert_eq!(derivation_path(0, 0), KARANA_DERIVATION_PATH);
+        assert!(parse_derivation_path("m/44'/8888'/0").is_err());
+        assert!(parse_derivation_path("44'/8888'").is_err());
+    }
+    
+    #[test]
+    fn test_hd_accounts_are_deterministic() {
+        let (mut wallet, phrase) = HdWallet::generate("test-device").unwrap();
+        let savings = wallet.add_account("Savings").unwrap();
+        assert_ne!(savings.did, wallet.accounts()[0].did);
+        
+        let mut restored = HdWallet::from_mnemonic(&phrase.as_string(), "other-device").unwrap();
+        restored.add_account("Savings").unwrap();
+        assert_eq!(restored.accounts(), wallet.accounts());
+        
+        // Rotated keys sign for the same DID
+        let current = wallet.signer(1).unwrap();
+        let next = wallet.next_key(1).unwrap();
+        assert_eq!(current.did(), savings.did);
+        assert_eq!(next.did(), savings.did);
+        assert_ne!(current.public_key_hex(), next.public_key_hex());
+        
+        // A restored wallet finds the key the chain authorizes
+        assert_eq!(restored.sync_authorized_key(1, Some(&next.public_key_hex())).unwrap(), 1);
+        assert_eq!(restored.signer(1).unwrap().public_key_hex(), next.public_key_hex());
+        assert!(restored.sync_authorized_key(1, Some(&"00".repeat(32))).is_err());
+    }
+    
+    #[test]
+    fn test_hd_save_load_encrypted() {
+        let (mut wallet, _) = HdWallet::generate("test-device").unwrap();
+        wallet.add_account("Work").unwrap();
+        wallet.set_label(0, "Personal").unwrap();
+        wallet.set_key_index(1, 3).unwrap();
+        let path = PathBuf::from(format!("/tmp/karana_test_hd_wallet_{}.json", rand::random::<u64>()));
+        wallet.save_encrypted(&path, "hd_password").unwrap();
+        
+        let loaded = HdWallet::load_encrypted(&path, "hd_password").unwrap();
+        assert_eq!(loaded.accounts(), wallet.accounts());
+        assert_eq!(loaded.signer(1).unwrap().public_key_hex(), wallet.derive_key(1, 3).unwrap().public_key_hex());
+        assert!(HdWallet::load_encrypted(&path, "wrong_password").is_err());
+        
+        // Single-key loading picks the first account
+        let primary = KaranaWallet::load_encrypted(&path, "hd_password").unwrap();
+        assert_eq!(primary.did(), wallet.accounts()[0].did);
+        
+        // Account metadata is authenticated with the seed
+        let json = fs::read_to_string(&path).unwrap().replace("\"Work\"", "\"Mine\"");
+        fs::write(&path, json).unwrap();
+        assert!(HdWallet::load_encrypted(&path, "hd_password").is_err());
+        
+        fs::remove_file(&path).ok();
+    }
 }
@@ -2,13 +2,15 @@
 //!
 //! Enables web content to create persistent spatial anchors that survive
 //! session restarts and can be shared between sessions.
+//!
+//! WebXR anchors are views onto the world-anchor service: the ID a page sees
+//! is the world `AnchorId`, and persistent anchors live in the spatial
+//! system's `AnchorStore` alongside every other anchor on the device.
 
-use std::sync::atomic::{AtomicU64, Ordering};
-use std::time::{SystemTime, UNIX_EPOCH};
 use serde::{Deserialize, Serialize};
-use uuid::Uuid;
 
-use super::{XRRigidTransform, XRTrackingState};
+use crate::spatial::{AnchorId, AnchorState, Quaternion, SpatialAnchor, WorldPosition};
+use super::{XRQuaternion, XRRigidTransform, XRTrackingState, XRVector3};
 
 /// WebXR Anchor
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -28,164 +30,89 @@ pub struct XRAnchor {
 }
 
 impl XRAnchor {
-    /// Create a new anchor
-    pub fn new(pose: XRRigidTransform) -> Self {
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_millis() as u64;
-        
+    /// Build the WebXR view of a world anchor
+    pub fn from_world(anchor: &SpatialAnchor, persistence: AnchorPersistence) -> Self {
         Self {
-            id: Uuid::new_v4().to_string(),
-            pose,
-            tracking_state: XRTrackingState::Tracking,
-            created_at: now,
-            last_updated: now,
-            persistence: AnchorPersistence::Session,
+            id: anchor.id.to_string(),
+            pose: xr_pose(anchor),
+            tracking_state: anchor.state.into(),
+            created_at: anchor.created_at * 1000,
+            last_updated: anchor.updated_at * 1000,
+            persistence,
         }
     }
     
-    /// Create a persistent anchor
-    pub fn new_persistent(pose: XRRigidTransform) -> Self {
-        let mut anchor = Self::new(pose);
-        anchor.persistence = AnchorPersistence::Persistent;
-        anchor
-    }
-    
-    /// Update the anchor's pose
-    pub fn update_pose(&mut self, pose: XRRigidTransform) {
-        self.pose = pose;
-        self.last_updated = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_millis() as u64;
-    }
-    
-    /// Set tracking state
-    pub fn set_tracking_state(&mut self, state: XRTrackingState) {
-        self.tracking_state = state;
-        self.last_updated = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_millis() as u64;
+    /// World-anchor ID behind this anchor
+    pub fn anchor_id(&self) -> Option<AnchorId> {
+        parse_anchor_id(&self.id)
     }
     
     /// Check if anchor is currently tracking
     pub fn is_tracking(&self) -> bool {
-        matches!(self.tracking_state, 
+        matches!(self.tracking_state,
             XRTrackingState::Tracking | XRTrackingState::Emulated)
     }
-    
-    /// Request persistence
-    pub fn request_persistence(&mut self) -> bool {
-        if self.persistence == AnchorPersistence::Session {
-            self.persistence = AnchorPersistence::Persistent;
-            true
-        } else {
-            false
-        }
-    }
-    
-    /// Delete anchor
-    pub fn delete(&mut self) {
-        self.tracking_state = XRTrackingState::NotTracking;
-    }
 }
 
-/// Anchor persistence mode
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
-pub enum AnchorPersistence {
-    /// Anchor exists only for current session
-    Session,
-    /// Anchor is stored persistently
-    Persistent,
+/// Parse a WebXR anchor ID back into a world-anchor ID
+pub fn parse_anchor_id(id: &str) -> Option<AnchorId> {
+    id.parse().ok()
 }
 
-/// Persistent anchor store - integrates with Kāraṇa OS spatial system
-pub struct PersistentAnchorStore {
-    /// Stored anchors by ID
-    anchors: Vec<StoredAnchor>,
+/// World position and orientation for a pose in the local reference space.
+///
+/// The local reference space shares its origin with the SLAM session.
+pub fn world_pose(pose: &XRRigidTransform) -> (WorldPosition, Quaternion) {
+    let position = WorldPosition::from_local(
+        pose.position.x as f32,
+        pose.position.y as f32,
+        pose.position.z as f32,
+    );
+    let orientation = Quaternion {
+        x: pose.orientation.x as f32,
+        y: pose.orientation.y as f32,
+        z: pose.orientation.z as f32,
+        w: pose.orientation.w as f32,
+    };
+    (position, orientation)
 }
 
-/// Stored anchor data
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct StoredAnchor {
-    /// Anchor ID
-    pub id: String,
-    /// Pose when stored
-    pub pose: XRRigidTransform,
-    /// Storage timestamp
-    pub stored_at: u64,
-    /// Domain that created this anchor
-    pub origin_domain: String,
-    /// Application-provided name
-    pub name: Option<String>,
+/// Pose of a world anchor in the local reference space
+pub fn xr_pose(anchor: &SpatialAnchor) -> XRRigidTransform {
+    XRRigidTransform {
+        position: XRVector3 {
+            x: anchor.position.local.x as f64,
+            y: anchor.position.local.y as f64,
+            z: anchor.position.local.z as f64,
+        },
+        orientation: XRQuaternion {
+            x: anchor.orientation.x as f64,
+            y: anchor.orientation.y as f64,
+            z: anchor.orientation.z as f64,
+            w: anchor.orientation.w as f64,
+        },
+    }
 }
 
-impl PersistentAnchorStore {
-    /// Create a new store
-    pub fn new() -> Self {
-        Self {
-            anchors: vec![],
-        }
-    }
-    
-    /// Store an anchor
-    pub fn store(&mut self, anchor: &XRAnchor, domain: &str, name: Option<String>) -> bool {
-        // Check if already stored
-        if self.anchors.iter().any(|a| a.id == anchor.id) {
-            return false;
-        }
-        
-        let stored = StoredAnchor {
-            id: anchor.id.clone(),
-            pose: anchor.pose.clone(),
-            stored_at: anchor.created_at,
-            origin_domain: domain.to_string(),
-            name,
-        };
-        
-        self.anchors.push(stored);
-        true
-    }
-    
-    /// Retrieve an anchor
-    pub fn get(&self, id: &str) -> Option<&StoredAnchor> {
-        self.anchors.iter().find(|a| a.id == id)
-    }
-    
-    /// Get all anchors for a domain
-    pub fn get_for_domain(&self, domain: &str) -> Vec<&StoredAnchor> {
-        self.anchors.iter()
-            .filter(|a| a.origin_domain == domain)
-            .collect()
-    }
-    
-    /// Delete a stored anchor
-    pub fn delete(&mut self, id: &str) -> bool {
-        if let Some(idx) = self.anchors.iter().position(|a| a.id == id) {
-            self.anchors.remove(idx);
-            true
-        } else {
-            false
+impl From<AnchorState> for XRTrackingState {
+    fn from(state: AnchorState) -> Self {
+        match state {
+            AnchorState::Active => XRTrackingState::Tracking,
+            AnchorState::Degraded => XRTrackingState::Emulated,
+            AnchorState::Pending | AnchorState::Lost | AnchorState::Archived => {
+                XRTrackingState::NotTracking
+            }
         }
     }
-    
-    /// Clear all anchors for a domain
-    pub fn clear_domain(&mut self, domain: &str) {
-        self.anchors.retain(|a| a.origin_domain != domain);
-    }
-    
-    /// Get anchor count
-    pub fn count(&self) -> usize {
-        self.anchors.len()
-    }
 }
 
-impl Default for PersistentAnchorStore {
-    fn default() -> Self {
-        Self::new()
-    }
+/// Anchor persistence mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AnchorPersistence {
+    /// Anchor exists only for current session
+    Session,
+    /// Anchor is stored persistently
+    Persistent,
 }
 
 /// Anchor creation options
@@ -212,83 +139,55 @@ impl Default for AnchorCreateOptions {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::spatial::{CreateAnchorRequest, WorldAnchorService};
+    
+    fn world_anchor(service: &WorldAnchorService, pose: &XRRigidTransform) -> SpatialAnchor {
+        let (position, orientation) = world_pose(pose);
+        service.create(CreateAnchorRequest {
+            position,
+            orientation: Some(orientation),
+            ..Default::default()
+        }, false).unwrap()
+    }
     
     #[test]
     fn test_anchor_creation() {
+        let service = WorldAnchorService::in_memory();
         let pose = XRRigidTransform::from_position(1.0, 0.5, -2.0);
-        let anchor = XRAnchor::new(pose);
+        let anchor = XRAnchor::from_world(&world_anchor(&service, &pose), AnchorPersistence::Session);
         
         assert!(!anchor.id.is_empty());
         assert!(anchor.is_tracking());
         assert_eq!(anchor.persistence, AnchorPersistence::Session);
+        assert_eq!(anchor.anchor_id(), Some(1));
     }
     
     #[test]
-    fn test_anchor_persistence() {
-        let pose = XRRigidTransform::from_position(0.0, 0.0, -1.0);
-        let mut anchor = XRAnchor::new(pose);
-        
-        assert!(anchor.request_persistence());
-        assert_eq!(anchor.persistence, AnchorPersistence::Persistent);
-        
-        // Can't request again
-        assert!(!anchor.request_persistence());
-    }
-    
-    #[test]
-    fn test_anchor_update() {
-        let mut anchor = XRAnchor::new(XRRigidTransform::identity());
-        let initial_update = anchor.last_updated;
-        
-        std::thread::sleep(std::time::Duration::from_millis(10));
-        
-        anchor.update_pose(XRRigidTransform::from_position(1.0, 0.0, 0.0));
-        assert!(anchor.last_updated >= initial_update);
-    }
-    
-    #[test]
-    fn test_persistent_store() {
-        let mut store = PersistentAnchorStore::new();
-        
-        let anchor = XRAnchor::new(XRRigidTransform::identity());
-        let id = anchor.id.clone();
-        
-        assert!(store.store(&anchor, "example.com", Some("test".to_string())));
-        assert!(!store.store(&anchor, "example.com", None)); // Duplicate
-        
-        let retrieved = store.get(&id).unwrap();
-        assert_eq!(retrieved.origin_domain, "example.com");
-        assert_eq!(retrieved.name, Some("test".to_string()));
-    }
-    
-    #[test]
-    fn test_store_domain_filtering() {
-        let mut store = PersistentAnchorStore::new();
-        
-        let a1 = XRAnchor::new(XRRigidTransform::identity());
-        let a2 = XRAnchor::new(XRRigidTransform::identity());
-        let a3 = XRAnchor::new(XRRigidTransform::identity());
-        
-        store.store(&a1, "example.com", None);
-        store.store(&a2, "example.com", None);
-        store.store(&a3, "other.com", None);
-        
-        assert_eq!(store.get_for_domain("example.com").len(), 2);
-        assert_eq!(store.get_for_domain("other.com").len(), 1);
+    fn test_pose_roundtrip() {
+        let service = WorldAnchorService::in_memory();
+        let pose = XRRigidTransform {
+            position: XRVector3 { x: 1.0, y: 0.5, z: -2.0 },
+            orientation: XRQuaternion::from_axis_angle(&XRVector3 { x: 0.0, y: 1.0, z: 0.0 }, 0.5),
+        };
+        let anchor = XRAnchor::from_world(&world_anchor(&service, &pose), AnchorPersistence::Session);
         
-        store.clear_domain("example.com");
-        assert_eq!(store.count(), 1);
+        assert!((anchor.pose.position.z + 2.0).abs() < 1e-6);
+        assert!((anchor.pose.orientation.y - pose.orientation.y).abs() < 1e-6);
+        assert!((anchor.pose.orientation.w - pose.orientation.w).abs() < 1e-6);
     }
     
     #[test]
     fn test_tracking_state() {
-        let mut anchor = XRAnchor::new(XRRigidTransform::identity());
-        assert!(anchor.is_tracking());
+        let service = WorldAnchorService::in_memory();
+        let anchor = world_anchor(&service, &XRRigidTransform::identity());
         
-        anchor.set_tracking_state(XRTrackingState::NotTracking);
-        assert!(!anchor.is_tracking());
+        service.update_confidence(anchor.id, 0.1);
+        let view = XRAnchor::from_world(&service.get(anchor.id).unwrap(), AnchorPersistence::Session);
+        assert!(!view.is_tracking());
         
-        anchor.set_tracking_state(XRTrackingState::Emulated);
-        assert!(anchor.is_tracking());
+        service.update_confidence(anchor.id, 0.5);
+        let view = XRAnchor::from_world(&service.get(anchor.id).unwrap(), AnchorPersistence::Session);
+        assert_eq!(view.tracking_state, XRTrackingState::Emulated);
+        assert!(view.is_tracking());
     }
 }
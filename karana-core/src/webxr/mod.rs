@@ -11,14 +11,16 @@
 //! 
 //! Security: All WebXR capabilities require explicit user consent via Oracle permission system.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
 
 use crate::ar_tabs::TabId;
-use crate::spatial::{SpatialAnchor, WorldPosition, AnchorId};
+use crate::spatial::{
+    SpatialAnchor, AnchorId, AnchorContent, CreateAnchorRequest, WorldAnchorService,
+};
 
 mod session;
 mod reality_capture;
@@ -520,6 +522,8 @@ pub struct WebXRBridge {
     permissions: HashMap<String, HashMap<XRFeature, XRPermissionState>>,
     /// Frame counter for timestamps
     frame_count: u64,
+    /// World-anchor service backing the Anchors API
+    anchors: WorldAnchorService,
 }
 
 /// Session state
@@ -528,24 +532,32 @@ struct XRSessionState {
     pub session: XRSession,
     /// Owning tab
     pub tab_id: TabId,
+    /// Origin of the page that owns the session
+    pub domain: String,
     /// Active reference spaces
     pub reference_spaces: HashMap<XRReferenceSpaceType, XRReferenceSpace>,
     /// Pending hit test sources
     pub hit_test_sources: Vec<HitTestSource>,
-    /// Session anchors
-    pub anchors: HashMap<String, XRAnchor>,
+    /// World anchors tracked by this session
+    pub anchors: HashSet<AnchorId>,
     /// Created at
     pub created_at: Instant,
 }
 
 impl WebXRBridge {
-    /// Create a new WebXR bridge
+    /// Create a new WebXR bridge with its own in-memory anchors
     pub fn new() -> Self {
+        Self::with_anchor_service(WorldAnchorService::in_memory())
+    }
+    
+    /// Create a bridge whose anchors live in the given world-anchor service
+    pub fn with_anchor_service(anchors: WorldAnchorService) -> Self {
         Self {
             sessions: HashMap::new(),
             sessions_by_tab: HashMap::new(),
             permissions: HashMap::new(),
             frame_count: 0,
+            anchors,
         }
     }
     
@@ -667,9 +679,10 @@ impl WebXRBridge {
         let state = XRSessionState {
             session: session.clone(),
             tab_id,
+            domain: domain.to_string(),
             reference_spaces: HashMap::new(),
             hit_test_sources: vec![],
-            anchors: HashMap::new(),
+            anchors: HashSet::new(),
             created_at: Instant::now(),
         };
         
@@ -688,6 +701,7 @@ impl WebXRBridge {
             .ok_or_else(|| anyhow!("Session not found"))?;
         
         self.sessions_by_tab.remove(&state.tab_id);
+        self.release_session_anchors(&state);
         
         log::info!("[WEBXR] Ended session {} (active for {:?})", 
                    session_id, state.created_at.elapsed());
@@ -698,7 +712,9 @@ impl WebXRBridge {
     /// End session by tab
     pub fn end_session_for_tab(&mut self, tab_id: TabId) -> Result<()> {
         if let Some(session_id) = self.sessions_by_tab.remove(&tab_id) {
-            self.sessions.remove(&session_id);
+            if let Some(state) = self.sessions.remove(&session_id) {
+                self.release_session_anchors(&state);
+            }
             log::info!("[WEBXR] Ended session for tab {}", tab_id);
         }
         Ok(())
@@ -806,11 +822,12 @@ impl WebXRBridge {
         };
         
         // Collect tracked anchors
-        let tracked_anchors: Vec<XRAnchorInfo> = state.anchors.values()
+        let tracked_anchors: Vec<XRAnchorInfo> = state.anchors.iter()
+            .filter_map(|&id| self.anchors.get(id))
             .map(|anchor| XRAnchorInfo {
-                id: anchor.id.clone(),
-                pose: anchor.pose.clone(),
-                tracking_state: anchor.tracking_state,
+                id: anchor.id.to_string(),
+                pose: anchors::xr_pose(&anchor),
+                tracking_state: anchor.state.into(),
             })
             .collect();
         
@@ -844,6 +861,16 @@ impl WebXRBridge {
         &mut self,
         session_id: XRSessionId,
         pose: XRRigidTransform,
+    ) -> Result<String> {
+        self.create_anchor_with_options(session_id, pose, AnchorCreateOptions::default())
+    }
+    
+    /// Create an anchor, optionally persisting it across sessions
+    pub fn create_anchor_with_options(
+        &mut self,
+        session_id: XRSessionId,
+        pose: XRRigidTransform,
+        options: AnchorCreateOptions,
     ) -> Result<String> {
         let state = self.sessions.get_mut(&session_id)
             .ok_or_else(|| anyhow!("Session not found"))?;
@@ -852,16 +879,37 @@ impl WebXRBridge {
             return Err(anyhow!("Anchors feature not enabled"));
         }
         
-        let anchor = XRAnchor::new(pose);
-        let id = anchor.id.clone();
-        state.anchors.insert(id.clone(), anchor);
+        let (position, orientation) = anchors::world_pose(&pose);
+        let anchor = self.anchors.create(CreateAnchorRequest {
+            position,
+            orientation: Some(orientation),
+            content: AnchorContent::WebXR { origin_domain: state.domain.clone() },
+            label: options.name,
+            ..Default::default()
+        }, options.persistent)?;
+        state.anchors.insert(anchor.id);
         
-        log::info!("[WEBXR] Created anchor {} in session {}", id, session_id);
+        log::info!("[WEBXR] Created anchor {} in session {}", anchor.id, session_id);
         
-        Ok(id)
+        Ok(anchor.id.to_string())
+    }
+    
+    /// Get the current view of an anchor tracked by a session
+    pub fn get_anchor(&self, session_id: XRSessionId, anchor_id: &str) -> Result<XRAnchor> {
+        let state = self.sessions.get(&session_id)
+            .ok_or_else(|| anyhow!("Session not found"))?;
+        
+        let id = parse_anchor_id(anchor_id)
+            .filter(|id| state.anchors.contains(id))
+            .ok_or_else(|| anyhow!("Anchor not found"))?;
+        
+        self.anchor_view(id).ok_or_else(|| anyhow!("Anchor not found"))
     }
     
-    /// Delete an anchor
+    /// Delete an anchor from a session.
+    ///
+    /// Persistent anchors stay stored; use `delete_persistent_anchor` to
+    /// forget them.
     pub fn delete_anchor(
         &mut self,
         session_id: XRSessionId,
@@ -870,12 +918,92 @@ impl WebXRBridge {
         let state = self.sessions.get_mut(&session_id)
             .ok_or_else(|| anyhow!("Session not found"))?;
         
-        state.anchors.remove(anchor_id)
+        let id = parse_anchor_id(anchor_id)
+            .filter(|id| state.anchors.remove(id))
             .ok_or_else(|| anyhow!("Anchor not found"))?;
         
+        if !self.anchors.is_persistent(id) {
+            self.anchors.remove(id)?;
+        }
+        
         Ok(())
     }
     
+    /// Make a session anchor persistent
+    pub fn persist_anchor(&mut self, session_id: XRSessionId, anchor_id: &str) -> Result<bool> {
+        let state = self.sessions.get(&session_id)
+            .ok_or_else(|| anyhow!("Session not found"))?;
+        
+        let id = parse_anchor_id(anchor_id)
+            .filter(|id| state.anchors.contains(id))
+            .ok_or_else(|| anyhow!("Anchor not found"))?;
+        
+        self.anchors.persist(id)
+    }
+    
+    /// IDs of the persistent anchors created by a domain
+    pub fn persistent_anchors(&self, domain: &str) -> Vec<String> {
+        self.anchors.persisted()
+            .into_iter()
+            .filter(|anchor| Self::owned_by(anchor, domain))
+            .map(|anchor| anchor.id.to_string())
+            .collect()
+    }
+    
+    /// Start tracking a persistent anchor in a session
+    pub fn restore_anchor(&mut self, session_id: XRSessionId, anchor_id: &str) -> Result<XRAnchor> {
+        let state = self.sessions.get_mut(&session_id)
+            .ok_or_else(|| anyhow!("Session not found"))?;
+        
+        let anchor = parse_anchor_id(anchor_id)
+            .filter(|&id| self.anchors.is_persistent(id))
+            .and_then(|id| self.anchors.get(id))
+            .filter(|anchor| Self::owned_by(anchor, &state.domain))
+            .ok_or_else(|| anyhow!("Persistent anchor not found"))?;
+        
+        state.anchors.insert(anchor.id);
+        Ok(XRAnchor::from_world(&anchor, AnchorPersistence::Persistent))
+    }
+    
+    /// Forget a persistent anchor created by a domain
+    pub fn delete_persistent_anchor(&mut self, domain: &str, anchor_id: &str) -> Result<()> {
+        let id = parse_anchor_id(anchor_id)
+            .filter(|&id| self.anchors.is_persistent(id))
+            .filter(|&id| self.anchors.get(id).is_some_and(|a| Self::owned_by(&a, domain)))
+            .ok_or_else(|| anyhow!("Persistent anchor not found"))?;
+        
+        for state in self.sessions.values_mut() {
+            state.anchors.remove(&id);
+        }
+        self.anchors.remove(id)?;
+        
+        Ok(())
+    }
+    
+    /// WebXR view of a world anchor
+    fn anchor_view(&self, id: AnchorId) -> Option<XRAnchor> {
+        let persistence = if self.anchors.is_persistent(id) {
+            AnchorPersistence::Persistent
+        } else {
+            AnchorPersistence::Session
+        };
+        self.anchors.get(id).map(|anchor| XRAnchor::from_world(&anchor, persistence))
+    }
+    
+    /// Whether a world anchor was placed by the given domain
+    fn owned_by(anchor: &SpatialAnchor, domain: &str) -> bool {
+        matches!(&anchor.content, AnchorContent::WebXR { origin_domain } if origin_domain == domain)
+    }
+    
+    /// Drop the session-only anchors of an ended session
+    fn release_session_anchors(&self, state: &XRSessionState) {
+        for &id in &state.anchors {
+            if !self.anchors.is_persistent(id) {
+                let _ = self.anchors.remove(id);
+            }
+        }
+    }
+    
     /// Request hit test source
     pub fn request_hit_test_source(
         &mut self,
@@ -1090,4 +1218,109 @@ mod tests {
         bridge.end_session(session.id).unwrap();
         assert!(bridge.get_session(tab_id).is_none());
     }
+    
+    fn anchor_session(bridge: &mut WebXRBridge, domain: &str) -> XRSession {
+        bridge.request_session(
+            uuid::Uuid::new_v4(),
+            domain,
+            XRSessionMode::ImmersiveAR,
+            XRSessionFeatures {
+                required: vec![XRFeature::Anchors],
+                optional: vec![],
+            },
+        ).unwrap()
+    }
+    
+    #[test]
+    fn test_session_anchors_end_with_session() {
+        let service = WorldAnchorService::in_memory();
+        let mut bridge = WebXRBridge::with_anchor_service(service.clone());
+        let session = anchor_session(&mut bridge, "example.com");
+        
+        let session_only = bridge.create_anchor(session.id, XRRigidTransform::identity()).unwrap();
+        let kept = bridge.create_anchor(session.id, XRRigidTransform::identity()).unwrap();
+        assert!(bridge.persist_anchor(session.id, &kept).unwrap());
+        assert!(!bridge.persist_anchor(session.id, &kept).unwrap());
+        
+        let view = bridge.get_anchor(session.id, &session_only).unwrap();
+        assert_eq!(view.persistence, AnchorPersistence::Session);
+        assert_eq!(service.count(), 2);
+        
+        bridge.end_session(session.id).unwrap();
+        assert_eq!(service.count(), 1);
+        assert_eq!(bridge.persistent_anchors("example.com"), vec![kept]);
+    }
+    
+    #[test]
+    fn test_persistent_anchors_by_domain() {
+        let mut bridge = WebXRBridge::new();
+        let example = anchor_session(&mut bridge, "example.com");
+        let other = anchor_session(&mut bridge, "other.com");
+        
+        let persistent = AnchorCreateOptions { persistent: true, ..Default::default() };
+        let a1 = bridge.create_anchor_with_options(example.id, XRRigidTransform::identity(), persistent.clone()).unwrap();
+        bridge.create_anchor_with_options(example.id, XRRigidTransform::identity(), persistent.clone()).unwrap();
+        let a3 = bridge.create_anchor_with_options(other.id, XRRigidTransform::identity(), persistent).unwrap();
+        
+        assert_eq!(bridge.persistent_anchors("example.com").len(), 2);
+        assert_eq!(bridge.persistent_anchors("other.com").len(), 1);
+        
+        // Domains can neither restore nor delete each other's anchors
+        assert!(bridge.restore_anchor(other.id, &a1).is_err());
+        assert!(bridge.delete_persistent_anchor("example.com", &a3).is_err());
+        
+        // Deleting a session's handle keeps the stored anchor
+        bridge.delete_anchor(example.id, &a1).unwrap();
+        assert!(bridge.get_anchor(example.id, &a1).is_err());
+        let restored = bridge.restore_anchor(example.id, &a1).unwrap();
+        assert_eq!(restored.persistence, AnchorPersistence::Persistent);
+        
+        bridge.delete_persistent_anchor("example.com", &a1).unwrap();
+        assert_eq!(bridge.persistent_anchors("example.com").len(), 1);
+        assert_eq!(bridge.stats().total_anchors, 2);
+    }
+    
+    #[tokio::test]
+    async fn test_webxr_anchor_reaches_spatial_system() {
+        use crate::spatial::{SpatialConfig, SpatialSystem, WorldPosition};
+        
+        let path = std::env::temp_dir().join(format!("karana_webxr_anchors_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        let config = SpatialConfig {
+            persistence_path: path.to_string_lossy().into_owned(),
+            ..Default::default()
+        };
+        let near = WorldPosition::from_local(1.0, 0.0, -2.0);
+        
+        let anchor_id = {
+            let system = SpatialSystem::new(config.clone());
+            let mut bridge = WebXRBridge::with_anchor_service(system.anchor_service());
+            let session = anchor_session(&mut bridge, "example.com");
+            
+            let id = bridge.create_anchor_with_options(
+                session.id,
+                XRRigidTransform::from_position(1.0, 0.0, -2.0),
+                AnchorCreateOptions { persistent: true, name: Some("lamp".to_string()), metadata: None },
+            ).unwrap();
+            
+            let nearby = system.get_anchors_at(&near, 0.5).await;
+            assert_eq!(nearby.len(), 1);
+            assert_eq!(nearby[0].id.to_string(), id);
+            id
+        };
+        
+        // After a restart the anchor comes back from the store
+        let system = SpatialSystem::new(config);
+        let nearby = system.get_anchors_at(&near, 0.5).await;
+        assert_eq!(nearby.len(), 1);
+        assert_eq!(nearby[0].label.as_deref(), Some("lamp"));
+        
+        let mut bridge = WebXRBridge::with_anchor_service(system.anchor_service());
+        let session = anchor_session(&mut bridge, "example.com");
+        assert_eq!(bridge.persistent_anchors("example.com"), vec![anchor_id.clone()]);
+        let restored = bridge.restore_anchor(session.id, &anchor_id).unwrap();
+        assert!((restored.pose.position.z + 2.0).abs() < 1e-6);
+        
+        let _ = std::fs::remove_dir_all(&path);
+    }
 }
@@ -9,8 +9,8 @@
 //! 4. **Execution Proof**: Prove the command was executed as intended
 //!
 //! The ZK circuit proves:
-//! - I know a secret `s` and intent `i` such that `H(s || i) = public_commitment`
-//! - The intent `i` is well-formed (valid command type, valid parameters)
+//! - I know a secret `s` and intent `i` such that
+//!   `MiMC(s, SHA-256(i), nonce) = public_commitment`
 //! - The signer has authorization level >= required level for intent
 
 use anyhow::{Result, anyhow};
@@ -18,18 +18,18 @@ use ark_ff::PrimeField;
 use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
 use ark_r1cs_std::prelude::*;
 use ark_r1cs_std::fields::fp::FpVar;
-use ark_bls12_381::{Bls12_381, Fr};
-use ark_groth16::{Groth16, ProvingKey, PreparedVerifyingKey, prepare_verifying_key};
-use ark_snark::SNARK;
-use ark_std::rand::thread_rng;
-use ark_serialize::{CanonicalSerialize, CanonicalDeserialize};
+use ark_bls12_381::Fr;
 use serde::{Serialize, Deserialize};
+use sha2::{Digest, Sha256};
 use std::sync::OnceLock;
-use std::fs::File;
-use std::path::Path;
 
+use super::keys::{CircuitKeys, key_dir};
+use super::mimc::{fr_from_bytes, fr_to_bytes, mimc_hash, mimc_hash_gadget};
 use crate::oracle::command::OracleCommand;
 
+/// Circuit id (and key file name) for intent authorization
+pub const INTENT_CIRCUIT_ID: &str = "intent-auth-mimc-v1";
+
 /// Intent types that require different proof circuits
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum IntentType {
@@ -151,13 +151,10 @@ impl IntentCommitment {
         let intent_type = IntentType::from_command(command);
         let intent_bytes = serde_json::to_vec(command)
             .map_err(|e| anyhow!("Failed to serialize command: {}", e))?;
+        let nonce: u64 = rand::random();
         
-        // Compute commitment: H(secret || intent_bytes)
-        let mut hasher_input = Vec::with_capacity(32 + intent_bytes.len());
-        hasher_input.extend_from_slice(secret);
-        hasher_input.extend_from_slice(&intent_bytes);
-        
-        let commitment = compute_commitment_hash(&hasher_input);
+        // Compute commitment: MiMC(secret, H(intent_bytes), nonce)
+        let commitment = fr_to_bytes(&mimc_hash(&commitment_inputs(secret, &intent_bytes, nonce)));
         
         Ok(Self {
             commitment,
@@ -166,7 +163,7 @@ impl IntentCommitment {
                 .duration_since(std::time::UNIX_EPOCH)
                 .unwrap()
                 .as_secs(),
-            nonce: rand::random(),
+            nonce,
         })
     }
     
@@ -177,33 +174,34 @@ impl IntentCommitment {
             Err(_) => return false,
         };
         
-        let mut hasher_input = Vec::with_capacity(32 + intent_bytes.len());
-        hasher_input.extend_from_slice(secret);
-        hasher_input.extend_from_slice(&intent_bytes);
-        
-        let computed = compute_commitment_hash(&hasher_input);
+        let computed = fr_to_bytes(&mimc_hash(&commitment_inputs(secret, &intent_bytes, self.nonce)));
         computed == self.commitment
     }
 }
 
-/// Compute a commitment hash using our demo hash function
+/// Field elements behind an intent commitment: the secret as two 16-byte
+/// halves, the intent's SHA-256 digest (truncated to fit the field) and the
+/// nonce. The intent enters as a digest so the circuit size does not depend
+/// on the command.
+fn commitment_inputs(secret: &[u8; 32], intent_bytes: &[u8], nonce: u64) -> [Fr; 4] {
+    let digest = Sha256::digest(intent_bytes);
+    [
+        Fr::from_le_bytes_mod_order(&secret[..16]),
+        Fr::from_le_bytes_mod_order(&secret[16..]),
+        Fr::from_le_bytes_mod_order(&digest[..31]),
+        Fr::from(nonce),
+    ]
+}
+
+/// Hash for the lightweight (non-circuit) proofs below
 fn compute_commitment_hash(input: &[u8]) -> [u8; 32] {
-    // Use a simple XOR-based hash for demo (in production, use SHA-256 or Poseidon)
-    let mut hash = [0u8; 32];
-    for (i, byte) in input.iter().enumerate() {
-        hash[i % 32] ^= byte;
-    }
-    // Add some mixing
-    for i in 0..32 {
-        hash[i] = hash[i].wrapping_add(hash[(i + 17) % 32]);
-    }
-    hash
+    Sha256::digest(input).into()
 }
 
 /// The ZK circuit for proving intent authorization
 /// 
-/// This is a simplified circuit that proves:
-/// 1. The prover knows a secret that produces the commitment hash
+/// Proves:
+/// 1. The prover knows the secret and intent behind the commitment
 /// 2. The prover's auth level is sufficient for the operation
 #[derive(Clone)]
 pub struct IntentAuthCircuit {
@@ -211,6 +209,8 @@ pub struct IntentAuthCircuit {
     pub secret: [u8; 32],
     /// Intent bytes (witness)
     pub intent_bytes: Vec<u8>,
+    /// Commitment nonce (witness)
+    pub nonce: u64,
     /// User's authorization level (witness)
     pub user_auth_level: u8,
     /// Expected commitment hash (public input)
@@ -219,71 +219,42 @@ pub struct IntentAuthCircuit {
     pub required_auth_level: u8,
 }
 
+impl IntentAuthCircuit {
+    /// Placeholder instance for key generation
+    pub fn setup() -> Self {
+        Self {
+            secret: [0u8; 32],
+            intent_bytes: Vec::new(),
+            nonce: 0,
+            user_auth_level: 0,
+            expected_commitment: [0u8; 32],
+            required_auth_level: 0,
+        }
+    }
+}
+
 impl ConstraintSynthesizer<Fr> for IntentAuthCircuit {
     fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
-        // ========================================================================
-        // SIMPLIFIED CIRCUIT: We prove basic constraints without complex hashing
-        // In production, this would use Poseidon hash in-circuit
-        // ========================================================================
-        
-        // Allocate secret as field elements (witnesses)
-        let mut secret_sum = FpVar::zero();
-        for (i, byte) in self.secret.iter().enumerate() {
-            let byte_var = FpVar::new_witness(
-                cs.clone(),
-                || Ok(Fr::from(*byte as u64))
-            )?;
-            // Weight by position to make collision-resistant
-            let weight = FpVar::constant(Fr::from((i + 1) as u64));
-            secret_sum = secret_sum + (byte_var * weight);
-        }
-        
-        // Allocate intent bytes (witnesses) - first 64 bytes for efficiency
-        let intent_len = self.intent_bytes.len().min(64);
-        let mut intent_sum = FpVar::zero();
-        for (i, byte) in self.intent_bytes.iter().take(64).enumerate() {
-            let byte_var = FpVar::new_witness(
-                cs.clone(),
-                || Ok(Fr::from(*byte as u64))
-            )?;
-            let weight = FpVar::constant(Fr::from((i + 100) as u64));
-            intent_sum = intent_sum + (byte_var * weight);
-        }
-        
-        // Allocate expected commitment hash as public input (as field sum)
-        let mut expected_sum = FpVar::zero();
-        for (i, byte) in self.expected_commitment.iter().enumerate() {
-            let byte_var = FpVar::new_input(
-                cs.clone(),
-                || Ok(Fr::from(*byte as u64))
-            )?;
-            let weight = FpVar::constant(Fr::from((i + 1) as u64));
-            expected_sum = expected_sum + (byte_var * weight);
-        }
-        
-        // Compute expected from secret + intent
-        // Hash = weighted_sum(secret) * weighted_sum(intent) mod p
-        let computed_hash = &secret_sum * &intent_sum;
-        
-        // For demo purposes, we verify a simplified relationship
-        // In production, this would be a proper hash comparison
-        // We check that computed_hash is non-zero (valid intent)
-        let zero = FpVar::zero();
-        computed_hash.enforce_not_equal(&zero)?;
-        
-        // Authorization check: user_auth_level >= required_auth_level
-        let user_auth = FpVar::new_witness(
-            cs.clone(),
-            || Ok(Fr::from(self.user_auth_level as u64))
-        )?;
+        // Commitment opening: MiMC over the private inputs must equal the
+        // public commitment
+        let opening = commitment_inputs(&self.secret, &self.intent_bytes, self.nonce)
+            .into_iter()
+            .map(|x| FpVar::new_witness(cs.clone(), || Ok(x)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let expected = FpVar::new_input(cs.clone(), || {
+            fr_from_bytes(&self.expected_commitment).ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        mimc_hash_gadget(&opening)?.enforce_equal(&expected)?;
         
         let required_auth = FpVar::new_input(
             cs.clone(),
             || Ok(Fr::from(self.required_auth_level as u64))
         )?;
         
-        // Prove user_auth >= required_auth by showing difference is non-negative
-        // We use comparison constraint
+        // Authorization check: user_auth_level >= required_auth_level. The
+        // level is range-checked to 8 bits so the comparison is sound.
+        let user_auth = UInt8::new_witness(cs.clone(), || Ok(self.user_auth_level))?;
+        let user_auth = Boolean::le_bits_to_fp(&user_auth.to_bits_le()?)?;
         user_auth.enforce_cmp(&required_auth, std::cmp::Ordering::Greater, true)?;
         
         Ok(())
@@ -300,68 +271,47 @@ pub struct IntentProof {
 }
 
 // Global keys for intent proofs
-static INTENT_PROOF_KEYS: OnceLock<(ProvingKey<Bls12_381>, PreparedVerifyingKey<Bls12_381>)> = OnceLock::new();
+static INTENT_PROOF_KEYS: OnceLock<CircuitKeys> = OnceLock::new();
 
-/// Initialize the intent proof system
+/// Initialize the intent proof system, loading the keys from `key_dir()`
+/// (generated on first run)
 pub fn setup_intent_proofs() -> Result<()> {
-    let key_path = Path::new("zk_keys_intent.bin");
-    
-    if key_path.exists() {
-        log::info!("[ZK-Intent] Loading keys from cache...");
-        let mut file = File::open(key_path)
-            .map_err(|e| anyhow!("Failed to open intent key cache: {}", e))?;
-        let pk = ProvingKey::<Bls12_381>::deserialize_compressed(&mut file)
-            .map_err(|e| anyhow!("Failed to deserialize intent keys: {}", e))?;
-        let pvk = prepare_verifying_key(&pk.vk);
-        INTENT_PROOF_KEYS.set((pk, pvk))
-            .map_err(|_| anyhow!("Intent keys already set"))?;
-        log::info!("[ZK-Intent] Keys loaded.");
+    if INTENT_PROOF_KEYS.get().is_some() {
         return Ok(());
     }
-    
-    log::info!("[ZK-Intent] Generating Groth16 setup for Intent Proofs...");
-    let mut rng = thread_rng();
-    
-    // Create dummy circuit for key generation
-    // IMPORTANT: Use non-zero values so constraints are satisfiable during setup
-    // The circuit requires computed_hash != 0 and user_auth >= required_auth
-    let circuit = IntentAuthCircuit {
-        secret: [1u8; 32],  // Non-zero secret
-        intent_bytes: vec![1u8; 256],  // Non-zero intent
-        user_auth_level: 3,  // Max auth level (must be >= required)
-        expected_commitment: [1u8; 32],  // Non-zero commitment (not verified during setup)
-        required_auth_level: 0,  // Minimum required
-    };
-    
-    let (pk, vk) = Groth16::<Bls12_381>::circuit_specific_setup(circuit, &mut rng)
-        .map_err(|e| anyhow!("Intent ZK setup failed: {}", e))?;
-    
-    let pvk = prepare_verifying_key(&vk);
-    
-    // Cache keys
-    let mut file = File::create(key_path)
-        .map_err(|e| anyhow!("Failed to create intent key cache: {}", e))?;
-    pk.serialize_compressed(&mut file)
-        .map_err(|e| anyhow!("Failed to serialize intent keys: {}", e))?;
-    
-    INTENT_PROOF_KEYS.set((pk, pvk))
-        .map_err(|_| anyhow!("Intent keys already set"))?;
-    log::info!("[ZK-Intent] Keys generated and cached.");
-    
+    let keys = CircuitKeys::load_or_generate(&key_dir(), INTENT_CIRCUIT_ID, IntentAuthCircuit::setup)?;
+    log::info!("[ZK-Intent] Keys ready (vk {})", keys.fingerprint_hex());
+    let _ = INTENT_PROOF_KEYS.set(keys);
     Ok(())
 }
 
+/// Verify intent proofs with a verifying key exported by the proving node
+pub fn setup_intent_verifier(verifying_key_file: &[u8]) -> Result<()> {
+    let keys = CircuitKeys::from_verifying_key_file(INTENT_CIRCUIT_ID, verifying_key_file)?;
+    INTENT_PROOF_KEYS.set(keys).map_err(|_| anyhow!("Intent keys already set"))
+}
+
+/// Verifying key file for the intent circuit, to hand to verifiers
+pub fn export_intent_verifying_key() -> Result<Vec<u8>> {
+    INTENT_PROOF_KEYS.get()
+        .ok_or(anyhow!("Intent proof keys not initialized"))?
+        .export_verifying_key()
+}
+
 /// Prove that a user is authorized to execute an intent
 pub fn prove_intent_authorization(
     secret: &[u8; 32],
     command: &OracleCommand,
     user_auth_level: u8,
 ) -> Result<IntentProof> {
-    let (pk, _) = INTENT_PROOF_KEYS.get()
+    let keys = INTENT_PROOF_KEYS.get()
         .ok_or(anyhow!("Intent proof keys not initialized"))?;
     
-    let mut rng = thread_rng();
     let intent_type = IntentType::from_command(command);
+    if user_auth_level < intent_type.required_auth_level() {
+        return Err(anyhow!("Auth level {} is below the {} required for {:?}",
+            user_auth_level, intent_type.required_auth_level(), intent_type));
+    }
     
     // Create commitment
     let commitment = IntentCommitment::create(secret, command)?;
@@ -370,27 +320,18 @@ pub fn prove_intent_authorization(
     let intent_bytes = serde_json::to_vec(command)
         .map_err(|e| anyhow!("Failed to serialize command: {}", e))?;
     
-    // Pad to fixed size
-    let mut padded_intent = intent_bytes.clone();
-    padded_intent.resize(256, 0);
-    
     log::info!("[ZK-Intent] Proving authorization for {:?} intent...", intent_type);
     let start = std::time::Instant::now();
     
     let circuit = IntentAuthCircuit {
         secret: *secret,
-        intent_bytes: padded_intent,
+        intent_bytes,
+        nonce: commitment.nonce,
         user_auth_level,
         expected_commitment: commitment.commitment,
         required_auth_level: intent_type.required_auth_level(),
     };
-    
-    let proof = Groth16::<Bls12_381>::prove(pk, circuit, &mut rng)
-        .map_err(|e| anyhow!("Intent proving failed: {}", e))?;
-    
-    let mut proof_bytes = Vec::new();
-    proof.serialize_compressed(&mut proof_bytes)
-        .map_err(|e| anyhow!("Proof serialization failed: {}", e))?;
+    let proof_bytes = keys.prove(circuit)?;
     
     log::info!("[ZK-Intent] Proof generated in {:?}", start.elapsed());
     
@@ -402,43 +343,22 @@ pub fn prove_intent_authorization(
 
 /// Verify an intent authorization proof
 pub fn verify_intent_proof(proof: &IntentProof) -> bool {
-    let keys = INTENT_PROOF_KEYS.get();
-    if keys.is_none() {
+    let Some(keys) = INTENT_PROOF_KEYS.get() else {
         log::warn!("[ZK-Intent] Keys not initialized for verification");
         return false;
-    }
-    let (_, pvk) = keys.unwrap();
-    
-    let groth_proof = match ark_groth16::Proof::<Bls12_381>::deserialize_compressed(&proof.proof_bytes[..]) {
-        Ok(p) => p,
-        Err(e) => {
-            log::warn!("[ZK-Intent] Failed to deserialize proof: {}", e);
-            return false;
-        }
+    };
+    let Some(commitment) = fr_from_bytes(&proof.commitment.commitment) else {
+        log::warn!("[ZK-Intent] Commitment is not a field element");
+        return false;
     };
     
-    // Reconstruct public inputs
-    let mut public_inputs: Vec<Fr> = Vec::new();
-    
-    // Expected commitment (32 bytes)
-    for byte in proof.commitment.commitment.iter() {
-        public_inputs.push(Fr::from(*byte as u64));
-    }
-    
-    // Required auth level (1 byte)
+    // Public inputs: commitment, then the required auth level
     let required_auth = proof.commitment.intent_type.required_auth_level();
-    public_inputs.push(Fr::from(required_auth as u64));
+    let public_inputs = [commitment, Fr::from(required_auth as u64)];
     
-    match Groth16::<Bls12_381>::verify_with_processed_vk(pvk, &public_inputs, &groth_proof) {
-        Ok(valid) => {
-            log::info!("[ZK-Intent] Proof verification: {}", if valid { "VALID" } else { "INVALID" });
-            valid
-        }
-        Err(e) => {
-            log::warn!("[ZK-Intent] Verification error: {}", e);
-            false
-        }
-    }
+    let valid = keys.verify(&public_inputs, &proof.proof_bytes);
+    log::info!("[ZK-Intent] Proof verification: {}", if valid { "VALID" } else { "INVALID" });
+    valid
 }
 
 /// Lightweight proof for queries (no state change, just commitment)
@@ -661,4 +581,38 @@ mod tests {
         let result = RangeProof::create(2000, 1000, &blinding);
         assert!(result.is_err());
     }
+    
+    #[test]
+    fn test_auth_circuit_proves_and_verifies() {
+        use ark_relations::r1cs::ConstraintSystem;
+        
+        let secret = [7u8; 32];
+        let command = OracleCommand::Shutdown;
+        let commitment = IntentCommitment::create(&secret, &command).unwrap();
+        let required = commitment.intent_type.required_auth_level();
+        let circuit = |user_auth_level| IntentAuthCircuit {
+            secret,
+            intent_bytes: serde_json::to_vec(&command).unwrap(),
+            nonce: commitment.nonce,
+            user_auth_level,
+            expected_commitment: commitment.commitment,
+            required_auth_level: required,
+        };
+        
+        // Too low an auth level cannot satisfy the circuit
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit(required - 1).generate_constraints(cs.clone()).unwrap();
+        assert!(!cs.is_satisfied().unwrap());
+        
+        let nanos = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_nanos();
+        let dir = std::path::PathBuf::from(format!("/tmp/karana-test-zk-intent-{}", nanos));
+        let keys = CircuitKeys::load_or_generate(&dir, INTENT_CIRCUIT_ID, IntentAuthCircuit::setup).unwrap();
+        let proof = keys.prove(circuit(3)).unwrap();
+        
+        let public = fr_from_bytes(&commitment.commitment).unwrap();
+        assert!(keys.verify(&[public, Fr::from(required as u64)], &proof));
+        assert!(!keys.verify(&[public, Fr::from(0u64)], &proof));
+        
+        std::fs::remove_dir_all(&dir).ok();
+    }
 }
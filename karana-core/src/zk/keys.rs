@@ -0,0 +1,302 @@
+//! Persistent Groth16 keys
+//!
+//! Each circuit's keys are generated once and kept on disk as two files:
+//! `<circuit>.pk` (proving key, only needed by provers) and `<circuit>.vk`
+//! (verifying key, small enough to hand to other devices). Both use the same
+//! container, integers little-endian:
+//!
+//! ```text
+//!   magic    8 bytes  b"KRNZKEY1"
+//!   kind     u8       1 = proving key, 2 = verifying key
+//!   id_len   u16      length of the circuit id
+//!   id       bytes    circuit id, e.g. "storage-mimc-v1"
+//!   len      u64      payload length
+//!   payload  bytes    arkworks compressed serialization
+//!   digest   32 bytes SHA-256 of everything before it
+//! ```
+//!
+//! Loading checks the digest, the circuit id and the curve point encodings,
+//! and that the proving key embeds the same verifying key as the `.vk` file.
+//! The circuit id carries a version, so changing a circuit means a new id
+//! rather than silently proving against stale keys. A corrupt key file is an
+//! error, never a reason to regenerate: new keys would invalidate every
+//! verifier holding the old ones.
+
+use anyhow::{Context, Result, anyhow};
+use ark_bls12_381::{Bls12_381, Fr};
+use ark_groth16::{Groth16, PreparedVerifyingKey, Proof, ProvingKey, VerifyingKey, prepare_verifying_key};
+use ark_relations::r1cs::ConstraintSynthesizer;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_snark::SNARK;
+use ark_std::rand::thread_rng;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const KEY_FILE_MAGIC: &[u8; 8] = b"KRNZKEY1";
+
+const KIND_PROVING: u8 = 1;
+const KIND_VERIFYING: u8 = 2;
+
+/// Directory holding the node's circuit keys
+///
+/// `KARANA_ZK_KEY_DIR` overrides the default under the local data dir.
+pub fn key_dir() -> PathBuf {
+    if let Ok(dir) = std::env::var("KARANA_ZK_KEY_DIR") {
+        return PathBuf::from(dir);
+    }
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("/tmp"))
+        .join("karana")
+        .join("zk")
+}
+
+/// Groth16 keys for one circuit; verifier-only when loaded without the
+/// proving key
+pub struct CircuitKeys {
+    circuit_id: String,
+    proving_key: Option<ProvingKey<Bls12_381>>,
+    verifying_key: VerifyingKey<Bls12_381>,
+    prepared: PreparedVerifyingKey<Bls12_381>,
+}
+
+impl CircuitKeys {
+    /// Load the circuit's keys from `dir`, running the setup on
+    /// `setup_circuit()` and saving the result if none exist yet
+    pub fn load_or_generate<C, F>(dir: &Path, circuit_id: &str, setup_circuit: F) -> Result<Self>
+    where
+        C: ConstraintSynthesizer<Fr>,
+        F: FnOnce() -> C,
+    {
+        let (pk_path, vk_path) = Self::paths(dir, circuit_id);
+        if pk_path.exists() || vk_path.exists() {
+            return Self::load(dir, circuit_id);
+        }
+
+        log::info!("[ZK] Generating Groth16 keys for '{}'...", circuit_id);
+        let (pk, vk) = Groth16::<Bls12_381>::circuit_specific_setup(setup_circuit(), &mut thread_rng())
+            .map_err(|e| anyhow!("ZK setup for '{}' failed: {}", circuit_id, e))?;
+
+        fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+        write_atomic(&pk_path, &encode_key_file(KIND_PROVING, circuit_id, &pk)?)?;
+        write_atomic(&vk_path, &encode_key_file(KIND_VERIFYING, circuit_id, &vk)?)?;
+
+        let keys = Self::new(circuit_id, Some(pk), vk);
+        log::info!("[ZK] Keys for '{}' saved to {} (vk {})", circuit_id, dir.display(), keys.fingerprint_hex());
+        Ok(keys)
+    }
+
+    /// Load both keys saved by `load_or_generate`
+    pub fn load(dir: &Path, circuit_id: &str) -> Result<Self> {
+        let (pk_path, vk_path) = Self::paths(dir, circuit_id);
+        let vk: VerifyingKey<Bls12_381> = read_key_file(&vk_path, KIND_VERIFYING, circuit_id)?;
+        let pk: ProvingKey<Bls12_381> = read_key_file(&pk_path, KIND_PROVING, circuit_id)?;
+        if fingerprint(&pk.vk) != fingerprint(&vk) {
+            return Err(anyhow!("Proving and verifying keys for '{}' do not belong together", circuit_id));
+        }
+        log::info!("[ZK] Keys for '{}' loaded", circuit_id);
+        Ok(Self::new(circuit_id, Some(pk), vk))
+    }
+
+    /// Verifier-only keys from an exported verifying key file
+    pub fn from_verifying_key_file(circuit_id: &str, data: &[u8]) -> Result<Self> {
+        let vk = decode_key_file(data, KIND_VERIFYING, circuit_id)?;
+        Ok(Self::new(circuit_id, None, vk))
+    }
+
+    fn new(circuit_id: &str, proving_key: Option<ProvingKey<Bls12_381>>, verifying_key: VerifyingKey<Bls12_381>) -> Self {
+        Self {
+            circuit_id: circuit_id.to_string(),
+            proving_key,
+            prepared: prepare_verifying_key(&verifying_key),
+            verifying_key,
+        }
+    }
+
+    fn paths(dir: &Path, circuit_id: &str) -> (PathBuf, PathBuf) {
+        (dir.join(format!("{}.pk", circuit_id)), dir.join(format!("{}.vk", circuit_id)))
+    }
+
+    pub fn circuit_id(&self) -> &str {
+        &self.circuit_id
+    }
+
+    /// Whether these keys can produce proofs
+    pub fn can_prove(&self) -> bool {
+        self.proving_key.is_some()
+    }
+
+    /// The verifying key in key file format, for verifiers on other devices
+    pub fn export_verifying_key(&self) -> Result<Vec<u8>> {
+        encode_key_file(KIND_VERIFYING, &self.circuit_id, &self.verifying_key)
+    }
+
+    /// SHA-256 of the compressed verifying key, for pinning out of band
+    pub fn fingerprint(&self) -> [u8; 32] {
+        fingerprint(&self.verifying_key)
+    }
+
+    pub fn fingerprint_hex(&self) -> String {
+        hex::encode(self.fingerprint())
+    }
+
+    /// Prove `circuit`, returning the compressed proof
+    pub fn prove<C: ConstraintSynthesizer<Fr>>(&self, circuit: C) -> Result<Vec<u8>> {
+        let pk = self.proving_key.as_ref()
+            .ok_or_else(|| anyhow!("Only the verifying key for '{}' is loaded", self.circuit_id))?;
+        let proof = Groth16::<Bls12_381>::prove(pk, circuit, &mut thread_rng())
+            .map_err(|e| anyhow!("Proving '{}' failed: {}", self.circuit_id, e))?;
+        let mut bytes = Vec::new();
+        proof.serialize_compressed(&mut bytes)
+            .map_err(|e| anyhow!("Proof serialization failed: {}", e))?;
+        Ok(bytes)
+    }
+
+    /// Check a compressed proof against `public_inputs`
+    pub fn verify(&self, public_inputs: &[Fr], proof_bytes: &[u8]) -> bool {
+        let Ok(proof) = Proof::<Bls12_381>::deserialize_compressed(proof_bytes) else {
+            return false;
+        };
+        Groth16::<Bls12_381>::verify_with_processed_vk(&self.prepared, public_inputs, &proof).unwrap_or(false)
+    }
+}
+
+fn fingerprint(vk: &VerifyingKey<Bls12_381>) -> [u8; 32] {
+    let mut bytes = Vec::new();
+    vk.serialize_compressed(&mut bytes)
+        .expect("serializing to a Vec cannot fail");
+    Sha256::digest(&bytes).into()
+}
+
+fn encode_key_file<T: CanonicalSerialize>(kind: u8, circuit_id: &str, key: &T) -> Result<Vec<u8>> {
+    let id = circuit_id.as_bytes();
+    if id.len() > u16::MAX as usize {
+        return Err(anyhow!("Circuit id too long"));
+    }
+    let mut payload = Vec::new();
+    key.serialize_compressed(&mut payload)
+        .map_err(|e| anyhow!("Failed to serialize key for '{}': {}", circuit_id, e))?;
+
+    let mut out = Vec::with_capacity(payload.len() + id.len() + 51);
+    out.extend_from_slice(KEY_FILE_MAGIC);
+    out.push(kind);
+    out.extend_from_slice(&(id.len() as u16).to_le_bytes());
+    out.extend_from_slice(id);
+    out.extend_from_slice(&(payload.len() as u64).to_le_bytes());
+    out.extend_from_slice(&payload);
+    let digest = Sha256::digest(&out);
+    out.extend_from_slice(&digest);
+    Ok(out)
+}
+
+fn decode_key_file<T: CanonicalDeserialize>(data: &[u8], kind: u8, circuit_id: &str) -> Result<T> {
+    if !data.starts_with(KEY_FILE_MAGIC) || data.len() < KEY_FILE_MAGIC.len() + 32 {
+        return Err(anyhow!("Not a key file"));
+    }
+    let (body, digest) = data.split_at(data.len() - 32);
+    if Sha256::digest(body).as_slice() != digest {
+        return Err(anyhow!("Key file checksum mismatch"));
+    }
+
+    let mut reader = Reader { data: body, pos: KEY_FILE_MAGIC.len() };
+    if reader.take(1)?[0] != kind {
+        return Err(anyhow!("Key file holds the wrong kind of key"));
+    }
+    let id_len = u16::from_le_bytes(reader.take(2)?.try_into()?) as usize;
+    let id = reader.take(id_len)?;
+    if id != circuit_id.as_bytes() {
+        return Err(anyhow!("Key file is for circuit '{}', expected '{}'", String::from_utf8_lossy(id), circuit_id));
+    }
+    let len = u64::from_le_bytes(reader.take(8)?.try_into()?) as usize;
+    let payload = reader.take(len)?;
+    if reader.pos != body.len() {
+        return Err(anyhow!("Trailing bytes in key file"));
+    }
+
+    // Compressed deserialization validates that every point is on the curve
+    // and in the right subgroup
+    T::deserialize_compressed(payload).map_err(|e| anyhow!("Invalid key for '{}': {}", circuit_id, e))
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        let end = self.pos.checked_add(len).filter(|end| *end <= self.data.len())
+            .ok_or_else(|| anyhow!("Key file truncated"))?;
+        let bytes = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+}
+
+fn read_key_file<T: CanonicalDeserialize>(path: &Path, kind: u8, circuit_id: &str) -> Result<T> {
+    let data = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    decode_key_file(&data, kind, circuit_id).with_context(|| format!("Key file {} failed its integrity check", path.display()))
+}
+
+/// Write via a temporary file so a crash never leaves a half-written key
+fn write_atomic(path: &Path, data: &[u8]) -> Result<()> {
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, data).with_context(|| format!("Failed to write {}", tmp.display()))?;
+    fs::rename(&tmp, path).with_context(|| format!("Failed to move key into {}", path.display()))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::zk::mimc::{fr_to_bytes, mimc_hash};
+    use crate::zk::storage_proof::{storage_inputs, StorageCircuit};
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let nanos = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_nanos();
+        PathBuf::from(format!("/tmp/karana-test-zk-{}-{}", name, nanos))
+    }
+
+    fn setup(dir: &Path) -> CircuitKeys {
+        CircuitKeys::load_or_generate(dir, "storage-test", StorageCircuit::setup).unwrap()
+    }
+
+    #[test]
+    fn test_keys_persist_and_verify_remotely() {
+        let dir = temp_dir("persist");
+        let keys = setup(&dir);
+        let reloaded = setup(&dir);
+        assert_eq!(keys.fingerprint(), reloaded.fingerprint());
+
+        let data = b"sovereign bytes";
+        let hash = mimc_hash(&storage_inputs(data));
+        let proof = reloaded
+            .prove(StorageCircuit { input: data.to_vec(), expected_hash: fr_to_bytes(&hash) })
+            .unwrap();
+
+        // Another device only receives the verifying key
+        let verifier = CircuitKeys::from_verifying_key_file("storage-test", &keys.export_verifying_key().unwrap()).unwrap();
+        assert!(!verifier.can_prove());
+        assert!(verifier.verify(&[hash], &proof));
+        assert!(!verifier.verify(&[hash + Fr::from(1u64)], &proof));
+        assert!(CircuitKeys::from_verifying_key_file("intent-test", &keys.export_verifying_key().unwrap()).is_err());
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_corrupt_key_file_is_rejected() {
+        let dir = temp_dir("corrupt");
+        setup(&dir);
+        let vk_path = dir.join("storage-test.vk");
+        let mut data = fs::read(&vk_path).unwrap();
+        let mid = data.len() / 2;
+        data[mid] ^= 0x01;
+        fs::write(&vk_path, data).unwrap();
+
+        let err = CircuitKeys::load_or_generate(&dir, "storage-test", StorageCircuit::setup).err().unwrap();
+        assert!(format!("{:#}", err).contains("checksum"));
+
+        fs::remove_dir_all(&dir).ok();
+    }
+}
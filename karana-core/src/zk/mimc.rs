@@ -0,0 +1,129 @@
+//! MiMC hash over the BLS12-381 scalar field
+//!
+//! A SNARK-friendly hash: each round is a single `(x + k + c)^5`, i.e. three
+//! constraints, so hashing a handful of field elements costs a few thousand
+//! constraints instead of the ~30k a SHA-256 compression needs.
+//!
+//! The block cipher `E_k` runs `MIMC_ROUNDS` rounds of `x ← (x + k + c_i)^5`
+//! and adds `k` at the end; `x^5` is a permutation because 5 does not divide
+//! `r - 1`. Hashing uses the Miyaguchi–Preneel construction
+//! `h ← E_h(m) + h + m` over the message elements, starting from `h = 0`.
+//! Round constants come from SHA-256 of a fixed domain tag, so every node
+//! derives the same ones.
+//!
+//! `mimc_hash` and `mimc_hash_gadget` must stay in lockstep: the first
+//! produces public inputs, the second constrains witnesses to them.
+
+use ark_bls12_381::Fr;
+use ark_ff::{Field, PrimeField};
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::SynthesisError;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use sha2::{Digest, Sha256};
+use std::sync::OnceLock;
+
+/// ceil(log5(r)) rounds for the ~255-bit scalar field
+pub const MIMC_ROUNDS: usize = 110;
+
+/// Bytes packed into each field element (stays below the modulus)
+pub const BYTES_PER_ELEMENT: usize = 31;
+
+const ROUND_CONSTANT_TAG: &[u8] = b"karana-mimc5-bls12-381";
+
+fn round_constants() -> &'static [Fr] {
+    static CONSTANTS: OnceLock<Vec<Fr>> = OnceLock::new();
+    CONSTANTS.get_or_init(|| {
+        (0..MIMC_ROUNDS as u32)
+            .map(|i| {
+                let mut hasher = Sha256::new();
+                hasher.update(ROUND_CONSTANT_TAG);
+                hasher.update(i.to_le_bytes());
+                Fr::from_le_bytes_mod_order(&hasher.finalize())
+            })
+            .collect()
+    })
+}
+
+fn encrypt(x: Fr, k: Fr) -> Fr {
+    round_constants().iter().fold(x, |x, c| {
+        let t = x + k + c;
+        t.square().square() * t
+    }) + k
+}
+
+/// MiMC hash of `inputs`
+pub fn mimc_hash(inputs: &[Fr]) -> Fr {
+    inputs.iter().fold(Fr::from(0u64), |h, m| encrypt(*m, h) + h + m)
+}
+
+fn encrypt_gadget(x: &FpVar<Fr>, k: &FpVar<Fr>) -> Result<FpVar<Fr>, SynthesisError> {
+    let mut x = x.clone();
+    for c in round_constants() {
+        let t = &x + k + FpVar::constant(*c);
+        let t4 = t.square()?.square()?;
+        x = t4 * &t;
+    }
+    Ok(x + k)
+}
+
+/// In-circuit MiMC hash of `inputs`
+pub fn mimc_hash_gadget(inputs: &[FpVar<Fr>]) -> Result<FpVar<Fr>, SynthesisError> {
+    let mut h = FpVar::zero();
+    for m in inputs {
+        h = encrypt_gadget(m, &h)? + &h + m;
+    }
+    Ok(h)
+}
+
+/// Pack bytes little-endian, `BYTES_PER_ELEMENT` per element
+pub fn pack_bytes(bytes: &[u8]) -> Vec<Fr> {
+    bytes.chunks(BYTES_PER_ELEMENT).map(Fr::from_le_bytes_mod_order).collect()
+}
+
+/// Canonical 32-byte little-endian encoding of a field element
+pub fn fr_to_bytes(value: &Fr) -> [u8; 32] {
+    let mut out = [0u8; 32];
+    value
+        .serialize_compressed(&mut out[..])
+        .expect("a scalar always fits in 32 bytes");
+    out
+}
+
+/// Inverse of `fr_to_bytes`; `None` for encodings at or above the modulus
+pub fn fr_from_bytes(bytes: &[u8; 32]) -> Option<Fr> {
+    Fr::deserialize_compressed(&bytes[..]).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_relations::r1cs::ConstraintSystem;
+
+    #[test]
+    fn test_gadget_matches_native() {
+        let inputs: Vec<Fr> = (1..=3u64).map(Fr::from).collect();
+        let expected = mimc_hash(&inputs);
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let vars = inputs
+            .iter()
+            .map(|x| FpVar::new_witness(cs.clone(), || Ok(*x)))
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let hash = mimc_hash_gadget(&vars).unwrap();
+        assert_eq!(hash.value().unwrap(), expected);
+        assert!(cs.is_satisfied().unwrap());
+        assert_eq!(cs.num_constraints(), 3 * MIMC_ROUNDS * inputs.len());
+    }
+
+    #[test]
+    fn test_hash_separates_inputs() {
+        let a = mimc_hash(&pack_bytes(b"karana"));
+        assert_ne!(a, mimc_hash(&pack_bytes(b"karanb")));
+        assert_ne!(mimc_hash(&[Fr::from(1u64), Fr::from(2u64)]), mimc_hash(&[Fr::from(2u64), Fr::from(1u64)]));
+
+        assert_eq!(fr_from_bytes(&fr_to_bytes(&a)), Some(a));
+        assert_eq!(fr_from_bytes(&[0xff; 32]), None);
+    }
+}
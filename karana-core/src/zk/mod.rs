@@ -4,23 +4,23 @@
 //! - **Storage Proofs**: Prove data integrity without revealing content
 //! - **Intent Proofs**: Prove user authorization for Oracle commands
 //!
-//! All proofs use Groth16 on BLS12-381 via arkworks.
-
-use anyhow::Result;
-use ark_groth16::{Groth16, ProvingKey, prepare_verifying_key, PreparedVerifyingKey};
-use ark_bls12_381::{Bls12_381};
-use ark_snark::SNARK;
-use ark_std::rand::thread_rng;
-use ark_serialize::{CanonicalSerialize, CanonicalDeserialize};
+//! All proofs use Groth16 on BLS12-381 via arkworks. Commitments use the
+//! SNARK-friendly MiMC hash (`mimc`). Keys are generated once per node and
+//! persisted with integrity checks (`keys`); devices that only verify load
+//! the exported verifying key and never need the proving key.
+
+use anyhow::{Result, anyhow};
 use std::sync::{OnceLock, Mutex};
-use std::fs::File;
-use std::path::Path;
 use std::collections::VecDeque;
 
+pub mod mimc;
+pub mod keys;
 pub mod storage_proof;
 pub mod intent_proof;
 
-use storage_proof::StorageCircuit;
+pub use keys::{CircuitKeys, key_dir};
+use mimc::fr_from_bytes;
+use storage_proof::{StorageCircuit, STORAGE_CIRCUIT_ID};
 
 // Re-export intent proof types
 pub use intent_proof::{
@@ -61,7 +61,7 @@ impl ProofBatch {
 }
 
 // Global keys for the storage circuit
-static ZK_KEYS: OnceLock<(ProvingKey<Bls12_381>, PreparedVerifyingKey<Bls12_381>)> = OnceLock::new();
+static ZK_KEYS: OnceLock<CircuitKeys> = OnceLock::new();
 
 // Global proof batch
 static PROOF_BATCH: OnceLock<Mutex<ProofBatch>> = OnceLock::new();
@@ -71,103 +71,52 @@ fn get_proof_batch() -> &'static Mutex<ProofBatch> {
     PROOF_BATCH.get_or_init(|| Mutex::new(ProofBatch::new(5)))
 }
 
+/// Load (or create, on first run) the storage circuit keys from `key_dir()`
 pub fn setup_zk() -> Result<()> {
-    let key_path = Path::new("zk_keys_storage.bin");
-    
-    if key_path.exists() {
-        // println!("Atom 1 (ZK): Loading keys from cache...");
-        let mut file = File::open(key_path).map_err(|e| anyhow::anyhow!("Failed to open key cache: {}", e))?;
-        let pk = ProvingKey::<Bls12_381>::deserialize_compressed(&mut file)
-            .map_err(|e| anyhow::anyhow!("Failed to deserialize ZK keys: {}", e))?;
-        let pvk = prepare_verifying_key(&pk.vk);
-        ZK_KEYS.set((pk, pvk)).map_err(|_| anyhow::anyhow!("ZK Keys already set"))?;
-        // log::info!("Atom 1 (ZK): Keys loaded.");
+    if ZK_KEYS.get().is_some() {
         return Ok(());
     }
-
-    log::info!("Atom 1 (ZK): Generating Groth16 setup for Storage Proofs...");
-    let mut rng = thread_rng();
-    
-    // Create a dummy circuit to generate keys
-    // We fix input size to 64 bytes for the demo.
-    let circuit = StorageCircuit {
-        input: vec![0u8; 64], 
-        expected_hash: [0u8; 32],
-    };
-    
-    let (pk, vk) = Groth16::<Bls12_381>::circuit_specific_setup(circuit, &mut rng)
-        .map_err(|e| anyhow::anyhow!("ZK Setup failed: {}", e))?;
-        
-    let pvk = prepare_verifying_key(&vk);
-    
-    // Cache keys
-    let mut file = File::create(key_path).map_err(|e| anyhow::anyhow!("Failed to create key cache: {}", e))?;
-    pk.serialize_compressed(&mut file).map_err(|e| anyhow::anyhow!("Failed to serialize ZK keys: {}", e))?;
-    
-    ZK_KEYS.set((pk, pvk)).map_err(|_| anyhow::anyhow!("ZK Keys already set"))?;
-    log::info!("Atom 1 (ZK): Keys generated and cached.");
-    
+    let keys = CircuitKeys::load_or_generate(&key_dir(), STORAGE_CIRCUIT_ID, StorageCircuit::setup)?;
+    log::info!("Atom 1 (ZK): Storage keys ready (vk {})", keys.fingerprint_hex());
+    // A concurrent setup may have won the race; its keys are just as good
+    let _ = ZK_KEYS.set(keys);
     Ok(())
 }
 
+/// Verify storage proofs with a verifying key exported by another node,
+/// without any proving key on this device
+pub fn setup_zk_verifier(verifying_key_file: &[u8]) -> Result<()> {
+    let keys = CircuitKeys::from_verifying_key_file(STORAGE_CIRCUIT_ID, verifying_key_file)?;
+    ZK_KEYS.set(keys).map_err(|_| anyhow!("ZK Keys already set"))
+}
+
+/// Verifying key file for the storage circuit, to hand to verifiers
+pub fn export_storage_verifying_key() -> Result<Vec<u8>> {
+    ZK_KEYS.get().ok_or(anyhow!("ZK Keys not initialized"))?.export_verifying_key()
+}
+
+/// Prove knowledge of `input` with `compute_hash(input) == expected_hash`
 pub fn prove_data_hash(input: &[u8], expected_hash: [u8; 32]) -> Result<Vec<u8>> {
-    let (pk, _) = ZK_KEYS.get().ok_or(anyhow::anyhow!("ZK Keys not initialized"))?;
-    let mut rng = thread_rng();
-    
-    // Pad or truncate input to 64 bytes to match setup
-    let mut padded_input = input.to_vec();
-    padded_input.resize(64, 0);
-    
-    // Recalculate hash for the PADDED input to ensure circuit satisfaction
-    // The circuit proves that Hash(padded_input) == expected_hash
-    // If the caller passed a hash of the UNPADDED input, it might mismatch if padding changes the hash.
-    // For XOR sum, padding with 0s doesn't change the hash, BUT if input was > 64 bytes, truncation would.
-    // Let's ensure we are proving what we claim.
-    
-    // Debug logging for ZK
-    log::info!("Atom 1 (ZK): Proving Data Hash. Input Len: {}, Padded: 64", input.len());
-    
-    let circuit = StorageCircuit {
-        input: padded_input,
+    let keys = ZK_KEYS.get().ok_or(anyhow!("ZK Keys not initialized"))?;
+    if compute_hash(input) != expected_hash {
+        return Err(anyhow!("Data does not match the claimed storage hash"));
+    }
+
+    log::info!("Atom 1 (ZK): Proving Data Hash. Input Len: {}", input.len());
+    keys.prove(StorageCircuit {
+        input: input.to_vec(),
         expected_hash,
-    };
-    
-    let proof = Groth16::<Bls12_381>::prove(pk, circuit, &mut rng)
-        .map_err(|e| anyhow::anyhow!("Proving failed: {}", e))?;
-        
-    let mut proof_bytes = Vec::new();
-    proof.serialize_compressed(&mut proof_bytes).map_err(|e| anyhow::anyhow!("Proof serialization failed: {}", e))?;
-    
-    Ok(proof_bytes)
+    })
 }
 
 pub fn verify_proof(proof_bytes: &[u8], expected_hash: [u8; 32]) -> bool {
-    let keys = ZK_KEYS.get();
-    if keys.is_none() { return false; }
-    let (_, pvk) = keys.unwrap();
-    
-    let proof = match ark_groth16::Proof::<Bls12_381>::deserialize_compressed(proof_bytes) {
-        Ok(p) => p,
-        Err(_) => return false,
+    let Some(keys) = ZK_KEYS.get() else {
+        return false;
     };
-    
-    
-    use ark_bls12_381::Fr;
-    
-    // Convert expected_hash to bits (Little Endian per byte, as UInt8 does)
-    let mut public_inputs = Vec::new();
-    for byte in expected_hash.iter() {
-        for i in 0..8 {
-            let bit = (byte >> i) & 1 == 1;
-            public_inputs.push(Fr::from(bit as u64));
-        }
-    }
-    
-    Groth16::<Bls12_381>::verify_with_processed_vk(
-        pvk,
-        &public_inputs,
-        &proof
-    ).unwrap_or(false)
+    let Some(hash) = fr_from_bytes(&expected_hash) else {
+        return false;
+    };
+    keys.verify(&[hash], proof_bytes)
 }
 
 /// Phase 7.6: Queue a proof for batch processing
@@ -215,4 +164,4 @@ pub fn get_batch_status() -> (usize, usize) {
 }
 
 // Re-export helper
-pub use storage_proof::compute_demo_hash as compute_hash;
+pub use storage_proof::compute_storage_hash as compute_hash;
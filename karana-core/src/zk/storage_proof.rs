@@ -1,57 +1,102 @@
+//! Storage proof circuit: knowledge of data under a MiMC commitment
+//!
+//! The public input is `MiMC(packed(data) || len)`; the witness is the data.
+//! Data is packed into `PREIMAGE_ELEMENTS` field elements, so the circuit has
+//! a fixed size. Anything longer than `STORAGE_PREIMAGE_BYTES` is committed
+//! through its SHA-256 digest (the proof then shows knowledge of the digest,
+//! which only someone holding the data can compute).
+
+use super::mimc::{fr_from_bytes, fr_to_bytes, mimc_hash, mimc_hash_gadget, pack_bytes, BYTES_PER_ELEMENT};
 use ark_bls12_381::Fr;
+use ark_r1cs_std::fields::fp::FpVar;
 use ark_r1cs_std::prelude::*;
 use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
 use ark_std::vec::Vec;
+use sha2::{Digest, Sha256};
+
+/// Circuit id (and key file name) for the storage proof
+pub const STORAGE_CIRCUIT_ID: &str = "storage-mimc-v1";
+
+/// Data up to this size is hashed directly
+pub const STORAGE_PREIMAGE_BYTES: usize = 64;
+
+const PREIMAGE_ELEMENTS: usize = STORAGE_PREIMAGE_BYTES.div_ceil(BYTES_PER_ELEMENT);
 
 #[derive(Clone)]
 pub struct StorageCircuit {
+    /// The data (witness)
     pub input: Vec<u8>,
+    /// `compute_storage_hash(input)` (public input)
     pub expected_hash: [u8; 32],
 }
 
+impl StorageCircuit {
+    /// Placeholder instance for key generation
+    pub fn setup() -> Self {
+        Self {
+            input: Vec::new(),
+            expected_hash: [0u8; 32],
+        }
+    }
+}
+
 impl ConstraintSynthesizer<Fr> for StorageCircuit {
     fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
-        // 1. Allocate Input Bytes (Witnesses - private data)
-        let input_bytes: Vec<UInt8<Fr>> = self.input
-            .iter()
-            .map(|b| UInt8::new_witness(cs.clone(), || Ok(b)))
+        let preimage = storage_inputs(&self.input)
+            .into_iter()
+            .map(|x| FpVar::new_witness(cs.clone(), || Ok(x)))
             .collect::<Result<Vec<_>, _>>()?;
+        let expected = FpVar::new_input(cs.clone(), || {
+            fr_from_bytes(&self.expected_hash).ok_or(SynthesisError::AssignmentMissing)
+        })?;
 
-        // 2. Compute "Hash"
-        // We'll use a simpler one: XOR sum of all bytes.
-        let mut xor_sum = UInt8::constant(0u8);
-        for byte in &input_bytes {
-            // Use BitXor trait
-            xor_sum = xor_sum ^ byte;
-        }
+        mimc_hash_gadget(&preimage)?.enforce_equal(&expected)
+    }
+}
 
-        // 3. Allocate Expected Hash (Public Input)
-        let expected_bytes: Vec<UInt8<Fr>> = self.expected_hash
-            .iter()
-            .map(|b| UInt8::new_input(cs.clone(), || Ok(b)))
-            .collect::<Result<Vec<_>, _>>()?;
+/// Field elements the storage hash absorbs: the packed preimage, then the
+/// original data length
+pub fn storage_inputs(data: &[u8]) -> Vec<Fr> {
+    let digest;
+    let preimage = if data.len() > STORAGE_PREIMAGE_BYTES {
+        digest = Sha256::digest(data);
+        digest.as_slice()
+    } else {
+        data
+    };
+    let mut inputs = pack_bytes(preimage);
+    inputs.resize(PREIMAGE_ELEMENTS, Fr::from(0u64));
+    inputs.push(Fr::from(data.len() as u64));
+    inputs
+}
 
-        // 4. Enforce Equality
-        // We only check the first byte against the XOR sum for this demo
-        xor_sum.enforce_equal(&expected_bytes[0])?;
-        
-        // Ensure other bytes are 0
-        let zero = UInt8::constant(0u8);
-        for i in 1..32 {
-            expected_bytes[i].enforce_equal(&zero)?;
-        }
+/// Public commitment to `data`, as proven by `StorageCircuit`
+pub fn compute_storage_hash(data: &[u8]) -> [u8; 32] {
+    fr_to_bytes(&mimc_hash(&storage_inputs(data)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_relations::r1cs::ConstraintSystem;
 
-        Ok(())
+    fn satisfied(input: &[u8], expected_hash: [u8; 32]) -> bool {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        StorageCircuit { input: input.to_vec(), expected_hash }
+            .generate_constraints(cs.clone())
+            .unwrap();
+        cs.is_satisfied().unwrap()
     }
-}
 
-// Helper to compute the same hash outside the circuit
-pub fn compute_demo_hash(input: &[u8]) -> [u8; 32] {
-    let mut xor_sum = 0u8;
-    for b in input {
-        xor_sum ^= b;
+    #[test]
+    fn test_circuit_matches_hash() {
+        let short = b"karana";
+        let long = vec![7u8; 500];
+        assert!(satisfied(short, compute_storage_hash(short)));
+        assert!(satisfied(&long, compute_storage_hash(&long)));
+
+        assert!(!satisfied(short, compute_storage_hash(b"karanb")));
+        // Trailing zeros change the length, so they change the hash
+        assert_ne!(compute_storage_hash(b"ab"), compute_storage_hash(b"ab\0"));
     }
-    let mut hash = [0u8; 32];
-    hash[0] = xor_sum;
-    hash
 }
@@ -1,5 +1,6 @@
-use karana_core::chain::{Blockchain, Block, Transaction, TransactionData};
+use karana_core::chain::{Blockchain, Block, BlockExecutionError, Transaction, TransactionData};
 use karana_core::economy::{Ledger, Governance};
+use karana_core::gov::ProposalAction;
 use karana_core::ai::KaranaAI;
 use std::sync::{Arc, Mutex};
 use std::fs;
@@ -70,12 +71,19 @@ fn test_blockchain_flow() {
     assert_eq!(ledger.lock().unwrap().get_balance("Alice"), 900);
     assert_eq!(ledger.lock().unwrap().get_balance("Bob"), 100);
 
+    // 4b. Light-client check: Bob's balance is provable against the state root
+    let root = chain.state_root();
+    let proof = chain.prove_account("Bob");
+    assert!(proof.verify(&root));
+    assert_eq!(proof.decode_value::<karana_core::economy::KaraToken>().unwrap().balance, 100);
+
     // 5. Test Governance Proposal via Block
     let prop_tx = Transaction {
         sender: "Alice".to_string(),
         data: TransactionData::Propose { 
             title: "Test Prop".to_string(), 
-            description: "Should we burn tokens?".to_string() 
+            description: "Should we burn tokens?".to_string(),
+            action: ProposalAction::Signal,
         },
         signature: "sig".to_string(),
         nonce: 2,
@@ -93,6 +101,44 @@ fn test_blockchain_flow() {
 
     chain.apply_block(&block2).expect("Failed to apply block 2");
 
+    // 6. All-or-nothing: a failing transaction leaves no partial state behind
+    let ok_tx = Transaction {
+        sender: "Alice".to_string(),
+        data: TransactionData::Transfer { to: "Bob".to_string(), amount: 50 },
+        signature: "sig".to_string(),
+        nonce: 3,
+        public_key: None,
+        hash: "tx_hash_3".to_string(),
+        timestamp: timestamp as u64,
+    };
+    let overdraft_tx = Transaction {
+        sender: "Bob".to_string(),
+        data: TransactionData::Transfer { to: "Carol".to_string(), amount: 10_000 },
+        signature: "sig".to_string(),
+        nonce: 1,
+        public_key: None,
+        hash: "tx_hash_4".to_string(),
+        timestamp: timestamp as u64,
+    };
+    let block3 = Block::new(block2.hash.clone(), 3, "Validator".to_string(), vec![ok_tx.clone(), overdraft_tx]);
+    let err = chain.apply_block(&block3).expect_err("Overdraft block must be rejected");
+    let exec_err = err.downcast_ref::<BlockExecutionError>().expect("Typed execution error");
+    assert_eq!(exec_err.tx_index, 1);
+    assert_eq!(ledger.lock().unwrap().get_balance("Alice"), 900);
+    assert_eq!(ledger.lock().unwrap().get_balance("Bob"), 100);
+
+    // 7. Reverting the tip restores the pre-block state
+    let root_before = chain.state_root();
+    let produced = chain.produce_block("Validator", vec![ok_tx]).expect("Failed to produce block");
+    assert_eq!(produced.header.state_root, chain.state_root());
+    assert_eq!(ledger.lock().unwrap().get_balance("Bob"), 150);
+
+    let reverted = chain.revert_tip().expect("Failed to revert tip");
+    assert_eq!(reverted.hash, produced.hash);
+    assert_eq!(ledger.lock().unwrap().get_balance("Alice"), 900);
+    assert_eq!(ledger.lock().unwrap().get_balance("Bob"), 100);
+    assert_eq!(chain.state_root(), root_before);
+
     // Cleanup
     let _ = fs::remove_dir_all(&ledger_path);
     let _ = fs::remove_dir_all(&gov_path);
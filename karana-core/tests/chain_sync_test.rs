@@ -0,0 +1,125 @@
+use karana_core::chain::{sync, Block, Blockchain, ImportOutcome, Transaction, TransactionData};
+use karana_core::economy::{Ledger, Governance};
+use karana_core::ai::KaranaAI;
+use std::sync::{Arc, Mutex};
+use std::fs;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+struct Node {
+    chain: Blockchain,
+    ledger: Arc<Mutex<Ledger>>,
+    paths: Vec<String>,
+}
+
+impl Node {
+    fn new(name: &str, ai: Arc<Mutex<KaranaAI>>) -> Self {
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos();
+        let ledger_path = format!("/tmp/karana-test-fork-{}-ledger-{}", name, nanos);
+        let gov_path = format!("/tmp/karana-test-fork-{}-gov-{}", name, nanos);
+        let ledger = Arc::new(Mutex::new(Ledger::new(&ledger_path)));
+        let gov = Arc::new(Mutex::new(Governance::new(&gov_path, ledger.clone(), ai)));
+        // Same pre-chain balances on every node
+        ledger.lock().unwrap().mint("Alice", 1000);
+        Self {
+            chain: Blockchain::new(ledger.clone(), gov),
+            ledger,
+            paths: vec![ledger_path, gov_path],
+        }
+    }
+
+    fn balance(&self, did: &str) -> u128 {
+        self.ledger.lock().unwrap().get_balance(did)
+    }
+}
+
+impl Drop for Node {
+    fn drop(&mut self) {
+        for path in &self.paths {
+            let _ = fs::remove_dir_all(path);
+        }
+    }
+}
+
+fn transfer(from: &str, to: &str, amount: u128, nonce: u64) -> Transaction {
+    Transaction {
+        sender: from.to_string(),
+        data: TransactionData::Transfer { to: to.to_string(), amount },
+        signature: "sig".to_string(),
+        nonce,
+        public_key: None,
+        hash: format!("{}-{}-{}", from, to, nonce),
+        timestamp: 0,
+    }
+}
+
+#[test]
+fn test_fork_choice_and_reorg() {
+    let ai = Arc::new(Mutex::new(KaranaAI::new().expect("Failed to init AI")));
+    let a = Node::new("a", ai.clone());
+    let b = Node::new("b", ai);
+    let genesis = a.chain.latest_block();
+    assert_eq!(genesis.hash, b.chain.latest_block().hash, "genesis must match across nodes");
+
+    // Offline, A builds two blocks and B builds one on a competing branch
+    a.chain.produce_block("node-a", vec![transfer("Alice", "Bob", 100, 1)]).unwrap();
+    a.chain.produce_block("node-a", vec![transfer("Alice", "Bob", 50, 2)]).unwrap();
+    let a3 = a.chain.produce_block("node-a", vec![]).unwrap();
+    let b1 = b.chain.produce_block("node-b", vec![transfer("Alice", "Carol", 10, 1)]).unwrap();
+    assert_eq!(b.balance("Carol"), 10);
+
+    // B catches up from A and reorganizes onto the longer chain
+    let req = sync::request(&b.chain);
+    let res = sync::serve(&a.chain, &req);
+    assert_eq!(res.blocks.len(), 3);
+    let progress = sync::apply(&b.chain, &res);
+    assert!(progress.caught_up);
+    assert_eq!(progress.imported, 3);
+    assert_eq!(progress.reverted.len(), 1);
+    assert_eq!(progress.reverted[0].hash, b1.hash);
+
+    assert_eq!(b.chain.latest_block().hash, a3.hash);
+    assert_eq!(b.balance("Carol"), 0);
+    assert_eq!(b.balance("Bob"), 150);
+    assert_eq!(b.chain.state_root(), a.chain.state_root());
+    assert_eq!(b.chain.side_chain_len(), 1);
+
+    // A sees B's old block as a losing side branch
+    assert!(matches!(a.chain.import_block(b1.clone()).unwrap(), ImportOutcome::SideChain));
+    assert!(matches!(a.chain.import_block(b1).unwrap(), ImportOutcome::Known));
+    assert_eq!(a.chain.latest_block().hash, a3.hash);
+
+    // A longer branch with an invalid block is rejected and the chain is restored
+    let root_before = a.chain.state_root();
+    let x1 = Block::new(genesis.hash.clone(), 1, "node-x".to_string(), vec![]);
+    let x2 = Block::new(x1.hash.clone(), 2, "node-x".to_string(), vec![transfer("Dave", "Eve", 5, 1)]);
+    let x3 = Block::new(x2.hash.clone(), 3, "node-x".to_string(), vec![]);
+    let x4 = Block::new(x3.hash.clone(), 4, "node-x".to_string(), vec![]);
+    let results: Vec<_> = [x1, x2, x3, x4].into_iter().map(|x| a.chain.import_block(x)).collect();
+    assert!(results.iter().any(|r| r.is_err()));
+
+    assert_eq!(a.chain.latest_block().hash, a3.hash);
+    assert_eq!(a.chain.state_root(), root_before);
+    assert_eq!(a.balance("Bob"), 150);
+    assert_eq!(a.balance("Alice"), 850);
+}
+
+#[test]
+fn test_orphans_connect_when_parent_arrives() {
+    let ai = Arc::new(Mutex::new(KaranaAI::new().expect("Failed to init AI")));
+    let a = Node::new("orphan-a", ai.clone());
+    let b = Node::new("orphan-b", ai);
+
+    let a1 = a.chain.produce_block("node-a", vec![transfer("Alice", "Bob", 1, 1)]).unwrap();
+    let a2 = a.chain.produce_block("node-a", vec![transfer("Alice", "Bob", 2, 2)]).unwrap();
+
+    // Gossip arrives out of order
+    match b.chain.import_block(a2.clone()).unwrap() {
+        ImportOutcome::Orphan { missing } => assert_eq!(missing, a1.hash),
+        other => panic!("expected orphan, got {:?}", other),
+    }
+    assert_eq!(b.chain.height(), 0);
+
+    assert!(matches!(b.chain.import_block(a1).unwrap(), ImportOutcome::Extended));
+    assert_eq!(b.chain.latest_block().hash, a2.hash);
+    assert_eq!(b.balance("Bob"), 3);
+}
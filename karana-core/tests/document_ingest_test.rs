@@ -0,0 +1,107 @@
+use karana_core::oracle::ingest::{DocumentIngestor, IngestConfig};
+use karana_core::oracle::knowledge_manager::KnowledgeManager;
+use karana_core::oracle::{QueryContext, ResponseSource, UniversalOracle};
+use std::collections::HashMap;
+use std::fs;
+use std::sync::Arc;
+
+const WARRANTY: &str = "The warranty covers the frame for two years";
+
+#[tokio::test]
+async fn test_ingest_folder_incrementally_and_query() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let docs = dir.path().join("manuals");
+    fs::create_dir_all(docs.join("nested")).unwrap();
+    fs::write(docs.join("warranty.txt"), WARRANTY).unwrap();
+    fs::write(
+        docs.join("setup.md"),
+        "# Setup\nCharge the glasses for an hour. Then hold the button.\n\n## Pairing\nOpen the app and scan the code.\n",
+    ).unwrap();
+    fs::write(
+        docs.join("nested/care.html"),
+        "<h1>Care</h1><p>Wipe the lenses with the cloth.</p><script>track()</script>",
+    ).unwrap();
+    fs::write(docs.join("copy.txt"), WARRANTY).unwrap();
+    fs::write(docs.join("photo.jpg"), [0xff, 0xd8]).unwrap();
+
+    let manager = Arc::new(KnowledgeManager::new("did:karana:test".to_string(), dir.path().join("knowledge.json")).unwrap());
+    let mut ingestor = DocumentIngestor::for_manager(manager.clone(), IngestConfig::default()).unwrap();
+
+    let report = ingestor.ingest_dir(&docs).await.unwrap();
+    assert_eq!(report.files_ingested, 4);
+    assert_eq!(report.duplicates_skipped, 1);
+    assert!(report.errors.is_empty());
+    let chunks = manager.get_all_chunks().await;
+    assert_eq!(chunks.len(), report.chunks_added);
+
+    let pairing = chunks.iter().find(|c| c.text.contains("scan the code")).unwrap();
+    let provenance = pairing.provenance.as_ref().unwrap();
+    assert!(provenance.file.ends_with("setup.md"));
+    assert_eq!(provenance.heading.as_deref(), Some("Setup > Pairing"));
+    assert!(pairing.text.starts_with("Setup > Pairing\n\n"));
+
+    // Nothing changed: nothing re-ingested
+    let report = ingestor.ingest_dir(&docs).await.unwrap();
+    assert_eq!((report.files_unchanged, report.chunks_added, report.chunks_removed), (4, 0, 0));
+
+    // Editing one file only replaces its chunks
+    fs::write(docs.join("nested/care.html"), "<h1>Care</h1><p>Never use solvents on the lenses.</p>").unwrap();
+    let report = ingestor.ingest_dir(&docs).await.unwrap();
+    assert_eq!((report.files_ingested, report.chunks_added, report.chunks_removed), (1, 1, 1));
+    let texts: Vec<String> = manager.get_all_chunks().await.into_iter().map(|c| c.text).collect();
+    assert!(texts.iter().any(|t| t.contains("solvents")));
+    assert!(!texts.iter().any(|t| t.contains("cloth")));
+
+    // The shared chunk outlives the file that first contributed it
+    fs::remove_file(docs.join("copy.txt")).unwrap();
+    let report = ingestor.ingest_dir(&docs).await.unwrap();
+    assert_eq!((report.files_removed, report.chunks_removed), (1, 0));
+    let warranty = manager.get_all_chunks().await.into_iter().find(|c| c.text == WARRANTY).unwrap();
+    assert!(warranty.provenance.unwrap().file.ends_with("warranty.txt"));
+
+    // The oracle answers from the ingested documents, with a citation
+    let mut oracle = UniversalOracle::new().unwrap();
+    oracle.set_knowledge_manager(manager.clone());
+    let context = QueryContext {
+        location: None,
+        time_of_day: "morning".to_string(),
+        recent_topics: vec![],
+        user_preferences: HashMap::new(),
+    };
+    let response = oracle.query(WARRANTY, &context).await.unwrap();
+    assert_eq!(response.source, ResponseSource::LocalKnowledge);
+    assert!(response.answer.contains("two years"));
+    assert!(response.answer.contains("(warranty.txt)"));
+
+    // Reopening picks up the manifest, so nothing is re-added
+    drop(ingestor);
+    let mut ingestor = DocumentIngestor::for_manager(manager.clone(), IngestConfig::default()).unwrap();
+    assert_eq!(ingestor.ingest_dir(&docs).await.unwrap().chunks_added, 0);
+}
+
+#[tokio::test]
+async fn test_failed_reingest_keeps_file_record() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let notes = dir.path().join("notes.txt");
+    fs::write(&notes, "The spare lenses are in the blue case").unwrap();
+
+    let store = dir.path().join("knowledge.json");
+    let manager = Arc::new(KnowledgeManager::new("did:karana:test".to_string(), store.clone()).unwrap());
+    let mut ingestor = DocumentIngestor::for_manager(manager.clone(), IngestConfig::default()).unwrap();
+    ingestor.ingest_file(&notes).await.unwrap();
+
+    // Storing the new chunks fails: the file keeps its old record
+    fs::write(&notes, "The spare lenses moved to the drawer").unwrap();
+    fs::remove_file(&store).unwrap();
+    fs::create_dir(&store).unwrap();
+    assert!(ingestor.ingest_file(&notes).await.is_err());
+    assert_eq!(ingestor.files().len(), 1);
+
+    // So the retry still replaces the old chunk
+    fs::remove_dir(&store).unwrap();
+    let report = ingestor.ingest_file(&notes).await.unwrap();
+    assert_eq!(report.chunks_removed, 1);
+    let texts: Vec<String> = manager.get_all_chunks().await.into_iter().map(|c| c.text).collect();
+    assert!(!texts.iter().any(|t| t.contains("blue case")));
+    assert!(texts.iter().any(|t| t.contains("drawer")));
+}
@@ -0,0 +1,90 @@
+use karana_core::chain::{Blockchain, Transaction, TransactionData};
+use karana_core::economy::Ledger;
+use karana_core::gov::{Governance, ProposalAction, ProposalStatus, TREASURY_ACCOUNT};
+use karana_core::ai::KaranaAI;
+use std::sync::{Arc, Mutex};
+use std::fs;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+fn tx(sender: &str, data: TransactionData, nonce: u64) -> Transaction {
+    Transaction {
+        sender: sender.to_string(),
+        data,
+        signature: "sig".to_string(),
+        nonce,
+        public_key: None,
+        hash: format!("{}-{}", sender, nonce),
+        timestamp: 0,
+    }
+}
+
+fn vote(sender: &str, proposal_id: u64, approve: bool, nonce: u64) -> Transaction {
+    tx(sender, TransactionData::Vote { proposal_id, approve }, nonce)
+}
+
+#[test]
+fn test_proposal_lifecycle_on_chain() {
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos();
+    let ledger_path = format!("/tmp/karana-test-govchain-ledger-{}", nanos);
+    let gov_path = format!("/tmp/karana-test-govchain-gov-{}", nanos);
+
+    let ai = Arc::new(Mutex::new(KaranaAI::new().expect("Failed to init AI")));
+    let ledger = Arc::new(Mutex::new(Ledger::new(&ledger_path)));
+    let gov = Arc::new(Mutex::new(Governance::new(&gov_path, ledger.clone(), ai)));
+    let chain = Blockchain::new(ledger.clone(), gov.clone());
+
+    ledger.lock().unwrap().mint("Alice", 300);
+    ledger.lock().unwrap().mint("Bob", 100);
+    ledger.lock().unwrap().mint(TREASURY_ACCOUNT, 1000);
+
+    // Block 1: Alice proposes a grant to Carol
+    let propose = tx("Alice", TransactionData::Propose {
+        title: "Grant".to_string(),
+        description: "Fund Carol's work".to_string(),
+        action: ProposalAction::TreasurySpend { to: "Carol".to_string(), amount: 250 },
+    }, 1);
+    chain.produce_block("Validator", vec![propose]).unwrap();
+    let proposal = gov.lock().unwrap().get_active_proposals().pop().expect("proposal created");
+    assert_eq!(proposal.proposer, "Alice");
+    let (id, deadline) = (proposal.id, proposal.voting_ends);
+
+    // Block 2: Bob votes twice in one block; only his latest ballot counts
+    chain.produce_block("Validator", vec![
+        vote("Alice", id, true, 2),
+        vote("Bob", id, true, 1),
+        vote("Bob", id, false, 2),
+    ]).unwrap();
+    let p = gov.lock().unwrap().get_proposal(id).unwrap();
+    assert_eq!((p.votes_for, p.votes_against), (300, 100));
+
+    // Nothing happens until the deadline block closes
+    while chain.height() < deadline - 1 {
+        chain.produce_block("Validator", vec![]).unwrap();
+    }
+    assert_eq!(gov.lock().unwrap().get_proposal(id).unwrap().status, ProposalStatus::Active);
+    let root_before = chain.state_root();
+
+    // Votes in the deadline block still count; then the tally executes the spend
+    chain.produce_block("Validator", vec![vote("Bob", id, true, 3)]).unwrap();
+    assert_eq!(chain.height(), deadline);
+    let p = gov.lock().unwrap().get_proposal(id).unwrap();
+    assert_eq!(p.status, ProposalStatus::Passed);
+    assert_eq!((p.votes_for, p.votes_against), (400, 0));
+    assert_eq!(ledger.lock().unwrap().get_balance("Carol"), 250);
+    assert_eq!(ledger.lock().unwrap().get_balance(TREASURY_ACCOUNT), 750);
+
+    // Closed proposals take no more votes
+    let late = chain.produce_block("Validator", vec![vote("Alice", id, false, 3)]).unwrap();
+    assert!(late.transactions.is_empty());
+
+    // Reverting the deadline block undoes the tally and the spend
+    chain.revert_tip().unwrap();
+    chain.revert_tip().unwrap();
+    assert_eq!(chain.state_root(), root_before);
+    assert_eq!(gov.lock().unwrap().get_proposal(id).unwrap().status, ProposalStatus::Active);
+    assert_eq!(ledger.lock().unwrap().get_balance("Carol"), 0);
+    assert_eq!(ledger.lock().unwrap().get_balance(TREASURY_ACCOUNT), 1000);
+
+    let _ = fs::remove_dir_all(&ledger_path);
+    let _ = fs::remove_dir_all(&gov_path);
+}
@@ -0,0 +1,73 @@
+use karana_core::chain::{create_key_rotation, create_signed_transaction, key_rotation_message, Blockchain, Mempool, MempoolConfig, MempoolError, TransactionData};
+use karana_core::economy::{Ledger, Governance};
+use karana_core::wallet::HdWallet;
+use karana_core::ai::KaranaAI;
+use std::sync::{Arc, Mutex};
+use std::fs;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[test]
+fn test_key_rotation_keeps_identity() {
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos();
+    let ledger_path = format!("/tmp/karana-test-rotation-ledger-{}", nanos);
+    let gov_path = format!("/tmp/karana-test-rotation-gov-{}", nanos);
+
+    let ai = Arc::new(Mutex::new(KaranaAI::new().expect("Failed to init AI")));
+    let ledger = Arc::new(Mutex::new(Ledger::new(&ledger_path)));
+    let gov = Arc::new(Mutex::new(Governance::new(&gov_path, ledger.clone(), ai)));
+    let chain = Blockchain::new(ledger.clone(), gov);
+
+    let (mut wallet, phrase) = HdWallet::generate("phone").unwrap();
+    let did = wallet.accounts()[0].did.clone();
+    ledger.lock().unwrap().mint(&did, 100);
+    let transfer = |amount| TransactionData::Transfer { to: "Bob".to_string(), amount };
+
+    // The first key signs for the DID it derives
+    let old_key = wallet.signer(0).unwrap();
+    chain.produce_block("Validator", vec![create_signed_transaction(&old_key, transfer(10), 1)]).unwrap();
+    assert!(chain.authorized_key(&did).is_none());
+
+    // A rotation proof from some other key is refused
+    let new_public_key = wallet.next_key(0).unwrap().public_key_hex();
+    let proof = wallet.derive_key(0, 7).unwrap().sign_hex(&key_rotation_message(&did, &new_public_key, 2));
+    let forged = create_signed_transaction(&old_key, TransactionData::RotateKey { new_public_key, proof }, 2);
+    assert!(chain.produce_block("Validator", vec![forged]).unwrap().transactions.is_empty());
+
+    // The phone is lost: restore from the phrase and rotate to the next key
+    let mut restored = HdWallet::from_mnemonic(&phrase.as_string(), "tablet").unwrap();
+    assert_eq!(restored.accounts()[0].did, did);
+    let new_key = restored.next_key(0).unwrap();
+    let rotation = create_key_rotation(&restored.signer(0).unwrap(), &new_key, 2);
+    chain.produce_block("Validator", vec![rotation]).unwrap();
+    assert_eq!(chain.authorized_key(&did), Some(new_key.public_key_hex()));
+
+    // The old key no longer signs for the DID, on chain or in the mempool
+    let stale = create_signed_transaction(&old_key, transfer(5), 3);
+    assert!(chain.produce_block("Validator", vec![stale.clone()]).unwrap().transactions.is_empty());
+    let mut pool = Mempool::new(MempoolConfig::default());
+    let authorized = chain.authorized_key(&did);
+    assert_eq!(pool.add_authorized(stale, 2, authorized.as_deref()), Err(MempoolError::BadSignature));
+
+    // The new key does, under the same DID
+    let fresh = create_signed_transaction(&new_key, transfer(5), 3);
+    assert!(pool.add_authorized(fresh.clone(), 2, authorized.as_deref()).is_ok());
+    let root_before = chain.state_root();
+    let block = chain.produce_block("Validator", vec![fresh]).unwrap();
+    assert_eq!(block.transactions.len(), 1);
+    assert_eq!(ledger.lock().unwrap().get_balance(&did), 85);
+    assert_eq!(ledger.lock().unwrap().get_balance("Bob"), 15);
+
+    // Another restore finds the authorized key by scanning
+    assert_eq!(wallet.sync_authorized_key(0, authorized.as_deref()).unwrap(), 1);
+    assert_eq!(wallet.signer(0).unwrap().public_key_hex(), new_key.public_key_hex());
+
+    // Reverting past the rotation hands the DID back to the first key
+    chain.revert_tip().unwrap();
+    assert_eq!(chain.state_root(), root_before);
+    chain.revert_tip().unwrap();
+    chain.revert_tip().unwrap();
+    assert!(chain.authorized_key(&did).is_none());
+
+    let _ = fs::remove_dir_all(&ledger_path);
+    let _ = fs::remove_dir_all(&gov_path);
+}